@@ -19,27 +19,49 @@ use cryptoxide::sha3::Sha3_256;
 use ed25519_bip32::XPub;
 
 use std::{
+    collections::BTreeMap,
     convert::{TryFrom, TryInto},
     fmt,
     io::{BufRead, Write},
 };
 
-// public key tag only for encoding/decoding purpose
-struct PubKeyTag();
+/// The type of the spending data of an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddrType {
+    /// spendable with the extended public key the address is derived from
+    PubKey,
+    /// spendable by a script, not supported here beyond parsing
+    Script,
+    /// redemption address of the initial vending, spendable with a plain
+    /// ed25519 public key
+    Redeem,
+}
+
+impl AddrType {
+    fn to_tag(self) -> u64 {
+        match self {
+            AddrType::PubKey => 0,
+            AddrType::Script => 1,
+            AddrType::Redeem => 2,
+        }
+    }
+}
 
 // [TkListLen 1, TkInt (fromEnum t)]
-impl cbor_event::se::Serialize for PubKeyTag {
+impl cbor_event::se::Serialize for AddrType {
     fn serialize<'se, W: Write>(
         &self,
         serializer: &'se mut Serializer<W>,
     ) -> cbor_event::Result<&'se mut Serializer<W>> {
-        serializer.write_unsigned_integer(0)
+        serializer.write_unsigned_integer(self.to_tag())
     }
 }
-impl cbor_event::de::Deserialize for PubKeyTag {
+impl cbor_event::de::Deserialize for AddrType {
     fn deserialize<R: BufRead>(reader: &mut Deserializer<R>) -> cbor_event::Result<Self> {
         match reader.unsigned_integer()? {
-            0 => Ok(PubKeyTag()),
+            0 => Ok(AddrType::PubKey),
+            1 => Ok(AddrType::Script),
+            2 => Ok(AddrType::Redeem),
             _ => Err(cbor_event::Error::CustomError(
                 "Invalid AddrType".to_owned(),
             )),
@@ -53,12 +75,16 @@ type HDAddressPayload = Vec<u8>;
 pub struct Attributes {
     pub derivation_path: Option<HDAddressPayload>,
     pub network_magic: Option<u32>,
+    /// the attributes this library does not know about, by key, kept
+    /// verbatim as they are part of the hash of the address
+    pub unknown: BTreeMap<u64, Vec<u8>>,
 }
 impl Attributes {
     pub fn new_bootstrap_era(hdap: Option<HDAddressPayload>, network_magic: Option<u32>) -> Self {
         Attributes {
             derivation_path: hdap,
             network_magic,
+            unknown: BTreeMap::new(),
         }
     }
 }
@@ -71,27 +97,24 @@ impl cbor_event::se::Serialize for Attributes {
         &self,
         serializer: &'se mut Serializer<W>,
     ) -> cbor_event::Result<&'se mut Serializer<W>> {
-        let mut len = 0;
-        if self.derivation_path.is_some() {
-            len += 1
-        };
-        if self.network_magic.is_some() {
-            len += 1
-        };
-        let serializer = serializer.write_map(cbor_event::Len::Len(len))?;
-        let serializer = match &self.derivation_path {
-            None => serializer,
-            Some(ref dp) => {
-                let s = serializer.write_unsigned_integer(ATTRIBUTE_NAME_TAG_DERIVATION)?;
-                cbor_event::se::serialize_cbor_in_cbor(dp.as_slice(), s)?
-            }
-        };
-        let serializer = match &self.network_magic {
-            None => serializer,
-            Some(network_magic) => serializer
-                .write_unsigned_integer(ATTRIBUTE_NAME_TAG_NETWORK_MAGIC)?
-                .write_bytes(cbor!(&network_magic)?)?,
-        };
+        // the values are all CBOR encoded in bytes, the keys are written in
+        // increasing order as in the canonical encoding of the addresses
+        let mut values = BTreeMap::new();
+        if let Some(dp) = &self.derivation_path {
+            let mut inner = Serializer::new_vec();
+            inner.write_bytes(dp)?;
+            values.insert(ATTRIBUTE_NAME_TAG_DERIVATION, inner.finalize());
+        }
+        if let Some(network_magic) = &self.network_magic {
+            values.insert(ATTRIBUTE_NAME_TAG_NETWORK_MAGIC, cbor!(network_magic)?);
+        }
+        for (key, value) in self.unknown.iter() {
+            values.insert(*key, value.clone());
+        }
+        let serializer = serializer.write_map(cbor_event::Len::Len(values.len() as u64))?;
+        for (key, value) in values {
+            serializer.write_unsigned_integer(key)?.write_bytes(value)?;
+        }
         Ok(serializer)
     }
 }
@@ -109,6 +132,7 @@ impl cbor_event::de::Deserialize for Attributes {
         };
         let mut derivation_path = None;
         let mut network_magic = None;
+        let mut unknown = BTreeMap::new();
         while len > 0 {
             let key = reader.unsigned_integer()?;
             match key {
@@ -125,10 +149,12 @@ impl cbor_event::de::Deserialize for Attributes {
                     network_magic = Some(n);
                 }
                 _ => {
-                    return Err(cbor_event::Error::CustomError(format!(
-                        "invalid Attribute key {}",
-                        key
-                    )));
+                    if unknown.insert(key, reader.bytes()?).is_some() {
+                        return Err(cbor_event::Error::CustomError(format!(
+                            "duplicated Attribute key {}",
+                            key
+                        )));
+                    }
                 }
             }
             len -= 1;
@@ -136,6 +162,7 @@ impl cbor_event::de::Deserialize for Attributes {
         Ok(Attributes {
             derivation_path,
             network_magic,
+            unknown,
         })
     }
 }
@@ -154,8 +181,12 @@ fn sha3_then_blake2b224(data: &[u8]) -> [u8; 28] {
     out
 }
 
-fn hash_spending_data(xpub: &XPub, attrs: &Attributes) -> [u8; 28] {
-    let buf = cbor!(&(&PubKeyTag(), &SpendingData(xpub), attrs))
+fn hash_spending_data(
+    addr_type: AddrType,
+    spending_data: &SpendingData,
+    attrs: &Attributes,
+) -> [u8; 28] {
+    let buf = cbor!(&(&addr_type, spending_data, attrs))
         .expect("serialize the HashedSpendingData's digest data");
     sha3_then_blake2b224(&buf)
 }
@@ -180,15 +211,28 @@ impl Addr {
         cbor_event::de::Deserialize::deserialize(&mut raw).unwrap() // unwrap should never fail from addr to extended addr
     }
 
+    /// The type of the address, `None` if the address is not well formed
+    pub fn addr_type(&self) -> Option<AddrType> {
+        ExtendedAddr::try_from(self.0.as_slice())
+            .ok()
+            .map(|ea| ea.addr_type)
+    }
+
+    fn identical_with(&self, addr_type: AddrType, spending_data: SpendingData) -> AddressMatchXPub {
+        match ExtendedAddr::try_from(self.0.as_slice()) {
+            Ok(ea)
+                if ea.addr_type == addr_type
+                    && ea.addr == hash_spending_data(addr_type, &spending_data, &ea.attributes) =>
+            {
+                AddressMatchXPub::Yes
+            }
+            _ => AddressMatchXPub::No,
+        }
+    }
+
     /// Check if the Addr can be reconstructed with a specific xpub
     pub fn identical_with_xpub(&self, xpub: &XPub) -> AddressMatchXPub {
-        let ea = self.deconstruct();
-        let newea = ExtendedAddr::new(xpub, ea.attributes);
-        if self == &newea.to_address() {
-            AddressMatchXPub::Yes
-        } else {
-            AddressMatchXPub::No
-        }
+        self.identical_with(AddrType::PubKey, SpendingData::PubKey(xpub))
     }
 
     /// mostly helper of the previous function, so not to have to expose the xpub construction
@@ -211,6 +255,11 @@ impl Addr {
             _ => AddressMatchXPub::No,
         }
     }
+
+    /// Check if the Addr is the redemption address of the given public key
+    pub fn identical_with_redeem_key(&self, pk: &[u8; PUBLIC_KEY_LENGTH]) -> AddressMatchXPub {
+        self.identical_with(AddrType::Redeem, SpendingData::Redeem(pk))
+    }
 }
 
 impl AsRef<[u8]> for Addr {
@@ -277,12 +326,24 @@ const EXTENDED_ADDR_LEN: usize = 28;
 pub struct ExtendedAddr {
     pub addr: [u8; EXTENDED_ADDR_LEN],
     pub attributes: Attributes,
+    pub addr_type: AddrType,
 }
 impl ExtendedAddr {
     pub fn new(xpub: &XPub, attrs: Attributes) -> Self {
         ExtendedAddr {
-            addr: hash_spending_data(xpub, &attrs),
+            addr: hash_spending_data(AddrType::PubKey, &SpendingData::PubKey(xpub), &attrs),
+            attributes: attrs,
+            addr_type: AddrType::PubKey,
+        }
+    }
+
+    /// redemption address of the given public key
+    pub fn new_redeem(pk: &[u8; PUBLIC_KEY_LENGTH], network_magic: Option<u32>) -> Self {
+        let attrs = Attributes::new_bootstrap_era(None, network_magic);
+        ExtendedAddr {
+            addr: hash_spending_data(AddrType::Redeem, &SpendingData::Redeem(pk), &attrs),
             attributes: attrs,
+            addr_type: AddrType::Redeem,
         }
     }
 
@@ -343,7 +404,10 @@ impl cbor_event::se::Serialize for ExtendedAddr {
         serializer: &'se mut Serializer<W>,
     ) -> cbor_event::Result<&'se mut Serializer<W>> {
         let addr_bytes = cbor_event::Value::Bytes(self.addr.to_vec());
-        cbor::util::encode_with_crc32_(&(&addr_bytes, &self.attributes, &PubKeyTag()), serializer)?;
+        cbor::util::encode_with_crc32_(
+            &(&addr_bytes, &self.attributes, &self.addr_type),
+            serializer,
+        )?;
         Ok(serializer)
     }
 }
@@ -361,9 +425,13 @@ impl cbor_event::de::Deserialize for ExtendedAddr {
             )
         })?;
         let attributes = cbor_event::de::Deserialize::deserialize(&mut raw)?;
-        let _addr_type: PubKeyTag = cbor_event::de::Deserialize::deserialize(&mut raw)?;
+        let addr_type = cbor_event::de::Deserialize::deserialize(&mut raw)?;
 
-        Ok(ExtendedAddr { addr, attributes })
+        Ok(ExtendedAddr {
+            addr,
+            attributes,
+            addr_type,
+        })
     }
 }
 impl fmt::Display for ExtendedAddr {
@@ -372,28 +440,37 @@ impl fmt::Display for ExtendedAddr {
     }
 }
 
-const SPENDING_DATA_TAG_PUBKEY: u64 = 0;
-
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct SpendingData<'a>(&'a XPub);
+enum SpendingData<'a> {
+    PubKey(&'a XPub),
+    Redeem(&'a [u8; PUBLIC_KEY_LENGTH]),
+}
 
 impl<'a> cbor_event::se::Serialize for SpendingData<'a> {
     fn serialize<'se, W: Write>(
         &self,
         serializer: &'se mut Serializer<W>,
     ) -> cbor_event::Result<&'se mut Serializer<W>> {
-        let ar: [u8; 64] = self.0.clone().into();
-        serializer
-            .write_array(cbor_event::Len::Len(2))?
-            .write_unsigned_integer(SPENDING_DATA_TAG_PUBKEY)?
-            .write_bytes(&ar[..])
+        let serializer = serializer.write_array(cbor_event::Len::Len(2))?;
+        match self {
+            SpendingData::PubKey(xpub) => {
+                let ar: [u8; 64] = (*xpub).clone().into();
+                serializer
+                    .write_unsigned_integer(AddrType::PubKey.to_tag())?
+                    .write_bytes(&ar[..])
+            }
+            SpendingData::Redeem(pk) => serializer
+                .write_unsigned_integer(AddrType::Redeem.to_tag())?
+                .write_bytes(&pk[..]),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Addr, AddressMatchXPub};
+    use super::{Addr, AddrType, AddressMatchXPub, Attributes, ExtendedAddr};
     use ed25519_bip32::XPub;
+    use std::convert::TryFrom;
 
     fn assert_same_address(address: Addr, xpub: XPub) {
         assert_eq!(
@@ -534,4 +611,95 @@ mod tests {
         ]);
         assert_same_address(address, public_key)
     }
+
+    fn assert_same_redeem_address(address: Addr, public_key: [u8; 32]) {
+        assert_eq!(address.addr_type(), Some(AddrType::Redeem));
+        assert_eq!(
+            address.identical_with_redeem_key(&public_key),
+            AddressMatchXPub::Yes,
+            "expected redeem key {:?} to match address {}",
+            public_key,
+            address
+        );
+        // the mainnet addresses carry no network magic
+        assert_eq!(
+            ExtendedAddr::new_redeem(&public_key, None).to_address(),
+            address
+        );
+    }
+
+    // redemption keys of the AVVM distribution of the mainnet genesis,
+    // `-0BJDi-gauylk4LptQTgjMeo7kY9lTCbZv12vwOSTZk=` in base64url
+    #[test]
+    fn test_vector_redeem_1() {
+        let address = "Ae2tdPwUPEZHFQnrr2dYB4GEQ8WVKspEyrg29pJ3f7qdjzaxjeShEEokF5f"
+            .parse()
+            .unwrap();
+        let public_key = [
+            0xfb, 0x40, 0x49, 0x0e, 0x2f, 0xa0, 0x6a, 0xec, 0xa5, 0x93, 0x82, 0xe9, 0xb5, 0x04,
+            0xe0, 0x8c, 0xc7, 0xa8, 0xee, 0x46, 0x3d, 0x95, 0x30, 0x9b, 0x66, 0xfd, 0x76, 0xbf,
+            0x03, 0x92, 0x4d, 0x99,
+        ];
+        assert_same_redeem_address(address, public_key)
+    }
+
+    // `-0Np4pyTOWF26iXWVIvu6fhz9QupwWRS2hcCaOEYlw0=` in base64url
+    #[test]
+    fn test_vector_redeem_2() {
+        let address = "Ae2tdPwUPEZFF5MA48FeLbPhKPbC9pG5DpzReXzzoFhofPR7nZQ8DSR3A4G"
+            .parse()
+            .unwrap();
+        let public_key = [
+            0xfb, 0x43, 0x69, 0xe2, 0x9c, 0x93, 0x39, 0x61, 0x76, 0xea, 0x25, 0xd6, 0x54, 0x8b,
+            0xee, 0xe9, 0xf8, 0x73, 0xf5, 0x0b, 0xa9, 0xc1, 0x64, 0x52, 0xda, 0x17, 0x02, 0x68,
+            0xe1, 0x18, 0x97, 0x0d,
+        ];
+        assert_same_redeem_address(address, public_key)
+    }
+
+    #[test]
+    fn redeem_address() {
+        let pk = [7u8; 32];
+        let address = ExtendedAddr::new_redeem(&pk, Some(764_824_073)).to_address();
+        assert_eq!(address.addr_type(), Some(AddrType::Redeem));
+        assert_eq!(
+            address.identical_with_redeem_key(&pk),
+            AddressMatchXPub::Yes
+        );
+        assert_eq!(
+            address.identical_with_redeem_key(&[8u8; 32]),
+            AddressMatchXPub::No
+        );
+        // the same key as the public part of an extended public key
+        assert_eq!(
+            address.identical_with_pubkey_raw(&pk, &[0u8; 32]),
+            AddressMatchXPub::No
+        );
+        let parsed: Addr = address.to_string().parse().unwrap();
+        assert_eq!(parsed, address);
+    }
+
+    #[test]
+    fn unknown_attributes_are_preserved() {
+        let xpub = XPub::from_bytes([3u8; 64]);
+        let mut attributes = Attributes::new_bootstrap_era(Some(vec![1, 2, 3]), Some(42));
+        attributes.unknown.insert(0, vec![0x41, 0x00]);
+        attributes.unknown.insert(7, vec![0x40]);
+        let address = ExtendedAddr::new(&xpub, attributes.clone()).to_address();
+
+        let parsed = ExtendedAddr::try_from(address.as_ref()).unwrap();
+        assert_eq!(parsed.attributes, attributes);
+        assert_eq!(parsed.to_address(), address);
+        assert_eq!(address.identical_with_xpub(&xpub), AddressMatchXPub::Yes);
+    }
+
+    #[test]
+    fn malformed_address_does_not_match() {
+        let address = Addr::new(vec![0x82, 0x00]);
+        assert_eq!(address.addr_type(), None);
+        assert_eq!(
+            address.identical_with_xpub(&XPub::from_bytes([3u8; 64])),
+            AddressMatchXPub::No
+        );
+    }
 }
@@ -17,4 +17,6 @@ mod cbor;
 #[cfg(feature = "with-bench")]
 pub mod cbor;
 
-pub use address::{Addr, AddressMatchXPub, Attributes, ExtendedAddr, ParseExtendedAddrError};
+pub use address::{
+    Addr, AddrType, AddressMatchXPub, Attributes, ExtendedAddr, ParseExtendedAddrError,
+};
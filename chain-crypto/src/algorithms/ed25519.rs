@@ -1,9 +1,20 @@
 use crate::key::{
     AsymmetricKey, AsymmetricPublicKey, PublicKeyError, SecretKeyError, SecretKeySizeStatic,
 };
-use crate::sign::{SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
+use crate::sign::{
+    ContextSigningAlgorithm, ContextVerificationAlgorithm, SignatureError, SigningAlgorithm,
+    Verification, VerificationAlgorithm,
+};
+use crate::validation::ValidationPolicy;
 use cryptoxide::ed25519;
+use curve25519_dalek_ng::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
 use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
 
 /// ED25519 Signing Algorithm
 pub struct Ed25519;
@@ -23,6 +34,12 @@ impl AsRef<[u8]> for Priv {
     }
 }
 
+impl Drop for Priv {
+    fn drop(&mut self) {
+        self.0.zeroize()
+    }
+}
+
 impl AsRef<[u8]> for Pub {
     fn as_ref(&self) -> &[u8] {
         &self.0
@@ -49,6 +66,10 @@ impl AsymmetricPublicKey for Ed25519 {
         buf[0..ed25519::PUBLIC_KEY_LENGTH].clone_from_slice(data);
         Ok(Pub(buf))
     }
+
+    fn public_validate(public: &Pub, policy: ValidationPolicy) -> Result<(), PublicKeyError> {
+        validate_public(&public.0, policy)
+    }
 }
 
 impl AsymmetricKey for Ed25519 {
@@ -100,6 +121,10 @@ impl VerificationAlgorithm for Ed25519 {
         Ok(Sig(buf))
     }
 
+    fn signature_validate(signature: &Sig, policy: ValidationPolicy) -> Result<(), SignatureError> {
+        validate_signature(&signature.0, policy)
+    }
+
     fn verify_bytes(
         pubkey: &Self::Public,
         signature: &Self::Signature,
@@ -116,12 +141,187 @@ impl SigningAlgorithm for Ed25519 {
     }
 }
 
+/// Decode a point under the strict policy: canonically encoded and not of
+/// small order
+fn strict_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    let mut buf = [0; 32];
+    buf.copy_from_slice(bytes);
+    let point = CompressedEdwardsY(buf).decompress()?;
+    // the decompression reduces y modulo p and ignores the sign of x = 0,
+    // only the canonical encodings round trip
+    if point.compress().as_bytes() != &buf || point.is_small_order() {
+        return None;
+    }
+    Some(point)
+}
+
+/// Validate the encoding of an Ed25519 public key, shared with the
+/// algorithms using the same keys
+pub(crate) fn validate_public(
+    public: &[u8],
+    policy: ValidationPolicy,
+) -> Result<(), PublicKeyError> {
+    if policy.is_strict() && strict_point(public).is_none() {
+        return Err(PublicKeyError::StructureInvalid);
+    }
+    Ok(())
+}
+
+/// Validate the encoding `R || s` of an Ed25519 signature, shared with the
+/// algorithms using the same signatures
+pub(crate) fn validate_signature(
+    signature: &[u8],
+    policy: ValidationPolicy,
+) -> Result<(), SignatureError> {
+    if !policy.is_strict() {
+        return Ok(());
+    }
+    let mut s = [0; 32];
+    s.copy_from_slice(&signature[32..64]);
+    if strict_point(&signature[0..32]).is_none() || Scalar::from_canonical_bytes(s).is_none() {
+        return Err(SignatureError::StructureInvalid);
+    }
+    Ok(())
+}
+
+// The variants of Ed25519 with a domain separation prefix, from RFC 8032
+#[derive(Clone, Copy)]
+pub(crate) enum Dom2 {
+    Context = 0,
+    Prehashed = 1,
+}
+
+fn dom2(flag: Dom2, context: &[u8]) -> Sha512 {
+    assert!(context.len() <= 255, "context longer than 255 bytes");
+    assert!(
+        !(matches!(flag, Dom2::Context) && context.is_empty()),
+        "Ed25519ctx requires a non empty context"
+    );
+    Sha512::new()
+        .chain(b"SigEd25519 no Ed25519 collisions")
+        .chain([flag as u8, context.len() as u8])
+        .chain(context)
+}
+
+/// Sign with the Ed25519 expanded secret key `scalar || prefix`
+pub(crate) fn sign_dom2(
+    extended: &[u8; 64],
+    flag: Dom2,
+    context: &[u8],
+    msg: &[u8],
+) -> [u8; ed25519::SIGNATURE_LENGTH] {
+    let mut scalar_bytes = [0; 32];
+    scalar_bytes.copy_from_slice(&extended[0..32]);
+    let a = Scalar::from_bits(scalar_bytes);
+    scalar_bytes.zeroize();
+    let public = (&a * &ED25519_BASEPOINT_TABLE).compress();
+
+    let r = Scalar::from_hash(dom2(flag, context).chain(&extended[32..64]).chain(msg));
+    let big_r = (&r * &ED25519_BASEPOINT_TABLE).compress();
+    let k = Scalar::from_hash(
+        dom2(flag, context)
+            .chain(big_r.as_bytes())
+            .chain(public.as_bytes())
+            .chain(msg),
+    );
+    let s = k * a + r;
+
+    let mut signature = [0; ed25519::SIGNATURE_LENGTH];
+    signature[0..32].copy_from_slice(big_r.as_bytes());
+    signature[32..64].copy_from_slice(s.as_bytes());
+    signature
+}
+
+fn verify_dom2(pubkey: &Pub, signature: &Sig, flag: Dom2, context: &[u8], msg: &[u8]) -> bool {
+    if context.len() > 255 || (matches!(flag, Dom2::Context) && context.is_empty()) {
+        return false;
+    }
+    let a = match CompressedEdwardsY(pubkey.0).decompress() {
+        Some(a) => a,
+        None => return false,
+    };
+    let mut big_r = [0; 32];
+    big_r.copy_from_slice(&signature.0[0..32]);
+    let mut s = [0; 32];
+    s.copy_from_slice(&signature.0[32..64]);
+    let s = match Scalar::from_canonical_bytes(s) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let k = Scalar::from_hash(dom2(flag, context).chain(big_r).chain(pubkey.0).chain(msg));
+    // [s]B - [k]A = R
+    let expected = EdwardsPoint::vartime_double_scalar_mul_basepoint(&k, &-a, &s);
+    expected.compress().as_bytes() == &big_r
+}
+
+fn expand_seed(seed: &[u8; ed25519::SEED_LENGTH]) -> [u8; 64] {
+    let mut extended = [0; 64];
+    extended.copy_from_slice(&Sha512::digest(seed));
+    extended[0] &= 0b1111_1000;
+    extended[31] &= 0b0111_1111;
+    extended[31] |= 0b0100_0000;
+    extended
+}
+
+impl ContextVerificationAlgorithm for Ed25519 {
+    fn verify_bytes_with_context(
+        pubkey: &Self::Public,
+        signature: &Self::Signature,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Verification {
+        verify_dom2(pubkey, signature, Dom2::Context, context, msg).into()
+    }
+
+    fn verify_prehashed_bytes(
+        pubkey: &Self::Public,
+        signature: &Self::Signature,
+        context: &[u8],
+        prehash: &[u8; 64],
+    ) -> Verification {
+        verify_dom2(pubkey, signature, Dom2::Prehashed, context, prehash).into()
+    }
+}
+
+impl ContextSigningAlgorithm for Ed25519 {
+    fn sign_with_context(key: &Self::Secret, context: &[u8], msg: &[u8]) -> Sig {
+        let mut extended = expand_seed(&key.0);
+        let sig = sign_dom2(&extended, Dom2::Context, context, msg);
+        extended.zeroize();
+        Sig(sig)
+    }
+
+    fn sign_prehashed(key: &Self::Secret, context: &[u8], prehash: &[u8; 64]) -> Sig {
+        let mut extended = expand_seed(&key.0);
+        let sig = sign_dom2(&extended, Dom2::Prehashed, context, prehash);
+        extended.zeroize();
+        Sig(sig)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
-    use crate::key::KeyPair;
+    use crate::key::{KeyPair, PublicKey, SecretKey};
     use crate::sign::test::{keypair_signing_ko, keypair_signing_ok};
+    use crate::sign::{ContextSignature, PrehashedSignature, Signature, SigningContext};
+
+    struct Foo;
+    impl SigningContext for Foo {
+        const CONTEXT: &'static [u8] = b"foo";
+    }
+
+    struct Bar;
+    impl SigningContext for Bar {
+        const CONTEXT: &'static [u8] = b"bar";
+    }
+
+    struct Empty;
+    impl SigningContext for Empty {
+        const CONTEXT: &'static [u8] = b"";
+    }
 
     #[quickcheck]
     fn sign_ok(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
@@ -132,4 +332,245 @@ mod test {
     fn sign_ko(input: (KeyPair<Ed25519>, KeyPair<Ed25519>, Vec<u8>)) -> bool {
         keypair_signing_ko(input)
     }
+
+    #[quickcheck]
+    fn sign_with_context_ok(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
+        let (sk, pk) = input.0.into_keys();
+        let signature: ContextSignature<Foo, _, _> = sk.sign_with_context(&input.1);
+        signature.verify(&pk, &input.1) == Verification::Success
+    }
+
+    #[quickcheck]
+    fn signatures_do_not_cross_domains(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
+        let (sk, pk) = input.0.into_keys();
+        let data = input.1;
+
+        let signature: ContextSignature<Foo, Vec<u8>, _> = sk.sign_with_context(&data);
+        let other_context: ContextSignature<Bar, Vec<u8>, _> =
+            ContextSignature::from_binary(signature.as_ref()).unwrap();
+        let prehashed: PrehashedSignature<Foo, Vec<u8>, _> =
+            PrehashedSignature::from_binary(signature.as_ref()).unwrap();
+        let legacy: Signature<Vec<u8>, Ed25519> =
+            Signature::from_binary(signature.as_ref()).unwrap();
+
+        let plain = sk.sign(&data);
+        let plain_as_context: ContextSignature<Foo, Vec<u8>, _> =
+            ContextSignature::from_binary(plain.as_ref()).unwrap();
+
+        other_context.verify(&pk, &data) == Verification::Failed
+            && prehashed.verify(&pk, &data) == Verification::Failed
+            && legacy.verify(&pk, &data) == Verification::Failed
+            && plain_as_context.verify(&pk, &data) == Verification::Failed
+    }
+
+    #[quickcheck]
+    fn sign_prehashed_ok(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
+        let (sk, pk) = input.0.into_keys();
+        let signature: PrehashedSignature<Empty, _, _> = sk.sign_prehashed(&input.1);
+        signature.verify(&pk, &input.1) == Verification::Success
+    }
+
+    #[quickcheck]
+    fn prehashed_same_as_dalek(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
+        let (sk, pk) = input.0.into_keys();
+        let signature: PrehashedSignature<Foo, _, _> = sk.sign_prehashed(&input.1);
+
+        let dalek_sk = ed25519_dalek::SecretKey::from_bytes(sk.leak_secret().as_ref()).unwrap();
+        let dalek_pk = ed25519_dalek::PublicKey::from(&dalek_sk);
+        let dalek_signature = ed25519_dalek::ExpandedSecretKey::from(&dalek_sk)
+            .sign_prehashed(Sha512::new().chain(&input.1), &dalek_pk, Some(Foo::CONTEXT))
+            .unwrap();
+        dalek_pk.as_bytes() == pk.as_ref()
+            && dalek_signature.to_bytes()[..] == signature.as_ref()[..]
+    }
+
+    #[test]
+    #[should_panic]
+    fn ed25519ctx_empty_context() {
+        let sk = SecretKey::<Ed25519>::from_binary(&[0; 32]).unwrap();
+        let _: ContextSignature<Empty, [u8], _> = sk.sign_slice_with_context(b"data");
+    }
+
+    fn rfc8032_key(secret: &str, public: &str) -> (SecretKey<Ed25519>, PublicKey<Ed25519>) {
+        let sk = SecretKey::from_binary(&hex::decode(secret).unwrap()).unwrap();
+        let pk = PublicKey::from_binary(&hex::decode(public).unwrap()).unwrap();
+        assert_eq!(sk.to_public(), pk);
+        (sk, pk)
+    }
+
+    // RFC 8032 section 7.2, test vector foo
+    #[test]
+    fn ed25519ctx_test_vector() {
+        let (sk, pk) = rfc8032_key(
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+        );
+        let msg = hex::decode("f726936d19c800494e3fdaff20b276a8").unwrap();
+        let signature: ContextSignature<Foo, _, _> = sk.sign_with_context(&msg);
+        assert_eq!(
+            hex::encode(signature.as_ref()),
+            "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a\
+             8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d"
+        );
+        assert_eq!(signature.verify(&pk, &msg), Verification::Success);
+    }
+
+    // RFC 8032 section 7.3, test vector abc
+    #[test]
+    fn ed25519ph_test_vector() {
+        let (sk, pk) = rfc8032_key(
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+        );
+        let signature: PrehashedSignature<Empty, _, _> = sk.sign_prehashed(b"abc");
+        assert_eq!(
+            hex::encode(signature.as_ref()),
+            "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406"
+        );
+        assert_eq!(signature.verify(&pk, b"abc"), Verification::Success);
+    }
+
+    // RFC 8032 section 7.1, test 1
+    const RFC8032_PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const RFC8032_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                                     5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    // the order of the base point, little endian
+    const GROUP_ORDER: [u8; 32] = [
+        0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde,
+        0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x10,
+    ];
+
+    // the encodings of the points of small order, canonical or not
+    const SMALL_ORDER_POINTS: &[&str] = &[
+        // order 1
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        // order 2
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        // order 4
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        // order 8
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc85",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+        // non canonical: x = 0 with the sign bit set
+        "0100000000000000000000000000000000000000000000000000000000000080",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        // non canonical: y = p + 1 and y = p, which reduce to 1 and 0
+        "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    ];
+
+    fn with_scalar(signature: &[u8], s: &[u8]) -> Vec<u8> {
+        let mut out = signature[0..32].to_vec();
+        out.extend_from_slice(s);
+        out
+    }
+
+    fn add_group_order(s: &[u8]) -> [u8; 32] {
+        let mut sum = [0; 32];
+        let mut carry = 0u16;
+        for ((out, a), b) in sum.iter_mut().zip(s).zip(GROUP_ORDER.iter()) {
+            let v = *a as u16 + *b as u16 + carry;
+            *out = v as u8;
+            carry = v >> 8;
+        }
+        sum
+    }
+
+    #[quickcheck]
+    fn strict_accepts_generated_signatures(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
+        let (sk, pk) = input.0.into_keys();
+        let signature = sk.sign(&input.1);
+        let context: ContextSignature<Foo, _, _> = sk.sign_with_context(&input.1);
+        pk.validate(ValidationPolicy::Strict).is_ok()
+            && signature.validate(ValidationPolicy::Strict).is_ok()
+            && context.validate(ValidationPolicy::Strict).is_ok()
+    }
+
+    #[test]
+    fn strict_accepts_rfc8032_vector() {
+        let public = hex::decode(RFC8032_PUBLIC).unwrap();
+        let signature = hex::decode(RFC8032_SIGNATURE).unwrap();
+        for policy in &[ValidationPolicy::Legacy, ValidationPolicy::Strict] {
+            let pk = PublicKey::<Ed25519>::from_binary_with_policy(&public, *policy).unwrap();
+            let signature: Signature<[u8], Ed25519> =
+                Signature::from_binary_with_policy(&signature, *policy).unwrap();
+            assert_eq!(signature.verify_slice(&pk, b""), Verification::Success);
+        }
+    }
+
+    #[test]
+    fn strict_rejects_small_order_public_keys() {
+        for point in SMALL_ORDER_POINTS {
+            let public = hex::decode(point).unwrap();
+            assert!(PublicKey::<Ed25519>::from_binary_with_policy(
+                &public,
+                ValidationPolicy::Legacy
+            )
+            .is_ok());
+            assert_eq!(
+                PublicKey::<Ed25519>::from_binary_with_policy(&public, ValidationPolicy::Strict)
+                    .err(),
+                Some(PublicKeyError::StructureInvalid),
+                "public key {}",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn strict_rejects_small_order_commitments() {
+        let signature = hex::decode(RFC8032_SIGNATURE).unwrap();
+        for point in SMALL_ORDER_POINTS {
+            let mut malformed = hex::decode(point).unwrap();
+            malformed.extend_from_slice(&signature[32..64]);
+            assert_eq!(
+                Signature::<[u8], Ed25519>::from_binary_with_policy(
+                    &malformed,
+                    ValidationPolicy::Strict
+                )
+                .err(),
+                Some(SignatureError::StructureInvalid),
+                "R = {}",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn strict_rejects_non_canonical_scalars() {
+        let signature = hex::decode(RFC8032_SIGNATURE).unwrap();
+        let mut high_bit = signature[32..64].to_vec();
+        high_bit[31] |= 0x80;
+        let malformed = vec![
+            // the malleated signature: s + l is the same scalar as s
+            with_scalar(&signature, &add_group_order(&signature[32..64])),
+            with_scalar(&signature, &GROUP_ORDER),
+            with_scalar(&signature, &high_bit),
+            with_scalar(&signature, &[0xff; 32]),
+        ];
+        for signature in malformed {
+            assert!(Signature::<[u8], Ed25519>::from_binary_with_policy(
+                &signature,
+                ValidationPolicy::Legacy
+            )
+            .is_ok());
+            assert_eq!(
+                Signature::<[u8], Ed25519>::from_binary_with_policy(
+                    &signature,
+                    ValidationPolicy::Strict
+                )
+                .err(),
+                Some(SignatureError::StructureInvalid),
+                "signature {}",
+                hex::encode(&signature)
+            );
+        }
+    }
 }
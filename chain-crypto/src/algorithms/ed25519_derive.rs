@@ -1,7 +1,9 @@
+use super::ed25519::{validate_public, validate_signature};
 use crate::key::{
     AsymmetricKey, AsymmetricPublicKey, PublicKeyError, SecretKeyError, SecretKeySizeStatic,
 };
 use crate::sign::{SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
+use crate::validation::ValidationPolicy;
 
 use ed25519_bip32 as i;
 use ed25519_bip32::{XPrv, XPub, XPRV_SIZE, XPUB_SIZE};
@@ -36,6 +38,11 @@ impl AsymmetricPublicKey for Ed25519Bip32 {
         let xpub = XPub::from_slice(data)?;
         Ok(xpub)
     }
+
+    fn public_validate(public: &XPub, policy: ValidationPolicy) -> Result<(), PublicKeyError> {
+        // the Ed25519 public key followed by the chain code
+        validate_public(&public.as_ref()[0..32], policy)
+    }
 }
 
 impl AsymmetricKey for Ed25519Bip32 {
@@ -88,6 +95,13 @@ impl VerificationAlgorithm for Ed25519Bip32 {
         Ok(xsig)
     }
 
+    fn signature_validate(
+        signature: &XSig,
+        policy: ValidationPolicy,
+    ) -> Result<(), SignatureError> {
+        validate_signature(signature.as_ref(), policy)
+    }
+
     fn verify_bytes(
         pubkey: &Self::Public,
         signature: &Self::Signature,
@@ -1,10 +1,11 @@
 use crate::key::{AsymmetricKey, AsymmetricPublicKey, SecretKeyError, SecretKeySizeStatic};
-use crate::sign::SigningAlgorithm;
+use crate::sign::{ContextSigningAlgorithm, SigningAlgorithm};
 
 use super::ed25519 as ei;
 
 use cryptoxide::ed25519;
 use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
 
 /// ED25519 Signing Algorithm with extended secret key
 pub struct Ed25519Extended;
@@ -20,6 +21,12 @@ impl AsRef<[u8]> for ExtendedPriv {
     }
 }
 
+impl Drop for ExtendedPriv {
+    fn drop(&mut self) {
+        self.0.zeroize()
+    }
+}
+
 impl From<[u8; EXTENDED_KEY_SIZE]> for ExtendedPriv {
     fn from(b: [u8; EXTENDED_KEY_SIZE]) -> ExtendedPriv {
         ExtendedPriv(b)
@@ -68,6 +75,16 @@ impl SigningAlgorithm for Ed25519Extended {
     }
 }
 
+impl ContextSigningAlgorithm for Ed25519Extended {
+    fn sign_with_context(key: &Self::Secret, context: &[u8], msg: &[u8]) -> ei::Sig {
+        ei::Sig(ei::sign_dom2(&key.0, ei::Dom2::Context, context, msg))
+    }
+
+    fn sign_prehashed(key: &Self::Secret, context: &[u8], prehash: &[u8; 64]) -> ei::Sig {
+        ei::Sig(ei::sign_dom2(&key.0, ei::Dom2::Prehashed, context, prehash))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -12,7 +12,7 @@ pub mod common;
 pub mod sum;
 
 use crate::evolving::{EvolvingStatus, KeyEvolvingAlgorithm};
-use crate::kes::KeyEvolvingSignatureAlgorithm;
+use crate::kes::{IncrementalKesVerification, KeyEvolvingSignatureAlgorithm};
 use crate::key::{AsymmetricKey, AsymmetricPublicKey, PublicKeyError, SecretKeyError};
 use crate::sign::{SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
 use rand_core::{CryptoRng, RngCore};
@@ -115,3 +115,23 @@ impl KeyEvolvingSignatureAlgorithm for SumEd25519_12 {
         sig.t() as u32
     }
 }
+
+impl IncrementalKesVerification for SumEd25519_12 {
+    type VerificationContext = sum::VerificationContext;
+
+    fn verification_context(pk: &Self::Public) -> Self::VerificationContext {
+        sum::VerificationContext::new(pk.clone())
+    }
+
+    fn verify_bytes_with_context(
+        context: &mut Self::VerificationContext,
+        signature: &Self::Signature,
+        msg: &[u8],
+    ) -> Verification {
+        if context.verify(msg, signature) {
+            Verification::Success
+        } else {
+            Verification::Failed
+        }
+    }
+}
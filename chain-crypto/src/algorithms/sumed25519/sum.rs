@@ -420,6 +420,11 @@ impl Signature {
         MerkleSignaturePublicKeys(bytes)
     }
 
+    // the leaf public key followed by the Merkle path
+    fn authenticated_path(&self) -> &[u8] {
+        &self.0[Self::PK_OFFSET..]
+    }
+
     // --------------------------------------
 
     fn create(
@@ -630,9 +635,14 @@ pub fn verify(pk: &PublicKey, m: &[u8], sig: &Signature) -> bool {
         return false;
     }
 
-    let t = sig.t();
-
     // verify that we have the expected root public key afterall
+    &merkle_root(sig) == pk
+}
+
+// the root public key the leaf public key of the signature leads to through
+// its Merkle path
+fn merkle_root(sig: &Signature) -> PublicKey {
+    let t = sig.t();
     let mut got = PublicKey::from_ed25519_publickey(&sig.pk());
     for (i, pk_combi) in sig.merkle_pks().rev().enumerate() {
         let right = (t & (1 << i)) != 0;
@@ -642,10 +652,55 @@ pub fn verify(pk: &PublicKey, m: &[u8], sig: &Signature) -> bool {
             got = hash(&got, &pk_combi);
         }
     }
-    if &got != pk {
-        return false;
+    got
+}
+
+/// Verification of the signatures of a single public key.
+///
+/// All the signatures of a period are made with the same leaf key, the
+/// context remembers the leaf key and Merkle path authenticated for the
+/// last verified period so that the path is not hashed again for the
+/// following signatures of that period. The outcome is the same as
+/// [`verify`].
+#[derive(Clone)]
+pub struct VerificationContext {
+    pk: PublicKey,
+    // the period and the bytes of the signature following the leaf
+    // signature, i.e. the leaf public key and the Merkle path
+    authenticated: Option<(usize, Vec<u8>)>,
+}
+
+impl VerificationContext {
+    pub fn new(pk: PublicKey) -> Self {
+        VerificationContext {
+            pk,
+            authenticated: None,
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+
+    pub fn verify(&mut self, m: &[u8], sig: &Signature) -> bool {
+        if sig.pk().verify(m, &sig.sigma()).is_err() {
+            return false;
+        }
+
+        let t = sig.t();
+        let path = sig.authenticated_path();
+        if let Some((period, authenticated)) = &self.authenticated {
+            if *period == t && authenticated.as_slice() == path {
+                return true;
+            }
+        }
+
+        if merkle_root(sig) != self.pk {
+            return false;
+        }
+        self.authenticated = Some((t, path.to_vec()));
+        true
     }
-    true
 }
 
 pub fn update(secret: &mut SecretKey) -> Result<(), Error> {
@@ -807,4 +862,33 @@ mod tests {
         let (_, pkrec) = sumrec::keygen(depth, &seed);
         pk.as_bytes() == pkrec.as_bytes()
     }
+
+    #[test]
+    fn verification_context_is_equivalent_to_verify() {
+        let depth = Depth(3);
+        let (mut sk, pk) = keygen(depth, &Seed::from_bytes([1u8; 32]));
+        let (_, other_pk) = keygen(depth, &Seed::from_bytes([2u8; 32]));
+        let mut context = VerificationContext::new(pk.clone());
+        let mut other_context = VerificationContext::new(other_pk);
+
+        loop {
+            for m in &[&b"header 1"[..], &b"header 2"[..]] {
+                let sig = sign(&sk, m);
+                assert!(verify(&pk, m, &sig));
+                assert!(context.verify(m, &sig));
+                assert!(!context.verify(b"another header", &sig));
+                assert!(!other_context.verify(m, &sig));
+
+                // a Merkle path altered after its period was authenticated
+                let mut tampered = sig.clone();
+                let last = tampered.0.len() - 1;
+                tampered.0[last] ^= 1;
+                assert!(!verify(&pk, m, &tampered));
+                assert!(!context.verify(m, &tampered));
+            }
+            if update(&mut sk).is_err() {
+                break;
+            }
+        }
+    }
 }
@@ -1,4 +1,5 @@
 mod dleq;
+pub mod range;
 #[allow(clippy::module_inception)]
 pub mod vrf;
 
@@ -7,6 +8,7 @@ use crate::key::{
 };
 use crate::vrf::{VRFVerification, VerifiableRandomFunction};
 use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
 
 pub use vrf::ProvenOutputSeed;
 
@@ -42,10 +44,9 @@ impl AsymmetricKey for Curve25519_2HashDH {
         }
         let mut buf = [0; vrf::SECRET_SIZE];
         buf[0..vrf::SECRET_SIZE].clone_from_slice(data);
-        match vrf::SecretKey::from_bytes(buf) {
-            None => Err(SecretKeyError::StructureInvalid),
-            Some(k) => Ok(k),
-        }
+        let key = vrf::SecretKey::from_bytes(buf);
+        buf.zeroize();
+        key.ok_or(SecretKeyError::StructureInvalid)
     }
 }
 
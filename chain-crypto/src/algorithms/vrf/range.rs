@@ -0,0 +1,130 @@
+//! Mapping of the VRF outputs to numbers, for sortition.
+//!
+//! The outputs of the VRF (e.g. the [`Blake2b256`] given by
+//! [`OutputSeed::to_output`]) are uniformly distributed bytes. Only their
+//! first 8 bytes, read as a big endian `u64` noted `x`, are used: `x` stands
+//! for the fraction `x / 2^64` of the unit interval, as in the Praos leader
+//! selection. The mappings below document how they round, so that all the
+//! implementations of a sortition get the same results:
+//!
+//! * [`to_unit_f64`] converts the fraction to the nearest `f64`, which is
+//!   exactly `1.0` for the last 1024 values of `x`;
+//! * [`to_range`] rounds down, mapping to `[0, n)` with a bias of at most
+//!   `n / 2^64` towards the lowest numbers;
+//! * [`below_ratio`] compares the fraction with a ratio of integers without
+//!   any rounding.
+//!
+//! [`Blake2b256`]: crate::hash::Blake2b256
+//! [`OutputSeed::to_output`]: super::vrf::OutputSeed::to_output
+
+use std::convert::TryInto;
+use std::num::NonZeroU64;
+
+/// The number of bytes of the output used by the mappings
+pub const OUTPUT_PREFIX_SIZE: usize = 8;
+
+/// The first 8 bytes of the output, as a big endian integer
+///
+/// # Panics
+///
+/// The output must be at least 8 bytes long.
+pub fn output_prefix(output: &[u8]) -> u64 {
+    u64::from_be_bytes(
+        output[..OUTPUT_PREFIX_SIZE]
+            .try_into()
+            .expect("output of at least 8 bytes"),
+    )
+}
+
+/// The output as a fraction of the unit interval, `x / 2^64` rounded to the
+/// nearest `f64` (ties to even). The result is in `[0, 1]`, and is `1.0`
+/// when `x` is at least `2^64 - 2^10`.
+pub fn to_unit_f64(output: &[u8]) -> f64 {
+    output_prefix(output) as f64 / 18_446_744_073_709_551_616.0
+}
+
+/// The output as a number of `[0, n)`, `floor(x * n / 2^64)`
+///
+/// # Panics
+///
+/// `n` must not be zero.
+pub fn to_range(output: &[u8], n: u64) -> u64 {
+    assert!(n > 0, "empty range");
+    ((u128::from(output_prefix(output)) * u128::from(n)) >> 64) as u64
+}
+
+/// Whether the output, as a fraction of the unit interval, is strictly lower
+/// than `numerator / denominator`. A ratio of 0 is never reached, a ratio of
+/// 1 or more always is.
+pub fn below_ratio(output: &[u8], numerator: u64, denominator: NonZeroU64) -> bool {
+    // x / 2^64 < numerator / denominator
+    u128::from(output_prefix(output)) * u128::from(denominator.get()) < u128::from(numerator) << 64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::TestResult;
+
+    fn output(x: u64) -> [u8; 32] {
+        let mut output = [0xaa; 32];
+        output[..8].copy_from_slice(&x.to_be_bytes());
+        output
+    }
+
+    fn ratio(numerator: u64, denominator: u64) -> (u64, NonZeroU64) {
+        (numerator, NonZeroU64::new(denominator).unwrap())
+    }
+
+    #[test]
+    fn rounding_at_the_bounds() {
+        assert_eq!(to_unit_f64(&output(0)), 0.0);
+        assert_eq!(to_unit_f64(&output(1 << 63)), 0.5);
+        assert_eq!(
+            to_unit_f64(&output(u64::MAX - 1024)),
+            1.0 - f64::EPSILON / 2.0
+        );
+        assert_eq!(to_unit_f64(&output(u64::MAX - 1023)), 1.0);
+        assert_eq!(to_unit_f64(&output(u64::MAX)), 1.0);
+
+        assert_eq!(to_range(&output(0), 3), 0);
+        assert_eq!(to_range(&output(u64::MAX), 3), 2);
+        assert_eq!(to_range(&output(u64::MAX), 1), 0);
+        // the lowest values reaching 1/3 and 2/3 of the unit interval
+        let (one_third, two_thirds) = (u64::MAX / 3 + 1, u64::MAX / 3 * 2 + 1);
+        assert_eq!(to_range(&output(one_third - 1), 3), 0);
+        assert_eq!(to_range(&output(one_third), 3), 1);
+        assert_eq!(to_range(&output(two_thirds - 1), 3), 1);
+        assert_eq!(to_range(&output(two_thirds), 3), 2);
+
+        let (numerator, denominator) = ratio(1, 2);
+        assert!(below_ratio(&output((1 << 63) - 1), numerator, denominator));
+        assert!(!below_ratio(&output(1 << 63), numerator, denominator));
+        let (numerator, denominator) = ratio(0, 1);
+        assert!(!below_ratio(&output(0), numerator, denominator));
+        let (numerator, denominator) = ratio(1, 1);
+        assert!(below_ratio(&output(u64::MAX), numerator, denominator));
+    }
+
+    #[quickcheck]
+    fn to_range_is_monotonic_and_in_range(x: u64, y: u64, n: u64) -> TestResult {
+        if n == 0 {
+            return TestResult::discard();
+        }
+        let (low, high) = (x.min(y), x.max(y));
+        let (low, high) = (to_range(&output(low), n), to_range(&output(high), n));
+        TestResult::from_bool(low <= high && high < n)
+    }
+
+    #[quickcheck]
+    fn below_ratio_agrees_with_to_range(x: u64, numerator: u64, denominator: u64) -> TestResult {
+        if denominator == 0 || numerator > denominator {
+            return TestResult::discard();
+        }
+        // to_range rounds down: x is below k / n exactly when its number
+        // in [0, n) is lower than k
+        let below = to_range(&output(x), denominator) < numerator;
+        let (numerator, denominator) = ratio(numerator, denominator);
+        TestResult::from_bool(below_ratio(&output(x), numerator, denominator) == below)
+    }
+}
@@ -0,0 +1,415 @@
+//! Public keys and signatures of any of the supported signature algorithms.
+//!
+//! [`AnyPublicKey`] and [`AnySignature`] carry the [`SignatureAlgorithmId`]
+//! of their algorithm, so that a format made of them can accept a new
+//! algorithm without changing its layout. The tagged binary form is the
+//! algorithm identifier byte followed by the key or the signature in the
+//! format of the algorithm.
+//!
+//! A signature only verifies with a public key of the same algorithm.
+
+use crate::{
+    bech32::{self, Bech32},
+    key::{AsymmetricPublicKey, PublicKey, PublicKeyError},
+    sign::{Signature, SignatureError, Verification, VerificationAlgorithm},
+    validation::ValidationPolicy,
+    Ed25519, Ed25519Bip32,
+};
+use std::fmt;
+
+/// Identifier of a signature algorithm, as written in the tagged binary form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SignatureAlgorithmId {
+    Ed25519,
+    Ed25519Bip32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyKeyError {
+    UnknownAlgorithm(u8),
+    PublicKey(PublicKeyError),
+    Signature(SignatureError),
+}
+
+/// A public key of one of the algorithms of [`SignatureAlgorithmId`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AnyPublicKey {
+    Ed25519(PublicKey<Ed25519>),
+    Ed25519Bip32(PublicKey<Ed25519Bip32>),
+}
+
+/// A signature of one of the algorithms of [`SignatureAlgorithmId`]
+pub enum AnySignature<T: ?Sized> {
+    Ed25519(Signature<T, Ed25519>),
+    Ed25519Bip32(Signature<T, Ed25519Bip32>),
+}
+
+impl SignatureAlgorithmId {
+    /// All the supported algorithms
+    pub const ALL: [SignatureAlgorithmId; 2] = [
+        SignatureAlgorithmId::Ed25519,
+        SignatureAlgorithmId::Ed25519Bip32,
+    ];
+
+    pub const fn to_u8(self) -> u8 {
+        match self {
+            SignatureAlgorithmId::Ed25519 => 1,
+            SignatureAlgorithmId::Ed25519Bip32 => 2,
+        }
+    }
+
+    pub fn from_u8(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(SignatureAlgorithmId::Ed25519),
+            2 => Some(SignatureAlgorithmId::Ed25519Bip32),
+            _ => None,
+        }
+    }
+
+    pub fn public_key_size(self) -> usize {
+        match self {
+            SignatureAlgorithmId::Ed25519 => Ed25519::PUBLIC_KEY_SIZE,
+            SignatureAlgorithmId::Ed25519Bip32 => Ed25519Bip32::PUBLIC_KEY_SIZE,
+        }
+    }
+
+    pub fn signature_size(self) -> usize {
+        match self {
+            SignatureAlgorithmId::Ed25519 => Ed25519::SIGNATURE_SIZE,
+            SignatureAlgorithmId::Ed25519Bip32 => Ed25519Bip32::SIGNATURE_SIZE,
+        }
+    }
+
+    /// The HRP of the bech32 encoding of the public keys of the algorithm
+    pub fn public_key_bech32_hrp(self) -> &'static str {
+        match self {
+            SignatureAlgorithmId::Ed25519 => Ed25519::PUBLIC_BECH32_HRP,
+            SignatureAlgorithmId::Ed25519Bip32 => Ed25519Bip32::PUBLIC_BECH32_HRP,
+        }
+    }
+}
+
+fn split_tag(data: &[u8]) -> Result<(SignatureAlgorithmId, &[u8]), AnyKeyError> {
+    match data.split_first() {
+        Some((tag, data)) => SignatureAlgorithmId::from_u8(*tag)
+            .map(|algorithm| (algorithm, data))
+            .ok_or(AnyKeyError::UnknownAlgorithm(*tag)),
+        None => Err(AnyKeyError::UnknownAlgorithm(0)),
+    }
+}
+
+fn tagged(algorithm: SignatureAlgorithmId, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 1);
+    out.push(algorithm.to_u8());
+    out.extend_from_slice(data);
+    out
+}
+
+impl AnyPublicKey {
+    pub fn algorithm(&self) -> SignatureAlgorithmId {
+        match self {
+            AnyPublicKey::Ed25519(_) => SignatureAlgorithmId::Ed25519,
+            AnyPublicKey::Ed25519Bip32(_) => SignatureAlgorithmId::Ed25519Bip32,
+        }
+    }
+
+    /// Read a public key of the given algorithm, without the identifier
+    pub fn from_binary(
+        algorithm: SignatureAlgorithmId,
+        data: &[u8],
+    ) -> Result<Self, PublicKeyError> {
+        match algorithm {
+            SignatureAlgorithmId::Ed25519 => {
+                PublicKey::from_binary(data).map(AnyPublicKey::Ed25519)
+            }
+            SignatureAlgorithmId::Ed25519Bip32 => {
+                PublicKey::from_binary(data).map(AnyPublicKey::Ed25519Bip32)
+            }
+        }
+    }
+
+    /// Read a public key of the given algorithm, without the identifier, and
+    /// check its encoding against the policy
+    pub fn from_binary_with_policy(
+        algorithm: SignatureAlgorithmId,
+        data: &[u8],
+        policy: ValidationPolicy,
+    ) -> Result<Self, PublicKeyError> {
+        let publickey = Self::from_binary(algorithm, data)?;
+        publickey.validate(policy)?;
+        Ok(publickey)
+    }
+
+    pub fn validate(&self, policy: ValidationPolicy) -> Result<(), PublicKeyError> {
+        match self {
+            AnyPublicKey::Ed25519(publickey) => publickey.validate(policy),
+            AnyPublicKey::Ed25519Bip32(publickey) => publickey.validate(policy),
+        }
+    }
+
+    pub fn from_tagged_binary(data: &[u8]) -> Result<Self, AnyKeyError> {
+        let (algorithm, data) = split_tag(data)?;
+        Self::from_binary(algorithm, data).map_err(AnyKeyError::PublicKey)
+    }
+
+    pub fn to_tagged_binary(&self) -> Vec<u8> {
+        tagged(self.algorithm(), self.as_ref())
+    }
+
+    /// Read a public key from its bech32 encoding, the algorithm is told by
+    /// the HRP
+    pub fn try_from_bech32_str(bech32_str: &str) -> Result<Self, bech32::Error> {
+        let (entry, bytes) = bech32::try_from_registered_bech32(bech32_str)?;
+        let algorithm = SignatureAlgorithmId::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.public_key_bech32_hrp() == entry.hrp)
+            .ok_or_else(|| bech32::Error::HrpUnknown(entry.hrp.to_owned()))?;
+        Self::from_binary(algorithm, &bytes).map_err(bech32::Error::data_invalid)
+    }
+
+    pub fn to_bech32_str(&self) -> String {
+        match self {
+            AnyPublicKey::Ed25519(publickey) => publickey.to_bech32_str(),
+            AnyPublicKey::Ed25519Bip32(publickey) => publickey.to_bech32_str(),
+        }
+    }
+}
+
+impl<T: ?Sized> AnySignature<T> {
+    pub fn algorithm(&self) -> SignatureAlgorithmId {
+        match self {
+            AnySignature::Ed25519(_) => SignatureAlgorithmId::Ed25519,
+            AnySignature::Ed25519Bip32(_) => SignatureAlgorithmId::Ed25519Bip32,
+        }
+    }
+
+    /// Read a signature of the given algorithm, without the identifier
+    pub fn from_binary(
+        algorithm: SignatureAlgorithmId,
+        data: &[u8],
+    ) -> Result<Self, SignatureError> {
+        match algorithm {
+            SignatureAlgorithmId::Ed25519 => {
+                Signature::from_binary(data).map(AnySignature::Ed25519)
+            }
+            SignatureAlgorithmId::Ed25519Bip32 => {
+                Signature::from_binary(data).map(AnySignature::Ed25519Bip32)
+            }
+        }
+    }
+
+    /// Read a signature of the given algorithm, without the identifier, and
+    /// check its encoding against the policy
+    pub fn from_binary_with_policy(
+        algorithm: SignatureAlgorithmId,
+        data: &[u8],
+        policy: ValidationPolicy,
+    ) -> Result<Self, SignatureError> {
+        let signature = Self::from_binary(algorithm, data)?;
+        signature.validate(policy)?;
+        Ok(signature)
+    }
+
+    pub fn validate(&self, policy: ValidationPolicy) -> Result<(), SignatureError> {
+        match self {
+            AnySignature::Ed25519(signature) => signature.validate(policy),
+            AnySignature::Ed25519Bip32(signature) => signature.validate(policy),
+        }
+    }
+
+    pub fn from_tagged_binary(data: &[u8]) -> Result<Self, AnyKeyError> {
+        let (algorithm, data) = split_tag(data)?;
+        Self::from_binary(algorithm, data).map_err(AnyKeyError::Signature)
+    }
+
+    pub fn to_tagged_binary(&self) -> Vec<u8> {
+        tagged(self.algorithm(), self.as_ref())
+    }
+
+    pub fn coerce<U: ?Sized>(self) -> AnySignature<U> {
+        match self {
+            AnySignature::Ed25519(signature) => AnySignature::Ed25519(signature.coerce()),
+            AnySignature::Ed25519Bip32(signature) => AnySignature::Ed25519Bip32(signature.coerce()),
+        }
+    }
+
+    /// Verify the signature with the algorithm of the public key, fails if
+    /// the key is of another algorithm than the signature
+    #[must_use]
+    pub fn verify_slice(&self, publickey: &AnyPublicKey, slice: &[u8]) -> Verification {
+        match (self, publickey) {
+            (AnySignature::Ed25519(signature), AnyPublicKey::Ed25519(publickey)) => {
+                signature.verify_slice(publickey, slice)
+            }
+            (AnySignature::Ed25519Bip32(signature), AnyPublicKey::Ed25519Bip32(publickey)) => {
+                signature.verify_slice(publickey, slice)
+            }
+            _ => Verification::Failed,
+        }
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> AnySignature<T> {
+    #[must_use]
+    pub fn verify(&self, publickey: &AnyPublicKey, object: &T) -> Verification {
+        self.verify_slice(publickey, object.as_ref())
+    }
+}
+
+impl AsRef<[u8]> for AnyPublicKey {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            AnyPublicKey::Ed25519(publickey) => publickey.as_ref(),
+            AnyPublicKey::Ed25519Bip32(publickey) => publickey.as_ref(),
+        }
+    }
+}
+
+impl<T: ?Sized> AsRef<[u8]> for AnySignature<T> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            AnySignature::Ed25519(signature) => signature.as_ref(),
+            AnySignature::Ed25519Bip32(signature) => signature.as_ref(),
+        }
+    }
+}
+
+impl<T> Clone for AnySignature<T> {
+    fn clone(&self) -> Self {
+        match self {
+            AnySignature::Ed25519(signature) => AnySignature::Ed25519(signature.clone()),
+            AnySignature::Ed25519Bip32(signature) => AnySignature::Ed25519Bip32(signature.clone()),
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for AnySignature<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnySignature::Ed25519(signature) => f.debug_tuple("Ed25519").field(signature).finish(),
+            AnySignature::Ed25519Bip32(signature) => {
+                f.debug_tuple("Ed25519Bip32").field(signature).finish()
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> PartialEq for AnySignature<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.algorithm() == other.algorithm() && self.as_ref() == other.as_ref()
+    }
+}
+
+impl<T: ?Sized> Eq for AnySignature<T> {}
+
+impl From<PublicKey<Ed25519>> for AnyPublicKey {
+    fn from(publickey: PublicKey<Ed25519>) -> Self {
+        AnyPublicKey::Ed25519(publickey)
+    }
+}
+
+impl From<PublicKey<Ed25519Bip32>> for AnyPublicKey {
+    fn from(publickey: PublicKey<Ed25519Bip32>) -> Self {
+        AnyPublicKey::Ed25519Bip32(publickey)
+    }
+}
+
+impl<T: ?Sized> From<Signature<T, Ed25519>> for AnySignature<T> {
+    fn from(signature: Signature<T, Ed25519>) -> Self {
+        AnySignature::Ed25519(signature)
+    }
+}
+
+impl<T: ?Sized> From<Signature<T, Ed25519Bip32>> for AnySignature<T> {
+    fn from(signature: Signature<T, Ed25519Bip32>) -> Self {
+        AnySignature::Ed25519Bip32(signature)
+    }
+}
+
+impl fmt::Display for SignatureAlgorithmId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureAlgorithmId::Ed25519 => "ed25519",
+            SignatureAlgorithmId::Ed25519Bip32 => "ed25519-bip32",
+        }
+        .fmt(f)
+    }
+}
+
+impl fmt::Display for AnyKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnyKeyError::UnknownAlgorithm(tag) => write!(f, "unknown signature algorithm {}", tag),
+            AnyKeyError::PublicKey(_) => "invalid public key".fmt(f),
+            AnyKeyError::Signature(_) => "invalid signature".fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AnyKeyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnyKeyError::UnknownAlgorithm(_) => None,
+            AnyKeyError::PublicKey(e) => Some(e),
+            AnyKeyError::Signature(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestCryptoGen;
+
+    #[quickcheck]
+    fn tagged_signatures_verify(gen: TestCryptoGen, data: Vec<u8>) -> bool {
+        let ed25519 = gen.keypair::<Ed25519>(0);
+        let bip32 = gen.keypair::<Ed25519Bip32>(1);
+        let ed25519_pk = AnyPublicKey::from(ed25519.public_key().clone());
+        let bip32_pk = AnyPublicKey::from(bip32.public_key().clone());
+        let ed25519_sig: AnySignature<[u8]> = ed25519.private_key().sign_slice(&data).into();
+        let bip32_sig: AnySignature<[u8]> = bip32.private_key().sign_slice(&data).into();
+
+        let decoded_pk = AnyPublicKey::from_tagged_binary(&bip32_pk.to_tagged_binary()).unwrap();
+        let decoded_sig =
+            AnySignature::<[u8]>::from_tagged_binary(&ed25519_sig.to_tagged_binary()).unwrap();
+
+        decoded_pk == bip32_pk
+            && decoded_sig == ed25519_sig
+            && ed25519_sig.verify_slice(&ed25519_pk, &data) == Verification::Success
+            && bip32_sig.verify_slice(&bip32_pk, &data) == Verification::Success
+            && ed25519_sig.verify_slice(&bip32_pk, &data) == Verification::Failed
+            && bip32_sig.verify_slice(&ed25519_pk, &data) == Verification::Failed
+    }
+
+    #[quickcheck]
+    fn bech32_public_keys_keep_their_algorithm(gen: TestCryptoGen) -> bool {
+        let ed25519_pk = AnyPublicKey::from(gen.keypair::<Ed25519>(0).public_key().clone());
+        let bip32 = gen.keypair::<Ed25519Bip32>(1);
+        let bip32_pk = AnyPublicKey::from(bip32.public_key().clone());
+
+        AnyPublicKey::try_from_bech32_str(&ed25519_pk.to_bech32_str()).unwrap() == ed25519_pk
+            && AnyPublicKey::try_from_bech32_str(&bip32_pk.to_bech32_str()).unwrap() == bip32_pk
+            && matches!(
+                AnyPublicKey::try_from_bech32_str(&bip32.private_key().to_bech32_str()),
+                Err(bech32::Error::HrpUnknown(_))
+            )
+    }
+
+    #[test]
+    fn unknown_algorithm_is_rejected() {
+        assert_eq!(
+            AnyPublicKey::from_tagged_binary(&[3; 33]),
+            Err(AnyKeyError::UnknownAlgorithm(3))
+        );
+        assert_eq!(
+            AnySignature::<[u8]>::from_tagged_binary(&[]),
+            Err(AnyKeyError::UnknownAlgorithm(0))
+        );
+        assert!(matches!(
+            AnyPublicKey::from_tagged_binary(&[1; 8]),
+            Err(AnyKeyError::PublicKey(_))
+        ));
+    }
+}
@@ -1,3 +1,19 @@
+//! Bech32 encoding of the keys, signatures and hashes.
+//!
+//! Every encodable type has its own human readable part (HRP), given by the
+//! [`Bech32`] trait, and decoding a string with the HRP of another type
+//! fails. The [`Bech32Registered`] trait maps each of these types to the
+//! kind of value and the algorithm its HRP identifies. The [`REGISTRY`] is
+//! built out of this mapping, so that a tool can tell which type a string of
+//! user input is meant for, or why it was refused.
+
+use crate::{
+    digest::{Digest, DigestOf},
+    hash,
+    key::{AsymmetricKey, AsymmetricPublicKey, PublicKey, SecretKey},
+    sign::{Signature, VerificationAlgorithm},
+    Curve25519_2HashDH, Ed25519, Ed25519Bip32, Ed25519Extended, SumEd25519_12,
+};
 use bech32::{self, Error as Bech32Error, FromBase32, ToBase32};
 use std::error::Error as StdError;
 use std::fmt;
@@ -15,6 +31,121 @@ pub trait Bech32 {
     fn to_bech32_str(&self) -> String;
 }
 
+/// The kind of value identified by an HRP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bech32Kind {
+    PublicKey,
+    SecretKey,
+    Signature,
+    Hash,
+}
+
+/// An HRP of the crate with what it encodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bech32Entry {
+    pub hrp: &'static str,
+    pub kind: Bech32Kind,
+    /// the name of the algorithm
+    pub algorithm: &'static str,
+}
+
+/// Name of an algorithm, in the description of the HRPs of its values
+pub trait Bech32Algorithm {
+    const ALGORITHM_NAME: &'static str;
+}
+
+/// Type level mapping of an encodable type to the kind of value its HRP
+/// identifies
+pub trait Bech32Registered: Bech32 {
+    const BECH32_KIND: Bech32Kind;
+    const BECH32_ALGORITHM: &'static str;
+
+    const BECH32_ENTRY: Bech32Entry = Bech32Entry {
+        hrp: Self::BECH32_HRP,
+        kind: Self::BECH32_KIND,
+        algorithm: Self::BECH32_ALGORITHM,
+    };
+}
+
+macro_rules! bech32_algorithm {
+    ($algorithm:ty, $name:expr) => {
+        impl Bech32Algorithm for $algorithm {
+            const ALGORITHM_NAME: &'static str = $name;
+        }
+    };
+}
+
+bech32_algorithm!(Ed25519, "ed25519");
+bech32_algorithm!(Ed25519Extended, "ed25519 extended");
+bech32_algorithm!(Ed25519Bip32, "ed25519 bip32");
+bech32_algorithm!(SumEd25519_12, "kes sum ed25519 depth 12");
+bech32_algorithm!(Curve25519_2HashDH, "vrf");
+
+impl<A: AsymmetricPublicKey + Bech32Algorithm> Bech32Registered for PublicKey<A> {
+    const BECH32_KIND: Bech32Kind = Bech32Kind::PublicKey;
+    const BECH32_ALGORITHM: &'static str = A::ALGORITHM_NAME;
+}
+
+impl<A: AsymmetricKey + Bech32Algorithm> Bech32Registered for SecretKey<A> {
+    const BECH32_KIND: Bech32Kind = Bech32Kind::SecretKey;
+    const BECH32_ALGORITHM: &'static str = A::ALGORITHM_NAME;
+}
+
+impl<T, A: VerificationAlgorithm + Bech32Algorithm> Bech32Registered for Signature<T, A> {
+    const BECH32_KIND: Bech32Kind = Bech32Kind::Signature;
+    const BECH32_ALGORITHM: &'static str = A::ALGORITHM_NAME;
+}
+
+impl Bech32Registered for hash::Blake2b256 {
+    const BECH32_KIND: Bech32Kind = Bech32Kind::Hash;
+    const BECH32_ALGORITHM: &'static str = "blake2b256";
+}
+
+impl Bech32Registered for Digest<hash::Blake2b256> {
+    const BECH32_KIND: Bech32Kind = Bech32Kind::Hash;
+    const BECH32_ALGORITHM: &'static str = "blake2b256 digest";
+}
+
+impl<T> Bech32Registered for DigestOf<hash::Blake2b256, T> {
+    const BECH32_KIND: Bech32Kind = Bech32Kind::Hash;
+    const BECH32_ALGORITHM: &'static str = "blake2b256 digest";
+}
+
+/// All the HRPs of the crate, each one identifies a single type
+///
+/// The entries come from the [`Bech32Registered`] implementations, the list
+/// is only needed to find the type of a string at run time.
+pub const REGISTRY: &[Bech32Entry] = &[
+    <PublicKey<Ed25519> as Bech32Registered>::BECH32_ENTRY,
+    <SecretKey<Ed25519> as Bech32Registered>::BECH32_ENTRY,
+    <Signature<(), Ed25519> as Bech32Registered>::BECH32_ENTRY,
+    <SecretKey<Ed25519Extended> as Bech32Registered>::BECH32_ENTRY,
+    <PublicKey<Ed25519Bip32> as Bech32Registered>::BECH32_ENTRY,
+    <SecretKey<Ed25519Bip32> as Bech32Registered>::BECH32_ENTRY,
+    <Signature<(), Ed25519Bip32> as Bech32Registered>::BECH32_ENTRY,
+    <PublicKey<SumEd25519_12> as Bech32Registered>::BECH32_ENTRY,
+    <SecretKey<SumEd25519_12> as Bech32Registered>::BECH32_ENTRY,
+    <Signature<(), SumEd25519_12> as Bech32Registered>::BECH32_ENTRY,
+    <PublicKey<Curve25519_2HashDH> as Bech32Registered>::BECH32_ENTRY,
+    <SecretKey<Curve25519_2HashDH> as Bech32Registered>::BECH32_ENTRY,
+    <hash::Blake2b256 as Bech32Registered>::BECH32_ENTRY,
+    <Digest<hash::Blake2b256> as Bech32Registered>::BECH32_ENTRY,
+];
+
+/// The registered type of the HRP, if any
+pub fn lookup_hrp(hrp: &str) -> Option<&'static Bech32Entry> {
+    REGISTRY.iter().find(|entry| entry.hrp == hrp)
+}
+
+/// Decode a bech32 string of any registered HRP, to be dispatched by the
+/// caller on the returned entry
+pub fn try_from_registered_bech32(bech32_str: &str) -> Result<(&'static Bech32Entry, Vec<u8>)> {
+    let (hrp, data) = bech32::decode(bech32_str)?;
+    let entry = lookup_hrp(&hrp).ok_or(Error::HrpUnknown(hrp))?;
+    let bytes = Vec::<u8>::from_base32(&data)?;
+    Ok((entry, bytes))
+}
+
 pub fn to_bech32_from_bytes<B: Bech32>(bytes: &[u8]) -> String {
     bech32::encode(B::BECH32_HRP, bytes.to_base32())
         .unwrap_or_else(|e| panic!("Failed to build bech32: {}", e))
@@ -31,6 +162,23 @@ pub fn try_from_bech32_to_bytes<B: Bech32>(bech32_str: &str) -> Result<Vec<u8>>
     Vec::<u8>::from_base32(&data).map_err(Into::into)
 }
 
+impl fmt::Display for Bech32Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> StdResult<(), fmt::Error> {
+        f.write_str(match self {
+            Bech32Kind::PublicKey => "public key",
+            Bech32Kind::SecretKey => "secret key",
+            Bech32Kind::Signature => "signature",
+            Bech32Kind::Hash => "hash",
+        })
+    }
+}
+
+impl fmt::Display for Bech32Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> StdResult<(), fmt::Error> {
+        write!(f, "{} {}", self.algorithm, self.kind)
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Bech32Malformed(Bech32Error),
@@ -38,6 +186,8 @@ pub enum Error {
         expected: &'static str,
         actual: String,
     },
+    /// the HRP is not registered, or not one of the HRPs accepted by the type
+    HrpUnknown(String),
     DataInvalid(Box<dyn StdError + Send + Sync + 'static>),
 }
 
@@ -57,11 +207,24 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> StdResult<(), fmt::Error> {
         match self {
             Error::Bech32Malformed(_) => write!(f, "Failed to parse bech32, invalid data format"),
-            Error::HrpInvalid { expected, actual } => write!(
-                f,
-                "Parsed bech32 has invalid HRP prefix '{}', expected '{}'",
-                actual, expected
-            ),
+            Error::HrpInvalid { expected, actual } => {
+                write!(
+                    f,
+                    "Parsed bech32 has invalid HRP prefix '{}', expected '{}'",
+                    actual, expected
+                )?;
+                match lookup_hrp(actual) {
+                    Some(entry) => write!(f, " (HRP of {})", entry),
+                    None => Ok(()),
+                }
+            }
+            Error::HrpUnknown(hrp) => {
+                write!(f, "Parsed bech32 has unexpected HRP prefix '{}'", hrp)?;
+                match lookup_hrp(hrp) {
+                    Some(entry) => write!(f, " (HRP of {})", entry),
+                    None => Ok(()),
+                }
+            }
             Error::DataInvalid(_) => write!(f, "Failed to parse data decoded from bech32"),
         }
     }
@@ -76,3 +239,69 @@ impl StdError for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyPair;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn registered_hrps_are_unique() {
+        for (i, entry) in REGISTRY.iter().enumerate() {
+            assert!(
+                REGISTRY[i + 1..].iter().all(|other| other.hrp != entry.hrp),
+                "duplicated HRP {}",
+                entry.hrp
+            );
+        }
+    }
+
+    #[test]
+    fn registry_follows_the_types() {
+        fn lookup<T: Bech32Registered>() -> Option<&'static Bech32Entry> {
+            lookup_hrp(T::BECH32_HRP)
+        }
+
+        assert_eq!(
+            lookup::<PublicKey<Ed25519Bip32>>(),
+            Some(&PublicKey::<Ed25519Bip32>::BECH32_ENTRY)
+        );
+        assert_eq!(
+            lookup::<Signature<(), SumEd25519_12>>().map(|entry| entry.kind),
+            Some(Bech32Kind::Signature)
+        );
+        assert_eq!(
+            SecretKey::<Curve25519_2HashDH>::BECH32_ENTRY.to_string(),
+            "vrf secret key"
+        );
+    }
+
+    #[test]
+    fn key_kinds_are_not_mixed_up() {
+        let mut rng = ChaChaRng::from_seed([7; 32]);
+        let pair = KeyPair::<Ed25519>::generate(&mut rng);
+        let secret = pair.private_key().to_bech32_str();
+        let public = pair.public_key().to_bech32_str();
+
+        match PublicKey::<Ed25519>::try_from_bech32_str(&secret) {
+            Err(error @ Error::HrpInvalid { .. }) => {
+                assert!(error.to_string().ends_with("(HRP of ed25519 secret key)"))
+            }
+            _ => panic!("a secret key was parsed as a public key"),
+        }
+        assert!(SecretKey::<Ed25519>::try_from_bech32_str(&public).is_err());
+        assert!(PublicKey::<SumEd25519_12>::try_from_bech32_str(&public).is_err());
+
+        let (entry, bytes) = try_from_registered_bech32(&public).unwrap();
+        assert_eq!(entry.kind, Bech32Kind::PublicKey);
+        assert_eq!(bytes, pair.public_key().as_ref());
+
+        let unknown = bech32::encode("foo", vec![0u8; 4].to_base32()).unwrap();
+        assert!(matches!(
+            try_from_registered_bech32(&unknown),
+            Err(Error::HrpUnknown(_))
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Secret keys from BIP39 mnemonic phrases.
+//!
+//! The derivation is fixed so that every wallet gets the same keys from the
+//! same phrase:
+//!
+//! 1. the mnemonic phrase and the passphrase are stretched into a 64 bytes
+//!    [`Seed`] as specified by BIP39: PBKDF2-HMAC-SHA512 with 2048 iterations
+//!    and `"mnemonic" || passphrase` as salt;
+//! 2. the ed25519 secret key is the SLIP-0010 ed25519 master key of the seed,
+//!    i.e. the first 32 bytes of `HMAC-SHA512(key = "ed25519 seed", seed)`;
+//! 3. the extended ed25519 secret key is the standard expansion (SHA512 and
+//!    clamping) of that same secret key, so both share the same public key.
+//!
+//! The phrase is used as given: it is not checked against a wordlist nor its
+//! checksum verified, and it has to be NFKD normalized already (which is
+//! always the case for the english wordlist). All the intermediate secret
+//! material is zeroed once it is not needed anymore.
+
+use crate::algorithms::{Ed25519, Ed25519Extended};
+use crate::key::{AsymmetricKey, SecretKey};
+use cryptoxide::{digest::Digest, ed25519, hmac::Hmac, mac::Mac, pbkdf2::pbkdf2, sha2::Sha512};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// size in bytes of a BIP39 seed
+pub const SEED_SIZE: usize = 64;
+
+const PBKDF2_ITERATIONS: u32 = 2048;
+const SALT_PREFIX: &[u8] = b"mnemonic";
+const SLIP10_ED25519_KEY: &[u8] = b"ed25519 seed";
+
+/// BIP39 seed, zeroed on drop
+pub struct Seed([u8; SEED_SIZE]);
+
+impl Seed {
+    /// Stretch a mnemonic phrase and its (possibly empty) passphrase into a seed
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str) -> Self {
+        let mut salt = Vec::with_capacity(SALT_PREFIX.len() + passphrase.len());
+        salt.extend_from_slice(SALT_PREFIX);
+        salt.extend_from_slice(passphrase.as_bytes());
+
+        let mut seed = [0u8; SEED_SIZE];
+        let mut mac = Hmac::new(Sha512::new(), mnemonic.as_bytes());
+        pbkdf2(&mut mac, &salt, PBKDF2_ITERATIONS, &mut seed);
+        salt.zeroize();
+        Seed(seed)
+    }
+
+    pub fn from_bytes(bytes: [u8; SEED_SIZE]) -> Self {
+        Seed(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Seed {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for Seed {
+    fn drop(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Seed(<secret>)")
+    }
+}
+
+/// Algorithms whose secret keys can be derived from a BIP39 seed
+pub trait SeedDerivable: AsymmetricKey {
+    fn secret_from_seed(seed: &Seed) -> Self::Secret;
+}
+
+// SLIP-0010 ed25519 master secret key
+fn master_secret(seed: &[u8]) -> [u8; ed25519::SEED_LENGTH] {
+    let mut i = [0u8; 64];
+    let mut mac = Hmac::new(Sha512::new(), SLIP10_ED25519_KEY);
+    mac.input(seed);
+    mac.raw_result(&mut i);
+
+    let mut secret = [0u8; ed25519::SEED_LENGTH];
+    secret.copy_from_slice(&i[..ed25519::SEED_LENGTH]);
+    i.zeroize();
+    secret
+}
+
+impl SeedDerivable for Ed25519 {
+    fn secret_from_seed(seed: &Seed) -> Self::Secret {
+        let mut secret = master_secret(seed.as_ref());
+        let key = Ed25519::secret_from_binary(&secret).expect("valid ed25519 secret key size");
+        secret.zeroize();
+        key
+    }
+}
+
+impl SeedDerivable for Ed25519Extended {
+    fn secret_from_seed(seed: &Seed) -> Self::Secret {
+        let mut secret = master_secret(seed.as_ref());
+        let mut extended = [0u8; 64];
+        let mut hash = Sha512::new();
+        hash.input(&secret);
+        hash.result(&mut extended);
+        extended[0] &= 0b1111_1000;
+        extended[31] &= 0b0011_1111;
+        extended[31] |= 0b0100_0000;
+
+        let key = Ed25519Extended::secret_from_binary(&extended)
+            .expect("valid ed25519 extended secret key size");
+        secret.zeroize();
+        extended.zeroize();
+        key
+    }
+}
+
+impl<A: SeedDerivable> SecretKey<A> {
+    pub fn from_bip39_seed(seed: &Seed) -> Self {
+        SecretKey(A::secret_from_seed(seed))
+    }
+
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str) -> Self {
+        Self::from_bip39_seed(&Seed::from_mnemonic(mnemonic, passphrase))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    // test vector from the reference BIP39 implementation
+    #[test]
+    fn seed_from_mnemonic() {
+        let seed = Seed::from_mnemonic(MNEMONIC, "TREZOR");
+        assert_eq!(
+            hex::encode(seed.as_ref()),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    // test vector 1 of SLIP-0010 for ed25519
+    #[test]
+    fn slip10_master_key() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let secret = master_secret(&seed);
+        assert_eq!(
+            hex::encode(secret),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        let public = SecretKey::<Ed25519>::from_binary(&secret)
+            .unwrap()
+            .to_public();
+        assert_eq!(
+            hex::encode(public.as_ref()),
+            "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed"
+        );
+    }
+
+    #[test]
+    fn extended_key_shares_public_key() {
+        let seed = Seed::from_mnemonic(MNEMONIC, "");
+        let key = SecretKey::<Ed25519>::from_bip39_seed(&seed);
+        let extended = SecretKey::<Ed25519Extended>::from_bip39_seed(&seed);
+        assert_eq!(key.to_public(), extended.to_public());
+    }
+
+    #[quickcheck]
+    fn passphrase_changes_key(passphrase: String) -> bool {
+        let key = SecretKey::<Ed25519>::from_mnemonic(MNEMONIC, &passphrase).to_public();
+        let other =
+            SecretKey::<Ed25519>::from_mnemonic(MNEMONIC, &format!("{}~", passphrase)).to_public();
+        key != other
+    }
+}
@@ -0,0 +1,511 @@
+//! C API for the key and signature operations, enabled with the `capi`
+//! feature, so that mobile wallets can link against this crate instead of
+//! reimplementing the schemes.
+//!
+//! Secret keys never cross the boundary as bytes once imported: they are
+//! held in opaque handles allocated by this library, which must be released
+//! with the matching `*_delete` function.
+//! Public keys and signatures are plain byte buffers whose sizes are given
+//! by the `CHAIN_CRYPTO_*_SIZE` constants. Every function returns a
+//! [`ChainCryptoResult`] and only writes its output on success. Messages
+//! are given as a pointer and a length, the pointer may be null if the
+//! length is zero.
+//!
+//! The symbols are exported when building this crate as a static or dynamic
+//! library, e.g. `cargo rustc -p chain-crypto --features capi --crate-type staticlib`,
+//! and the C header can be generated with cbindgen.
+
+use crate::algorithms::{Ed25519, Ed25519Bip32};
+use crate::bip39::Seed;
+use crate::key::{AsymmetricKey, AsymmetricPublicKey, PublicKey, SecretKey};
+use crate::sign::{Signature, SigningAlgorithm, Verification, VerificationAlgorithm};
+use ed25519_bip32::{DerivationScheme, XPUB_SIZE};
+use rand_core::OsRng;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+pub const CHAIN_CRYPTO_ED25519_SECRET_KEY_SIZE: usize = 32;
+pub const CHAIN_CRYPTO_ED25519_PUBLIC_KEY_SIZE: usize = 32;
+pub const CHAIN_CRYPTO_ED25519_SIGNATURE_SIZE: usize = 64;
+pub const CHAIN_CRYPTO_ED25519_BIP32_SECRET_KEY_SIZE: usize = 96;
+pub const CHAIN_CRYPTO_ED25519_BIP32_PUBLIC_KEY_SIZE: usize = XPUB_SIZE;
+pub const CHAIN_CRYPTO_ED25519_BIP32_SIGNATURE_SIZE: usize = 64;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainCryptoResult {
+    Ok = 0,
+    /// a pointer argument is null
+    NullPointer = 1,
+    /// the bytes are not a valid key
+    InvalidKey = 2,
+    /// the signature does not verify, or is malformed
+    InvalidSignature = 3,
+    /// a string argument is not valid UTF-8
+    InvalidString = 4,
+    /// hardened derivation was requested from a public key
+    DerivationFailed = 5,
+}
+
+/// Opaque handle to an Ed25519 secret key
+pub struct ChainCryptoEd25519SecretKey(SecretKey<Ed25519>);
+
+/// Opaque handle to an Ed25519 BIP32 extended secret key
+pub struct ChainCryptoEd25519Bip32SecretKey(SecretKey<Ed25519Bip32>);
+
+macro_rules! check_null {
+    ($($ptr:expr),+) => {
+        if $($ptr.is_null())||+ {
+            return ChainCryptoResult::NullPointer;
+        }
+    };
+}
+
+unsafe fn export_handle<T>(handle: T, out: *mut *mut T) -> ChainCryptoResult {
+    *out = Box::into_raw(Box::new(handle));
+    ChainCryptoResult::Ok
+}
+
+unsafe fn copy_out(bytes: &[u8], out: *mut u8) -> ChainCryptoResult {
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    ChainCryptoResult::Ok
+}
+
+// the message may be null when it is empty
+macro_rules! message {
+    ($msg:expr, $msg_len:expr) => {
+        if $msg_len == 0 {
+            &[]
+        } else if $msg.is_null() {
+            return ChainCryptoResult::NullPointer;
+        } else {
+            slice::from_raw_parts($msg, $msg_len)
+        }
+    };
+}
+
+unsafe fn verify<A: VerificationAlgorithm>(
+    public_key: *const u8,
+    msg: &[u8],
+    signature: *const u8,
+) -> ChainCryptoResult {
+    let public_key = slice::from_raw_parts(public_key, A::PUBLIC_KEY_SIZE);
+    let signature = slice::from_raw_parts(signature, A::SIGNATURE_SIZE);
+    let public_key = match PublicKey::<A>::from_binary(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return ChainCryptoResult::InvalidKey,
+    };
+    let signature = match Signature::<[u8], A>::from_binary(signature) {
+        Ok(signature) => signature,
+        Err(_) => return ChainCryptoResult::InvalidSignature,
+    };
+    match signature.verify_slice(&public_key, msg) {
+        Verification::Success => ChainCryptoResult::Ok,
+        Verification::Failed => ChainCryptoResult::InvalidSignature,
+    }
+}
+
+unsafe fn sign<A>(key: &SecretKey<A>, msg: &[u8], signature: *mut u8) -> ChainCryptoResult
+where
+    A: SigningAlgorithm,
+    <A as AsymmetricKey>::PubAlg: VerificationAlgorithm,
+{
+    copy_out(key.sign_slice::<[u8]>(msg).as_ref(), signature)
+}
+
+/// Generate a new Ed25519 secret key with the operating system's random
+/// number generator.
+///
+/// # Safety
+///
+/// `out` must be valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_generate(
+    out: *mut *mut ChainCryptoEd25519SecretKey,
+) -> ChainCryptoResult {
+    check_null!(out);
+    export_handle(ChainCryptoEd25519SecretKey(SecretKey::generate(OsRng)), out)
+}
+
+/// Import an Ed25519 secret key from its
+/// `CHAIN_CRYPTO_ED25519_SECRET_KEY_SIZE` bytes.
+///
+/// # Safety
+///
+/// `bytes` must be valid for reading the size of a secret key and `out`
+/// valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_from_bytes(
+    bytes: *const u8,
+    out: *mut *mut ChainCryptoEd25519SecretKey,
+) -> ChainCryptoResult {
+    check_null!(bytes, out);
+    let bytes = slice::from_raw_parts(bytes, CHAIN_CRYPTO_ED25519_SECRET_KEY_SIZE);
+    match SecretKey::from_binary(bytes) {
+        Ok(key) => export_handle(ChainCryptoEd25519SecretKey(key), out),
+        Err(_) => ChainCryptoResult::InvalidKey,
+    }
+}
+
+/// Derive an Ed25519 secret key from a BIP39 mnemonic phrase and passphrase,
+/// as described in the [`bip39`](crate::bip39) module.
+///
+/// # Safety
+///
+/// `mnemonic` and `passphrase` must be NUL terminated strings and `out`
+/// valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_from_mnemonic(
+    mnemonic: *const c_char,
+    passphrase: *const c_char,
+    out: *mut *mut ChainCryptoEd25519SecretKey,
+) -> ChainCryptoResult {
+    check_null!(mnemonic, passphrase, out);
+    let (mnemonic, passphrase) = match (
+        CStr::from_ptr(mnemonic).to_str(),
+        CStr::from_ptr(passphrase).to_str(),
+    ) {
+        (Ok(mnemonic), Ok(passphrase)) => (mnemonic, passphrase),
+        _ => return ChainCryptoResult::InvalidString,
+    };
+    let seed = Seed::from_mnemonic(mnemonic, passphrase);
+    export_handle(
+        ChainCryptoEd25519SecretKey(SecretKey::from_bip39_seed(&seed)),
+        out,
+    )
+}
+
+/// Write the `CHAIN_CRYPTO_ED25519_PUBLIC_KEY_SIZE` bytes of the public key.
+///
+/// # Safety
+///
+/// `key` must be a live handle and `out` valid for writing a public key.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_public_key(
+    key: *const ChainCryptoEd25519SecretKey,
+    out: *mut u8,
+) -> ChainCryptoResult {
+    check_null!(key, out);
+    copy_out((*key).0.to_public().as_ref(), out)
+}
+
+/// Sign a message, writing the `CHAIN_CRYPTO_ED25519_SIGNATURE_SIZE` bytes
+/// of the signature.
+///
+/// # Safety
+///
+/// `key` must be a live handle, `msg` valid for reading `msg_len` bytes and
+/// `signature` valid for writing a signature.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_sign(
+    key: *const ChainCryptoEd25519SecretKey,
+    msg: *const u8,
+    msg_len: usize,
+    signature: *mut u8,
+) -> ChainCryptoResult {
+    check_null!(key, signature);
+    sign(&(*key).0, message!(msg, msg_len), signature)
+}
+
+/// Verify the signature of a message, returns `Ok` only if it is valid.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid for reading a public key and
+/// a signature, and `msg` for reading `msg_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_verify(
+    public_key: *const u8,
+    msg: *const u8,
+    msg_len: usize,
+    signature: *const u8,
+) -> ChainCryptoResult {
+    check_null!(public_key, signature);
+    verify::<Ed25519>(public_key, message!(msg, msg_len), signature)
+}
+
+/// Release a secret key handle, null is ignored.
+///
+/// # Safety
+///
+/// `key` must be null or a handle not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_delete(key: *mut ChainCryptoEd25519SecretKey) {
+    if !key.is_null() {
+        drop(Box::from_raw(key))
+    }
+}
+
+/// Generate a new Ed25519 BIP32 root key with the operating system's
+/// random number generator.
+///
+/// # Safety
+///
+/// `out` must be valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_generate(
+    out: *mut *mut ChainCryptoEd25519Bip32SecretKey,
+) -> ChainCryptoResult {
+    check_null!(out);
+    export_handle(
+        ChainCryptoEd25519Bip32SecretKey(SecretKey::generate(OsRng)),
+        out,
+    )
+}
+
+/// Import an Ed25519 BIP32 extended secret key from its
+/// `CHAIN_CRYPTO_ED25519_BIP32_SECRET_KEY_SIZE` bytes.
+///
+/// # Safety
+///
+/// `bytes` must be valid for reading the size of a secret key and `out`
+/// valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_from_bytes(
+    bytes: *const u8,
+    out: *mut *mut ChainCryptoEd25519Bip32SecretKey,
+) -> ChainCryptoResult {
+    check_null!(bytes, out);
+    let bytes = slice::from_raw_parts(bytes, CHAIN_CRYPTO_ED25519_BIP32_SECRET_KEY_SIZE);
+    match SecretKey::from_binary(bytes) {
+        Ok(key) => export_handle(ChainCryptoEd25519Bip32SecretKey(key), out),
+        Err(_) => ChainCryptoResult::InvalidKey,
+    }
+}
+
+/// Derive the child key at `index` (hardened from `0x80000000`) with the
+/// V2 derivation scheme, into a new handle.
+///
+/// # Safety
+///
+/// `key` must be a live handle and `out` valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_derive(
+    key: *const ChainCryptoEd25519Bip32SecretKey,
+    index: u32,
+    out: *mut *mut ChainCryptoEd25519Bip32SecretKey,
+) -> ChainCryptoResult {
+    check_null!(key, out);
+    let child = (*key).0 .0.derive(DerivationScheme::V2, index);
+    export_handle(ChainCryptoEd25519Bip32SecretKey(SecretKey(child)), out)
+}
+
+/// Write the `CHAIN_CRYPTO_ED25519_BIP32_PUBLIC_KEY_SIZE` bytes of the
+/// extended public key (public key followed by the chain code).
+///
+/// # Safety
+///
+/// `key` must be a live handle and `out` valid for writing a public key.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_public_key(
+    key: *const ChainCryptoEd25519Bip32SecretKey,
+    out: *mut u8,
+) -> ChainCryptoResult {
+    check_null!(key, out);
+    copy_out((*key).0.to_public().as_ref(), out)
+}
+
+/// Derive the extended public key of the child at `index` from an extended
+/// public key, so addresses can be generated without the secret key. Only
+/// soft derivation (`index < 0x80000000`) is possible.
+///
+/// # Safety
+///
+/// `public_key` must be valid for reading and `out` for writing an extended
+/// public key.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_public_derive(
+    public_key: *const u8,
+    index: u32,
+    out: *mut u8,
+) -> ChainCryptoResult {
+    check_null!(public_key, out);
+    let public_key = slice::from_raw_parts(public_key, Ed25519Bip32::PUBLIC_KEY_SIZE);
+    let public_key = match Ed25519Bip32::public_from_binary(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return ChainCryptoResult::InvalidKey,
+    };
+    match public_key.derive(DerivationScheme::V2, index) {
+        Ok(child) => copy_out(child.as_ref(), out),
+        Err(_) => ChainCryptoResult::DerivationFailed,
+    }
+}
+
+/// Sign a message, writing the `CHAIN_CRYPTO_ED25519_BIP32_SIGNATURE_SIZE`
+/// bytes of the signature.
+///
+/// # Safety
+///
+/// `key` must be a live handle, `msg` valid for reading `msg_len` bytes and
+/// `signature` valid for writing a signature.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_sign(
+    key: *const ChainCryptoEd25519Bip32SecretKey,
+    msg: *const u8,
+    msg_len: usize,
+    signature: *mut u8,
+) -> ChainCryptoResult {
+    check_null!(key, signature);
+    sign(&(*key).0, message!(msg, msg_len), signature)
+}
+
+/// Verify the signature of a message with an extended public key, returns
+/// `Ok` only if it is valid.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid for reading an extended public
+/// key and a signature, and `msg` for reading `msg_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_verify(
+    public_key: *const u8,
+    msg: *const u8,
+    msg_len: usize,
+    signature: *const u8,
+) -> ChainCryptoResult {
+    check_null!(public_key, signature);
+    verify::<Ed25519Bip32>(public_key, message!(msg, msg_len), signature)
+}
+
+/// Release an extended secret key handle, null is ignored.
+///
+/// # Safety
+///
+/// `key` must be null or a handle not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn chain_crypto_ed25519_bip32_delete(
+    key: *mut ChainCryptoEd25519Bip32SecretKey,
+) {
+    if !key.is_null() {
+        drop(Box::from_raw(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MSG: &[u8] = b"message to sign";
+
+    #[test]
+    fn ed25519_sign_and_verify() {
+        unsafe {
+            let mut key = ptr::null_mut();
+            assert_eq!(
+                chain_crypto_ed25519_generate(&mut key),
+                ChainCryptoResult::Ok
+            );
+
+            let mut public_key = [0u8; CHAIN_CRYPTO_ED25519_PUBLIC_KEY_SIZE];
+            let mut signature = [0u8; CHAIN_CRYPTO_ED25519_SIGNATURE_SIZE];
+            chain_crypto_ed25519_public_key(key, public_key.as_mut_ptr());
+            chain_crypto_ed25519_sign(key, MSG.as_ptr(), MSG.len(), signature.as_mut_ptr());
+            chain_crypto_ed25519_delete(key);
+
+            assert_eq!(
+                chain_crypto_ed25519_verify(
+                    public_key.as_ptr(),
+                    MSG.as_ptr(),
+                    MSG.len(),
+                    signature.as_ptr()
+                ),
+                ChainCryptoResult::Ok
+            );
+            signature[0] ^= 1;
+            assert_eq!(
+                chain_crypto_ed25519_verify(
+                    public_key.as_ptr(),
+                    MSG.as_ptr(),
+                    MSG.len(),
+                    signature.as_ptr()
+                ),
+                ChainCryptoResult::InvalidSignature
+            );
+        }
+    }
+
+    #[test]
+    fn ed25519_from_mnemonic_matches_rust_api() {
+        let expected = SecretKey::<Ed25519>::from_mnemonic("abandon about", "pass").to_public();
+        unsafe {
+            let mut key = ptr::null_mut();
+            assert_eq!(
+                chain_crypto_ed25519_from_mnemonic(
+                    b"abandon about\0".as_ptr() as *const c_char,
+                    b"pass\0".as_ptr() as *const c_char,
+                    &mut key
+                ),
+                ChainCryptoResult::Ok
+            );
+            let mut public_key = [0u8; CHAIN_CRYPTO_ED25519_PUBLIC_KEY_SIZE];
+            chain_crypto_ed25519_public_key(key, public_key.as_mut_ptr());
+            chain_crypto_ed25519_delete(key);
+            assert_eq!(&public_key[..], expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_rejected() {
+        unsafe {
+            assert_eq!(
+                chain_crypto_ed25519_generate(ptr::null_mut()),
+                ChainCryptoResult::NullPointer
+            );
+            assert_eq!(
+                chain_crypto_ed25519_verify(ptr::null(), MSG.as_ptr(), MSG.len(), ptr::null()),
+                ChainCryptoResult::NullPointer
+            );
+            chain_crypto_ed25519_delete(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn ed25519_bip32_derive_sign_and_verify() {
+        unsafe {
+            let mut root = ptr::null_mut();
+            let mut child = ptr::null_mut();
+            chain_crypto_ed25519_bip32_generate(&mut root);
+            assert_eq!(
+                chain_crypto_ed25519_bip32_derive(root, 0x8000_0000, &mut child),
+                ChainCryptoResult::Ok
+            );
+
+            let mut public_key = [0u8; CHAIN_CRYPTO_ED25519_BIP32_PUBLIC_KEY_SIZE];
+            let mut signature = [0u8; CHAIN_CRYPTO_ED25519_BIP32_SIGNATURE_SIZE];
+            chain_crypto_ed25519_bip32_public_key(child, public_key.as_mut_ptr());
+            chain_crypto_ed25519_bip32_sign(child, MSG.as_ptr(), MSG.len(), signature.as_mut_ptr());
+            chain_crypto_ed25519_bip32_delete(child);
+            chain_crypto_ed25519_bip32_delete(root);
+
+            assert_eq!(
+                chain_crypto_ed25519_bip32_verify(
+                    public_key.as_ptr(),
+                    MSG.as_ptr(),
+                    MSG.len(),
+                    signature.as_ptr()
+                ),
+                ChainCryptoResult::Ok
+            );
+
+            let mut derived = [0u8; CHAIN_CRYPTO_ED25519_BIP32_PUBLIC_KEY_SIZE];
+            assert_eq!(
+                chain_crypto_ed25519_bip32_public_derive(
+                    public_key.as_ptr(),
+                    1,
+                    derived.as_mut_ptr()
+                ),
+                ChainCryptoResult::Ok
+            );
+            assert_eq!(
+                chain_crypto_ed25519_bip32_public_derive(
+                    public_key.as_ptr(),
+                    0x8000_0001,
+                    derived.as_mut_ptr()
+                ),
+                ChainCryptoResult::DerivationFailed
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Short fingerprints of public keys, to refer to a key in logs and in the
+//! operator tooling without writing the whole key.
+//!
+//! A [`Fingerprint`] is the [`SignatureAlgorithmId`] of the key and the first
+//! [`FINGERPRINT_HASH_SIZE`] bytes of the Blake2b hash of the tagged binary
+//! form of the key, so that the keys of different algorithms with the same
+//! bytes have different fingerprints. It is displayed as the name of the
+//! algorithm and the hash in hexadecimal, separated by a colon:
+//!
+//! ```text
+//! ed25519:5e2a0dd3e6b5c84bd6a1cc0b9f2e16f4
+//! ```
+
+use crate::{AnyPublicKey, SignatureAlgorithmId};
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::digest::Digest as _;
+use hex::FromHexError;
+use std::{fmt, str::FromStr};
+
+/// Size in bytes of the truncated hash of a fingerprint
+pub const FINGERPRINT_HASH_SIZE: usize = 16;
+
+/// Fingerprint of a public key of any of the supported algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint {
+    algorithm: SignatureAlgorithmId,
+    hash: [u8; FINGERPRINT_HASH_SIZE],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FingerprintFromStrError {
+    MissingAlgorithm,
+    UnknownAlgorithm(String),
+    InvalidHex(FromHexError),
+    InvalidHashSize(usize),
+}
+
+impl Fingerprint {
+    pub fn new(publickey: &AnyPublicKey) -> Self {
+        let mut b2b = Blake2b::new(FINGERPRINT_HASH_SIZE);
+        let mut hash = [0; FINGERPRINT_HASH_SIZE];
+        b2b.input(&publickey.to_tagged_binary());
+        b2b.result(&mut hash);
+        Fingerprint {
+            algorithm: publickey.algorithm(),
+            hash,
+        }
+    }
+
+    pub fn algorithm(&self) -> SignatureAlgorithmId {
+        self.algorithm
+    }
+
+    pub fn hash_bytes(&self) -> &[u8; FINGERPRINT_HASH_SIZE] {
+        &self.hash
+    }
+
+    /// Whether this is the fingerprint of the given key
+    pub fn matches(&self, publickey: &AnyPublicKey) -> bool {
+        *self == Self::new(publickey)
+    }
+}
+
+impl AnyPublicKey {
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::new(self)
+    }
+}
+
+impl From<&AnyPublicKey> for Fingerprint {
+    fn from(publickey: &AnyPublicKey) -> Self {
+        Self::new(publickey)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, hex::encode(&self.hash))
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = FingerprintFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, hash_hex) = match s.find(':') {
+            Some(separator) => (&s[..separator], &s[separator + 1..]),
+            None => return Err(FingerprintFromStrError::MissingAlgorithm),
+        };
+        let algorithm = SignatureAlgorithmId::ALL
+            .iter()
+            .copied()
+            .find(|known| known.to_string() == algorithm)
+            .ok_or_else(|| FingerprintFromStrError::UnknownAlgorithm(algorithm.to_owned()))?;
+        let bytes = hex::decode(hash_hex).map_err(FingerprintFromStrError::InvalidHex)?;
+        if bytes.len() != FINGERPRINT_HASH_SIZE {
+            return Err(FingerprintFromStrError::InvalidHashSize(bytes.len()));
+        }
+        let mut hash = [0; FINGERPRINT_HASH_SIZE];
+        hash.copy_from_slice(&bytes);
+        Ok(Fingerprint { algorithm, hash })
+    }
+}
+
+impl fmt::Display for FingerprintFromStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FingerprintFromStrError::MissingAlgorithm => {
+                "the algorithm of the fingerprint is missing".fmt(f)
+            }
+            FingerprintFromStrError::UnknownAlgorithm(name) => {
+                write!(f, "unknown signature algorithm '{}'", name)
+            }
+            FingerprintFromStrError::InvalidHex(_) => "invalid hex encoding".fmt(f),
+            FingerprintFromStrError::InvalidHashSize(size) => write!(
+                f,
+                "invalid fingerprint size, expected {} but received {} bytes",
+                FINGERPRINT_HASH_SIZE, size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FingerprintFromStrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FingerprintFromStrError::InvalidHex(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestCryptoGen;
+    use crate::{Ed25519, Ed25519Bip32};
+
+    #[quickcheck]
+    fn fingerprint_display_parse(gen: TestCryptoGen) -> bool {
+        let ed25519_pk = AnyPublicKey::from(gen.keypair::<Ed25519>(0).public_key().clone());
+        let bip32_pk = AnyPublicKey::from(gen.keypair::<Ed25519Bip32>(1).public_key().clone());
+
+        [ed25519_pk, bip32_pk].iter().all(|publickey| {
+            let fingerprint = publickey.fingerprint();
+            let decoded: Fingerprint = fingerprint.to_string().parse().unwrap();
+            decoded == fingerprint
+                && decoded.algorithm() == publickey.algorithm()
+                && decoded.matches(publickey)
+        })
+    }
+
+    #[quickcheck]
+    fn fingerprints_of_different_keys_differ(gen: TestCryptoGen) -> bool {
+        let pk1 = AnyPublicKey::from(gen.keypair::<Ed25519>(0).public_key().clone());
+        let pk2 = AnyPublicKey::from(gen.keypair::<Ed25519>(1).public_key().clone());
+
+        !pk1.fingerprint().matches(&pk2)
+    }
+
+    #[test]
+    fn invalid_fingerprints_are_rejected() {
+        let hash = "00".repeat(FINGERPRINT_HASH_SIZE);
+        assert!("ed25519-bip32:".parse::<Fingerprint>().is_err());
+        assert_eq!(
+            hash.parse::<Fingerprint>(),
+            Err(FingerprintFromStrError::MissingAlgorithm)
+        );
+        assert_eq!(
+            format!("rsa:{}", hash).parse::<Fingerprint>(),
+            Err(FingerprintFromStrError::UnknownAlgorithm("rsa".to_owned()))
+        );
+        assert!(matches!(
+            "ed25519:zz".parse::<Fingerprint>(),
+            Err(FingerprintFromStrError::InvalidHex(_))
+        ));
+        assert_eq!(
+            "ed25519:0011".parse::<Fingerprint>(),
+            Err(FingerprintFromStrError::InvalidHashSize(2))
+        );
+        assert!(format!("ed25519:{}", hash).parse::<Fingerprint>().is_ok());
+    }
+}
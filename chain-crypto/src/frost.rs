@@ -0,0 +1,887 @@
+//! FROST threshold signatures, producing plain Ed25519 signatures.
+//!
+//! A set of `n` participants runs a distributed key generation, after which
+//! each of them holds a share of a group secret key which nobody knows. Any
+//! `threshold` of them can then sign a message in two rounds:
+//!
+//! 1. every signer draws a pair of single use nonces and broadcasts the
+//!    [`NonceCommitment`];
+//! 2. once the commitments of all the signers are known, every signer
+//!    computes its [`SignatureShare`] of the message.
+//!
+//! Anyone knowing the [`GroupKey`] then checks the shares, identifying the
+//! misbehaving signers, and aggregates them into a signature. The group
+//! operations take place in the prime order subgroup of Edwards25519 (the
+//! group the Ristretto encoding is built on) and the challenge is the one
+//! of Ed25519, so the aggregated signature is a regular `Signature<_,
+//! Ed25519>` of the group public key: a BFT leader key can be held by
+//! several operators without the verifiers knowing about it.
+//!
+//! The protocol messages travel over authenticated channels, and the key
+//! shares sent during the key generation over confidential ones. Each of
+//! them has a byte encoding, `to_bytes` and `from_bytes`, with the
+//! participant identifiers in little endian, the points compressed and the
+//! scalars in their canonical form. Nonces must never be reused:
+//! [`SigningNonces`] are consumed by the signature.
+
+use crate::{key::PublicKey, sign::Signature, Ed25519};
+use curve25519_dalek_ng::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Identifier of a participant, from 1 to the number of participants
+pub type ParticipantId = u16;
+
+const DOMAIN: &[u8] = b"chain-crypto FROST-ED25519-SHA512 ";
+
+const ID_SIZE: usize = 2;
+const POINT_SIZE: usize = 32;
+const SCALAR_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrostError {
+    /// the threshold is zero or above the number of participants, or an
+    /// identifier is out of range
+    InvalidParameters,
+    /// the commitments of the key generation are not one per participant
+    InvalidKeyGenCommitments,
+    /// the participant did not prove the knowledge of its secret
+    InvalidProofOfKnowledge(ParticipantId),
+    /// the key share sent by the participant does not match its commitment
+    InvalidKeyShare(ParticipantId),
+    /// the nonce commitments are duplicated, fewer than the threshold, or
+    /// do not include the signer
+    InvalidNonceCommitments,
+    /// the signature share of the participant is invalid
+    InvalidSignatureShare(ParticipantId),
+    /// the bytes are not the encoding of a protocol message
+    InvalidEncoding,
+}
+
+impl fmt::Display for FrostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrostError::InvalidParameters => write!(f, "invalid threshold parameters"),
+            FrostError::InvalidKeyGenCommitments => {
+                write!(f, "expecting one key generation commitment per participant")
+            }
+            FrostError::InvalidProofOfKnowledge(id) => {
+                write!(f, "invalid proof of knowledge of participant {}", id)
+            }
+            FrostError::InvalidKeyShare(id) => write!(f, "invalid key share of participant {}", id),
+            FrostError::InvalidNonceCommitments => write!(f, "invalid set of nonce commitments"),
+            FrostError::InvalidSignatureShare(id) => {
+                write!(f, "invalid signature share of participant {}", id)
+            }
+            FrostError::InvalidEncoding => write!(f, "invalid protocol message encoding"),
+        }
+    }
+}
+
+impl std::error::Error for FrostError {}
+
+fn hash_to_scalar(tag: &[u8], parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(DOMAIN);
+    hasher.update(tag);
+    for part in parts {
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    Scalar::from_hash(hasher)
+}
+
+fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes);
+    let scalar = Scalar::from_bytes_mod_order_wide(&bytes);
+    bytes.zeroize();
+    scalar
+}
+
+fn mul_base(scalar: &Scalar) -> EdwardsPoint {
+    scalar * &ED25519_BASEPOINT_TABLE
+}
+
+fn read_id(bytes: &[u8]) -> ParticipantId {
+    ParticipantId::from_le_bytes(bytes.try_into().unwrap())
+}
+
+/// Only the points of the prime order subgroup are accepted
+fn read_point(bytes: &[u8]) -> Result<EdwardsPoint, FrostError> {
+    CompressedEdwardsY(bytes.try_into().unwrap())
+        .decompress()
+        .filter(EdwardsPoint::is_torsion_free)
+        .ok_or(FrostError::InvalidEncoding)
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<Scalar, FrostError> {
+    Scalar::from_canonical_bytes(bytes.try_into().unwrap()).ok_or(FrostError::InvalidEncoding)
+}
+
+/// The value of the polynomial committed by `coefficients` at `id`
+fn evaluate_commitment(coefficients: &[EdwardsPoint], id: ParticipantId) -> EdwardsPoint {
+    let x = Scalar::from(id as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(EdwardsPoint::identity(), |acc, coefficient| {
+            acc * x + coefficient
+        })
+}
+
+/// Lagrange coefficient of `id` for the interpolation at zero over `ids`
+fn lagrange_coefficient(id: ParticipantId, ids: &[ParticipantId]) -> Scalar {
+    let x = Scalar::from(id as u64);
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for other in ids.iter().filter(|other| **other != id) {
+        let other = Scalar::from(*other as u64);
+        numerator *= other;
+        denominator *= other - x;
+    }
+    numerator * denominator.invert()
+}
+
+/* Key generation *********************************************************** */
+
+/// Secret state of a participant during the key generation
+pub struct KeyGen {
+    session: Vec<u8>,
+    id: ParticipantId,
+    participants: u16,
+    coefficients: Vec<Scalar>,
+}
+
+/// Broadcast by every participant at the start of the key generation: the
+/// commitment to its secret polynomial, with a proof of knowledge of the
+/// constant term
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyGenCommitment {
+    sender: ParticipantId,
+    coefficients: Vec<EdwardsPoint>,
+    proof: (EdwardsPoint, Scalar),
+}
+
+/// Sent privately by a participant to each other participant during the
+/// key generation
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyShare {
+    sender: ParticipantId,
+    receiver: ParticipantId,
+    value: Scalar,
+}
+
+impl fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("sender", &self.sender)
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+/// The challenge of the proof of knowledge is bound to the key generation
+/// session and to the participant, so that a proof cannot be replayed in
+/// another session or by another participant
+fn proof_challenge(
+    session: &[u8],
+    sender: ParticipantId,
+    constant: &EdwardsPoint,
+    r: &EdwardsPoint,
+) -> Scalar {
+    hash_to_scalar(
+        b"dkg",
+        &[
+            session,
+            &sender.to_le_bytes(),
+            constant.compress().as_bytes(),
+            r.compress().as_bytes(),
+        ],
+    )
+}
+
+impl KeyGenCommitment {
+    pub fn sender(&self) -> ParticipantId {
+        self.sender
+    }
+
+    /// `sender || number of coefficients (2 bytes) || coefficients || R || z`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            2 * ID_SIZE + self.coefficients.len() * POINT_SIZE + POINT_SIZE + SCALAR_SIZE,
+        );
+        bytes.extend_from_slice(&self.sender.to_le_bytes());
+        bytes.extend_from_slice(&(self.coefficients.len() as u16).to_le_bytes());
+        for coefficient in self.coefficients.iter() {
+            bytes.extend_from_slice(coefficient.compress().as_bytes());
+        }
+        bytes.extend_from_slice(self.proof.0.compress().as_bytes());
+        bytes.extend_from_slice(self.proof.1.as_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FrostError> {
+        if bytes.len() < 2 * ID_SIZE {
+            return Err(FrostError::InvalidEncoding);
+        }
+        let sender = read_id(&bytes[..ID_SIZE]);
+        let count = read_id(&bytes[ID_SIZE..2 * ID_SIZE]) as usize;
+        let proof_start = 2 * ID_SIZE + count * POINT_SIZE;
+        if count == 0 || bytes.len() != proof_start + POINT_SIZE + SCALAR_SIZE {
+            return Err(FrostError::InvalidEncoding);
+        }
+        let coefficients = bytes[2 * ID_SIZE..proof_start]
+            .chunks(POINT_SIZE)
+            .map(read_point)
+            .collect::<Result<_, _>>()?;
+        let r = read_point(&bytes[proof_start..proof_start + POINT_SIZE])?;
+        let z = read_scalar(&bytes[proof_start + POINT_SIZE..])?;
+        Ok(KeyGenCommitment {
+            sender,
+            coefficients,
+            proof: (r, z),
+        })
+    }
+}
+
+impl KeyShare {
+    pub const SIZE: usize = 2 * ID_SIZE + SCALAR_SIZE;
+
+    pub fn sender(&self) -> ParticipantId {
+        self.sender
+    }
+
+    pub fn receiver(&self) -> ParticipantId {
+        self.receiver
+    }
+
+    /// `sender || receiver || value`
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..ID_SIZE].copy_from_slice(&self.sender.to_le_bytes());
+        bytes[ID_SIZE..2 * ID_SIZE].copy_from_slice(&self.receiver.to_le_bytes());
+        bytes[2 * ID_SIZE..].copy_from_slice(self.value.as_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FrostError> {
+        if bytes.len() != Self::SIZE {
+            return Err(FrostError::InvalidEncoding);
+        }
+        Ok(KeyShare {
+            sender: read_id(&bytes[..ID_SIZE]),
+            receiver: read_id(&bytes[ID_SIZE..2 * ID_SIZE]),
+            value: read_scalar(&bytes[2 * ID_SIZE..])?,
+        })
+    }
+}
+
+impl KeyGen {
+    /// Start the key generation as participant `id` of `participants`, any
+    /// `threshold` of which will be able to sign. The `session` identifies
+    /// this key generation, the participants must agree on it beforehand and
+    /// never reuse it, e.g. the hash of the list of the participants and of
+    /// a counter.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        session: &[u8],
+        id: ParticipantId,
+        threshold: u16,
+        participants: u16,
+    ) -> Result<(Self, KeyGenCommitment), FrostError> {
+        if threshold == 0 || threshold > participants || id == 0 || id > participants {
+            return Err(FrostError::InvalidParameters);
+        }
+        let coefficients: Vec<Scalar> = (0..threshold).map(|_| random_scalar(rng)).collect();
+        let commitments: Vec<EdwardsPoint> = coefficients.iter().map(mul_base).collect();
+
+        let mut k = random_scalar(rng);
+        let r = mul_base(&k);
+        let z = k + coefficients[0] * proof_challenge(session, id, &commitments[0], &r);
+        k.zeroize();
+
+        let commitment = KeyGenCommitment {
+            sender: id,
+            coefficients: commitments,
+            proof: (r, z),
+        };
+        Ok((
+            KeyGen {
+                session: session.to_vec(),
+                id,
+                participants,
+                coefficients,
+            },
+            commitment,
+        ))
+    }
+
+    pub fn id(&self) -> ParticipantId {
+        self.id
+    }
+
+    /// The share of the participant `receiver`, to send it privately
+    pub fn share_for(&self, receiver: ParticipantId) -> KeyShare {
+        let x = Scalar::from(receiver as u64);
+        let value = self
+            .coefficients
+            .iter()
+            .rev()
+            .fold(Scalar::zero(), |acc, coefficient| acc * x + coefficient);
+        KeyShare {
+            sender: self.id,
+            receiver,
+            value,
+        }
+    }
+
+    /// Finish the key generation with the commitments of all the
+    /// participants and the shares received from all the others
+    pub fn finish(
+        self,
+        commitments: &[KeyGenCommitment],
+        shares: &[KeyShare],
+    ) -> Result<(SignerKey, GroupKey), FrostError> {
+        let threshold = self.coefficients.len();
+        let mut by_sender = BTreeMap::new();
+        for commitment in commitments {
+            if commitment.sender == 0
+                || commitment.sender > self.participants
+                || commitment.coefficients.len() != threshold
+                || by_sender.insert(commitment.sender, commitment).is_some()
+            {
+                return Err(FrostError::InvalidKeyGenCommitments);
+            }
+        }
+        if by_sender.len() != self.participants as usize {
+            return Err(FrostError::InvalidKeyGenCommitments);
+        }
+        for (sender, commitment) in by_sender.iter() {
+            let (r, z) = &commitment.proof;
+            let c = proof_challenge(&self.session, *sender, &commitment.coefficients[0], r);
+            if mul_base(z) - commitment.coefficients[0] * c != *r {
+                return Err(FrostError::InvalidProofOfKnowledge(*sender));
+            }
+        }
+
+        let mut secret = self.share_for(self.id).value;
+        let mut received = vec![self.id];
+        for share in shares {
+            if share.receiver != self.id
+                || share.sender == self.id
+                || received.contains(&share.sender)
+            {
+                return Err(FrostError::InvalidKeyShare(share.sender));
+            }
+            let commitment = by_sender
+                .get(&share.sender)
+                .ok_or(FrostError::InvalidKeyShare(share.sender))?;
+            if mul_base(&share.value) != evaluate_commitment(&commitment.coefficients, self.id) {
+                return Err(FrostError::InvalidKeyShare(share.sender));
+            }
+            secret += share.value;
+            received.push(share.sender);
+        }
+        if let Some(missing) = (1..=self.participants).find(|id| !received.contains(id)) {
+            return Err(FrostError::InvalidKeyShare(missing));
+        }
+
+        let public_key = by_sender
+            .values()
+            .map(|commitment| commitment.coefficients[0])
+            .fold(EdwardsPoint::identity(), |acc, point| acc + point);
+        let public_shares = (1..=self.participants)
+            .map(|id| {
+                let share = by_sender
+                    .values()
+                    .map(|commitment| evaluate_commitment(&commitment.coefficients, id))
+                    .fold(EdwardsPoint::identity(), |acc, point| acc + point);
+                (id, share)
+            })
+            .collect();
+        let group = GroupKey {
+            threshold: threshold as u16,
+            public_key,
+            public_shares,
+        };
+        let signer = SignerKey {
+            id: self.id,
+            secret,
+            group: group.clone(),
+        };
+        Ok((signer, group))
+    }
+}
+
+impl Drop for KeyGen {
+    fn drop(&mut self) {
+        self.coefficients.zeroize();
+    }
+}
+
+/* Signing ****************************************************************** */
+
+/// The public part of the key generation outcome, the same for all the
+/// participants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupKey {
+    threshold: u16,
+    public_key: EdwardsPoint,
+    public_shares: BTreeMap<ParticipantId, EdwardsPoint>,
+}
+
+/// The key share of a participant
+pub struct SignerKey {
+    id: ParticipantId,
+    secret: Scalar,
+    group: GroupKey,
+}
+
+/// The single use nonces of a signer for one signature
+pub struct SigningNonces {
+    id: ParticipantId,
+    hiding: Scalar,
+    binding: Scalar,
+}
+
+/// Broadcast by every signer in the first round of a signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceCommitment {
+    id: ParticipantId,
+    hiding: EdwardsPoint,
+    binding: EdwardsPoint,
+}
+
+/// Sent by every signer in the second round of a signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureShare {
+    id: ParticipantId,
+    z: Scalar,
+}
+
+impl NonceCommitment {
+    pub const SIZE: usize = ID_SIZE + 2 * POINT_SIZE;
+
+    pub fn id(&self) -> ParticipantId {
+        self.id
+    }
+
+    /// `id || hiding || binding`
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..ID_SIZE].copy_from_slice(&self.id.to_le_bytes());
+        bytes[ID_SIZE..ID_SIZE + POINT_SIZE].copy_from_slice(self.hiding.compress().as_bytes());
+        bytes[ID_SIZE + POINT_SIZE..].copy_from_slice(self.binding.compress().as_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FrostError> {
+        if bytes.len() != Self::SIZE {
+            return Err(FrostError::InvalidEncoding);
+        }
+        Ok(NonceCommitment {
+            id: read_id(&bytes[..ID_SIZE]),
+            hiding: read_point(&bytes[ID_SIZE..ID_SIZE + POINT_SIZE])?,
+            binding: read_point(&bytes[ID_SIZE + POINT_SIZE..])?,
+        })
+    }
+}
+
+impl SignatureShare {
+    pub const SIZE: usize = ID_SIZE + SCALAR_SIZE;
+
+    pub fn id(&self) -> ParticipantId {
+        self.id
+    }
+
+    /// `id || z`
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..ID_SIZE].copy_from_slice(&self.id.to_le_bytes());
+        bytes[ID_SIZE..].copy_from_slice(self.z.as_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FrostError> {
+        if bytes.len() != Self::SIZE {
+            return Err(FrostError::InvalidEncoding);
+        }
+        Ok(SignatureShare {
+            id: read_id(&bytes[..ID_SIZE]),
+            z: read_scalar(&bytes[ID_SIZE..])?,
+        })
+    }
+}
+
+/// What all the signers and the aggregator derive from the message and the
+/// nonce commitments
+struct SigningPackage {
+    ids: Vec<ParticipantId>,
+    binding_factors: BTreeMap<ParticipantId, Scalar>,
+    commitments: BTreeMap<ParticipantId, NonceCommitment>,
+    group_commitment: EdwardsPoint,
+    challenge: Scalar,
+}
+
+impl GroupKey {
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    /// The Ed25519 public key the aggregated signatures are verified with
+    pub fn public_key(&self) -> PublicKey<Ed25519> {
+        PublicKey::from_binary(self.public_key.compress().as_bytes())
+            .expect("a valid compressed Edwards point is a valid Ed25519 public key")
+    }
+
+    fn signing_package(
+        &self,
+        message: &[u8],
+        commitments: &[NonceCommitment],
+    ) -> Result<SigningPackage, FrostError> {
+        let mut sorted = BTreeMap::new();
+        for commitment in commitments {
+            if !self.public_shares.contains_key(&commitment.id)
+                || sorted.insert(commitment.id, *commitment).is_some()
+            {
+                return Err(FrostError::InvalidNonceCommitments);
+            }
+        }
+        if sorted.len() < self.threshold as usize {
+            return Err(FrostError::InvalidNonceCommitments);
+        }
+
+        let mut encoded = Vec::with_capacity(sorted.len() * 66);
+        for commitment in sorted.values() {
+            encoded.extend_from_slice(&commitment.id.to_le_bytes());
+            encoded.extend_from_slice(commitment.hiding.compress().as_bytes());
+            encoded.extend_from_slice(commitment.binding.compress().as_bytes());
+        }
+        let public_key = self.public_key.compress();
+        let binding_factors: BTreeMap<_, _> = sorted
+            .keys()
+            .map(|id| {
+                let factor = hash_to_scalar(
+                    b"rho",
+                    &[&id.to_le_bytes(), public_key.as_bytes(), message, &encoded],
+                );
+                (*id, factor)
+            })
+            .collect();
+        let group_commitment = sorted
+            .values()
+            .map(|commitment| {
+                commitment.hiding + commitment.binding * binding_factors[&commitment.id]
+            })
+            .fold(EdwardsPoint::identity(), |acc, point| acc + point);
+
+        // the challenge of Ed25519: SHA-512(R || A || M)
+        let mut hasher = Sha512::new();
+        hasher.update(group_commitment.compress().as_bytes());
+        hasher.update(public_key.as_bytes());
+        hasher.update(message);
+        let challenge = Scalar::from_hash(hasher);
+
+        Ok(SigningPackage {
+            ids: sorted.keys().copied().collect(),
+            binding_factors,
+            commitments: sorted,
+            group_commitment,
+            challenge,
+        })
+    }
+
+    fn share_is_valid(&self, package: &SigningPackage, share: &SignatureShare) -> bool {
+        let (commitment, public_share) = match (
+            package.commitments.get(&share.id),
+            self.public_shares.get(&share.id),
+        ) {
+            (Some(commitment), Some(public_share)) => (commitment, public_share),
+            _ => return false,
+        };
+        let lambda = lagrange_coefficient(share.id, &package.ids);
+        mul_base(&share.z)
+            == commitment.hiding
+                + commitment.binding * package.binding_factors[&share.id]
+                + public_share * (package.challenge * lambda)
+    }
+
+    /// Check the signature share of a signer
+    pub fn verify_share(
+        &self,
+        message: &[u8],
+        commitments: &[NonceCommitment],
+        share: &SignatureShare,
+    ) -> Result<bool, FrostError> {
+        let package = self.signing_package(message, commitments)?;
+        Ok(self.share_is_valid(&package, share))
+    }
+
+    /// Aggregate the shares of all the signers which committed to nonces
+    /// into an Ed25519 signature of the group public key. Fails on the
+    /// first invalid or missing share.
+    pub fn aggregate(
+        &self,
+        message: &[u8],
+        commitments: &[NonceCommitment],
+        shares: &[SignatureShare],
+    ) -> Result<Signature<[u8], Ed25519>, FrostError> {
+        let package = self.signing_package(message, commitments)?;
+        let shares: BTreeMap<_, _> = shares.iter().map(|share| (share.id, share)).collect();
+        let mut z = Scalar::zero();
+        for id in package.ids.iter() {
+            match shares.get(id) {
+                Some(share) if self.share_is_valid(&package, share) => z += share.z,
+                _ => return Err(FrostError::InvalidSignatureShare(*id)),
+            }
+        }
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(package.group_commitment.compress().as_bytes());
+        signature[32..].copy_from_slice(z.as_bytes());
+        Ok(Signature::from_binary(&signature).expect("64 bytes Ed25519 signature"))
+    }
+}
+
+impl SignerKey {
+    pub fn id(&self) -> ParticipantId {
+        self.id
+    }
+
+    pub fn group(&self) -> &GroupKey {
+        &self.group
+    }
+
+    /// First round: draw the nonces of a new signature
+    pub fn commit<R: RngCore + CryptoRng>(&self, rng: &mut R) -> (SigningNonces, NonceCommitment) {
+        let hiding = random_scalar(rng);
+        let binding = random_scalar(rng);
+        let commitment = NonceCommitment {
+            id: self.id,
+            hiding: mul_base(&hiding),
+            binding: mul_base(&binding),
+        };
+        let nonces = SigningNonces {
+            id: self.id,
+            hiding,
+            binding,
+        };
+        (nonces, commitment)
+    }
+
+    /// Second round: sign `message` with the nonces committed to in the
+    /// first round, given the commitments of all the signers
+    pub fn sign(
+        &self,
+        nonces: SigningNonces,
+        message: &[u8],
+        commitments: &[NonceCommitment],
+    ) -> Result<SignatureShare, FrostError> {
+        let package = self.group.signing_package(message, commitments)?;
+        match package.commitments.get(&self.id) {
+            Some(commitment)
+                if nonces.id == self.id
+                    && commitment.hiding == mul_base(&nonces.hiding)
+                    && commitment.binding == mul_base(&nonces.binding) => {}
+            _ => return Err(FrostError::InvalidNonceCommitments),
+        }
+        let lambda = lagrange_coefficient(self.id, &package.ids);
+        let z = nonces.hiding
+            + nonces.binding * package.binding_factors[&self.id]
+            + lambda * self.secret * package.challenge;
+        Ok(SignatureShare { id: self.id, z })
+    }
+}
+
+impl Drop for SignerKey {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl Drop for SigningNonces {
+    fn drop(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeterministicRng, Verification};
+    use rand_core::SeedableRng;
+
+    const SESSION: &[u8] = b"test session";
+
+    fn key_generation(
+        rng: &mut DeterministicRng,
+        threshold: u16,
+        participants: u16,
+    ) -> Vec<SignerKey> {
+        let (keygens, commitments): (Vec<_>, Vec<_>) = (1..=participants)
+            .map(|id| KeyGen::new(rng, SESSION, id, threshold, participants).unwrap())
+            .unzip();
+        let shares: Vec<Vec<_>> = keygens
+            .iter()
+            .map(|keygen| {
+                keygens
+                    .iter()
+                    .filter(|other| other.id() != keygen.id())
+                    .map(|other| other.share_for(keygen.id()))
+                    .collect()
+            })
+            .collect();
+        keygens
+            .into_iter()
+            .zip(shares)
+            .map(|(keygen, shares)| keygen.finish(&commitments, &shares).unwrap().0)
+            .collect()
+    }
+
+    fn sign(
+        rng: &mut DeterministicRng,
+        signers: &[&SignerKey],
+        message: &[u8],
+    ) -> (Vec<NonceCommitment>, Vec<SignatureShare>) {
+        let (nonces, commitments): (Vec<_>, Vec<_>) =
+            signers.iter().map(|signer| signer.commit(rng)).unzip();
+        let shares = signers
+            .iter()
+            .zip(nonces)
+            .map(|(signer, nonces)| signer.sign(nonces, message, &commitments).unwrap())
+            .collect();
+        (commitments, shares)
+    }
+
+    #[test]
+    fn threshold_signature_verifies_as_ed25519() {
+        let mut rng = DeterministicRng::from_seed([1; 32]);
+        let signers = key_generation(&mut rng, 2, 3);
+        let group = signers[0].group().clone();
+        assert!(signers.iter().all(|signer| *signer.group() == group));
+
+        let message = b"block header";
+        for subset in &[[0, 1], [0, 2], [1, 2]] {
+            let subset: Vec<_> = subset.iter().map(|i| &signers[*i]).collect();
+            let (commitments, shares) = sign(&mut rng, &subset, message);
+            let signature = group.aggregate(message, &commitments, &shares).unwrap();
+            assert_eq!(
+                signature.verify_slice(&group.public_key(), message),
+                Verification::Success
+            );
+            assert_eq!(
+                signature.verify_slice(&group.public_key(), b"another header"),
+                Verification::Failed
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_shares_are_identified() {
+        let mut rng = DeterministicRng::from_seed([2; 32]);
+        let signers = key_generation(&mut rng, 2, 3);
+        let group = signers[0].group().clone();
+        let message = b"block header";
+
+        let (commitments, mut shares) = sign(&mut rng, &[&signers[0], &signers[2]], message);
+        shares[1].z += Scalar::one();
+        assert_eq!(
+            group.verify_share(message, &commitments, &shares[0]),
+            Ok(true)
+        );
+        assert_eq!(
+            group.verify_share(message, &commitments, &shares[1]),
+            Ok(false)
+        );
+        assert_eq!(
+            group.aggregate(message, &commitments, &shares).unwrap_err(),
+            FrostError::InvalidSignatureShare(3)
+        );
+
+        // a single signer is below the threshold
+        let (nonces, commitment) = signers[0].commit(&mut rng);
+        assert_eq!(
+            signers[0].sign(nonces, message, &[commitment]).unwrap_err(),
+            FrostError::InvalidNonceCommitments
+        );
+    }
+
+    #[test]
+    fn invalid_key_shares_are_identified() {
+        let mut rng = DeterministicRng::from_seed([3; 32]);
+        let (first, first_commitment) = KeyGen::new(&mut rng, SESSION, 1, 2, 2).unwrap();
+        let (second, second_commitment) = KeyGen::new(&mut rng, SESSION, 2, 2, 2).unwrap();
+        let mut share = second.share_for(1);
+        share.value += Scalar::one();
+        assert_eq!(
+            first
+                .finish(&[first_commitment, second_commitment], &[share])
+                .unwrap_err(),
+            FrostError::InvalidKeyShare(2)
+        );
+        assert!(KeyGen::new(&mut rng, SESSION, 3, 2, 2).is_err());
+    }
+
+    #[test]
+    fn proofs_of_knowledge_are_bound_to_the_session_and_sender() {
+        let mut rng = DeterministicRng::from_seed([4; 32]);
+        let (first, first_commitment) = KeyGen::new(&mut rng, SESSION, 1, 2, 2).unwrap();
+        let (_, replayed) = KeyGen::new(&mut rng, b"another session", 2, 2, 2).unwrap();
+        assert_eq!(
+            first
+                .finish(&[first_commitment.clone(), replayed], &[])
+                .unwrap_err(),
+            FrostError::InvalidProofOfKnowledge(2)
+        );
+
+        let (first, _) = KeyGen::new(&mut rng, SESSION, 1, 2, 2).unwrap();
+        let mut stolen = first_commitment.clone();
+        stolen.sender = 2;
+        assert_eq!(
+            first.finish(&[first_commitment, stolen], &[]).unwrap_err(),
+            FrostError::InvalidProofOfKnowledge(2)
+        );
+    }
+
+    #[test]
+    fn messages_encoding_roundtrip() {
+        let mut rng = DeterministicRng::from_seed([5; 32]);
+        let (keygen, commitment) = KeyGen::new(&mut rng, SESSION, 1, 2, 3).unwrap();
+        let bytes = commitment.to_bytes();
+        assert_eq!(KeyGenCommitment::from_bytes(&bytes), Ok(commitment));
+        assert_eq!(
+            KeyGenCommitment::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(FrostError::InvalidEncoding)
+        );
+        let share = keygen.share_for(2);
+        assert_eq!(KeyShare::from_bytes(&share.to_bytes()), Ok(share));
+
+        let signers = key_generation(&mut rng, 2, 3);
+        let (commitments, shares) = sign(&mut rng, &[&signers[0], &signers[1]], b"header");
+        for commitment in commitments {
+            let bytes = commitment.to_bytes();
+            assert_eq!(NonceCommitment::from_bytes(&bytes), Ok(commitment));
+        }
+        for share in shares {
+            let mut bytes = share.to_bytes();
+            assert_eq!(SignatureShare::from_bytes(&bytes), Ok(share));
+            // scalars must be canonical
+            bytes[ID_SIZE + SCALAR_SIZE - 1] = 0xff;
+            assert_eq!(
+                SignatureShare::from_bytes(&bytes),
+                Err(FrostError::InvalidEncoding)
+            );
+        }
+    }
+}
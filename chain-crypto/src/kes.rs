@@ -1,6 +1,59 @@
-use crate::sign::VerificationAlgorithm;
+use crate::key::PublicKey;
+use crate::sign::{Signature, Verification, VerificationAlgorithm};
 
 pub trait KeyEvolvingSignatureAlgorithm: VerificationAlgorithm {
     /// Get the period associated with this signature
     fn get_period(sig: &Self::Signature) -> u32;
 }
+
+/// Key evolving signatures whose verification can reuse the work done for
+/// the previous signatures of the same key, see [`KesVerifier`]
+pub trait IncrementalKesVerification: KeyEvolvingSignatureAlgorithm {
+    type VerificationContext: Clone;
+
+    fn verification_context(pk: &Self::Public) -> Self::VerificationContext;
+
+    /// Same outcome as `verify_bytes` with the public key of the context
+    fn verify_bytes_with_context(
+        context: &mut Self::VerificationContext,
+        signature: &Self::Signature,
+        msg: &[u8],
+    ) -> Verification;
+}
+
+/// Verifier of the signatures made with a single key evolving key.
+///
+/// The verifier is meant to be kept while verifying many signatures of the
+/// same key, like the headers of a pool in a batch of blocks: the
+/// authentication of the key of a period is only computed once for all the
+/// signatures of that period.
+#[derive(Clone)]
+pub struct KesVerifier<A: IncrementalKesVerification> {
+    context: A::VerificationContext,
+}
+
+impl<A: IncrementalKesVerification> KesVerifier<A> {
+    pub fn new(publickey: &PublicKey<A>) -> Self {
+        KesVerifier {
+            context: A::verification_context(&publickey.0),
+        }
+    }
+
+    #[must_use]
+    pub fn verify<T: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        signature: &Signature<T, A>,
+        object: &T,
+    ) -> Verification {
+        self.verify_slice(signature, object.as_ref())
+    }
+
+    #[must_use]
+    pub fn verify_slice<T: ?Sized>(
+        &mut self,
+        signature: &Signature<T, A>,
+        slice: &[u8],
+    ) -> Verification {
+        A::verify_bytes_with_context(&mut self.context, &signature.signdata, slice)
+    }
+}
@@ -1,4 +1,6 @@
 use crate::bech32::{self, Bech32};
+use crate::secret_decoding::{self, DecodingError};
+use crate::validation::ValidationPolicy;
 use hex::FromHexError;
 use rand_core::{CryptoRng, RngCore};
 use std::fmt;
@@ -23,12 +25,27 @@ pub enum PublicKeyFromStrError {
     KeyInvalid(PublicKeyError),
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretKeyFromStrError {
+    HexMalformed(DecodingError),
+    KeyInvalid(SecretKeyError),
+}
+
 pub trait AsymmetricPublicKey {
     type Public: AsRef<[u8]> + Clone + PartialEq + Eq + Hash;
     const PUBLIC_BECH32_HRP: &'static str;
     const PUBLIC_KEY_SIZE: usize;
 
     fn public_from_binary(data: &[u8]) -> Result<Self::Public, PublicKeyError>;
+
+    /// Check the encoding of a decoded public key against the policy, the
+    /// keys of the algorithms without malleable encodings are always valid
+    fn public_validate(
+        _public: &Self::Public,
+        _policy: ValidationPolicy,
+    ) -> Result<(), PublicKeyError> {
+        Ok(())
+    }
 }
 
 pub trait AsymmetricKey {
@@ -103,6 +120,15 @@ impl<A: AsymmetricPublicKey> FromStr for PublicKey<A> {
     }
 }
 
+impl<A: AsymmetricKey> FromStr for SecretKey<A> {
+    type Err = SecretKeyFromStrError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let bytes = secret_decoding::from_hex(hex).map_err(SecretKeyFromStrError::HexMalformed)?;
+        Self::from_binary(&bytes).map_err(SecretKeyFromStrError::KeyInvalid)
+    }
+}
+
 impl fmt::Display for SecretKeyError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -131,6 +157,16 @@ impl fmt::Display for PublicKeyFromStrError {
     }
 }
 
+impl fmt::Display for SecretKeyFromStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretKeyFromStrError::HexMalformed(_) => "hex encoding malformed",
+            SecretKeyFromStrError::KeyInvalid(_) => "invalid secret key data",
+        }
+        .fmt(f)
+    }
+}
+
 impl std::error::Error for SecretKeyError {}
 
 impl std::error::Error for PublicKeyError {}
@@ -144,6 +180,15 @@ impl std::error::Error for PublicKeyFromStrError {
     }
 }
 
+impl std::error::Error for SecretKeyFromStrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SecretKeyFromStrError::HexMalformed(e) => Some(e),
+            SecretKeyFromStrError::KeyInvalid(e) => Some(e),
+        }
+    }
+}
+
 impl<A: AsymmetricPublicKey> AsRef<[u8]> for PublicKey<A> {
     fn as_ref(&self) -> &[u8] {
         self.0.as_ref()
@@ -182,6 +227,20 @@ impl<A: AsymmetricPublicKey> PublicKey<A> {
         )?))
     }
 
+    /// Decode the public key and check its encoding against the policy
+    pub fn from_binary_with_policy(
+        data: &[u8],
+        policy: ValidationPolicy,
+    ) -> Result<Self, PublicKeyError> {
+        let publickey = Self::from_binary(data)?;
+        publickey.validate(policy)?;
+        Ok(publickey)
+    }
+
+    pub fn validate(&self, policy: ValidationPolicy) -> Result<(), PublicKeyError> {
+        <A as AsymmetricPublicKey>::public_validate(&self.0, policy)
+    }
+
     pub fn inner(self) -> A::Public {
         self.0
     }
@@ -249,7 +308,7 @@ impl<A: AsymmetricKey> Bech32 for SecretKey<A> {
     const BECH32_HRP: &'static str = A::SECRET_BECH32_HRP;
 
     fn try_from_bech32_str(bech32_str: &str) -> Result<Self, bech32::Error> {
-        let bytes = bech32::try_from_bech32_to_bytes::<Self>(bech32_str)?;
+        let bytes = secret_decoding::from_bech32::<Self>(bech32_str)?;
         Self::from_binary(&bytes).map_err(bech32::Error::data_invalid)
     }
 
@@ -17,30 +17,49 @@ cfg_if! {
     }
 }
 
+#[cfg(any(test, feature = "property-test-api"))]
+pub mod rng;
+
 pub mod algorithms;
+mod any;
 pub mod asymlock;
 pub mod bech32;
+pub mod bip39;
+#[cfg(feature = "capi")]
+pub mod capi;
 pub mod digest;
 mod evolving;
+pub mod fingerprint;
+pub mod frost;
 pub mod hash;
 mod kes;
 mod key;
 pub mod multilock;
 mod sign;
+pub mod validation;
 mod vrf;
 
 pub mod role;
+pub mod secret_decoding;
+pub mod self_test;
 
+pub use any::{AnyKeyError, AnyPublicKey, AnySignature, SignatureAlgorithmId};
 pub use evolving::{EvolvingStatus, KeyEvolvingAlgorithm};
-pub use kes::KeyEvolvingSignatureAlgorithm;
+pub use fingerprint::{Fingerprint, FingerprintFromStrError};
+pub use kes::{IncrementalKesVerification, KesVerifier, KeyEvolvingSignatureAlgorithm};
 pub use key::{
     AsymmetricKey, AsymmetricPublicKey, KeyPair, PublicKey, PublicKeyError, PublicKeyFromStrError,
-    SecretKey, SecretKeyError, SecretKeySizeStatic,
+    SecretKey, SecretKeyError, SecretKeyFromStrError, SecretKeySizeStatic,
 };
+#[cfg(any(test, feature = "property-test-api"))]
+pub use rng::DeterministicRng;
+pub use self_test::{self_test, self_test_with_rng, SelfTestReport};
 pub use sign::{
-    Signature, SignatureError, SignatureFromStrError, SigningAlgorithm, Verification,
-    VerificationAlgorithm,
+    ContextSignature, ContextSigningAlgorithm, ContextVerificationAlgorithm, PrehashedSignature,
+    Signature, SignatureError, SignatureFromStrError, SigningAlgorithm, SigningContext,
+    Verification, VerificationAlgorithm,
 };
+pub use validation::ValidationPolicy;
 pub use vrf::{
     vrf_evaluate_and_prove, vrf_verified_get_output, vrf_verify, VRFVerification,
     VerifiableRandomFunction,
@@ -0,0 +1,92 @@
+//! Deterministic random number generator for test vectors and fixtures.
+//!
+//! All the functions of this crate needing randomness (key generation, VRF
+//! proofs, the randomized self-tests, ...) take the generator as a
+//! `RngCore + CryptoRng` parameter. Production code passes the operating
+//! system's generator (`rand_core::OsRng`), while tests and tools producing
+//! reproducible fixtures pass a [`DeterministicRng`].
+//!
+//! The output of a [`DeterministicRng`] is the ChaCha20 keystream keyed by
+//! the 32 bytes seed, with a zero nonce, as specified by
+//! `rand_chacha::ChaCha20Rng`. It does not depend on the platform nor on
+//! the way the bytes are requested, so the keys generated from a given seed
+//! are the same on every machine and can be recorded as test vectors.
+//!
+//! The generator is only as secret as its seed, which is usually a constant
+//! or a label: it is only built for the tests of this crate and with the
+//! `property-test-api` feature, so that it cannot be handed to the key
+//! generation of production code by mistake.
+
+use crate::hash::Blake2b256;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, Error as RngError, RngCore, SeedableRng};
+
+/// Reproducible ChaCha20 based generator, see the module documentation
+#[derive(Clone, Debug)]
+pub struct DeterministicRng(ChaCha20Rng);
+
+impl DeterministicRng {
+    /// The generator seeded with the Blake2b-256 digest of `label`, so that
+    /// every fixture can have its own generator named after it
+    pub fn from_label(label: &[u8]) -> Self {
+        let mut seed = [0; 32];
+        seed.copy_from_slice(Blake2b256::new(label).as_ref());
+        Self::from_seed(seed)
+    }
+}
+
+impl RngCore for DeterministicRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RngError> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl SeedableRng for DeterministicRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self(ChaCha20Rng::from_seed(seed))
+    }
+}
+
+impl CryptoRng for DeterministicRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::SecretKey;
+    use crate::Ed25519;
+
+    #[test]
+    fn chacha20_keystream() {
+        // RFC 8439, section 2.3.2 (block 0 of the all-zero key and nonce)
+        let mut rng = DeterministicRng::from_seed([0; 32]);
+        let mut bytes = [0; 16];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(hex::encode(bytes), "76b8e0ada0f13d90405d6ae55386bd28");
+    }
+
+    #[test]
+    fn keys_are_reproducible() {
+        let key = |label: &[u8]| {
+            SecretKey::<Ed25519>::generate(DeterministicRng::from_label(label))
+                .to_public()
+                .as_ref()
+                .to_vec()
+        };
+        assert_eq!(key(b"alice"), key(b"alice"));
+        assert_ne!(key(b"alice"), key(b"bob"));
+    }
+}
@@ -0,0 +1,353 @@
+//! Decoding of secret key material from its text encodings.
+//!
+//! The decoders of this module run in constant time with respect to the
+//! content of the encoded secret: the characters are mapped to their values
+//! with arithmetic instead of lookup tables, and the validity of the input
+//! is accumulated and only checked once everything is decoded. Only the
+//! length of the input, and the parts of it which are not secret, like the
+//! HRP of a bech32 string or the padding of base64, change the time taken.
+//!
+//! The decoded bytes are returned in a [`SecretBytes`] buffer, allocated at
+//! its final size and wiped when dropped, so that no copy of the secret is
+//! left behind in the memory released by the decoding.
+
+use crate::bech32::{self, Bech32};
+use ::bech32::Error as Bech32Error;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Bytes of secret key material, zeroed on drop
+pub type SecretBytes = Zeroizing<Vec<u8>>;
+
+/// Failure to decode secret key material. Which character is invalid is not
+/// reported, so as not to tell anything about the secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodingError {
+    LengthInvalid,
+    CharacterInvalid,
+    PaddingInvalid,
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DecodingError::LengthInvalid => "invalid length of the encoded secret",
+            DecodingError::CharacterInvalid => "invalid character in the encoded secret",
+            DecodingError::PaddingInvalid => "invalid padding of the encoded secret",
+        })
+    }
+}
+
+impl std::error::Error for DecodingError {}
+
+/// 0xff if `x == y`, 0 otherwise
+fn ct_eq(x: u8, y: u8) -> u8 {
+    (u32::from(x ^ y).wrapping_sub(1) >> 8) as u8
+}
+
+/// 0xff if `x < y`, 0 otherwise
+fn ct_lt(x: u8, y: u8) -> u8 {
+    (u32::from(x).wrapping_sub(u32::from(y)) >> 8) as u8
+}
+
+/// 0xff if `low <= x <= high`, 0 otherwise
+fn ct_in_range(x: u8, low: u8, high: u8) -> u8 {
+    !ct_lt(x, low) & !ct_lt(high, x)
+}
+
+/// the value of a hexadecimal digit, with 0xff as second value if it is
+/// valid
+fn hex_digit(c: u8) -> (u8, u8) {
+    let digit = ct_in_range(c, b'0', b'9');
+    let lower = ct_in_range(c, b'a', b'f');
+    let upper = ct_in_range(c, b'A', b'F');
+    let value = (digit & c.wrapping_sub(b'0'))
+        | (lower & c.wrapping_sub(b'a' - 10))
+        | (upper & c.wrapping_sub(b'A' - 10));
+    (value, digit | lower | upper)
+}
+
+/// the value of a character of the standard base64 alphabet, with 0xff as
+/// second value if it is valid
+fn base64_digit(c: u8) -> (u8, u8) {
+    let upper = ct_in_range(c, b'A', b'Z');
+    let lower = ct_in_range(c, b'a', b'z');
+    let digit = ct_in_range(c, b'0', b'9');
+    let plus = ct_eq(c, b'+');
+    let slash = ct_eq(c, b'/');
+    let value = (upper & c.wrapping_sub(b'A'))
+        | (lower & c.wrapping_sub(b'a' - 26))
+        | (digit & c.wrapping_add(52 - b'0'))
+        | (plus & 62)
+        | (slash & 63);
+    (value, upper | lower | digit | plus | slash)
+}
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [
+    0x3b6a_57b2,
+    0x2650_8e6d,
+    0x1ea1_19fa,
+    0x3d42_33dd,
+    0x2a14_62b3,
+];
+const BECH32_CHECKSUM_LEN: usize = 6;
+
+/// the value of a bech32 character, with 0xff as second value if it is
+/// valid, and the masks telling whether it is lower or upper case
+fn bech32_digit(c: u8) -> (u8, u8, u8, u8) {
+    let upper = ct_in_range(c, b'A', b'Z');
+    let lower = ct_in_range(c, b'a', b'z');
+    let c = c | (upper & 0x20);
+    let mut value = 0;
+    let mut valid = 0;
+    for (i, d) in BECH32_CHARSET.iter().enumerate() {
+        let eq = ct_eq(c, *d);
+        value |= eq & (i as u8);
+        valid |= eq;
+    }
+    (value, valid, lower, upper)
+}
+
+fn bech32_polymod_step(checksum: u32, value: u8) -> u32 {
+    let top = checksum >> 25;
+    let mut checksum = ((checksum & 0x01ff_ffff) << 5) ^ u32::from(value);
+    for (i, generator) in BECH32_GENERATOR.iter().enumerate() {
+        checksum ^= generator & 0u32.wrapping_sub((top >> i) & 1);
+    }
+    checksum
+}
+
+/// Decode the hexadecimal encoding of a secret, in lower or upper case
+pub fn from_hex(s: &str) -> Result<SecretBytes, DecodingError> {
+    let s = s.as_bytes();
+    if s.len() % 2 != 0 {
+        return Err(DecodingError::LengthInvalid);
+    }
+    let mut bytes = Zeroizing::new(Vec::with_capacity(s.len() / 2));
+    let mut invalid = 0;
+    for pair in s.chunks_exact(2) {
+        let (high, high_valid) = hex_digit(pair[0]);
+        let (low, low_valid) = hex_digit(pair[1]);
+        invalid |= !(high_valid & low_valid);
+        bytes.push((high << 4) | low);
+    }
+    if invalid != 0 {
+        return Err(DecodingError::CharacterInvalid);
+    }
+    Ok(bytes)
+}
+
+/// Decode the base64 encoding of a secret, with the standard alphabet. The
+/// padding is optional, the bits beyond the last byte have to be zero.
+pub fn from_base64(s: &str) -> Result<SecretBytes, DecodingError> {
+    let s = s.as_bytes();
+    let data = match s {
+        [data @ .., b'=', b'='] | [data @ .., b'='] => {
+            if s.len() % 4 != 0 {
+                return Err(DecodingError::PaddingInvalid);
+            }
+            data
+        }
+        data => data,
+    };
+    if data.len() % 4 == 1 {
+        return Err(DecodingError::LengthInvalid);
+    }
+    let mut bytes = Zeroizing::new(Vec::with_capacity(data.len() * 3 / 4));
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    let mut invalid = 0;
+    for c in data {
+        let (value, valid) = base64_digit(*c);
+        invalid |= !valid;
+        accumulator = (accumulator << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((accumulator >> bits) as u8);
+        }
+    }
+    let padding = accumulator & ((1 << bits) - 1);
+    accumulator.zeroize();
+    if invalid != 0 {
+        return Err(DecodingError::CharacterInvalid);
+    }
+    if padding != 0 {
+        return Err(DecodingError::PaddingInvalid);
+    }
+    Ok(bytes)
+}
+
+/// Decode the bech32 encoding of a secret, which has to have the HRP of `B`
+pub fn from_bech32<B: Bech32>(s: &str) -> bech32::Result<SecretBytes> {
+    let hrp = B::BECH32_HRP;
+    let has_hrp = s.len() > hrp.len()
+        && s.as_bytes()[..hrp.len()].eq_ignore_ascii_case(hrp.as_bytes())
+        && s.as_bytes()[hrp.len()] == b'1';
+    if !has_hrp {
+        let separator = s.rfind('1').ok_or(bech32::Error::Bech32Malformed(
+            Bech32Error::MissingSeparator,
+        ))?;
+        return Err(bech32::Error::HrpInvalid {
+            expected: hrp,
+            actual: s[..separator].to_lowercase(),
+        });
+    }
+    let hrp_case = &s.as_bytes()[..hrp.len()];
+    let data = &s.as_bytes()[hrp.len() + 1..];
+    if data.len() < BECH32_CHECKSUM_LEN {
+        return Err(bech32::Error::Bech32Malformed(Bech32Error::InvalidLength));
+    }
+    let payload_len = data.len() - BECH32_CHECKSUM_LEN;
+    if payload_len * 5 % 8 >= 5 {
+        return Err(bech32::Error::Bech32Malformed(Bech32Error::InvalidPadding));
+    }
+
+    let mut checksum = 1;
+    for c in hrp.as_bytes() {
+        checksum = bech32_polymod_step(checksum, c >> 5);
+    }
+    checksum = bech32_polymod_step(checksum, 0);
+    for c in hrp.as_bytes() {
+        checksum = bech32_polymod_step(checksum, c & 0x1f);
+    }
+
+    let mut lower = hrp_case
+        .iter()
+        .fold(0, |acc, c| acc | ct_in_range(*c, b'a', b'z'));
+    let mut upper = hrp_case
+        .iter()
+        .fold(0, |acc, c| acc | ct_in_range(*c, b'A', b'Z'));
+    let mut bytes = Zeroizing::new(Vec::with_capacity(payload_len * 5 / 8));
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    let mut invalid = 0;
+    for (i, c) in data.iter().enumerate() {
+        let (value, valid, is_lower, is_upper) = bech32_digit(*c);
+        invalid |= !valid;
+        lower |= is_lower;
+        upper |= is_upper;
+        checksum = bech32_polymod_step(checksum, value);
+        if i < payload_len {
+            accumulator = ((accumulator << 5) | u32::from(value)) & 0xfff;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((accumulator >> bits) as u8);
+            }
+        }
+    }
+    let padding = accumulator & ((1 << bits) - 1);
+    accumulator.zeroize();
+    if invalid != 0 || checksum != 1 {
+        return Err(bech32::Error::Bech32Malformed(Bech32Error::InvalidChecksum));
+    }
+    if lower & upper != 0 {
+        return Err(bech32::Error::Bech32Malformed(Bech32Error::MixedCase));
+    }
+    if padding != 0 {
+        return Err(bech32::Error::Bech32Malformed(Bech32Error::InvalidPadding));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ed25519, SecretKey, SecretKeyError, SecretKeyFromStrError};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[quickcheck]
+    fn hex_decodes_as_the_hex_crate(bytes: Vec<u8>) -> bool {
+        let encoded = hex::encode(&bytes);
+        *from_hex(&encoded).unwrap() == bytes
+            && *from_hex(&encoded.to_uppercase()).unwrap() == bytes
+    }
+
+    #[quickcheck]
+    fn bech32_decodes_as_the_bech32_crate(bytes: Vec<u8>) -> bool {
+        let encoded = bech32::to_bech32_from_bytes::<SecretKey<Ed25519>>(&bytes);
+        *from_bech32::<SecretKey<Ed25519>>(&encoded).unwrap() == bytes
+            && *from_bech32::<SecretKey<Ed25519>>(&encoded.to_uppercase()).unwrap() == bytes
+    }
+
+    #[test]
+    fn invalid_hex() {
+        assert_eq!(from_hex("abc"), Err(DecodingError::LengthInvalid));
+        assert_eq!(from_hex("0g"), Err(DecodingError::CharacterInvalid));
+        assert_eq!(from_hex("/0"), Err(DecodingError::CharacterInvalid));
+    }
+
+    #[test]
+    fn base64_test_vectors() {
+        let vectors: &[(&str, &[u8])] = &[
+            ("", b""),
+            ("Zg==", b"f"),
+            ("Zm8=", b"fo"),
+            ("Zm9v", b"foo"),
+            ("Zm9vYg==", b"foob"),
+            ("Zm9vYmE=", b"fooba"),
+            ("Zm9vYmFy", b"foobar"),
+            ("Zm9vYmE", b"fooba"),
+            ("+/+/", &[0xfb, 0xff, 0xbf]),
+        ];
+        for (encoded, decoded) in vectors {
+            assert_eq!(&*from_base64(encoded).unwrap(), decoded, "{}", encoded);
+        }
+        assert_eq!(from_base64("Zm9vY"), Err(DecodingError::LengthInvalid));
+        assert_eq!(
+            from_base64("Zm9=Yg=="),
+            Err(DecodingError::CharacterInvalid)
+        );
+        assert_eq!(from_base64("Zh=="), Err(DecodingError::PaddingInvalid));
+        assert_eq!(from_base64("Zm9vYg="), Err(DecodingError::PaddingInvalid));
+    }
+
+    #[test]
+    fn invalid_bech32() {
+        let encoded = bech32::to_bech32_from_bytes::<SecretKey<Ed25519>>(&[7; 32]);
+        let mut corrupted = encoded.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+        let corrupted = String::from_utf8(corrupted).unwrap();
+        let mixed_case = format!("{}{}", &encoded[..20], encoded[20..].to_uppercase());
+
+        assert!(matches!(
+            from_bech32::<SecretKey<Ed25519>>(&corrupted),
+            Err(bech32::Error::Bech32Malformed(Bech32Error::InvalidChecksum))
+        ));
+        assert!(matches!(
+            from_bech32::<SecretKey<Ed25519>>(&mixed_case),
+            Err(bech32::Error::Bech32Malformed(Bech32Error::MixedCase))
+        ));
+        assert!(matches!(
+            from_bech32::<SecretKey<Ed25519>>(&encoded.replacen("ed25519_sk", "foo", 1)),
+            Err(bech32::Error::HrpInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn secret_key_from_str() {
+        let key = SecretKey::<Ed25519>::generate(ChaChaRng::from_seed([0; 32]));
+        let encoded = hex::encode(key.clone().leak_secret());
+        let decoded: SecretKey<Ed25519> = encoded.parse().unwrap();
+        assert_eq!(decoded.to_bech32_str(), key.to_bech32_str());
+        let decoded = SecretKey::<Ed25519>::try_from_bech32_str(&key.to_bech32_str()).unwrap();
+        assert_eq!(decoded.to_bech32_str(), key.to_bech32_str());
+
+        assert!(matches!(
+            encoded[2..].parse::<SecretKey<Ed25519>>(),
+            Err(SecretKeyFromStrError::KeyInvalid(
+                SecretKeyError::SizeInvalid
+            ))
+        ));
+        assert!(matches!(
+            encoded[1..].parse::<SecretKey<Ed25519>>(),
+            Err(SecretKeyFromStrError::HexMalformed(
+                DecodingError::LengthInvalid
+            ))
+        ));
+    }
+}
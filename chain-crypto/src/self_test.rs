@@ -0,0 +1,534 @@
+//! Runtime self-tests of the cryptographic algorithms.
+//!
+//! Some deployments require the cryptographic primitives to be checked when
+//! the node starts, before any key is used. [`self_test`] runs for each
+//! algorithm:
+//!
+//! * known-answer tests: fixed inputs whose outputs (digests, public keys,
+//!   signatures, VRF outputs) must match the values recorded here;
+//! * randomized tests: fresh keys and messages which must round-trip
+//!   through signing and verification, while tampered messages must be
+//!   rejected.
+//!
+//! The results are reported per algorithm, so that the caller can decide
+//! which failures are fatal.
+
+use crate::digest::{Context, Digest};
+use crate::evolving::EvolvingStatus;
+use crate::hash::Blake2b256;
+use crate::kes::KeyEvolvingSignatureAlgorithm;
+use crate::key::{PublicKey, SecretKey};
+use crate::sign::{Signature, SigningAlgorithm, Verification, VerificationAlgorithm};
+use crate::vrf::{vrf_evaluate_and_prove, vrf_verified_get_output, vrf_verify, VRFVerification};
+use crate::{Curve25519_2HashDH, Ed25519, SumEd25519_12};
+use rand_core::{impls, CryptoRng, Error as RngError, OsRng, RngCore};
+use sha2::Digest as _;
+use std::fmt;
+
+/// Number of fresh keys and messages tried by the randomized tests
+const RANDOMIZED_ROUNDS: usize = 4;
+
+/// The algorithms covered by the self-tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Blake2b256,
+    Sha256,
+    Sha512,
+    Ed25519,
+    SumEd25519_12,
+    Curve25519_2HashDH,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Algorithm::Blake2b256 => "blake2b256",
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha512 => "sha512",
+            Algorithm::Ed25519 => "ed25519",
+            Algorithm::SumEd25519_12 => "sumed25519_12",
+            Algorithm::Curve25519_2HashDH => "curve25519_2hashdh",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The first check an algorithm failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelfTestFailure {
+    /// the output computed from a fixed input does not match the recorded one
+    KnownAnswerMismatch { test: &'static str },
+    /// a valid signature or proof has been rejected
+    ValidRejected { test: &'static str },
+    /// a signature or proof has been accepted for a tampered input
+    InvalidAccepted { test: &'static str },
+    /// two ways of computing the same value disagree
+    Inconsistent { test: &'static str },
+}
+
+impl fmt::Display for SelfTestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelfTestFailure::KnownAnswerMismatch { test } => {
+                write!(f, "known-answer test '{}' mismatch", test)
+            }
+            SelfTestFailure::ValidRejected { test } => {
+                write!(f, "test '{}' rejected a valid input", test)
+            }
+            SelfTestFailure::InvalidAccepted { test } => {
+                write!(f, "test '{}' accepted a tampered input", test)
+            }
+            SelfTestFailure::Inconsistent { test } => {
+                write!(f, "test '{}' computed inconsistent results", test)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SelfTestFailure {}
+
+/// Outcome of the self-tests of one algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlgorithmResult {
+    pub algorithm: Algorithm,
+    pub result: Result<(), SelfTestFailure>,
+}
+
+/// Outcome of [`self_test`], one result per algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestReport {
+    results: Vec<AlgorithmResult>,
+}
+
+impl SelfTestReport {
+    /// true if every algorithm passed its self-tests
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|r| r.result.is_ok())
+    }
+
+    pub fn results(&self) -> &[AlgorithmResult] {
+        &self.results
+    }
+
+    /// the algorithms which failed, with the first check they failed
+    pub fn failures(&self) -> impl Iterator<Item = (Algorithm, &SelfTestFailure)> {
+        self.results
+            .iter()
+            .filter_map(|r| r.result.as_ref().err().map(|e| (r.algorithm, e)))
+    }
+
+    /// the result of the given algorithm
+    pub fn get(&self, algorithm: Algorithm) -> Option<&Result<(), SelfTestFailure>> {
+        self.results
+            .iter()
+            .find(|r| r.algorithm == algorithm)
+            .map(|r| &r.result)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for r in self.results.iter() {
+            match &r.result {
+                Ok(()) => writeln!(f, "{}: ok", r.algorithm)?,
+                Err(e) => writeln!(f, "{}: FAILED, {}", r.algorithm, e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+type AlgorithmTest<R> = fn(&mut R) -> Result<(), SelfTestFailure>;
+
+/// Run the known-answer and randomized self-tests of every algorithm.
+///
+/// The randomized tests draw their inputs from the operating system's
+/// generator; use [`self_test_with_rng`] to provide the generator.
+pub fn self_test() -> SelfTestReport {
+    self_test_with_rng(OsRng)
+}
+
+/// Same as [`self_test`], drawing the inputs of the randomized tests from
+/// `rng`
+pub fn self_test_with_rng<R: RngCore + CryptoRng>(mut rng: R) -> SelfTestReport {
+    let tests: [(Algorithm, AlgorithmTest<R>); 6] = [
+        (Algorithm::Blake2b256, test_blake2b256),
+        (Algorithm::Sha256, test_sha256),
+        (Algorithm::Sha512, test_sha512),
+        (Algorithm::Ed25519, test_ed25519),
+        (Algorithm::SumEd25519_12, test_sumed25519_12),
+        (Algorithm::Curve25519_2HashDH, test_curve25519_2hashdh),
+    ];
+    let results = tests
+        .iter()
+        .map(|(algorithm, test)| AlgorithmResult {
+            algorithm: *algorithm,
+            result: test(&mut rng),
+        })
+        .collect();
+    SelfTestReport { results }
+}
+
+fn check(condition: bool, failure: SelfTestFailure) -> Result<(), SelfTestFailure> {
+    if condition {
+        Ok(())
+    } else {
+        Err(failure)
+    }
+}
+
+fn known_answer(
+    computed: &[u8],
+    expected: &str,
+    test: &'static str,
+) -> Result<(), SelfTestFailure> {
+    check(
+        hex::encode(computed) == expected,
+        SelfTestFailure::KnownAnswerMismatch { test },
+    )
+}
+
+fn random_message<R: RngCore>(rng: &mut R) -> Vec<u8> {
+    let mut message = vec![0; 1 + (rng.next_u32() % 256) as usize];
+    rng.fill_bytes(&mut message);
+    message
+}
+
+/// The same message with one bit flipped
+fn tampered(message: &[u8]) -> Vec<u8> {
+    let mut message = message.to_vec();
+    let last = message.len() - 1;
+    message[last] ^= 0x01;
+    message
+}
+
+/// Check a digest computed in one go against the same digest computed from
+/// chunks of the input
+fn chunked_digest<R: RngCore>(
+    rng: &mut R,
+    one_shot: impl Fn(&[u8]) -> Vec<u8>,
+    chunked: impl Fn(&[&[u8]]) -> Vec<u8>,
+    test: &'static str,
+) -> Result<(), SelfTestFailure> {
+    for _ in 0..RANDOMIZED_ROUNDS {
+        let message = random_message(rng);
+        let (left, right) = message.split_at(rng.next_u32() as usize % message.len());
+        check(
+            one_shot(&message) == chunked(&[left, right]),
+            SelfTestFailure::Inconsistent { test },
+        )?;
+    }
+    Ok(())
+}
+
+fn test_blake2b256<R: RngCore>(rng: &mut R) -> Result<(), SelfTestFailure> {
+    known_answer(
+        Blake2b256::new(b"abc").as_ref(),
+        "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+        "blake2b256 abc",
+    )?;
+    chunked_digest(
+        rng,
+        |data| Blake2b256::new(data).as_ref().to_vec(),
+        |chunks| {
+            let mut context = Context::<Blake2b256>::new();
+            for chunk in chunks {
+                context.append_data(chunk);
+            }
+            let digest: Digest<Blake2b256> = context.finalize();
+            digest.as_ref().to_vec()
+        },
+        "blake2b256 chunked",
+    )
+}
+
+fn test_sha256<R: RngCore>(rng: &mut R) -> Result<(), SelfTestFailure> {
+    known_answer(
+        &sha2::Sha256::digest(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        "sha256 abc",
+    )?;
+    chunked_digest(
+        rng,
+        |data| sha2::Sha256::digest(data).to_vec(),
+        |chunks| {
+            let mut context = sha2::Sha256::new();
+            for chunk in chunks {
+                context.update(chunk);
+            }
+            context.finalize().to_vec()
+        },
+        "sha256 chunked",
+    )
+}
+
+fn test_sha512<R: RngCore>(rng: &mut R) -> Result<(), SelfTestFailure> {
+    known_answer(
+        &sha2::Sha512::digest(b"abc"),
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+         2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        "sha512 abc",
+    )?;
+    chunked_digest(
+        rng,
+        |data| sha2::Sha512::digest(data).to_vec(),
+        |chunks| {
+            let mut context = sha2::Sha512::new();
+            for chunk in chunks {
+                context.update(chunk);
+            }
+            context.finalize().to_vec()
+        },
+        "sha512 chunked",
+    )
+}
+
+/// Sign a message with the key, check that the signature verifies and that
+/// it does not verify a tampered message
+fn sign_and_verify<A>(
+    key: &SecretKey<A>,
+    message: &[u8],
+    test: &'static str,
+) -> Result<Signature<[u8], A::PubAlg>, SelfTestFailure>
+where
+    A: SigningAlgorithm,
+    A::PubAlg: VerificationAlgorithm,
+{
+    let public = key.to_public();
+    let signature = key.sign_slice(message);
+    check(
+        signature.verify_slice(&public, message) == Verification::Success,
+        SelfTestFailure::ValidRejected { test },
+    )?;
+    check(
+        signature.verify_slice(&public, &tampered(message)) == Verification::Failed,
+        SelfTestFailure::InvalidAccepted { test },
+    )?;
+    Ok(signature)
+}
+
+fn test_ed25519<R: RngCore + CryptoRng>(rng: &mut R) -> Result<(), SelfTestFailure> {
+    // RFC 8032, section 7.1, test 3
+    const TEST: &str = "ed25519 rfc8032";
+    let key = SecretKey::<Ed25519>::from_binary(
+        &hex::decode("c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7").unwrap(),
+    )
+    .map_err(|_| SelfTestFailure::KnownAnswerMismatch { test: TEST })?;
+    known_answer(
+        key.to_public().as_ref(),
+        "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+        TEST,
+    )?;
+    let signature = sign_and_verify(&key, &[0xaf, 0x82], TEST)?;
+    known_answer(
+        signature.as_ref(),
+        "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+         18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        TEST,
+    )?;
+
+    for _ in 0..RANDOMIZED_ROUNDS {
+        let key = SecretKey::<Ed25519>::generate(&mut *rng);
+        let message = random_message(rng);
+        let signature = sign_and_verify(&key, &message, "ed25519 random")?;
+        let decoded =
+            Signature::<[u8], Ed25519>::from_binary(signature.as_ref()).map_err(|_| {
+                SelfTestFailure::Inconsistent {
+                    test: "ed25519 signature encoding",
+                }
+            })?;
+        check(
+            decoded.verify_slice(&key.to_public(), &message) == Verification::Success,
+            SelfTestFailure::Inconsistent {
+                test: "ed25519 signature encoding",
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Sign at the current period and at the next one, checking the period
+/// carried by the signatures
+fn sign_and_evolve(
+    key: &mut SecretKey<SumEd25519_12>,
+    message: &[u8],
+    test: &'static str,
+) -> Result<[Signature<[u8], SumEd25519_12>; 2], SelfTestFailure> {
+    let period = SecretKey::get_period(key);
+    let before = sign_and_verify(key, message, test)?;
+    check(
+        SecretKey::evolve(key) == EvolvingStatus::Success,
+        SelfTestFailure::Inconsistent { test },
+    )?;
+    let after = sign_and_verify(key, message, test)?;
+    let signature_period = |s: &Signature<[u8], SumEd25519_12>| -> Result<u32, SelfTestFailure> {
+        let sig = <SumEd25519_12 as VerificationAlgorithm>::signature_from_bytes(s.as_ref())
+            .map_err(|_| SelfTestFailure::Inconsistent { test })?;
+        Ok(<SumEd25519_12 as KeyEvolvingSignatureAlgorithm>::get_period(&sig))
+    };
+    check(
+        signature_period(&before)? == period
+            && signature_period(&after)? == period + 1
+            && SecretKey::get_period(key) == period + 1,
+        SelfTestFailure::Inconsistent { test },
+    )?;
+    Ok([before, after])
+}
+
+fn test_sumed25519_12<R: RngCore + CryptoRng>(rng: &mut R) -> Result<(), SelfTestFailure> {
+    const TEST: &str = "sumed25519_12 fixed seed";
+    let mut key = SecretKey::<SumEd25519_12>::generate(FixedBytes(0x5e));
+    known_answer(key.to_public().as_ref(), KES_PUBLIC, TEST)?;
+    let [before, after] = sign_and_evolve(&mut key, b"abc", TEST)?;
+    known_answer(
+        Blake2b256::new(before.as_ref()).as_ref(),
+        KES_SIGNATURE_0,
+        TEST,
+    )?;
+    known_answer(
+        Blake2b256::new(after.as_ref()).as_ref(),
+        KES_SIGNATURE_1,
+        TEST,
+    )?;
+
+    // the key generation is costly, evolve a single fresh key instead
+    let mut key = SecretKey::<SumEd25519_12>::generate(&mut *rng);
+    for _ in 0..RANDOMIZED_ROUNDS / 2 {
+        let message = random_message(rng);
+        sign_and_evolve(&mut key, &message, "sumed25519_12 random")?;
+    }
+    Ok(())
+}
+
+// public key generated from the seed 0x5e..5e, and blake2b256 digests of the
+// signatures of "abc" at periods 0 and 1 (the signatures themselves are 484
+// bytes long)
+const KES_PUBLIC: &str = "5ddb193febb33e2e0e955bd9ddd2adfbb3e02063fe77fdc907c13193b5a530c0";
+const KES_SIGNATURE_0: &str = "abf5faa7c11ead5b0366becfec0321a258457a467d48570f65e81cea2ae1bcb4";
+const KES_SIGNATURE_1: &str = "df688c7616fdfa8c137f5800fac210b6fe8ca091ae3145e9084b011bcbde1c75";
+
+fn test_curve25519_2hashdh<R: RngCore + CryptoRng>(rng: &mut R) -> Result<(), SelfTestFailure> {
+    const TEST: &str = "curve25519_2hashdh fixed key";
+    let mut secret = [0; 32];
+    secret[0] = 0x2a;
+    let key = SecretKey::<Curve25519_2HashDH>::from_binary(&secret)
+        .map_err(|_| SelfTestFailure::KnownAnswerMismatch { test: TEST })?;
+    known_answer(key.to_public().as_ref(), VRF_PUBLIC, TEST)?;
+    let output = evaluate_and_verify(&key, b"abc", &mut *rng, TEST)?;
+    known_answer(output.as_ref(), VRF_OUTPUT, TEST)?;
+
+    for _ in 0..RANDOMIZED_ROUNDS {
+        const TEST: &str = "curve25519_2hashdh random";
+        let key = SecretKey::<Curve25519_2HashDH>::generate(&mut *rng);
+        let input = random_message(rng);
+        let output = evaluate_and_verify(&key, &input, &mut *rng, TEST)?;
+        // the proof is randomized, the output is not
+        check(
+            evaluate_and_verify(&key, &input, &mut *rng, TEST)? == output,
+            SelfTestFailure::Inconsistent { test: TEST },
+        )?;
+    }
+    Ok(())
+}
+
+// public key of the secret scalar 42, and output of the evaluation of "abc"
+const VRF_PUBLIC: &str = "e00af9c74d9edb8ebcc160ceec97d531cbd6e2956f9e9162b8e9eda260e82e43";
+const VRF_OUTPUT: &str = "7d789d7351a7691209e2244948f6afef46645445f24a884f1fa944d62d8fb731";
+
+/// Evaluate the VRF, check the proof against the input and a tampered
+/// input, and return the output
+fn evaluate_and_verify<R: RngCore + CryptoRng>(
+    key: &SecretKey<Curve25519_2HashDH>,
+    input: &[u8],
+    rng: R,
+    test: &'static str,
+) -> Result<Blake2b256, SelfTestFailure> {
+    let public: PublicKey<Curve25519_2HashDH> = key.to_public();
+    let proven = vrf_evaluate_and_prove(key, input, rng);
+    check(
+        vrf_verify(&public, input, &proven) == VRFVerification::Success,
+        SelfTestFailure::ValidRejected { test },
+    )?;
+    check(
+        vrf_verify(&public, &tampered(input), &proven) == VRFVerification::Failed,
+        SelfTestFailure::InvalidAccepted { test },
+    )?;
+    Ok(vrf_verified_get_output::<Curve25519_2HashDH>(&proven).to_output(input, b""))
+}
+
+/// Generator returning the same byte over and over, to derive the keys of
+/// the known-answer tests of the algorithms only generating keys from a
+/// random source
+struct FixedBytes(u8);
+
+impl RngCore for FixedBytes {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.iter_mut().for_each(|b| *b = self.0)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RngError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for FixedBytes {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestCryptoGen;
+
+    #[test]
+    fn self_test_passes() {
+        let report = self_test();
+        assert!(report.is_success(), "{}", report);
+        assert_eq!(report.results().len(), 6);
+        assert_eq!(report.failures().count(), 0);
+    }
+
+    #[test]
+    fn self_test_with_rng_passes() {
+        let report = self_test_with_rng(TestCryptoGen(42).get_rng(0));
+        assert!(report.is_success(), "{}", report);
+        assert_eq!(report.get(Algorithm::SumEd25519_12), Some(&Ok(())));
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let failure = known_answer(b"abc", "616263ff", "abc").unwrap_err();
+        assert_eq!(
+            failure,
+            SelfTestFailure::KnownAnswerMismatch { test: "abc" }
+        );
+        let report = SelfTestReport {
+            results: vec![
+                AlgorithmResult {
+                    algorithm: Algorithm::Sha256,
+                    result: Ok(()),
+                },
+                AlgorithmResult {
+                    algorithm: Algorithm::Ed25519,
+                    result: Err(failure.clone()),
+                },
+            ],
+        };
+        assert!(!report.is_success());
+        assert_eq!(
+            report.failures().collect::<Vec<_>>(),
+            vec![(Algorithm::Ed25519, &failure)]
+        );
+        assert_eq!(
+            report.to_string(),
+            "sha256: ok\ned25519: FAILED, known-answer test 'abc' mismatch\n"
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{
     bech32::{self, Bech32},
     hex, key,
+    validation::ValidationPolicy,
 };
 use hex::FromHexError;
+use sha2::{Digest as _, Sha512};
 use std::{fmt, marker::PhantomData, str::FromStr};
 use typed_bytes::{ByteArray, ByteSlice};
 
@@ -44,6 +46,16 @@ pub trait VerificationAlgorithm: key::AsymmetricPublicKey {
         -> Verification;
 
     fn signature_from_bytes(data: &[u8]) -> Result<Self::Signature, SignatureError>;
+
+    /// Check the encoding of a decoded signature against the policy, the
+    /// signatures of the algorithms without malleable encodings are always
+    /// valid
+    fn signature_validate(
+        _signature: &Self::Signature,
+        _policy: ValidationPolicy,
+    ) -> Result<(), SignatureError> {
+        Ok(())
+    }
 }
 
 pub trait SigningAlgorithm: key::AsymmetricKey
@@ -54,7 +66,7 @@ where
 }
 
 pub struct Signature<T: ?Sized, A: VerificationAlgorithm> {
-    signdata: A::Signature,
+    pub(crate) signdata: A::Signature,
     phantom: PhantomData<T>,
 }
 
@@ -116,6 +128,21 @@ impl<A: VerificationAlgorithm, T: ?Sized> Signature<T, A> {
             phantom: PhantomData,
         })
     }
+
+    /// Decode the signature and check its encoding against the policy
+    pub fn from_binary_with_policy(
+        sig: &[u8],
+        policy: ValidationPolicy,
+    ) -> Result<Self, SignatureError> {
+        let signature = Self::from_binary(sig)?;
+        signature.validate(policy)?;
+        Ok(signature)
+    }
+
+    pub fn validate(&self, policy: ValidationPolicy) -> Result<(), SignatureError> {
+        A::signature_validate(&self.signdata, policy)
+    }
+
     pub fn coerce<U: ?Sized>(self) -> Signature<U, A> {
         Signature {
             signdata: self.signdata,
@@ -180,6 +207,214 @@ where
     }
 }
 
+/// A signing context, the domain a signature is bound to.
+///
+/// A signature made in a context only verifies in the same context, so that
+/// the signatures of one protocol, or of one part of a protocol, cannot be
+/// reused in another one.
+pub trait SigningContext {
+    /// The context string, between 1 and 255 bytes
+    const CONTEXT: &'static [u8];
+}
+
+/// Signing algorithms supporting the context-separated and the prehashed
+/// variants of the algorithm, like Ed25519ctx and Ed25519ph from RFC 8032.
+pub trait ContextVerificationAlgorithm: VerificationAlgorithm {
+    fn verify_bytes_with_context(
+        pubkey: &Self::Public,
+        signature: &Self::Signature,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Verification;
+
+    fn verify_prehashed_bytes(
+        pubkey: &Self::Public,
+        signature: &Self::Signature,
+        context: &[u8],
+        prehash: &[u8; 64],
+    ) -> Verification;
+}
+
+pub trait ContextSigningAlgorithm: SigningAlgorithm
+where
+    Self::PubAlg: ContextVerificationAlgorithm,
+{
+    fn sign_with_context(
+        key: &Self::Secret,
+        context: &[u8],
+        msg: &[u8],
+    ) -> <Self::PubAlg as VerificationAlgorithm>::Signature;
+
+    fn sign_prehashed(
+        key: &Self::Secret,
+        context: &[u8],
+        prehash: &[u8; 64],
+    ) -> <Self::PubAlg as VerificationAlgorithm>::Signature;
+}
+
+/// The SHA-512 digest of a message, signed by the prehashed signatures
+fn prehash(msg: &[u8]) -> [u8; 64] {
+    let mut prehash = [0; 64];
+    prehash.copy_from_slice(&Sha512::digest(msg));
+    prehash
+}
+
+/// Signature of an object of type `T` in the context `C`
+pub struct ContextSignature<C: ?Sized, T: ?Sized, A: VerificationAlgorithm> {
+    signdata: A::Signature,
+    context: PhantomData<C>,
+    phantom: PhantomData<T>,
+}
+
+/// Signature of the digest of an object of type `T` in the context `C`
+pub struct PrehashedSignature<C: ?Sized, T: ?Sized, A: VerificationAlgorithm> {
+    signdata: A::Signature,
+    context: PhantomData<C>,
+    phantom: PhantomData<T>,
+}
+
+macro_rules! context_signature_common {
+    ($name:ident) => {
+        impl<C: ?Sized, T: ?Sized, A: VerificationAlgorithm> $name<C, T, A> {
+            pub fn from_binary(sig: &[u8]) -> Result<Self, SignatureError> {
+                Ok($name {
+                    signdata: A::signature_from_bytes(sig)?,
+                    context: PhantomData,
+                    phantom: PhantomData,
+                })
+            }
+
+            /// Decode the signature and check its encoding against the policy
+            pub fn from_binary_with_policy(
+                sig: &[u8],
+                policy: ValidationPolicy,
+            ) -> Result<Self, SignatureError> {
+                let signature = Self::from_binary(sig)?;
+                signature.validate(policy)?;
+                Ok(signature)
+            }
+
+            pub fn validate(&self, policy: ValidationPolicy) -> Result<(), SignatureError> {
+                A::signature_validate(&self.signdata, policy)
+            }
+
+            pub fn coerce<U: ?Sized>(self) -> $name<C, U, A> {
+                $name {
+                    signdata: self.signdata,
+                    context: PhantomData,
+                    phantom: PhantomData,
+                }
+            }
+        }
+
+        impl<C: ?Sized, T: ?Sized, A: VerificationAlgorithm> fmt::Debug for $name<C, T, A> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(self.signdata.as_ref()))
+            }
+        }
+
+        impl<C: ?Sized, T: ?Sized, A: VerificationAlgorithm> Clone for $name<C, T, A> {
+            fn clone(&self) -> Self {
+                $name {
+                    signdata: self.signdata.clone(),
+                    context: PhantomData,
+                    phantom: PhantomData,
+                }
+            }
+        }
+
+        impl<C: ?Sized, T: ?Sized, A: VerificationAlgorithm> AsRef<[u8]> for $name<C, T, A> {
+            fn as_ref(&self) -> &[u8] {
+                self.signdata.as_ref()
+            }
+        }
+    };
+}
+
+context_signature_common!(ContextSignature);
+context_signature_common!(PrehashedSignature);
+
+impl<C: ?Sized + SigningContext, T: ?Sized, A: ContextVerificationAlgorithm>
+    ContextSignature<C, T, A>
+{
+    #[must_use]
+    pub fn verify_slice(&self, publickey: &key::PublicKey<A>, slice: &[u8]) -> Verification {
+        A::verify_bytes_with_context(&publickey.0, &self.signdata, C::CONTEXT, slice)
+    }
+}
+
+impl<C: ?Sized + SigningContext, T: ?Sized + AsRef<[u8]>, A: ContextVerificationAlgorithm>
+    ContextSignature<C, T, A>
+{
+    #[must_use]
+    pub fn verify(&self, publickey: &key::PublicKey<A>, object: &T) -> Verification {
+        self.verify_slice(publickey, object.as_ref())
+    }
+}
+
+impl<C: ?Sized + SigningContext, T: ?Sized, A: ContextVerificationAlgorithm>
+    PrehashedSignature<C, T, A>
+{
+    #[must_use]
+    pub fn verify_prehash(
+        &self,
+        publickey: &key::PublicKey<A>,
+        prehash: &[u8; 64],
+    ) -> Verification {
+        A::verify_prehashed_bytes(&publickey.0, &self.signdata, C::CONTEXT, prehash)
+    }
+}
+
+impl<C: ?Sized + SigningContext, T: ?Sized + AsRef<[u8]>, A: ContextVerificationAlgorithm>
+    PrehashedSignature<C, T, A>
+{
+    #[must_use]
+    pub fn verify(&self, publickey: &key::PublicKey<A>, object: &T) -> Verification {
+        self.verify_prehash(publickey, &prehash(object.as_ref()))
+    }
+}
+
+impl<A: ContextSigningAlgorithm> key::SecretKey<A>
+where
+    <A as key::AsymmetricKey>::PubAlg: ContextVerificationAlgorithm,
+{
+    pub fn sign_with_context<C: ?Sized + SigningContext, T: ?Sized + AsRef<[u8]>>(
+        &self,
+        object: &T,
+    ) -> ContextSignature<C, T, A::PubAlg> {
+        self.sign_slice_with_context(object.as_ref())
+    }
+
+    pub fn sign_slice_with_context<C: ?Sized + SigningContext, T: ?Sized>(
+        &self,
+        slice: &[u8],
+    ) -> ContextSignature<C, T, A::PubAlg> {
+        ContextSignature {
+            signdata: A::sign_with_context(&self.0, C::CONTEXT, slice),
+            context: PhantomData,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn sign_prehashed<C: ?Sized + SigningContext, T: ?Sized + AsRef<[u8]>>(
+        &self,
+        object: &T,
+    ) -> PrehashedSignature<C, T, A::PubAlg> {
+        self.sign_prehash(&prehash(object.as_ref()))
+    }
+
+    pub fn sign_prehash<C: ?Sized + SigningContext, T: ?Sized>(
+        &self,
+        prehash: &[u8; 64],
+    ) -> PrehashedSignature<C, T, A::PubAlg> {
+        PrehashedSignature {
+            signdata: A::sign_prehashed(&self.0, C::CONTEXT, prehash),
+            context: PhantomData,
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<T, A: VerificationAlgorithm> Clone for Signature<T, A> {
     fn clone(&self) -> Self {
         Signature {
@@ -0,0 +1,88 @@
+//! Validation policies of the encodings of the public keys and signatures.
+//!
+//! Decoding a public key or a signature only checks its size by default, the
+//! historical behaviour which the ledgers already depend on. With Ed25519,
+//! this leaves room for malleability: the same signature has several
+//! encodings (the scalar `s` is accepted beyond the order of the group), and
+//! the points of small order let a key verify signatures without knowing a
+//! secret key.
+//!
+//! A [`ValidationPolicy`] selects the checks done on top of the size. The
+//! policies are versioned, so that a chain can record the version it moves
+//! to and validate the older blocks with the policy they were produced with:
+//!
+//! * version 0, [`ValidationPolicy::Legacy`]: the size only;
+//! * version 1, [`ValidationPolicy::Strict`]: the points are canonically
+//!   encoded and not of small order, and the scalars are canonically encoded.
+//!
+//! The algorithms without malleable encodings validate under every policy.
+
+use std::fmt;
+
+/// The checks done when decoding a public key or a signature, see the
+/// [module documentation](self)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ValidationPolicy {
+    /// Check the size of the encodings only
+    Legacy,
+    /// Reject the non canonical encodings and the points of small order
+    Strict,
+}
+
+impl ValidationPolicy {
+    /// The most recent version of the validation policy
+    pub const LATEST: Self = ValidationPolicy::Strict;
+
+    pub fn version(self) -> u8 {
+        match self {
+            ValidationPolicy::Legacy => 0,
+            ValidationPolicy::Strict => 1,
+        }
+    }
+
+    pub fn from_version(version: u8) -> Option<Self> {
+        match version {
+            0 => Some(ValidationPolicy::Legacy),
+            1 => Some(ValidationPolicy::Strict),
+            _ => None,
+        }
+    }
+
+    pub fn is_strict(self) -> bool {
+        self >= ValidationPolicy::Strict
+    }
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        ValidationPolicy::Legacy
+    }
+}
+
+impl fmt::Display for ValidationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationPolicy::Legacy => "legacy",
+            ValidationPolicy::Strict => "strict",
+        }
+        .fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_round_trip() {
+        for policy in &[ValidationPolicy::Legacy, ValidationPolicy::Strict] {
+            assert_eq!(
+                ValidationPolicy::from_version(policy.version()),
+                Some(*policy)
+            );
+        }
+        assert_eq!(ValidationPolicy::from_version(2), None);
+        assert_eq!(ValidationPolicy::default(), ValidationPolicy::Legacy);
+        assert!(ValidationPolicy::LATEST.is_strict());
+    }
+}
@@ -5,7 +5,7 @@ use chain_core::{
     mempack::{ReadBuf, ReadError, Readable},
     property,
 };
-use chain_crypto::{Ed25519, PublicKey, Signature};
+use chain_crypto::{AnyPublicKey, Ed25519, PublicKey, Signature};
 
 pub use account::{DelegationRatio, DelegationType, LedgerError, SpendingCounter};
 
@@ -29,6 +29,14 @@ impl From<Identifier> for PublicKey<AccountAlg> {
     }
 }
 
+/// The accounts are identified by ed25519 keys, so their binding signatures
+/// are only verified with [`AnyPublicKey::Ed25519`]
+impl From<Identifier> for AnyPublicKey {
+    fn from(i: Identifier) -> Self {
+        AnyPublicKey::Ed25519(i.0)
+    }
+}
+
 impl AsRef<PublicKey<AccountAlg>> for Identifier {
     fn as_ref(&self) -> &PublicKey<AccountAlg> {
         &self.0
@@ -1,5 +1,5 @@
 use crate::certificate::PoolId;
-use crate::date::Epoch;
+use crate::date::{BlockDate, Epoch};
 use crate::value::*;
 use imhamt::HamtIter;
 
@@ -75,12 +75,22 @@ impl DelegationRatio {
     }
 }
 
+/// A delegation that only takes effect from its activation epoch,
+/// until then the current delegation of the account is used
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PendingDelegation {
+    pub delegation: DelegationType,
+    pub activation: Epoch,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct AccountState<Extra> {
     pub counter: SpendingCounter,
     pub delegation: DelegationType,
+    pub pending_delegation: Option<PendingDelegation>,
     pub value: Value,
     pub last_rewards: LastRewards,
+    pub last_activity: Option<BlockDate>,
     pub extra: Extra,
 }
 
@@ -90,8 +100,10 @@ impl<Extra> AccountState<Extra> {
         Self {
             counter: SpendingCounter(0),
             delegation: DelegationType::NonDelegated,
+            pending_delegation: None,
             value: v,
             last_rewards: LastRewards::default(),
+            last_activity: None,
             extra: e,
         }
     }
@@ -107,10 +119,28 @@ impl<Extra> AccountState<Extra> {
         &self.delegation
     }
 
+    /// Get the delegation waiting for its activation epoch, if any
+    pub fn pending_delegation(&self) -> Option<&PendingDelegation> {
+        self.pending_delegation.as_ref()
+    }
+
+    /// Get the delegation in effect during the given epoch
+    pub fn delegation_at(&self, epoch: Epoch) -> &DelegationType {
+        match &self.pending_delegation {
+            Some(pending) if pending.activation <= epoch => &pending.delegation,
+            _ => &self.delegation,
+        }
+    }
+
     pub fn value(&self) -> Value {
         self.value
     }
 
+    /// Get the date of the last fragment spending from the account, if any
+    pub fn last_activity(&self) -> Option<BlockDate> {
+        self.last_activity
+    }
+
     // deprecated use value()
     pub fn get_value(&self) -> Value {
         self.value
@@ -175,10 +205,41 @@ impl<Extra: Clone> AccountState<Extra> {
         }
     }
 
-    /// Set delegation
+    /// Set delegation, effective immediately
     pub fn set_delegation(&self, delegation: DelegationType) -> Self {
         let mut st = self.clone();
         st.delegation = delegation;
+        st.pending_delegation = None;
+        st
+    }
+
+    /// Set delegation, effective from the `activation` epoch onward.
+    ///
+    /// `now` is the current epoch: a pending delegation already active is
+    /// made the current one before being replaced, and an activation epoch
+    /// not in the future sets the delegation immediately.
+    pub fn set_delegation_from(
+        &self,
+        delegation: DelegationType,
+        activation: Epoch,
+        now: Epoch,
+    ) -> Self {
+        if activation <= now {
+            return self.set_delegation(delegation);
+        }
+        let mut st = self.clone();
+        st.delegation = self.delegation_at(now).clone();
+        st.pending_delegation = Some(PendingDelegation {
+            delegation,
+            activation,
+        });
+        st
+    }
+
+    /// Set the date of the last fragment spending from the account
+    pub fn set_last_activity(&self, date: BlockDate) -> Self {
+        let mut st = self.clone();
+        st.last_activity = Some(date);
         st
     }
 }
@@ -347,6 +408,7 @@ mod tests {
         ) -> AccountState<()> {
             let result_spending_counter = initial_account_state.counter.0 + subs;
             let mut delegation = initial_account_state.delegation().clone();
+            let mut pending_delegation = initial_account_state.pending_delegation.clone();
             let mut result_value = initial_account_state.get_value();
 
             for operation in operations {
@@ -365,17 +427,21 @@ mod tests {
                     }
                     ArbitraryAccountStateOp::Delegate(new_delegation) => {
                         delegation = DelegationType::Full(new_delegation.clone());
+                        pending_delegation = None;
                     }
                     ArbitraryAccountStateOp::RemoveDelegation => {
                         delegation = DelegationType::NonDelegated;
+                        pending_delegation = None;
                     }
                 }
             }
             AccountState {
                 counter: SpendingCounter(result_spending_counter),
                 delegation,
+                pending_delegation,
                 value: result_value,
                 last_rewards: LastRewards::default(),
+                last_activity: None,
                 extra: (),
             }
         }
@@ -474,6 +540,43 @@ mod tests {
                 && account_state.get_value() != value)
     }
 
+    #[test]
+    pub fn delegation_with_activation_epoch() {
+        let first_pool = StakePoolBuilder::new().build().id();
+        let second_pool = StakePoolBuilder::new().build().id();
+        let account_state = AccountState::new(Value(100), ())
+            .set_delegation(DelegationType::Full(first_pool.clone()))
+            .set_delegation_from(DelegationType::Full(second_pool.clone()), 3, 1);
+
+        assert_eq!(
+            *account_state.delegation_at(2),
+            DelegationType::Full(first_pool.clone())
+        );
+        assert_eq!(
+            *account_state.delegation_at(3),
+            DelegationType::Full(second_pool.clone())
+        );
+
+        // the pending delegation is already active at epoch 4
+        let account_state = account_state.set_delegation_from(DelegationType::NonDelegated, 6, 4);
+        assert_eq!(
+            *account_state.delegation(),
+            DelegationType::Full(second_pool)
+        );
+        assert_eq!(
+            *account_state.delegation_at(6),
+            DelegationType::NonDelegated
+        );
+
+        // an immediate delegation cancels the pending one
+        let account_state = account_state.set_delegation(DelegationType::Full(first_pool.clone()));
+        assert!(account_state.pending_delegation().is_none());
+        assert_eq!(
+            *account_state.delegation_at(6),
+            DelegationType::Full(first_pool)
+        );
+    }
+
     #[test]
     pub fn delegation_ratio_correct() {
         let fake_pool_id = StakePoolBuilder::new().build().id();
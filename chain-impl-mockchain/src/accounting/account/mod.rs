@@ -6,7 +6,10 @@
 
 pub mod account_state;
 pub mod last_rewards;
-use crate::{date::Epoch, value::*};
+use crate::{
+    date::{BlockDate, Epoch},
+    value::*,
+};
 use imhamt::{Hamt, InsertError, UpdateError};
 use std::collections::hash_map::DefaultHasher;
 use std::fmt::{self, Debug};
@@ -95,6 +98,35 @@ impl<ID: Clone + Eq + Hash, Extra: Clone> Ledger<ID, Extra> {
             .map_err(|e| e.into())
     }
 
+    /// Set the delegation of an account in this ledger, effective from the
+    /// `activation` epoch onward
+    pub fn set_delegation_from(
+        &self,
+        identifier: &ID,
+        delegation: &DelegationType,
+        activation: Epoch,
+        now: Epoch,
+    ) -> Result<Self, LedgerError> {
+        self.0
+            .update(identifier, |st| {
+                Ok(Some(st.set_delegation_from(
+                    delegation.clone(),
+                    activation,
+                    now,
+                )))
+            })
+            .map(Ledger)
+            .map_err(|e| e.into())
+    }
+
+    /// Set the date of the last fragment spending from an account
+    pub fn set_last_activity(&self, identifier: &ID, date: BlockDate) -> Result<Self, LedgerError> {
+        self.0
+            .update(identifier, |st| Ok(Some(st.set_last_activity(date))))
+            .map(Ledger)
+            .map_err(|e| e.into())
+    }
+
     /// check if an account already exist
     #[inline]
     pub fn exists(&self, identifier: &ID) -> bool {
@@ -399,7 +431,9 @@ mod tests {
                         reward: value,
                     },
                     delegation: DelegationType::Full(stake_pool_id),
+                    pending_delegation: None,
                     value: value_after_reward,
+                    last_activity: None,
                     extra: (),
                 };
 
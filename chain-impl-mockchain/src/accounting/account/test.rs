@@ -1,5 +1,5 @@
 #[warn(unused_imports)]
-use super::{AccountState, DelegationType, LastRewards, SpendingCounter};
+use super::{AccountState, DelegationType, LastRewards, PendingDelegation, SpendingCounter};
 use quickcheck::{Arbitrary, Gen};
 
 impl Arbitrary for SpendingCounter {
@@ -13,9 +13,20 @@ impl Arbitrary for AccountState<()> {
         AccountState {
             counter: Arbitrary::arbitrary(gen),
             delegation: DelegationType::Full(Arbitrary::arbitrary(gen)),
+            pending_delegation: Arbitrary::arbitrary(gen),
             value: Arbitrary::arbitrary(gen),
             last_rewards: LastRewards::default(),
+            last_activity: Arbitrary::arbitrary(gen),
             extra: (),
         }
     }
 }
+
+impl Arbitrary for PendingDelegation {
+    fn arbitrary<G: Gen>(gen: &mut G) -> Self {
+        PendingDelegation {
+            delegation: DelegationType::Full(Arbitrary::arbitrary(gen)),
+            activation: Arbitrary::arbitrary(gen),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Conversions between the addresses of `chain_addr` and the identifiers
+//! the ledger keeps the funds under.
+//!
+//! [`AddressForms`] gives the addresses a public key can receive funds on,
+//! [`AddressContents::classify`] goes the other way and tells where the
+//! ledger records the funds sent to an address, and which account holds
+//! their stake.
+
+use crate::{account, multisig};
+use chain_addr::{Address, Discrimination, Kind, KindType};
+use chain_crypto::{Ed25519, PublicKey};
+
+/// The addresses of a public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressForms {
+    /// the outputs are spent with the key, their stake is not delegated
+    pub single: Address,
+    /// the outputs are spent with the key and delegated by the account of
+    /// the same key
+    pub group: Address,
+    /// the funds are held by the account of the key
+    pub account: Address,
+}
+
+impl AddressForms {
+    pub fn new(discrimination: Discrimination, public_key: &PublicKey<Ed25519>) -> Self {
+        let identifier = account::Identifier::from(public_key.clone());
+        AddressForms {
+            single: single_address(discrimination, public_key),
+            group: group_address(discrimination, public_key, &identifier),
+            account: account_address(discrimination, &identifier),
+        }
+    }
+}
+
+pub fn single_address(discrimination: Discrimination, spending: &PublicKey<Ed25519>) -> Address {
+    Address(discrimination, Kind::Single(spending.clone()))
+}
+
+/// The address of the outputs spent with `spending` whose stake is
+/// delegated by `account`
+pub fn group_address(
+    discrimination: Discrimination,
+    spending: &PublicKey<Ed25519>,
+    account: &account::Identifier,
+) -> Address {
+    Address(
+        discrimination,
+        Kind::Group(spending.clone(), account.as_ref().clone()),
+    )
+}
+
+pub fn account_address(discrimination: Discrimination, account: &account::Identifier) -> Address {
+    Address(discrimination, Kind::Account(account.as_ref().clone()))
+}
+
+pub fn multisig_address(
+    discrimination: Discrimination,
+    multisig: &multisig::Identifier,
+) -> Address {
+    Address(discrimination, Kind::Multisig(multisig.clone().into()))
+}
+
+/// The content of an address, with the keys of the accounts as the ledger
+/// identifies them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressContents {
+    Single {
+        spending: PublicKey<Ed25519>,
+    },
+    Group {
+        spending: PublicKey<Ed25519>,
+        account: account::Identifier,
+    },
+    Account(account::Identifier),
+    Multisig(multisig::Identifier),
+    Script([u8; 32]),
+}
+
+impl AddressContents {
+    pub fn classify(address: &Address) -> Self {
+        match address.kind() {
+            Kind::Single(spending) => AddressContents::Single {
+                spending: spending.clone(),
+            },
+            Kind::Group(spending, account) => AddressContents::Group {
+                spending: spending.clone(),
+                account: account.clone().into(),
+            },
+            Kind::Account(account) => AddressContents::Account(account.clone().into()),
+            Kind::Multisig(identifier) => {
+                AddressContents::Multisig(multisig::Identifier::from(*identifier))
+            }
+            Kind::Script(identifier) => AddressContents::Script(*identifier),
+        }
+    }
+
+    pub fn kind_type(&self) -> KindType {
+        match self {
+            AddressContents::Single { .. } => KindType::Single,
+            AddressContents::Group { .. } => KindType::Group,
+            AddressContents::Account(_) => KindType::Account,
+            AddressContents::Multisig(_) => KindType::Multisig,
+            AddressContents::Script(_) => KindType::Script,
+        }
+    }
+
+    /// Whether the funds are kept in the UTxO set, or in an account
+    pub fn is_utxo(&self) -> bool {
+        matches!(
+            self,
+            AddressContents::Single { .. } | AddressContents::Group { .. }
+        )
+    }
+
+    /// The account whose stake the funds count towards, if any
+    pub fn stake_account(&self) -> Option<&account::Identifier> {
+        match self {
+            AddressContents::Group { account, .. } | AddressContents::Account(account) => {
+                Some(account)
+            }
+            AddressContents::Single { .. }
+            | AddressContents::Multisig(_)
+            | AddressContents::Script(_) => None,
+        }
+    }
+
+    pub fn to_address(&self, discrimination: Discrimination) -> Address {
+        match self {
+            AddressContents::Single { spending } => single_address(discrimination, spending),
+            AddressContents::Group { spending, account } => {
+                group_address(discrimination, spending, account)
+            }
+            AddressContents::Account(account) => account_address(discrimination, account),
+            AddressContents::Multisig(identifier) => multisig_address(discrimination, identifier),
+            AddressContents::Script(identifier) => {
+                Address(discrimination, Kind::Script(*identifier))
+            }
+        }
+    }
+}
+
+impl From<&Address> for AddressContents {
+    fn from(address: &Address) -> Self {
+        AddressContents::classify(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_crypto::KeyPair;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn classify_to_address_bijection(address: Address) -> bool {
+        AddressContents::classify(&address).to_address(address.discrimination()) == address
+    }
+
+    #[quickcheck]
+    fn address_forms_are_classified_back(key_pair: KeyPair<Ed25519>) -> bool {
+        let public_key = key_pair.public_key().clone();
+        let forms = AddressForms::new(Discrimination::Test, &public_key);
+        let identifier = account::Identifier::from(public_key.clone());
+        AddressContents::classify(&forms.single)
+            == AddressContents::Single {
+                spending: public_key.clone(),
+            }
+            && AddressContents::classify(&forms.group).stake_account() == Some(&identifier)
+            && AddressContents::classify(&forms.account) == AddressContents::Account(identifier)
+            && !AddressContents::classify(&forms.account).is_utxo()
+    }
+}
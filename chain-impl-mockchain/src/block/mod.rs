@@ -24,7 +24,7 @@ pub use builder::builder;
 
 pub use crate::header::{BlockVersion, ChainLength};
 
-pub use crate::date::{BlockDate, BlockDateParseError, Epoch, SlotId};
+pub use crate::date::{BlockDate, BlockDateParseError, Epoch, MissedSlots, SlotId};
 
 /// `Block` is an element of the blockchain it contains multiple
 /// transaction and a reference to the parent block. Alongside
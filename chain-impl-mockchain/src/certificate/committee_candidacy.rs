@@ -0,0 +1,145 @@
+use crate::{
+    certificate::{CertificateSlice, VotePlanId},
+    key::deserialize_public_key,
+    transaction::{Payload, PayloadAuthData, PayloadData, PayloadSlice},
+};
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property,
+};
+use chain_crypto::{
+    algorithms::vrf::ProvenOutputSeed, Curve25519_2HashDH, PublicKey, VerifiableRandomFunction,
+};
+use chain_vote::MemberPublicKey;
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// Candidacy of an account to the committee of a vote plan, drawn by the
+/// ledger when the voting starts, see
+/// [`CommitteeSortition`](crate::vote::CommitteeSortition).
+///
+/// The candidate is the account of the single input of the transaction,
+/// which signs it like a vote cast. The candidate first applies, then
+/// reveals its ticket once the nonce of the draw is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitteeCandidacy {
+    vote_plan: VotePlanId,
+    step: CandidacyStep,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandidacyStep {
+    /// apply to the committee with the VRF key the ticket is evaluated with,
+    /// and with the election key of the candidate if the vote is private
+    Apply {
+        vrf_key: PublicKey<Curve25519_2HashDH>,
+        member_key: Option<MemberPublicKey>,
+    },
+    /// reveal the ticket of the candidate, the VRF evaluation of the nonce
+    /// of the draw with its proof
+    Ticket { proof: ProvenOutputSeed },
+}
+
+impl CommitteeCandidacy {
+    pub fn new(vote_plan: VotePlanId, step: CandidacyStep) -> Self {
+        Self { vote_plan, step }
+    }
+
+    pub fn vote_plan(&self) -> &VotePlanId {
+        &self.vote_plan
+    }
+
+    pub fn step(&self) -> &CandidacyStep {
+        &self.step
+    }
+
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        let bb = bb.bytes(self.vote_plan.as_ref());
+        match &self.step {
+            CandidacyStep::Apply {
+                vrf_key,
+                member_key,
+            } => {
+                let bb = bb.u8(0).bytes(vrf_key.as_ref());
+                match member_key {
+                    None => bb.u8(0),
+                    Some(member_key) => bb.u8(1).bytes(member_key.to_bytes().as_ref()),
+                }
+            }
+            CandidacyStep::Ticket { proof } => bb.u8(1).bytes(&proof.bytes()),
+        }
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+}
+
+/* Auth/Payload ************************************************************* */
+
+impl Payload for CommitteeCandidacy {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = false;
+    type Auth = ();
+
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn payload_auth_data(_: &Self::Auth) -> PayloadAuthData<Self> {
+        PayloadAuthData(Vec::with_capacity(0).into(), std::marker::PhantomData)
+    }
+
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
+/* Ser/De ******************************************************************* */
+
+impl property::Serialize for CommitteeCandidacy {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())?;
+        Ok(())
+    }
+}
+
+impl Readable for CommitteeCandidacy {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let vote_plan = <[u8; 32]>::read(buf)?.into();
+        let step = match buf.get_u8()? {
+            0 => {
+                let vrf_key = deserialize_public_key(buf)?;
+                let member_key = match buf.get_u8()? {
+                    0 => None,
+                    1 => {
+                        let key_buf = buf.get_slice(MemberPublicKey::BYTES_LEN)?;
+                        Some(MemberPublicKey::from_bytes(key_buf).ok_or_else(|| {
+                            ReadError::StructureInvalid("invalid public key format".to_string())
+                        })?)
+                    }
+                    n => return Err(ReadError::UnknownTag(n as u32)),
+                };
+                CandidacyStep::Apply {
+                    vrf_key,
+                    member_key,
+                }
+            }
+            1 => {
+                let proof_buf = buf.get_slice(Curve25519_2HashDH::VERIFIED_RANDOM_SIZE)?;
+                let proof =
+                    ProvenOutputSeed::from_bytes_unverified(proof_buf).ok_or_else(|| {
+                        ReadError::StructureInvalid("invalid VRF proof format".to_string())
+                    })?;
+                CandidacyStep::Ticket { proof }
+            }
+            n => return Err(ReadError::UnknownTag(n as u32)),
+        };
+        Ok(Self { vote_plan, step })
+    }
+}
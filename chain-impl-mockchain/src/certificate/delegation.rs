@@ -1,5 +1,6 @@
 use crate::accounting::account::{DelegationRatio, DelegationType, DELEGATION_RATIO_MAX_DECLS};
 use crate::certificate::CertificateSlice;
+use crate::date::Epoch;
 use crate::transaction::{
     AccountBindingSignature, Payload, PayloadAuthData, PayloadData, PayloadSlice,
     UnspecifiedAccountIdentifier,
@@ -138,6 +139,131 @@ impl Payload for StakeDelegation {
     }
 }
 
+/// An owner stake delegation only taken into account in the stake
+/// distribution from the `activation` epoch onward.
+///
+/// As for the `OwnerStakeDelegation`, the owner is the single input of the
+/// transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferredOwnerStakeDelegation {
+    pub delegation: OwnerStakeDelegation,
+    pub activation: Epoch,
+}
+
+impl DeferredOwnerStakeDelegation {
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        bb.sub(|sb| self.delegation.serialize_in(sb))
+            .u32(self.activation)
+    }
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+
+    pub fn get_delegation_type(&self) -> &DelegationType {
+        self.delegation.get_delegation_type()
+    }
+}
+
+/// A stake delegation of an account only taken into account in the stake
+/// distribution from the `activation` epoch onward.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferredStakeDelegation {
+    pub delegation: StakeDelegation,
+    pub activation: Epoch,
+}
+
+impl DeferredStakeDelegation {
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        bb.sub(|sb| self.delegation.serialize_in(sb))
+            .u32(self.activation)
+    }
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+
+    pub fn get_delegation_type(&self) -> &DelegationType {
+        self.delegation.get_delegation_type()
+    }
+}
+
+impl property::Serialize for DeferredOwnerStakeDelegation {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())
+    }
+}
+
+impl Readable for DeferredOwnerStakeDelegation {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let delegation = OwnerStakeDelegation::read(buf)?;
+        let activation = buf.get_u32()?;
+        Ok(Self {
+            delegation,
+            activation,
+        })
+    }
+}
+
+impl Payload for DeferredOwnerStakeDelegation {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = false;
+    type Auth = ();
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            PhantomData,
+        )
+    }
+    fn payload_auth_data(_: &Self::Auth) -> PayloadAuthData<Self> {
+        PayloadAuthData(Vec::with_capacity(0).into(), PhantomData)
+    }
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
+impl property::Serialize for DeferredStakeDelegation {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())
+    }
+}
+
+impl Readable for DeferredStakeDelegation {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let delegation = StakeDelegation::read(buf)?;
+        let activation = buf.get_u32()?;
+        Ok(Self {
+            delegation,
+            activation,
+        })
+    }
+}
+
+impl Payload for DeferredStakeDelegation {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = true;
+    type Auth = AccountBindingSignature;
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            PhantomData,
+        )
+    }
+
+    fn payload_auth_data(auth: &Self::Auth) -> PayloadAuthData<Self> {
+        let bb = auth.serialize_in(ByteBuilder::new()).finalize_as_vec();
+        PayloadAuthData(bb.into(), PhantomData)
+    }
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
 // Format is either:
 // 0 (byte)
 // 1 (byte)     POOL_ID (32 bytes)
@@ -1,4 +1,6 @@
-use crate::transaction::{SingleAccountBindingSignature, TransactionBindingAuthData};
+use crate::transaction::{
+    BindingSignatureContext, SingleAccountBindingSignature, TransactionBindingAuthData,
+};
 use crate::vote::CommitteeId;
 use crate::{
     certificate::{CertificateSlice, VotePlanId},
@@ -8,7 +10,7 @@ use chain_core::{
     mempack::{ReadBuf, ReadError, Readable},
     property,
 };
-use chain_crypto::Verification;
+use chain_crypto::{ValidationPolicy, Verification};
 use typed_bytes::{ByteArray, ByteBuilder};
 
 #[derive(Debug, Clone)]
@@ -27,9 +29,19 @@ impl EncryptedVoteTallyProof {
         bb.bytes(self.id.as_ref()).bytes(self.signature.as_ref())
     }
 
-    pub fn verify<'a>(&self, verify_data: &TransactionBindingAuthData<'a>) -> Verification {
+    pub fn verify<'a>(
+        &self,
+        verify_data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
+    ) -> Verification {
         let pk = self.id.public_key();
-        self.signature.verify_slice(&pk, verify_data)
+        self.signature.verify_slice(&pk, verify_data, context)
+    }
+
+    /// Check the encoding of the committee member key and of the signature
+    /// against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        self.id.public_key().validate(policy).is_ok() && self.signature.validate(policy).is_ok()
     }
 }
 
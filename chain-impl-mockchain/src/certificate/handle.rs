@@ -0,0 +1,193 @@
+use crate::certificate::CertificateSlice;
+use crate::transaction::{
+    AccountBindingSignature, Payload, PayloadAuthData, PayloadData, PayloadSlice,
+    UnspecifiedAccountIdentifier,
+};
+
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property,
+};
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// A short human readable name for an account.
+///
+/// A handle is a non-empty UTF-8 string of at most [`Handle::MAX_LENGTH`]
+/// bytes, without any whitespace or control character. Handles are compared
+/// byte for byte: no case folding or normalization is applied.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle(String);
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HandleError {
+    #[error("a handle cannot be empty")]
+    Empty,
+    #[error("a handle is at most {max} bytes long, got {length}")]
+    TooLong { length: usize, max: usize },
+    #[error("invalid character {0:?} in handle")]
+    InvalidCharacter(char),
+}
+
+impl Handle {
+    /// maximum length of a handle, in bytes
+    pub const MAX_LENGTH: usize = 32;
+
+    pub fn new<S: Into<String>>(handle: S) -> Result<Self, HandleError> {
+        let handle = handle.into();
+        if handle.is_empty() {
+            return Err(HandleError::Empty);
+        }
+        if handle.len() > Self::MAX_LENGTH {
+            return Err(HandleError::TooLong {
+                length: handle.len(),
+                max: Self::MAX_LENGTH,
+            });
+        }
+        if let Some(c) = handle.chars().find(|c| c.is_whitespace() || c.is_control()) {
+            return Err(HandleError::InvalidCharacter(c));
+        }
+        Ok(Handle(handle))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Handle {
+    type Err = HandleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Handle::new(s)
+    }
+}
+
+impl AsRef<str> for Handle {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Register a handle for an account, or renew the registration of a handle
+/// already owned by the account.
+///
+/// The registration is authenticated by the account, like a
+/// [`StakeDelegation`](crate::certificate::StakeDelegation). Registering a
+/// handle locks a deposit which is refunded to the account once the handle is
+/// registered by another account after it has expired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandleRegistration {
+    pub handle: Handle,
+    pub account_id: UnspecifiedAccountIdentifier,
+}
+
+impl HandleRegistration {
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        serialize_handle(&self.handle, bb).bytes(self.account_id.as_ref())
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+}
+
+// Format: LENGTH (byte) UTF-8 bytes (LENGTH bytes)
+fn serialize_handle<T>(handle: &Handle, bb: ByteBuilder<T>) -> ByteBuilder<T> {
+    let bytes = handle.as_str().as_bytes();
+    bb.u8(bytes.len() as u8).bytes(bytes)
+}
+
+impl Readable for Handle {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let length = buf.get_u8()? as usize;
+        let bytes = buf.get_slice(length)?;
+        let handle =
+            std::str::from_utf8(bytes).map_err(|e| ReadError::StructureInvalid(e.to_string()))?;
+        Handle::new(handle).map_err(|e| ReadError::StructureInvalid(e.to_string()))
+    }
+}
+
+impl property::Serialize for HandleRegistration {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())
+    }
+}
+
+impl Readable for HandleRegistration {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let handle = Handle::read(buf)?;
+        let account_identifier = <[u8; 32]>::read(buf)?;
+        Ok(HandleRegistration {
+            handle,
+            account_id: account_identifier.into(),
+        })
+    }
+}
+
+impl Payload for HandleRegistration {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = true;
+    type Auth = AccountBindingSignature;
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            PhantomData,
+        )
+    }
+
+    fn payload_auth_data(auth: &Self::Auth) -> PayloadAuthData<Self> {
+        let bb = auth.serialize_in(ByteBuilder::new()).finalize_as_vec();
+        PayloadAuthData(bb.into(), PhantomData)
+    }
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_validation() {
+        assert!(Handle::new("alice").is_ok());
+        assert!(Handle::new("ζωή-42").is_ok());
+        assert!(Handle::new("a".repeat(Handle::MAX_LENGTH)).is_ok());
+        assert_eq!(Handle::new(""), Err(HandleError::Empty));
+        assert_eq!(
+            Handle::new("a".repeat(Handle::MAX_LENGTH + 1)),
+            Err(HandleError::TooLong {
+                length: Handle::MAX_LENGTH + 1,
+                max: Handle::MAX_LENGTH
+            })
+        );
+        assert_eq!(
+            Handle::new("alice bob"),
+            Err(HandleError::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            Handle::new("alice\u{0}"),
+            Err(HandleError::InvalidCharacter('\u{0}'))
+        );
+    }
+
+    #[test]
+    fn invalid_handle_is_not_readable() {
+        let bytes = [3, b'a', b' ', b'b'];
+        assert!(Handle::read(&mut ReadBuf::from(&bytes[..])).is_err());
+        let bytes = [2, 0xc3, 0x28];
+        assert!(Handle::read(&mut ReadBuf::from(&bytes[..])).is_err());
+    }
+}
@@ -1,29 +1,42 @@
+mod committee_candidacy;
 mod delegation;
 mod encrypted_vote_tally;
+mod handle;
 mod pool;
 mod vote_cast;
+mod vote_cast_batch;
 mod vote_plan;
+mod vote_result;
 mod vote_tally;
+mod voting_power_snapshot;
 
 #[cfg(any(test, feature = "property-test-api"))]
 mod test;
 
 use crate::transaction::{Payload, PayloadData, PayloadSlice};
 
+pub use self::committee_candidacy::{CandidacyStep, CommitteeCandidacy};
 pub use self::encrypted_vote_tally::{EncryptedVoteTally, EncryptedVoteTallyProof};
+pub use self::handle::{Handle, HandleError, HandleRegistration};
 pub use self::vote_cast::VoteCast;
+pub use self::vote_cast_batch::VoteCastBatch;
 pub use self::vote_plan::{
-    ExternalProposalDocument, ExternalProposalId, Proposal, Proposals, PushProposal, VoteAction,
-    VotePlan, VotePlanId, VotePlanProof,
+    DocumentContentType, ExternalProposalDocument, ExternalProposalId, Proposal, ProposalDocument,
+    ProposalDocumentError, Proposals, PushProposal, VoteAction, VotePlan, VotePlanId,
+    VotePlanProof, VOTE_PLAN_ID_DOMAIN,
 };
+pub use self::vote_result::{VoteResultCertification, VoteResultCertificationProof};
 pub use self::vote_tally::{
     DecryptedPrivateTally, DecryptedPrivateTallyProposal, TallyProof, VoteTally, VoteTallyPayload,
 };
-pub use delegation::{OwnerStakeDelegation, StakeDelegation};
+pub use self::voting_power_snapshot::{VotingPowerSnapshot, VotingPowerSnapshotProof};
+pub use delegation::{
+    DeferredOwnerStakeDelegation, DeferredStakeDelegation, OwnerStakeDelegation, StakeDelegation,
+};
 pub use pool::{
-    GenesisPraosLeaderHash, IndexSignatures, ManagementThreshold, PoolId, PoolOwnersSigned,
-    PoolPermissions, PoolRegistration, PoolRegistrationHash, PoolRetirement, PoolSignature,
-    PoolUpdate,
+    GenesisPraosLeaderHash, IndexSignatures, ManagementThreshold, PoolId, PoolKeysRotation,
+    PoolOwnersSigned, PoolPermissions, PoolRegistration, PoolRegistrationHash, PoolRetirement,
+    PoolSignature, PoolUpdate, POOL_ID_DOMAIN,
 };
 
 pub enum CertificateSlice<'a> {
@@ -34,8 +47,16 @@ pub enum CertificateSlice<'a> {
     PoolUpdate(PayloadSlice<'a, PoolUpdate>),
     VotePlan(PayloadSlice<'a, VotePlan>),
     VoteCast(PayloadSlice<'a, VoteCast>),
+    VoteCastBatch(PayloadSlice<'a, VoteCastBatch>),
     VoteTally(PayloadSlice<'a, VoteTally>),
     EncryptedVoteTally(PayloadSlice<'a, EncryptedVoteTally>),
+    HandleRegistration(PayloadSlice<'a, HandleRegistration>),
+    VotingPowerSnapshot(PayloadSlice<'a, VotingPowerSnapshot>),
+    PoolKeysRotation(PayloadSlice<'a, PoolKeysRotation>),
+    VoteResultCertification(PayloadSlice<'a, VoteResultCertification>),
+    CommitteeCandidacy(PayloadSlice<'a, CommitteeCandidacy>),
+    DeferredStakeDelegation(PayloadSlice<'a, DeferredStakeDelegation>),
+    DeferredOwnerStakeDelegation(PayloadSlice<'a, DeferredOwnerStakeDelegation>),
 }
 
 impl<'a> From<PayloadSlice<'a, StakeDelegation>> for CertificateSlice<'a> {
@@ -79,6 +100,12 @@ impl<'a> From<PayloadSlice<'a, VoteCast>> for CertificateSlice<'a> {
     }
 }
 
+impl<'a> From<PayloadSlice<'a, VoteCastBatch>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, VoteCastBatch>) -> CertificateSlice<'a> {
+        CertificateSlice::VoteCastBatch(payload)
+    }
+}
+
 impl<'a> From<PayloadSlice<'a, VoteTally>> for CertificateSlice<'a> {
     fn from(payload: PayloadSlice<'a, VoteTally>) -> CertificateSlice<'a> {
         CertificateSlice::VoteTally(payload)
@@ -91,6 +118,48 @@ impl<'a> From<PayloadSlice<'a, EncryptedVoteTally>> for CertificateSlice<'a> {
     }
 }
 
+impl<'a> From<PayloadSlice<'a, HandleRegistration>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, HandleRegistration>) -> CertificateSlice<'a> {
+        CertificateSlice::HandleRegistration(payload)
+    }
+}
+
+impl<'a> From<PayloadSlice<'a, VotingPowerSnapshot>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, VotingPowerSnapshot>) -> CertificateSlice<'a> {
+        CertificateSlice::VotingPowerSnapshot(payload)
+    }
+}
+
+impl<'a> From<PayloadSlice<'a, PoolKeysRotation>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, PoolKeysRotation>) -> CertificateSlice<'a> {
+        CertificateSlice::PoolKeysRotation(payload)
+    }
+}
+
+impl<'a> From<PayloadSlice<'a, VoteResultCertification>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, VoteResultCertification>) -> CertificateSlice<'a> {
+        CertificateSlice::VoteResultCertification(payload)
+    }
+}
+
+impl<'a> From<PayloadSlice<'a, CommitteeCandidacy>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, CommitteeCandidacy>) -> CertificateSlice<'a> {
+        CertificateSlice::CommitteeCandidacy(payload)
+    }
+}
+
+impl<'a> From<PayloadSlice<'a, DeferredStakeDelegation>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, DeferredStakeDelegation>) -> CertificateSlice<'a> {
+        CertificateSlice::DeferredStakeDelegation(payload)
+    }
+}
+
+impl<'a> From<PayloadSlice<'a, DeferredOwnerStakeDelegation>> for CertificateSlice<'a> {
+    fn from(payload: PayloadSlice<'a, DeferredOwnerStakeDelegation>) -> CertificateSlice<'a> {
+        CertificateSlice::DeferredOwnerStakeDelegation(payload)
+    }
+}
+
 impl<'a> CertificateSlice<'a> {
     pub fn into_owned(self) -> Certificate {
         match self {
@@ -105,10 +174,32 @@ impl<'a> CertificateSlice<'a> {
             }
             CertificateSlice::VotePlan(c) => Certificate::VotePlan(c.into_payload()),
             CertificateSlice::VoteCast(c) => Certificate::VoteCast(c.into_payload()),
+            CertificateSlice::VoteCastBatch(c) => Certificate::VoteCastBatch(c.into_payload()),
             CertificateSlice::VoteTally(c) => Certificate::VoteTally(c.into_payload()),
             CertificateSlice::EncryptedVoteTally(c) => {
                 Certificate::EncryptedVoteTally(c.into_payload())
             }
+            CertificateSlice::HandleRegistration(c) => {
+                Certificate::HandleRegistration(c.into_payload())
+            }
+            CertificateSlice::VotingPowerSnapshot(c) => {
+                Certificate::VotingPowerSnapshot(c.into_payload())
+            }
+            CertificateSlice::PoolKeysRotation(c) => {
+                Certificate::PoolKeysRotation(c.into_payload())
+            }
+            CertificateSlice::VoteResultCertification(c) => {
+                Certificate::VoteResultCertification(c.into_payload())
+            }
+            CertificateSlice::CommitteeCandidacy(c) => {
+                Certificate::CommitteeCandidacy(c.into_payload())
+            }
+            CertificateSlice::DeferredStakeDelegation(c) => {
+                Certificate::DeferredStakeDelegation(c.into_payload())
+            }
+            CertificateSlice::DeferredOwnerStakeDelegation(c) => {
+                Certificate::DeferredOwnerStakeDelegation(c.into_payload())
+            }
         }
     }
 }
@@ -122,8 +213,16 @@ pub enum CertificatePayload {
     PoolUpdate(PayloadData<PoolUpdate>),
     VotePlan(PayloadData<VotePlan>),
     VoteCast(PayloadData<VoteCast>),
+    VoteCastBatch(PayloadData<VoteCastBatch>),
     VoteTally(PayloadData<VoteTally>),
     EncryptedVoteTally(PayloadData<EncryptedVoteTally>),
+    HandleRegistration(PayloadData<HandleRegistration>),
+    VotingPowerSnapshot(PayloadData<VotingPowerSnapshot>),
+    PoolKeysRotation(PayloadData<PoolKeysRotation>),
+    VoteResultCertification(PayloadData<VoteResultCertification>),
+    CommitteeCandidacy(PayloadData<CommitteeCandidacy>),
+    DeferredStakeDelegation(PayloadData<DeferredStakeDelegation>),
+    DeferredOwnerStakeDelegation(PayloadData<DeferredOwnerStakeDelegation>),
 }
 
 impl CertificatePayload {
@@ -136,8 +235,16 @@ impl CertificatePayload {
             CertificatePayload::PoolUpdate(payload) => payload.borrow().into(),
             CertificatePayload::VotePlan(payload) => payload.borrow().into(),
             CertificatePayload::VoteCast(payload) => payload.borrow().into(),
+            CertificatePayload::VoteCastBatch(payload) => payload.borrow().into(),
             CertificatePayload::VoteTally(payload) => payload.borrow().into(),
             CertificatePayload::EncryptedVoteTally(payload) => payload.borrow().into(),
+            CertificatePayload::HandleRegistration(payload) => payload.borrow().into(),
+            CertificatePayload::VotingPowerSnapshot(payload) => payload.borrow().into(),
+            CertificatePayload::PoolKeysRotation(payload) => payload.borrow().into(),
+            CertificatePayload::VoteResultCertification(payload) => payload.borrow().into(),
+            CertificatePayload::CommitteeCandidacy(payload) => payload.borrow().into(),
+            CertificatePayload::DeferredStakeDelegation(payload) => payload.borrow().into(),
+            CertificatePayload::DeferredOwnerStakeDelegation(payload) => payload.borrow().into(),
         }
     }
 }
@@ -162,12 +269,36 @@ impl<'a> From<&'a Certificate> for CertificatePayload {
             }
             Certificate::VotePlan(payload) => CertificatePayload::VotePlan(payload.payload_data()),
             Certificate::VoteCast(payload) => CertificatePayload::VoteCast(payload.payload_data()),
+            Certificate::VoteCastBatch(payload) => {
+                CertificatePayload::VoteCastBatch(payload.payload_data())
+            }
             Certificate::VoteTally(payload) => {
                 CertificatePayload::VoteTally(payload.payload_data())
             }
             Certificate::EncryptedVoteTally(payload) => {
                 CertificatePayload::EncryptedVoteTally(payload.payload_data())
             }
+            Certificate::HandleRegistration(payload) => {
+                CertificatePayload::HandleRegistration(payload.payload_data())
+            }
+            Certificate::VotingPowerSnapshot(payload) => {
+                CertificatePayload::VotingPowerSnapshot(payload.payload_data())
+            }
+            Certificate::PoolKeysRotation(payload) => {
+                CertificatePayload::PoolKeysRotation(payload.payload_data())
+            }
+            Certificate::VoteResultCertification(payload) => {
+                CertificatePayload::VoteResultCertification(payload.payload_data())
+            }
+            Certificate::CommitteeCandidacy(payload) => {
+                CertificatePayload::CommitteeCandidacy(payload.payload_data())
+            }
+            Certificate::DeferredStakeDelegation(payload) => {
+                CertificatePayload::DeferredStakeDelegation(payload.payload_data())
+            }
+            Certificate::DeferredOwnerStakeDelegation(payload) => {
+                CertificatePayload::DeferredOwnerStakeDelegation(payload.payload_data())
+            }
         }
     }
 }
@@ -182,8 +313,16 @@ pub enum Certificate {
     PoolUpdate(PoolUpdate),
     VotePlan(VotePlan),
     VoteCast(VoteCast),
+    VoteCastBatch(VoteCastBatch),
     VoteTally(VoteTally),
     EncryptedVoteTally(EncryptedVoteTally),
+    HandleRegistration(HandleRegistration),
+    VotingPowerSnapshot(VotingPowerSnapshot),
+    PoolKeysRotation(PoolKeysRotation),
+    VoteResultCertification(VoteResultCertification),
+    CommitteeCandidacy(CommitteeCandidacy),
+    DeferredStakeDelegation(DeferredStakeDelegation),
+    DeferredOwnerStakeDelegation(DeferredOwnerStakeDelegation),
 }
 
 impl From<StakeDelegation> for Certificate {
@@ -228,6 +367,12 @@ impl From<VoteCast> for Certificate {
     }
 }
 
+impl From<VoteCastBatch> for Certificate {
+    fn from(vote_cast_batch: VoteCastBatch) -> Self {
+        Self::VoteCastBatch(vote_cast_batch)
+    }
+}
+
 impl From<VoteTally> for Certificate {
     fn from(vote_tally: VoteTally) -> Self {
         Self::VoteTally(vote_tally)
@@ -240,6 +385,48 @@ impl From<EncryptedVoteTally> for Certificate {
     }
 }
 
+impl From<HandleRegistration> for Certificate {
+    fn from(cert: HandleRegistration) -> Certificate {
+        Certificate::HandleRegistration(cert)
+    }
+}
+
+impl From<VotingPowerSnapshot> for Certificate {
+    fn from(cert: VotingPowerSnapshot) -> Certificate {
+        Certificate::VotingPowerSnapshot(cert)
+    }
+}
+
+impl From<PoolKeysRotation> for Certificate {
+    fn from(cert: PoolKeysRotation) -> Certificate {
+        Certificate::PoolKeysRotation(cert)
+    }
+}
+
+impl From<VoteResultCertification> for Certificate {
+    fn from(cert: VoteResultCertification) -> Certificate {
+        Certificate::VoteResultCertification(cert)
+    }
+}
+
+impl From<CommitteeCandidacy> for Certificate {
+    fn from(cert: CommitteeCandidacy) -> Certificate {
+        Certificate::CommitteeCandidacy(cert)
+    }
+}
+
+impl From<DeferredStakeDelegation> for Certificate {
+    fn from(cert: DeferredStakeDelegation) -> Certificate {
+        Certificate::DeferredStakeDelegation(cert)
+    }
+}
+
+impl From<DeferredOwnerStakeDelegation> for Certificate {
+    fn from(cert: DeferredOwnerStakeDelegation) -> Certificate {
+        Certificate::DeferredOwnerStakeDelegation(cert)
+    }
+}
+
 impl Certificate {
     pub fn need_auth(&self) -> bool {
         match self {
@@ -250,8 +437,22 @@ impl Certificate {
             Certificate::OwnerStakeDelegation(_) => <OwnerStakeDelegation as Payload>::HAS_AUTH,
             Certificate::VotePlan(_) => <VotePlan as Payload>::HAS_AUTH,
             Certificate::VoteCast(_) => <VoteCast as Payload>::HAS_AUTH,
+            Certificate::VoteCastBatch(_) => <VoteCastBatch as Payload>::HAS_AUTH,
             Certificate::VoteTally(_) => <VoteTally as Payload>::HAS_AUTH,
             Certificate::EncryptedVoteTally(_) => <EncryptedVoteTally as Payload>::HAS_AUTH,
+            Certificate::HandleRegistration(_) => <HandleRegistration as Payload>::HAS_AUTH,
+            Certificate::VotingPowerSnapshot(_) => <VotingPowerSnapshot as Payload>::HAS_AUTH,
+            Certificate::PoolKeysRotation(_) => <PoolKeysRotation as Payload>::HAS_AUTH,
+            Certificate::VoteResultCertification(_) => {
+                <VoteResultCertification as Payload>::HAS_AUTH
+            }
+            Certificate::CommitteeCandidacy(_) => <CommitteeCandidacy as Payload>::HAS_AUTH,
+            Certificate::DeferredStakeDelegation(_) => {
+                <DeferredStakeDelegation as Payload>::HAS_AUTH
+            }
+            Certificate::DeferredOwnerStakeDelegation(_) => {
+                <DeferredOwnerStakeDelegation as Payload>::HAS_AUTH
+            }
         }
     }
 }
@@ -270,6 +471,22 @@ pub enum SignedCertificate {
     VotePlan(VotePlan, <VotePlan as Payload>::Auth),
     VoteTally(VoteTally, <VoteTally as Payload>::Auth),
     EncryptedVoteTally(EncryptedVoteTally, <EncryptedVoteTally as Payload>::Auth),
+    HandleRegistration(HandleRegistration, <HandleRegistration as Payload>::Auth),
+    VotingPowerSnapshot(VotingPowerSnapshot, <VotingPowerSnapshot as Payload>::Auth),
+    PoolKeysRotation(PoolKeysRotation, <PoolKeysRotation as Payload>::Auth),
+    VoteResultCertification(
+        VoteResultCertification,
+        <VoteResultCertification as Payload>::Auth,
+    ),
+    CommitteeCandidacy(CommitteeCandidacy, <CommitteeCandidacy as Payload>::Auth),
+    DeferredStakeDelegation(
+        DeferredStakeDelegation,
+        <DeferredStakeDelegation as Payload>::Auth,
+    ),
+    DeferredOwnerStakeDelegation(
+        DeferredOwnerStakeDelegation,
+        <DeferredOwnerStakeDelegation as Payload>::Auth,
+    ),
 }
 
 #[cfg(test)]
@@ -288,8 +505,16 @@ mod tests {
             Certificate::OwnerStakeDelegation(_) => false,
             Certificate::VotePlan(_) => true,
             Certificate::VoteCast(_) => false,
+            Certificate::VoteCastBatch(_) => false,
             Certificate::VoteTally(_) => true,
             Certificate::EncryptedVoteTally(_) => true,
+            Certificate::HandleRegistration(_) => true,
+            Certificate::VotingPowerSnapshot(_) => true,
+            Certificate::PoolKeysRotation(_) => true,
+            Certificate::VoteResultCertification(_) => true,
+            Certificate::CommitteeCandidacy(_) => false,
+            Certificate::DeferredStakeDelegation(_) => true,
+            Certificate::DeferredOwnerStakeDelegation(_) => false,
         };
         TestResult::from_bool(certificate.need_auth() == expected_result)
     }
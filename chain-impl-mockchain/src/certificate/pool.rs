@@ -1,15 +1,18 @@
 use super::CertificateSlice;
-use crate::key::{deserialize_public_key, deserialize_signature, GenesisPraosLeader};
+use crate::key::{deserialize_public_key, deserialize_signature, GenesisPraosLeader, HashVersion};
 use crate::rewards::TaxType;
 use crate::transaction::{
-    AccountIdentifier, Payload, PayloadAuthData, PayloadData, PayloadSlice,
-    SingleAccountBindingSignature, TransactionBindingAuthData,
+    AccountIdentifier, BindingSignatureContext, Payload, PayloadAuthData, PayloadData,
+    PayloadSlice, SingleAccountBindingSignature, TransactionBindingAuthData,
 };
 use chain_core::{
     mempack::{ReadBuf, ReadError, Readable},
     property,
 };
-use chain_crypto::{digest::DigestOf, Blake2b256, Ed25519, PublicKey, Verification};
+use chain_crypto::{
+    digest::{Digest, DigestOf},
+    Blake2b256, Ed25519, PublicKey, ValidationPolicy, Verification,
+};
 use chain_time::{DurationSeconds, TimeOffsetSeconds};
 use std::marker::PhantomData;
 use typed_bytes::{ByteArray, ByteBuilder};
@@ -20,6 +23,9 @@ pub type PoolId = PoolRegistrationHash;
 /// Pool Registration Cryptographic Hash
 pub type PoolRegistrationHash = DigestOf<Blake2b256, PoolRegistration>;
 
+/// Domain separation tag of the pool ids, see [`HashVersion`]
+pub const POOL_ID_DOMAIN: &[u8] = b"chain-libs pool id";
+
 /// Hash of keys used for pool
 pub type GenesisPraosLeaderHash = DigestOf<Blake2b256, GenesisPraosLeader>;
 
@@ -46,6 +52,11 @@ pub struct PoolRegistration {
     pub rewards: TaxType,
     /// Reward account
     pub reward_account: Option<AccountIdentifier>,
+    /// Account receiving the rewards of the delegators, for the pool to pay
+    /// them out on its own terms. If none, the rewards are distributed to
+    /// the delegators directly, proportionally to their stake. Only single
+    /// accounts are accepted by the ledger.
+    pub delegators_account: Option<AccountIdentifier>,
     /// Genesis Praos keys
     pub keys: GenesisPraosLeader,
 }
@@ -89,6 +100,20 @@ pub struct PoolUpdate {
     pub new_pool_reg: PoolRegistration,
 }
 
+/// Rotation of the Genesis Praos keys of a pool.
+///
+/// Unlike a [`PoolUpdate`], only the keys and the start of their validity
+/// are replaced: the rest of the registration is kept as it is. The pool
+/// keeps its id, and so its delegations. It has to be signed by the owners
+/// of the pool, as per its management threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolKeysRotation {
+    pub pool_id: PoolId,
+    pub last_pool_reg_hash: PoolRegistrationHash,
+    pub start_validity: TimeOffsetSeconds,
+    pub keys: GenesisPraosLeader,
+}
+
 /// Retirement info for a pool
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PoolRetirement {
@@ -122,10 +147,13 @@ impl PoolRegistration {
             .iter8(&mut self.operators.iter(), |bb, o| bb.bytes(o.as_ref()))
             .sub(|sbb| self.rewards.serialize_in(sbb));
 
-        match &self.reward_account {
-            None => bb.u8(0),
-            Some(AccountIdentifier::Single(pk)) => bb.u8(1).bytes(pk.as_ref().as_ref()),
-            Some(AccountIdentifier::Multi(pk)) => bb.u8(2).bytes(pk.as_ref()),
+        match &self.delegators_account {
+            None => serialize_reward_account(bb, &self.reward_account, 0),
+            Some(_) => {
+                let bb =
+                    serialize_reward_account(bb, &self.reward_account, DELEGATORS_ACCOUNT_FLAG);
+                serialize_reward_account(bb, &self.delegators_account, 0)
+            }
         }
     }
 
@@ -134,8 +162,13 @@ impl PoolRegistration {
     }
 
     pub fn to_id(&self) -> PoolId {
+        self.to_id_with_version(HashVersion::CURRENT)
+    }
+
+    /// The id of the pool computed with the given version of the scheme
+    pub fn to_id_with_version(&self, version: HashVersion) -> PoolId {
         let ba = self.serialize();
-        DigestOf::digest_byteslice(&ba.as_byteslice())
+        Digest::digest(&version.hashed_bytes(POOL_ID_DOMAIN, ba.as_slice())).into()
     }
 
     pub fn management_threshold(&self) -> u8 {
@@ -168,6 +201,44 @@ impl Readable for PoolUpdate {
     }
 }
 
+impl PoolKeysRotation {
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        bb.bytes(self.pool_id.as_ref())
+            .bytes(self.last_pool_reg_hash.as_ref())
+            .u64(self.start_validity.into())
+            .bytes(self.keys.vrf_public_key.as_ref())
+            .bytes(self.keys.kes_public_key.as_ref())
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+
+    /// The registration of the pool with the rotated keys
+    pub fn rotate(&self, registration: &PoolRegistration) -> PoolRegistration {
+        PoolRegistration {
+            start_validity: self.start_validity,
+            keys: self.keys.clone(),
+            ..registration.clone()
+        }
+    }
+}
+
+impl Readable for PoolKeysRotation {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let pool_id = <[u8; 32]>::read(buf)?.into();
+        let last_pool_reg_hash = <[u8; 32]>::read(buf)?.into();
+        let start_validity = DurationSeconds::from(buf.get_u64()?).into();
+        let keys = GenesisPraosLeader::read(buf)?;
+        Ok(PoolKeysRotation {
+            pool_id,
+            last_pool_reg_hash,
+            start_validity,
+            keys,
+        })
+    }
+}
+
 impl PoolRetirement {
     pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
         bb.bytes(self.pool_id.as_ref())
@@ -198,6 +269,14 @@ impl property::Serialize for PoolUpdate {
     }
 }
 
+impl property::Serialize for PoolKeysRotation {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())?;
+        Ok(())
+    }
+}
+
 impl property::Serialize for PoolRetirement {
     type Error = std::io::Error;
     fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
@@ -231,6 +310,31 @@ impl Payload for PoolUpdate {
     }
 }
 
+impl Payload for PoolKeysRotation {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = true;
+    type Auth = PoolSignature;
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            PhantomData,
+        )
+    }
+    fn payload_auth_data(auth: &Self::Auth) -> PayloadAuthData<Self> {
+        PayloadAuthData(
+            auth.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            PhantomData,
+        )
+    }
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
 impl Payload for PoolRetirement {
     const HAS_DATA: bool = true;
     const HAS_AUTH: bool = true;
@@ -286,18 +390,15 @@ impl Readable for PoolRegistration {
         }
 
         let rewards = TaxType::read_frombuf(buf)?;
-        let reward_account = match buf.get_u8()? {
-            0 => None,
-            1 => {
-                let pk = deserialize_public_key(buf)?;
-                Some(AccountIdentifier::Single(pk.into()))
-            }
-            2 => {
-                let mut pk = [0u8; 32];
-                buf.copy_to_slice_mut(&mut pk)?;
-                Some(AccountIdentifier::Multi(pk.into()))
-            }
-            n => return Err(ReadError::UnknownTag(n as u32)),
+        let tag = buf.get_u8()?;
+        let reward_account = read_reward_account(tag & !DELEGATORS_ACCOUNT_FLAG, buf)?;
+        let delegators_account = if tag & DELEGATORS_ACCOUNT_FLAG == 0 {
+            None
+        } else {
+            let tag = buf.get_u8()?;
+            Some(read_reward_account(tag, buf)?.ok_or_else(|| {
+                ReadError::StructureInvalid("missing delegators account".to_string())
+            })?)
         };
 
         let info = Self {
@@ -308,12 +409,46 @@ impl Readable for PoolRegistration {
             operators: operators.into(),
             rewards,
             reward_account,
+            delegators_account,
             keys,
         };
         Ok(info)
     }
 }
 
+/// set on the tag of the reward account of the pool registrations which
+/// carry a delegators account, written after the reward account. The
+/// registrations without one keep the original encoding.
+const DELEGATORS_ACCOUNT_FLAG: u8 = 0x80;
+
+fn serialize_reward_account(
+    bb: ByteBuilder<PoolRegistration>,
+    account: &Option<AccountIdentifier>,
+    flags: u8,
+) -> ByteBuilder<PoolRegistration> {
+    match account {
+        None => bb.u8(flags),
+        Some(AccountIdentifier::Single(pk)) => bb.u8(1 | flags).bytes(pk.as_ref().as_ref()),
+        Some(AccountIdentifier::Multi(pk)) => bb.u8(2 | flags).bytes(pk.as_ref()),
+    }
+}
+
+fn read_reward_account(tag: u8, buf: &mut ReadBuf) -> Result<Option<AccountIdentifier>, ReadError> {
+    match tag {
+        0 => Ok(None),
+        1 => {
+            let pk = deserialize_public_key(buf)?;
+            Ok(Some(AccountIdentifier::Single(pk.into())))
+        }
+        2 => {
+            let mut pk = [0u8; 32];
+            buf.copy_to_slice_mut(&mut pk)?;
+            Ok(Some(AccountIdentifier::Multi(pk.into())))
+        }
+        n => Err(ReadError::UnknownTag(n as u32)),
+    }
+}
+
 impl Payload for PoolRegistration {
     const HAS_DATA: bool = true;
     const HAS_AUTH: bool = true;
@@ -359,10 +494,19 @@ impl PoolSignature {
         &self,
         pool_info: &PoolRegistration,
         verify_data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
     ) -> Verification {
         match self {
             PoolSignature::Operator(_) => Verification::Failed,
-            PoolSignature::Owners(owners) => owners.verify(pool_info, verify_data),
+            PoolSignature::Owners(owners) => owners.verify(pool_info, verify_data, context),
+        }
+    }
+
+    /// Check the encoding of the signatures against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        match self {
+            PoolSignature::Operator(sig) => sig.validate(policy).is_ok(),
+            PoolSignature::Owners(owners) => owners.validate(policy),
         }
     }
 }
@@ -378,6 +522,7 @@ impl PoolOwnersSignature {
         &self,
         pool_info: &PoolRegistration,
         verify_data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
     ) -> Verification {
         // fast track if we don't meet the management threshold already
         if self.signatures.len() < pool_info.management_threshold() as usize {
@@ -403,7 +548,7 @@ impl PoolOwnersSignature {
 
             // Verify the cryptographic signature of a signatory
             let pk = &pool_info.owners[i];
-            if sig.verify_slice(pk, verify_data) == Verification::Failed {
+            if sig.verify_slice(pk, verify_data, context) == Verification::Failed {
                 return Verification::Failed;
             }
             signatories += 1
@@ -417,6 +562,13 @@ impl PoolOwnersSignature {
 
         Verification::Success
     }
+
+    /// Check the encoding of the signatures against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        self.signatures
+            .iter()
+            .all(|(_, sig)| sig.validate(policy).is_ok())
+    }
 }
 
 impl Readable for PoolOwnersSigned {
@@ -453,7 +605,7 @@ impl Readable for PoolSignature {
 #[cfg(test)]
 mod tests {
 
-    use super::{PoolOwnersSigned, PoolPermissions};
+    use super::{PoolOwnersSigned, PoolPermissions, PoolRegistration, DELEGATORS_ACCOUNT_FLAG};
     use crate::{
         chaintypes::HeaderId,
         key::EitherEd25519SecretKey,
@@ -469,6 +621,7 @@ mod tests {
         value::Value,
     };
     use chain_addr::Discrimination;
+    use chain_core::mempack::{ReadBuf, Readable};
     use chain_crypto::{Ed25519, PublicKey, Verification};
     use std::iter;
 
@@ -521,6 +674,34 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn registrations_without_delegators_account_keep_the_original_encoding() {
+        let registration = StakePoolBuilder::new()
+            .with_reward_account(true)
+            .build()
+            .info();
+        let serialized = registration.serialize();
+        // the tagged reward account closes the registration
+        let tag = serialized.as_slice().len() - 33;
+        assert_eq!(serialized.as_slice()[tag], 1);
+
+        let mut delegated = registration.clone();
+        delegated.delegators_account = registration.reward_account.clone();
+        let extended = delegated.serialize();
+        assert_eq!(extended.as_slice()[tag], 1 | DELEGATORS_ACCOUNT_FLAG);
+        assert_eq!(extended.as_slice().len(), serialized.as_slice().len() + 33);
+        assert_eq!(
+            PoolRegistration::read(&mut ReadBuf::from(extended.as_slice())).unwrap(),
+            delegated
+        );
+
+        // the flag without a delegators account is not a valid encoding
+        let mut flagged = serialized.as_slice().to_vec();
+        flagged[tag] |= DELEGATORS_ACCOUNT_FLAG;
+        flagged.push(0);
+        assert!(PoolRegistration::read(&mut ReadBuf::from(flagged.as_slice())).is_err());
+    }
+
     /// Signatures count is grater than pool permissions and signatories have correct indexes
     #[test]
     pub fn pool_owners_correct_signature() {
@@ -747,7 +928,9 @@ mod tests {
         let auth_data = builder.get_auth_data();
         let mut sigs = Vec::new();
         for (i, key) in pool_owner_with_sign.indexed_signatories_sks() {
-            let sig = SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice(&d.0));
+            let sig = SingleAccountBindingSignature::new(&auth_data, |d| {
+                key.sign_slice_with_context(&d.0)
+            });
             sigs.push((i as u8, sig))
         }
         let pool_owner_signed = PoolOwnersSigned { signatures: sigs };
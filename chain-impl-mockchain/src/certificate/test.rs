@@ -37,6 +37,17 @@ impl Arbitrary for PoolUpdate {
     }
 }
 
+impl Arbitrary for PoolKeysRotation {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        PoolKeysRotation {
+            pool_id: Arbitrary::arbitrary(g),
+            last_pool_reg_hash: Arbitrary::arbitrary(g),
+            start_validity: DurationSeconds::from(u64::arbitrary(g)).into(),
+            keys: Arbitrary::arbitrary(g),
+        }
+    }
+}
+
 impl Arbitrary for PoolOwnersSigned {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         let mut signatoree = u8::arbitrary(g) % 32;
@@ -84,6 +95,45 @@ impl Arbitrary for StakeDelegation {
     }
 }
 
+impl Arbitrary for DeferredStakeDelegation {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        DeferredStakeDelegation {
+            delegation: Arbitrary::arbitrary(g),
+            activation: Arbitrary::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for Handle {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let length = 1 + usize::arbitrary(g) % Handle::MAX_LENGTH;
+        let handle: String = (0..length)
+            .map(|_| (b'a' + u8::arbitrary(g) % 26) as char)
+            .collect();
+        Handle::new(handle).unwrap()
+    }
+}
+
+impl Arbitrary for HandleRegistration {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        HandleRegistration {
+            handle: Arbitrary::arbitrary(g),
+            account_id: Arbitrary::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for VotingPowerSnapshot {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        VotingPowerSnapshot::new(
+            Arbitrary::arbitrary(g),
+            Arbitrary::arbitrary(g),
+            Arbitrary::arbitrary(g),
+            Arbitrary::arbitrary(g),
+        )
+    }
+}
+
 impl Arbitrary for OwnerStakeDelegation {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         Self {
@@ -92,6 +142,15 @@ impl Arbitrary for OwnerStakeDelegation {
     }
 }
 
+impl Arbitrary for DeferredOwnerStakeDelegation {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self {
+            delegation: Arbitrary::arbitrary(g),
+            activation: Arbitrary::arbitrary(g),
+        }
+    }
+}
+
 impl Arbitrary for PoolRegistration {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         let start_validity: DurationSeconds = u64::arbitrary(g).into();
@@ -120,6 +179,7 @@ impl Arbitrary for PoolRegistration {
             operators: operators.into(),
             rewards: TaxType::zero(),
             reward_account: None,
+            delegators_account: Arbitrary::arbitrary(g),
             keys,
         }
     }
@@ -149,7 +209,25 @@ impl Arbitrary for Proposal {
         let funding_plan = vote::Options::arbitrary(g);
         let action = VoteAction::arbitrary(g);
 
-        Self::new(external_id, funding_plan, action)
+        let proposal = Self::new(external_id, funding_plan, action);
+        match Option::<ProposalDocument>::arbitrary(g) {
+            Some(document) => proposal.with_document(document),
+            None => proposal,
+        }
+    }
+}
+
+impl Arbitrary for ProposalDocument {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let content_type = match u8::arbitrary(g) % 5 {
+            0 => DocumentContentType::PlainText,
+            1 => DocumentContentType::Markdown,
+            2 => DocumentContentType::Html,
+            3 => DocumentContentType::Json,
+            _ => DocumentContentType::Pdf,
+        };
+        let size = u32::arbitrary(g) % ProposalDocument::MAX_SIZE + 1;
+        ProposalDocument::new(ExternalProposalId::arbitrary(g), size, content_type).unwrap()
     }
 }
 
@@ -201,6 +279,7 @@ impl Arbitrary for VotePlan {
             payload_type,
             keys,
         )
+        .with_enactment_delay(u32::arbitrary(g) % 4)
     }
 }
 
@@ -219,7 +298,34 @@ impl Arbitrary for VoteCast {
         let proposal_index = u8::arbitrary(g);
         let payload = vote::Payload::arbitrary(g);
 
-        VoteCast::new(vote_plan, proposal_index, payload)
+        let cast = VoteCast::new(vote_plan, proposal_index, payload);
+        if bool::arbitrary(g) {
+            cast.with_voting_power_proof(Arbitrary::arbitrary(g))
+        } else {
+            cast
+        }
+    }
+}
+
+impl Arbitrary for vote::VotingPowerProof {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let path_len = usize::arbitrary(g) % 32;
+        vote::VotingPowerProof {
+            voting_power: Arbitrary::arbitrary(g),
+            index: Arbitrary::arbitrary(g),
+            path: (0..path_len).map(|_| Arbitrary::arbitrary(g)).collect(),
+        }
+    }
+}
+
+impl Arbitrary for VoteCastBatch {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let vote_plan = VotePlanId::arbitrary(g);
+        let ballots = (0..usize::arbitrary(g) % 16)
+            .map(|_| (u8::arbitrary(g), vote::Payload::arbitrary(g)))
+            .collect();
+
+        VoteCastBatch::new(vote_plan, ballots)
     }
 }
 
@@ -255,9 +361,67 @@ impl Arbitrary for EncryptedVoteTallyProof {
     }
 }
 
+impl Arbitrary for VoteResultCertification {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let results = (0..usize::arbitrary(g) % 8)
+            .map(|_| {
+                (0..usize::arbitrary(g) % 8)
+                    .map(|_| Arbitrary::arbitrary(g))
+                    .collect::<Vec<u64>>()
+                    .into_boxed_slice()
+            })
+            .collect();
+        Self::new(Arbitrary::arbitrary(g), results)
+    }
+}
+
+impl Arbitrary for VoteResultCertificationProof {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let signatures = (0..usize::arbitrary(g) % 4)
+            .map(|_| (Arbitrary::arbitrary(g), Arbitrary::arbitrary(g)))
+            .collect();
+        Self::new(signatures)
+    }
+}
+
+impl Arbitrary for CandidacyStep {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        use chain_crypto::{Curve25519_2HashDH, SecretKey};
+        use rand_core::SeedableRng;
+
+        let mut seed = [0u8; 32];
+        g.fill_bytes(&mut seed);
+        let mut rng = rand_chacha::ChaCha20Rng::from_seed(seed);
+        let vrf_key = SecretKey::<Curve25519_2HashDH>::generate(&mut rng);
+        if bool::arbitrary(g) {
+            let member_key = if bool::arbitrary(g) {
+                let h = chain_vote::CRS::from_hash(&seed);
+                let mc = chain_vote::MemberCommunicationKey::new(&mut rng);
+                let member = chain_vote::MemberState::new(&mut rng, 1, &h, &[mc.to_public()], 0);
+                Some(member.public_key())
+            } else {
+                None
+            };
+            CandidacyStep::Apply {
+                vrf_key: vrf_key.to_public(),
+                member_key,
+            }
+        } else {
+            let proof = chain_crypto::vrf_evaluate_and_prove(&vrf_key, &seed[..], &mut rng);
+            CandidacyStep::Ticket { proof }
+        }
+    }
+}
+
+impl Arbitrary for CommitteeCandidacy {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self::new(Arbitrary::arbitrary(g), Arbitrary::arbitrary(g))
+    }
+}
+
 impl Arbitrary for Certificate {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
-        let option = u8::arbitrary(g) % 9;
+        let option = u8::arbitrary(g) % 17;
         match option {
             0 => Certificate::StakeDelegation(Arbitrary::arbitrary(g)),
             1 => Certificate::OwnerStakeDelegation(Arbitrary::arbitrary(g)),
@@ -268,6 +432,14 @@ impl Arbitrary for Certificate {
             6 => Certificate::VoteCast(Arbitrary::arbitrary(g)),
             7 => Certificate::VoteTally(Arbitrary::arbitrary(g)),
             8 => Certificate::EncryptedVoteTally(Arbitrary::arbitrary(g)),
+            9 => Certificate::VoteCastBatch(Arbitrary::arbitrary(g)),
+            10 => Certificate::HandleRegistration(Arbitrary::arbitrary(g)),
+            11 => Certificate::VotingPowerSnapshot(Arbitrary::arbitrary(g)),
+            12 => Certificate::PoolKeysRotation(Arbitrary::arbitrary(g)),
+            13 => Certificate::VoteResultCertification(Arbitrary::arbitrary(g)),
+            14 => Certificate::CommitteeCandidacy(Arbitrary::arbitrary(g)),
+            15 => Certificate::DeferredStakeDelegation(Arbitrary::arbitrary(g)),
+            16 => Certificate::DeferredOwnerStakeDelegation(Arbitrary::arbitrary(g)),
             _ => panic!("unimplemented"),
         }
     }
@@ -283,3 +455,140 @@ fn pool_reg_serialization_bijection(b: PoolRegistration) -> TestResult {
     assert_eq!(buf.get_slice_end(), &[]);
     TestResult::from_bool(left == result)
 }
+
+#[quickcheck]
+fn vote_cast_serialization_bijection(b: VoteCast) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = VoteCast::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn vote_cast_without_proof_keeps_the_original_encoding(b: VoteCast) -> TestResult {
+    let plain = VoteCast::new(
+        b.vote_plan().clone(),
+        b.proposal_index(),
+        b.payload().clone(),
+    );
+    let mut expected = b.vote_plan().as_ref().to_vec();
+    expected.push(b.proposal_index());
+    expected.extend(
+        b.payload()
+            .serialize_in(typed_bytes::ByteBuilder::<VoteCast>::new())
+            .finalize_as_vec(),
+    );
+    TestResult::from_bool(plain.serialize().as_slice() == expected.as_slice())
+}
+
+#[quickcheck]
+fn vote_cast_batch_serialization_bijection(b: VoteCastBatch) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = VoteCastBatch::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn handle_registration_serialization_bijection(b: HandleRegistration) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = HandleRegistration::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn voting_power_snapshot_serialization_bijection(b: VotingPowerSnapshot) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = VotingPowerSnapshot::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn pool_keys_rotation_serialization_bijection(b: PoolKeysRotation) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = PoolKeysRotation::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn vote_result_certification_serialization_bijection(b: VoteResultCertification) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = VoteResultCertification::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn committee_candidacy_serialization_bijection(b: CommitteeCandidacy) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = CommitteeCandidacy::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn deferred_stake_delegation_serialization_bijection(b: DeferredStakeDelegation) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = DeferredStakeDelegation::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[quickcheck]
+fn deferred_owner_stake_delegation_serialization_bijection(
+    b: DeferredOwnerStakeDelegation,
+) -> TestResult {
+    let b_got = b.serialize();
+    let mut buf = ReadBuf::from(b_got.as_ref());
+    let result = DeferredOwnerStakeDelegation::read(&mut buf);
+    let left = Ok(b);
+    assert_eq!(buf.get_slice_end(), &[]);
+    TestResult::from_bool(left == result)
+}
+
+#[test]
+fn stake_delegation_keeps_the_legacy_encoding() {
+    let account_id = [1u8; 32];
+    let pool_id = [2u8; 32];
+    let mut bytes = account_id.to_vec();
+    bytes.push(1);
+    bytes.extend_from_slice(&pool_id);
+
+    let mut buf = ReadBuf::from(bytes.as_slice());
+    let delegation = StakeDelegation::read(&mut buf).unwrap();
+    assert!(buf.is_end());
+    assert_eq!(
+        delegation.delegation,
+        DelegationType::Full(PoolId::from(pool_id))
+    );
+    assert_eq!(delegation.serialize().as_slice(), bytes.as_slice());
+}
+
+#[test]
+#[should_panic]
+fn vote_cast_batch_holds_at_most_max_ballots() {
+    let ballot = (0, vote::Payload::public(vote::Choice::new(0)));
+    VoteCastBatch::new(
+        [0u8; 32].into(),
+        vec![ballot; VoteCastBatch::MAX_BALLOTS + 1],
+    );
+}
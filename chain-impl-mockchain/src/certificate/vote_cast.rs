@@ -1,7 +1,7 @@
 use crate::{
     certificate::{CertificateSlice, VotePlanId},
     transaction::{Payload, PayloadAuthData, PayloadData, PayloadSlice},
-    vote,
+    vote::{self, VotingPowerProof},
 };
 use chain_core::{
     mempack::{ReadBuf, ReadError, Readable},
@@ -9,11 +9,19 @@ use chain_core::{
 };
 use typed_bytes::{ByteArray, ByteBuilder};
 
+/// set on the payload type byte of the vote casts carrying a proof of voting
+/// power, which follows the payload. The other vote casts keep the original
+/// encoding.
+const VOTING_POWER_PROOF_FLAG: u8 = 0x80;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VoteCast {
     vote_plan: VotePlanId,
     proposal_index: u8,
     payload: vote::Payload,
+    /// proof of the voting power of the voter, required by the vote plans
+    /// with an imported voting power snapshot
+    voting_power: Option<VotingPowerProof>,
 }
 
 impl VoteCast {
@@ -22,6 +30,14 @@ impl VoteCast {
             vote_plan,
             proposal_index,
             payload,
+            voting_power: None,
+        }
+    }
+
+    pub fn with_voting_power_proof(self, proof: VotingPowerProof) -> Self {
+        Self {
+            voting_power: Some(proof),
+            ..self
         }
     }
 
@@ -37,13 +53,23 @@ impl VoteCast {
         &self.payload
     }
 
+    pub fn voting_power_proof(&self) -> Option<&VotingPowerProof> {
+        self.voting_power.as_ref()
+    }
+
     pub(crate) fn into_payload(self) -> vote::Payload {
         self.payload
     }
 
     pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
         let bb = bb.bytes(self.vote_plan.as_ref()).u8(self.proposal_index);
-        self.payload.serialize_in(bb)
+        match &self.voting_power {
+            None => self.payload.serialize_in(bb),
+            Some(proof) => proof.serialize_in(
+                self.payload
+                    .serialize_flagged_in(bb, VOTING_POWER_PROOF_FLAG),
+            ),
+        }
     }
 
     pub fn serialize(&self) -> ByteArray<Self> {
@@ -90,8 +116,18 @@ impl Readable for VoteCast {
     fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
         let vote_plan = <[u8; 32]>::read(buf)?.into();
         let proposal_index = buf.get_u8()?;
-        let payload = vote::Payload::read(buf)?;
-
-        Ok(Self::new(vote_plan, proposal_index, payload))
+        let (payload, flags) = vote::Payload::read_flagged(buf, VOTING_POWER_PROOF_FLAG)?;
+        let voting_power = if flags & VOTING_POWER_PROOF_FLAG == 0 {
+            None
+        } else {
+            Some(VotingPowerProof::read(buf)?)
+        };
+
+        Ok(Self {
+            vote_plan,
+            proposal_index,
+            payload,
+            voting_power,
+        })
     }
 }
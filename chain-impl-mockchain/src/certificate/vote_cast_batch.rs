@@ -0,0 +1,121 @@
+use crate::{
+    certificate::{CertificateSlice, VoteCast, VotePlanId},
+    transaction::{Payload, PayloadAuthData, PayloadData, PayloadSlice},
+    vote,
+};
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property,
+};
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// Ballots for multiple proposals of the same vote plan, cast by a single
+/// account in one transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VoteCastBatch {
+    vote_plan: VotePlanId,
+    ballots: Vec<(u8, vote::Payload)>,
+}
+
+impl VoteCastBatch {
+    /// maximum number of ballots in a batch
+    pub const MAX_BALLOTS: usize = u8::MAX as usize;
+
+    /// # Panics
+    ///
+    /// if there are more than `MAX_BALLOTS` ballots
+    pub fn new(vote_plan: VotePlanId, ballots: Vec<(u8, vote::Payload)>) -> Self {
+        assert!(
+            ballots.len() <= Self::MAX_BALLOTS,
+            "a vote cast batch holds at most {} ballots",
+            Self::MAX_BALLOTS
+        );
+        Self { vote_plan, ballots }
+    }
+
+    pub fn vote_plan(&self) -> &VotePlanId {
+        &self.vote_plan
+    }
+
+    /// the ballots as `(proposal index, payload)`
+    pub fn ballots(&self) -> &[(u8, vote::Payload)] {
+        &self.ballots
+    }
+
+    /// split the batch into the equivalent individual vote casts
+    pub fn vote_casts(&self) -> impl Iterator<Item = VoteCast> + '_ {
+        self.ballots.iter().map(move |(proposal_index, payload)| {
+            VoteCast::new(self.vote_plan.clone(), *proposal_index, payload.clone())
+        })
+    }
+
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        bb.bytes(self.vote_plan.as_ref())
+            .iter8(&self.ballots, |bb, (proposal_index, payload)| {
+                payload.serialize_in(bb.u8(*proposal_index))
+            })
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+}
+
+impl<'a> PayloadSlice<'a, VoteCastBatch> {
+    /// the number of ballots of the batch, without decoding them
+    pub fn nb_ballots(&self) -> usize {
+        // the 32 bytes of the vote plan id are followed by the number of
+        // ballots
+        self.0[32] as usize
+    }
+}
+
+/* Auth/Payload ************************************************************* */
+
+impl Payload for VoteCastBatch {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = false;
+    type Auth = ();
+
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn payload_auth_data(_: &Self::Auth) -> PayloadAuthData<Self> {
+        PayloadAuthData(Vec::with_capacity(0).into(), std::marker::PhantomData)
+    }
+
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
+/* Ser/De ******************************************************************* */
+
+impl property::Serialize for VoteCastBatch {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())?;
+        Ok(())
+    }
+}
+
+impl Readable for VoteCastBatch {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let vote_plan = <[u8; 32]>::read(buf)?.into();
+        let nb_ballots = buf.get_u8()? as usize;
+        let mut ballots = Vec::with_capacity(nb_ballots);
+        for _ in 0..nb_ballots {
+            let proposal_index = buf.get_u8()?;
+            let payload = vote::Payload::read(buf)?;
+            ballots.push((proposal_index, payload));
+        }
+
+        Ok(Self::new(vote_plan, ballots))
+    }
+}
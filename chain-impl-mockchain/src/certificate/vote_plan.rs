@@ -1,10 +1,11 @@
 use crate::{
     block::BlockDate,
     certificate::CertificateSlice,
+    key::HashVersion,
     ledger::governance::{Governance, ParametersGovernanceAction, TreasuryGovernanceAction},
     transaction::{
-        Payload, PayloadAuthData, PayloadData, PayloadSlice, SingleAccountBindingSignature,
-        TransactionBindingAuthData,
+        BindingSignatureContext, Payload, PayloadAuthData, PayloadData, PayloadSlice,
+        SingleAccountBindingSignature, TransactionBindingAuthData,
     },
     vote,
 };
@@ -12,9 +13,13 @@ use chain_core::{
     mempack::{ReadBuf, ReadError, Readable},
     property,
 };
-use chain_crypto::{digest::DigestOf, Blake2b256, Verification};
+use chain_crypto::{
+    digest::{Digest, DigestOf},
+    Blake2b256, ValidationPolicy, Verification,
+};
 use chain_vote::MemberPublicKey;
 use std::ops::Deref;
+use thiserror::Error;
 use typed_bytes::{ByteArray, ByteBuilder};
 
 /// abstract tag type to represent an external document, whatever it may be
@@ -25,9 +30,47 @@ pub struct ExternalProposalDocument;
 ///
 pub type ExternalProposalId = DigestOf<Blake2b256, ExternalProposalDocument>;
 
+/// reference to the off-chain document describing a proposal
+///
+/// Frontends retrieving the document can check that it is the one the
+/// proposal was voted with, see [`ProposalDocument::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalDocument {
+    hash: ExternalProposalId,
+    size: u32,
+    content_type: DocumentContentType,
+}
+
+/// the format of the off-chain document of a proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentContentType {
+    PlainText = 0,
+    Markdown = 1,
+    Html = 2,
+    Json = 3,
+    Pdf = 4,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ProposalDocumentError {
+    #[error("the proposal document is empty")]
+    Empty,
+    #[error("the proposal document of {size} bytes exceeds the maximum of {max} bytes")]
+    TooLarge { size: u32, max: u32 },
+    #[error("unknown proposal document content type {0}")]
+    UnknownContentType(u8),
+}
+
 /// the vote plan identifier on the blockchain
 pub type VotePlanId = DigestOf<Blake2b256, VotePlan>;
 
+/// Domain separation tag of the vote plan ids, see [`HashVersion`]
+pub const VOTE_PLAN_ID_DOMAIN: &[u8] = b"chain-libs vote plan id";
+
+/// set on the payload type byte of the vote plans using the extended
+/// encoding, which carries the enactment delay and the proposal documents
+const EXTENDED_PLAN_FLAG: u8 = 0x80;
+
 /// a vote plan for the voting system
 ///
 /// A vote plan defines what is being voted, for how long and how long
@@ -49,6 +92,9 @@ pub struct VotePlan {
     payload_type: vote::PayloadType,
     /// encrypting votes public keys
     committee_public_keys: Vec<chain_vote::MemberPublicKey>,
+    /// number of epochs between the tally and the enactment of the accepted
+    /// governance actions, only encoded in the extended vote plans
+    enactment_delay: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -88,6 +134,7 @@ pub struct Proposal {
     external_id: ExternalProposalId,
     options: vote::Options,
     action: VoteAction,
+    document: Option<ProposalDocument>,
 }
 
 #[must_use = "Adding a proposal may fail"]
@@ -107,9 +154,16 @@ impl Proposal {
             external_id,
             options,
             action,
+            document: None,
         }
     }
 
+    /// attach the reference to the off-chain document of the proposal
+    pub fn with_document(mut self, document: ProposalDocument) -> Self {
+        self.document = Some(document);
+        self
+    }
+
     pub fn check_governance(&self, governance: &Governance) -> bool {
         let criteria = match self.action() {
             VoteAction::OffChain => {
@@ -139,10 +193,104 @@ impl Proposal {
         &self.action
     }
 
-    fn serialize_in(&self, bb: ByteBuilder<VotePlan>) -> ByteBuilder<VotePlan> {
-        bb.bytes(self.external_id.as_ref())
+    pub fn document(&self) -> Option<&ProposalDocument> {
+        self.document.as_ref()
+    }
+
+    /// the document is only part of the extended encoding of the vote plans
+    fn serialize_in(&self, bb: ByteBuilder<VotePlan>, extended: bool) -> ByteBuilder<VotePlan> {
+        let bb = bb
+            .bytes(self.external_id.as_ref())
             .u8(self.options.as_byte())
-            .sub(|bb| self.action.serialize_in(bb))
+            .sub(|bb| self.action.serialize_in(bb));
+        if !extended {
+            return bb;
+        }
+        match &self.document {
+            None => bb.u8(0),
+            Some(document) => bb
+                .u8(1)
+                .bytes(document.hash.as_ref())
+                .u32(document.size)
+                .u8(document.content_type as u8),
+        }
+    }
+}
+
+impl ProposalDocument {
+    /// the largest document a proposal can refer to, 16 MiB
+    pub const MAX_SIZE: u32 = 16 * 1024 * 1024;
+
+    pub fn new(
+        hash: ExternalProposalId,
+        size: u32,
+        content_type: DocumentContentType,
+    ) -> Result<Self, ProposalDocumentError> {
+        if size == 0 {
+            return Err(ProposalDocumentError::Empty);
+        }
+        if size > Self::MAX_SIZE {
+            return Err(ProposalDocumentError::TooLarge {
+                size,
+                max: Self::MAX_SIZE,
+            });
+        }
+        Ok(Self {
+            hash,
+            size,
+            content_type,
+        })
+    }
+
+    /// reference the given document
+    pub fn from_document(
+        document: &[u8],
+        content_type: DocumentContentType,
+    ) -> Result<Self, ProposalDocumentError> {
+        let size = if document.len() > Self::MAX_SIZE as usize {
+            u32::MAX
+        } else {
+            document.len() as u32
+        };
+        Self::new(document_hash(document), size, content_type)
+    }
+
+    /// the Blake2b 256 bits hash of the document
+    pub fn hash(&self) -> &ExternalProposalId {
+        &self.hash
+    }
+
+    /// the size of the document in bytes
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn content_type(&self) -> DocumentContentType {
+        self.content_type
+    }
+
+    /// check that the retrieved document is the referenced one
+    pub fn verify(&self, document: &[u8]) -> bool {
+        document.len() == self.size as usize && document_hash(document) == self.hash
+    }
+}
+
+fn document_hash(document: &[u8]) -> ExternalProposalId {
+    Digest::<Blake2b256>::digest(document).into()
+}
+
+impl std::convert::TryFrom<u8> for DocumentContentType {
+    type Error = ProposalDocumentError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::PlainText),
+            1 => Ok(Self::Markdown),
+            2 => Ok(Self::Html),
+            3 => Ok(Self::Json),
+            4 => Ok(Self::Pdf),
+            t => Err(ProposalDocumentError::UnknownContentType(t)),
+        }
     }
 }
 
@@ -199,9 +347,26 @@ impl VotePlan {
             proposals,
             payload_type,
             committee_public_keys,
+            enactment_delay: 0,
         }
     }
 
+    /// delay the enactment of the accepted governance actions by the given
+    /// number of epochs after the tally
+    ///
+    /// With no delay, the treasury actions are enacted by the tally itself
+    /// and the parameters actions at the end of the epoch of the tally.
+    /// With a delay of `n` epochs, both kinds of actions are enacted at the
+    /// end of the `n`-th epoch after the one of the tally, and they can be
+    /// cancelled by a parameters governance vote until then.
+    ///
+    /// A delay makes the vote plan an extended one, see
+    /// [`is_extended`](Self::is_extended).
+    pub fn with_enactment_delay(mut self, epochs: u32) -> Self {
+        self.enactment_delay = epochs;
+        self
+    }
+
     pub fn check_governance(&self, governance: &Governance) -> bool {
         self.proposals()
             .iter()
@@ -250,6 +415,38 @@ impl VotePlan {
         &self.committee_public_keys
     }
 
+    pub fn enactment_delay(&self) -> u32 {
+        self.enactment_delay
+    }
+
+    /// whether the vote plan uses the extended encoding, which is needed for
+    /// the fields the original encoding has no room for: a non zero
+    /// enactment delay or a proposal document
+    ///
+    /// The vote plans which do not need it keep the original encoding, and
+    /// so the same identifier.
+    pub fn is_extended(&self) -> bool {
+        self.enactment_delay != 0
+            || self
+                .proposals
+                .iter()
+                .any(|proposal| proposal.document.is_some())
+    }
+
+    /// The common reference string of the private votes of the plan, derived
+    /// from the identifier of the plan
+    ///
+    /// The extended vote plans hash to the curve with the RFC 9380 suite,
+    /// the other ones keep the legacy construction they were voted with.
+    pub fn crs(&self) -> chain_vote::CRS {
+        let version = if self.is_extended() {
+            chain_vote::HashToCurveVersion::Rfc9380
+        } else {
+            chain_vote::HashToCurveVersion::Legacy
+        };
+        chain_vote::crs_from_seed(self.to_id().as_ref(), version)
+    }
+
     #[inline]
     pub fn vote_started(&self, date: BlockDate) -> bool {
         self.vote_start <= date
@@ -290,19 +487,31 @@ impl VotePlan {
     }
 
     pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
-        bb.u32(self.vote_start.epoch)
+        let extended = self.is_extended();
+        let payload_type = if extended {
+            self.payload_type as u8 | EXTENDED_PLAN_FLAG
+        } else {
+            self.payload_type as u8
+        };
+        let bb = bb
+            .u32(self.vote_start.epoch)
             .u32(self.vote_start.slot_id)
             .u32(self.vote_end.epoch)
             .u32(self.vote_end.slot_id)
             .u32(self.committee_end.epoch)
             .u32(self.committee_end.slot_id)
-            .u8(self.payload_type as u8)
+            .u8(payload_type)
             .iter8(&mut self.proposals.iter(), |bb, proposal| {
-                proposal.serialize_in(bb)
+                proposal.serialize_in(bb, extended)
             })
             .iter8(self.committee_public_keys.iter(), |bb, key| {
                 bb.bytes(key.to_bytes().as_ref())
-            })
+            });
+        if extended {
+            bb.u32(self.enactment_delay)
+        } else {
+            bb
+        }
     }
 
     pub fn serialize(&self) -> ByteArray<Self> {
@@ -314,8 +523,14 @@ impl VotePlan {
     /// this is not a very efficient function so it is better not
     /// to call it in tight loop
     pub fn to_id(&self) -> VotePlanId {
+        self.to_id_with_version(HashVersion::CURRENT)
+    }
+
+    /// build the identifier of the vote plan with the given version of the
+    /// scheme
+    pub fn to_id_with_version(&self, version: HashVersion) -> VotePlanId {
         let ba = self.serialize();
-        DigestOf::digest_byteslice(&ba.as_byteslice())
+        Digest::digest(&version.hashed_bytes(VOTE_PLAN_ID_DOMAIN, ba.as_slice())).into()
     }
 }
 
@@ -324,9 +539,19 @@ impl VotePlanProof {
         bb.bytes(self.id.as_ref()).bytes(self.signature.as_ref())
     }
 
-    pub fn verify<'a>(&self, verify_data: &TransactionBindingAuthData<'a>) -> Verification {
+    pub fn verify<'a>(
+        &self,
+        verify_data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
+    ) -> Verification {
         let pk = self.id.public_key();
-        self.signature.verify_slice(&pk, verify_data)
+        self.signature.verify_slice(&pk, verify_data, context)
+    }
+
+    /// Check the encoding of the committee member key and of the signature
+    /// against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        self.id.public_key().validate(policy).is_ok() && self.signature.validate(policy).is_ok()
     }
 }
 
@@ -398,6 +623,19 @@ impl Readable for VoteAction {
     }
 }
 
+impl Readable for ProposalDocument {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        use std::convert::TryInto as _;
+
+        let hash = <[u8; 32]>::read(buf)?.into();
+        let size = buf.get_u32()?;
+        buf.get_u8()?
+            .try_into()
+            .and_then(|content_type| Self::new(hash, size, content_type))
+            .map_err(|e: ProposalDocumentError| ReadError::StructureInvalid(e.to_string()))
+    }
+}
+
 impl Readable for VotePlan {
     fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
         use std::convert::TryInto as _;
@@ -415,8 +653,9 @@ impl Readable for VotePlan {
             slot_id: buf.get_u32()?,
         };
 
-        let payload_type = buf
-            .get_u8()?
+        let payload_type = buf.get_u8()?;
+        let extended = payload_type & EXTENDED_PLAN_FLAG != 0;
+        let payload_type = (payload_type & !EXTENDED_PLAN_FLAG)
             .try_into()
             .map_err(|e: vote::TryFromIntError| ReadError::StructureInvalid(e.to_string()))?;
 
@@ -431,11 +670,21 @@ impl Readable for VotePlan {
                     .map_err(|e| ReadError::StructureInvalid(e.to_string()))
             })?;
             let action = VoteAction::read(buf)?;
+            let document = if !extended {
+                None
+            } else {
+                match buf.get_u8()? {
+                    0 => None,
+                    1 => Some(ProposalDocument::read(buf)?),
+                    t => return Err(ReadError::UnknownTag(t as u32)),
+                }
+            };
 
             let proposal = Proposal {
                 external_id,
                 options,
                 action,
+                document,
             };
 
             proposals.proposals.push(proposal);
@@ -450,14 +699,25 @@ impl Readable for VotePlan {
             })?);
         }
 
-        Ok(Self {
+        let enactment_delay = if extended { buf.get_u32()? } else { 0 };
+
+        let plan = Self {
             vote_start,
             vote_end,
             committee_end,
             proposals,
             payload_type,
             committee_public_keys,
-        })
+            enactment_delay,
+        };
+        // a vote plan has a single encoding, so that its identifier is the
+        // one of the certificate it was read from
+        if plan.is_extended() != extended {
+            return Err(ReadError::StructureInvalid(
+                "extended vote plan without extended fields".to_string(),
+            ));
+        }
+        Ok(plan)
     }
 }
 
@@ -481,6 +741,58 @@ mod tests {
         decoded == vote_plan
     }
 
+    #[test]
+    pub fn plans_without_extended_fields_keep_the_original_encoding() {
+        let vote_plan = VoteTestGen::vote_plan();
+        assert!(!vote_plan.is_extended());
+        let serialized = vote_plan.serialize();
+        // the payload type follows the three dates
+        assert_eq!(serialized.as_slice()[24], vote_plan.payload_type() as u8);
+
+        let delayed = vote_plan.clone().with_enactment_delay(2);
+        assert!(delayed.is_extended());
+        let extended = delayed.serialize();
+        assert_eq!(
+            extended.as_slice()[24],
+            vote_plan.payload_type() as u8 | EXTENDED_PLAN_FLAG
+        );
+        // a document tag per proposal and the delay
+        assert_eq!(
+            extended.as_slice().len(),
+            serialized.as_slice().len() + vote_plan.proposals().len() + 4
+        );
+        assert_ne!(delayed.to_id(), vote_plan.to_id());
+
+        // the extended encoding without extended fields is not valid
+        let mut flagged = extended.as_slice().to_vec();
+        let delay = flagged.len() - 4;
+        flagged[delay..].copy_from_slice(&0u32.to_be_bytes());
+        assert!(VotePlan::read(&mut ReadBuf::from(flagged.as_slice())).is_err());
+    }
+
+    #[test]
+    pub fn proposal_documents_make_the_plan_extended() {
+        let mut vote_plan = VoteTestGen::vote_plan();
+        assert!(!vote_plan.is_extended());
+        let document =
+            ProposalDocument::from_document(b"proposal", DocumentContentType::Markdown).unwrap();
+        let proposal = vote_plan.proposals_mut().proposals.remove(0);
+        vote_plan
+            .proposals_mut()
+            .proposals
+            .insert(0, proposal.with_document(document));
+        assert!(vote_plan.is_extended());
+        assert_eq!(vote_plan.enactment_delay(), 0);
+
+        let serialized = vote_plan.serialize();
+        assert_eq!(
+            serialized.as_slice()[24],
+            vote_plan.payload_type() as u8 | EXTENDED_PLAN_FLAG
+        );
+        let decoded = VotePlan::read(&mut ReadBuf::from(serialized.as_slice())).unwrap();
+        assert_eq!(decoded, vote_plan);
+    }
+
     #[test]
     pub fn proposals_are_full() {
         let mut proposals = VoteTestGen::proposals(Proposals::MAX_LEN);
@@ -575,4 +887,56 @@ mod tests {
         assert!(!vote_plan.can_vote(after_committee_time));
         assert!(!vote_plan.committee_time(after_committee_time));
     }
+
+    #[test]
+    pub fn proposal_document_matches_the_referenced_document() {
+        let document = b"# Fund the documentation effort";
+        let reference =
+            ProposalDocument::from_document(document, DocumentContentType::Markdown).unwrap();
+        assert_eq!(reference.size(), document.len() as u32);
+        assert_eq!(reference.content_type(), DocumentContentType::Markdown);
+        assert!(reference.verify(document));
+        assert!(!reference.verify(b"# Fund the documentation effort!"));
+        assert!(!reference.verify(b"# Fund the documentation effor"));
+
+        assert_eq!(
+            ProposalDocument::from_document(b"", DocumentContentType::PlainText),
+            Err(ProposalDocumentError::Empty)
+        );
+        assert_eq!(
+            ProposalDocument::new(
+                *reference.hash(),
+                ProposalDocument::MAX_SIZE + 1,
+                DocumentContentType::Pdf
+            ),
+            Err(ProposalDocumentError::TooLarge {
+                size: ProposalDocument::MAX_SIZE + 1,
+                max: ProposalDocument::MAX_SIZE,
+            })
+        );
+    }
+
+    #[test]
+    pub fn oversized_proposal_document_is_rejected_on_read() {
+        let proposal = VoteTestGen::proposal().with_document(
+            ProposalDocument::from_document(b"{}", DocumentContentType::Json).unwrap(),
+        );
+        let mut proposals = Proposals::new();
+        assert_eq!(proposals.push(proposal), PushProposal::Success);
+        let vote_plan = VotePlan::new(
+            BlockDate::from_epoch_slot_id(1, 0),
+            BlockDate::from_epoch_slot_id(2, 0),
+            BlockDate::from_epoch_slot_id(3, 0),
+            proposals,
+            vote::PayloadType::Public,
+            Vec::new(),
+        );
+        let mut bytes = vote_plan.serialize().as_slice().to_vec();
+        // the document reference ends the proposal, before the number of
+        // committee keys and the enactment delay
+        let size_at = bytes.len() - 4 - 1 - 1 - 4;
+        bytes[size_at..size_at + 4]
+            .copy_from_slice(&(ProposalDocument::MAX_SIZE + 1).to_be_bytes());
+        assert!(VotePlan::read(&mut ReadBuf::from(&bytes[..])).is_err());
+    }
 }
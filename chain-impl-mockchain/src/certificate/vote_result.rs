@@ -0,0 +1,187 @@
+use crate::{
+    certificate::{CertificateSlice, VotePlanId},
+    transaction::{
+        BindingSignatureContext, Payload, PayloadAuthData, PayloadData, PayloadSlice,
+        SingleAccountBindingSignature, TransactionBindingAuthData,
+    },
+    vote::CommitteeId,
+};
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property,
+};
+use chain_crypto::{ValidationPolicy, Verification};
+use std::collections::HashSet;
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// Publication of the final results of a tallied vote plan by the committee.
+///
+/// The certificate carries the weight of each option of each proposal of
+/// the vote plan, as decrypted by the tally. It is signed by a majority of
+/// the committee and has to match the tally recorded in the ledger. When
+/// the certification of the results is required by the settings, the
+/// actions of the accepted proposals are only applied once the results of
+/// their vote plan are certified.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VoteResultCertification {
+    vote_plan: VotePlanId,
+    results: Box<[Box<[u64]>]>,
+}
+
+/// The signatures of the committee members certifying the results
+#[derive(Debug, Clone)]
+pub struct VoteResultCertificationProof {
+    signatures: Box<[(CommitteeId, SingleAccountBindingSignature)]>,
+}
+
+impl VoteResultCertification {
+    pub fn new(vote_plan: VotePlanId, results: Vec<Box<[u64]>>) -> Self {
+        Self {
+            vote_plan,
+            results: results.into_boxed_slice(),
+        }
+    }
+
+    pub fn vote_plan(&self) -> &VotePlanId {
+        &self.vote_plan
+    }
+
+    /// the weight of each option, for each proposal in the order of the
+    /// vote plan
+    pub fn results(&self) -> &[Box<[u64]>] {
+        &self.results
+    }
+
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        use std::convert::TryInto;
+
+        bb.bytes(self.vote_plan.as_ref())
+            .u8(self.results.len().try_into().unwrap())
+            .fold(self.results.iter(), |bb, proposal| {
+                bb.u8(proposal.len().try_into().unwrap())
+                    .fold(proposal.iter(), |bb, weight| bb.u64(*weight))
+            })
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+}
+
+impl VoteResultCertificationProof {
+    pub fn new(signatures: Vec<(CommitteeId, SingleAccountBindingSignature)>) -> Self {
+        Self {
+            signatures: signatures.into_boxed_slice(),
+        }
+    }
+
+    /// the committee members signing the certificate
+    pub fn signers(&self) -> impl Iterator<Item = &CommitteeId> {
+        self.signatures.iter().map(|(id, _)| id)
+    }
+
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        use std::convert::TryInto;
+
+        bb.u8(self.signatures.len().try_into().unwrap())
+            .fold(self.signatures.iter(), |bb, (id, signature)| {
+                bb.bytes(id.as_ref()).bytes(signature.as_ref())
+            })
+    }
+
+    /// check all the signatures, each member may only sign once
+    pub fn verify<'a>(
+        &self,
+        verify_data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
+    ) -> Verification {
+        let mut signers = HashSet::new();
+        for (id, signature) in self.signatures.iter() {
+            if !signers.insert(id)
+                || signature.verify_slice(&id.public_key(), verify_data, context)
+                    == Verification::Failed
+            {
+                return Verification::Failed;
+            }
+        }
+        Verification::Success
+    }
+
+    /// Check the encoding of the committee member keys and of the
+    /// signatures against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        self.signatures.iter().all(|(id, signature)| {
+            id.public_key().validate(policy).is_ok() && signature.validate(policy).is_ok()
+        })
+    }
+}
+
+/* Auth/Payload ************************************************************* */
+
+impl Payload for VoteResultCertification {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = true;
+    type Auth = VoteResultCertificationProof;
+
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn payload_auth_data(auth: &Self::Auth) -> PayloadAuthData<Self> {
+        PayloadAuthData(
+            auth.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
+/* Ser/De ******************************************************************* */
+
+impl property::Serialize for VoteResultCertification {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())?;
+        Ok(())
+    }
+}
+
+impl Readable for VoteResultCertification {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let vote_plan = <[u8; 32]>::read(buf)?.into();
+        let proposals_number = buf.get_u8()? as usize;
+        let mut results = Vec::with_capacity(proposals_number);
+        for _ in 0..proposals_number {
+            let options_number = buf.get_u8()? as usize;
+            let mut weights = Vec::with_capacity(options_number);
+            for _ in 0..options_number {
+                weights.push(buf.get_u64()?);
+            }
+            results.push(weights.into_boxed_slice());
+        }
+        Ok(Self::new(vote_plan, results))
+    }
+}
+
+impl Readable for VoteResultCertificationProof {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let signatures_number = buf.get_u8()? as usize;
+        let mut signatures = Vec::with_capacity(signatures_number);
+        for _ in 0..signatures_number {
+            let id = CommitteeId::read(buf)?;
+            let signature = SingleAccountBindingSignature::read(buf)?;
+            signatures.push((id, signature));
+        }
+        Ok(Self::new(signatures))
+    }
+}
@@ -1,8 +1,8 @@
 use crate::{
     certificate::{CertificateSlice, VotePlanId},
     transaction::{
-        Payload, PayloadAuthData, PayloadData, PayloadSlice, SingleAccountBindingSignature,
-        TransactionBindingAuthData,
+        BindingSignatureContext, Payload, PayloadAuthData, PayloadData, PayloadSlice,
+        SingleAccountBindingSignature, TransactionBindingAuthData,
     },
     vote::{CommitteeId, PayloadType, TryFromIntError},
 };
@@ -10,7 +10,7 @@ use chain_core::{
     mempack::{ReadBuf, ReadError, Readable},
     property,
 };
-use chain_crypto::Verification;
+use chain_crypto::{ValidationPolicy, Verification};
 use chain_vote::TallyDecryptShare;
 use typed_bytes::{ByteArray, ByteBuilder};
 
@@ -144,6 +144,7 @@ impl TallyProof {
         &self,
         tally_type: PayloadType,
         verify_data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
     ) -> Verification {
         match self {
             Self::Public { id, signature } => {
@@ -151,7 +152,7 @@ impl TallyProof {
                     Verification::Failed
                 } else {
                     let pk = id.public_key();
-                    signature.verify_slice(&pk, verify_data)
+                    signature.verify_slice(&pk, verify_data, context)
                 }
             }
             Self::Private { id, signature } => {
@@ -159,11 +160,21 @@ impl TallyProof {
                     Verification::Failed
                 } else {
                     let pk = id.public_key();
-                    signature.verify_slice(&pk, verify_data)
+                    signature.verify_slice(&pk, verify_data, context)
                 }
             }
         }
     }
+
+    /// Check the encoding of the committee member key and of the signature
+    /// against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        match self {
+            Self::Public { id, signature } | Self::Private { id, signature } => {
+                id.public_key().validate(policy).is_ok() && signature.validate(policy).is_ok()
+            }
+        }
+    }
 }
 
 impl DecryptedPrivateTally {
@@ -0,0 +1,119 @@
+use crate::{
+    certificate::{CertificateSlice, VotePlanId, VotePlanProof},
+    key::Hash,
+    transaction::{Payload, PayloadAuthData, PayloadData, PayloadSlice},
+};
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property,
+};
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// Import the voting power of the accounts for a vote plan, computed
+/// off-chain.
+///
+/// The certificate carries the root of the
+/// [`VotingPowerTree`](crate::vote::VotingPowerTree) of the snapshot, with
+/// the number of accounts and the total voting power it commits to. It is
+/// signed by a member of the committee of the vote plan, like a tally, and
+/// has to be applied before the start of the vote.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VotingPowerSnapshot {
+    vote_plan: VotePlanId,
+    root: Hash,
+    voters: u32,
+    total: u64,
+}
+
+/// The signature of a committee member, as for a vote plan
+pub type VotingPowerSnapshotProof = VotePlanProof;
+
+impl VotingPowerSnapshot {
+    pub fn new(vote_plan: VotePlanId, root: Hash, voters: u32, total: u64) -> Self {
+        Self {
+            vote_plan,
+            root,
+            voters,
+            total,
+        }
+    }
+
+    pub fn vote_plan(&self) -> &VotePlanId {
+        &self.vote_plan
+    }
+
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    /// number of accounts in the snapshot
+    pub fn voters(&self) -> u32 {
+        self.voters
+    }
+
+    /// the sum of the voting power of all the accounts in the snapshot
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        bb.bytes(self.vote_plan.as_ref())
+            .bytes(self.root.as_ref())
+            .u32(self.voters)
+            .u64(self.total)
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+}
+
+/* Auth/Payload ************************************************************* */
+
+impl Payload for VotingPowerSnapshot {
+    const HAS_DATA: bool = true;
+    const HAS_AUTH: bool = true;
+    type Auth = VotingPowerSnapshotProof;
+
+    fn payload_data(&self) -> PayloadData<Self> {
+        PayloadData(
+            self.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn payload_auth_data(auth: &Self::Auth) -> PayloadAuthData<Self> {
+        PayloadAuthData(
+            auth.serialize_in(ByteBuilder::new())
+                .finalize_as_vec()
+                .into(),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn payload_to_certificate_slice(p: PayloadSlice<'_, Self>) -> Option<CertificateSlice<'_>> {
+        Some(CertificateSlice::from(p))
+    }
+}
+
+/* Ser/De ******************************************************************* */
+
+impl property::Serialize for VotingPowerSnapshot {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())?;
+        Ok(())
+    }
+}
+
+impl Readable for VotingPowerSnapshot {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let vote_plan = <[u8; 32]>::read(buf)?.into();
+        let root = Hash::read(buf)?;
+        let voters = buf.get_u32()?;
+        let total = buf.get_u64()?;
+        Ok(Self::new(vote_plan, root, voters, total))
+    }
+}
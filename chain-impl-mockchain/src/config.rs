@@ -5,14 +5,17 @@ use crate::rewards::{Ratio, TaxType};
 use crate::value::Value;
 use crate::{
     chaintypes::ConsensusType,
-    fee::{LinearFee, PerCertificateFee, PerVoteCertificateFee},
+    fee::{
+        FeeDiscount, GovernanceFeeDiscount, LinearFee, PerCertificateFee, PerVoteCertificateFee,
+    },
+    ledger::governance::TreasuryBudget,
     vote::CommitteeId,
 };
 use chain_addr::Discrimination;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::packer::Codec;
 use chain_core::property;
-use chain_crypto::PublicKey;
+use chain_crypto::{PublicKey, ValidationPolicy};
 use std::fmt::{self, Display, Formatter};
 use std::io::{self, Cursor, Write};
 use std::num::{NonZeroU32, NonZeroU64};
@@ -82,6 +85,23 @@ pub enum ConfigParam {
     AddCommitteeId(CommitteeId),
     RemoveCommitteeId(CommitteeId),
     PerVoteCertificateFees(PerVoteCertificateFee),
+    BlockContentMaxWeight(u32),
+    PoolRegistrationDeposit(Value),
+    VotePlanDeposit(Value),
+    MaxActiveVotePlansPerCommittee(u32),
+    PerTokenFee(u64),
+    HandleRegistrationDeposit(Value),
+    HandleValidity(u32),
+    CanonicalFragmentOrder(bool),
+    VotingInactivityThreshold(u32),
+    GovernanceFeeDiscount(GovernanceFeeDiscount),
+    BlockContentSoftMaxSize(u32),
+    VoteResultCertification(bool),
+    TreasuryBudget(TreasuryBudget),
+    CommitteeSortitionSize(u32),
+    BindingSignatureContextEpoch(Epoch),
+    HashVersionEpoch(Epoch),
+    ValidationPolicy(ValidationPolicy),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -98,10 +118,14 @@ pub enum RewardParams {
         epoch_start: Epoch,
         epoch_rate: NonZeroU32,
     },
+    Fixed {
+        constant: u64,
+        epoch_start: Epoch,
+    },
 }
 
 // Discriminants can NEVER be 1024 or higher
-#[derive(AsRefStr, Clone, Copy, Debug, EnumIter, EnumString, PartialEq)]
+#[derive(AsRefStr, Clone, Copy, Debug, EnumIter, EnumString, PartialEq, Eq, Hash)]
 pub enum Tag {
     #[strum(to_string = "discrimination")]
     Discrimination = 1,
@@ -153,6 +177,40 @@ pub enum Tag {
     RemoveCommitteeId = 27,
     #[strum(to_string = "per-vote-certificate-fees")]
     PerVoteCertificateFees = 28,
+    #[strum(to_string = "block-content-max-weight")]
+    BlockContentMaxWeight = 29,
+    #[strum(to_string = "pool-registration-deposit")]
+    PoolRegistrationDeposit = 30,
+    #[strum(to_string = "vote-plan-deposit")]
+    VotePlanDeposit = 31,
+    #[strum(to_string = "max-active-vote-plans-per-committee")]
+    MaxActiveVotePlansPerCommittee = 32,
+    #[strum(to_string = "per-token-fee")]
+    PerTokenFee = 33,
+    #[strum(to_string = "handle-registration-deposit")]
+    HandleRegistrationDeposit = 34,
+    #[strum(to_string = "handle-validity")]
+    HandleValidity = 35,
+    #[strum(to_string = "canonical-fragment-order")]
+    CanonicalFragmentOrder = 36,
+    #[strum(to_string = "voting-inactivity-threshold")]
+    VotingInactivityThreshold = 37,
+    #[strum(to_string = "governance-fee-discount")]
+    GovernanceFeeDiscount = 38,
+    #[strum(to_string = "block-content-soft-max-size")]
+    BlockContentSoftMaxSize = 39,
+    #[strum(to_string = "vote-result-certification")]
+    VoteResultCertification = 40,
+    #[strum(to_string = "treasury-budget")]
+    TreasuryBudget = 41,
+    #[strum(to_string = "committee-sortition-size")]
+    CommitteeSortitionSize = 42,
+    #[strum(to_string = "binding-signature-context-epoch")]
+    BindingSignatureContextEpoch = 43,
+    #[strum(to_string = "hash-version-epoch")]
+    HashVersionEpoch = 44,
+    #[strum(to_string = "validation-policy")]
+    ValidationPolicy = 45,
 }
 
 impl Tag {
@@ -183,6 +241,23 @@ impl Tag {
             26 => Some(Tag::AddCommitteeId),
             27 => Some(Tag::RemoveCommitteeId),
             28 => Some(Tag::PerVoteCertificateFees),
+            29 => Some(Tag::BlockContentMaxWeight),
+            30 => Some(Tag::PoolRegistrationDeposit),
+            31 => Some(Tag::VotePlanDeposit),
+            32 => Some(Tag::MaxActiveVotePlansPerCommittee),
+            33 => Some(Tag::PerTokenFee),
+            34 => Some(Tag::HandleRegistrationDeposit),
+            35 => Some(Tag::HandleValidity),
+            36 => Some(Tag::CanonicalFragmentOrder),
+            37 => Some(Tag::VotingInactivityThreshold),
+            38 => Some(Tag::GovernanceFeeDiscount),
+            39 => Some(Tag::BlockContentSoftMaxSize),
+            40 => Some(Tag::VoteResultCertification),
+            41 => Some(Tag::TreasuryBudget),
+            42 => Some(Tag::CommitteeSortitionSize),
+            43 => Some(Tag::BindingSignatureContextEpoch),
+            44 => Some(Tag::HashVersionEpoch),
+            45 => Some(Tag::ValidationPolicy),
             _ => None,
         }
     }
@@ -218,6 +293,23 @@ impl<'a> From<&'a ConfigParam> for Tag {
             ConfigParam::AddCommitteeId(..) => Tag::AddCommitteeId,
             ConfigParam::RemoveCommitteeId(..) => Tag::RemoveCommitteeId,
             ConfigParam::PerVoteCertificateFees(..) => Tag::PerVoteCertificateFees,
+            ConfigParam::BlockContentMaxWeight(_) => Tag::BlockContentMaxWeight,
+            ConfigParam::PoolRegistrationDeposit(_) => Tag::PoolRegistrationDeposit,
+            ConfigParam::VotePlanDeposit(_) => Tag::VotePlanDeposit,
+            ConfigParam::MaxActiveVotePlansPerCommittee(_) => Tag::MaxActiveVotePlansPerCommittee,
+            ConfigParam::PerTokenFee(_) => Tag::PerTokenFee,
+            ConfigParam::HandleRegistrationDeposit(_) => Tag::HandleRegistrationDeposit,
+            ConfigParam::HandleValidity(_) => Tag::HandleValidity,
+            ConfigParam::CanonicalFragmentOrder(_) => Tag::CanonicalFragmentOrder,
+            ConfigParam::VotingInactivityThreshold(_) => Tag::VotingInactivityThreshold,
+            ConfigParam::GovernanceFeeDiscount(_) => Tag::GovernanceFeeDiscount,
+            ConfigParam::BlockContentSoftMaxSize(_) => Tag::BlockContentSoftMaxSize,
+            ConfigParam::VoteResultCertification(_) => Tag::VoteResultCertification,
+            ConfigParam::TreasuryBudget(_) => Tag::TreasuryBudget,
+            ConfigParam::CommitteeSortitionSize(_) => Tag::CommitteeSortitionSize,
+            ConfigParam::BindingSignatureContextEpoch(_) => Tag::BindingSignatureContextEpoch,
+            ConfigParam::HashVersionEpoch(_) => Tag::HashVersionEpoch,
+            ConfigParam::ValidationPolicy(_) => Tag::ValidationPolicy,
         }
     }
 }
@@ -298,6 +390,55 @@ impl Readable for ConfigParam {
             Tag::PerVoteCertificateFees => {
                 ConfigParamVariant::from_payload(bytes).map(ConfigParam::PerVoteCertificateFees)
             }
+            Tag::BlockContentMaxWeight => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::BlockContentMaxWeight)
+            }
+            Tag::PoolRegistrationDeposit => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::PoolRegistrationDeposit)
+            }
+            Tag::VotePlanDeposit => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::VotePlanDeposit)
+            }
+            Tag::MaxActiveVotePlansPerCommittee => ConfigParamVariant::from_payload(bytes)
+                .map(ConfigParam::MaxActiveVotePlansPerCommittee),
+            Tag::PerTokenFee => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::PerTokenFee)
+            }
+            Tag::HandleRegistrationDeposit => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::HandleRegistrationDeposit)
+            }
+            Tag::HandleValidity => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::HandleValidity)
+            }
+            Tag::CanonicalFragmentOrder => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::CanonicalFragmentOrder)
+            }
+            Tag::VotingInactivityThreshold => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::VotingInactivityThreshold)
+            }
+            Tag::GovernanceFeeDiscount => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::GovernanceFeeDiscount)
+            }
+            Tag::BlockContentSoftMaxSize => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::BlockContentSoftMaxSize)
+            }
+            Tag::VoteResultCertification => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::VoteResultCertification)
+            }
+            Tag::TreasuryBudget => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::TreasuryBudget)
+            }
+            Tag::CommitteeSortitionSize => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::CommitteeSortitionSize)
+            }
+            Tag::BindingSignatureContextEpoch => ConfigParamVariant::from_payload(bytes)
+                .map(ConfigParam::BindingSignatureContextEpoch),
+            Tag::HashVersionEpoch => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::HashVersionEpoch)
+            }
+            Tag::ValidationPolicy => {
+                ConfigParamVariant::from_payload(bytes).map(ConfigParam::ValidationPolicy)
+            }
         }
         .map_err(Into::into)
     }
@@ -334,6 +475,23 @@ impl property::Serialize for ConfigParam {
             ConfigParam::AddCommitteeId(data) => data.to_payload(),
             ConfigParam::RemoveCommitteeId(data) => data.to_payload(),
             ConfigParam::PerVoteCertificateFees(data) => data.to_payload(),
+            ConfigParam::BlockContentMaxWeight(data) => data.to_payload(),
+            ConfigParam::PoolRegistrationDeposit(data) => data.to_payload(),
+            ConfigParam::VotePlanDeposit(data) => data.to_payload(),
+            ConfigParam::MaxActiveVotePlansPerCommittee(data) => data.to_payload(),
+            ConfigParam::PerTokenFee(data) => data.to_payload(),
+            ConfigParam::HandleRegistrationDeposit(data) => data.to_payload(),
+            ConfigParam::HandleValidity(data) => data.to_payload(),
+            ConfigParam::CanonicalFragmentOrder(data) => data.to_payload(),
+            ConfigParam::VotingInactivityThreshold(data) => data.to_payload(),
+            ConfigParam::GovernanceFeeDiscount(data) => data.to_payload(),
+            ConfigParam::BlockContentSoftMaxSize(data) => data.to_payload(),
+            ConfigParam::VoteResultCertification(data) => data.to_payload(),
+            ConfigParam::TreasuryBudget(data) => data.to_payload(),
+            ConfigParam::CommitteeSortitionSize(data) => data.to_payload(),
+            ConfigParam::BindingSignatureContextEpoch(data) => data.to_payload(),
+            ConfigParam::HashVersionEpoch(data) => data.to_payload(),
+            ConfigParam::ValidationPolicy(data) => data.to_payload(),
         };
         let taglen = TagLen::new(tag, bytes.len()).ok_or_else(|| {
             io::Error::new(
@@ -452,6 +610,10 @@ impl ConfigParamVariant for RewardParams {
                 .u64(ratio.denominator.get())
                 .u32(*epoch_start)
                 .u32(epoch_rate.get()),
+            RewardParams::Fixed {
+                constant,
+                epoch_start,
+            } => ByteBuilder::new().u8(3).u64(*constant).u32(*epoch_start),
         };
         bb.finalize_as_vec()
     }
@@ -493,6 +655,15 @@ impl ConfigParamVariant for RewardParams {
                     epoch_rate: erate,
                 })
             }
+            3 => {
+                let constant = rb.get_u64()?;
+                let epoch_start = rb.get_u32()?;
+                rb.expect_end()?;
+                Ok(RewardParams::Fixed {
+                    constant,
+                    epoch_start,
+                })
+            }
             _ => Err(Error::InvalidTag),
         }
     }
@@ -589,6 +760,17 @@ impl ConfigParamVariant for u8 {
     }
 }
 
+impl ConfigParamVariant for ValidationPolicy {
+    fn to_payload(&self) -> Vec<u8> {
+        vec![self.version()]
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        let version = u8::from_payload(payload)?;
+        ValidationPolicy::from_version(version).ok_or(Error::StructureInvalid)
+    }
+}
+
 impl ConfigParamVariant for u64 {
     fn to_payload(&self) -> Vec<u8> {
         self.to_be_bytes().to_vec()
@@ -661,6 +843,7 @@ impl ConfigParamVariant for LinearFee {
             certificate: u64::from_payload(&payload[16..24])?,
             per_certificate_fees: PerCertificateFee::default(),
             per_vote_certificate_fees: PerVoteCertificateFee::default(),
+            per_token: 0,
         })
     }
 }
@@ -728,6 +911,46 @@ impl ConfigParamVariant for PerVoteCertificateFee {
     }
 }
 
+impl ConfigParamVariant for GovernanceFeeDiscount {
+    fn to_payload(&self) -> Vec<u8> {
+        let bb: ByteBuilder<()> = ByteBuilder::new();
+        bb.u16(self.vote_cast.per_mille())
+            .u16(self.vote_cast_batch.per_mille())
+            .u16(self.vote_tally.per_mille())
+            .finalize_as_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() != 3 * 2 {
+            return Err(Error::SizeInvalid);
+        }
+        let mut rb = ReadBuf::from(payload);
+        let mut discount = || FeeDiscount::new(rb.get_u16()?).ok_or(Error::StructureInvalid);
+        Ok(GovernanceFeeDiscount {
+            vote_cast: discount()?,
+            vote_cast_batch: discount()?,
+            vote_tally: discount()?,
+        })
+    }
+}
+
+impl ConfigParamVariant for TreasuryBudget {
+    fn to_payload(&self) -> Vec<u8> {
+        let bb: ByteBuilder<()> = ByteBuilder::new();
+        bb.u32(self.epochs.get()).u64(self.cap.0).finalize_as_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() != 4 + 8 {
+            return Err(Error::SizeInvalid);
+        }
+        let mut rb = ReadBuf::from(payload);
+        let epochs = NonZeroU32::new(rb.get_u32()?).ok_or(Error::StructureInvalid)?;
+        let cap = Value(rb.get_u64()?);
+        Ok(TreasuryBudget { epochs, cap })
+    }
+}
+
 impl ConfigParamVariant for CommitteeId {
     fn to_payload(&self) -> Vec<u8> {
         self.as_ref().to_vec()
@@ -838,27 +1061,37 @@ mod test {
 
     impl Arbitrary for RewardParams {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            if bool::arbitrary(g) {
-                RewardParams::Linear {
+            match u8::arbitrary(g) % 3 {
+                0 => RewardParams::Linear {
                     constant: Arbitrary::arbitrary(g),
                     ratio: Arbitrary::arbitrary(g),
                     epoch_start: Arbitrary::arbitrary(g),
                     epoch_rate: NonZeroU32::new(20).unwrap(),
+                },
+                1 => {
+                    // the settings only accept decreasing halving
+                    let ratio = Ratio::arbitrary(g);
+                    RewardParams::Halving {
+                        constant: Arbitrary::arbitrary(g),
+                        ratio: Ratio {
+                            numerator: std::cmp::min(ratio.numerator, ratio.denominator.get()),
+                            denominator: ratio.denominator,
+                        },
+                        epoch_start: Arbitrary::arbitrary(g),
+                        epoch_rate: NonZeroU32::new(20).unwrap(),
+                    }
                 }
-            } else {
-                RewardParams::Halving {
+                _ => RewardParams::Fixed {
                     constant: Arbitrary::arbitrary(g),
-                    ratio: Arbitrary::arbitrary(g),
                     epoch_start: Arbitrary::arbitrary(g),
-                    epoch_rate: NonZeroU32::new(20).unwrap(),
-                }
+                },
             }
         }
     }
 
     impl Arbitrary for ConfigParam {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            match u8::arbitrary(g) % 29 {
+            match u8::arbitrary(g) % 46 {
                 0 => ConfigParam::Block0Date(Arbitrary::arbitrary(g)),
                 1 => ConfigParam::Discrimination(Arbitrary::arbitrary(g)),
                 2 => ConfigParam::ConsensusVersion(Arbitrary::arbitrary(g)),
@@ -888,6 +1121,30 @@ mod test {
                 26 => ConfigParam::AddCommitteeId(Arbitrary::arbitrary(g)),
                 27 => ConfigParam::RemoveCommitteeId(Arbitrary::arbitrary(g)),
                 28 => ConfigParam::PerCertificateFees(Arbitrary::arbitrary(g)),
+                29 => ConfigParam::BlockContentMaxWeight(Arbitrary::arbitrary(g)),
+                30 => ConfigParam::PoolRegistrationDeposit(Arbitrary::arbitrary(g)),
+                31 => ConfigParam::VotePlanDeposit(Arbitrary::arbitrary(g)),
+                32 => ConfigParam::MaxActiveVotePlansPerCommittee(Arbitrary::arbitrary(g)),
+                33 => ConfigParam::PerTokenFee(Arbitrary::arbitrary(g)),
+                34 => ConfigParam::HandleRegistrationDeposit(Arbitrary::arbitrary(g)),
+                35 => ConfigParam::HandleValidity(Arbitrary::arbitrary(g)),
+                36 => ConfigParam::CanonicalFragmentOrder(Arbitrary::arbitrary(g)),
+                37 => ConfigParam::VotingInactivityThreshold(Arbitrary::arbitrary(g)),
+                38 => ConfigParam::GovernanceFeeDiscount(Arbitrary::arbitrary(g)),
+                39 => ConfigParam::BlockContentSoftMaxSize(Arbitrary::arbitrary(g)),
+                40 => ConfigParam::VoteResultCertification(Arbitrary::arbitrary(g)),
+                41 => ConfigParam::TreasuryBudget(TreasuryBudget {
+                    epochs: NonZeroU32::new(u32::arbitrary(g).max(1)).unwrap(),
+                    cap: Arbitrary::arbitrary(g),
+                }),
+                42 => ConfigParam::CommitteeSortitionSize(Arbitrary::arbitrary(g)),
+                43 => ConfigParam::BindingSignatureContextEpoch(Arbitrary::arbitrary(g)),
+                44 => ConfigParam::HashVersionEpoch(Arbitrary::arbitrary(g)),
+                45 => ConfigParam::ValidationPolicy(if bool::arbitrary(g) {
+                    ValidationPolicy::Strict
+                } else {
+                    ValidationPolicy::Legacy
+                }),
                 _ => unreachable!(),
             }
         }
@@ -1,8 +1,8 @@
 use chain_core::property;
-use chain_time::era::EpochPosition;
-use chain_time::era::TimeEra;
+use chain_time::era::{EpochPosition, EpochSlotOffset, TimeEra};
+use chain_time::{Epoch as TimeEpoch, Slot};
 
-use std::{error, fmt, num::ParseIntError, str};
+use std::{convert::TryFrom, error, fmt, num::ParseIntError, ops::Range, str};
 
 /// Non unique identifier of the transaction position in the
 /// blockchain. There may be many transactions related to the same
@@ -49,6 +49,113 @@ impl BlockDate {
             slot_id: 0,
         }
     }
+
+    /// The date standing for this one in epochs of `slots_per_epoch` slots.
+    ///
+    /// A date recorded before a change of the epoch length may have a slot
+    /// beyond the end of its epoch, it stands for the start of the following
+    /// epoch: every valid date of the new length compares with both dates
+    /// the same way.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn remap_to_epoch_length(self, slots_per_epoch: u32) -> BlockDate {
+        if self.slot_id < slots_per_epoch {
+            self
+        } else {
+            self.next_epoch()
+        }
+    }
+
+    /// The flat slot of this date, in the given eras sorted by their first
+    /// epoch, none if the date is before the first era.
+    ///
+    /// Each date is placed in the era its epoch belongs to, a slot beyond the
+    /// end of its epoch standing for the start of the following epoch, see
+    /// [`BlockDate::remap_to_epoch_length`].
+    pub fn to_slot(self, eras: &[TimeEra]) -> Option<Slot> {
+        let era = era_of(eras, self.epoch)?;
+        let date = self.remap_to_epoch_length(era.slots_per_epoch());
+        let era = era_of(eras, date.epoch)?;
+        Some(era.from_era_to_slot(EpochPosition {
+            epoch: TimeEpoch(date.epoch),
+            slot: EpochSlotOffset(date.slot_id),
+        }))
+    }
+
+    /// Number of slots from this date to `other`, in the given eras sorted
+    /// by their first epoch. None if `other` is before this date or if one
+    /// of the dates is before the first era.
+    pub fn slots_until(self, other: BlockDate, eras: &[TimeEra]) -> Option<u64> {
+        other.to_slot(eras)?.0.checked_sub(self.to_slot(eras)?.0)
+    }
+
+    /// The epochs without any block between a block at this date and the
+    /// following block at `next`.
+    pub fn missed_epochs(self, next: BlockDate) -> Range<Epoch> {
+        let first = self.epoch.saturating_add(1);
+        first..next.epoch.max(first)
+    }
+
+    /// The slots without any block between a block at this date and the
+    /// following block at `next`, in order, in the given eras sorted by
+    /// their first epoch.
+    pub fn missed_slots(self, next: BlockDate, eras: &[TimeEra]) -> MissedSlots<'_> {
+        MissedSlots {
+            last: self,
+            next,
+            remaining: self
+                .slots_until(next, eras)
+                .map_or(0, |slots| slots.saturating_sub(1)),
+            eras,
+        }
+    }
+}
+
+/// The era of `eras`, sorted by their first epoch, containing `epoch`
+fn era_of(eras: &[TimeEra], epoch: Epoch) -> Option<&TimeEra> {
+    eras.iter()
+        .rev()
+        .find(|era| era.epoch_start() <= TimeEpoch(epoch))
+}
+
+/// Iterator over the slots without block between two blocks, see
+/// [`BlockDate::missed_slots`]
+#[derive(Debug, Clone)]
+pub struct MissedSlots<'a> {
+    last: BlockDate,
+    next: BlockDate,
+    remaining: u64,
+    eras: &'a [TimeEra],
+}
+
+impl<'a> Iterator for MissedSlots<'a> {
+    type Item = BlockDate;
+
+    fn next(&mut self) -> Option<BlockDate> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        // the era is known: the slots are only counted when both dates are
+        // in the eras
+        let slots_per_epoch = era_of(self.eras, self.last.epoch)?.slots_per_epoch();
+        self.last = if self.last.slot_id + 1 >= slots_per_epoch {
+            self.last.next_epoch()
+        } else {
+            BlockDate {
+                epoch: self.last.epoch,
+                slot_id: self.last.slot_id + 1,
+            }
+        };
+        debug_assert!(self.last < self.next);
+        Some(self.last)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.remaining) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
 }
 
 impl From<EpochPosition> for BlockDate {
@@ -162,6 +269,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn missed_epochs_and_slots() {
+        use chain_time::{Epoch as TimeEpoch, Slot, TimeEra};
+
+        let era = TimeEra::new(Slot(0), TimeEpoch(0), 3);
+        let date = |epoch, slot_id| BlockDate { epoch, slot_id };
+
+        assert_eq!(date(1, 2).missed_epochs(date(2, 0)), 2..2);
+        assert_eq!(date(1, 2).missed_epochs(date(4, 1)), 2..4);
+        assert_eq!(date(1, 0).missed_epochs(date(1, 2)).count(), 0);
+
+        let eras = [era];
+        assert_eq!(date(1, 2).slots_until(date(2, 0), &eras), Some(1));
+        assert_eq!(date(1, 2).slots_until(date(1, 1), &eras), None);
+        assert_eq!(date(1, 2).missed_slots(date(2, 0), &eras).count(), 0);
+        assert_eq!(
+            date(1, 1)
+                .missed_slots(date(3, 1), &eras)
+                .collect::<Vec<_>>(),
+            vec![date(1, 2), date(2, 0), date(2, 1), date(2, 2), date(3, 0)]
+        );
+    }
+
+    #[test]
+    fn missed_slots_across_an_epoch_length_change() {
+        use chain_time::{Epoch as TimeEpoch, Slot, TimeEra};
+
+        let era = TimeEra::new(Slot(0), TimeEpoch(0), 3);
+        let eras = [era.clone(), era.with_epoch_length(TimeEpoch(2), 5)];
+        let date = |epoch, slot_id| BlockDate { epoch, slot_id };
+
+        assert_eq!(date(1, 1).to_slot(&eras), Some(Slot(4)));
+        assert_eq!(date(2, 4).to_slot(&eras), Some(Slot(10)));
+        // a slot recorded with the previous length stands for the next epoch
+        assert_eq!(date(1, 4).to_slot(&eras), date(2, 0).to_slot(&eras));
+        assert_eq!(date(1, 1).slots_until(date(3, 1), &eras), Some(8));
+        assert_eq!(
+            date(1, 1)
+                .missed_slots(date(3, 1), &eras)
+                .collect::<Vec<_>>(),
+            vec![
+                date(1, 2),
+                date(2, 0),
+                date(2, 1),
+                date(2, 2),
+                date(2, 3),
+                date(2, 4),
+                date(3, 0)
+            ]
+        );
+
+        let eras = [era.with_epoch_length(TimeEpoch(2), 5)];
+        assert_eq!(date(1, 1).to_slot(&eras), None);
+        assert_eq!(date(1, 1).missed_slots(date(3, 1), &eras).count(), 0);
+    }
+
     impl Arbitrary for BlockDate {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
             BlockDate {
@@ -4,7 +4,8 @@ use crate::value::Value;
 use std::num::NonZeroU64;
 
 /// Linear fee using the basic affine formula
-/// `COEFFICIENT * bytes(COUNT(tx.inputs) + COUNT(tx.outputs)) + CONSTANT + CERTIFICATE*COUNT(certificates)`.
+/// `COEFFICIENT * bytes(COUNT(tx.inputs) + COUNT(tx.outputs)) + CONSTANT + CERTIFICATE*COUNT(certificates)
+///  + PER_TOKEN*COUNT(distinct tokens in tx.outputs)`.
 #[derive(PartialEq, Eq, PartialOrd, Debug, Clone, Copy)]
 pub struct LinearFee {
     pub constant: u64,
@@ -12,6 +13,8 @@ pub struct LinearFee {
     pub certificate: u64,
     pub per_certificate_fees: PerCertificateFee,
     pub per_vote_certificate_fees: PerVoteCertificateFee,
+    pub per_token: u64,
+    pub governance_fee_discount: GovernanceFeeDiscount,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Debug, Clone, Copy, Default)]
@@ -27,6 +30,23 @@ pub struct PerVoteCertificateFee {
     pub certificate_vote_cast: Option<NonZeroU64>,
 }
 
+/// Discount on the whole fee of a transaction, in thousandths of the fee
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Default, Hash)]
+pub struct FeeDiscount(u16);
+
+/// Discounts on the fees of the transactions carrying governance
+/// certificates, so that voting is not cost-prohibitive for small accounts.
+///
+/// The votes can only be cast during the voting window of their vote plan,
+/// so is the discount on the ballots.
+#[derive(PartialEq, Eq, PartialOrd, Debug, Clone, Copy, Default)]
+pub struct GovernanceFeeDiscount {
+    pub vote_cast: FeeDiscount,
+    pub vote_cast_batch: FeeDiscount,
+    /// applies to the tallies of the public and the private vote plans
+    pub vote_tally: FeeDiscount,
+}
+
 impl LinearFee {
     pub fn new(constant: u64, coefficient: u64, certificate: u64) -> Self {
         LinearFee {
@@ -35,6 +55,8 @@ impl LinearFee {
             certificate,
             per_certificate_fees: PerCertificateFee::default(),
             per_vote_certificate_fees: PerVoteCertificateFee::default(),
+            per_token: 0,
+            governance_fee_discount: GovernanceFeeDiscount::default(),
         }
     }
 
@@ -45,6 +67,14 @@ impl LinearFee {
     pub fn per_vote_certificate_fees(&mut self, per_vote_certificate_fees: PerVoteCertificateFee) {
         self.per_vote_certificate_fees = per_vote_certificate_fees;
     }
+
+    pub fn per_token(&mut self, per_token: u64) {
+        self.per_token = per_token;
+    }
+
+    pub fn governance_fee_discount(&mut self, governance_fee_discount: GovernanceFeeDiscount) {
+        self.governance_fee_discount = governance_fee_discount;
+    }
 }
 
 impl PerCertificateFee {
@@ -65,10 +95,11 @@ impl PerCertificateFee {
             CertificateSlice::PoolRegistration(_) => {
                 self.certificate_pool_registration.map(|v| Value(v.get()))
             }
-            CertificateSlice::StakeDelegation(_) => {
+            CertificateSlice::StakeDelegation(_) | CertificateSlice::DeferredStakeDelegation(_) => {
                 self.certificate_stake_delegation.map(|v| Value(v.get()))
             }
-            CertificateSlice::OwnerStakeDelegation(_) => self
+            CertificateSlice::OwnerStakeDelegation(_)
+            | CertificateSlice::DeferredOwnerStakeDelegation(_) => self
                 .certificate_owner_stake_delegation
                 .map(|v| Value(v.get())),
             _ => None,
@@ -91,27 +122,105 @@ impl PerVoteCertificateFee {
         match cert {
             CertificateSlice::VotePlan(_) => self.certificate_vote_plan.map(|v| Value(v.get())),
             CertificateSlice::VoteCast(_) => self.certificate_vote_cast.map(|v| Value(v.get())),
+            // every ballot of a batch pays the fee of a vote cast
+            CertificateSlice::VoteCastBatch(batch) => {
+                let ballots = batch.nb_ballots() as u64;
+                self.certificate_vote_cast
+                    .map(|v| Value(v.get().saturating_mul(ballots)))
+            }
             _ => None,
         }
     }
 }
 
+impl FeeDiscount {
+    pub const NONE: Self = FeeDiscount(0);
+    pub const EXEMPTION: Self = FeeDiscount(1000);
+
+    /// None if the discount is more than 1000 thousandths
+    pub fn new(per_mille: u16) -> Option<Self> {
+        if per_mille <= Self::EXEMPTION.0 {
+            Some(FeeDiscount(per_mille))
+        } else {
+            None
+        }
+    }
+
+    pub fn per_mille(self) -> u16 {
+        self.0
+    }
+
+    /// The discounted fee, rounded up
+    pub fn apply(self, fee: Value) -> Value {
+        let discount = (fee.0 as u128 * self.0 as u128 / 1000) as u64;
+        Value(fee.0 - discount)
+    }
+}
+
+impl GovernanceFeeDiscount {
+    pub fn new(
+        vote_cast: FeeDiscount,
+        vote_cast_batch: FeeDiscount,
+        vote_tally: FeeDiscount,
+    ) -> Self {
+        Self {
+            vote_cast,
+            vote_cast_batch,
+            vote_tally,
+        }
+    }
+
+    fn discount_for_certificate<'a>(&self, cert: &CertificateSlice<'a>) -> FeeDiscount {
+        match cert {
+            CertificateSlice::VoteCast(_) => self.vote_cast,
+            CertificateSlice::VoteCastBatch(_) => self.vote_cast_batch,
+            CertificateSlice::VoteTally(_) | CertificateSlice::EncryptedVoteTally(_) => {
+                self.vote_tally
+            }
+            _ => FeeDiscount::NONE,
+        }
+    }
+}
+
 pub trait FeeAlgorithm {
     fn baseline(&self) -> Value;
     fn fees_for_inputs_outputs(&self, inputs: u8, outputs: u8) -> Value;
     fn fees_for_certificate(&self, cert: CertificateSlice) -> Value;
+    fn fees_for_tokens(&self, distinct_tokens: usize) -> Value;
+
+    /// Discount on the whole fee of a transaction carrying the certificate
+    fn discount_for_certificate(&self, _cert: &CertificateSlice) -> FeeDiscount {
+        FeeDiscount::NONE
+    }
 
     fn calculate(&self, cert: Option<CertificateSlice>, inputs: u8, outputs: u8) -> Value {
-        self.baseline()
+        self.calculate_with_tokens(cert, inputs, outputs, 0)
+    }
+
+    fn calculate_with_tokens(
+        &self,
+        cert: Option<CertificateSlice>,
+        inputs: u8,
+        outputs: u8,
+        distinct_tokens: usize,
+    ) -> Value {
+        let discount = cert
+            .as_ref()
+            .map_or(FeeDiscount::NONE, |c| self.discount_for_certificate(c));
+        let fee = self
+            .baseline()
             .saturating_add(self.fees_for_inputs_outputs(inputs, outputs))
-            .saturating_add(cert.map_or(Value::zero(), |c| self.fees_for_certificate(c)))
+            .saturating_add(self.fees_for_tokens(distinct_tokens))
+            .saturating_add(cert.map_or(Value::zero(), |c| self.fees_for_certificate(c)));
+        discount.apply(fee)
     }
 
     fn calculate_tx<P: tx::Payload>(&self, tx: &tx::TransactionSlice<P>) -> Value {
-        self.calculate(
+        self.calculate_with_tokens(
             tx.payload().into_certificate_slice(),
             tx.nb_inputs(),
             tx.nb_outputs(),
+            tx.outputs().nb_distinct_tokens(),
         )
     }
 }
@@ -135,6 +244,14 @@ impl FeeAlgorithm for LinearFee {
             .fees_for_certificate(&cert_slice);
         f1.or(f2).unwrap_or(Value(self.certificate))
     }
+
+    fn fees_for_tokens(&self, distinct_tokens: usize) -> Value {
+        Value(self.per_token.saturating_mul(distinct_tokens as u64))
+    }
+
+    fn discount_for_certificate(&self, cert: &CertificateSlice) -> FeeDiscount {
+        self.governance_fee_discount.discount_for_certificate(cert)
+    }
 }
 
 #[cfg(any(test, feature = "property-test-api"))]
@@ -174,10 +291,28 @@ mod test {
                 certificate: Arbitrary::arbitrary(g),
                 per_certificate_fees: PerCertificateFee::new(None, None, None),
                 per_vote_certificate_fees: PerVoteCertificateFee::new(None, None),
+                per_token: 0,
+                governance_fee_discount: GovernanceFeeDiscount::default(),
             }
         }
     }
 
+    impl Arbitrary for FeeDiscount {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            FeeDiscount(u16::arbitrary(g) % (FeeDiscount::EXEMPTION.0 + 1))
+        }
+    }
+
+    impl Arbitrary for GovernanceFeeDiscount {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Self::new(
+                Arbitrary::arbitrary(g),
+                Arbitrary::arbitrary(g),
+                Arbitrary::arbitrary(g),
+            )
+        }
+    }
+
     #[quickcheck]
     pub fn linear_fee_certificate_calculation(
         certificate: Certificate,
@@ -217,6 +352,44 @@ mod test {
         }
     }
 
+    #[quickcheck]
+    pub fn governance_fee_discount_calculation(
+        certificate: Certificate,
+        inputs: u8,
+        outputs: u8,
+        mut fee: LinearFee,
+        discount: FeeDiscount,
+    ) -> TestResult {
+        let certificate_payload: CertificatePayload = (&certificate).into();
+        let full_fee = fee.calculate(Some(certificate_payload.as_slice()), inputs, outputs);
+        fee.governance_fee_discount(GovernanceFeeDiscount::new(discount, discount, discount));
+        let fee_value = fee.calculate(Some(certificate_payload.as_slice()), inputs, outputs);
+
+        let expected_value = match certificate {
+            Certificate::VoteCast(_)
+            | Certificate::VoteCastBatch(_)
+            | Certificate::VoteTally(_)
+            | Certificate::EncryptedVoteTally(_) => {
+                let discounted = full_fee.0 as u128 * (1000 - discount.0 as u128);
+                Value(((discounted + 999) / 1000) as u64)
+            }
+            _ => full_fee,
+        };
+        if fee_value == expected_value {
+            TestResult::passed()
+        } else {
+            TestResult::error(format!("Wrong fee: {} vs {}", fee_value, expected_value))
+        }
+    }
+
+    #[test]
+    fn fee_discount_bounds() {
+        assert_eq!(FeeDiscount::new(1001), None);
+        assert_eq!(FeeDiscount::EXEMPTION.apply(Value(u64::MAX)), Value::zero());
+        assert_eq!(FeeDiscount::NONE.apply(Value(u64::MAX)), Value(u64::MAX));
+        assert_eq!(FeeDiscount::new(500).unwrap().apply(Value(3)), Value(2));
+    }
+
     #[cfg(test)]
     fn calculate_expected_cert_fee_value(certificate: &Certificate, fee: &LinearFee) -> u64 {
         let cert_fees = fee.per_certificate_fees;
@@ -225,14 +398,19 @@ mod test {
             Certificate::PoolRegistration { .. } => {
                 cert_fees.certificate_pool_registration.unwrap().into()
             }
-            Certificate::StakeDelegation { .. } => {
+            Certificate::StakeDelegation { .. } | Certificate::DeferredStakeDelegation { .. } => {
                 cert_fees.certificate_stake_delegation.unwrap().into()
             }
-            Certificate::OwnerStakeDelegation { .. } => {
+            Certificate::OwnerStakeDelegation { .. }
+            | Certificate::DeferredOwnerStakeDelegation { .. } => {
                 cert_fees.certificate_owner_stake_delegation.unwrap().into()
             }
             Certificate::VotePlan { .. } => vote_cert_fees.certificate_vote_plan.unwrap().into(),
             Certificate::VoteCast { .. } => vote_cert_fees.certificate_vote_cast.unwrap().into(),
+            Certificate::VoteCastBatch(batch) => {
+                u64::from(vote_cert_fees.certificate_vote_cast.unwrap())
+                    .saturating_mul(batch.ballots().len() as u64)
+            }
             _ => fee.certificate,
         }
     }
@@ -0,0 +1,265 @@
+//! Admission of the fragments in the mempool of a node.
+//!
+//! A fragment valid for the ledger may still be refused by a node, to
+//! protect its mempool from spam. The [`FragmentAdmission`] policy is
+//! consulted by [`Ledger::admit_fragment`] before the fragment is validated
+//! against the ledger, so that the cheap checks come first, and is told about
+//! the fragments finally admitted, to keep track of the rate of submission.
+//!
+//! [`DefaultAdmission`] is the reference policy, shared by the node
+//! implementations so that their mempools accept the same fragments.
+//!
+//! [`Ledger::admit_fragment`]: crate::ledger::Ledger::admit_fragment
+
+use super::{order::spent_accounts, selection::fee_paid, Fragment};
+use crate::{
+    date::{BlockDate, Epoch},
+    ledger::{self, LedgerParameters},
+    value::Value,
+};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The number of fragments spending from an account admitted per epoch by
+/// [`DefaultAdmission`], unless configured otherwise
+pub const DEFAULT_MAX_PER_ACCOUNT_PER_EPOCH: usize = 1_000;
+
+/// What the admission of a fragment is decided upon, besides the fragment
+pub struct AdmissionContext<'a> {
+    pub ledger_params: &'a LedgerParameters,
+    /// the date of the block the fragment is validated for
+    pub block_date: BlockDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AdmissionRejection {
+    #[error("the fragment is {size} bytes long, at most {max} bytes are accepted")]
+    TooLarge { size: u64, max: u64 },
+    #[error("the fragment pays a fee of {paid}, at least {required} is required")]
+    FeeTooLow { paid: Value, required: Value },
+    #[error("{0} fragments spending from the account were already accepted in the epoch")]
+    RateExceeded(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum AdmissionError {
+    #[error("the fragment is refused by the admission policy")]
+    Rejected(#[from] AdmissionRejection),
+    #[error("the fragment is invalid")]
+    Invalid(#[from] ledger::Error),
+}
+
+/// Policy of admission of the fragments in the mempool, on top of their
+/// validity
+pub trait FragmentAdmission {
+    /// Check the fragment before its validation against the ledger
+    fn check(
+        &self,
+        fragment: &Fragment,
+        context: &AdmissionContext,
+    ) -> Result<(), AdmissionRejection>;
+
+    /// Record a fragment which passed the check and the validation
+    fn admitted(&mut self, fragment: &Fragment, context: &AdmissionContext);
+}
+
+/// The reference admission policy:
+///
+/// * a fragment has to fit in a block, and in the configured size limit;
+/// * the transactions have to pay at least the fee floor plus the fee per
+///   byte of their size, the update proposals and votes, which carry no
+///   transaction, are not subject to the fee floor;
+/// * a limited number of fragments spending from the same account are
+///   admitted per epoch.
+#[derive(Debug, Clone)]
+pub struct DefaultAdmission {
+    max_fragment_size: Option<u64>,
+    fee_floor: Value,
+    fee_per_byte: u64,
+    max_per_account_per_epoch: usize,
+    epoch: Epoch,
+    spent: HashMap<[u8; 32], usize>,
+}
+
+impl Default for DefaultAdmission {
+    fn default() -> Self {
+        DefaultAdmission {
+            max_fragment_size: None,
+            fee_floor: Value::zero(),
+            fee_per_byte: 0,
+            max_per_account_per_epoch: DEFAULT_MAX_PER_ACCOUNT_PER_EPOCH,
+            epoch: 0,
+            spent: HashMap::new(),
+        }
+    }
+}
+
+impl DefaultAdmission {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_fragment_size(mut self, max_fragment_size: u64) -> Self {
+        self.max_fragment_size = Some(max_fragment_size);
+        self
+    }
+
+    pub fn with_fee_floor(mut self, fee_floor: Value, fee_per_byte: u64) -> Self {
+        self.fee_floor = fee_floor;
+        self.fee_per_byte = fee_per_byte;
+        self
+    }
+
+    pub fn with_max_per_account_per_epoch(mut self, max_per_account_per_epoch: usize) -> Self {
+        self.max_per_account_per_epoch = max_per_account_per_epoch;
+        self
+    }
+
+    /// The number of fragments spending from the account admitted in the
+    /// given epoch
+    fn spent_in(&self, account: &[u8; 32], epoch: Epoch) -> usize {
+        if epoch == self.epoch {
+            self.spent.get(account).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+impl FragmentAdmission for DefaultAdmission {
+    fn check(
+        &self,
+        fragment: &Fragment,
+        context: &AdmissionContext,
+    ) -> Result<(), AdmissionRejection> {
+        let size = fragment.to_raw().size_bytes_plus_size() as u64;
+        let max = self
+            .max_fragment_size
+            .unwrap_or(u64::MAX)
+            .min(context.ledger_params.block_content_max_size as u64);
+        if size > max {
+            return Err(AdmissionRejection::TooLarge { size, max });
+        }
+
+        if !matches!(
+            fragment,
+            Fragment::UpdateProposal(_) | Fragment::UpdateVote(_)
+        ) {
+            let required = Value(
+                self.fee_floor
+                    .0
+                    .saturating_add(self.fee_per_byte.saturating_mul(size)),
+            );
+            let paid = fee_paid(fragment);
+            if paid < required {
+                return Err(AdmissionRejection::FeeTooLow { paid, required });
+            }
+        }
+
+        let epoch = context.block_date.epoch;
+        for account in spent_accounts(fragment) {
+            let spent = self.spent_in(&account, epoch);
+            if spent >= self.max_per_account_per_epoch {
+                return Err(AdmissionRejection::RateExceeded(spent));
+            }
+        }
+        Ok(())
+    }
+
+    fn admitted(&mut self, fragment: &Fragment, context: &AdmissionContext) {
+        let epoch = context.block_date.epoch;
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.spent.clear();
+        }
+        for account in spent_accounts(fragment) {
+            *self.spent.entry(account).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fee::LinearFee,
+        testing::{
+            builders::TestTxBuilder,
+            data::{AddressDataValue, Wallet},
+            ledger::{ConfigBuilder, TestLedger},
+            scenario::{prepare_scenario, wallet},
+        },
+    };
+
+    fn pay(ledger: &mut TestLedger, from: &Wallet, to: &Wallet, fee: u64) -> Fragment {
+        TestTxBuilder::new(ledger.block0_hash)
+            .move_funds_multiple(
+                ledger,
+                &[AddressDataValue::new(from.as_account_data(), Value(100))],
+                &[AddressDataValue::new(
+                    to.as_account_data(),
+                    Value(100 - fee),
+                )],
+            )
+            .get_fragment()
+    }
+
+    #[test]
+    fn admission_checks_the_policy_then_the_validity() {
+        let (mut ledger, controller) = prepare_scenario()
+            .with_config(ConfigBuilder::new(0).with_fee(LinearFee::new(2, 0, 0)))
+            .with_initials(vec![wallet("Alice").with(1_000), wallet("Bob").with(1_000)])
+            .build()
+            .unwrap();
+        let alice = controller.wallet("Alice").unwrap();
+        let bob = controller.wallet("Bob").unwrap();
+        let date = ledger.date();
+        let mut admission = DefaultAdmission::new()
+            .with_fee_floor(Value(3), 0)
+            .with_max_per_account_per_epoch(1);
+
+        let valid = pay(&mut ledger, &alice, &bob, 2);
+        assert!(matches!(
+            ledger
+                .ledger
+                .admit_fragment(&mut admission, &ledger.parameters, &valid, date),
+            Err(AdmissionError::Rejected(
+                AdmissionRejection::FeeTooLow { .. }
+            ))
+        ));
+
+        let mut admission = admission.with_fee_floor(Value(1), 0);
+        let invalid = pay(&mut ledger, &alice, &bob, 1);
+        assert!(matches!(
+            ledger
+                .ledger
+                .admit_fragment(&mut admission, &ledger.parameters, &invalid, date),
+            Err(AdmissionError::Invalid(_))
+        ));
+        // the invalid fragment does not count towards the rate of Alice
+        ledger
+            .ledger
+            .admit_fragment(&mut admission, &ledger.parameters, &valid, date)
+            .unwrap();
+
+        let context = AdmissionContext {
+            ledger_params: &ledger.parameters,
+            block_date: date,
+        };
+        assert_eq!(
+            admission.check(&valid, &context),
+            Err(AdmissionRejection::RateExceeded(1))
+        );
+        let next_epoch = AdmissionContext {
+            ledger_params: &ledger.parameters,
+            block_date: date.next_epoch(),
+        };
+        assert_eq!(admission.check(&valid, &next_epoch), Ok(()));
+
+        let admission = DefaultAdmission::new().with_max_fragment_size(10);
+        assert!(matches!(
+            admission.check(&valid, &context),
+            Err(AdmissionRejection::TooLarge { max: 10, .. })
+        ));
+    }
+}
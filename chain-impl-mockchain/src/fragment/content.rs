@@ -1,4 +1,5 @@
-use crate::fragment::Fragment;
+use super::order::canonical_order;
+use crate::fragment::{BlockContentWeight, Fragment};
 use crate::key::Hash;
 use chain_core::property::Serialize;
 use std::slice;
@@ -54,6 +55,23 @@ impl Contents {
     pub fn compute_hash(&self) -> BlockContentHash {
         self.compute_hash_size().0
     }
+
+    /// Total weight of the fragments, see [`Fragment::weight`]
+    pub fn compute_weight(&self) -> BlockContentWeight {
+        self.iter().fold(0, |weight, fragment| {
+            weight.saturating_add(fragment.weight())
+        })
+    }
+
+    /// Index of the first fragment which is not at its place in the
+    /// canonical order, none if the fragments are in the canonical order
+    pub fn non_canonical_index(&self) -> Option<usize> {
+        canonical_order(&self.0)
+            .into_iter()
+            .enumerate()
+            .find(|(position, index)| position != index)
+            .map(|(position, _)| position)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -80,4 +98,16 @@ impl ContentsBuilder {
         self.fragments.extend(fragments);
         self
     }
+
+    /// Put the fragments in their canonical order, keeping the order of the
+    /// fragments depending on each other
+    pub fn sort_canonically(&mut self) -> &mut Self {
+        let order = canonical_order(&self.fragments);
+        let mut fragments: Vec<Option<Fragment>> = self.fragments.drain(..).map(Some).collect();
+        self.fragments = order
+            .into_iter()
+            .filter_map(|index| fragments[index].take())
+            .collect();
+        self
+    }
 }
@@ -1,19 +1,32 @@
+mod admission;
 pub mod config;
 mod content;
+mod order;
 mod raw;
+mod selection;
+mod weight;
 
 use crate::legacy;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
 
+pub use admission::{
+    AdmissionContext, AdmissionError, AdmissionRejection, DefaultAdmission, FragmentAdmission,
+    DEFAULT_MAX_PER_ACCOUNT_PER_EPOCH,
+};
 pub use config::ConfigParams;
-pub use raw::{FragmentId, FragmentRaw};
+pub use raw::{FragmentId, FragmentRaw, FRAGMENT_ID_DOMAIN};
 
 pub use content::{BlockContentHash, BlockContentSize, Contents, ContentsBuilder};
+pub use selection::{fee_paid, select_by_fee_density, FragmentSelection};
+pub use weight::{
+    BlockContentWeight, BALLOT_OPTION_WEIGHT, BASE_WEIGHT, DECRYPT_SHARE_WEIGHT, SIGNATURE_WEIGHT,
+};
 
 use crate::{
     certificate,
-    transaction::{NoExtra, Transaction},
+    key::HashVersion,
+    transaction::{NoExtra, Transaction, TransactionLayout},
     update::{SignedUpdateProposal, SignedUpdateVote},
 };
 
@@ -43,6 +56,47 @@ pub enum Fragment {
     VoteCast(Transaction<certificate::VoteCast>),
     VoteTally(Transaction<certificate::VoteTally>),
     EncryptedVoteTally(Transaction<certificate::EncryptedVoteTally>),
+    VoteCastBatch(Transaction<certificate::VoteCastBatch>),
+    HandleRegistration(Transaction<certificate::HandleRegistration>),
+    VotingPowerSnapshot(Transaction<certificate::VotingPowerSnapshot>),
+    PoolKeysRotation(Transaction<certificate::PoolKeysRotation>),
+    VoteResultCertification(Transaction<certificate::VoteResultCertification>),
+    CommitteeCandidacy(Transaction<certificate::CommitteeCandidacy>),
+    DeferredStakeDelegation(Transaction<certificate::DeferredStakeDelegation>),
+    DeferredOwnerStakeDelegation(Transaction<certificate::DeferredOwnerStakeDelegation>),
+}
+
+macro_rules! fragment_from_transaction {
+    ($($payload:ty => $variant:ident,)*) => {
+        $(
+            impl From<Transaction<$payload>> for Fragment {
+                fn from(tx: Transaction<$payload>) -> Self {
+                    Fragment::$variant(tx)
+                }
+            }
+        )*
+    };
+}
+
+fragment_from_transaction! {
+    NoExtra => Transaction,
+    certificate::OwnerStakeDelegation => OwnerStakeDelegation,
+    certificate::StakeDelegation => StakeDelegation,
+    certificate::PoolRegistration => PoolRegistration,
+    certificate::PoolRetirement => PoolRetirement,
+    certificate::PoolUpdate => PoolUpdate,
+    certificate::VotePlan => VotePlan,
+    certificate::VoteCast => VoteCast,
+    certificate::VoteTally => VoteTally,
+    certificate::EncryptedVoteTally => EncryptedVoteTally,
+    certificate::VoteCastBatch => VoteCastBatch,
+    certificate::HandleRegistration => HandleRegistration,
+    certificate::VotingPowerSnapshot => VotingPowerSnapshot,
+    certificate::PoolKeysRotation => PoolKeysRotation,
+    certificate::VoteResultCertification => VoteResultCertification,
+    certificate::CommitteeCandidacy => CommitteeCandidacy,
+    certificate::DeferredStakeDelegation => DeferredStakeDelegation,
+    certificate::DeferredOwnerStakeDelegation => DeferredOwnerStakeDelegation,
 }
 
 impl PartialEq for Fragment {
@@ -52,6 +106,10 @@ impl PartialEq for Fragment {
 }
 impl Eq for Fragment {}
 
+/// Flag of the fragment tag marking a transaction serialized with the layout
+/// [`TransactionLayout::V1`]
+const TRANSACTION_LAYOUT_V1_FLAG: u8 = 0x80;
+
 /// Tag enumeration of all known fragment
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum FragmentTag {
@@ -69,6 +127,14 @@ pub(super) enum FragmentTag {
     VoteCast = 11,
     VoteTally = 12,
     EncryptedVoteTally = 13,
+    VoteCastBatch = 14,
+    HandleRegistration = 15,
+    VotingPowerSnapshot = 16,
+    PoolKeysRotation = 17,
+    VoteResultCertification = 18,
+    CommitteeCandidacy = 19,
+    DeferredStakeDelegation = 20,
+    DeferredOwnerStakeDelegation = 21,
 }
 
 impl FragmentTag {
@@ -88,6 +154,14 @@ impl FragmentTag {
             11 => Some(FragmentTag::VoteCast),
             12 => Some(FragmentTag::VoteTally),
             13 => Some(FragmentTag::EncryptedVoteTally),
+            14 => Some(FragmentTag::VoteCastBatch),
+            15 => Some(FragmentTag::HandleRegistration),
+            16 => Some(FragmentTag::VotingPowerSnapshot),
+            17 => Some(FragmentTag::PoolKeysRotation),
+            18 => Some(FragmentTag::VoteResultCertification),
+            19 => Some(FragmentTag::CommitteeCandidacy),
+            20 => Some(FragmentTag::DeferredStakeDelegation),
+            21 => Some(FragmentTag::DeferredOwnerStakeDelegation),
             _ => None,
         }
     }
@@ -111,6 +185,43 @@ impl Fragment {
             Fragment::VoteCast(_) => FragmentTag::VoteCast,
             Fragment::VoteTally(_) => FragmentTag::VoteTally,
             Fragment::EncryptedVoteTally(_) => FragmentTag::EncryptedVoteTally,
+            Fragment::VoteCastBatch(_) => FragmentTag::VoteCastBatch,
+            Fragment::HandleRegistration(_) => FragmentTag::HandleRegistration,
+            Fragment::VotingPowerSnapshot(_) => FragmentTag::VotingPowerSnapshot,
+            Fragment::PoolKeysRotation(_) => FragmentTag::PoolKeysRotation,
+            Fragment::VoteResultCertification(_) => FragmentTag::VoteResultCertification,
+            Fragment::CommitteeCandidacy(_) => FragmentTag::CommitteeCandidacy,
+            Fragment::DeferredStakeDelegation(_) => FragmentTag::DeferredStakeDelegation,
+            Fragment::DeferredOwnerStakeDelegation(_) => FragmentTag::DeferredOwnerStakeDelegation,
+        }
+    }
+
+    /// The layout of the transaction of the fragment, the legacy one for the
+    /// fragments without transaction
+    fn transaction_layout(&self) -> TransactionLayout {
+        match self {
+            Fragment::Initial(_)
+            | Fragment::OldUtxoDeclaration(_)
+            | Fragment::UpdateProposal(_)
+            | Fragment::UpdateVote(_) => TransactionLayout::Legacy,
+            Fragment::Transaction(tx) => tx.layout(),
+            Fragment::OwnerStakeDelegation(tx) => tx.layout(),
+            Fragment::StakeDelegation(tx) => tx.layout(),
+            Fragment::PoolRegistration(tx) => tx.layout(),
+            Fragment::PoolRetirement(tx) => tx.layout(),
+            Fragment::PoolUpdate(tx) => tx.layout(),
+            Fragment::VotePlan(tx) => tx.layout(),
+            Fragment::VoteCast(tx) => tx.layout(),
+            Fragment::VoteTally(tx) => tx.layout(),
+            Fragment::EncryptedVoteTally(tx) => tx.layout(),
+            Fragment::VoteCastBatch(tx) => tx.layout(),
+            Fragment::HandleRegistration(tx) => tx.layout(),
+            Fragment::VotingPowerSnapshot(tx) => tx.layout(),
+            Fragment::PoolKeysRotation(tx) => tx.layout(),
+            Fragment::VoteResultCertification(tx) => tx.layout(),
+            Fragment::CommitteeCandidacy(tx) => tx.layout(),
+            Fragment::DeferredStakeDelegation(tx) => tx.layout(),
+            Fragment::DeferredOwnerStakeDelegation(tx) => tx.layout(),
         }
     }
 
@@ -121,7 +232,11 @@ impl Fragment {
         let v = Vec::new();
         let mut codec = Codec::new(v);
         codec.put_u8(0).unwrap();
-        codec.put_u8(self.get_tag() as u8).unwrap();
+        let tag = match self.transaction_layout() {
+            TransactionLayout::Legacy => self.get_tag() as u8,
+            TransactionLayout::V1 => self.get_tag() as u8 | TRANSACTION_LAYOUT_V1_FLAG,
+        };
+        codec.put_u8(tag).unwrap();
         match self {
             Fragment::Initial(i) => i.serialize(&mut codec).unwrap(),
             Fragment::OldUtxoDeclaration(s) => s.serialize(&mut codec).unwrap(),
@@ -137,6 +252,16 @@ impl Fragment {
             Fragment::VoteCast(vote_plan) => vote_plan.serialize(&mut codec).unwrap(),
             Fragment::VoteTally(vote_tally) => vote_tally.serialize(&mut codec).unwrap(),
             Fragment::EncryptedVoteTally(vote_tally) => vote_tally.serialize(&mut codec).unwrap(),
+            Fragment::VoteCastBatch(vote_cast_batch) => {
+                vote_cast_batch.serialize(&mut codec).unwrap()
+            }
+            Fragment::HandleRegistration(tx) => tx.serialize(&mut codec).unwrap(),
+            Fragment::VotingPowerSnapshot(tx) => tx.serialize(&mut codec).unwrap(),
+            Fragment::PoolKeysRotation(tx) => tx.serialize(&mut codec).unwrap(),
+            Fragment::VoteResultCertification(tx) => tx.serialize(&mut codec).unwrap(),
+            Fragment::CommitteeCandidacy(tx) => tx.serialize(&mut codec).unwrap(),
+            Fragment::DeferredStakeDelegation(tx) => tx.serialize(&mut codec).unwrap(),
+            Fragment::DeferredOwnerStakeDelegation(tx) => tx.serialize(&mut codec).unwrap(),
         }
         FragmentRaw(codec.into_inner())
     }
@@ -150,6 +275,11 @@ impl Fragment {
     pub fn hash(&self) -> FragmentId {
         self.to_raw().id()
     }
+
+    /// The id of the fragment computed with the given version of the scheme
+    pub fn hash_with_version(&self, version: HashVersion) -> FragmentId {
+        self.to_raw().id_with_version(version)
+    }
 }
 
 impl Readable for Fragment {
@@ -163,36 +293,81 @@ impl Readable for Fragment {
         }
 
         let tag = buf.get_u8()?;
-        match FragmentTag::from_u8(tag) {
-            Some(FragmentTag::Initial) => ConfigParams::read(buf).map(Fragment::Initial),
-            Some(FragmentTag::OldUtxoDeclaration) => {
+        let layout = if tag & TRANSACTION_LAYOUT_V1_FLAG == 0 {
+            TransactionLayout::Legacy
+        } else {
+            TransactionLayout::V1
+        };
+        let legacy_layout = layout == TransactionLayout::Legacy;
+        match FragmentTag::from_u8(tag & !TRANSACTION_LAYOUT_V1_FLAG) {
+            Some(FragmentTag::Initial) if legacy_layout => {
+                ConfigParams::read(buf).map(Fragment::Initial)
+            }
+            Some(FragmentTag::OldUtxoDeclaration) if legacy_layout => {
                 legacy::UtxoDeclaration::read(buf).map(Fragment::OldUtxoDeclaration)
             }
-            Some(FragmentTag::Transaction) => Transaction::read(buf).map(Fragment::Transaction),
+            Some(FragmentTag::Transaction) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::Transaction)
+            }
             Some(FragmentTag::OwnerStakeDelegation) => {
-                Transaction::read(buf).map(Fragment::OwnerStakeDelegation)
+                Transaction::read_with_layout(buf, layout).map(Fragment::OwnerStakeDelegation)
             }
             Some(FragmentTag::StakeDelegation) => {
-                Transaction::read(buf).map(Fragment::StakeDelegation)
+                Transaction::read_with_layout(buf, layout).map(Fragment::StakeDelegation)
             }
             Some(FragmentTag::PoolRegistration) => {
-                Transaction::read(buf).map(Fragment::PoolRegistration)
+                Transaction::read_with_layout(buf, layout).map(Fragment::PoolRegistration)
             }
             Some(FragmentTag::PoolRetirement) => {
-                Transaction::read(buf).map(Fragment::PoolRetirement)
+                Transaction::read_with_layout(buf, layout).map(Fragment::PoolRetirement)
             }
-            Some(FragmentTag::PoolUpdate) => Transaction::read(buf).map(Fragment::PoolUpdate),
-            Some(FragmentTag::UpdateProposal) => {
+            Some(FragmentTag::PoolUpdate) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::PoolUpdate)
+            }
+            Some(FragmentTag::UpdateProposal) if legacy_layout => {
                 SignedUpdateProposal::read(buf).map(Fragment::UpdateProposal)
             }
-            Some(FragmentTag::UpdateVote) => SignedUpdateVote::read(buf).map(Fragment::UpdateVote),
-            Some(FragmentTag::VotePlan) => Transaction::read(buf).map(Fragment::VotePlan),
-            Some(FragmentTag::VoteCast) => Transaction::read(buf).map(Fragment::VoteCast),
-            Some(FragmentTag::VoteTally) => Transaction::read(buf).map(Fragment::VoteTally),
+            Some(FragmentTag::UpdateVote) if legacy_layout => {
+                SignedUpdateVote::read(buf).map(Fragment::UpdateVote)
+            }
+            Some(FragmentTag::VotePlan) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::VotePlan)
+            }
+            Some(FragmentTag::VoteCast) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::VoteCast)
+            }
+            Some(FragmentTag::VoteTally) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::VoteTally)
+            }
             Some(FragmentTag::EncryptedVoteTally) => {
-                Transaction::read(buf).map(Fragment::EncryptedVoteTally)
+                Transaction::read_with_layout(buf, layout).map(Fragment::EncryptedVoteTally)
+            }
+            Some(FragmentTag::VoteCastBatch) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::VoteCastBatch)
+            }
+            Some(FragmentTag::HandleRegistration) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::HandleRegistration)
+            }
+            Some(FragmentTag::VotingPowerSnapshot) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::VotingPowerSnapshot)
+            }
+            Some(FragmentTag::PoolKeysRotation) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::PoolKeysRotation)
+            }
+            Some(FragmentTag::VoteResultCertification) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::VoteResultCertification)
+            }
+            Some(FragmentTag::CommitteeCandidacy) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::CommitteeCandidacy)
+            }
+            Some(FragmentTag::DeferredStakeDelegation) => {
+                Transaction::read_with_layout(buf, layout).map(Fragment::DeferredStakeDelegation)
+            }
+            Some(FragmentTag::DeferredOwnerStakeDelegation) => {
+                Transaction::read_with_layout(buf, layout)
+                    .map(Fragment::DeferredOwnerStakeDelegation)
             }
-            None => Err(ReadError::UnknownTag(tag as u32)),
+            _ => Err(ReadError::UnknownTag(tag as u32)),
         }
     }
 }
@@ -0,0 +1,189 @@
+//! Canonical order of the fragments of a block.
+//!
+//! Two fragments depend on each other when they touch the same part of the
+//! ledger: a transaction spending the output of another one, two fragments
+//! spending from or crediting the same account, or two fragments carrying a
+//! certificate or an update (which may refer to a stake pool or a vote plan
+//! created earlier in the block). Dependent fragments have to be applied in
+//! the order chosen by the block producer, the other ones can be swapped
+//! without changing the resulting ledger.
+//!
+//! The canonical order keeps the relative order of the dependent fragments,
+//! and otherwise puts the fragments with the lowest id first. When
+//! [`ConfigParam::CanonicalFragmentOrder`](crate::config::ConfigParam) is set,
+//! the blocks whose fragments are not in their canonical order are rejected.
+
+use super::{Fragment, FragmentId};
+use crate::transaction::{InputEnum, Payload, Transaction};
+use chain_addr::Kind;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// The part of the ledger touched by a fragment
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Resource {
+    /// the outputs created by the fragment with this id
+    Outputs(FragmentId),
+    /// the account with this identifier, single or multisig
+    Account([u8; 32]),
+    /// the certificates and the update proposals and votes
+    Governance,
+}
+
+fn resources(fragment: &Fragment, id: FragmentId) -> Vec<Resource> {
+    match fragment {
+        Fragment::Transaction(tx) => transaction_resources(tx, id),
+        Fragment::OwnerStakeDelegation(tx) => governance_resources(tx, id),
+        Fragment::StakeDelegation(tx) => governance_resources(tx, id),
+        Fragment::DeferredOwnerStakeDelegation(tx) => governance_resources(tx, id),
+        Fragment::DeferredStakeDelegation(tx) => governance_resources(tx, id),
+        Fragment::PoolRegistration(tx) => governance_resources(tx, id),
+        Fragment::PoolRetirement(tx) => governance_resources(tx, id),
+        Fragment::PoolUpdate(tx) => governance_resources(tx, id),
+        Fragment::PoolKeysRotation(tx) => governance_resources(tx, id),
+        Fragment::VotePlan(tx) => governance_resources(tx, id),
+        Fragment::VoteCast(tx) => governance_resources(tx, id),
+        Fragment::VoteTally(tx) => governance_resources(tx, id),
+        Fragment::EncryptedVoteTally(tx) => governance_resources(tx, id),
+        Fragment::VoteCastBatch(tx) => governance_resources(tx, id),
+        Fragment::HandleRegistration(tx) => governance_resources(tx, id),
+        Fragment::VotingPowerSnapshot(tx) => governance_resources(tx, id),
+        Fragment::VoteResultCertification(tx) => governance_resources(tx, id),
+        Fragment::CommitteeCandidacy(tx) => governance_resources(tx, id),
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => vec![Resource::Governance],
+    }
+}
+
+fn transaction_resources<P: Payload>(tx: &Transaction<P>, id: FragmentId) -> Vec<Resource> {
+    let tx = tx.as_slice();
+    let mut resources = vec![Resource::Outputs(id)];
+    for input in tx.inputs().iter() {
+        resources.push(match input.to_enum() {
+            InputEnum::AccountInput(account, _) => Resource::Account(account_bytes(&account)),
+            InputEnum::UtxoInput(pointer) => Resource::Outputs(pointer.transaction_id),
+        });
+    }
+    for output in tx.outputs().iter() {
+        match output.address.kind() {
+            Kind::Account(key) => resources.push(Resource::Account(account_bytes(key))),
+            Kind::Multisig(account) => resources.push(Resource::Account(*account)),
+            Kind::Single(_) | Kind::Group(..) | Kind::Script(_) => {}
+        }
+    }
+    resources
+}
+
+/// The accounts, single or multisig, the fragment spends from
+pub(super) fn spent_accounts(fragment: &Fragment) -> Vec<[u8; 32]> {
+    match fragment {
+        Fragment::Transaction(tx) => transaction_spent_accounts(tx),
+        Fragment::OwnerStakeDelegation(tx) => transaction_spent_accounts(tx),
+        Fragment::StakeDelegation(tx) => transaction_spent_accounts(tx),
+        Fragment::DeferredOwnerStakeDelegation(tx) => transaction_spent_accounts(tx),
+        Fragment::DeferredStakeDelegation(tx) => transaction_spent_accounts(tx),
+        Fragment::PoolRegistration(tx) => transaction_spent_accounts(tx),
+        Fragment::PoolRetirement(tx) => transaction_spent_accounts(tx),
+        Fragment::PoolUpdate(tx) => transaction_spent_accounts(tx),
+        Fragment::PoolKeysRotation(tx) => transaction_spent_accounts(tx),
+        Fragment::VotePlan(tx) => transaction_spent_accounts(tx),
+        Fragment::VoteCast(tx) => transaction_spent_accounts(tx),
+        Fragment::VoteTally(tx) => transaction_spent_accounts(tx),
+        Fragment::EncryptedVoteTally(tx) => transaction_spent_accounts(tx),
+        Fragment::VoteCastBatch(tx) => transaction_spent_accounts(tx),
+        Fragment::HandleRegistration(tx) => transaction_spent_accounts(tx),
+        Fragment::VotingPowerSnapshot(tx) => transaction_spent_accounts(tx),
+        Fragment::VoteResultCertification(tx) => transaction_spent_accounts(tx),
+        Fragment::CommitteeCandidacy(tx) => transaction_spent_accounts(tx),
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Vec::new(),
+    }
+}
+
+fn transaction_spent_accounts<P: Payload>(tx: &Transaction<P>) -> Vec<[u8; 32]> {
+    tx.as_slice()
+        .inputs()
+        .iter()
+        .filter_map(|input| match input.to_enum() {
+            InputEnum::AccountInput(account, _) => Some(account_bytes(&account)),
+            InputEnum::UtxoInput(_) => None,
+        })
+        .collect()
+}
+
+fn account_bytes<A: AsRef<[u8]>>(account: &A) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(account.as_ref());
+    bytes
+}
+
+fn governance_resources<P: Payload>(tx: &Transaction<P>, id: FragmentId) -> Vec<Resource> {
+    let mut resources = transaction_resources(tx, id);
+    resources.push(Resource::Governance);
+    resources
+}
+
+/// For every fragment, the indices of the following fragments which directly
+/// depend on it.
+///
+/// Every fragment depends on the previous fragment touching the same
+/// resource, which is enough to keep the order of all the dependent ones.
+pub(super) fn dependents(fragments: &[Fragment], ids: &[FragmentId]) -> Vec<Vec<usize>> {
+    let mut dependents = vec![Vec::new(); fragments.len()];
+    let mut last_touched = HashMap::new();
+    for (index, fragment) in fragments.iter().enumerate() {
+        for resource in resources(fragment, ids[index]) {
+            if let Some(previous) = last_touched.insert(resource, index) {
+                if previous != index && !dependents[previous].contains(&index) {
+                    dependents[previous].push(index);
+                }
+            }
+        }
+    }
+    dependents
+}
+
+/// The canonical order of the fragments, as the indices of the fragments in
+/// the given slice.
+pub(super) fn canonical_order(fragments: &[Fragment]) -> Vec<usize> {
+    let ids: Vec<FragmentId> = fragments.iter().map(Fragment::hash).collect();
+    let dependents = dependents(fragments, &ids);
+    let mut dependencies = vec![0usize; fragments.len()];
+    for dependent in dependents.iter().flatten() {
+        dependencies[*dependent] += 1;
+    }
+
+    let mut ready: BinaryHeap<_> = (0..fragments.len())
+        .filter(|index| dependencies[*index] == 0)
+        .map(|index| Reverse((ids[index], index)))
+        .collect();
+    let mut order = Vec::with_capacity(fragments.len());
+    while let Some(Reverse((_, index))) = ready.pop() {
+        order.push(index);
+        for &dependent in &dependents[index] {
+            dependencies[dependent] -= 1;
+            if dependencies[dependent] == 0 {
+                ready.push(Reverse((ids[dependent], dependent)));
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fragment::{Contents, ContentsBuilder, Fragment};
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn sorted_contents_are_canonical(fragments: Vec<Fragment>) -> bool {
+        let mut builder = ContentsBuilder::new();
+        builder.push_many(fragments.clone()).sort_canonically();
+        let contents = Contents::from(builder);
+        contents.non_canonical_index().is_none() && contents.iter().count() == fragments.len()
+    }
+}
@@ -1,8 +1,11 @@
-use crate::key::Hash;
+use crate::key::{Hash, HashVersion};
 use chain_core::property;
 
 pub type FragmentId = Hash;
 
+/// Domain separation tag of the fragment ids, see [`HashVersion`]
+pub const FRAGMENT_ID_DOMAIN: &[u8] = b"chain-libs fragment id";
+
 /// A serialized Message
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FragmentRaw(pub(super) Vec<u8>);
@@ -13,7 +16,12 @@ impl FragmentRaw {
     }
 
     pub fn id(&self) -> FragmentId {
-        FragmentId::hash_bytes(self.0.as_ref())
+        self.id_with_version(HashVersion::CURRENT)
+    }
+
+    /// The id of the fragment computed with the given version of the scheme
+    pub fn id_with_version(&self, version: HashVersion) -> FragmentId {
+        FragmentId::hash_bytes(&version.hashed_bytes(FRAGMENT_ID_DOMAIN, self.0.as_ref()))
     }
 }
 
@@ -0,0 +1,207 @@
+//! Selection of the fragments of a block by its producer.
+//!
+//! The ledger rejects the blocks whose content is larger than
+//! [`ConfigParam::BlockContentMaxSize`], and the block producers aim for the
+//! smaller [`ConfigParam::BlockContentSoftMaxSize`] when it is set, leaving
+//! some room for the fragments which could not wait for the next block.
+//! When the pending fragments do not fit, [`select_by_fee_density`] is the
+//! reference strategy to choose the ones left out: the fragments paying the
+//! lowest fee per byte are evicted first, along with the following fragments
+//! depending on them, which could not be applied without them.
+//!
+//! [`ConfigParam::BlockContentMaxSize`]: crate::config::ConfigParam
+//! [`ConfigParam::BlockContentSoftMaxSize`]: crate::config::ConfigParam
+
+use super::{order::dependents, BlockContentSize, Fragment, FragmentId};
+use crate::{
+    transaction::{Payload, Transaction},
+    value::Value,
+};
+
+/// The fragments kept for a block and the ones left in the mempool
+#[derive(Debug, Clone, Default)]
+pub struct FragmentSelection {
+    /// the fragments of the block, in their original order
+    pub selected: Vec<Fragment>,
+    /// the fragments which did not fit, in their original order
+    pub evicted: Vec<Fragment>,
+}
+
+/// The fee paid by the fragment, the difference between the value of its
+/// inputs and the value of its outputs
+pub fn fee_paid(fragment: &Fragment) -> Value {
+    match fragment {
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Value::zero(),
+        Fragment::Transaction(tx) => transaction_fee(tx),
+        Fragment::OwnerStakeDelegation(tx) => transaction_fee(tx),
+        Fragment::StakeDelegation(tx) => transaction_fee(tx),
+        Fragment::DeferredOwnerStakeDelegation(tx) => transaction_fee(tx),
+        Fragment::DeferredStakeDelegation(tx) => transaction_fee(tx),
+        Fragment::PoolRegistration(tx) => transaction_fee(tx),
+        Fragment::PoolRetirement(tx) => transaction_fee(tx),
+        Fragment::PoolUpdate(tx) => transaction_fee(tx),
+        Fragment::PoolKeysRotation(tx) => transaction_fee(tx),
+        Fragment::VotePlan(tx) => transaction_fee(tx),
+        Fragment::VoteCast(tx) => transaction_fee(tx),
+        Fragment::VoteCastBatch(tx) => transaction_fee(tx),
+        Fragment::VoteTally(tx) => transaction_fee(tx),
+        Fragment::EncryptedVoteTally(tx) => transaction_fee(tx),
+        Fragment::HandleRegistration(tx) => transaction_fee(tx),
+        Fragment::VotingPowerSnapshot(tx) => transaction_fee(tx),
+        Fragment::VoteResultCertification(tx) => transaction_fee(tx),
+        Fragment::CommitteeCandidacy(tx) => transaction_fee(tx),
+    }
+}
+
+fn transaction_fee<P: Payload>(tx: &Transaction<P>) -> Value {
+    // the deposits and the refunds make the balance of some certificates
+    // differ from their fee, a fragment taking out more than it puts in is
+    // considered as paying nothing
+    tx.total_input()
+        .and_then(|input| tx.total_output().and_then(|output| input - output))
+        .unwrap_or_else(|_| Value::zero())
+}
+
+/// Keep the fragments in the order they are given while their total size
+/// exceeds `max_size`, evicting first the fragment with the lowest fee per
+/// byte, the latest one among equal densities, with all the following
+/// fragments depending on it.
+pub fn select_by_fee_density(
+    fragments: Vec<Fragment>,
+    max_size: BlockContentSize,
+) -> FragmentSelection {
+    let ids: Vec<FragmentId> = fragments.iter().map(Fragment::hash).collect();
+    let dependents = dependents(&fragments, &ids);
+    let sizes: Vec<u64> = fragments
+        .iter()
+        .map(|fragment| fragment.to_raw().size_bytes_plus_size() as u64)
+        .collect();
+    let fees: Vec<u64> = fragments
+        .iter()
+        .map(|fragment| fee_paid(fragment).0)
+        .collect();
+
+    let mut candidates: Vec<usize> = (0..fragments.len()).collect();
+    candidates.sort_by(|a, b| {
+        let density_a = fees[*a] as u128 * sizes[*b] as u128;
+        let density_b = fees[*b] as u128 * sizes[*a] as u128;
+        density_a.cmp(&density_b).then(b.cmp(a))
+    });
+
+    let mut total: u64 = sizes.iter().sum();
+    let mut evicted = vec![false; fragments.len()];
+    for candidate in candidates {
+        if total <= max_size as u64 {
+            break;
+        }
+        let mut to_evict = vec![candidate];
+        while let Some(index) = to_evict.pop() {
+            if !evicted[index] {
+                evicted[index] = true;
+                total -= sizes[index];
+                to_evict.extend(&dependents[index]);
+            }
+        }
+    }
+
+    let mut selection = FragmentSelection::default();
+    for (fragment, evicted) in fragments.into_iter().zip(evicted) {
+        if evicted {
+            selection.evicted.push(fragment);
+        } else {
+            selection.selected.push(fragment);
+        }
+    }
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fragment::Contents,
+        testing::{
+            builders::TestTxBuilder,
+            data::{AddressDataValue, Wallet},
+            ledger::TestLedger,
+            scenario::{prepare_scenario, wallet},
+        },
+    };
+    use quickcheck_macros::quickcheck;
+
+    fn content_size(fragments: &[Fragment]) -> BlockContentSize {
+        Contents(fragments.to_vec().into()).compute_hash_size().1
+    }
+
+    #[quickcheck]
+    fn selection_fits_and_keeps_the_fragments(fragments: Vec<Fragment>, cut: u8) -> bool {
+        let total = content_size(&fragments);
+        let max_size = total - (total as u64 * cut as u64 / 255) as BlockContentSize;
+        let selection = select_by_fee_density(fragments.clone(), max_size);
+        content_size(&selection.selected) <= max_size
+            && selection.selected.len() + selection.evicted.len() == fragments.len()
+            && (max_size < total || selection.evicted.is_empty())
+    }
+
+    fn pay(ledger: &mut TestLedger, from: &Wallet, to: &Wallet, fee: u64) -> Fragment {
+        TestTxBuilder::new(ledger.block0_hash)
+            .move_funds_multiple(
+                ledger,
+                &[AddressDataValue::new(from.as_account_data(), Value(100))],
+                &[AddressDataValue::new(
+                    to.as_account_data(),
+                    Value(100 - fee),
+                )],
+            )
+            .get_fragment()
+    }
+
+    #[test]
+    fn lowest_fee_density_is_evicted_with_its_dependents() {
+        let (mut ledger, controller) = prepare_scenario()
+            .with_initials(vec![
+                wallet("Alice").with(1_000),
+                wallet("Bob").with(1_000),
+                wallet("Clarice").with(1_000),
+                wallet("David").with(1_000),
+            ])
+            .build()
+            .unwrap();
+        let alice = controller.wallet("Alice").unwrap();
+        let bob = controller.wallet("Bob").unwrap();
+        let clarice = controller.wallet("Clarice").unwrap();
+        let david = controller.wallet("David").unwrap();
+
+        let cheap = pay(&mut ledger, &alice, &bob, 1);
+        let expensive = pay(&mut ledger, &clarice, &david, 10);
+        // touches the account of Bob, credited by the cheap fragment
+        let dependent = pay(&mut ledger, &bob, &alice, 50);
+        let fragments = vec![cheap.clone(), expensive.clone(), dependent.clone()];
+
+        let all = select_by_fee_density(fragments.clone(), content_size(&fragments));
+        assert_eq!(all.selected.len(), 3);
+        assert!(all.evicted.is_empty());
+
+        let max_size = content_size(&fragments) - 1;
+        let selection = select_by_fee_density(fragments, max_size);
+        assert_eq!(
+            selection
+                .selected
+                .iter()
+                .map(Fragment::hash)
+                .collect::<Vec<_>>(),
+            vec![expensive.hash()]
+        );
+        assert_eq!(
+            selection
+                .evicted
+                .iter()
+                .map(Fragment::hash)
+                .collect::<Vec<_>>(),
+            vec![cheap.hash(), dependent.hash()]
+        );
+    }
+}
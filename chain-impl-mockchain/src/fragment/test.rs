@@ -7,7 +7,7 @@ use quickcheck_macros::quickcheck;
 
 impl Arbitrary for Fragment {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
-        match g.next_u32() % 14 {
+        match g.next_u32() % 22 {
             0 => Fragment::Initial(Arbitrary::arbitrary(g)),
             1 => Fragment::OldUtxoDeclaration(Arbitrary::arbitrary(g)),
             2 => Fragment::Transaction(Arbitrary::arbitrary(g)),
@@ -22,6 +22,14 @@ impl Arbitrary for Fragment {
             11 => Fragment::VoteCast(Arbitrary::arbitrary(g)),
             12 => Fragment::VoteTally(Arbitrary::arbitrary(g)),
             13 => Fragment::EncryptedVoteTally(Arbitrary::arbitrary(g)),
+            14 => Fragment::VoteCastBatch(Arbitrary::arbitrary(g)),
+            15 => Fragment::HandleRegistration(Arbitrary::arbitrary(g)),
+            16 => Fragment::VotingPowerSnapshot(Arbitrary::arbitrary(g)),
+            17 => Fragment::PoolKeysRotation(Arbitrary::arbitrary(g)),
+            18 => Fragment::VoteResultCertification(Arbitrary::arbitrary(g)),
+            19 => Fragment::CommitteeCandidacy(Arbitrary::arbitrary(g)),
+            20 => Fragment::DeferredStakeDelegation(Arbitrary::arbitrary(g)),
+            21 => Fragment::DeferredOwnerStakeDelegation(Arbitrary::arbitrary(g)),
             _ => unreachable!(),
         }
     }
@@ -33,6 +41,44 @@ fn fragment_serialization_bijection(b: Fragment) -> TestResult {
     TestResult::from_bool(b == b_got)
 }
 
+#[quickcheck]
+fn fragment_tag_flags_the_transaction_layout(fragment: Fragment) -> bool {
+    let mut raw = fragment.to_raw().0;
+    let flagged = raw[1] & TRANSACTION_LAYOUT_V1_FLAG != 0;
+    raw[1] |= TRANSACTION_LAYOUT_V1_FLAG;
+    let without_transaction = matches!(
+        fragment,
+        Fragment::Initial(_)
+            | Fragment::OldUtxoDeclaration(_)
+            | Fragment::UpdateProposal(_)
+            | Fragment::UpdateVote(_)
+    );
+    flagged == (fragment.transaction_layout() == TransactionLayout::V1)
+        && (!without_transaction || Fragment::from_raw(&FragmentRaw(raw)).is_err())
+}
+
+#[quickcheck]
+fn fragment_id_versions_are_separated(fragment: Fragment) -> bool {
+    let raw = fragment.to_raw();
+    let v0 = fragment.hash_with_version(HashVersion::V0);
+    let v1 = fragment.hash_with_version(HashVersion::V1);
+    fragment.hash() == fragment.hash_with_version(HashVersion::CURRENT)
+        && v0 == FragmentId::hash_bytes(raw.as_ref())
+        && v1 != v0
+        && v1 == raw.id_with_version(HashVersion::V1)
+}
+
+#[quickcheck]
+fn transaction_sign_data_hash_versions_are_separated(tx: Transaction<NoExtra>) -> bool {
+    let tx = tx.as_slice();
+    let v0 = tx.transaction_sign_data_hash_with_version(HashVersion::V0);
+    let v1 = tx.transaction_sign_data_hash_with_version(HashVersion::V1);
+    let fragment_domain = FragmentId::hash_bytes(
+        &HashVersion::V1.hashed_bytes(FRAGMENT_ID_DOMAIN, tx.transaction_auth_data().0),
+    );
+    tx.transaction_sign_data_hash() == v0 && v1 != v0 && fragment_domain.as_bytes() != v1.as_ref()
+}
+
 quickcheck! {
     fn initial_ents_serialization_bijection(config_params: ConfigParams) -> TestResult {
         chain_test_utils::property::serialization_bijection_r(config_params)
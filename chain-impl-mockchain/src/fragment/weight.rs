@@ -0,0 +1,112 @@
+//! Validation cost of the fragments.
+//!
+//! The size of a block says little about the time it takes to validate it:
+//! a private vote cast is a few hundred bytes long but checking its proof of
+//! correctness costs as much as hundreds of signature verifications. Every
+//! fragment is given a weight estimating its validation cost, and the total
+//! weight of a block content can be capped with
+//! [`ConfigParam::BlockContentMaxWeight`](crate::config::ConfigParam), on top
+//! of the limit on its size.
+
+use super::Fragment;
+use crate::{
+    certificate::VoteTally,
+    transaction::{Payload, Transaction},
+    vote,
+};
+
+pub type BlockContentWeight = u32;
+
+/// weight of any fragment, for decoding it and looking up the ledger state
+pub const BASE_WEIGHT: BlockContentWeight = 1;
+/// weight of a signature verification, for a witness or a certificate
+/// authentication
+pub const SIGNATURE_WEIGHT: BlockContentWeight = 10;
+/// weight of verifying the proof of correctness of a private ballot, for
+/// every option of the ballot
+pub const BALLOT_OPTION_WEIGHT: BlockContentWeight = 100;
+/// weight of checking one decryption share of a private tally
+pub const DECRYPT_SHARE_WEIGHT: BlockContentWeight = 20;
+
+impl Fragment {
+    /// Estimated cost of validating the fragment
+    pub fn weight(&self) -> BlockContentWeight {
+        match self {
+            Fragment::Initial(_) | Fragment::OldUtxoDeclaration(_) => BASE_WEIGHT,
+            Fragment::UpdateProposal(_) | Fragment::UpdateVote(_) => BASE_WEIGHT + SIGNATURE_WEIGHT,
+            Fragment::Transaction(tx) => transaction_weight(tx),
+            Fragment::OwnerStakeDelegation(tx) => transaction_weight(tx),
+            Fragment::StakeDelegation(tx) => transaction_weight(tx),
+            Fragment::DeferredOwnerStakeDelegation(tx) => transaction_weight(tx),
+            Fragment::DeferredStakeDelegation(tx) => transaction_weight(tx),
+            Fragment::PoolRegistration(tx) => transaction_weight(tx),
+            Fragment::PoolRetirement(tx) => transaction_weight(tx),
+            Fragment::PoolUpdate(tx) => transaction_weight(tx),
+            Fragment::PoolKeysRotation(tx) => transaction_weight(tx),
+            Fragment::VotePlan(tx) => transaction_weight(tx),
+            Fragment::VoteCast(tx) => transaction_weight(tx).saturating_add(ballot_weight(
+                tx.as_slice().payload().into_payload().payload(),
+            )),
+            Fragment::VoteCastBatch(tx) => tx
+                .as_slice()
+                .payload()
+                .into_payload()
+                .ballots()
+                .iter()
+                .fold(transaction_weight(tx), |weight, (_, ballot)| {
+                    weight.saturating_add(ballot_weight(ballot))
+                }),
+            Fragment::VoteTally(tx) => transaction_weight(tx)
+                .saturating_add(tally_weight(&tx.as_slice().payload().into_payload())),
+            Fragment::EncryptedVoteTally(tx) => transaction_weight(tx),
+            Fragment::HandleRegistration(tx) => transaction_weight(tx),
+            Fragment::VotingPowerSnapshot(tx) => transaction_weight(tx),
+            Fragment::VoteResultCertification(tx) => transaction_weight(tx),
+            Fragment::CommitteeCandidacy(tx) => transaction_weight(tx),
+        }
+    }
+}
+
+fn transaction_weight<P: Payload>(tx: &Transaction<P>) -> BlockContentWeight {
+    let signatures =
+        tx.as_slice().nb_witnesses() as BlockContentWeight + P::HAS_AUTH as BlockContentWeight;
+    BASE_WEIGHT + signatures * SIGNATURE_WEIGHT
+}
+
+fn ballot_weight(ballot: &vote::Payload) -> BlockContentWeight {
+    match ballot {
+        vote::Payload::Public { .. } => 0,
+        vote::Payload::Private { encrypted_vote, .. } => {
+            (encrypted_vote.options() as BlockContentWeight).saturating_mul(BALLOT_OPTION_WEIGHT)
+        }
+    }
+}
+
+fn tally_weight(tally: &VoteTally) -> BlockContentWeight {
+    tally.tally_decrypted().map_or(0, |decrypted| {
+        decrypted.iter().fold(0, |weight, proposal| {
+            weight.saturating_add(
+                (proposal.decrypt_shares.len() as BlockContentWeight)
+                    .saturating_mul(DECRYPT_SHARE_WEIGHT),
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fragment::Contents;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn fragment_weight_is_at_least_base_weight(fragment: Fragment) -> bool {
+        fragment.weight() >= BASE_WEIGHT
+    }
+
+    #[quickcheck]
+    fn contents_weight_is_sum_of_fragments_weight(first: Fragment, second: Fragment) -> bool {
+        let expected = first.weight() + second.weight();
+        Contents(vec![first, second].into()).compute_weight() == expected
+    }
+}
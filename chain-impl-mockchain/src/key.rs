@@ -12,6 +12,7 @@ use rand_core::{CryptoRng, RngCore};
 use typed_bytes::ByteBuilder;
 
 use chain_core::packer::Codec;
+use std::borrow::Cow;
 use std::str::FromStr;
 
 #[derive(Clone)]
@@ -45,6 +46,16 @@ impl EitherEd25519SecretKey {
             EitherEd25519SecretKey::Normal(sk) => sk.sign_slice(dat),
         }
     }
+
+    pub fn sign_slice_with_context<C: ?Sized + crypto::SigningContext, T: ?Sized>(
+        &self,
+        dat: &[u8],
+    ) -> crypto::ContextSignature<C, T, crypto::Ed25519> {
+        match self {
+            EitherEd25519SecretKey::Extended(sk) => sk.sign_slice_with_context(dat),
+            EitherEd25519SecretKey::Normal(sk) => sk.sign_slice_with_context(dat),
+        }
+    }
 }
 
 pub type SpendingPublicKey = crypto::PublicKey<crypto::Ed25519>;
@@ -65,7 +76,7 @@ fn chain_crypto_pub_err(e: crypto::PublicKeyError) -> ReadError {
         }
     }
 }
-fn chain_crypto_sig_err(e: crypto::SignatureError) -> ReadError {
+pub(crate) fn chain_crypto_sig_err(e: crypto::SignatureError) -> ReadError {
     match e {
         crypto::SignatureError::SizeInvalid { expected, got } => ReadError::StructureInvalid(
             format!("signature size invalid, expected {} got {}", expected, got),
@@ -216,6 +227,73 @@ impl<T: Clone, A: VerificationAlgorithm> Clone for Signed<T, A> {
     }
 }
 
+/// Version of the scheme computing the ids of the fragments, pools and vote
+/// plans and the hashes signed by the witnesses of the transactions.
+///
+/// The version 0 hashes the serialized data alone. The later versions hash
+/// a domain separation tag and the version before the serialized data, so
+/// that an id cannot be the hash of something else and the ids of two
+/// versions never collide: a change of the serialization which would give
+/// the same bytes to different contents, or different bytes to the same
+/// content, comes with a new version.
+///
+/// The ledger uses the version selected by
+/// [`Settings::hash_version`](crate::setting::Settings::hash_version) for the
+/// date of the fragment, the version 1 being activated from the epoch of the
+/// `HashVersionEpoch` setting. The ids of both versions of the same content
+/// can be computed, with [`FragmentRaw::id_with_version`] and
+/// [`TransactionSlice::transaction_sign_data_hash_with_version`], to map the
+/// ids known before the activation to the new ones. The ids computed
+/// without a ledger are of [`HashVersion::CURRENT`]. The pools and vote plans
+/// keep the id they were registered with.
+///
+/// [`FragmentRaw::id_with_version`]: crate::fragment::FragmentRaw::id_with_version
+/// [`TransactionSlice::transaction_sign_data_hash_with_version`]: crate::transaction::TransactionSlice::transaction_sign_data_hash_with_version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashVersion {
+    V0,
+    V1,
+}
+
+impl HashVersion {
+    /// The version of the hashes computed without a ledger
+    pub const CURRENT: Self = HashVersion::V0;
+
+    pub const ALL: [Self; 2] = [HashVersion::V0, HashVersion::V1];
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            HashVersion::V0 => 0,
+            HashVersion::V1 => 1,
+        }
+    }
+
+    pub fn from_u8(version: u8) -> Option<Self> {
+        match version {
+            0 => Some(HashVersion::V0),
+            1 => Some(HashVersion::V1),
+            _ => None,
+        }
+    }
+
+    /// The bytes hashed for the data of the domain: the length of the
+    /// domain, the domain, the version and the data from the version 1
+    pub fn hashed_bytes<'a>(self, domain: &[u8], data: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            HashVersion::V0 => Cow::Borrowed(data),
+            HashVersion::V1 => {
+                debug_assert!(domain.len() <= u8::MAX as usize);
+                let mut bytes = Vec::with_capacity(domain.len() + data.len() + 2);
+                bytes.push(domain.len() as u8);
+                bytes.extend_from_slice(domain);
+                bytes.push(self.to_u8());
+                bytes.extend_from_slice(data);
+                Cow::Owned(bytes)
+            }
+        }
+    }
+}
+
 /// Hash that is used as an address of the various components.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Hash(crypto::Blake2b256);
@@ -4,6 +4,7 @@ use crate::{
     leadership::{Error, ErrorKind, Verification},
     ledger::Ledger,
 };
+use chain_crypto::ValidationPolicy;
 use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,6 +14,8 @@ pub struct BftRoundRobinIndex(u64);
 #[derive(Debug)]
 pub struct LeadershipData {
     pub(crate) leaders: Arc<Box<[BftLeaderId]>>,
+    // checks done on the encoding of the signatures of the leaders
+    pub(crate) validation_policy: ValidationPolicy,
 }
 
 impl LeadershipData {
@@ -24,6 +27,7 @@ impl LeadershipData {
 
         Some(LeadershipData {
             leaders: Arc::clone(&ledger.settings.bft_leaders),
+            validation_policy: ledger.settings.validation_policy,
         })
     }
 
@@ -47,6 +51,13 @@ impl LeadershipData {
             Proof::Bft(bft_proof) => {
                 if bft_proof.leader_id != self.get_leader_at(block_header.block_date()) {
                     Verification::Failure(Error::new(ErrorKind::InvalidLeader))
+                } else if bft_proof
+                    .signature
+                    .0
+                    .validate(self.validation_policy)
+                    .is_err()
+                {
+                    Verification::Failure(Error::new(ErrorKind::InvalidLeaderSignature))
                 } else {
                     Verification::Success
                 }
@@ -12,6 +12,7 @@ use crate::{
 };
 use chain_crypto::Verification as SigningVerification;
 use chain_crypto::{Curve25519_2HashDH, SecretKey};
+use rand_core::{CryptoRng, RngCore};
 use thiserror::Error;
 pub(crate) use vrfeval::witness_to_nonce;
 use vrfeval::VrfEvaluator;
@@ -57,7 +58,7 @@ impl LeadershipData {
         LeadershipData {
             epoch_nonce: ledger.settings.consensus_nonce.clone(),
             nodes: ledger.delegation.clone(),
-            distribution: ledger.get_stake_distribution(),
+            distribution: ledger.get_stake_distribution_at(epoch),
             epoch,
             active_slots_coeff: ledger.settings.active_slots_coeff,
         }
@@ -76,6 +77,18 @@ impl LeadershipData {
         pool_id: &PoolId,
         vrf_key: &SecretKey<Curve25519_2HashDH>,
         date: BlockDate,
+    ) -> Result<Option<Witness>, Error> {
+        self.leader_with_rng(pool_id, vrf_key, date, rand_core::OsRng)
+    }
+
+    /// Same as [`leader`](Self::leader), with the random number generator
+    /// used by the VRF proof
+    pub fn leader_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        pool_id: &PoolId,
+        vrf_key: &SecretKey<Curve25519_2HashDH>,
+        date: BlockDate,
+        rng: R,
     ) -> Result<Option<Witness>, Error> {
         if date.epoch != self.epoch {
             return Err(Error::new_(
@@ -113,7 +126,7 @@ impl LeadershipData {
                     slot_id: date.slot_id,
                     active_slots_coeff: self.active_slots_coeff,
                 };
-                Ok(evaluator.evaluate(vrf_key))
+                Ok(evaluator.evaluate_with_rng(vrf_key, rng))
             }
         }
     }
@@ -229,7 +242,7 @@ mod tests {
     };
     use crate::value::Value;
     use chain_core::property::ChainLength;
-    use chain_crypto::{Curve25519_2HashDH, SecretKey};
+    use chain_crypto::{Curve25519_2HashDH, DeterministicRng, SecretKey};
 
     use std::collections::HashMap;
 
@@ -536,6 +549,37 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    pub fn leadership_leader_with_deterministic_rng() {
+        let cb = ConfigBuilder::new(0).with_active_slots_coeff(Milli::ONE);
+        let mut ledger = LedgerBuilder::from_config(cb)
+            .build()
+            .expect("cannot build test ledger")
+            .ledger;
+        let (pool_id, vrf_key) = make_pool(&mut ledger);
+        let mut pools = Pools::new();
+        pools.insert(
+            pool_id.clone(),
+            (vrf_key.clone(), 0, Stake::from_value(Value(100))),
+        );
+        let selection = make_leadership_with_pools(&ledger, &pools);
+        let date = ledger.date();
+
+        let witness = |label: &[u8]| {
+            selection
+                .leader_with_rng(
+                    &pool_id,
+                    &vrf_key,
+                    date,
+                    DeterministicRng::from_label(label),
+                )
+                .unwrap()
+                .expect("the only pool is always elected")
+        };
+        assert_eq!(witness(b"leader"), witness(b"leader"));
+        assert_ne!(witness(b"leader"), witness(b"another leader"));
+    }
+
     use crate::fragment::Contents;
     use crate::header::{BlockVersion, HeaderBuilderNew};
 
@@ -5,10 +5,10 @@ use crate::date::SlotId;
 use crate::setting::ActiveSlotsCoeff;
 use crate::stake::PercentStake;
 use chain_crypto::{
-    vrf_evaluate_and_prove, vrf_verified_get_output, vrf_verify, Curve25519_2HashDH, PublicKey,
-    SecretKey, VRFVerification, VerifiableRandomFunction,
+    algorithms::vrf::range, vrf_evaluate_and_prove, vrf_verified_get_output, vrf_verify,
+    Curve25519_2HashDH, PublicKey, SecretKey, VRFVerification, VerifiableRandomFunction,
 };
-use rand_core::OsRng;
+use rand_core::{CryptoRng, OsRng, RngCore};
 
 /// Threshold between 0.0 and 1.0
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
@@ -17,16 +17,8 @@ pub struct Threshold(f64);
 impl Threshold {
     pub fn from_u256(v: &[u8]) -> Self {
         assert_eq!(v.len(), 32);
-        // TODO, only consider the highest part
-        let v64 = (v[0] as u64) << 56
-            | (v[1] as u64) << 48
-            | (v[2] as u64) << 40
-            | (v[3] as u64) << 32
-            | (v[4] as u64) << 24
-            | (v[5] as u64) << 16
-            | (v[6] as u64) << 8
-            | (v[7] as u64);
-        Threshold((v64 as f64) / 18_446_744_073_709_551_616.0)
+        // only the highest 64 bits are considered
+        Threshold(range::to_unit_f64(v))
     }
 }
 
@@ -73,9 +65,18 @@ impl<'a> VrfEvaluator<'a> {
     ///
     /// On threshold success, the witness is returned, otherwise None is returned
     pub fn evaluate(&self, key: &SecretKey<Curve25519_2HashDH>) -> Option<Witness> {
+        self.evaluate_with_rng(key, OsRng)
+    }
+
+    /// Same as [`evaluate`](Self::evaluate), with the random number generator
+    /// used by the VRF proof
+    pub fn evaluate_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        key: &SecretKey<Curve25519_2HashDH>,
+        rng: R,
+    ) -> Option<Witness> {
         let input = Input::create(self.nonce, self.slot_id);
-        let csprng = OsRng;
-        let vr = vrf_evaluate_and_prove(key, &input.0, csprng);
+        let vr = vrf_evaluate_and_prove(key, &input.0, rng);
         let r = vrf_verified_get_output::<Curve25519_2HashDH>(&vr);
         let t = get_threshold(&input, &r);
         if above_stake_threshold(t, &self.stake, self.active_slots_coeff) {
@@ -9,6 +9,7 @@ use crate::{
 };
 use chain_crypto::{Curve25519_2HashDH, Ed25519, SecretKey, SumEd25519_12};
 use chain_time::era::TimeEra;
+use std::sync::Arc;
 
 pub mod bft;
 pub mod genesis;
@@ -139,7 +140,14 @@ impl LeadershipConsensus {
 }
 
 impl Leadership {
+    /// Create the leadership of `epoch`, the update proposals accepted
+    /// before the start of `epoch` are taken into account, so the consensus
+    /// may differ from the one `ledger` currently uses.
     pub fn new(epoch: Epoch, ledger: &Ledger) -> Self {
+        // if the accepted proposals cannot be applied, the first block of
+        // the epoch will be rejected anyway
+        let next_ledger = ledger.at_epoch_start(epoch);
+        let ledger = next_ledger.as_ref().unwrap_or(ledger);
         let inner = match ledger.settings.consensus_version {
             ConsensusType::Bft => {
                 LeadershipConsensus::Bft(bft::LeadershipData::new(ledger).unwrap())
@@ -156,6 +164,27 @@ impl Leadership {
         }
     }
 
+    /// The leadership of a later epoch, when it can be derived from this one
+    /// without the state of the ledger at the end of this epoch.
+    ///
+    /// This is the case of BFT, the leaders being carried over unless an update
+    /// proposal changes them, while the Genesis Praos leadership depends on the
+    /// stake distribution.
+    pub(crate) fn for_epoch(&self, epoch: Epoch) -> Option<Self> {
+        match &self.inner {
+            LeadershipConsensus::Bft(bft) => Some(Leadership {
+                epoch,
+                era: self.era.clone(),
+                inner: LeadershipConsensus::Bft(bft::LeadershipData {
+                    leaders: Arc::clone(&bft.leaders),
+                    validation_policy: bft.validation_policy,
+                }),
+                ledger_parameters: self.ledger_parameters.clone(),
+            }),
+            LeadershipConsensus::GenesisPraos(_) => None,
+        }
+    }
+
     /// get the epoch associated to the `Leadership`
     #[inline]
     pub fn epoch(&self) -> Epoch {
@@ -0,0 +1,168 @@
+//! Last activity of the accounts.
+//!
+//! The date of the last fragment spending from a single account is kept in
+//! its state. When [`ConfigParam::VotingInactivityThreshold`] is set, the
+//! accounts which did not spend for more epochs than the threshold, or
+//! never did, are left out of the stake the votes are tallied with.
+//!
+//! [`ConfigParam::VotingInactivityThreshold`]: crate::config::ConfigParam
+
+use super::{Error, Ledger};
+use crate::{
+    account::Identifier,
+    date::BlockDate,
+    fragment::Fragment,
+    stake::StakeControl,
+    transaction::{InputEnum, Transaction, Witness},
+};
+
+fn push_spending_accounts<P>(tx: &Transaction<P>, accounts: &mut Vec<Identifier>) {
+    let tx = tx.as_slice();
+    for (input, witness) in tx.inputs_and_witnesses().iter() {
+        if let (InputEnum::AccountInput(account, _), Witness::Account(_)) =
+            (input.to_enum(), &witness)
+        {
+            if let Some(account) = account.to_single_account() {
+                if !accounts.contains(&account) {
+                    accounts.push(account)
+                }
+            }
+        }
+    }
+}
+
+/// Single accounts spent from by a fragment
+fn spending_accounts(fragment: &Fragment) -> Vec<Identifier> {
+    let mut accounts = Vec::new();
+    match fragment {
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => {}
+        Fragment::Transaction(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::OwnerStakeDelegation(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::StakeDelegation(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::DeferredOwnerStakeDelegation(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::DeferredStakeDelegation(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::PoolRegistration(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::PoolRetirement(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::PoolUpdate(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::PoolKeysRotation(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::VotePlan(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::VoteCast(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::VoteCastBatch(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::VoteTally(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::EncryptedVoteTally(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::HandleRegistration(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::VotingPowerSnapshot(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::VoteResultCertification(tx) => push_spending_accounts(tx, &mut accounts),
+        Fragment::CommitteeCandidacy(tx) => push_spending_accounts(tx, &mut accounts),
+    }
+    accounts
+}
+
+impl Ledger {
+    /// The date of the last fragment spending from the account, none if the
+    /// account never spent or does not exist
+    pub fn account_last_activity(&self, account: &Identifier) -> Option<BlockDate> {
+        self.accounts
+            .get_state(account)
+            .ok()
+            .and_then(|state| state.last_activity())
+    }
+
+    /// Whether the account counts in the tally of the votes at the current
+    /// date, see the [module documentation](self)
+    pub fn is_account_active_for_voting(&self, account: &Identifier) -> bool {
+        match self.settings.voting_inactivity_threshold {
+            None => true,
+            Some(threshold) => match self.account_last_activity(account) {
+                None => false,
+                Some(date) => date.epoch.saturating_add(threshold) >= self.date.epoch,
+            },
+        }
+    }
+
+    /// Record the date of the fragment in the accounts it spent from, to be
+    /// called once the fragment is applied
+    pub(super) fn record_activity(
+        mut self,
+        fragment: &Fragment,
+        date: BlockDate,
+    ) -> Result<Self, Error> {
+        for account in spending_accounts(fragment) {
+            // an account is removed when its spending counter overflows
+            if self.accounts.exists(&account) {
+                self.accounts = self.accounts.set_last_activity(&account, date)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// The stake the votes are tallied with
+    pub(super) fn voting_stake(&self) -> StakeControl {
+        if self.settings.voting_inactivity_threshold.is_none() {
+            return StakeControl::new_with(&self.accounts, &self.utxos);
+        }
+        StakeControl::new_with_filter(&self.accounts, &self.utxos, |account| {
+            self.is_account_active_for_voting(account)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        ledger::ConfigBuilder,
+        scenario::{prepare_scenario, wallet},
+    };
+
+    #[test]
+    fn spending_account_records_its_last_activity() {
+        let (mut ledger, controller) = prepare_scenario()
+            .with_config(ConfigBuilder::new(0).with_voting_inactivity_threshold(2))
+            .with_initials(vec![wallet("Alice").with(1_000), wallet("Bob").with(1_000)])
+            .build()
+            .unwrap();
+        let alice = controller.wallet("Alice").unwrap();
+        let bob = controller.wallet("Bob").unwrap();
+        let alice_id: Identifier = alice.public_key().into();
+        let bob_id: Identifier = bob.public_key().into();
+
+        assert_eq!(ledger.ledger.account_last_activity(&alice_id), None);
+        assert!(!ledger.ledger.is_account_active_for_voting(&alice_id));
+
+        let date = BlockDate {
+            epoch: 1,
+            slot_id: 3,
+        };
+        let fragment = controller
+            .fragment_factory()
+            .transaction(&alice, &bob, &mut ledger, 100);
+        ledger.ledger = ledger
+            .ledger
+            .apply_fragment(&ledger.parameters, &fragment, date)
+            .unwrap();
+
+        assert_eq!(ledger.ledger.account_last_activity(&alice_id), Some(date));
+        // receiving is not an activity
+        assert_eq!(ledger.ledger.account_last_activity(&bob_id), None);
+
+        ledger.ledger.date = BlockDate {
+            epoch: 3,
+            slot_id: 0,
+        };
+        assert!(ledger.ledger.is_account_active_for_voting(&alice_id));
+        ledger.ledger.date.epoch = 4;
+        assert!(!ledger.ledger.is_account_active_for_voting(&alice_id));
+
+        let stake = ledger.ledger.voting_stake();
+        assert_eq!(stake.by(&alice_id), None);
+        assert_eq!(stake.by(&bob_id), None);
+
+        ledger.ledger.settings.voting_inactivity_threshold = None;
+        assert!(ledger.ledger.voting_stake().by(&alice_id).is_some());
+        assert!(ledger.ledger.voting_stake().by(&bob_id).is_some());
+    }
+}
@@ -1,8 +1,11 @@
 use super::{Block0Error, Error};
 use crate::certificate;
+use crate::date::BlockDate;
+use crate::fragment::Fragment;
 use crate::transaction::*;
 use crate::value::Value;
-use chain_addr::Address;
+use chain_addr::{Address, Kind};
+use chain_crypto::ValidationPolicy;
 use thiserror::Error;
 
 pub const CHECK_TX_MAXIMUM_INPUTS: u8 = 255;
@@ -59,12 +62,204 @@ pub(super) fn valid_output_value(output: &Output<Address>) -> LedgerCheck {
     )
 }
 
+/// Check that the output only carries tokens if it creates a UTxO, as the
+/// accounts only hold the native value
+pub(super) fn valid_output_tokens(output: &Output<Address>) -> LedgerCheck {
+    if_cond_fail_with!(
+        !output.tokens.is_empty()
+            && !matches!(output.address.kind(), Kind::Single(_) | Kind::Group(..)),
+        Error::TokensToNonUtxoAddress {
+            output: output.clone()
+        }
+    )
+}
+
+/// Check that the transaction can be included in a block of the given date
+pub(super) fn valid_transaction_date<P>(
+    tx: &TransactionSlice<P>,
+    block_date: BlockDate,
+) -> LedgerCheck {
+    let ValidityInterval {
+        valid_from,
+        valid_until,
+    } = tx.validity();
+    if_cond_fail_with!(
+        block_date < valid_from,
+        Error::TransactionNotYetValid {
+            valid_from,
+            block_date
+        }
+    )?;
+    if_cond_fail_with!(
+        block_date > valid_until,
+        Error::TransactionExpired {
+            valid_until,
+            block_date
+        }
+    )
+}
+
+/// Check the date of the transaction of the fragment, if it has one
+pub(super) fn valid_fragment_date(fragment: &Fragment, block_date: BlockDate) -> LedgerCheck {
+    match fragment {
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Ok(()),
+        Fragment::Transaction(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::OwnerStakeDelegation(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::StakeDelegation(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::DeferredOwnerStakeDelegation(tx) => {
+            valid_transaction_date(&tx.as_slice(), block_date)
+        }
+        Fragment::DeferredStakeDelegation(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::PoolRegistration(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::PoolRetirement(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::PoolUpdate(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::PoolKeysRotation(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::VotePlan(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::VoteCast(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::VoteCastBatch(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::VoteTally(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::EncryptedVoteTally(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::HandleRegistration(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::VotingPowerSnapshot(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::VoteResultCertification(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+        Fragment::CommitteeCandidacy(tx) => valid_transaction_date(&tx.as_slice(), block_date),
+    }
+}
+
+/// Check that the key of the account, if it is a single account, is encoded
+/// according to the policy
+fn valid_account_key_encoding(
+    account_id: &UnspecifiedAccountIdentifier,
+    policy: ValidationPolicy,
+) -> bool {
+    account_id
+        .to_single_account()
+        .map_or(true, |id| id.as_ref().validate(policy).is_ok())
+}
+
+/// Check the encoding of the keys and signatures of the witnesses of the
+/// transaction, and of the keys of the single accounts they spend from
+pub(super) fn valid_witnesses_encoding<P>(
+    tx: &TransactionSlice<P>,
+    policy: ValidationPolicy,
+) -> LedgerCheck {
+    for (input, witness) in tx.inputs_and_witnesses().iter() {
+        if !witness.validate(policy) {
+            return Err(Error::WitnessInvalidEncoding { witness });
+        }
+        if let (InputEnum::AccountInput(account_id, _), Witness::Account(_)) =
+            (input.to_enum(), &witness)
+        {
+            if_cond_fail_with!(
+                !valid_account_key_encoding(&account_id, policy),
+                Error::AccountIdentifierInvalid
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn valid_pool_keys_encoding(reg: &certificate::PoolRegistration, policy: ValidationPolicy) -> bool {
+    reg.owners
+        .iter()
+        .chain(reg.operators.iter())
+        .all(|pk| pk.validate(policy).is_ok())
+}
+
+/// Check the encoding of the keys and signatures of the witnesses and of the
+/// certificate of the transaction, `valid` checking the certificate and its
+/// signatures
+fn valid_certificate_encoding<P, F>(
+    tx: &TransactionSlice<P>,
+    policy: ValidationPolicy,
+    valid: F,
+) -> LedgerCheck
+where
+    P: Payload,
+    F: FnOnce(&P, &P::Auth) -> bool,
+{
+    valid_witnesses_encoding(tx, policy)?;
+    let cert = tx.payload().into_payload();
+    let auth = tx.payload_auth().into_payload_auth();
+    if_cond_fail_with!(!valid(&cert, &auth), Error::CertificateInvalidEncoding)
+}
+
+/// Check the encoding of the keys and signatures of the fragment against the
+/// validation policy of the ledger, on top of their verification
+pub(super) fn valid_fragment_encoding(
+    fragment: &Fragment,
+    policy: ValidationPolicy,
+) -> LedgerCheck {
+    match fragment {
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Ok(()),
+        Fragment::Transaction(tx) => valid_witnesses_encoding(&tx.as_slice(), policy),
+        Fragment::OwnerStakeDelegation(tx) => valid_witnesses_encoding(&tx.as_slice(), policy),
+        Fragment::DeferredOwnerStakeDelegation(tx) => {
+            valid_witnesses_encoding(&tx.as_slice(), policy)
+        }
+        Fragment::VoteCast(tx) => valid_witnesses_encoding(&tx.as_slice(), policy),
+        Fragment::VoteCastBatch(tx) => valid_witnesses_encoding(&tx.as_slice(), policy),
+        Fragment::CommitteeCandidacy(tx) => valid_witnesses_encoding(&tx.as_slice(), policy),
+        Fragment::StakeDelegation(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |cert, sig| {
+                valid_account_key_encoding(&cert.account_id, policy) && sig.validate(policy).is_ok()
+            })
+        }
+        Fragment::DeferredStakeDelegation(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |cert, sig| {
+                valid_account_key_encoding(&cert.delegation.account_id, policy)
+                    && sig.validate(policy).is_ok()
+            })
+        }
+        Fragment::HandleRegistration(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |cert, sig| {
+                valid_account_key_encoding(&cert.account_id, policy) && sig.validate(policy).is_ok()
+            })
+        }
+        Fragment::PoolRegistration(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |cert, sig| {
+                valid_pool_keys_encoding(cert, policy) && sig.validate(policy)
+            })
+        }
+        Fragment::PoolUpdate(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |cert, sig| {
+                valid_pool_keys_encoding(&cert.new_pool_reg, policy) && sig.validate(policy)
+            })
+        }
+        Fragment::PoolRetirement(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, sig| sig.validate(policy))
+        }
+        Fragment::PoolKeysRotation(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, sig| sig.validate(policy))
+        }
+        Fragment::VotePlan(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, proof| proof.validate(policy))
+        }
+        Fragment::VoteTally(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, proof| proof.validate(policy))
+        }
+        Fragment::EncryptedVoteTally(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, proof| proof.validate(policy))
+        }
+        Fragment::VotingPowerSnapshot(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, proof| proof.validate(policy))
+        }
+        Fragment::VoteResultCertification(tx) => {
+            valid_certificate_encoding(&tx.as_slice(), policy, |_, proof| proof.validate(policy))
+        }
+    }
+}
+
 /// check that the transaction input/outputs/witnesses is valid for stake_owner_delegation
 ///
 /// * Only 1 input (subsequently 1 witness), no output
-pub(super) fn valid_stake_owner_delegation_transaction(
-    tx: &TransactionSlice<certificate::OwnerStakeDelegation>,
-) -> LedgerCheck {
+pub(super) fn valid_stake_owner_delegation_transaction<P>(tx: &TransactionSlice<P>) -> LedgerCheck {
     if_cond_fail_with!(
         tx.inputs().nb_inputs() != 1
             || tx.witnesses().nb_witnesses() != 1
@@ -85,10 +280,59 @@ pub(super) fn valid_vote_cast(tx: &TransactionSlice<certificate::VoteCast>) -> L
     )
 }
 
+/// check that the transaction input/outputs/witnesses is valid for the
+/// committee candidacy
+///
+/// * Only 1 input (subsequently 1 witness), no output
+pub(super) fn valid_committee_candidacy(
+    tx: &TransactionSlice<certificate::CommitteeCandidacy>,
+) -> LedgerCheck {
+    if_cond_fail_with!(
+        tx.inputs().nb_inputs() != 1
+            || tx.witnesses().nb_witnesses() != 1
+            || tx.outputs().nb_outputs() != 0,
+        Error::CommitteeCandidacyInvalidTransaction
+    )
+}
+
+/// check that the transaction input/outputs/witnesses is valid for the ballots batch
+///
+/// * Only 1 input (subsequently 1 witness), no output
+pub(super) fn valid_vote_cast_batch(
+    tx: &TransactionSlice<certificate::VoteCastBatch>,
+) -> LedgerCheck {
+    if_cond_fail_with!(
+        tx.inputs().nb_inputs() != 1
+            || tx.witnesses().nb_witnesses() != 1
+            || tx.outputs().nb_outputs() != 0,
+        Error::VoteCastBatchInvalidTransaction
+    )
+}
+
+/// check that the ballots batch is valid
+///
+/// * there is at least one ballot
+/// * there is at most one ballot per proposal
+pub(super) fn valid_vote_cast_batch_certificate(batch: &certificate::VoteCastBatch) -> LedgerCheck {
+    if_cond_fail_with!(batch.ballots().is_empty(), Error::VoteCastBatchEmpty)?;
+    let mut proposals = [false; 256];
+    for (proposal_index, _) in batch.ballots() {
+        if_cond_fail_with!(
+            proposals[*proposal_index as usize],
+            Error::VoteCastBatchDuplicatedProposal {
+                proposal_index: *proposal_index
+            }
+        )?;
+        proposals[*proposal_index as usize] = true;
+    }
+    Ok(())
+}
+
 /// check that the pool registration certificate is valid
 ///
 /// * management threshold T is valid: 0 < T <= #owners
 /// * there is no more than MAXIMUM_OWNERS
+/// * the delegators account, if any, is a single account
 pub(super) fn valid_pool_registration_certificate(
     auth_cert: &certificate::PoolRegistration,
 ) -> LedgerCheck {
@@ -112,6 +356,13 @@ pub(super) fn valid_pool_registration_certificate(
         auth_cert.operators.len() > CHECK_POOL_REG_MAXIMUM_OPERATORS,
         Error::PoolRegistrationHasTooManyOperators
     )?;
+    if_cond_fail_with!(
+        matches!(
+            auth_cert.delegators_account,
+            Some(AccountIdentifier::Multi(_))
+        ),
+        Error::PoolRegistrationMultisigDelegatorsAccount
+    )?;
     Ok(())
 }
 
@@ -209,7 +460,11 @@ mod tests {
             && (pool_registration.management_threshold() as usize)
                 <= pool_registration.owners.len()
             && pool_registration.owners.len() <= CHECK_POOL_REG_MAXIMUM_OWNERS
-            && pool_registration.operators.len() <= CHECK_POOL_REG_MAXIMUM_OPERATORS;
+            && pool_registration.operators.len() <= CHECK_POOL_REG_MAXIMUM_OPERATORS
+            && !matches!(
+                pool_registration.delegators_account,
+                Some(AccountIdentifier::Multi(_))
+            );
         let result = valid_pool_registration_certificate(&pool_registration);
         to_quickchek_result(result, is_valid)
     }
@@ -0,0 +1,155 @@
+//! Deposits locked by certificates.
+//!
+//! Registering a stake pool, creating a vote plan or registering a handle
+//! locks a deposit, configured with [`ConfigParam::PoolRegistrationDeposit`],
+//! [`ConfigParam::VotePlanDeposit`] and
+//! [`ConfigParam::HandleRegistrationDeposit`], on top of the fee of the
+//! transaction. The deposit is refunded to the account recorded when it was
+//! locked once the pool is retired, the vote plan is tallied or the expired
+//! handle is taken over, so that the certificates cost nothing to honest
+//! users but keep the ledger from being spammed. The deposit of a vote plan
+//! which was never tallied is refunded on the first epoch transition after
+//! the end of its committee period.
+//!
+//! [`ConfigParam::PoolRegistrationDeposit`]: crate::config::ConfigParam
+//! [`ConfigParam::VotePlanDeposit`]: crate::config::ConfigParam
+//! [`ConfigParam::HandleRegistrationDeposit`]: crate::config::ConfigParam
+
+use crate::account;
+use crate::certificate::{Handle, PoolId, VotePlanId};
+use crate::value::{Value, ValueError};
+use imhamt::{Hamt, HamtIter, InsertError};
+use std::collections::hash_map::DefaultHasher;
+
+/// The certificate a deposit has been locked for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DepositOwner {
+    StakePool(PoolId),
+    VotePlan(VotePlanId),
+    Handle(Handle),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deposit {
+    pub value: Value,
+    /// account credited with the deposit when it is refunded
+    pub refund_to: account::Identifier,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DepositError {
+    #[error("a deposit is already locked for {0:?}")]
+    AlreadyLocked(DepositOwner),
+    #[error("total value of the deposits is too big")]
+    TotalValueTooBig(#[from] ValueError),
+}
+
+/// All the deposits currently locked in the ledger
+#[derive(Clone, PartialEq, Eq)]
+pub struct Deposits {
+    total: Value,
+    deposits: Hamt<DefaultHasher, DepositOwner, Deposit>,
+}
+
+impl Default for Deposits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deposits {
+    pub fn new() -> Self {
+        Deposits {
+            total: Value::zero(),
+            deposits: Hamt::new(),
+        }
+    }
+
+    /// Total value of the locked deposits
+    pub fn total(&self) -> Value {
+        self.total
+    }
+
+    pub fn get(&self, owner: &DepositOwner) -> Option<&Deposit> {
+        self.deposits.lookup(owner)
+    }
+
+    /// Lock a deposit for the given certificate
+    pub fn lock(&self, owner: DepositOwner, deposit: Deposit) -> Result<Self, DepositError> {
+        let total = (self.total + deposit.value)?;
+        let deposits = self
+            .deposits
+            .insert(owner.clone(), deposit)
+            .map_err(|InsertError::EntryExists| DepositError::AlreadyLocked(owner))?;
+        Ok(Deposits { total, deposits })
+    }
+
+    /// Release the deposit locked for the given certificate, if any, so it
+    /// can be refunded
+    pub fn release(&self, owner: &DepositOwner) -> (Self, Option<Deposit>) {
+        match self.deposits.lookup(owner) {
+            None => (self.clone(), None),
+            Some(deposit) => {
+                let deposit = deposit.clone();
+                let deposits = Deposits {
+                    total: (self.total - deposit.value)
+                        .expect("the total covers every locked deposit"),
+                    deposits: self
+                        .deposits
+                        .remove(owner)
+                        .expect("the deposit has just been looked up"),
+                };
+                (deposits, Some(deposit))
+            }
+        }
+    }
+
+    pub fn iter(&self) -> HamtIter<'_, DepositOwner, Deposit> {
+        self.deposits.iter()
+    }
+}
+
+impl std::fmt::Debug for Deposits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.deposits.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{builders::StakePoolBuilder, TestGen, VoteTestGen};
+
+    fn deposit(value: u64) -> Deposit {
+        Deposit {
+            value: Value(value),
+            refund_to: TestGen::identifier(),
+        }
+    }
+
+    #[test]
+    fn lock_and_release() {
+        let pool = DepositOwner::StakePool(StakePoolBuilder::new().build().id());
+        let plan = DepositOwner::VotePlan(VoteTestGen::vote_plan().to_id());
+
+        let deposits = Deposits::new()
+            .lock(pool.clone(), deposit(100))
+            .unwrap()
+            .lock(plan, deposit(20))
+            .unwrap();
+        assert_eq!(deposits.total(), Value(120));
+        assert_eq!(
+            deposits.lock(pool.clone(), deposit(1)),
+            Err(DepositError::AlreadyLocked(pool.clone()))
+        );
+
+        let (deposits, released) = deposits.release(&pool);
+        assert_eq!(released.unwrap().value, Value(100));
+        assert_eq!(deposits.total(), Value(20));
+        assert!(deposits.get(&pool).is_none());
+
+        let (deposits, released) = deposits.release(&pool);
+        assert!(released.is_none());
+        assert_eq!(deposits.total(), Value(20));
+    }
+}
@@ -0,0 +1,454 @@
+//! Expected effects of a fragment, for the node APIs to preview a fragment
+//! before it is submitted.
+//!
+//! [`LedgerView::effects`] dry-runs the fragment and reports whether the
+//! ledger would accept it along with what it would change: the value spent
+//! from and received by every address, the certificate it registers and the
+//! votes it casts. The effects are computed from the content of the fragment
+//! even when it is rejected, so that a wallet can show what the user tried
+//! to do next to the reason of the rejection.
+//!
+//! [`FragmentEffects`] has a stable binary form, for the node to send them
+//! over its APIs.
+
+use super::{LedgerView, RejectionReason};
+use crate::{
+    account,
+    certificate::{
+        Certificate, CommitteeCandidacy, DeferredOwnerStakeDelegation, DeferredStakeDelegation,
+        EncryptedVoteTally, HandleRegistration, OwnerStakeDelegation, PoolKeysRotation,
+        PoolRegistration, PoolRetirement, PoolUpdate, StakeDelegation, VoteCast, VoteCastBatch,
+        VotePlan, VotePlanId, VoteResultCertification, VoteTally, VotingPowerSnapshot,
+    },
+    fragment::{fee_paid, Fragment, FragmentId, FragmentTag},
+    key::Hash,
+    transaction::{InputEnum, Payload, Transaction, Witness},
+    value::Value,
+};
+use chain_addr::{Address, Kind};
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property,
+};
+use std::convert::TryFrom;
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// Whether the ledger would accept a fragment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FragmentStatus {
+    Accepted,
+    Rejected(RejectionReason),
+}
+
+/// Value an address would spend and receive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceDelta {
+    pub address: Address,
+    pub spent: Value,
+    pub received: Value,
+}
+
+/// Vote the ledger would record for a proposal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteRecord {
+    pub vote_plan: VotePlanId,
+    pub proposal_index: u8,
+    pub voter: account::Identifier,
+}
+
+#[derive(Debug, Clone)]
+pub struct FragmentEffects {
+    pub fragment_id: FragmentId,
+    pub status: FragmentStatus,
+    /// the fee the fragment pays
+    pub fee: Value,
+    /// the balance changes, by address, in the order of their first input
+    /// or output. The inputs spending legacy outputs are not listed.
+    pub balance_deltas: Vec<BalanceDelta>,
+    /// the certificate the fragment registers
+    pub certificate: Option<Certificate>,
+    pub votes: Vec<VoteRecord>,
+}
+
+impl FragmentEffects {
+    pub fn is_accepted(&self) -> bool {
+        self.status == FragmentStatus::Accepted
+    }
+
+    fn delta_mut(&mut self, address: Address) -> &mut BalanceDelta {
+        match self
+            .balance_deltas
+            .iter()
+            .position(|delta| delta.address == address)
+        {
+            Some(index) => &mut self.balance_deltas[index],
+            None => {
+                self.balance_deltas.push(BalanceDelta {
+                    address,
+                    spent: Value::zero(),
+                    received: Value::zero(),
+                });
+                self.balance_deltas.last_mut().unwrap()
+            }
+        }
+    }
+
+    pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
+        let bb = bb.bytes(self.fragment_id.as_ref());
+        let bb = match self.status {
+            FragmentStatus::Accepted => bb.u8(0),
+            FragmentStatus::Rejected(reason) => bb.u8(1).u16(reason.code()),
+        };
+        let bb = bb
+            .u64(self.fee.0)
+            .iter16(self.balance_deltas.iter(), |bb, delta| {
+                bb.bytes(&delta.address.to_bytes())
+                    .u64(delta.spent.0)
+                    .u64(delta.received.0)
+            });
+        let bb = match &self.certificate {
+            None => bb.u8(0),
+            Some(certificate) => {
+                let (tag, payload) = certificate_payload(certificate);
+                bb.u8(1).u8(tag as u8).bytes(&payload)
+            }
+        };
+        bb.iter16(self.votes.iter(), |bb, vote| {
+            bb.bytes(vote.vote_plan.as_ref())
+                .u8(vote.proposal_index)
+                .bytes(vote.voter.as_ref().as_ref())
+        })
+    }
+
+    pub fn serialize(&self) -> ByteArray<Self> {
+        self.serialize_in(ByteBuilder::new()).finalize()
+    }
+}
+
+fn certificate_payload(certificate: &Certificate) -> (FragmentTag, Box<[u8]>) {
+    match certificate {
+        Certificate::StakeDelegation(c) => (FragmentTag::StakeDelegation, c.payload_data().0),
+        Certificate::OwnerStakeDelegation(c) => {
+            (FragmentTag::OwnerStakeDelegation, c.payload_data().0)
+        }
+        Certificate::PoolRegistration(c) => (FragmentTag::PoolRegistration, c.payload_data().0),
+        Certificate::PoolRetirement(c) => (FragmentTag::PoolRetirement, c.payload_data().0),
+        Certificate::PoolUpdate(c) => (FragmentTag::PoolUpdate, c.payload_data().0),
+        Certificate::VotePlan(c) => (FragmentTag::VotePlan, c.payload_data().0),
+        Certificate::VoteCast(c) => (FragmentTag::VoteCast, c.payload_data().0),
+        Certificate::VoteCastBatch(c) => (FragmentTag::VoteCastBatch, c.payload_data().0),
+        Certificate::VoteTally(c) => (FragmentTag::VoteTally, c.payload_data().0),
+        Certificate::EncryptedVoteTally(c) => (FragmentTag::EncryptedVoteTally, c.payload_data().0),
+        Certificate::HandleRegistration(c) => (FragmentTag::HandleRegistration, c.payload_data().0),
+        Certificate::VotingPowerSnapshot(c) => {
+            (FragmentTag::VotingPowerSnapshot, c.payload_data().0)
+        }
+        Certificate::PoolKeysRotation(c) => (FragmentTag::PoolKeysRotation, c.payload_data().0),
+        Certificate::VoteResultCertification(c) => {
+            (FragmentTag::VoteResultCertification, c.payload_data().0)
+        }
+        Certificate::CommitteeCandidacy(c) => (FragmentTag::CommitteeCandidacy, c.payload_data().0),
+        Certificate::DeferredStakeDelegation(c) => {
+            (FragmentTag::DeferredStakeDelegation, c.payload_data().0)
+        }
+        Certificate::DeferredOwnerStakeDelegation(c) => (
+            FragmentTag::DeferredOwnerStakeDelegation,
+            c.payload_data().0,
+        ),
+    }
+}
+
+fn read_certificate(buf: &mut ReadBuf) -> Result<Certificate, ReadError> {
+    let tag = buf.get_u8()?;
+    let certificate: Certificate = match FragmentTag::from_u8(tag) {
+        Some(FragmentTag::StakeDelegation) => StakeDelegation::read(buf)?.into(),
+        Some(FragmentTag::OwnerStakeDelegation) => OwnerStakeDelegation::read(buf)?.into(),
+        Some(FragmentTag::PoolRegistration) => PoolRegistration::read(buf)?.into(),
+        Some(FragmentTag::PoolRetirement) => PoolRetirement::read(buf)?.into(),
+        Some(FragmentTag::PoolUpdate) => PoolUpdate::read(buf)?.into(),
+        Some(FragmentTag::VotePlan) => VotePlan::read(buf)?.into(),
+        Some(FragmentTag::VoteCast) => VoteCast::read(buf)?.into(),
+        Some(FragmentTag::VoteCastBatch) => VoteCastBatch::read(buf)?.into(),
+        Some(FragmentTag::VoteTally) => VoteTally::read(buf)?.into(),
+        Some(FragmentTag::EncryptedVoteTally) => EncryptedVoteTally::read(buf)?.into(),
+        Some(FragmentTag::HandleRegistration) => HandleRegistration::read(buf)?.into(),
+        Some(FragmentTag::VotingPowerSnapshot) => VotingPowerSnapshot::read(buf)?.into(),
+        Some(FragmentTag::PoolKeysRotation) => PoolKeysRotation::read(buf)?.into(),
+        Some(FragmentTag::VoteResultCertification) => VoteResultCertification::read(buf)?.into(),
+        Some(FragmentTag::CommitteeCandidacy) => CommitteeCandidacy::read(buf)?.into(),
+        Some(FragmentTag::DeferredStakeDelegation) => DeferredStakeDelegation::read(buf)?.into(),
+        Some(FragmentTag::DeferredOwnerStakeDelegation) => {
+            DeferredOwnerStakeDelegation::read(buf)?.into()
+        }
+        _ => return Err(ReadError::UnknownTag(tag as u32)),
+    };
+    Ok(certificate)
+}
+
+impl property::Serialize for FragmentEffects {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(self.serialize().as_slice())?;
+        Ok(())
+    }
+}
+
+impl Readable for FragmentEffects {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let fragment_id = Hash::read(buf)?;
+        let status = match buf.get_u8()? {
+            0 => FragmentStatus::Accepted,
+            1 => {
+                let code = buf.get_u16()?;
+                let reason = RejectionReason::try_from(code)
+                    .map_err(|_| ReadError::UnknownTag(code as u32))?;
+                FragmentStatus::Rejected(reason)
+            }
+            t => return Err(ReadError::UnknownTag(t as u32)),
+        };
+        let fee = Value::read(buf)?;
+        let nb_deltas = buf.get_u16()?;
+        let balance_deltas = (0..nb_deltas)
+            .map(|_| {
+                Ok(BalanceDelta {
+                    address: Address::read(buf)?,
+                    spent: Value::read(buf)?,
+                    received: Value::read(buf)?,
+                })
+            })
+            .collect::<Result<_, ReadError>>()?;
+        let certificate = match buf.get_u8()? {
+            0 => None,
+            1 => Some(read_certificate(buf)?),
+            t => return Err(ReadError::UnknownTag(t as u32)),
+        };
+        let nb_votes = buf.get_u16()?;
+        let votes = (0..nb_votes)
+            .map(|_| {
+                Ok(VoteRecord {
+                    vote_plan: <[u8; 32]>::read(buf)?.into(),
+                    proposal_index: buf.get_u8()?,
+                    voter: account::Identifier::read(buf)?,
+                })
+            })
+            .collect::<Result<_, ReadError>>()?;
+
+        Ok(FragmentEffects {
+            fragment_id,
+            status,
+            fee,
+            balance_deltas,
+            certificate,
+            votes,
+        })
+    }
+}
+
+impl<'a> LedgerView<'a> {
+    /// Dry-run the fragment and compute what it would change in the ledger.
+    /// The viewed ledger is left untouched.
+    pub fn effects(&self, fragment: &Fragment) -> FragmentEffects {
+        let status = match self.dry_run(fragment) {
+            Ok(()) => FragmentStatus::Accepted,
+            Err(error) => FragmentStatus::Rejected(error.reason()),
+        };
+        let hash_version = self.ledger().settings.hash_version(self.date().epoch);
+        let mut effects = FragmentEffects {
+            fragment_id: fragment.hash_with_version(hash_version),
+            status,
+            fee: fee_paid(fragment),
+            balance_deltas: Vec::new(),
+            certificate: None,
+            votes: Vec::new(),
+        };
+        match fragment {
+            Fragment::Initial(_)
+            | Fragment::OldUtxoDeclaration(_)
+            | Fragment::UpdateProposal(_)
+            | Fragment::UpdateVote(_) => {}
+            Fragment::Transaction(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::OwnerStakeDelegation(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::StakeDelegation(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::DeferredOwnerStakeDelegation(tx) => {
+                self.transaction_effects(tx, &mut effects)
+            }
+            Fragment::DeferredStakeDelegation(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::PoolRegistration(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::PoolRetirement(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::PoolUpdate(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::PoolKeysRotation(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::VotePlan(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::VoteCast(tx) => {
+                self.transaction_effects(tx, &mut effects);
+                if let Some(voter) = voter(tx) {
+                    let vote_cast = tx.as_slice().payload().into_payload();
+                    effects.votes.push(VoteRecord {
+                        vote_plan: vote_cast.vote_plan().clone(),
+                        proposal_index: vote_cast.proposal_index(),
+                        voter,
+                    });
+                }
+            }
+            Fragment::VoteCastBatch(tx) => {
+                self.transaction_effects(tx, &mut effects);
+                if let Some(voter) = voter(tx) {
+                    let batch = tx.as_slice().payload().into_payload();
+                    effects
+                        .votes
+                        .extend(
+                            batch
+                                .ballots()
+                                .iter()
+                                .map(|(proposal_index, _)| VoteRecord {
+                                    vote_plan: batch.vote_plan().clone(),
+                                    proposal_index: *proposal_index,
+                                    voter: voter.clone(),
+                                }),
+                        );
+                }
+            }
+            Fragment::VoteTally(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::EncryptedVoteTally(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::HandleRegistration(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::VotingPowerSnapshot(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::VoteResultCertification(tx) => self.transaction_effects(tx, &mut effects),
+            Fragment::CommitteeCandidacy(tx) => self.transaction_effects(tx, &mut effects),
+        }
+        effects
+    }
+
+    fn transaction_effects<P: Payload>(&self, tx: &Transaction<P>, effects: &mut FragmentEffects) {
+        let tx = tx.as_slice();
+        let discrimination = self.ledger().settings.discrimination;
+        for (input, witness) in tx.inputs_and_witnesses().iter() {
+            let address = match (input.to_enum(), witness) {
+                (InputEnum::UtxoInput(pointer), _) => self
+                    .ledger()
+                    .utxos
+                    .get(&pointer.transaction_id, pointer.output_index)
+                    .map(|entry| entry.output.address.clone()),
+                (InputEnum::AccountInput(account, _), Witness::Multisig(_)) => Some(Address(
+                    discrimination,
+                    Kind::Multisig(account.to_multi_account().into()),
+                )),
+                (InputEnum::AccountInput(account, _), _) => account
+                    .to_single_account()
+                    .map(|id| Address(discrimination, Kind::Account(id.into()))),
+            };
+            if let Some(address) = address {
+                let delta = effects.delta_mut(address);
+                delta.spent = delta.spent.saturating_add(input.value());
+            }
+        }
+        for output in tx.outputs().iter() {
+            let delta = effects.delta_mut(output.address);
+            delta.received = delta.received.saturating_add(output.value);
+        }
+        effects.certificate = P::payload_to_certificate_slice(tx.payload())
+            .map(|certificate| certificate.into_owned());
+    }
+}
+
+/// The account casting the votes of a vote cast transaction
+fn voter<P: Payload>(tx: &Transaction<P>) -> Option<account::Identifier> {
+    match tx.as_slice().inputs().iter().next()?.to_enum() {
+        InputEnum::AccountInput(account, _) => account.to_single_account(),
+        InputEnum::UtxoInput(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{
+            builders::witness_builder::make_witness, data::AddressDataValue, ConfigBuilder,
+            LedgerBuilder, TestLedger,
+        },
+        transaction::TxBuilder,
+    };
+    use chain_addr::Discrimination;
+
+    fn transfer(
+        test_ledger: &TestLedger,
+        faucet: &AddressDataValue,
+        receiver: &AddressDataValue,
+        input: Value,
+    ) -> Fragment {
+        let builder = TxBuilder::new().set_nopayload().set_ios(
+            &[faucet.make_input_with_value(None, input)],
+            &[receiver.make_output()],
+        );
+        let witness = make_witness(
+            test_ledger.block0_hash(),
+            &faucet.clone().into(),
+            &builder.get_auth_data_for_witness().hash(),
+        );
+        builder
+            .set_witnesses(&[witness])
+            .set_payload_auth(&())
+            .into()
+    }
+
+    fn round_trip(effects: &FragmentEffects) {
+        let bytes = effects.serialize();
+        let decoded = FragmentEffects::read(&mut ReadBuf::from(bytes.as_slice())).unwrap();
+        assert_eq!(decoded.serialize().as_slice(), bytes.as_slice());
+        assert_eq!(decoded.status, effects.status);
+        assert_eq!(decoded.balance_deltas, effects.balance_deltas);
+        assert_eq!(decoded.votes, effects.votes);
+    }
+
+    #[test]
+    fn effects_of_a_transfer() {
+        let faucet = AddressDataValue::account(Discrimination::Test, Value(1000));
+        let receiver = AddressDataValue::utxo(Discrimination::Test, Value(100));
+        let test_ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .faucet(&faucet)
+            .build()
+            .expect("cannot build test ledger");
+
+        let fragment = transfer(&test_ledger, &faucet, &receiver, Value(100));
+        let effects = test_ledger.ledger.view().effects(&fragment);
+        assert!(effects.is_accepted());
+        assert_eq!(effects.fragment_id, fragment.hash());
+        assert_eq!(effects.fee, Value::zero());
+        assert_eq!(
+            effects.balance_deltas,
+            vec![
+                BalanceDelta {
+                    address: faucet.address_data.address.clone(),
+                    spent: Value(100),
+                    received: Value::zero(),
+                },
+                BalanceDelta {
+                    address: receiver.address_data.address.clone(),
+                    spent: Value::zero(),
+                    received: Value(100),
+                },
+            ]
+        );
+        assert!(effects.certificate.is_none());
+        assert!(effects.votes.is_empty());
+        round_trip(&effects);
+    }
+
+    #[test]
+    fn effects_of_a_rejected_transfer() {
+        let faucet = AddressDataValue::account(Discrimination::Test, Value(1000));
+        let receiver = AddressDataValue::utxo(Discrimination::Test, Value(100));
+        let test_ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .faucet(&faucet)
+            .build()
+            .expect("cannot build test ledger");
+
+        let fragment = transfer(&test_ledger, &faucet, &receiver, Value(90));
+        let effects = test_ledger.ledger.view().effects(&fragment);
+        assert_eq!(
+            effects.status,
+            FragmentStatus::Rejected(RejectionReason::TransactionBalanceInvalid)
+        );
+        assert_eq!(effects.balance_deltas.len(), 2);
+        round_trip(&effects);
+    }
+}
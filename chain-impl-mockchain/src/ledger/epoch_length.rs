@@ -0,0 +1,174 @@
+//! Change of the length of the epochs.
+//!
+//! The number of slots per epoch is changed like the other settings, by an
+//! update proposal or by a parameters governance action. The slots of an
+//! epoch cannot be renumbered once it started, so a new length only takes
+//! effect from the start of the epoch following the change: the ledger then
+//! starts a new [`TimeEra`] at this epoch, the previous epochs keeping their
+//! slots. The leadership of an epoch is computed from the era the ledger has
+//! at its start, see [`Ledger::at_epoch_start`].
+//!
+//! The dates recorded before the change, like the phases of the vote plans,
+//! are kept as they are and keep being compared with the dates of the chain
+//! by epoch then by slot. A slot beyond the end of its epoch in the new era
+//! stands for the start of the following epoch, see
+//! [`BlockDate::remap_to_epoch_length`].
+//!
+//! [`TimeEra`]: chain_time::TimeEra
+//! [`BlockDate::remap_to_epoch_length`]: crate::date::BlockDate::remap_to_epoch_length
+
+use super::Ledger;
+use crate::date::Epoch;
+use chain_time::Epoch as TimeEpoch;
+
+impl Ledger {
+    /// Start a new era at `epoch` if the epoch length of the settings
+    /// differs from the one of the current era
+    pub(super) fn update_era(&mut self, epoch: Epoch) {
+        let slots_per_epoch = self.settings.slots_per_epoch;
+        if slots_per_epoch == 0 || slots_per_epoch == self.era.slots_per_epoch() {
+            return;
+        }
+        let epoch = TimeEpoch(epoch).max(self.era.epoch_start());
+        self.era = self.era.with_epoch_length(epoch, slots_per_epoch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        chaineval::ConsensusEvalContext,
+        config::ConfigParam,
+        date::BlockDate,
+        leadership::Leadership,
+        testing::{
+            ledger::ConfigBuilder,
+            scenario::{prepare_scenario, proposal, vote_plan, wallet},
+            VoteTestGen,
+        },
+        vote::Choice,
+    };
+    use chain_time::era::{Epoch as TimeEpoch, EpochPosition, EpochSlotOffset};
+
+    const ALICE: &str = "Alice";
+    const STAKE_POOL: &str = "stake_pool";
+
+    fn date(epoch: u32, slot_id: u32) -> BlockDate {
+        BlockDate { epoch, slot_id }
+    }
+
+    fn position(epoch: u32, slot: u32) -> EpochPosition {
+        EpochPosition {
+            epoch: TimeEpoch(epoch),
+            slot: EpochSlotOffset(slot),
+        }
+    }
+
+    #[test]
+    fn epoch_length_change_keeps_vote_plans_spanning_it() {
+        let favorable = Choice::new(1);
+        let (mut ledger, controller) = prepare_scenario()
+            .with_config(ConfigBuilder::new(0).with_slots_per_epoch(10))
+            .with_initials(vec![wallet(ALICE)
+                .with(1_000)
+                .owns(STAKE_POOL)
+                .committee_member()])
+            .with_vote_plans(vec![
+                vote_plan("epoch_length")
+                    .owner(ALICE)
+                    .vote_dates(date(0, 0), date(0, 5), date(0, 9))
+                    .with_proposal(
+                        proposal(VoteTestGen::external_proposal_id())
+                            .options(3)
+                            .action_update_parameters(vec![ConfigParam::SlotsPerEpoch(5)]),
+                    ),
+                vote_plan("spanning")
+                    .owner(ALICE)
+                    .vote_dates(date(0, 0), date(1, 8), date(2, 8))
+                    .with_proposal(
+                        proposal(VoteTestGen::external_proposal_id())
+                            .options(3)
+                            .action_off_chain(),
+                    ),
+            ])
+            .build()
+            .unwrap();
+        let mut alice = controller.wallet(ALICE).unwrap();
+        let epoch_length = controller.vote_plan("epoch_length").unwrap();
+        let spanning = controller.vote_plan("spanning").unwrap();
+
+        controller
+            .cast_vote_public(
+                &alice,
+                &epoch_length,
+                &epoch_length.proposal(0).id(),
+                favorable,
+                &mut ledger,
+            )
+            .unwrap();
+        alice.confirm_transaction();
+        ledger.fast_forward_to(date(0, 5));
+        controller
+            .tally_vote_public(&alice, &epoch_length, &mut ledger)
+            .unwrap();
+        alice.confirm_transaction();
+        ledger.apply_protocol_changes().unwrap();
+
+        // the current epoch keeps its length, the leadership of the next one
+        // is computed with the new length
+        let old_era = ledger.era().clone();
+        assert_eq!(ledger.ledger.settings.slots_per_epoch, 5);
+        assert_eq!(old_era.slots_per_epoch(), 10);
+        let next_epoch = ledger.ledger.at_epoch_start(1).unwrap();
+        assert_eq!(next_epoch.era().slots_per_epoch(), 5);
+        assert_eq!(Leadership::new(1, &next_epoch).era().slots_per_epoch(), 5);
+
+        let parameters = ledger.ledger.get_ledger_parameters();
+        let chain_length = ledger.ledger.chain_length().increase();
+        ledger.ledger = ledger
+            .ledger
+            .begin_block(parameters, chain_length, date(1, 0))
+            .unwrap()
+            .finish(&ConsensusEvalContext::Bft);
+        let era = ledger.era().clone();
+        assert_eq!(era, *next_epoch.era());
+        assert_eq!(era.epoch_start(), TimeEpoch(1));
+        let epoch1 = old_era.from_era_to_slot(position(1, 0));
+        assert_eq!(era.from_slot_to_era(epoch1), Some(position(1, 0)));
+        assert_eq!(
+            era.from_slot_to_era(chain_time::Slot(epoch1.0 + 7)),
+            Some(position(2, 2))
+        );
+
+        // the phases of the vote plan are still ordered with the dates of
+        // the new era, its tally starting at the start of epoch 2
+        assert_eq!(date(1, 8).remap_to_epoch_length(5), date(2, 0));
+        assert_eq!(date(2, 8).remap_to_epoch_length(5), date(3, 0));
+        ledger.fast_forward_to(date(1, 4));
+        controller
+            .cast_vote_public(
+                &alice,
+                &spanning,
+                &spanning.proposal(0).id(),
+                favorable,
+                &mut ledger,
+            )
+            .unwrap();
+        alice.confirm_transaction();
+
+        ledger.fast_forward_to(date(2, 0));
+        assert!(controller
+            .cast_vote_public(
+                &alice,
+                &spanning,
+                &spanning.proposal(0).id(),
+                favorable,
+                &mut ledger,
+            )
+            .is_err());
+        ledger.fast_forward_to(date(2, 4));
+        controller
+            .tally_vote_public(&alice, &spanning, &mut ledger)
+            .unwrap();
+    }
+}
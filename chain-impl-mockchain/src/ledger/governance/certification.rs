@@ -0,0 +1,89 @@
+use crate::{
+    certificate::{VoteAction, VotePlanId, VoteResultCertification},
+    vote::CommitteeId,
+};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum VoteResultCertificationError {
+    #[error("the vote result is signed by {0} which is not in the committee")]
+    SignerNotInCommittee(CommitteeId),
+    #[error("the vote result is signed by {signers} of the {committee} committee members, a majority is required")]
+    NoMajority { signers: usize, committee: usize },
+    #[error("the vote plan {0} is not in the ledger")]
+    UnknownVotePlan(VotePlanId),
+    #[error("the vote plan {0} is not tallied yet")]
+    NotTallied(VotePlanId),
+    #[error("the vote result does not match the tally of the vote plan {0}")]
+    ResultsMismatch(VotePlanId),
+    #[error("the vote result of the vote plan {0} is already certified")]
+    AlreadyCertified(VotePlanId),
+}
+
+/// the results of the vote plans certified by the committee, and the
+/// actions accepted by the tallies which wait for the certification of the
+/// results of their vote plan, in order of acceptance
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ResultCertifications {
+    certified: Vec<VoteResultCertification>,
+    held: Vec<(VotePlanId, VoteAction)>,
+}
+
+impl ResultCertifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the certified results of the given vote plan, if any
+    pub fn get(&self, vote_plan: &VotePlanId) -> Option<&VoteResultCertification> {
+        self.certified
+            .iter()
+            .find(|certification| certification.vote_plan() == vote_plan)
+    }
+
+    pub fn is_certified(&self, vote_plan: &VotePlanId) -> bool {
+        self.get(vote_plan).is_some()
+    }
+
+    /// the actions waiting for the certification of their vote plan
+    pub fn held(&self) -> impl Iterator<Item = &(VotePlanId, VoteAction)> {
+        self.held.iter()
+    }
+
+    pub fn hold(&mut self, vote_plan: VotePlanId, action: VoteAction) {
+        self.held.push((vote_plan, action))
+    }
+
+    /// record the certified results of a vote plan, returns the actions of
+    /// the vote plan which were held until now
+    pub fn certify(&mut self, certification: VoteResultCertification) -> Vec<VoteAction> {
+        let (released, held) = std::mem::take(&mut self.held)
+            .into_iter()
+            .partition(|(vote_plan, _)| vote_plan == certification.vote_plan());
+        self.held = held;
+        self.certified.push(certification);
+        released.into_iter().map(|(_, action)| action).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::VoteTestGen;
+
+    #[test]
+    fn certify_releases_the_held_actions_of_the_vote_plan() {
+        let first = VoteTestGen::vote_plan().to_id();
+        let second = VoteTestGen::vote_plan().to_id();
+        let mut certifications = ResultCertifications::new();
+        certifications.hold(first.clone(), VoteAction::OffChain);
+        certifications.hold(second.clone(), VoteAction::OffChain);
+
+        let released =
+            certifications.certify(VoteResultCertification::new(second.clone(), Vec::new()));
+        assert_eq!(released, vec![VoteAction::OffChain]);
+        assert!(certifications.is_certified(&second));
+        assert!(!certifications.is_certified(&first));
+        assert_eq!(certifications.held().count(), 1);
+    }
+}
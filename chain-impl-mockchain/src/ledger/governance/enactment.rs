@@ -0,0 +1,88 @@
+use crate::{
+    certificate::{VoteAction, VotePlanId},
+    date::Epoch,
+};
+
+/// an action accepted by the tally of a vote plan, waiting for the end of
+/// the epoch it is enacted at
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PendingEnactment {
+    pub vote_plan: VotePlanId,
+    /// the action is enacted at the transition from this epoch to the next
+    pub epoch: Epoch,
+    pub action: VoteAction,
+}
+
+/// the accepted actions of the vote plans with an enactment delay, in
+/// order of acceptance
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PendingEnactments {
+    pending: Vec<PendingEnactment>,
+}
+
+impl PendingEnactments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PendingEnactment> {
+        self.pending.iter()
+    }
+
+    pub fn register(&mut self, enactment: PendingEnactment) {
+        self.pending.push(enactment)
+    }
+
+    /// drop the pending actions of the given vote plan, returns the number
+    /// of actions cancelled
+    pub fn cancel(&mut self, vote_plan: &VotePlanId) -> usize {
+        let before = self.pending.len();
+        self.pending
+            .retain(|enactment| &enactment.vote_plan != vote_plan);
+        before - self.pending.len()
+    }
+
+    /// remove and return the actions to enact at the end of `epoch`,
+    /// including the ones which were due earlier, in order of acceptance
+    pub fn take_due(&mut self, epoch: Epoch) -> Vec<PendingEnactment> {
+        let (due, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|enactment| enactment.epoch <= epoch);
+        self.pending = pending;
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::VoteTestGen;
+
+    fn enactment(vote_plan: &VotePlanId, epoch: Epoch) -> PendingEnactment {
+        PendingEnactment {
+            vote_plan: vote_plan.clone(),
+            epoch,
+            action: VoteAction::OffChain,
+        }
+    }
+
+    #[test]
+    fn take_due_and_cancel() {
+        let first = VoteTestGen::vote_plan().to_id();
+        let second = VoteTestGen::vote_plan().to_id();
+        let mut enactments = PendingEnactments::new();
+        enactments.register(enactment(&first, 3));
+        enactments.register(enactment(&second, 1));
+        enactments.register(enactment(&first, 5));
+
+        assert!(enactments.take_due(0).is_empty());
+        assert_eq!(enactments.take_due(2), vec![enactment(&second, 1)]);
+        assert_eq!(enactments.cancel(&second), 0);
+        assert_eq!(enactments.cancel(&first), 2);
+        assert!(enactments.is_empty());
+    }
+}
@@ -1,14 +1,22 @@
 //! define how to rule over the blockchain
 //!
 
+mod certification;
+mod enactment;
 mod parameters;
 mod treasury;
 
 pub use self::{
+    certification::{ResultCertifications, VoteResultCertificationError},
+    enactment::{PendingEnactment, PendingEnactments},
     parameters::{
-        ParametersGovernance, ParametersGovernanceAction, ParametersGovernanceActionType,
+        ParametersChangesError, ParametersGovernance, ParametersGovernanceAction,
+        ParametersGovernanceActionType,
+    },
+    treasury::{
+        SkippedTreasuryAction, TreasuryBudget, TreasuryGovernance, TreasuryGovernanceAction,
+        TreasuryGovernanceActionType, TreasurySpending,
     },
-    treasury::{TreasuryGovernance, TreasuryGovernanceAction, TreasuryGovernanceActionType},
 };
 use crate::{
     rewards::Ratio,
@@ -20,6 +28,11 @@ use std::num::NonZeroU64;
 pub struct Governance {
     pub treasury: treasury::TreasuryGovernance,
     pub parameters: parameters::ParametersGovernance,
+    /// the accepted actions of the vote plans with an enactment delay
+    pub enactments: enactment::PendingEnactments,
+    /// the results certified by the committee and the actions waiting for
+    /// them
+    pub certifications: certification::ResultCertifications,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,19 +1,55 @@
-use crate::{ledger::governance::GovernanceAcceptanceCriteria, value::Value};
-use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use crate::{
+    certificate::VotePlanId,
+    config::{ConfigParam, Tag},
+    fragment::ConfigParams,
+    ledger::governance::GovernanceAcceptanceCriteria,
+    setting,
+    value::Value,
+};
+use chain_core::{
+    mempack::{ReadBuf, ReadError, Readable},
+    property::Serialize as _,
+};
 use imhamt::Hamt;
 use std::collections::hash_map::DefaultHasher;
+use thiserror::Error;
 use typed_bytes::ByteBuilder;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParametersGovernanceAction {
     NoOp,
-    RewardAdd { value: Value },
+    RewardAdd {
+        value: Value,
+    },
+    /// change several settings at once. The changes are applied together
+    /// when the protocol changes are applied: if the settings resulting
+    /// from the whole package are not valid, none of the changes is applied.
+    UpdateParameters {
+        changes: ConfigParams,
+    },
+    /// drop the actions of the given vote plan which are still waiting for
+    /// their enactment
+    CancelEnactment {
+        vote_plan: VotePlanId,
+    },
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub enum ParametersGovernanceActionType {
     NoOp,
     RewardAdd,
+    UpdateParameters,
+    CancelEnactment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParametersChangesError {
+    #[error("the parameters update does not change any setting")]
+    NoChanges,
+    #[error("the setting {0:?} cannot be changed")]
+    ReadOnlySetting(Tag),
+    #[error("the setting {0:?} is changed more than once")]
+    DuplicatedSetting(Tag),
 }
 
 #[derive(Default, Clone, Eq, PartialEq)]
@@ -31,13 +67,55 @@ impl ParametersGovernanceAction {
         match self {
             Self::NoOp => ParametersGovernanceActionType::NoOp,
             Self::RewardAdd { .. } => ParametersGovernanceActionType::RewardAdd,
+            Self::UpdateParameters { .. } => ParametersGovernanceActionType::UpdateParameters,
+            Self::CancelEnactment { .. } => ParametersGovernanceActionType::CancelEnactment,
+        }
+    }
+
+    /// check the action can be voted on, regardless of the current
+    /// settings: a parameters update must change at least one setting, may
+    /// only change the settings an update proposal could change, and may
+    /// set each of them only once. Adding and removing BFT leaders or
+    /// committee members can be repeated.
+    pub fn check(&self) -> Result<(), ParametersChangesError> {
+        let changes = match self {
+            Self::NoOp | Self::RewardAdd { .. } | Self::CancelEnactment { .. } => return Ok(()),
+            Self::UpdateParameters { changes } => changes,
+        };
+        if changes.iter().next().is_none() {
+            return Err(ParametersChangesError::NoChanges);
+        }
+
+        let mut seen = Vec::new();
+        for param in changes.iter() {
+            let tag = Tag::from(param);
+            if setting::is_read_only(param) {
+                return Err(ParametersChangesError::ReadOnlySetting(tag));
+            }
+            match param {
+                ConfigParam::AddBftLeader(_)
+                | ConfigParam::RemoveBftLeader(_)
+                | ConfigParam::AddCommitteeId(_)
+                | ConfigParam::RemoveCommitteeId(_) => {}
+                _ if seen.contains(&tag) => {
+                    return Err(ParametersChangesError::DuplicatedSetting(tag));
+                }
+                _ => seen.push(tag),
+            }
         }
+        Ok(())
     }
 
     pub(crate) fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
         match self {
             Self::NoOp => bb.u8(0),
             Self::RewardAdd { value } => bb.u8(1).u64(value.0),
+            Self::UpdateParameters { changes } => bb.u8(2).bytes(
+                &changes
+                    .serialize_as_vec()
+                    .expect("in-memory serialization of the config params"),
+            ),
+            Self::CancelEnactment { vote_plan } => bb.u8(3).bytes(vote_plan.as_ref()),
         }
     }
 }
@@ -110,6 +188,14 @@ impl Readable for ParametersGovernanceAction {
                 let value = Value::read(buf)?;
                 Ok(Self::RewardAdd { value })
             }
+            2 => {
+                let changes = ConfigParams::read(buf)?;
+                Ok(Self::UpdateParameters { changes })
+            }
+            3 => {
+                let vote_plan = <[u8; 32]>::read(buf)?.into();
+                Ok(Self::CancelEnactment { vote_plan })
+            }
             t => Err(ReadError::UnknownTag(t as u32)),
         }
     }
@@ -118,17 +204,31 @@ impl Readable for ParametersGovernanceAction {
 #[cfg(test)]
 mod tests {
 
-    use super::{ParametersGovernance, ParametersGovernanceAction, ParametersGovernanceActionType};
-    use crate::{ledger::governance::GovernanceAcceptanceCriteria, value::Value, vote::Choice};
+    use super::{
+        ParametersChangesError, ParametersGovernance, ParametersGovernanceAction,
+        ParametersGovernanceActionType,
+    };
+    use crate::{
+        config::{ConfigParam, Tag},
+        fragment::ConfigParams,
+        ledger::governance::GovernanceAcceptanceCriteria,
+        value::Value,
+        vote::{Choice, CommitteeId},
+    };
+    use chain_addr::Discrimination;
+    use chain_core::mempack::{ReadBuf, Readable};
     use quickcheck::{Arbitrary, Gen};
     use quickcheck_macros::quickcheck;
+    use typed_bytes::ByteBuilder;
 
     impl Arbitrary for ParametersGovernanceActionType {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            let option = u8::arbitrary(g) % 2;
+            let option = u8::arbitrary(g) % 4;
             match option {
                 0 => ParametersGovernanceActionType::NoOp,
                 1 => ParametersGovernanceActionType::RewardAdd,
+                2 => ParametersGovernanceActionType::UpdateParameters,
+                3 => ParametersGovernanceActionType::CancelEnactment,
                 _ => unreachable!(),
             }
         }
@@ -136,12 +236,18 @@ mod tests {
 
     impl Arbitrary for ParametersGovernanceAction {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            let option = u8::arbitrary(g) % 2;
+            let option = u8::arbitrary(g) % 4;
             match option {
                 0 => ParametersGovernanceAction::NoOp,
                 1 => ParametersGovernanceAction::RewardAdd {
                     value: Arbitrary::arbitrary(g),
                 },
+                2 => ParametersGovernanceAction::UpdateParameters {
+                    changes: Arbitrary::arbitrary(g),
+                },
+                3 => ParametersGovernanceAction::CancelEnactment {
+                    vote_plan: Arbitrary::arbitrary(g),
+                },
                 _ => unreachable!(),
             }
         }
@@ -154,6 +260,68 @@ mod tests {
 
         let action = ParametersGovernanceAction::RewardAdd { value: Value(10) };
         assert_eq!(action.to_type(), ParametersGovernanceActionType::RewardAdd);
+
+        let action = ParametersGovernanceAction::UpdateParameters {
+            changes: ConfigParams::new(),
+        };
+        assert_eq!(
+            action.to_type(),
+            ParametersGovernanceActionType::UpdateParameters
+        );
+    }
+
+    fn update_parameters(params: Vec<ConfigParam>) -> ParametersGovernanceAction {
+        let mut changes = ConfigParams::new();
+        for param in params {
+            changes.push(param);
+        }
+        ParametersGovernanceAction::UpdateParameters { changes }
+    }
+
+    #[test]
+    pub fn parameters_update_check() {
+        let action = update_parameters(vec![
+            ConfigParam::SlotDuration(5),
+            ConfigParam::SlotsPerEpoch(200),
+        ]);
+        assert_eq!(action.check(), Ok(()));
+
+        let action = update_parameters(vec![]);
+        assert_eq!(action.check(), Err(ParametersChangesError::NoChanges));
+
+        let action = update_parameters(vec![
+            ConfigParam::SlotDuration(5),
+            ConfigParam::Discrimination(Discrimination::Test),
+        ]);
+        assert_eq!(
+            action.check(),
+            Err(ParametersChangesError::ReadOnlySetting(Tag::Discrimination))
+        );
+
+        let action = update_parameters(vec![
+            ConfigParam::SlotDuration(5),
+            ConfigParam::SlotsPerEpoch(200),
+            ConfigParam::SlotDuration(10),
+        ]);
+        assert_eq!(
+            action.check(),
+            Err(ParametersChangesError::DuplicatedSetting(Tag::SlotDuration))
+        );
+
+        let committee_id = CommitteeId::from([1; 32]);
+        let action = update_parameters(vec![
+            ConfigParam::AddCommitteeId(committee_id),
+            ConfigParam::AddCommitteeId(CommitteeId::from([2; 32])),
+            ConfigParam::RemoveCommitteeId(committee_id),
+        ]);
+        assert_eq!(action.check(), Ok(()));
+    }
+
+    #[quickcheck]
+    pub fn parameters_action_serialization(action: ParametersGovernanceAction) -> bool {
+        let bytes = action.serialize_in(ByteBuilder::new()).finalize_as_vec();
+        let mut buf = ReadBuf::from(&bytes);
+        ParametersGovernanceAction::read(&mut buf).unwrap() == action && buf.is_end()
     }
 
     #[test]
@@ -1,7 +1,11 @@
-use crate::{ledger::governance::GovernanceAcceptanceCriteria, value::Value};
+use crate::{
+    certificate::VotePlanId, date::Epoch, ledger::governance::GovernanceAcceptanceCriteria,
+    value::Value,
+};
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use imhamt::Hamt;
 use std::collections::hash_map::DefaultHasher;
+use std::num::NonZeroU32;
 use typed_bytes::ByteBuilder;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -16,12 +20,50 @@ pub enum TreasuryGovernanceActionType {
     TransferToRewards,
 }
 
+/// The maximum value the treasury actions can draw from the treasury per
+/// budget period. The budget periods are `epochs` long, the first one
+/// starting at the epoch 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreasuryBudget {
+    pub epochs: NonZeroU32,
+    pub cap: Value,
+}
+
+impl TreasuryBudget {
+    /// the first epoch of the budget period of `epoch`
+    pub fn period_start(&self, epoch: Epoch) -> Epoch {
+        epoch - epoch % self.epochs.get()
+    }
+}
+
+/// The value drawn from the treasury by the treasury actions during a
+/// budget period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreasurySpending {
+    pub period_start: Epoch,
+    pub spent: Value,
+}
+
+/// A treasury action of a vote plan which was dropped because it drew more
+/// than the remaining budget of its budget period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedTreasuryAction {
+    pub vote_plan: VotePlanId,
+    pub epoch: Epoch,
+    pub value: Value,
+    pub remaining_budget: Value,
+}
+
 #[derive(Default, Clone, Eq, PartialEq)]
 pub struct TreasuryGovernance {
     acceptance_criteria_per_action:
         Hamt<DefaultHasher, TreasuryGovernanceActionType, GovernanceAcceptanceCriteria>,
 
     default_acceptance_criteria: GovernanceAcceptanceCriteria,
+
+    spending: TreasurySpending,
+
+    skipped: Vec<SkippedTreasuryAction>,
 }
 
 impl TreasuryGovernanceAction {
@@ -83,6 +125,58 @@ impl TreasuryGovernance {
             .lookup(&action)
             .unwrap_or_else(|| self.default_acceptance_criteria())
     }
+
+    /// the value drawn from the treasury during the budget period of
+    /// `epoch`, the budget periods being one epoch long without budget
+    pub fn spent(&self, budget: Option<&TreasuryBudget>, epoch: Epoch) -> Value {
+        if self.spending.period_start == period_start(budget, epoch) {
+            self.spending.spent
+        } else {
+            Value::zero()
+        }
+    }
+
+    /// the value the treasury actions can still draw from the treasury
+    /// during the budget period of `epoch`
+    pub fn remaining_budget(&self, budget: &TreasuryBudget, epoch: Epoch) -> Value {
+        budget
+            .cap
+            .checked_sub(self.spent(Some(budget), epoch))
+            .unwrap_or_else(|_| Value::zero())
+    }
+
+    /// record the value drawn from the treasury by an action at `epoch`
+    pub fn record_spending(&mut self, budget: Option<&TreasuryBudget>, epoch: Epoch, value: Value) {
+        self.spending = TreasurySpending {
+            period_start: period_start(budget, epoch),
+            spent: self.spent(budget, epoch).saturating_add(value),
+        };
+    }
+
+    /// the treasury actions dropped during the budget period of `epoch`
+    pub fn skipped(
+        &self,
+        budget: &TreasuryBudget,
+        epoch: Epoch,
+    ) -> impl Iterator<Item = &SkippedTreasuryAction> {
+        let period_start = budget.period_start(epoch);
+        self.skipped
+            .iter()
+            .filter(move |skipped| budget.period_start(skipped.epoch) == period_start)
+    }
+
+    /// record a treasury action dropped for lack of budget, forgetting the
+    /// ones of the previous budget periods
+    pub fn record_skipped(&mut self, budget: &TreasuryBudget, skipped: SkippedTreasuryAction) {
+        let period_start = budget.period_start(skipped.epoch);
+        self.skipped
+            .retain(|previous| budget.period_start(previous.epoch) == period_start);
+        self.skipped.push(skipped);
+    }
+}
+
+fn period_start(budget: Option<&TreasuryBudget>, epoch: Epoch) -> Epoch {
+    budget.map_or(epoch, |budget| budget.period_start(epoch))
 }
 
 /* Ser/De ******************************************************************* */
@@ -103,10 +197,14 @@ impl Readable for TreasuryGovernanceAction {
 #[cfg(test)]
 mod tests {
 
-    use super::{TreasuryGovernance, TreasuryGovernanceAction, TreasuryGovernanceActionType};
+    use super::{
+        SkippedTreasuryAction, TreasuryBudget, TreasuryGovernance, TreasuryGovernanceAction,
+        TreasuryGovernanceActionType,
+    };
     use crate::{ledger::governance::GovernanceAcceptanceCriteria, value::Value, vote::Choice};
     use quickcheck::{Arbitrary, Gen};
     use quickcheck_macros::quickcheck;
+    use std::num::NonZeroU32;
 
     impl Arbitrary for TreasuryGovernanceActionType {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
@@ -153,6 +251,43 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn treasury_governance_spending_per_budget_period() {
+        let budget = TreasuryBudget {
+            epochs: NonZeroU32::new(3).unwrap(),
+            cap: Value(100),
+        };
+        let mut governance = TreasuryGovernance::new();
+        assert_eq!(governance.remaining_budget(&budget, 4), Value(100));
+
+        governance.record_spending(Some(&budget), 4, Value(60));
+        governance.record_spending(Some(&budget), 5, Value(30));
+        assert_eq!(governance.spent(Some(&budget), 3), Value(90));
+        assert_eq!(governance.remaining_budget(&budget, 5), Value(10));
+        // a new budget period starts at the epoch 6
+        assert_eq!(governance.remaining_budget(&budget, 6), Value(100));
+
+        let skipped = |epoch| SkippedTreasuryAction {
+            vote_plan: [0; 32].into(),
+            epoch,
+            value: Value(50),
+            remaining_budget: Value(10),
+        };
+        governance.record_skipped(&budget, skipped(5));
+        assert_eq!(governance.skipped(&budget, 3).count(), 1);
+        governance.record_skipped(&budget, skipped(6));
+        assert_eq!(governance.skipped(&budget, 5).count(), 0);
+        assert_eq!(
+            governance.skipped(&budget, 8).collect::<Vec<_>>(),
+            vec![&skipped(6)]
+        );
+
+        // without budget, the spending is tracked per epoch
+        governance.record_spending(None, 7, Value(10));
+        assert_eq!(governance.spent(None, 7), Value(10));
+        assert_eq!(governance.spent(None, 8), Value::zero());
+    }
+
     fn some_new_governance_criteria() -> GovernanceAcceptanceCriteria {
         let mut new_governance_criteria: GovernanceAcceptanceCriteria = Default::default();
         let new_option = Choice::new(20);
@@ -0,0 +1,139 @@
+//! Registry of the handles naming accounts.
+//!
+//! A handle is registered with a [`HandleRegistration`] certificate signed by
+//! the account it names, locking the deposit configured with
+//! [`ConfigParam::HandleRegistrationDeposit`]. When
+//! [`ConfigParam::HandleValidity`] is set the handle expires after that many
+//! epochs, unless its owner renews the registration with a new certificate.
+//! An expired handle stays in the registry but does not resolve anymore, and
+//! can be registered by another account, refunding the deposit of the
+//! previous owner.
+//!
+//! [`HandleRegistration`]: crate::certificate::HandleRegistration
+//! [`ConfigParam::HandleRegistrationDeposit`]: crate::config::ConfigParam
+//! [`ConfigParam::HandleValidity`]: crate::config::ConfigParam
+
+use crate::account;
+use crate::certificate::Handle;
+use crate::date::Epoch;
+use imhamt::{Hamt, HamtIter};
+use std::collections::hash_map::DefaultHasher;
+
+/// The registration of a handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandleRecord {
+    /// account named by the handle
+    pub account: account::Identifier,
+    /// last epoch during which the handle is valid, none if it never expires
+    pub expiry: Option<Epoch>,
+}
+
+impl HandleRecord {
+    pub fn is_valid_at(&self, epoch: Epoch) -> bool {
+        match self.expiry {
+            None => true,
+            Some(expiry) => epoch <= expiry,
+        }
+    }
+}
+
+/// All the handles registered in the ledger, including the expired ones
+#[derive(Clone, PartialEq, Eq)]
+pub struct Handles {
+    handles: Hamt<DefaultHasher, Handle, HandleRecord>,
+}
+
+impl Default for Handles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Handles {
+    pub fn new() -> Self {
+        Handles {
+            handles: Hamt::new(),
+        }
+    }
+
+    /// The registration of the handle, whether it has expired or not
+    pub fn get(&self, handle: &Handle) -> Option<&HandleRecord> {
+        self.handles.lookup(handle)
+    }
+
+    /// The account named by the handle during the given epoch
+    pub fn resolve(&self, handle: &Handle, epoch: Epoch) -> Option<&account::Identifier> {
+        self.get(handle)
+            .filter(|record| record.is_valid_at(epoch))
+            .map(|record| &record.account)
+    }
+
+    /// The handles naming the account during the given epoch
+    pub fn handles_of<'a>(
+        &'a self,
+        account: &'a account::Identifier,
+        epoch: Epoch,
+    ) -> impl Iterator<Item = &'a Handle> + 'a {
+        self.handles.iter().filter_map(move |(handle, record)| {
+            if &record.account == account && record.is_valid_at(epoch) {
+                Some(handle)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Record the registration of the handle, replacing the previous one
+    pub fn set(&self, handle: Handle, record: HandleRecord) -> Self {
+        let handles = self
+            .handles
+            .insert_or_update_simple(handle, record.clone(), |_| Some(record));
+        Handles { handles }
+    }
+
+    pub fn iter(&self) -> HamtIter<'_, Handle, HandleRecord> {
+        self.handles.iter()
+    }
+}
+
+impl std::fmt::Debug for Handles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.handles.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGen;
+
+    #[test]
+    fn resolve_until_expiry() {
+        let alice = TestGen::identifier();
+        let bob = TestGen::identifier();
+        let handle = Handle::new("alice").unwrap();
+
+        let handles = Handles::new().set(
+            handle.clone(),
+            HandleRecord {
+                account: alice.clone(),
+                expiry: Some(3),
+            },
+        );
+        assert_eq!(handles.resolve(&handle, 3), Some(&alice));
+        assert_eq!(handles.handles_of(&alice, 0).count(), 1);
+        assert_eq!(handles.resolve(&handle, 4), None);
+        assert_eq!(handles.handles_of(&alice, 4).count(), 0);
+        assert!(handles.get(&handle).is_some());
+
+        let handles = handles.set(
+            handle.clone(),
+            HandleRecord {
+                account: bob.clone(),
+                expiry: None,
+            },
+        );
+        assert_eq!(handles.resolve(&handle, 1000), Some(&bob));
+        assert_eq!(handles.iter().count(), 1);
+    }
+}
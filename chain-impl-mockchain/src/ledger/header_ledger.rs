@@ -0,0 +1,365 @@
+//! Validation of the headers without the blocks contents.
+//!
+//! A [`HeaderLedger`] holds the part of the state of the ledger the headers
+//! are validated against: the tip of the chain, the leadership of the current
+//! epoch and the consensus nonce. It is advanced header by header, so that a
+//! node can validate the headers of a branch, and choose between forks, before
+//! downloading the contents of the blocks.
+//!
+//! The stake distribution and the update proposals are changed by the contents
+//! of the blocks, so the leadership of a new epoch cannot always be derived
+//! from the headers: the BFT leaders are carried over from the previous epoch,
+//! while the Genesis Praos leadership of the new epoch has to be provided with
+//! [`HeaderLedger::with_leadership`], once computed from the full ledger at the
+//! end of the previous epoch.
+
+use super::Ledger;
+use crate::{
+    chaineval::{ConsensusEvalContext, PraosNonce},
+    chaintypes::{ChainLength, HeaderId},
+    date::{BlockDate, Epoch},
+    header::{Header, Proof},
+    leadership::{self, Leadership},
+};
+use chain_crypto::Verification;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HeaderLedgerError {
+    #[error("The header does not follow the tip {tip}, its parent is {parent}")]
+    WrongParent { tip: HeaderId, parent: HeaderId },
+    #[error("Wrong chain length, expected {expected} but received {actual}")]
+    WrongChainLength {
+        actual: ChainLength,
+        expected: ChainLength,
+    },
+    #[error("Non Monotonic date, chain date is at {chain_date} but the header is at {block_date}")]
+    NonMonotonicDate {
+        block_date: BlockDate,
+        chain_date: BlockDate,
+    },
+    #[error("The leadership of epoch {epoch} is not known")]
+    UnknownLeadership { epoch: Epoch },
+    #[error("Invalid BFT leader signature")]
+    InvalidBftSignature,
+    #[error("Invalid block leader")]
+    InvalidLeader(#[source] leadership::Error),
+}
+
+/// The state of the chain needed to validate the headers
+#[derive(Clone)]
+pub struct HeaderLedger {
+    tip: HeaderId,
+    chain_length: ChainLength,
+    date: BlockDate,
+    // updated with the VRF output of every Genesis Praos header
+    consensus_nonce: PraosNonce,
+    leadership: Arc<Leadership>,
+    // the leadership of a later epoch, provided before reaching it
+    next_leadership: Option<Arc<Leadership>>,
+}
+
+impl HeaderLedger {
+    /// Create the header ledger of the chain whose tip is `tip`, `ledger`
+    /// being the state after the tip has been applied.
+    pub fn new(ledger: &Ledger, tip: HeaderId) -> Self {
+        HeaderLedger {
+            tip,
+            chain_length: ledger.chain_length(),
+            date: ledger.date(),
+            consensus_nonce: ledger.settings.consensus_nonce.clone(),
+            leadership: Arc::new(Leadership::new(ledger.date().epoch, ledger)),
+            next_leadership: None,
+        }
+    }
+
+    /// Provide the leadership of an epoch, used to validate its headers:
+    /// typically the leadership of the next epoch, created with
+    /// [`Leadership::new`] from the full ledger at the end of the current one.
+    pub fn with_leadership(mut self, leadership: Arc<Leadership>) -> Self {
+        if leadership.epoch() == self.leadership.epoch() {
+            self.leadership = leadership;
+        } else {
+            self.next_leadership = Some(leadership);
+        }
+        self
+    }
+
+    pub fn tip(&self) -> &HeaderId {
+        &self.tip
+    }
+
+    pub fn chain_length(&self) -> ChainLength {
+        self.chain_length
+    }
+
+    pub fn date(&self) -> BlockDate {
+        self.date
+    }
+
+    pub fn consensus_nonce(&self) -> &PraosNonce {
+        &self.consensus_nonce
+    }
+
+    /// The leadership of the epoch of the tip
+    pub fn leadership(&self) -> &Leadership {
+        &self.leadership
+    }
+
+    /// Validate a header following the tip, and return the state with this
+    /// header as the new tip.
+    pub fn apply_header(&self, header: &Header) -> Result<Self, HeaderLedgerError> {
+        let parent = header.block_parent_hash();
+        if parent != self.tip {
+            return Err(HeaderLedgerError::WrongParent {
+                tip: self.tip,
+                parent,
+            });
+        }
+
+        let chain_length = self.chain_length.increase();
+        if header.chain_length() != chain_length {
+            return Err(HeaderLedgerError::WrongChainLength {
+                actual: header.chain_length(),
+                expected: chain_length,
+            });
+        }
+
+        let date = header.block_date();
+        if date <= self.date {
+            return Err(HeaderLedgerError::NonMonotonicDate {
+                block_date: date,
+                chain_date: self.date,
+            });
+        }
+
+        let mut next_leadership = self.next_leadership.clone();
+        let leadership = if date.epoch == self.leadership.epoch() {
+            Arc::clone(&self.leadership)
+        } else {
+            match next_leadership.take() {
+                Some(next) if next.epoch() == date.epoch => next,
+                // the epoch of the provided leadership had no block
+                Some(next) if next.epoch() < date.epoch => next
+                    .for_epoch(date.epoch)
+                    .map(Arc::new)
+                    .ok_or(HeaderLedgerError::UnknownLeadership { epoch: date.epoch })?,
+                next => {
+                    next_leadership = next;
+                    self.leadership
+                        .for_epoch(date.epoch)
+                        .map(Arc::new)
+                        .ok_or(HeaderLedgerError::UnknownLeadership { epoch: date.epoch })?
+                }
+            }
+        };
+
+        if let Proof::Bft(proof) = header.proof() {
+            let signature = proof
+                .signature
+                .0
+                .verify_slice(proof.leader_id.as_public_key(), header.as_auth_slice());
+            if signature == Verification::Failed {
+                return Err(HeaderLedgerError::InvalidBftSignature);
+            }
+        }
+        leadership
+            .verify(header)
+            .into_error()
+            .map_err(HeaderLedgerError::InvalidLeader)?;
+
+        let mut consensus_nonce = self.consensus_nonce.clone();
+        if let ConsensusEvalContext::Praos { nonce, .. } = header.get_consensus_eval_context() {
+            consensus_nonce.hash_with(&nonce);
+        }
+
+        Ok(HeaderLedger {
+            tip: header.id(),
+            chain_length,
+            date,
+            consensus_nonce,
+            leadership,
+            next_leadership,
+        })
+    }
+}
+
+impl std::fmt::Debug for HeaderLedger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeaderLedger")
+            .field("tip", &self.tip)
+            .field("chain_length", &self.chain_length)
+            .field("date", &self.date)
+            .field("consensus_nonce", &self.consensus_nonce)
+            .field("epoch", &self.leadership.epoch())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chaintypes::ConsensusType,
+        fragment::Contents,
+        header::{BftSignature, BlockVersion, HeaderBuilderNew},
+        key::{BftLeaderId, Hash},
+        testing::{
+            builders::GenesisPraosBlockBuilder, ConfigBuilder, LedgerBuilder, TestGen, TestLedger,
+        },
+    };
+    use chain_crypto::{Ed25519, SecretKey};
+
+    fn leader_keys() -> Vec<SecretKey<Ed25519>> {
+        (0..2)
+            .map(|_| SecretKey::generate(rand_core::OsRng))
+            .collect()
+    }
+
+    fn bft_ledger(keys: &[SecretKey<Ed25519>]) -> TestLedger {
+        let leaders: Vec<BftLeaderId> = keys.iter().map(|key| key.to_public().into()).collect();
+        LedgerBuilder::from_config(
+            ConfigBuilder::new(0)
+                .with_leaders(&leaders)
+                .with_slots_per_epoch(2),
+        )
+        .build()
+        .unwrap()
+    }
+
+    fn bft_header(
+        parent: &HeaderId,
+        chain_length: u32,
+        date: BlockDate,
+        key: &SecretKey<Ed25519>,
+    ) -> Header {
+        HeaderBuilderNew::new(BlockVersion::Ed25519Signed, &Contents::empty())
+            .set_parent(parent, ChainLength(chain_length))
+            .set_date(date)
+            .into_bft_builder()
+            .unwrap()
+            .sign_using(key)
+            .generalize()
+    }
+
+    fn date(epoch: u32, slot_id: u32) -> BlockDate {
+        BlockDate { epoch, slot_id }
+    }
+
+    #[test]
+    fn bft_headers_across_epochs() {
+        let keys = leader_keys();
+        let ledger = bft_ledger(&keys);
+        let headers = HeaderLedger::new(&ledger.ledger, *ledger.block0_hash());
+
+        let header1 = bft_header(ledger.block0_hash(), 1, date(0, 1), &keys[1]);
+        let headers = headers.apply_header(&header1).unwrap();
+        let header2 = bft_header(&header1.id(), 2, date(1, 0), &keys[0]);
+        let headers = headers.apply_header(&header2).unwrap();
+        let header3 = bft_header(&header2.id(), 3, date(1, 1), &keys[1]);
+        let headers = headers.apply_header(&header3).unwrap();
+
+        assert_eq!(headers.tip(), &header3.id());
+        assert_eq!(headers.chain_length(), ChainLength(3));
+        assert_eq!(headers.date(), date(1, 1));
+        assert_eq!(headers.leadership().epoch(), 1);
+        assert_eq!(
+            headers.consensus_nonce(),
+            &ledger.ledger.settings.consensus_nonce
+        );
+    }
+
+    #[test]
+    fn bft_headers_across_an_empty_epoch() {
+        let keys = leader_keys();
+        let ledger = bft_ledger(&keys);
+        let next = Arc::new(Leadership::new(1, &ledger.ledger));
+        let headers =
+            HeaderLedger::new(&ledger.ledger, *ledger.block0_hash()).with_leadership(next);
+
+        // no block in the epoch 1
+        let header = bft_header(ledger.block0_hash(), 1, date(2, 0), &keys[0]);
+        let headers = headers.apply_header(&header).unwrap();
+
+        assert_eq!(headers.date(), date(2, 0));
+        assert_eq!(headers.leadership().epoch(), 2);
+        assert!(headers.next_leadership.is_none());
+    }
+
+    #[test]
+    fn invalid_bft_headers() {
+        let keys = leader_keys();
+        let ledger = bft_ledger(&keys);
+        let block0 = *ledger.block0_hash();
+        let headers = HeaderLedger::new(&ledger.ledger, block0);
+
+        let wrong_parent = bft_header(&Hash::hash_bytes(b"parent"), 1, date(0, 1), &keys[1]);
+        assert!(matches!(
+            headers.apply_header(&wrong_parent),
+            Err(HeaderLedgerError::WrongParent { .. })
+        ));
+
+        let wrong_chain_length = bft_header(&block0, 2, date(0, 1), &keys[1]);
+        assert!(matches!(
+            headers.apply_header(&wrong_chain_length),
+            Err(HeaderLedgerError::WrongChainLength { .. })
+        ));
+
+        let same_date = bft_header(&block0, 1, date(0, 0), &keys[0]);
+        assert!(matches!(
+            headers.apply_header(&same_date),
+            Err(HeaderLedgerError::NonMonotonicDate { .. })
+        ));
+
+        let wrong_leader = bft_header(&block0, 1, date(0, 1), &keys[0]);
+        assert!(matches!(
+            headers.apply_header(&wrong_leader),
+            Err(HeaderLedgerError::InvalidLeader(_))
+        ));
+
+        let builder = HeaderBuilderNew::new(BlockVersion::Ed25519Signed, &Contents::empty())
+            .set_parent(&block0, ChainLength(1))
+            .set_date(date(0, 1))
+            .into_bft_builder()
+            .unwrap()
+            .set_consensus_data(&keys[1].to_public().into());
+        let forged = keys[0].sign_slice(builder.get_authenticated_data());
+        let forged = builder.set_signature(BftSignature(forged)).generalize();
+        assert!(matches!(
+            headers.apply_header(&forged),
+            Err(HeaderLedgerError::InvalidBftSignature)
+        ));
+    }
+
+    #[test]
+    fn praos_leadership_of_a_new_epoch_is_provided() {
+        let ledger = LedgerBuilder::from_config(
+            ConfigBuilder::new(0)
+                .with_consensus_version(ConsensusType::GenesisPraos)
+                .with_slots_per_epoch(2),
+        )
+        .build()
+        .unwrap();
+        let stake_pool = TestGen::stake_pool();
+        let block = GenesisPraosBlockBuilder::new()
+            .with_date(date(0, 1))
+            .with_chain_length(ledger.ledger.chain_length())
+            .with_parent_id(*ledger.block0_hash())
+            .build(&stake_pool, ledger.ledger.era());
+
+        let headers = HeaderLedger::new(&ledger.ledger, *ledger.block0_hash());
+        assert!(matches!(
+            headers.apply_header(&block.header),
+            Err(HeaderLedgerError::UnknownLeadership { epoch: 1 })
+        ));
+
+        // the pool is not registered, but the header is now checked against
+        // the leadership of its epoch
+        let headers = headers.with_leadership(Arc::new(Leadership::new(1, &ledger.ledger)));
+        assert!(matches!(
+            headers.apply_header(&block.header),
+            Err(HeaderLedgerError::InvalidLeader(_))
+        ));
+    }
+}
@@ -0,0 +1,383 @@
+//! Optional per-account history commitment.
+//!
+//! For every single account, the ids of the fragments touching it are
+//! appended to a Merkle Mountain Range (MMR). The root of an account's
+//! MMR commits to its full history: a light wallet holding a trusted root
+//! can check that a list of fragment ids provided by a full node is complete
+//! (by recomputing the root with [`Mmr::from_leaves`]), or that a single
+//! fragment belongs to the history (with an [`MmrProof`]).
+//!
+//! Maintaining the history is not part of the ledger rules, nodes that want
+//! to serve it feed the applied fragments to [`AccountHistory::apply_fragment`].
+
+use crate::{
+    account::Identifier,
+    fragment::{Fragment, FragmentId},
+    key::{Hash, HashVersion},
+    transaction::{InputEnum, Transaction},
+};
+use chain_addr::Kind;
+use imhamt::Hamt;
+use std::collections::hash_map::DefaultHasher;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+const ROOT_PREFIX: u8 = 2;
+
+fn hash_leaf(id: &FragmentId) -> Hash {
+    let mut buf = Vec::with_capacity(33);
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(id.as_bytes());
+    Hash::hash_bytes(&buf)
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut buf = Vec::with_capacity(65);
+    buf.push(NODE_PREFIX);
+    buf.extend_from_slice(left.as_bytes());
+    buf.extend_from_slice(right.as_bytes());
+    Hash::hash_bytes(&buf)
+}
+
+fn hash_root<'a, I: Iterator<Item = &'a Hash>>(leaf_count: u64, peaks: I) -> Hash {
+    let mut buf = vec![ROOT_PREFIX];
+    buf.extend_from_slice(&leaf_count.to_be_bytes());
+    for peak in peaks {
+        buf.extend_from_slice(peak.as_bytes());
+    }
+    Hash::hash_bytes(&buf)
+}
+
+/// Append only Merkle Mountain Range of fragment ids
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Mmr {
+    /// `levels[h]` contains all the complete nodes of height `h`, from left to right
+    levels: Vec<Vec<Hash>>,
+}
+
+/// Proof that a fragment id is the `leaf_index`-th element of a MMR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmrProof {
+    pub leaf_index: u64,
+    pub leaf_count: u64,
+    /// siblings from the leaf up to (excluding) its peak
+    pub path: Vec<Hash>,
+    /// all the peaks of the MMR, highest first
+    pub peaks: Vec<Hash>,
+}
+
+impl Mmr {
+    pub fn new() -> Self {
+        Mmr::default()
+    }
+
+    /// Build the MMR of the given history, in order
+    pub fn from_leaves<'a, I: IntoIterator<Item = &'a FragmentId>>(leaves: I) -> Self {
+        let mut mmr = Mmr::new();
+        for leaf in leaves {
+            mmr.append(leaf);
+        }
+        mmr
+    }
+
+    pub fn leaf_count(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| leaves.len() as u64)
+    }
+
+    pub fn append(&mut self, id: &FragmentId) {
+        let mut node = hash_leaf(id);
+        let mut height = 0;
+        loop {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            let level = &mut self.levels[height];
+            level.push(node);
+            if level.len() % 2 == 1 {
+                break;
+            }
+            node = hash_node(&level[level.len() - 2], &level[level.len() - 1]);
+            height += 1;
+        }
+    }
+
+    fn peaks(&self) -> Vec<Hash> {
+        self.levels
+            .iter()
+            .rev()
+            .filter(|level| level.len() % 2 == 1)
+            .map(|level| level[level.len() - 1])
+            .collect()
+    }
+
+    /// Commitment to the whole history
+    pub fn root(&self) -> Hash {
+        hash_root(self.leaf_count(), self.peaks().iter())
+    }
+
+    /// Inclusion proof of the `leaf_index`-th fragment id
+    pub fn proof(&self, leaf_index: u64) -> Option<MmrProof> {
+        if leaf_index >= self.leaf_count() {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut index = leaf_index as usize;
+        for level in self.levels.iter() {
+            match level.get(index ^ 1) {
+                Some(sibling) => path.push(*sibling),
+                None => break,
+            }
+            index >>= 1;
+        }
+        Some(MmrProof {
+            leaf_index,
+            leaf_count: self.leaf_count(),
+            path,
+            peaks: self.peaks(),
+        })
+    }
+}
+
+impl MmrProof {
+    /// Check that `id` is part of the history committed by `root`
+    pub fn verify(&self, id: &FragmentId, root: &Hash) -> bool {
+        if self.leaf_index >= self.leaf_count {
+            return false;
+        }
+        // locate the peak covering the leaf, peaks match the set bits of
+        // leaf_count from the highest one
+        let mut start = 0u64;
+        let mut peak = None;
+        for (peak_index, height) in (0..64)
+            .rev()
+            .filter(|h| self.leaf_count & (1 << h) != 0)
+            .enumerate()
+        {
+            let size = 1u64 << height;
+            if self.leaf_index < start + size {
+                peak = Some((peak_index, height as usize));
+                break;
+            }
+            start += size;
+        }
+        let (peak_index, height) = match peak {
+            Some(peak) => peak,
+            None => return false,
+        };
+        if self.path.len() != height || self.peaks.len() != self.leaf_count.count_ones() as usize {
+            return false;
+        }
+
+        let mut node = hash_leaf(id);
+        let mut index = self.leaf_index;
+        for sibling in self.path.iter() {
+            node = if index % 2 == 0 {
+                hash_node(&node, sibling)
+            } else {
+                hash_node(sibling, &node)
+            };
+            index >>= 1;
+        }
+        node == self.peaks[peak_index] && hash_root(self.leaf_count, self.peaks.iter()) == *root
+    }
+}
+
+/// History commitments of all the single accounts
+#[derive(Clone, PartialEq, Eq)]
+pub struct AccountHistory(Hamt<DefaultHasher, Identifier, Mmr>);
+
+impl Default for AccountHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AccountHistory {
+    pub fn new() -> Self {
+        AccountHistory(Hamt::new())
+    }
+
+    /// Append `id` to the history of `account`
+    pub fn append(&self, account: &Identifier, id: &FragmentId) -> Self {
+        let mut mmr = Mmr::new();
+        mmr.append(id);
+        AccountHistory(self.0.insert_or_update_simple(account.clone(), mmr, |mmr| {
+            let mut mmr = mmr.clone();
+            mmr.append(id);
+            Some(mmr)
+        }))
+    }
+
+    /// Record an applied fragment in the history of every account it touches,
+    /// under its id of the version used by the ledger at the date of its
+    /// block, see [`Settings::hash_version`](crate::setting::Settings::hash_version)
+    pub fn apply_fragment(&self, fragment: &Fragment, hash_version: HashVersion) -> Self {
+        let id = fragment.hash_with_version(hash_version);
+        touched_accounts(fragment)
+            .iter()
+            .fold(self.clone(), |history, account| {
+                history.append(account, &id)
+            })
+    }
+
+    pub fn get(&self, account: &Identifier) -> Option<&Mmr> {
+        self.0.lookup(account)
+    }
+
+    pub fn root(&self, account: &Identifier) -> Option<Hash> {
+        self.get(account).map(Mmr::root)
+    }
+
+    pub fn proof(&self, account: &Identifier, leaf_index: u64) -> Option<MmrProof> {
+        self.get(account).and_then(|mmr| mmr.proof(leaf_index))
+    }
+}
+
+fn push_unique(accounts: &mut Vec<Identifier>, account: Identifier) {
+    if !accounts.contains(&account) {
+        accounts.push(account)
+    }
+}
+
+fn transaction_accounts<P>(tx: &Transaction<P>, accounts: &mut Vec<Identifier>) {
+    let tx = tx.as_slice();
+    for input in tx.inputs().iter() {
+        if let InputEnum::AccountInput(account, _) = input.to_enum() {
+            if let Some(account) = account.to_single_account() {
+                push_unique(accounts, account)
+            }
+        }
+    }
+    for output in tx.outputs().iter() {
+        if let Kind::Account(pk) = output.address.kind() {
+            push_unique(accounts, pk.clone().into())
+        }
+    }
+}
+
+/// Single accounts spending from, receiving to or delegated by a fragment
+pub fn touched_accounts(fragment: &Fragment) -> Vec<Identifier> {
+    let mut accounts = Vec::new();
+    match fragment {
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => {}
+        Fragment::Transaction(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::OwnerStakeDelegation(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::StakeDelegation(tx) => {
+            if let Some(account) = tx
+                .as_slice()
+                .payload()
+                .into_payload()
+                .account_id
+                .to_single_account()
+            {
+                push_unique(&mut accounts, account)
+            }
+            transaction_accounts(tx, &mut accounts)
+        }
+        Fragment::DeferredOwnerStakeDelegation(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::DeferredStakeDelegation(tx) => {
+            if let Some(account) = tx
+                .as_slice()
+                .payload()
+                .into_payload()
+                .delegation
+                .account_id
+                .to_single_account()
+            {
+                push_unique(&mut accounts, account)
+            }
+            transaction_accounts(tx, &mut accounts)
+        }
+        Fragment::PoolRegistration(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::PoolRetirement(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::PoolUpdate(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::PoolKeysRotation(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::VotePlan(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::VoteCast(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::VoteCastBatch(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::VoteTally(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::EncryptedVoteTally(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::VotingPowerSnapshot(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::VoteResultCertification(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::CommitteeCandidacy(tx) => transaction_accounts(tx, &mut accounts),
+        Fragment::HandleRegistration(tx) => {
+            if let Some(account) = tx
+                .as_slice()
+                .payload()
+                .into_payload()
+                .account_id
+                .to_single_account()
+            {
+                push_unique(&mut accounts, account)
+            }
+            transaction_accounts(tx, &mut accounts)
+        }
+    }
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+
+    fn fragment_ids(count: u8) -> Vec<FragmentId> {
+        (0..count).map(|i| Hash::hash_bytes(&[i])).collect()
+    }
+
+    #[quickcheck]
+    fn mmr_proofs_verify(count: u8) -> TestResult {
+        let ids = fragment_ids(count);
+        let mmr = Mmr::from_leaves(ids.iter());
+        let root = mmr.root();
+        for (index, id) in ids.iter().enumerate() {
+            let proof = mmr.proof(index as u64).unwrap();
+            if !proof.verify(id, &root) {
+                return TestResult::error(format!("proof of leaf {} does not verify", index));
+            }
+            if proof.verify(&Hash::hash_bytes(b"not in history"), &root) {
+                return TestResult::error(format!("proof of leaf {} accepts any id", index));
+            }
+        }
+        TestResult::from_bool(mmr.proof(count as u64).is_none())
+    }
+
+    #[quickcheck]
+    fn mmr_root_commits_to_complete_history(count: u8) -> TestResult {
+        if count == 0 {
+            return TestResult::discard();
+        }
+        let ids = fragment_ids(count);
+        let root = Mmr::from_leaves(ids.iter()).root();
+        let truncated = Mmr::from_leaves(ids.iter().skip(1)).root();
+        TestResult::from_bool(root != truncated)
+    }
+
+    #[test]
+    fn mmr_proof_rejects_stale_root() {
+        let ids = fragment_ids(5);
+        let mut mmr = Mmr::from_leaves(ids.iter());
+        let proof = mmr.proof(2).unwrap();
+        mmr.append(&Hash::hash_bytes(b"new fragment"));
+        assert!(!proof.verify(&ids[2], &mmr.root()));
+        assert!(mmr.proof(2).unwrap().verify(&ids[2], &mmr.root()));
+    }
+
+    #[quickcheck]
+    fn history_records_touched_accounts(fragment: Fragment) -> TestResult {
+        let history = AccountHistory::new().apply_fragment(&fragment, HashVersion::V1);
+        let id = fragment.hash_with_version(HashVersion::V1);
+        for account in touched_accounts(&fragment) {
+            let root = history.root(&account).unwrap();
+            let proof = history.proof(&account, 0).unwrap();
+            if proof.leaf_count != 1 || !proof.verify(&id, &root) {
+                return TestResult::error(format!("history of {} is not recorded", account));
+            }
+        }
+        TestResult::passed()
+    }
+}
@@ -8,6 +8,7 @@ impl Ledger {
             oldutxos,
             accounts,
             settings: _,
+            settings_provenance: _,
             updates: _,
             multisig,
             delegation: _,
@@ -18,7 +19,13 @@ impl Ledger {
             pots: _,
             leaders_log: _,
             votes: _,
+            sortition: _,
             governance: _,
+            deposits,
+            tokens,
+            handles,
+            signature_cache: _,
+            randomness: _,
         } = self;
 
         vec![
@@ -42,6 +49,13 @@ impl Ledger {
                 multisig.iter_accounts().count(),
                 Value::sum(multisig.iter_accounts().map(|x| x.1.value))
             ),
+            format!(
+                "deposits: #{} Total={:?}",
+                deposits.iter().count(),
+                deposits.total()
+            ),
+            format!("tokens  : #{}", tokens.iter().count()),
+            format!("handles : #{}", handles.iter().count()),
         ]
     }
 
@@ -51,6 +65,7 @@ impl Ledger {
             oldutxos: oldutxos1,
             accounts: accounts1,
             settings: settings1,
+            settings_provenance: settings_provenance1,
             updates: updates1,
             multisig: multisig1,
             delegation: delegation1,
@@ -61,7 +76,13 @@ impl Ledger {
             pots: pots1,
             leaders_log: leaders_log1,
             votes: votes1,
+            sortition: sortition1,
             governance: governance1,
+            deposits: deposits1,
+            tokens: tokens1,
+            handles: handles1,
+            signature_cache: _,
+            randomness: _,
         } = self;
 
         let Ledger {
@@ -69,6 +90,7 @@ impl Ledger {
             oldutxos: oldutxos2,
             accounts: accounts2,
             settings: settings2,
+            settings_provenance: settings_provenance2,
             updates: updates2,
             multisig: multisig2,
             delegation: delegation2,
@@ -79,7 +101,13 @@ impl Ledger {
             pots: pots2,
             leaders_log: leaders_log2,
             votes: votes2,
+            sortition: sortition2,
             governance: governance2,
+            deposits: deposits2,
+            tokens: tokens2,
+            handles: handles2,
+            signature_cache: _,
+            randomness: _,
         } = other;
 
         vec![
@@ -88,6 +116,10 @@ impl Ledger {
             format!("accounts-same: {}", accounts1 == accounts2),
             format!("multisig-same: {}", multisig1 == multisig2),
             format!("settings-same: {}", settings1 == settings2),
+            format!(
+                "settings-provenance-same: {}",
+                settings_provenance1 == settings_provenance2
+            ),
             format!("delegation-same: {}", delegation1 == delegation2),
             format!("static_params-same: {}", static_params1 == static_params2),
             format!("updates-same: {}", updates1 == updates2),
@@ -97,7 +129,11 @@ impl Ledger {
             format!("pots-same: {}", pots1 == pots2),
             format!("leaders-log-same: {}", leaders_log1 == leaders_log2),
             format!("vote-plans: {}", votes1 == votes2),
+            format!("committee-sortition: {}", sortition1 == sortition2),
             format!("governance: {}", governance1 == governance2),
+            format!("deposits: {}", deposits1 == deposits2),
+            format!("tokens: {}", tokens1 == tokens2),
+            format!("handles: {}", handles1 == handles2),
         ]
     }
 }
@@ -1,14 +1,18 @@
+use super::deposits::{Deposit, DepositOwner, Deposits};
 use super::governance::Governance;
+use super::handles::{HandleRecord, Handles};
 use super::ledger::{Error, Ledger, LedgerStaticParameters};
 use super::pots::{self, Pots};
+use super::settings_report::{SettingProvenance, SettingsProvenance};
+use super::token_distribution::TokenDistribution;
 use super::LeadersParticipationRecord;
-use crate::certificate::{VotePlan, VotePlanId};
+use crate::certificate::{Handle, VotePlan, VotePlanId};
 use crate::chaintypes::ChainLength;
-use crate::config::ConfigParam;
+use crate::config::{ConfigParam, Tag};
 use crate::date::BlockDate;
 use crate::key::Hash;
 use crate::stake::PoolsState;
-use crate::vote::{VotePlanLedger, VotePlanManager};
+use crate::vote::{Candidacies, CommitteeId, CommitteeSortition, VotePlanLedger, VotePlanManager};
 use crate::{account, legacy, multisig, setting, update, utxo};
 use chain_addr::Address;
 use chain_time::TimeEra;
@@ -46,7 +50,12 @@ pub enum Entry<'a> {
     ),
     StakePool((&'a crate::certificate::PoolId, &'a crate::stake::PoolState)),
     LeaderParticipation((&'a crate::certificate::PoolId, &'a u32)),
-    VotePlan(&'a VotePlan),
+    VotePlan((&'a VotePlanId, &'a VotePlan)),
+    Deposit((&'a DepositOwner, &'a Deposit)),
+    CommitteeVotePlans((&'a CommitteeId, &'a Vec<VotePlanId>)),
+    Handle((&'a Handle, &'a HandleRecord)),
+    SettingProvenance((&'a Tag, &'a SettingProvenance)),
+    CommitteeCandidacies((&'a VotePlanId, &'a Candidacies)),
 }
 
 #[derive(Clone)]
@@ -77,7 +86,12 @@ pub enum EntryOwned {
     MultisigDeclaration((crate::multisig::Identifier, crate::multisig::Declaration)),
     StakePool((crate::certificate::PoolId, crate::stake::PoolState)),
     LeaderParticipation((crate::certificate::PoolId, u32)),
-    VotePlan(VotePlan),
+    VotePlan((VotePlanId, VotePlan)),
+    Deposit((DepositOwner, Deposit)),
+    CommitteeVotePlans((CommitteeId, Vec<VotePlanId>)),
+    Handle((Handle, HandleRecord)),
+    SettingProvenance((Tag, SettingProvenance)),
+    CommitteeCandidacies((VotePlanId, Candidacies)),
     StopEntry,
 }
 
@@ -121,7 +135,18 @@ impl EntryOwned {
             EntryOwned::LeaderParticipation((pool_id, participation)) => {
                 Some(Entry::LeaderParticipation((pool_id, participation)))
             }
-            EntryOwned::VotePlan(vote_plan) => Some(Entry::VotePlan(vote_plan)),
+            EntryOwned::VotePlan((id, vote_plan)) => Some(Entry::VotePlan((id, vote_plan))),
+            EntryOwned::Deposit((owner, deposit)) => Some(Entry::Deposit((owner, deposit))),
+            EntryOwned::CommitteeVotePlans((committee_id, plans)) => {
+                Some(Entry::CommitteeVotePlans((committee_id, plans)))
+            }
+            EntryOwned::Handle((handle, record)) => Some(Entry::Handle((handle, record))),
+            EntryOwned::SettingProvenance((tag, provenance)) => {
+                Some(Entry::SettingProvenance((tag, provenance)))
+            }
+            EntryOwned::CommitteeCandidacies((id, candidacies)) => {
+                Some(Entry::CommitteeCandidacies((id, candidacies)))
+            }
             EntryOwned::StopEntry => None,
         }
     }
@@ -156,6 +181,11 @@ enum IterState<'a> {
     Pots(pots::Entries<'a>),
     LeaderParticipations(imhamt::HamtIter<'a, crate::certificate::PoolId, u32>),
     VotePlan(imhamt::HamtIter<'a, VotePlanId, VotePlanManager>),
+    Deposits(imhamt::HamtIter<'a, DepositOwner, Deposit>),
+    CommitteeVotePlans(imhamt::HamtIter<'a, CommitteeId, Vec<VotePlanId>>),
+    Handles(imhamt::HamtIter<'a, Handle, HandleRecord>),
+    SettingsProvenance(imhamt::HamtIter<'a, Tag, SettingProvenance>),
+    CommitteeCandidacies(imhamt::HamtIter<'a, VotePlanId, Candidacies>),
     Done,
 }
 
@@ -251,11 +281,48 @@ impl<'a> Iterator for LedgerIterator<'a> {
                 Some(x) => Some(Entry::LeaderParticipation(x)),
             },
             IterState::VotePlan(iter) => match iter.next() {
+                None => {
+                    self.state = IterState::Deposits(self.ledger.deposits.iter());
+                    self.next()
+                }
+                Some((id, plan_manager)) => Some(Entry::VotePlan((id, plan_manager.plan()))),
+            },
+            IterState::Deposits(iter) => match iter.next() {
+                None => {
+                    self.state =
+                        IterState::CommitteeVotePlans(self.ledger.votes.by_committee.iter());
+                    self.next()
+                }
+                Some(x) => Some(Entry::Deposit(x)),
+            },
+            IterState::CommitteeVotePlans(iter) => match iter.next() {
+                None => {
+                    self.state = IterState::Handles(self.ledger.handles.iter());
+                    self.next()
+                }
+                Some(x) => Some(Entry::CommitteeVotePlans(x)),
+            },
+            IterState::Handles(iter) => match iter.next() {
+                None => {
+                    self.state =
+                        IterState::SettingsProvenance(self.ledger.settings_provenance.iter());
+                    self.next()
+                }
+                Some(x) => Some(Entry::Handle(x)),
+            },
+            IterState::SettingsProvenance(iter) => match iter.next() {
+                None => {
+                    self.state = IterState::CommitteeCandidacies(self.ledger.sortition.iter());
+                    self.next()
+                }
+                Some(x) => Some(Entry::SettingProvenance(x)),
+            },
+            IterState::CommitteeCandidacies(iter) => match iter.next() {
                 None => {
                     self.state = IterState::Done;
                     self.next()
                 }
-                Some((_, plan_manager)) => Some(Entry::VotePlan(plan_manager.plan())),
+                Some(x) => Some(Entry::CommitteeCandidacies(x)),
             },
             IterState::Done => None,
         }
@@ -288,6 +355,11 @@ impl<'a> std::iter::FromIterator<Entry<'a>> for Result<Ledger, Error> {
         // TODO: votes don't have their entry
         let mut votes = VotePlanLedger::new();
         let governance = Governance::default();
+        let mut deposits = Deposits::new();
+        let mut tokens = TokenDistribution::new();
+        let mut handles = Handles::new();
+        let mut settings_provenance = SettingsProvenance::new();
+        let mut candidacies = Vec::new();
 
         for entry in iter {
             match entry {
@@ -296,6 +368,9 @@ impl<'a> std::iter::FromIterator<Entry<'a>> for Result<Ledger, Error> {
                     // FIXME: check duplicate
                 }
                 Entry::Utxo(entry) => {
+                    tokens = tokens
+                        .add(&entry.output.tokens)
+                        .map_err(|error| Error::TokenSupplyInvalid { error })?;
                     utxos
                         .entry(entry.fragment_id)
                         .or_default()
@@ -334,16 +409,38 @@ impl<'a> std::iter::FromIterator<Entry<'a>> for Result<Ledger, Error> {
                 Entry::LeaderParticipation((pool_id, pool_participation)) => leaders_log
                     .set_for(pool_id.clone(), *pool_participation)
                     .unwrap(),
-                Entry::VotePlan(vote_plan) => {
+                Entry::VotePlan((id, vote_plan)) => {
                     // TODO: don't use default
                     votes.plans = votes
                         .plans
                         .insert(
-                            vote_plan.to_id(),
-                            VotePlanManager::new(vote_plan.clone(), Default::default()),
+                            id.clone(),
+                            VotePlanManager::with_id(
+                                id.clone(),
+                                vote_plan.clone(),
+                                Default::default(),
+                            ),
                         )
                         .unwrap();
                 }
+                Entry::Deposit((owner, deposit)) => {
+                    deposits = deposits.lock(owner.clone(), deposit.clone())?;
+                }
+                Entry::CommitteeVotePlans((committee_id, plans)) => {
+                    votes.by_committee = votes
+                        .by_committee
+                        .insert(*committee_id, plans.clone())
+                        .unwrap();
+                }
+                Entry::Handle((handle, record)) => {
+                    handles = handles.set(handle.clone(), record.clone());
+                }
+                Entry::SettingProvenance((tag, provenance)) => {
+                    settings_provenance = settings_provenance.set(*tag, *provenance);
+                }
+                Entry::CommitteeCandidacies((id, plan_candidacies)) => {
+                    candidacies.push((id.clone(), plan_candidacies.clone()));
+                }
             }
         }
 
@@ -354,6 +451,7 @@ impl<'a> std::iter::FromIterator<Entry<'a>> for Result<Ledger, Error> {
             oldutxos: oldutxos.into_iter().collect(),
             accounts: accounts.into_iter().collect(),
             settings: setting::Settings::new().apply(&config_params)?,
+            settings_provenance,
             updates,
             multisig: multisig::Ledger::restore(multisig_accounts, multisig_declarations),
             delegation,
@@ -364,7 +462,13 @@ impl<'a> std::iter::FromIterator<Entry<'a>> for Result<Ledger, Error> {
             pots,
             leaders_log,
             votes,
+            sortition: CommitteeSortition::restore(candidacies),
             governance,
+            deposits,
+            tokens,
+            handles,
+            signature_cache: None,
+            randomness: None,
         })
     }
 }
@@ -469,8 +573,36 @@ mod tests {
                 Entry::LeaderParticipation((pool_id, pool_record)) => {
                     println!("LeaderParticipation {} {}", pool_id, pool_record);
                 }
-                Entry::VotePlan(plan) => {
-                    println!("VotePlan {}", plan.to_id());
+                Entry::VotePlan((id, _)) => {
+                    println!("VotePlan {}", id);
+                }
+                Entry::Deposit((owner, deposit)) => {
+                    println!(
+                        "Deposit {:?} {} {}",
+                        owner, deposit.value, deposit.refund_to
+                    );
+                }
+                Entry::CommitteeVotePlans((committee_id, plans)) => {
+                    println!("CommitteeVotePlans {} {}", committee_id, plans.len());
+                }
+                Entry::Handle((handle, record)) => {
+                    println!("Handle {} {} {:?}", handle, record.account, record.expiry);
+                }
+                Entry::SettingProvenance((tag, provenance)) => {
+                    println!(
+                        "SettingProvenance {} {} {:?}",
+                        tag.as_ref(),
+                        provenance.date,
+                        provenance.source
+                    );
+                }
+                Entry::CommitteeCandidacies((id, candidacies)) => {
+                    println!(
+                        "CommitteeCandidacies {} {} {}",
+                        id,
+                        candidacies.vote_start(),
+                        candidacies.candidates().len()
+                    );
                 }
             }
         }
@@ -2,32 +2,52 @@
 //! current state and verify transactions.
 
 use super::check::{self, TxVerifyError};
-use super::governance::{Governance, ParametersGovernanceAction, TreasuryGovernanceAction};
+use super::deposits::{Deposit, DepositError, DepositOwner, Deposits};
+use super::governance::{
+    Governance, ParametersChangesError, ParametersGovernanceAction, PendingEnactment,
+    SkippedTreasuryAction, TreasuryGovernanceAction, VoteResultCertificationError,
+};
+use super::handles::{HandleRecord, Handles};
 use super::leaderlog::LeadersParticipationRecord;
 use super::pots::Pots;
+use super::prevalidation::Prevalidation;
+use super::randomness::RandomnessBeacon;
 use super::reward_info::{EpochRewardsInfo, RewardsInfoParameters};
+use super::settings_report::{SettingSource, SettingsProvenance};
+use super::signature_cache::{SignatureCache, WitnessCheck};
+use super::token_distribution::TokenDistribution;
 use crate::chaineval::HeaderContentEvalContext;
 use crate::chaintypes::{ChainLength, ConsensusType, HeaderId};
 use crate::config::{self, ConfigParam};
 use crate::date::{BlockDate, Epoch};
 use crate::fee::{FeeAlgorithm, LinearFee};
-use crate::fragment::{BlockContentHash, BlockContentSize, Contents, Fragment, FragmentId};
+use crate::fragment::{
+    select_by_fee_density, AdmissionContext, AdmissionError, BlockContentHash, BlockContentSize,
+    BlockContentWeight, ConfigParams, Contents, Fragment, FragmentAdmission, FragmentId,
+    FragmentSelection,
+};
+use crate::key::HashVersion;
 use crate::rewards;
 use crate::setting::ActiveSlotsCoeffError;
 use crate::stake::{
-    PercentStake, PoolError, PoolStakeInformation, PoolsState, StakeControl, StakeDistribution,
+    PercentStake, PoolError, PoolStakeInformation, PoolsState, StakeDistribution,
+    StakeDistributionSnapshot,
 };
+use crate::tokens::{TokenBundle, TokenIdentifier};
 use crate::transaction::*;
 use crate::treasury::Treasury;
 use crate::value::*;
-use crate::vote::{CommitteeId, VotePlanLedger, VotePlanLedgerError, VotePlanStatus};
+use crate::vote::{
+    Candidacies, CommitteeId, CommitteeSortition, CommitteeSortitionError, PayloadType,
+    VotePlanLedger, VotePlanLedgerError, VotePlanStatus, Voters,
+};
 use crate::{account, certificate, legacy, multisig, setting, stake, update, utxo};
 use crate::{
-    certificate::{PoolId, VoteAction, VotePlan},
+    certificate::{Handle, PoolId, VoteAction, VotePlan},
     chaineval::ConsensusEvalContext,
 };
 use chain_addr::{Address, Discrimination, Kind};
-use chain_crypto::Verification;
+use chain_crypto::{Ed25519, PublicKey, Verification};
 use chain_time::{Epoch as TimeEpoch, SlotDuration, TimeEra, TimeFrame, Timeline};
 use std::collections::HashSet;
 use std::convert::TryInto;
@@ -57,6 +77,8 @@ pub struct LedgerParameters {
     pub reward_params: rewards::Parameters,
     /// the block content's max size in bytes
     pub block_content_max_size: BlockContentSize,
+    /// the maximum total weight of the block's fragments, if limited
+    pub block_content_max_weight: Option<BlockContentWeight>,
     /// the epoch stability parameter, the depth, number of blocks, to which
     /// we consider the blockchain to be stable and prevent rollback beyond
     /// that depth.
@@ -65,6 +87,23 @@ pub struct LedgerParameters {
     pub fees_goes_to: setting::FeesGoesTo,
     /// List of committee members
     pub committees: Arc<Box<[CommitteeId]>>,
+    /// Deposit locked by a stake pool registration
+    pub pool_registration_deposit: Value,
+    /// Deposit locked by a vote plan creation
+    pub vote_plan_deposit: Value,
+    /// the maximum number of active vote plans per committee member, if limited
+    pub max_active_vote_plans_per_committee: Option<u32>,
+    /// Deposit locked by a handle registration
+    pub handle_registration_deposit: Value,
+    /// Number of epochs a handle remains valid after its registration, if
+    /// limited
+    pub handle_validity: Option<u32>,
+    /// whether the fragments of a block have to be in their canonical order
+    pub canonical_fragment_order: bool,
+    /// the version of the ids and of the hashes signed by the witnesses, the
+    /// one of the date of the ledger, replaced by the one of the block date
+    /// when applying a fragment
+    pub hash_version: HashVersion,
 }
 
 /// Overall ledger structure.
@@ -80,6 +119,7 @@ pub struct Ledger {
     pub(crate) oldutxos: utxo::Ledger<legacy::OldAddress>,
     pub(crate) accounts: account::Ledger,
     pub(crate) settings: setting::Settings,
+    pub(crate) settings_provenance: SettingsProvenance,
     pub(crate) updates: update::UpdateState,
     pub(crate) multisig: multisig::Ledger,
     pub(crate) delegation: PoolsState,
@@ -90,7 +130,13 @@ pub struct Ledger {
     pub(crate) pots: Pots,
     pub(crate) leaders_log: LeadersParticipationRecord,
     pub(crate) votes: VotePlanLedger,
+    pub(crate) sortition: CommitteeSortition,
     pub(crate) governance: Governance,
+    pub(crate) deposits: Deposits,
+    pub(crate) tokens: TokenDistribution,
+    pub(crate) handles: Handles,
+    pub(crate) signature_cache: Option<SignatureCache>,
+    pub(crate) randomness: Option<RandomnessBeacon>,
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +145,7 @@ pub struct ApplyBlockLedger {
     ledger_params: LedgerParameters,
     chain_length: ChainLength,
     block_date: BlockDate,
+    update_events: Vec<update::UpdateProposalEvent>,
 }
 
 // Dummy implementation of Debug for Ledger
@@ -164,6 +211,10 @@ pub enum Block0Error {
     HasVoteCast,
     #[error("Vote tallying are not valid in the block0")]
     HasVoteTally,
+    #[error("Vote result certifications are not valid in the block0")]
+    HasVoteResultCertification,
+    #[error("Committee candidacies are not valid in the block0")]
+    HasCommitteeCandidacy,
 }
 
 pub type OutputOldAddress = Output<legacy::OldAddress>;
@@ -206,6 +257,8 @@ pub enum Error {
         multisig: multisig::Identifier,
         witness: Witness,
     },
+    #[error("Witness with a non canonical key or signature")]
+    WitnessInvalidEncoding { witness: Witness },
     #[error("Transaction malformed")]
     TransactionMalformed(#[from] TxVerifyError),
     #[error("Error while computing the fees")]
@@ -226,6 +279,15 @@ pub enum Error {
     NotBalanced { inputs: Value, outputs: Value },
     #[error("Empty output")]
     ZeroOutput { output: Output<Address> },
+    #[error("Tokens can only be sent to UTxO addresses")]
+    TokensToNonUtxoAddress { output: Output<Address> },
+    #[error("Tokens not balanced, transaction spends [{inputs}] and outputs [{outputs}]")]
+    TokensNotBalanced {
+        inputs: TokenBundle,
+        outputs: TokenBundle,
+    },
+    #[error("Token supply invalid: {error}")]
+    TokenSupplyInvalid { error: ValueError },
     #[error("Output group invalid")]
     OutputGroupInvalid { output: Output<Address> },
     #[error("Error or Invalid delegation")]
@@ -242,12 +304,24 @@ pub enum Error {
     ExpectingInitialMessage,
     #[error("Invalid certificate's signature")]
     CertificateInvalidSignature,
+    #[error("Certificate with a non canonical key or signature")]
+    CertificateInvalidEncoding,
     #[error("Error or Invalid update")]
     Update(#[from] update::Error),
     #[error("Transaction for OwnerStakeDelegation is invalid. expecting 1 input, 1 witness and 0 output")]
     OwnerStakeDelegationInvalidTransaction,
     #[error("Transaction for VoteCast is invalid. expecting 1 input, 1 witness and 0 output")]
     VoteCastInvalidTransaction,
+    #[error("Transaction for VoteCastBatch is invalid. expecting 1 input, 1 witness and 0 output")]
+    VoteCastBatchInvalidTransaction,
+    #[error("VoteCastBatch does not contain any ballot")]
+    VoteCastBatchEmpty,
+    #[error("VoteCastBatch contains more than one ballot for the proposal {proposal_index}")]
+    VoteCastBatchDuplicatedProposal { proposal_index: u8 },
+    #[error("Transaction for CommitteeCandidacy is invalid. expecting 1 single account input, 1 witness and 0 output")]
+    CommitteeCandidacyInvalidTransaction,
+    #[error("Invalid committee candidacy")]
+    CommitteeSortition(#[from] CommitteeSortitionError),
     #[error("Wrong chain length, expected {expected} but received {actual}")]
     WrongChainLength {
         actual: ChainLength,
@@ -260,6 +334,13 @@ pub enum Error {
     },
     #[error("Wrong block content size, received {actual} bytes but max is {max} bytes")]
     InvalidContentSize { actual: u32, max: u32 },
+    #[error("Wrong block content weight, received {actual} but max is {max}")]
+    InvalidContentWeight {
+        actual: BlockContentWeight,
+        max: BlockContentWeight,
+    },
+    #[error("Fragment {index} of the block is not in the canonical order")]
+    NonCanonicalFragmentOrder { index: usize },
     #[error("Wrong block content hash, received {actual} but expected {expected}")]
     InvalidContentHash {
         actual: BlockContentHash,
@@ -269,12 +350,16 @@ pub enum Error {
     IncompleteLedger,
     #[error("Ledger pot value invalid: {error}")]
     PotValueInvalid { error: ValueError },
+    #[error("Invalid certificate deposit")]
+    Deposit(#[from] DepositError),
     #[error("Pool registration with no owner")]
     PoolRegistrationHasNoOwner,
     #[error("Pool registration with too many owners")]
     PoolRegistrationHasTooManyOwners,
     #[error("Pool registration with too many operators")]
     PoolRegistrationHasTooManyOperators,
+    #[error("Pool registration with a multisig delegators account")]
+    PoolRegistrationMultisigDelegatorsAccount,
     #[error("Pool registration management threshold is zero")]
     PoolRegistrationManagementThresholdZero,
     #[error("Pool registration management threshold above owners")]
@@ -283,6 +368,10 @@ pub enum Error {
     PoolUpdateNotAllowedYet,
     #[error("Stake Delegation payload signature failed")]
     StakeDelegationSignatureFailed,
+    #[error("Handle Registration payload signature failed")]
+    HandleRegistrationSignatureFailed,
+    #[error("Handle {handle} is registered by another account")]
+    HandleAlreadyRegistered { handle: Handle },
     #[error("Pool Retirement payload signature failed")]
     PoolRetirementSignatureFailed,
     #[error("Vote Plan Proof has an invalid signature")]
@@ -291,10 +380,20 @@ pub enum Error {
     VotePlanProofInvalidCommittee,
     #[error("Vote plan contains proposal(s) that does not pass governance criteria")]
     VotePlanInvalidGovernanceParameters,
+    #[error("Vote plan proposes invalid parameters changes")]
+    VotePlanInvalidParametersChanges(#[from] ParametersChangesError),
+    #[error("Vote plan proposes to draw {value} from the treasury, above the budget of {cap}")]
+    VotePlanTreasuryActionAboveBudget { value: Value, cap: Value },
     #[error("Vote Tally Proof failed")]
     VoteTallyProofFailed,
     #[error("Vote tally decryption failed")]
     VoteTallyDecryptionFailed,
+    #[error("Voting power snapshot proof failed")]
+    VotingPowerSnapshotProofFailed,
+    #[error("Vote result certification proof failed")]
+    VoteResultCertificationProofFailed,
+    #[error("Invalid vote result certification")]
+    VoteResultCertificationInvalid(#[from] VoteResultCertificationError),
     #[error("Pool update payload signature failed")]
     PoolUpdateSignatureFailed,
     #[error("Pool update last known registration hash doesn't match")]
@@ -307,6 +406,16 @@ pub enum Error {
     VotePlan(#[from] VotePlanLedgerError),
     #[error("Scripts addresses are not yet supported by the system")]
     ScriptsAddressNotAllowedYet,
+    #[error("Transaction is not valid before {valid_from}, the block is at {block_date}")]
+    TransactionNotYetValid {
+        valid_from: BlockDate,
+        block_date: BlockDate,
+    },
+    #[error("Transaction expired at {valid_until}, the block is at {block_date}")]
+    TransactionExpired {
+        valid_until: BlockDate,
+        block_date: BlockDate,
+    },
 }
 
 impl LedgerParameters {
@@ -327,6 +436,7 @@ impl Ledger {
             oldutxos: utxo::Ledger::new(),
             accounts: account::Ledger::new(),
             settings,
+            settings_provenance: SettingsProvenance::new(),
             updates: update::UpdateState::new(),
             multisig: multisig::Ledger::new(),
             delegation: PoolsState::new(),
@@ -337,7 +447,13 @@ impl Ledger {
             pots,
             leaders_log: LeadersParticipationRecord::new(),
             votes: VotePlanLedger::new(),
+            sortition: CommitteeSortition::new(),
             governance: Governance::default(),
+            deposits: Deposits::new(),
+            tokens: TokenDistribution::new(),
+            handles: Handles::new(),
+            signature_cache: None,
+            randomness: None,
         }
     }
 
@@ -354,7 +470,7 @@ impl Ledger {
         }?;
 
         let mut ledger = {
-            let mut regular_ents = crate::fragment::ConfigParams::new();
+            let mut regular_ents = ConfigParams::new();
             let mut block0_start_time = None;
             let mut slot_duration = None;
             let mut discrimination = None;
@@ -428,7 +544,7 @@ impl Ledger {
         let params = ledger.get_ledger_parameters();
 
         for content in content_iter {
-            let fragment_id = content.hash();
+            let fragment_id = content.hash_with_version(params.hash_version);
             match content {
                 Fragment::Initial(_) => {
                     return Err(Error::Block0(Block0Error::InitialMessageMany));
@@ -456,15 +572,27 @@ impl Ledger {
                     check::valid_block0_cert_transaction(&tx)?;
                     ledger = ledger.apply_stake_delegation(&tx.payload().into_payload())?;
                 }
+                Fragment::DeferredStakeDelegation(tx) => {
+                    let tx = tx.as_slice();
+                    check::valid_block0_cert_transaction(&tx)?;
+                    ledger =
+                        ledger.apply_deferred_stake_delegation(&tx.payload().into_payload(), 0)?;
+                }
+                Fragment::DeferredOwnerStakeDelegation(_) => {
+                    return Err(Error::Block0(Block0Error::HasOwnerStakeDelegation));
+                }
                 Fragment::PoolRegistration(tx) => {
                     let tx = tx.as_slice();
                     check::valid_block0_cert_transaction(&tx)?;
-                    ledger = ledger.apply_pool_registration(&tx.payload().into_payload())?;
+                    ledger = ledger.apply_pool_registration(
+                        &tx.payload().into_payload(),
+                        params.hash_version,
+                    )?;
                 }
                 Fragment::PoolRetirement(_) => {
                     return Err(Error::Block0(Block0Error::HasPoolManagement));
                 }
-                Fragment::PoolUpdate(_) => {
+                Fragment::PoolUpdate(_) | Fragment::PoolKeysRotation(_) => {
                     return Err(Error::Block0(Block0Error::HasPoolManagement));
                 }
                 Fragment::VotePlan(tx) => {
@@ -490,6 +618,43 @@ impl Ledger {
                 Fragment::EncryptedVoteTally(_) => {
                     return Err(Error::Block0(Block0Error::HasVoteTally));
                 }
+                Fragment::VoteCastBatch(_) => {
+                    return Err(Error::Block0(Block0Error::HasVoteCast));
+                }
+                Fragment::VoteResultCertification(_) => {
+                    return Err(Error::Block0(Block0Error::HasVoteResultCertification));
+                }
+                Fragment::CommitteeCandidacy(_) => {
+                    return Err(Error::Block0(Block0Error::HasCommitteeCandidacy));
+                }
+                Fragment::VotingPowerSnapshot(tx) => {
+                    let tx = tx.as_slice();
+                    check::valid_block0_cert_transaction(&tx)?;
+                    let cur_date = ledger.date();
+                    ledger = ledger.apply_voting_power_snapshot(
+                        cur_date,
+                        &tx.payload().into_payload(),
+                        &tx.transaction_binding_auth_data(),
+                        tx.payload_auth().into_payload_auth(),
+                    )?;
+                }
+                Fragment::HandleRegistration(tx) => {
+                    let tx = tx.as_slice();
+                    check::valid_block0_cert_transaction(&tx)?;
+                    let cert = tx.payload().into_payload();
+                    let account = cert
+                        .account_id
+                        .to_single_account()
+                        .ok_or(Error::AccountIdentifierInvalid)?;
+                    ledger.check_handle_registration(&cert.handle, &account, 0)?;
+                    let expiry = params.handle_validity;
+                    ledger = ledger.register_handle(
+                        cert.handle,
+                        account,
+                        expiry,
+                        Some(Value::zero()),
+                    )?;
+                }
             }
         }
 
@@ -501,22 +666,126 @@ impl Ledger {
         self.leaders_log.total() != 0
     }
 
+    /// Apply the parameters governance actions accepted by the vote tallies,
+    /// then the delayed governance actions due at the end of the current
+    /// epoch.
+    ///
+    /// Each parameters update is applied atomically: if the settings
+    /// resulting from the whole update are not valid, the update is dropped
+    /// and none of its changes is applied. The following updates are still
+    /// applied.
     pub fn apply_protocol_changes(&self) -> Result<Self, Error> {
         let mut new = self.clone();
 
-        for action in new.governance.parameters.logs() {
-            match action {
-                ParametersGovernanceAction::NoOp => {}
-                ParametersGovernanceAction::RewardAdd { value } => {
-                    new.pots.rewards_add(*value)?;
+        for action in self.governance.parameters.logs() {
+            new.apply_parameters_action(action)?;
+        }
+        new.governance.parameters.logs_clear();
+
+        for enactment in new.governance.enactments.take_due(self.date.epoch) {
+            match enactment.action {
+                VoteAction::OffChain => {}
+                VoteAction::Treasury { action } => {
+                    new.apply_treasury_action(&enactment.vote_plan, action)?
                 }
+                VoteAction::Parameters { action } => new.apply_parameters_action(&action)?,
             }
         }
 
-        new.governance.parameters.logs_clear();
         Ok(new)
     }
 
+    fn apply_parameters_action(
+        &mut self,
+        action: &ParametersGovernanceAction,
+    ) -> Result<(), Error> {
+        match action {
+            ParametersGovernanceAction::NoOp => {}
+            ParametersGovernanceAction::RewardAdd { value } => {
+                self.pots.rewards_add(*value)?;
+            }
+            ParametersGovernanceAction::UpdateParameters { changes } => {
+                if let Ok(settings) = self.settings_with_changes(changes) {
+                    self.change_settings(settings, self.date, SettingSource::Vote);
+                }
+            }
+            ParametersGovernanceAction::CancelEnactment { vote_plan } => {
+                self.governance.enactments.cancel(vote_plan);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply a treasury action of the given vote plan, unless it draws more
+    /// than the remaining budget of the period, in which case the action is
+    /// dropped and recorded, see [`Ledger::treasury_skipped_actions`]
+    fn apply_treasury_action(
+        &mut self,
+        vote_plan: &certificate::VotePlanId,
+        action: TreasuryGovernanceAction,
+    ) -> Result<(), Error> {
+        match action {
+            TreasuryGovernanceAction::NoOp => {}
+            TreasuryGovernanceAction::TransferToRewards { value } => {
+                let budget = self.settings.treasury_budget;
+                let epoch = self.date.epoch;
+                if let Some(budget) = &budget {
+                    let remaining_budget = self.governance.treasury.remaining_budget(budget, epoch);
+                    if value > remaining_budget {
+                        self.governance.treasury.record_skipped(
+                            budget,
+                            SkippedTreasuryAction {
+                                vote_plan: vote_plan.clone(),
+                                epoch,
+                                value,
+                                remaining_budget,
+                            },
+                        );
+                        return Ok(());
+                    }
+                }
+                let value = self.pots.draw_treasury(value);
+                self.governance
+                    .treasury
+                    .record_spending(budget.as_ref(), epoch, value);
+                self.pots.rewards_add(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The value drawn from the treasury by the treasury actions during the
+    /// current budget period, or the current epoch if there is no budget
+    pub fn treasury_spent(&self) -> Value {
+        self.governance
+            .treasury
+            .spent(self.settings.treasury_budget.as_ref(), self.date.epoch)
+    }
+
+    /// The value the treasury actions can still draw from the treasury
+    /// during the current budget period, none if there is no budget
+    pub fn treasury_remaining_budget(&self) -> Option<Value> {
+        self.settings.treasury_budget.as_ref().map(|budget| {
+            self.governance
+                .treasury
+                .remaining_budget(budget, self.date.epoch)
+        })
+    }
+
+    /// The treasury actions dropped during the current budget period because
+    /// they drew more than the remaining budget
+    pub fn treasury_skipped_actions(&self) -> Vec<SkippedTreasuryAction> {
+        match &self.settings.treasury_budget {
+            None => Vec::new(),
+            Some(budget) => self
+                .governance
+                .treasury
+                .skipped(budget, self.date.epoch)
+                .cloned()
+                .collect(),
+        }
+    }
+
     /// This need to be called before the *first* block of a new epoch
     ///
     /// * Reset the leaders log
@@ -713,8 +982,24 @@ impl Ledger {
             }
         }
 
-        // distribute the rest to delegators
+        // distribute the rest to delegators (or the delegators account)
         let mut leftover_reward = distr.after_tax;
+        if let Some(delegators_account) = &reg.delegators_account {
+            match delegators_account {
+                AccountIdentifier::Single(single_account) => {
+                    self.accounts = self.accounts.add_rewards_to_account(
+                        single_account,
+                        epoch,
+                        leftover_reward,
+                        (),
+                    )?;
+                    reward_info.add_to_account(single_account, leftover_reward);
+                    leftover_reward = Value::zero();
+                }
+                // rejected by the validation of the registration
+                AccountIdentifier::Multi(_) => {}
+            }
+        }
         if leftover_reward > Value::zero() {
             for (account, stake) in distribution.stake.accounts.iter() {
                 let ps = PercentStake::new(*stake, distribution.stake.total);
@@ -765,19 +1050,29 @@ impl Ledger {
         }
 
         // Process Update proposals if needed
-        let (updates, settings) = new_ledger.updates.process_proposals(
-            new_ledger.settings,
+        let (updates, settings, update_events) = new_ledger.updates.process_proposals(
+            new_ledger.settings.clone(),
             new_ledger.date,
             block_date,
         )?;
         new_ledger.updates = updates;
-        new_ledger.settings = settings;
+        new_ledger.change_settings(settings, block_date, SettingSource::UpdateProposal);
+
+        if block_date.epoch > self.date.epoch {
+            new_ledger.update_era(self.date.epoch + 1);
+            new_ledger.record_epoch_randomness(block_date);
+            new_ledger.take_committee_nonces(block_date);
+            new_ledger = new_ledger.refund_untallied_vote_plan_deposits(block_date)?;
+        }
+
+        new_ledger.draw_committees(block_date)?;
 
         Ok(ApplyBlockLedger {
             ledger: new_ledger,
             ledger_params,
             chain_length,
             block_date,
+            update_events,
         })
     }
 
@@ -787,6 +1082,19 @@ impl Ledger {
         ledger_params: LedgerParameters,
         contents: &Contents,
         metadata: &HeaderContentEvalContext,
+    ) -> Result<Self, Error> {
+        self.apply_block_with(ledger_params, contents, metadata, Prevalidation::Sequential)
+    }
+
+    /// Same as [`Ledger::apply_block`], validating the fragments as
+    /// requested. Both modes lead to the same ledger or to the same error,
+    /// see [`Prevalidation`].
+    pub fn apply_block_with(
+        &self,
+        ledger_params: LedgerParameters,
+        contents: &Contents,
+        metadata: &HeaderContentEvalContext,
+        prevalidation: Prevalidation,
     ) -> Result<Self, Error> {
         let (content_hash, content_size) = contents.compute_hash_size();
 
@@ -797,6 +1105,22 @@ impl Ledger {
             });
         }
 
+        if let Some(max) = ledger_params.block_content_max_weight {
+            let weight = contents.compute_weight();
+            if weight > max {
+                return Err(Error::InvalidContentWeight {
+                    actual: weight,
+                    max,
+                });
+            }
+        }
+
+        if ledger_params.canonical_fragment_order {
+            if let Some(index) = contents.non_canonical_index() {
+                return Err(Error::NonCanonicalFragmentOrder { index });
+            }
+        }
+
         if content_hash != metadata.content_hash {
             return Err(Error::InvalidContentHash {
                 actual: content_hash,
@@ -806,14 +1130,58 @@ impl Ledger {
 
         let new_block_ledger =
             self.begin_block(ledger_params, metadata.chain_length, metadata.block_date)?;
-        let new_block_ledger = contents
-            .iter()
-            .try_fold(new_block_ledger, |new_block_ledger, fragment| {
-                new_block_ledger.apply_fragment(fragment)
-            })?;
+        let new_block_ledger = match prevalidation {
+            Prevalidation::Sequential => contents
+                .iter()
+                .try_fold(new_block_ledger, |new_block_ledger, fragment| {
+                    new_block_ledger.apply_fragment(fragment)
+                })?,
+            Prevalidation::Parallel => {
+                let fragments = contents.iter_slice().as_slice();
+                // a transaction has at most 255 inputs
+                let cache = self
+                    .signature_cache
+                    .clone()
+                    .unwrap_or_else(|| SignatureCache::new(fragments.len() * 256));
+                let prevalidated = new_block_ledger.ledger.prevalidate_fragments(
+                    fragments,
+                    metadata.block_date,
+                    &cache,
+                );
+
+                let mut new_block_ledger = new_block_ledger;
+                new_block_ledger.ledger.signature_cache = Some(cache);
+                for (fragment, prevalidated) in fragments.iter().zip(prevalidated) {
+                    prevalidated?;
+                    new_block_ledger = new_block_ledger.apply_fragment(fragment)?;
+                }
+                new_block_ledger.ledger.signature_cache = self.signature_cache.clone();
+                new_block_ledger
+            }
+        };
         Ok(new_block_ledger.finish(&metadata.consensus_eval_context))
     }
 
+    /// Admit a fragment in the mempool: the fragment is checked by the
+    /// admission policy, then applied like with `apply_fragment`, and the
+    /// policy is told about it if it is valid
+    pub fn admit_fragment<A: FragmentAdmission>(
+        &self,
+        admission: &mut A,
+        ledger_params: &LedgerParameters,
+        fragment: &Fragment,
+        block_date: BlockDate,
+    ) -> Result<Self, AdmissionError> {
+        let context = AdmissionContext {
+            ledger_params,
+            block_date,
+        };
+        admission.check(fragment, &context)?;
+        let ledger = self.apply_fragment(ledger_params, fragment, block_date)?;
+        admission.admitted(fragment, &context);
+        Ok(ledger)
+    }
+
     /// Try to apply a message to the State, and return the new State if successful
     ///
     /// this does not _advance_ the state to the new _state_ but apply a simple fragment
@@ -827,7 +1195,14 @@ impl Ledger {
     ) -> Result<Self, Error> {
         let mut new_ledger = self.clone();
 
-        let fragment_id = content.hash();
+        let ledger_params = &LedgerParameters {
+            hash_version: self.settings.hash_version(block_date.epoch),
+            ..ledger_params.clone()
+        };
+        let fragment_id = content.hash_with_version(ledger_params.hash_version);
+        check::valid_fragment_date(content, block_date)?;
+        check::valid_fragment_encoding(content, self.settings.validation_policy)?;
+        let binding_context = self.settings.binding_signature_context(block_date.epoch);
         match content {
             Fragment::Initial(_) => return Err(Error::Block0OnlyFragmentReceived),
             Fragment::OldUtxoDeclaration(_) => return Err(Error::Block0OnlyFragmentReceived),
@@ -840,27 +1215,32 @@ impl Ledger {
             Fragment::OwnerStakeDelegation(tx) => {
                 let tx = tx.as_slice();
                 let (new_ledger_, _fee) =
-                    new_ledger.apply_owner_stake_delegation(&tx, &ledger_params)?;
+                    new_ledger.apply_owner_stake_delegation(&fragment_id, &tx, &ledger_params)?;
+                new_ledger = new_ledger_;
+            }
+            Fragment::DeferredOwnerStakeDelegation(tx) => {
+                let tx = tx.as_slice();
+                let (new_ledger_, _fee) = new_ledger.apply_deferred_owner_stake_delegation(
+                    &fragment_id,
+                    &tx,
+                    &ledger_params,
+                    block_date.epoch,
+                )?;
                 new_ledger = new_ledger_;
             }
             Fragment::StakeDelegation(tx) => {
                 let tx = tx.as_slice();
                 let payload = tx.payload().into_payload();
                 let payload_auth = tx.payload_auth().into_payload_auth();
-                let verified = match payload_auth {
-                    AccountBindingSignature::Single(signature) => {
-                        let account_pk = payload
-                            .account_id
-                            .to_single_account()
-                            .ok_or(Error::AccountIdentifierInvalid)?;
-                        signature
-                            .verify_slice(&account_pk.into(), &tx.transaction_binding_auth_data())
-                    }
-                    AccountBindingSignature::Multi(_) => {
-                        // TODO
-                        Verification::Failed
-                    }
-                };
+                let account_pk = payload
+                    .account_id
+                    .to_single_account()
+                    .ok_or(Error::AccountIdentifierInvalid)?;
+                let verified = payload_auth.verify_slice(
+                    &account_pk.into(),
+                    &tx.transaction_binding_auth_data(),
+                    binding_context,
+                );
 
                 if verified == Verification::Failed {
                     return Err(Error::StakeDelegationSignatureFailed);
@@ -870,14 +1250,51 @@ impl Ledger {
                     new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
                 new_ledger = new_ledger_.apply_stake_delegation(&payload)?;
             }
-            Fragment::PoolRegistration(tx) => {
+            Fragment::DeferredStakeDelegation(tx) => {
                 let tx = tx.as_slice();
+                let payload = tx.payload().into_payload();
+                let payload_auth = tx.payload_auth().into_payload_auth();
+                let account_pk = payload
+                    .delegation
+                    .account_id
+                    .to_single_account()
+                    .ok_or(Error::AccountIdentifierInvalid)?;
+                let verified = payload_auth.verify_slice(
+                    &account_pk.into(),
+                    &tx.transaction_binding_auth_data(),
+                    binding_context,
+                );
+
+                if verified == Verification::Failed {
+                    return Err(Error::StakeDelegationSignatureFailed);
+                }
+
                 let (new_ledger_, _fee) =
                     new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
+                new_ledger =
+                    new_ledger_.apply_deferred_stake_delegation(&payload, block_date.epoch)?;
+            }
+            Fragment::PoolRegistration(tx) => {
+                let tx = tx.as_slice();
+                let deposit = ledger_params.pool_registration_deposit;
+                let (new_ledger_, _fee) = new_ledger.apply_transaction_with_deposit(
+                    &fragment_id,
+                    &tx,
+                    &ledger_params,
+                    deposit,
+                )?;
+                let cert = tx.payload().into_payload();
                 new_ledger = new_ledger_.apply_pool_registration_signcheck(
-                    &tx.payload().into_payload(),
+                    &cert,
                     &tx.transaction_binding_auth_data(),
                     tx.payload_auth().into_payload_auth(),
+                    binding_context,
+                    ledger_params.hash_version,
+                )?;
+                new_ledger = new_ledger.lock_deposit(
+                    DepositOwner::StakePool(cert.to_id_with_version(ledger_params.hash_version)),
+                    deposit,
+                    || pool_deposit_refund_account(&cert),
                 )?;
             }
             Fragment::PoolRetirement(tx) => {
@@ -885,11 +1302,14 @@ impl Ledger {
 
                 let (new_ledger_, _fee) =
                     new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
+                let cert = tx.payload().into_payload();
                 new_ledger = new_ledger_.apply_pool_retirement(
-                    &tx.payload().into_payload(),
+                    &cert,
                     &tx.transaction_binding_auth_data(),
                     tx.payload_auth().into_payload_auth(),
+                    binding_context,
                 )?;
+                new_ledger = new_ledger.refund_deposit(&DepositOwner::StakePool(cert.pool_id))?;
             }
             Fragment::PoolUpdate(tx) => {
                 let tx = tx.as_slice();
@@ -900,6 +1320,21 @@ impl Ledger {
                     &tx.payload().into_payload(),
                     &tx.transaction_binding_auth_data(),
                     tx.payload_auth().into_payload_auth(),
+                    binding_context,
+                    ledger_params.hash_version,
+                )?;
+            }
+            Fragment::PoolKeysRotation(tx) => {
+                let tx = tx.as_slice();
+
+                let (new_ledger_, _fee) =
+                    new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
+                new_ledger = new_ledger_.apply_pool_keys_rotation(
+                    &tx.payload().into_payload(),
+                    &tx.transaction_binding_auth_data(),
+                    tx.payload_auth().into_payload_auth(),
+                    binding_context,
+                    ledger_params.hash_version,
                 )?;
             }
             Fragment::UpdateProposal(update_proposal) => {
@@ -916,20 +1351,56 @@ impl Ledger {
                 new_ledger = new_ledger.apply_update_vote(&vote)?;
             }
             Fragment::VotePlan(tx) => {
+                let tx = tx.as_slice();
+                let deposit = ledger_params.vote_plan_deposit;
+                let (new_ledger_, _fee) = new_ledger.apply_transaction_with_deposit(
+                    &fragment_id,
+                    &tx,
+                    &ledger_params,
+                    deposit,
+                )?;
+                let vote_plan = tx.payload().into_payload();
+                let vote_plan_id = vote_plan.to_id_with_version(ledger_params.hash_version);
+                let sig = tx.payload_auth().into_payload_auth();
+                let refund_to = sig.id.public_key().into();
+                new_ledger =
+                    new_ledger_.apply_vote_plan(&tx, block_date, vote_plan, &ledger_params, sig)?;
+                new_ledger = new_ledger.lock_deposit(
+                    DepositOwner::VotePlan(vote_plan_id),
+                    deposit,
+                    || refund_to,
+                )?;
+            }
+            Fragment::VoteCast(tx) => {
                 let tx = tx.as_slice();
                 let (new_ledger_, _fee) =
-                    new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
-                new_ledger = new_ledger_.apply_vote_plan(
+                    new_ledger.apply_vote_cast(&fragment_id, &tx, &ledger_params)?;
+                new_ledger = new_ledger_;
+            }
+            Fragment::CommitteeCandidacy(tx) => {
+                let tx = tx.as_slice();
+                let (new_ledger_, _fee) = new_ledger.apply_committee_candidacy(
+                    &fragment_id,
                     &tx,
                     block_date,
-                    tx.payload().into_payload(),
                     &ledger_params,
-                    tx.payload_auth().into_payload_auth(),
                 )?;
+                new_ledger = new_ledger_;
             }
-            Fragment::VoteCast(tx) => {
+            Fragment::VoteCastBatch(tx) => {
+                let tx = tx.as_slice();
+                let (new_ledger_, _fee) =
+                    new_ledger.apply_vote_cast_batch(&fragment_id, &tx, &ledger_params)?;
+                new_ledger = new_ledger_;
+            }
+            Fragment::HandleRegistration(tx) => {
                 let tx = tx.as_slice();
-                let (new_ledger_, _fee) = new_ledger.apply_vote_cast(&tx, &ledger_params)?;
+                let (new_ledger_, _fee) = new_ledger.apply_handle_registration(
+                    &fragment_id,
+                    &tx,
+                    &ledger_params,
+                    block_date.epoch,
+                )?;
                 new_ledger = new_ledger_;
             }
             Fragment::VoteTally(tx) => {
@@ -938,11 +1409,15 @@ impl Ledger {
                 let (new_ledger_, _fee) =
                     new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
 
+                let tally = tx.payload().into_payload();
                 new_ledger = new_ledger_.apply_vote_tally(
-                    &tx.payload().into_payload(),
+                    &tally,
                     &tx.transaction_binding_auth_data(),
                     tx.payload_auth().into_payload_auth(),
+                    binding_context,
                 )?;
+                new_ledger =
+                    new_ledger.refund_deposit(&DepositOwner::VotePlan(tally.id().clone()))?;
             }
             Fragment::EncryptedVoteTally(tx) => {
                 let tx = tx.as_slice();
@@ -954,18 +1429,62 @@ impl Ledger {
                     &tx.payload().into_payload(),
                     &tx.transaction_binding_auth_data(),
                     tx.payload_auth().into_payload_auth(),
+                    binding_context,
+                )?;
+            }
+            Fragment::VotingPowerSnapshot(tx) => {
+                let tx = tx.as_slice();
+
+                let (new_ledger_, _fee) =
+                    new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
+
+                new_ledger = new_ledger_.apply_voting_power_snapshot(
+                    block_date,
+                    &tx.payload().into_payload(),
+                    &tx.transaction_binding_auth_data(),
+                    tx.payload_auth().into_payload_auth(),
+                )?;
+            }
+            Fragment::VoteResultCertification(tx) => {
+                let tx = tx.as_slice();
+
+                let (new_ledger_, _fee) =
+                    new_ledger.apply_transaction(&fragment_id, &tx, &ledger_params)?;
+
+                new_ledger = new_ledger_.apply_vote_result_certification(
+                    &tx.payload().into_payload(),
+                    &tx.transaction_binding_auth_data(),
+                    tx.payload_auth().into_payload_auth(),
+                    binding_context,
                 )?;
             }
         }
 
-        Ok(new_ledger)
+        new_ledger.record_activity(content, block_date)
     }
 
     pub fn apply_transaction<'a, Extra>(
+        self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, Extra>,
+        dyn_params: &LedgerParameters,
+    ) -> Result<(Self, Value), Error>
+    where
+        Extra: Payload,
+        LinearFee: FeeAlgorithm,
+    {
+        self.apply_transaction_with_deposit(fragment_id, tx, dyn_params, Value::zero())
+    }
+
+    /// Apply a transaction whose inputs pay for a deposit on top of the
+    /// outputs and the fee. The deposit is taken out of the transaction but
+    /// it is up to the caller to lock it with the certificate.
+    fn apply_transaction_with_deposit<'a, Extra>(
         mut self,
         fragment_id: &FragmentId,
         tx: &TransactionSlice<'a, Extra>,
         dyn_params: &LedgerParameters,
+        deposit: Value,
     ) -> Result<(Self, Value), Error>
     where
         Extra: Payload,
@@ -973,15 +1492,44 @@ impl Ledger {
     {
         check::valid_transaction_ios_number(tx)?;
         let fee = calculate_fee(tx, dyn_params);
-        tx.verify_strictly_balanced(fee)?;
-        self = self.apply_tx_inputs(tx)?;
+        tx.verify_strictly_balanced((fee + deposit)?)?;
+        let (ledger, spent_tokens) =
+            self.apply_tx_inputs(fragment_id, tx, dyn_params.hash_version)?;
+        self = ledger;
+        let output_tokens = tx
+            .outputs()
+            .total_tokens()
+            .map_err(|error| Error::TokenSupplyInvalid { error })?;
+        if spent_tokens != output_tokens {
+            return Err(Error::TokensNotBalanced {
+                inputs: spent_tokens,
+                outputs: output_tokens,
+            });
+        }
         self = self.apply_tx_outputs(*fragment_id, tx.outputs())?;
         self = self.apply_tx_fee(fee)?;
         Ok((self, fee))
     }
 
+    /// The settings resulting from applying all the changes at once,
+    /// checked for coherence
+    fn settings_with_changes(
+        &self,
+        changes: &ConfigParams,
+    ) -> Result<setting::Settings, update::Error> {
+        let settings = self.settings.apply(changes)?;
+        settings.check_coherence()?;
+        if settings.consensus_version == ConsensusType::GenesisPraos
+            && self.delegation.stake_pools.is_empty()
+        {
+            return Err(update::Error::NoStakePools);
+        }
+        Ok(settings)
+    }
+
     pub fn apply_update(mut self, update: &update::UpdateProposal) -> Result<Self, Error> {
-        self.settings = self.settings.apply(&update.changes)?;
+        let settings = self.settings.apply(&update.changes)?;
+        self.change_settings(settings, self.date, SettingSource::UpdateProposal);
         Ok(self)
     }
 
@@ -991,6 +1539,20 @@ impl Ledger {
         proposal: &update::SignedUpdateProposal,
         cur_date: BlockDate,
     ) -> Result<Self, Error> {
+        // genesis praos elects the slot leaders among the stake pools
+        let to_genesis_praos = proposal
+            .proposal
+            .proposal
+            .changes
+            .iter()
+            .any(|param| *param == ConfigParam::ConsensusVersion(ConsensusType::GenesisPraos));
+        if to_genesis_praos
+            && self.settings.consensus_version != ConsensusType::GenesisPraos
+            && self.delegation.stake_pools.is_empty()
+        {
+            return Err(update::Error::NoStakePools.into());
+        }
+
         self.updates =
             self.updates
                 .apply_proposal(proposal_id, proposal, &self.settings, cur_date)?;
@@ -1010,7 +1572,8 @@ impl Ledger {
         dyn_params: &LedgerParameters,
         sig: certificate::VotePlanProof,
     ) -> Result<Self, Error> {
-        if sig.verify(&tx.transaction_binding_auth_data()) == Verification::Failed {
+        let context = self.settings.binding_signature_context(cur_date.epoch);
+        if sig.verify(&tx.transaction_binding_auth_data(), context) == Verification::Failed {
             return Err(Error::VotePlanProofInvalidSignature);
         }
 
@@ -1018,6 +1581,25 @@ impl Ledger {
             return Err(Error::VotePlanInvalidGovernanceParameters);
         }
 
+        for proposal in vote_plan.proposals().iter() {
+            match proposal.action() {
+                VoteAction::Parameters { action } => action.check()?,
+                VoteAction::Treasury {
+                    action: TreasuryGovernanceAction::TransferToRewards { value },
+                } => {
+                    if let Some(budget) = &self.settings.treasury_budget {
+                        if *value > budget.cap {
+                            return Err(Error::VotePlanTreasuryActionAboveBudget {
+                                value: *value,
+                                cap: budget.cap,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
         let mut committee = HashSet::new();
         if !vote_plan.is_governance() {
             for input in tx.inputs().iter() {
@@ -1037,24 +1619,201 @@ impl Ledger {
             return Err(Error::VotePlanProofInvalidCommittee);
         }
 
-        self.votes = self.votes.add_vote_plan(cur_date, vote_plan, committee)?;
+        self.votes = self.votes.add_vote_plan(
+            cur_date,
+            vote_plan,
+            dyn_params.hash_version,
+            committee,
+            sig.id,
+            dyn_params.max_active_vote_plans_per_committee,
+        )?;
         Ok(self)
     }
 
     pub fn apply_vote_cast<'a>(
-        mut self,
+        self,
+        fragment_id: &FragmentId,
         tx: &TransactionSlice<'a, certificate::VoteCast>,
         dyn_params: &LedgerParameters,
     ) -> Result<(Self, Value), Error> {
-        let sign_data_hash = tx.transaction_sign_data_hash();
+        check::valid_vote_cast(tx)?;
 
-        let (account_id, value, witness) = {
-            check::valid_vote_cast(tx)?;
+        let (mut new_ledger, account_id, fee) = self.apply_voter_input(
+            fragment_id,
+            tx,
+            dyn_params,
+            Error::VoteCastInvalidTransaction,
+        )?;
+
+        let vote = tx.payload().into_payload();
+        new_ledger.votes = new_ledger
+            .votes
+            .apply_vote(new_ledger.date(), account_id, vote)?;
+
+        Ok((new_ledger, fee))
+    }
+
+    /// register the account of the input as a candidate to the committee of
+    /// the vote plan, or record its ticket, see [`CommitteeSortition`]
+    pub fn apply_committee_candidacy<'a>(
+        self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, certificate::CommitteeCandidacy>,
+        block_date: BlockDate,
+        dyn_params: &LedgerParameters,
+    ) -> Result<(Self, Value), Error> {
+        check::valid_committee_candidacy(tx)?;
+        // the committee members are single accounts
+        if !matches!(tx.witnesses().iter().next(), Some(Witness::Account(_))) {
+            return Err(Error::CommitteeCandidacyInvalidTransaction);
+        }
+        if self.settings.committee_sortition_size.is_none() {
+            return Err(CommitteeSortitionError::Disabled.into());
+        }
+        if self.settings.consensus_version == ConsensusType::Bft {
+            return Err(CommitteeSortitionError::BftConsensus.into());
+        }
+
+        let candidacy = tx.payload().into_payload();
+        let id = candidacy.vote_plan();
+        let plan = self
+            .votes
+            .plans
+            .lookup(id)
+            .map(|plan| plan.plan())
+            .ok_or_else(|| CommitteeSortitionError::UnknownVotePlan { id: id.clone() })?;
+        let vote_start = plan.vote_start();
+        if let certificate::CandidacyStep::Apply { member_key, .. } = candidacy.step() {
+            // the votes of a private vote plan are encrypted to the keys of
+            // the drawn members too
+            let private = plan.payload_type() == PayloadType::Private;
+            if member_key.is_some() != private {
+                return Err(CommitteeSortitionError::InvalidMemberKey { id: id.clone() }.into());
+            }
+        }
+
+        let (mut new_ledger, account_id, fee) = self.apply_voter_input(
+            fragment_id,
+            tx,
+            dyn_params,
+            Error::CommitteeCandidacyInvalidTransaction,
+        )?;
 
+        let account = account_id
+            .to_single_account()
+            .ok_or(Error::AccountIdentifierInvalid)?;
+        let candidate = CommitteeId::from(PublicKey::<Ed25519>::from(account));
+        new_ledger.sortition = match candidacy.step() {
+            certificate::CandidacyStep::Apply {
+                vrf_key,
+                member_key,
+            } => new_ledger.sortition.add_candidate(
+                block_date,
+                id,
+                vote_start,
+                candidate,
+                vrf_key.clone(),
+                member_key.clone(),
+            )?,
+            certificate::CandidacyStep::Ticket { proof } => new_ledger
+                .sortition
+                .reveal_ticket(block_date, id, candidate, proof)?,
+        };
+
+        Ok((new_ledger, fee))
+    }
+
+    /// take the nonce of the draw and the stake of the candidates to the
+    /// committees of the vote plans whose vote starts by the next epoch, to
+    /// be called on the first block of the epoch. The stake of a candidate
+    /// is the value of its account.
+    fn take_committee_nonces(&mut self, block_date: BlockDate) {
+        let accounts = &self.accounts;
+        self.sortition =
+            self.sortition
+                .take_nonces(block_date, &self.settings.consensus_nonce, |candidate| {
+                    let account = account::Identifier::from(PublicKey::<Ed25519>::from(*candidate));
+                    accounts
+                        .get_state(&account)
+                        .map(|state| state.value())
+                        .unwrap_or_else(|_| Value::zero())
+                });
+    }
+
+    /// draw the committees of the vote plans whose vote starts by the date of
+    /// the block, and add the drawn members to the committees of the vote
+    /// plans
+    fn draw_committees(&mut self, block_date: BlockDate) -> Result<(), Error> {
+        let size = match self.settings.committee_sortition_size {
+            Some(size) => size,
+            None => return Ok(()),
+        };
+        let (sortition, drawn) = self.sortition.draw(block_date, size);
+        self.sortition = sortition;
+        for (id, members) in drawn {
+            let members = members
+                .into_iter()
+                .map(|member| (*member.id(), member.member_key().cloned()))
+                .collect();
+            self.votes = self.votes.add_committee_members(&id, members)?;
+        }
+        Ok(())
+    }
+
+    /// The candidacies to the committee of the given vote plan, until the
+    /// committee is drawn
+    pub fn committee_candidacies(
+        &self,
+        vote_plan_id: &certificate::VotePlanId,
+    ) -> Option<&Candidacies> {
+        self.sortition.candidacies(vote_plan_id)
+    }
+
+    /// apply all the ballots of the batch, the batch is rejected as a whole
+    /// if any of the ballots is not valid
+    pub fn apply_vote_cast_batch<'a>(
+        self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, certificate::VoteCastBatch>,
+        dyn_params: &LedgerParameters,
+    ) -> Result<(Self, Value), Error> {
+        check::valid_vote_cast_batch(tx)?;
+        let batch = tx.payload().into_payload();
+        check::valid_vote_cast_batch_certificate(&batch)?;
+
+        let (mut new_ledger, account_id, fee) = self.apply_voter_input(
+            fragment_id,
+            tx,
+            dyn_params,
+            Error::VoteCastBatchInvalidTransaction,
+        )?;
+
+        let date = new_ledger.date();
+        for vote in batch.vote_casts() {
+            new_ledger.votes = new_ledger
+                .votes
+                .apply_vote(date, account_id.clone(), vote)?;
+        }
+
+        Ok((new_ledger, fee))
+    }
+
+    /// verify and spend the single account input paying the fees of a vote
+    /// transaction, returning the identifier of the voter
+    fn apply_voter_input<'a, P: Payload>(
+        mut self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, P>,
+        dyn_params: &LedgerParameters,
+        invalid_transaction: Error,
+    ) -> Result<(Self, UnspecifiedAccountIdentifier, Value), Error> {
+        let sign_data_hash = tx.transaction_sign_data_hash_with_version(dyn_params.hash_version);
+
+        let (account_id, value, witness) = {
             let input = tx.inputs().iter().next().unwrap();
             match input.to_enum() {
                 InputEnum::UtxoInput(_) => {
-                    return Err(Error::VoteCastInvalidTransaction);
+                    return Err(invalid_transaction);
                 }
                 InputEnum::AccountInput(account_id, value) => {
                     let witness = tx.witnesses().iter().next().unwrap();
@@ -1080,6 +1839,7 @@ impl Ledger {
                     &account_id,
                     witness,
                     value,
+                    WitnessCheck::new(self.signature_cache.as_ref(), fragment_id, 0),
                 )?;
             }
             MatchingIdentifierWitness::Multi(account_id, witness) => {
@@ -1090,15 +1850,13 @@ impl Ledger {
                     &account_id,
                     witness,
                     value,
+                    WitnessCheck::new(self.signature_cache.as_ref(), fragment_id, 0),
                 )?;
             }
         };
         self = self.apply_tx_fee(fee)?;
 
-        let vote = tx.payload().into_payload();
-        self.votes = self.votes.apply_vote(self.date(), account_id, vote)?;
-
-        Ok((self, fee))
+        Ok((self, account_id, fee))
     }
 
     pub fn active_vote_plans(&self) -> Vec<VotePlanStatus> {
@@ -1109,17 +1867,36 @@ impl Ledger {
             .collect()
     }
 
+    /// The vote plans submitted by the given committee member, in order of
+    /// submission
+    pub fn vote_plans_by_committee(&self, committee_id: &CommitteeId) -> Vec<VotePlanStatus> {
+        self.votes
+            .vote_plans_by_committee(committee_id)
+            .map(|plan| plan.statuses())
+            .collect()
+    }
+
+    /// The accounts which voted in the given vote plan, none if the vote
+    /// plan is not in the ledger
+    pub fn vote_plan_voters(&self, vote_plan_id: &certificate::VotePlanId) -> Option<Arc<Voters>> {
+        self.votes
+            .plans
+            .lookup(vote_plan_id)
+            .map(|plan| plan.voters())
+    }
+
     pub fn apply_vote_tally<'a>(
         mut self,
         tally: &certificate::VoteTally,
         bad: &TransactionBindingAuthData<'a>,
         sig: certificate::TallyProof,
+        context: BindingSignatureContext,
     ) -> Result<Self, Error> {
-        if sig.verify(tally.tally_type(), bad) == Verification::Failed {
+        if sig.verify(tally.tally_type(), bad, context) == Verification::Failed {
             return Err(Error::VoteTallyProofFailed);
         }
 
-        let stake = StakeControl::new_with(&self.accounts, &self.utxos);
+        let stake = self.voting_stake();
 
         let mut actions = Vec::new();
 
@@ -1132,23 +1909,124 @@ impl Ledger {
             |action: &VoteAction| actions.push(action.clone()),
         )?;
 
+        if self.settings.vote_result_certification {
+            for action in actions {
+                self.governance
+                    .certifications
+                    .hold(tally.id().clone(), action);
+            }
+        } else {
+            self.apply_vote_actions(tally.id(), actions)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Apply the actions accepted by the tally of a vote plan, or register
+    /// them for later if the vote plan has an enactment delay
+    fn apply_vote_actions(
+        &mut self,
+        vote_plan: &certificate::VotePlanId,
+        actions: Vec<VoteAction>,
+    ) -> Result<(), Error> {
+        let enactment_delay = self
+            .votes
+            .plans
+            .lookup(vote_plan)
+            .map_or(0, |plan| plan.plan().enactment_delay());
+
         for action in actions {
             match action {
                 VoteAction::OffChain => {}
-                VoteAction::Treasury {
-                    action: TreasuryGovernanceAction::NoOp,
-                } => {}
-                VoteAction::Treasury {
-                    action: TreasuryGovernanceAction::TransferToRewards { value },
-                } => {
-                    let value = self.pots.draw_treasury(value);
-                    self.pots.rewards_add(value)?;
+                action if enactment_delay > 0 => {
+                    self.governance.enactments.register(PendingEnactment {
+                        vote_plan: vote_plan.clone(),
+                        epoch: self.date.epoch.saturating_add(enactment_delay),
+                        action,
+                    });
                 }
+                VoteAction::Treasury { action } => self.apply_treasury_action(vote_plan, action)?,
                 VoteAction::Parameters { action } => {
                     self.governance.parameters.logs_register(action);
                 }
             }
         }
+        Ok(())
+    }
+
+    /// Record the results of a tallied vote plan certified by a majority of
+    /// the committee, then apply the actions accepted by its tally which
+    /// were waiting for the certification. The enactment delay of the vote
+    /// plan, if any, counts from the certification.
+    pub fn apply_vote_result_certification<'a>(
+        mut self,
+        certification: &certificate::VoteResultCertification,
+        bad: &TransactionBindingAuthData<'a>,
+        sig: certificate::VoteResultCertificationProof,
+        context: BindingSignatureContext,
+    ) -> Result<Self, Error> {
+        if sig.verify(bad, context) == Verification::Failed {
+            return Err(Error::VoteResultCertificationProofFailed);
+        }
+
+        let vote_plan = certification.vote_plan();
+        if self.governance.certifications.is_certified(vote_plan) {
+            return Err(VoteResultCertificationError::AlreadyCertified(vote_plan.clone()).into());
+        }
+        let plan_manager = self
+            .votes
+            .plans
+            .lookup(vote_plan)
+            .ok_or_else(|| VoteResultCertificationError::UnknownVotePlan(vote_plan.clone()))?;
+
+        // the results are certified by the committee of the vote plan, which
+        // tallied them
+        let committee = plan_manager.committee_set();
+        let mut signers = 0;
+        for signer in sig.signers() {
+            if !committee.contains(signer) {
+                return Err(VoteResultCertificationError::SignerNotInCommittee(*signer).into());
+            }
+            signers += 1;
+        }
+        if signers * 2 <= committee.len() {
+            return Err(VoteResultCertificationError::NoMajority {
+                signers,
+                committee: committee.len(),
+            }
+            .into());
+        }
+
+        let status = plan_manager.statuses();
+        let mut tallied = Vec::with_capacity(status.proposals.len());
+        for proposal in status.proposals.iter() {
+            let result = proposal
+                .tally
+                .as_ref()
+                .and_then(|tally| tally.result())
+                .ok_or_else(|| VoteResultCertificationError::NotTallied(vote_plan.clone()))?;
+            tallied.push(result.results());
+        }
+        let matches = tallied.len() == certification.results().len()
+            && tallied
+                .iter()
+                .zip(certification.results())
+                .all(|(tallied, certified)| {
+                    tallied.len() == certified.len()
+                        && tallied
+                            .iter()
+                            .zip(certified.iter())
+                            .all(|(weight, certified)| u64::from(*weight) == *certified)
+                });
+        if !matches {
+            return Err(VoteResultCertificationError::ResultsMismatch(vote_plan.clone()).into());
+        }
+
+        let released = self
+            .governance
+            .certifications
+            .certify(certification.clone());
+        self.apply_vote_actions(vote_plan, released)?;
 
         Ok(self)
     }
@@ -1158,12 +2036,13 @@ impl Ledger {
         tally: &certificate::EncryptedVoteTally,
         bad: &TransactionBindingAuthData<'a>,
         sig: certificate::EncryptedVoteTallyProof,
+        context: BindingSignatureContext,
     ) -> Result<Self, Error> {
-        if sig.verify(bad) == Verification::Failed {
+        if sig.verify(bad, context) == Verification::Failed {
             return Err(Error::VoteTallyProofFailed);
         }
 
-        let stake = StakeControl::new_with(&self.accounts, &self.utxos);
+        let stake = self.voting_stake();
 
         self.votes = self
             .votes
@@ -1172,29 +2051,53 @@ impl Ledger {
         Ok(self)
     }
 
+    pub fn apply_voting_power_snapshot<'a>(
+        mut self,
+        block_date: BlockDate,
+        snapshot: &certificate::VotingPowerSnapshot,
+        bad: &TransactionBindingAuthData<'a>,
+        sig: certificate::VotingPowerSnapshotProof,
+    ) -> Result<Self, Error> {
+        let context = self.settings.binding_signature_context(block_date.epoch);
+        if sig.verify(bad, context) == Verification::Failed {
+            return Err(Error::VotingPowerSnapshotProofFailed);
+        }
+
+        self.votes = self
+            .votes
+            .apply_voting_power_snapshot(block_date, snapshot, sig.id)?;
+
+        Ok(self)
+    }
+
     pub fn apply_pool_registration_signcheck<'a>(
         self,
         cert: &certificate::PoolRegistration,
         bad: &TransactionBindingAuthData<'a>,
         sig: certificate::PoolSignature,
+        context: BindingSignatureContext,
+        hash_version: HashVersion,
     ) -> Result<Self, Error> {
         check::valid_pool_registration_certificate(cert)?;
         check::valid_pool_signature(&sig)?;
 
-        if sig.verify(cert, bad) == Verification::Failed {
+        if sig.verify(cert, bad, context) == Verification::Failed {
             return Err(Error::PoolRetirementSignatureFailed);
         }
 
-        self.apply_pool_registration(cert)
+        self.apply_pool_registration(cert, hash_version)
     }
 
     pub fn apply_pool_registration(
         mut self,
         cert: &certificate::PoolRegistration,
+        hash_version: HashVersion,
     ) -> Result<Self, Error> {
         check::valid_pool_registration_certificate(cert)?;
 
-        self.delegation = self.delegation.register_stake_pool(cert.clone())?;
+        self.delegation = self
+            .delegation
+            .register_stake_pool_with_version(cert.clone(), hash_version)?;
         Ok(self)
     }
 
@@ -1203,11 +2106,12 @@ impl Ledger {
         auth_cert: &certificate::PoolRetirement,
         bad: &TransactionBindingAuthData<'a>,
         sig: certificate::PoolSignature,
+        context: BindingSignatureContext,
     ) -> Result<Self, Error> {
         check::valid_pool_signature(&sig)?;
 
         let reg = self.delegation.stake_pool_get(&auth_cert.pool_id)?;
-        if sig.verify(reg, bad) == Verification::Failed {
+        if sig.verify(reg, bad, context) == Verification::Failed {
             return Err(Error::PoolRetirementSignatureFailed);
         }
 
@@ -1220,13 +2124,15 @@ impl Ledger {
         auth_cert: &certificate::PoolUpdate,
         bad: &TransactionBindingAuthData<'a>,
         sig: certificate::PoolSignature,
+        context: BindingSignatureContext,
+        hash_version: HashVersion,
     ) -> Result<Self, Error> {
         check::valid_pool_update_certificate(auth_cert)?;
         check::valid_pool_signature(&sig)?;
 
         let state = self.delegation.stake_pool_get_state(&auth_cert.pool_id)?;
 
-        if auth_cert.last_pool_reg_hash != state.current_pool_registration_hash() {
+        if auth_cert.last_pool_reg_hash != state.current_pool_registration_hash(hash_version) {
             return Err(Error::PoolUpdateLastHashDoesntMatch);
         }
 
@@ -1237,7 +2143,7 @@ impl Ledger {
             return Err(Error::PoolUpdateFeesNotAllowedYet);
         }
 
-        if sig.verify(&state.registration, bad) == Verification::Failed {
+        if sig.verify(&state.registration, bad, context) == Verification::Failed {
             return Err(Error::PoolUpdateSignatureFailed);
         }
 
@@ -1252,6 +2158,38 @@ impl Ledger {
         Ok(self)
     }
 
+    /// Replace the keys of a pool, keeping its id, its delegations and the
+    /// rest of its registration.
+    pub fn apply_pool_keys_rotation<'a>(
+        mut self,
+        auth_cert: &certificate::PoolKeysRotation,
+        bad: &TransactionBindingAuthData<'a>,
+        sig: certificate::PoolSignature,
+        context: BindingSignatureContext,
+        hash_version: HashVersion,
+    ) -> Result<Self, Error> {
+        check::valid_pool_signature(&sig)?;
+
+        let state = self.delegation.stake_pool_get_state(&auth_cert.pool_id)?;
+
+        if auth_cert.last_pool_reg_hash != state.current_pool_registration_hash(hash_version) {
+            return Err(Error::PoolUpdateLastHashDoesntMatch);
+        }
+
+        // only the owners can sign, as for a full update
+        if sig.verify(&state.registration, bad, context) == Verification::Failed {
+            return Err(Error::PoolUpdateSignatureFailed);
+        }
+
+        let mut updated_state = state.clone();
+        updated_state.registration = Arc::new(auth_cert.rotate(&state.registration));
+
+        self.delegation
+            .stake_pool_set_state(&auth_cert.pool_id, updated_state)?;
+
+        Ok(self)
+    }
+
     pub fn apply_stake_delegation(
         mut self,
         auth_cert: &certificate::StakeDelegation,
@@ -1266,12 +2204,157 @@ impl Ledger {
         Ok(self)
     }
 
-    pub fn apply_owner_stake_delegation<'a>(
+    /// Set the delegation of the account, taking effect from the activation
+    /// epoch of the certificate only. `epoch` is the current epoch.
+    pub fn apply_deferred_stake_delegation(
         mut self,
+        auth_cert: &certificate::DeferredStakeDelegation,
+        epoch: Epoch,
+    ) -> Result<Self, Error> {
+        let delegation = auth_cert.get_delegation_type();
+
+        let account_key = auth_cert
+            .delegation
+            .account_id
+            .to_single_account()
+            .ok_or(Error::AccountIdentifierInvalid)?;
+        self.accounts = self.accounts.set_delegation_from(
+            &account_key,
+            delegation,
+            auth_cert.activation,
+            epoch,
+        )?;
+        Ok(self)
+    }
+
+    /// Register the handle for the account signing the certificate, or renew
+    /// its registration if the account already owns the handle.
+    ///
+    /// The transaction pays for the deposit of a new registration, a renewal
+    /// only pays the fee and keeps the deposit locked by the registration.
+    pub fn apply_handle_registration<'a>(
+        self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, certificate::HandleRegistration>,
+        dyn_params: &LedgerParameters,
+        epoch: Epoch,
+    ) -> Result<(Self, Value), Error> {
+        let cert = tx.payload().into_payload();
+        let account = cert
+            .account_id
+            .to_single_account()
+            .ok_or(Error::AccountIdentifierInvalid)?;
+        let verified = tx.payload_auth().into_payload_auth().verify_slice(
+            &account.clone().into(),
+            &tx.transaction_binding_auth_data(),
+            self.settings.binding_signature_context(epoch),
+        );
+        if verified == Verification::Failed {
+            return Err(Error::HandleRegistrationSignatureFailed);
+        }
+
+        let renewal = self.check_handle_registration(&cert.handle, &account, epoch)?;
+        let deposit = if renewal {
+            Value::zero()
+        } else {
+            dyn_params.handle_registration_deposit
+        };
+        let (new_ledger, fee) =
+            self.apply_transaction_with_deposit(fragment_id, tx, dyn_params, deposit)?;
+
+        let expiry = dyn_params
+            .handle_validity
+            .map(|validity| epoch.saturating_add(validity));
+        let deposit = if renewal { None } else { Some(deposit) };
+        let new_ledger = new_ledger.register_handle(cert.handle, account, expiry, deposit)?;
+        Ok((new_ledger, fee))
+    }
+
+    /// Check that the account can register the handle during `epoch`,
+    /// returning whether the account already owns the handle
+    fn check_handle_registration(
+        &self,
+        handle: &Handle,
+        account: &account::Identifier,
+        epoch: Epoch,
+    ) -> Result<bool, Error> {
+        match self.handles.get(handle) {
+            Some(record) if &record.account == account => Ok(true),
+            Some(record) if record.is_valid_at(epoch) => Err(Error::HandleAlreadyRegistered {
+                handle: handle.clone(),
+            }),
+            _ => Ok(false),
+        }
+    }
+
+    /// Record the registration of the handle. The deposit of a new
+    /// registration replaces the one of the previous owner of the expired
+    /// handle, which is refunded. A renewal, without deposit, keeps the
+    /// deposit already locked.
+    fn register_handle(
+        mut self,
+        handle: Handle,
+        account: account::Identifier,
+        expiry: Option<Epoch>,
+        deposit: Option<Value>,
+    ) -> Result<Self, Error> {
+        if let Some(deposit) = deposit {
+            let owner = DepositOwner::Handle(handle.clone());
+            self = self.refund_deposit(&owner)?;
+            let refund_to = account.clone();
+            self = self.lock_deposit(owner, deposit, || refund_to)?;
+        }
+        self.handles = self.handles.set(handle, HandleRecord { account, expiry });
+        Ok(self)
+    }
+
+    pub fn apply_owner_stake_delegation<'a>(
+        self,
+        fragment_id: &FragmentId,
         tx: &TransactionSlice<'a, certificate::OwnerStakeDelegation>,
         dyn_params: &LedgerParameters,
     ) -> Result<(Self, Value), Error> {
-        let sign_data_hash = tx.transaction_sign_data_hash();
+        let payload = tx.payload().into_payload();
+        self.apply_owner_delegation(
+            fragment_id,
+            tx,
+            payload.get_delegation_type(),
+            None,
+            dyn_params,
+        )
+    }
+
+    /// Set the delegation of the owner of the transaction input, taking
+    /// effect from the activation epoch of the certificate only. `epoch` is
+    /// the current epoch.
+    pub fn apply_deferred_owner_stake_delegation<'a>(
+        self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, certificate::DeferredOwnerStakeDelegation>,
+        dyn_params: &LedgerParameters,
+        epoch: Epoch,
+    ) -> Result<(Self, Value), Error> {
+        let payload = tx.payload().into_payload();
+        self.apply_owner_delegation(
+            fragment_id,
+            tx,
+            payload.get_delegation_type(),
+            Some((payload.activation, epoch)),
+            dyn_params,
+        )
+    }
+
+    /// `activation` is the activation epoch of a deferred delegation,
+    /// paired with the current epoch.
+    fn apply_owner_delegation<'a, P: Payload>(
+        mut self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<'a, P>,
+        delegation: &account::DelegationType,
+        activation: Option<(Epoch, Epoch)>,
+        dyn_params: &LedgerParameters,
+    ) -> Result<(Self, Value), Error> {
+        let sign_data_hash = tx.transaction_sign_data_hash_with_version(dyn_params.hash_version);
 
         let (account_id, value, witness) = {
             check::valid_stake_owner_delegation_transaction(tx)?;
@@ -1305,11 +2388,14 @@ impl Ledger {
                     &account_id,
                     witness,
                     value,
+                    WitnessCheck::new(self.signature_cache.as_ref(), fragment_id, 0),
                 )?;
-                self.accounts = single.set_delegation(
-                    &account_id,
-                    tx.payload().into_payload().get_delegation_type(),
-                )?;
+                self.accounts = match activation {
+                    None => single.set_delegation(&account_id, delegation)?,
+                    Some((activation, epoch)) => {
+                        single.set_delegation_from(&account_id, delegation, activation, epoch)?
+                    }
+                };
             }
             MatchingIdentifierWitness::Multi(account_id, witness) => {
                 let multi = input_multi_account_verify(
@@ -1319,19 +2405,34 @@ impl Ledger {
                     &account_id,
                     witness,
                     value,
+                    WitnessCheck::new(self.signature_cache.as_ref(), fragment_id, 0),
                 )?;
-                self.multisig = multi.set_delegation(
-                    &account_id,
-                    tx.payload().into_payload().get_delegation_type(),
-                )?;
+                self.multisig = match activation {
+                    None => multi.set_delegation(&account_id, delegation)?,
+                    Some((activation, epoch)) => {
+                        multi.set_delegation_from(&account_id, delegation, activation, epoch)?
+                    }
+                };
             }
         };
         self = self.apply_tx_fee(fee)?;
         Ok((self, fee))
     }
 
+    /// Stake distribution with the delegations in effect at the current epoch
     pub fn get_stake_distribution(&self) -> StakeDistribution {
-        stake::get_distribution(&self.accounts, &self.delegation, &self.utxos)
+        self.get_stake_distribution_at(self.date.epoch)
+    }
+
+    /// Stake distribution with the delegations in effect at the given epoch
+    pub fn get_stake_distribution_at(&self, epoch: Epoch) -> StakeDistribution {
+        stake::get_distribution(&self.accounts, &self.delegation, &self.utxos, epoch)
+    }
+
+    /// Canonical serialization of the stake distribution of the current
+    /// epoch, to be taken right after the transition to the epoch
+    pub fn stake_distribution_snapshot(&self) -> StakeDistributionSnapshot {
+        StakeDistributionSnapshot::new(self.date.epoch, &self.get_stake_distribution())
     }
 
     /// access the ledger static parameters
@@ -1343,6 +2444,66 @@ impl Ledger {
         &self.accounts
     }
 
+    /// Supply of the tokens carried by the unspent outputs
+    pub fn token_distribution(&self) -> &TokenDistribution {
+        &self.tokens
+    }
+
+    /// Total value of the given token carried by the unspent outputs
+    pub fn token_supply(&self, token: &TokenIdentifier) -> Value {
+        self.tokens.supply(token)
+    }
+
+    /// The deposits locked by the stake pool registrations and vote plans
+    pub fn deposits(&self) -> &Deposits {
+        &self.deposits
+    }
+
+    /// The deposit locked by the registration of the given stake pool, if any
+    pub fn pool_deposit(&self, pool_id: &PoolId) -> Option<&Deposit> {
+        self.deposits.get(&DepositOwner::StakePool(pool_id.clone()))
+    }
+
+    /// The deposit locked by the creation of the given vote plan, if any
+    pub fn vote_plan_deposit(&self, vote_plan_id: &certificate::VotePlanId) -> Option<&Deposit> {
+        self.deposits
+            .get(&DepositOwner::VotePlan(vote_plan_id.clone()))
+    }
+
+    /// The handles registered in the ledger, including the expired ones
+    pub fn handles(&self) -> &Handles {
+        &self.handles
+    }
+
+    /// The account named by the handle, if it is registered and has not
+    /// expired at the date of the ledger
+    pub fn resolve_handle(&self, handle: &Handle) -> Option<&account::Identifier> {
+        self.handles.resolve(handle, self.date.epoch)
+    }
+
+    /// Share the cache of the verified witnesses with the ledgers derived from
+    /// this one, see [`SignatureCache`]
+    pub fn with_signature_cache(mut self, cache: SignatureCache) -> Self {
+        self.signature_cache = Some(cache);
+        self
+    }
+
+    pub fn signature_cache(&self) -> Option<&SignatureCache> {
+        self.signature_cache.as_ref()
+    }
+
+    /// The deposit locked by the registration of the given handle, if any
+    pub fn handle_deposit(&self, handle: &Handle) -> Option<&Deposit> {
+        self.deposits.get(&DepositOwner::Handle(handle.clone()))
+    }
+
+    /// Choose the fragments of the next block among the pending ones, so
+    /// that the block content does not exceed the soft maximum size of the
+    /// settings, see [`select_by_fee_density`]
+    pub fn select_block_fragments(&self, fragments: Vec<Fragment>) -> FragmentSelection {
+        select_by_fee_density(fragments, self.settings.block_content_soft_max_size())
+    }
+
     pub fn get_ledger_parameters(&self) -> LedgerParameters {
         LedgerParameters {
             fees: self.settings.linear_fees,
@@ -1352,12 +2513,43 @@ impl Ledger {
                 .unwrap_or_else(rewards::TaxType::zero),
             reward_params: self.settings.to_reward_params(),
             block_content_max_size: self.settings.block_content_max_size,
+            block_content_max_weight: self.settings.block_content_max_weight,
             epoch_stability_depth: self.settings.epoch_stability_depth,
             fees_goes_to: self.settings.fees_goes_to,
             committees: self.settings.committees.clone(),
+            pool_registration_deposit: self.settings.pool_registration_deposit,
+            vote_plan_deposit: self.settings.vote_plan_deposit,
+            max_active_vote_plans_per_committee: self.settings.max_active_vote_plans_per_committee,
+            handle_registration_deposit: self.settings.handle_registration_deposit,
+            handle_validity: self.settings.handle_validity,
+            canonical_fragment_order: self.settings.canonical_fragment_order,
+            hash_version: self.settings.hash_version(self.date.epoch),
         }
     }
 
+    /// The ledger as it will be at the start of `epoch`, with the update
+    /// proposals accepted by then applied to the settings.
+    ///
+    /// Accepted proposals only take effect on the first block of a new epoch,
+    /// this is the state the leadership of an upcoming epoch is computed from,
+    /// so a change of consensus version or of the epoch length is honoured
+    /// from its first slot.
+    pub fn at_epoch_start(&self, epoch: Epoch) -> Result<Self, Error> {
+        let mut new_ledger = self.clone();
+        if epoch > self.date.epoch {
+            let epoch_start = BlockDate { epoch, slot_id: 0 };
+            let (updates, settings, _) = new_ledger.updates.process_proposals(
+                new_ledger.settings.clone(),
+                self.date,
+                epoch_start,
+            )?;
+            new_ledger.updates = updates;
+            new_ledger.change_settings(settings, epoch_start, SettingSource::UpdateProposal);
+            new_ledger.update_era(self.date.epoch + 1);
+        }
+        Ok(new_ledger)
+    }
+
     pub fn consensus_version(&self) -> ConsensusType {
         self.settings.consensus_version
     }
@@ -1396,6 +2588,15 @@ impl Ledger {
         self.date
     }
 
+    /// The last epoch the pending update proposal can be accepted in, see
+    /// `UpdateProposalState::expiry_epoch`
+    pub fn update_proposal_expiry(&self, proposal_id: &update::UpdateProposalId) -> Option<Epoch> {
+        self.updates
+            .proposals
+            .get(proposal_id)
+            .map(|proposal| proposal.expiry_epoch(&self.settings))
+    }
+
     pub fn era(&self) -> &TimeEra {
         &self.era
     }
@@ -1420,19 +2621,32 @@ impl Ledger {
             .chain(new_utxo_values)
             .chain(Some(account_value))
             .chain(Some(multisig_value))
+            .chain(Some(self.deposits.total()))
             .chain(self.pots.values());
         Value::sum(all_utxo_values).map_err(|_| Error::Block0(Block0Error::UtxoTotalValueTooBig))
     }
 
+    /// Spend the inputs of the transaction, returning the tokens carried by
+    /// the spent outputs
     fn apply_tx_inputs<Extra: Payload>(
         mut self,
+        fragment_id: &FragmentId,
         tx: &TransactionSlice<Extra>,
-    ) -> Result<Self, Error> {
-        let sign_data_hash = tx.transaction_sign_data_hash();
-        for (input, witness) in tx.inputs_and_witnesses().iter() {
+        hash_version: HashVersion,
+    ) -> Result<(Self, TokenBundle), Error> {
+        let sign_data_hash = tx.transaction_sign_data_hash_with_version(hash_version);
+        let mut spent_tokens = TokenBundle::new();
+        let cache = self.signature_cache.clone();
+        for (index, (input, witness)) in tx.inputs_and_witnesses().iter().enumerate() {
+            let check = WitnessCheck::new(cache.as_ref(), fragment_id, index as u8);
             match input.to_enum() {
                 InputEnum::UtxoInput(utxo) => {
-                    self = self.apply_input_to_utxo(&sign_data_hash, &utxo, &witness)?
+                    let (ledger, tokens) =
+                        self.apply_input_to_utxo(&sign_data_hash, &utxo, &witness, check)?;
+                    self = ledger;
+                    spent_tokens = spent_tokens
+                        .checked_add(&tokens)
+                        .map_err(|error| Error::TokenSupplyInvalid { error })?;
                 }
                 InputEnum::AccountInput(account_id, value) => {
                     match match_identifier_witness(&account_id, &witness)? {
@@ -1444,6 +2658,7 @@ impl Ledger {
                                 &account_id,
                                 witness,
                                 value,
+                                check,
                             )?
                         }
                         MatchingIdentifierWitness::Multi(account_id, witness) => {
@@ -1454,13 +2669,14 @@ impl Ledger {
                                 &account_id,
                                 witness,
                                 value,
+                                check,
                             )?
                         }
                     }
                 }
             }
         }
-        Ok(self)
+        Ok((self, spent_tokens))
     }
 
     fn apply_tx_outputs(
@@ -1471,6 +2687,7 @@ impl Ledger {
         let mut new_utxos = Vec::new();
         for (index, output) in outputs.iter().enumerate() {
             check::valid_output_value(&output)?;
+            check::valid_output_tokens(&output)?;
 
             if output.address.discrimination() != self.static_params.discrimination {
                 return Err(Error::InvalidDiscrimination);
@@ -1504,6 +2721,12 @@ impl Ledger {
             }
         }
         if !new_utxos.is_empty() {
+            for (_, output) in new_utxos.iter() {
+                self.tokens = self
+                    .tokens
+                    .add(&output.tokens)
+                    .map_err(|error| Error::TokenSupplyInvalid { error })?;
+            }
             self.utxos = self.utxos.add(&fragment_id, &new_utxos)?;
         }
         Ok(self)
@@ -1529,12 +2752,70 @@ impl Ledger {
         Ok(self)
     }
 
+    /// Lock the deposit paid for a certificate, nothing is locked when no
+    /// deposit is required.
+    fn lock_deposit<F>(
+        mut self,
+        owner: DepositOwner,
+        value: Value,
+        refund_to: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnOnce() -> account::Identifier,
+    {
+        if value != Value::zero() {
+            let deposit = Deposit {
+                value,
+                refund_to: refund_to(),
+            };
+            self.deposits = self.deposits.lock(owner, deposit)?;
+        }
+        Ok(self)
+    }
+
+    /// Refund the deposit locked for a certificate, if any, to the account
+    /// recorded when it was locked.
+    fn refund_deposit(mut self, owner: &DepositOwner) -> Result<Self, Error> {
+        let (deposits, deposit) = self.deposits.release(owner);
+        self.deposits = deposits;
+        if let Some(deposit) = deposit {
+            self.accounts =
+                self.accounts
+                    .add_value_or_account(&deposit.refund_to, deposit.value, ())?;
+        }
+        Ok(self)
+    }
+
+    /// Refund the deposits of the vote plans whose committee period ended
+    /// before `date` without a tally, they can no longer be tallied and their
+    /// deposit would otherwise stay locked forever.
+    fn refund_untallied_vote_plan_deposits(mut self, date: BlockDate) -> Result<Self, Error> {
+        let elapsed: Vec<DepositOwner> = self
+            .deposits
+            .iter()
+            .filter_map(|(owner, _)| match owner {
+                DepositOwner::VotePlan(id) => self
+                    .votes
+                    .plans
+                    .lookup(id)
+                    .filter(|plan| plan.plan().committee_finished(date))
+                    .map(|_| owner.clone()),
+                _ => None,
+            })
+            .collect();
+        for owner in elapsed {
+            self = self.refund_deposit(&owner)?;
+        }
+        Ok(self)
+    }
+
     fn apply_input_to_utxo(
         mut self,
         sign_data_hash: &TransactionSignDataHash,
         utxo: &UtxoPointer,
         witness: &Witness,
-    ) -> Result<Self, Error> {
+        check: WitnessCheck<'_>,
+    ) -> Result<(Self, TokenBundle), Error> {
         match witness {
             Witness::Account(_) => Err(Error::ExpectingUtxoWitness),
             Witness::Multisig(_) => Err(Error::ExpectingUtxoWitness),
@@ -1566,8 +2847,10 @@ impl Ledger {
                     sign_data_hash,
                     WitnessUtxoVersion::Legacy,
                 );
-                let verified = signature.verify(&pk, &data_to_verify);
-                if verified == chain_crypto::Verification::Failed {
+                let verified = check.verify(data_to_verify.as_ref(), || {
+                    signature.verify(&pk, &data_to_verify) == chain_crypto::Verification::Success
+                });
+                if !verified {
                     return Err(Error::OldUtxoInvalidSignature {
                         utxo: *utxo,
                         output: associated_output,
@@ -1575,7 +2858,7 @@ impl Ledger {
                     });
                 };
 
-                Ok(self)
+                Ok((self, TokenBundle::new()))
             }
             Witness::Utxo(signature) => {
                 let (new_utxos, associated_output) =
@@ -1593,18 +2876,24 @@ impl Ledger {
                     sign_data_hash,
                     WitnessUtxoVersion::Normal,
                 );
-                let verified = signature.verify(
-                    &associated_output.address.public_key().unwrap(),
-                    &data_to_verify,
-                );
-                if verified == chain_crypto::Verification::Failed {
+                let verified = check.verify(data_to_verify.as_ref(), || {
+                    signature.verify(
+                        &associated_output.address.public_key().unwrap(),
+                        &data_to_verify,
+                    ) == chain_crypto::Verification::Success
+                });
+                if !verified {
                     return Err(Error::UtxoInvalidSignature {
                         utxo: *utxo,
                         output: associated_output,
                         witness: witness.clone(),
                     });
                 };
-                Ok(self)
+                self.tokens = self
+                    .tokens
+                    .remove(&associated_output.tokens)
+                    .map_err(|error| Error::TokenSupplyInvalid { error })?;
+                Ok((self, associated_output.tokens))
             }
         }
     }
@@ -1619,6 +2908,12 @@ impl Ledger {
 }
 
 impl ApplyBlockLedger {
+    /// The update proposals accepted or expired on the epoch transition of
+    /// the block, if it is the first block of an epoch
+    pub fn update_proposal_events(&self) -> &[update::UpdateProposalEvent] {
+        &self.update_events
+    }
+
     pub fn apply_fragment(&self, fragment: &Fragment) -> Result<Self, Error> {
         let ledger = self
             .ledger
@@ -1660,6 +2955,7 @@ fn apply_old_declaration(
         let output = Output {
             address: d.0.clone(),
             value: d.1,
+            tokens: TokenBundle::new(),
         };
         outputs.push((i as u8, output))
     }
@@ -1674,6 +2970,15 @@ fn calculate_fee<'a, Extra: Payload>(
     dyn_params.fees.calculate_tx(tx)
 }
 
+/// The pool deposit is refunded to the reward account of the pool when it
+/// is a single account, to its first owner otherwise.
+fn pool_deposit_refund_account(cert: &certificate::PoolRegistration) -> account::Identifier {
+    match &cert.reward_account {
+        Some(AccountIdentifier::Single(id)) => id.clone(),
+        _ => cert.owners[0].clone().into(),
+    }
+}
+
 pub enum MatchingIdentifierWitness<'a> {
     Single(account::Identifier, &'a account::Witness),
     Multi(multisig::Identifier, &'a multisig::Witness),
@@ -1708,14 +3013,17 @@ fn input_single_account_verify<'a>(
     account: &account::Identifier,
     witness: &'a account::Witness,
     value: Value,
+    check: WitnessCheck<'_>,
 ) -> Result<account::Ledger, Error> {
     // .remove_value() check if there's enough value and if not, returns a Err.
     let (new_ledger, spending_counter) = ledger.remove_value(&account, value)?;
     ledger = new_ledger;
 
     let tidsc = WitnessAccountData::new(block0_hash, sign_data_hash, spending_counter);
-    let verified = witness.verify(account.as_ref(), &tidsc);
-    if verified == chain_crypto::Verification::Failed {
+    let verified = check.verify(tidsc.as_ref(), || {
+        witness.verify(account.as_ref(), &tidsc) == chain_crypto::Verification::Success
+    });
+    if !verified {
         return Err(Error::AccountInvalidSignature {
             account: account.clone(),
             witness: Witness::Account(witness.clone()),
@@ -1731,12 +3039,15 @@ fn input_multi_account_verify<'a>(
     account: &multisig::Identifier,
     witness: &'a multisig::Witness,
     value: Value,
+    check: WitnessCheck<'_>,
 ) -> Result<multisig::Ledger, Error> {
     // .remove_value() check if there's enough value and if not, returns a Err.
     let (new_ledger, declaration, spending_counter) = ledger.remove_value(&account, value)?;
 
     let data_to_verify = WitnessMultisigData::new(&block0_hash, sign_data_hash, spending_counter);
-    if !witness.verify(declaration, &data_to_verify) {
+    if !check.verify(data_to_verify.as_ref(), || {
+        witness.verify(declaration, &data_to_verify)
+    }) {
         return Err(Error::MultisigInvalidSignature {
             multisig: account.clone(),
             witness: Witness::Multisig(witness.clone()),
@@ -1795,9 +3106,17 @@ mod tests {
                 treasury_tax: Arbitrary::arbitrary(g),
                 reward_params: Arbitrary::arbitrary(g),
                 block_content_max_size: Arbitrary::arbitrary(g),
+                block_content_max_weight: Arbitrary::arbitrary(g),
                 epoch_stability_depth: Arbitrary::arbitrary(g),
                 fees_goes_to: Arbitrary::arbitrary(g),
                 committees: Arc::new(committees.into()),
+                pool_registration_deposit: Value::zero(),
+                vote_plan_deposit: Value::zero(),
+                max_active_vote_plans_per_committee: None,
+                handle_registration_deposit: Value::zero(),
+                handle_validity: None,
+                canonical_fragment_order: false,
+                hash_version: HashVersion::CURRENT,
             }
         }
     }
@@ -1935,6 +3254,7 @@ mod tests {
             &id,
             &witness,
             value_to_sub,
+            WitnessCheck::uncached(),
         );
 
         TestResult::from_bool(result.is_err())
@@ -1963,6 +3283,7 @@ mod tests {
             &id,
             &to_account_witness(&signed_tx.witnesses().iter().next().unwrap()),
             value_to_sub,
+            WitnessCheck::uncached(),
         );
         assert!(result.is_ok())
     }
@@ -1999,6 +3320,7 @@ mod tests {
             &id,
             &to_account_witness(&signed_tx.witnesses().iter().next().unwrap()),
             value_to_sub,
+            WitnessCheck::uncached(),
         );
         assert!(result.is_err())
     }
@@ -2034,6 +3356,7 @@ mod tests {
             &id,
             &to_account_witness(&signed_tx.witnesses().iter().next().unwrap()),
             value_to_sub,
+            WitnessCheck::uncached(),
         );
         assert!(result.is_err())
     }
@@ -2063,6 +3386,7 @@ mod tests {
             &non_existing_account.public_key().into(),
             &to_account_witness(&signed_tx.witnesses().iter().next().unwrap()),
             value_to_sub,
+            WitnessCheck::uncached(),
         );
         assert!(result.is_err())
     }
@@ -2080,7 +3404,12 @@ mod tests {
             .unwrap();
 
         let inner_ledger: Ledger = test_ledger.into();
-        let result = inner_ledger.apply_input_to_utxo(&sign_data_hash, &utxo_pointer, &witness);
+        let result = inner_ledger.apply_input_to_utxo(
+            &sign_data_hash,
+            &utxo_pointer,
+            &witness,
+            WitnessCheck::uncached(),
+        );
         match (witness, result) {
             (Witness::OldUtxo(..), Ok(_)) => TestResult::error("expecting error, but got success"),
             (Witness::OldUtxo(..), Err(_)) => TestResult::passed(),
@@ -2113,6 +3442,7 @@ mod tests {
             &sign_data_hash,
             &utxo_pointer,
             &signed_tx.witnesses().iter().next().unwrap(),
+            WitnessCheck::uncached(),
         );
         assert!(result.is_ok())
     }
@@ -2137,6 +3467,7 @@ mod tests {
             &sign_data_hash,
             &utxo_pointer,
             &signed_tx.witnesses().iter().next().unwrap(),
+            WitnessCheck::uncached(),
         );
         assert!(result.is_err())
     }
@@ -2238,9 +3569,17 @@ mod tests {
                 treasury_tax: rewards::TaxType::zero(),
                 reward_params: rewards::Parameters::zero(),
                 block_content_max_size: 10_240,
+                block_content_max_weight: None,
                 epoch_stability_depth: 1000,
                 fees_goes_to: FeesGoesTo::Rewards,
                 committees: Arc::new(Box::new([])),
+                pool_registration_deposit: Value::zero(),
+                vote_plan_deposit: Value::zero(),
+                max_active_vote_plans_per_committee: None,
+                handle_registration_deposit: Value::zero(),
+                handle_validity: None,
+                canonical_fragment_order: false,
+                hash_version: HashVersion::CURRENT,
             };
             InternalApplyTransactionTestParams {
                 dyn_params,
@@ -1,19 +1,52 @@
+mod activity;
 pub mod check;
+mod deposits;
+mod effects;
+mod epoch_length;
 pub mod governance;
+mod handles;
+mod header_ledger;
+pub mod history;
 mod info;
 pub mod iter;
 mod leaderlog;
 #[allow(clippy::module_inception)]
 pub mod ledger;
 mod pots;
+mod prevalidation;
+mod randomness;
+mod reason;
 pub mod recovery;
 mod reward_info;
+mod settings_report;
+mod shadow;
+mod signature_cache;
+mod token_distribution;
+mod transition;
+mod utxo_report;
+mod view;
 
+pub use deposits::{Deposit, DepositError, DepositOwner, Deposits};
+pub use effects::{BalanceDelta, FragmentEffects, FragmentStatus, VoteRecord};
+pub use handles::{HandleRecord, Handles};
+pub use header_ledger::{HeaderLedger, HeaderLedgerError};
 pub use iter::*;
 pub use leaderlog::LeadersParticipationRecord;
 pub use ledger::*;
 pub use pots::Pots;
+pub use prevalidation::Prevalidation;
+pub use randomness::RandomnessBeacon;
+pub use reason::{RejectionReason, UnknownRejectionReason};
 pub use reward_info::{EpochRewardsInfo, RewardsInfoParameters};
+pub use settings_report::{
+    SettingProvenance, SettingReportEntry, SettingSource, SettingsProvenance,
+};
+pub use shadow::{Divergence, LedgerPart, ShadowDivergence, ShadowLedger, ShadowSide};
+pub use signature_cache::SignatureCache;
+pub use token_distribution::TokenDistribution;
+pub use transition::{EpochTransitionPreview, LeadershipInputs};
+pub use utxo_report::{AddressUtxoStats, UtxoReport};
+pub use view::LedgerView;
 
 cfg_if! {
    if #[cfg(test)] {
@@ -0,0 +1,326 @@
+//! Prevalidation of the fragments of a block.
+//!
+//! With [`Prevalidation::Parallel`], the application of a block is split in
+//! two stages. The first one runs over all the fragments of the block in
+//! parallel, against the ledger at the start of the block:
+//!
+//! * the checks which do not depend on the state of the ledger, as long as
+//!   the sequential application would run them first for that fragment, so
+//!   that a block is rejected with the same error whatever the mode;
+//! * the verification of the witnesses of the inputs, the successful ones
+//!   being remembered in a [`SignatureCache`], the one of the ledger if any
+//!   or one dedicated to the block otherwise.
+//!
+//! The second stage applies the fragments in order, as without
+//! prevalidation, except that a fragment which failed the first stage is
+//! rejected before being applied and that the cached witness verifications
+//! are not done again. A witness checked against a state which changes in
+//! the course of the block, like the spending counter of an account used by
+//! several fragments, is simply verified again by the second stage.
+//!
+//! Legacy and multisig witnesses, and the proofs of the private ballots
+//! whose vote plan may be registered by the same block, are left to the
+//! second stage.
+
+use super::check;
+use super::signature_cache::{SignatureCache, WitnessCheck};
+use super::{Error, Ledger};
+use crate::date::BlockDate;
+use crate::fragment::{Fragment, FragmentId};
+use crate::key::HashVersion;
+use crate::transaction::*;
+use chain_crypto::Verification;
+use rayon::prelude::*;
+
+/// How the fragments of a block are validated, see [`Ledger::apply_block_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prevalidation {
+    /// validate and apply the fragments one after the other
+    Sequential,
+    /// validate what can be of all the fragments in parallel before
+    /// applying them one after the other
+    Parallel,
+}
+
+impl Default for Prevalidation {
+    fn default() -> Self {
+        Prevalidation::Sequential
+    }
+}
+
+impl Ledger {
+    /// The outcome of the first stage for every fragment, in order
+    pub(super) fn prevalidate_fragments(
+        &self,
+        fragments: &[Fragment],
+        block_date: BlockDate,
+        cache: &SignatureCache,
+    ) -> Vec<Result<(), Error>> {
+        let binding_context = self.settings.binding_signature_context(block_date.epoch);
+        let hash_version = self.settings.hash_version(block_date.epoch);
+        fragments
+            .par_iter()
+            .map(|fragment| {
+                self.prevalidate_fragment(fragment, binding_context, hash_version, cache)
+            })
+            .collect()
+    }
+
+    fn prevalidate_fragment(
+        &self,
+        fragment: &Fragment,
+        binding_context: BindingSignatureContext,
+        hash_version: HashVersion,
+        cache: &SignatureCache,
+    ) -> Result<(), Error> {
+        let fragment_id = fragment.hash_with_version(hash_version);
+        match fragment {
+            Fragment::Initial(_) | Fragment::OldUtxoDeclaration(_) => {
+                return Err(Error::Block0OnlyFragmentReceived)
+            }
+            Fragment::UpdateProposal(_) | Fragment::UpdateVote(_) => {}
+            Fragment::Transaction(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::OwnerStakeDelegation(tx) => {
+                let tx = tx.as_slice();
+                check::valid_stake_owner_delegation_transaction(&tx)?;
+                self.prevalidate_witnesses(&fragment_id, &tx, hash_version, cache);
+            }
+            Fragment::StakeDelegation(tx) => {
+                let tx = tx.as_slice();
+                let payload = tx.payload().into_payload();
+                let account_pk = payload
+                    .account_id
+                    .to_single_account()
+                    .ok_or(Error::AccountIdentifierInvalid)?;
+                let verified = tx.payload_auth().into_payload_auth().verify_slice(
+                    &account_pk.into(),
+                    &tx.transaction_binding_auth_data(),
+                    binding_context,
+                );
+                if verified == Verification::Failed {
+                    return Err(Error::StakeDelegationSignatureFailed);
+                }
+                self.prevalidate_transaction(&fragment_id, &tx, hash_version, cache)?
+            }
+            Fragment::DeferredOwnerStakeDelegation(tx) => {
+                let tx = tx.as_slice();
+                check::valid_stake_owner_delegation_transaction(&tx)?;
+                self.prevalidate_witnesses(&fragment_id, &tx, hash_version, cache);
+            }
+            Fragment::DeferredStakeDelegation(tx) => {
+                let tx = tx.as_slice();
+                let payload = tx.payload().into_payload();
+                let account_pk = payload
+                    .delegation
+                    .account_id
+                    .to_single_account()
+                    .ok_or(Error::AccountIdentifierInvalid)?;
+                let verified = tx.payload_auth().into_payload_auth().verify_slice(
+                    &account_pk.into(),
+                    &tx.transaction_binding_auth_data(),
+                    binding_context,
+                );
+                if verified == Verification::Failed {
+                    return Err(Error::StakeDelegationSignatureFailed);
+                }
+                self.prevalidate_transaction(&fragment_id, &tx, hash_version, cache)?
+            }
+            Fragment::PoolRegistration(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::PoolRetirement(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::PoolUpdate(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::PoolKeysRotation(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::VotePlan(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::VoteCast(tx) => {
+                let tx = tx.as_slice();
+                check::valid_vote_cast(&tx)?;
+                self.prevalidate_witnesses(&fragment_id, &tx, hash_version, cache);
+            }
+            Fragment::VoteCastBatch(tx) => {
+                let tx = tx.as_slice();
+                check::valid_vote_cast_batch(&tx)?;
+                check::valid_vote_cast_batch_certificate(&tx.payload().into_payload())?;
+                self.prevalidate_witnesses(&fragment_id, &tx, hash_version, cache);
+            }
+            Fragment::HandleRegistration(tx) => {
+                // the registration is checked against the registered handles
+                // before the transaction, only its signature comes first
+                let tx = tx.as_slice();
+                let account = tx
+                    .payload()
+                    .into_payload()
+                    .account_id
+                    .to_single_account()
+                    .ok_or(Error::AccountIdentifierInvalid)?;
+                let verified = tx.payload_auth().into_payload_auth().verify_slice(
+                    &account.into(),
+                    &tx.transaction_binding_auth_data(),
+                    binding_context,
+                );
+                if verified == Verification::Failed {
+                    return Err(Error::HandleRegistrationSignatureFailed);
+                }
+                self.prevalidate_witnesses(&fragment_id, &tx, hash_version, cache);
+            }
+            Fragment::VoteTally(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::EncryptedVoteTally(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::VotingPowerSnapshot(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::VoteResultCertification(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+            Fragment::CommitteeCandidacy(tx) => {
+                self.prevalidate_transaction(&fragment_id, &tx.as_slice(), hash_version, cache)?
+            }
+        }
+        Ok(())
+    }
+
+    fn prevalidate_transaction<Extra: Payload>(
+        &self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<Extra>,
+        hash_version: HashVersion,
+        cache: &SignatureCache,
+    ) -> Result<(), Error> {
+        check::valid_transaction_ios_number(tx)?;
+        self.prevalidate_witnesses(fragment_id, tx, hash_version, cache);
+        Ok(())
+    }
+
+    /// Verify the witnesses of the inputs against the state at the start of
+    /// the block. Nothing is reported: a witness may be valid against the
+    /// state in which the fragment is eventually applied.
+    fn prevalidate_witnesses<Extra: Payload>(
+        &self,
+        fragment_id: &FragmentId,
+        tx: &TransactionSlice<Extra>,
+        hash_version: HashVersion,
+        cache: &SignatureCache,
+    ) {
+        let block0_hash = &self.static_params.block0_initial_hash;
+        let sign_data_hash = tx.transaction_sign_data_hash_with_version(hash_version);
+        for (index, (input, witness)) in tx.inputs_and_witnesses().iter().enumerate() {
+            let check = WitnessCheck::new(Some(cache), fragment_id, index as u8);
+            match (input.to_enum(), &witness) {
+                (InputEnum::UtxoInput(utxo), Witness::Utxo(signature)) => {
+                    let public_key = self
+                        .utxos
+                        .get(&utxo.transaction_id, utxo.output_index)
+                        .and_then(|entry| entry.output.address.public_key().cloned());
+                    if let Some(public_key) = public_key {
+                        let data = WitnessUtxoData::new(
+                            block0_hash,
+                            &sign_data_hash,
+                            WitnessUtxoVersion::Normal,
+                        );
+                        check.verify(data.as_ref(), || {
+                            signature.verify(&public_key, &data) == Verification::Success
+                        });
+                    }
+                }
+                (InputEnum::AccountInput(account_id, _), Witness::Account(signature)) => {
+                    let account = match account_id.to_single_account() {
+                        Some(account) => account,
+                        None => continue,
+                    };
+                    if let Ok(state) = self.accounts.get_state(&account) {
+                        let data =
+                            WitnessAccountData::new(block0_hash, &sign_data_hash, state.counter);
+                        check.verify(data.as_ref(), || {
+                            signature.verify(account.as_ref(), &data) == Verification::Success
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        block::Block,
+        fragment::ConfigParams,
+        testing::{
+            ledger::{ConfigBuilder, TestLedger},
+            scenario::{prepare_scenario, stake_pool, wallet},
+        },
+        value::Value,
+    };
+
+    fn apply(
+        ledger: &TestLedger,
+        block: &Block,
+        prevalidation: Prevalidation,
+    ) -> Result<Ledger, Error> {
+        ledger.ledger.apply_block_with(
+            ledger.ledger.get_ledger_parameters(),
+            &block.contents,
+            &block.header.get_content_eval_context(),
+            prevalidation,
+        )
+    }
+
+    #[test]
+    fn parallel_prevalidation_is_equivalent_to_sequential_application() {
+        let (mut ledger, controller) = prepare_scenario()
+            .with_config(ConfigBuilder::new(0))
+            .with_initials(vec![
+                wallet("Alice").with(1_000).owns("stake_pool"),
+                wallet("Bob").with(1_000),
+                wallet("Clarice").with(1_000),
+            ])
+            .with_stake_pools(vec![stake_pool("stake_pool")])
+            .build()
+            .unwrap();
+        let stake_pool = controller.stake_pool("stake_pool").unwrap();
+        let alice = controller.wallet("Alice").unwrap();
+        let bob = controller.wallet("Bob").unwrap();
+        let clarice = controller.wallet("Clarice").unwrap();
+        let factory = controller.fragment_factory();
+
+        let valid = vec![
+            factory.transaction(&alice, &clarice, &mut ledger, 100),
+            factory.transaction(&bob, &clarice, &mut ledger, 100),
+        ];
+        let block = ledger.forge_block_with_fragments(&stake_pool, valid.clone());
+        let sequential = apply(&ledger, &block, Prevalidation::Sequential).unwrap();
+        let parallel = apply(&ledger, &block, Prevalidation::Parallel).unwrap();
+        assert!(parallel == sequential);
+        assert!(parallel.signature_cache().is_none());
+
+        // the second transaction of Bob is signed with a spending counter
+        // which is only valid at the start of the block
+        let stale = factory.transaction(&bob, &clarice, &mut ledger, 50);
+        let block0_only = Fragment::Initial(ConfigParams::new());
+        for fragments in vec![
+            vec![valid[1].clone(), stale.clone(), block0_only.clone()],
+            vec![valid[0].clone(), block0_only, stale],
+        ] {
+            let block = ledger.forge_block_with_fragments(&stake_pool, fragments);
+            assert_eq!(
+                apply(&ledger, &block, Prevalidation::Parallel).unwrap_err(),
+                apply(&ledger, &block, Prevalidation::Sequential).unwrap_err()
+            );
+        }
+    }
+}
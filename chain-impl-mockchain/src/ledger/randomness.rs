@@ -0,0 +1,224 @@
+//! Randomness beacon from the consensus nonce.
+//!
+//! Under Genesis-Praos, the VRF output of every block is hashed into the
+//! consensus nonce of the ledger, and the leadership of an epoch is drawn
+//! with the nonce reached at the end of the previous epoch. This nonce is
+//! not known before that epoch and cannot be biased by a single block
+//! producer short of giving up its slot, so it can serve as a source of
+//! randomness for applications like lotteries or committee sortition.
+//!
+//! A ledger set up [`Ledger::with_randomness_beacon`] records the nonce of
+//! each epoch as it starts, for a bounded number of epochs. An epoch without
+//! any block is recorded with the nonce of the following epoch: no VRF output
+//! is added to the nonce in the meantime.
+//! [`RandomnessBeacon::derive`] should be used rather than the raw nonce, so
+//! that the values consumed by different applications, and by the
+//! leadership, are independent of each other.
+
+use super::Ledger;
+use crate::chaineval::PraosNonce;
+use crate::date::{BlockDate, Epoch};
+use crate::key::Hash;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+const DOMAIN: &[u8] = b"epoch randomness";
+
+/// The nonces of the latest epochs, see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomnessBeacon {
+    history: usize,
+    epochs: VecDeque<(Epoch, PraosNonce)>,
+}
+
+impl RandomnessBeacon {
+    /// Keep the nonces of the `history` latest epochs, which must not be zero
+    pub fn new(history: usize) -> Self {
+        assert!(
+            history > 0,
+            "the randomness beacon keeps at least one epoch"
+        );
+        RandomnessBeacon {
+            history,
+            epochs: VecDeque::with_capacity(history),
+        }
+    }
+
+    /// Record the nonce of consecutive epochs, the leadership of all of
+    /// them being drawn with the same nonce
+    pub(super) fn record(&mut self, epochs: RangeInclusive<Epoch>, nonce: &PraosNonce) {
+        let (first, last) = epochs.into_inner();
+        // only the latest epochs are kept, even if there are many of them
+        let oldest = last.saturating_sub(u32::try_from(self.history - 1).unwrap_or(u32::MAX));
+        for epoch in first.max(oldest)..=last {
+            if self.epochs.len() == self.history {
+                self.epochs.pop_front();
+            }
+            self.epochs.push_back((epoch, nonce.clone()));
+        }
+    }
+
+    /// The nonce the leadership of the epoch was drawn with, if it is
+    /// still recorded
+    pub fn nonce(&self, epoch: Epoch) -> Option<&PraosNonce> {
+        self.epochs
+            .iter()
+            .find(|(e, _)| *e == epoch)
+            .map(|(_, nonce)| nonce)
+    }
+
+    /// The last recorded epoch and its nonce
+    pub fn latest(&self) -> Option<(Epoch, &PraosNonce)> {
+        self.epochs.back().map(|(epoch, nonce)| (*epoch, nonce))
+    }
+
+    /// The recorded epochs and their nonces, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (Epoch, &PraosNonce)> {
+        self.epochs.iter().map(|(epoch, nonce)| (*epoch, nonce))
+    }
+
+    /// Randomness of the epoch for the application identified by `domain`,
+    /// the hash of the nonce of the epoch with the epoch and the domain.
+    pub fn derive(&self, epoch: Epoch, domain: &[u8]) -> Option<Hash> {
+        self.nonce(epoch)
+            .map(|nonce| derive_randomness(nonce, epoch, domain))
+    }
+}
+
+fn derive_randomness(nonce: &PraosNonce, epoch: Epoch, domain: &[u8]) -> Hash {
+    let mut bytes = Vec::with_capacity(DOMAIN.len() + 4 + 8 + domain.len() + 32);
+    bytes.extend_from_slice(DOMAIN);
+    bytes.extend_from_slice(&epoch.to_be_bytes());
+    bytes.extend_from_slice(&(domain.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(domain);
+    bytes.extend_from_slice(nonce.as_ref());
+    Hash::hash_bytes(&bytes)
+}
+
+impl Ledger {
+    /// The consensus nonce accumulated so far in the current epoch, which
+    /// becomes the nonce of the next epoch
+    pub fn consensus_nonce(&self) -> &PraosNonce {
+        &self.settings.consensus_nonce
+    }
+
+    /// Record the nonce of every epoch starting from now on in a
+    /// [`RandomnessBeacon`] keeping the `history` latest epochs. The nonce
+    /// of the current epoch is not known anymore and is not recorded. The
+    /// beacon is not part of the serialized ledger, like the signature cache.
+    pub fn with_randomness_beacon(mut self, history: usize) -> Self {
+        self.randomness = Some(RandomnessBeacon::new(history));
+        self
+    }
+
+    pub fn randomness_beacon(&self) -> Option<&RandomnessBeacon> {
+        self.randomness.as_ref()
+    }
+
+    /// Record the nonce of the epoch of `block_date` if the beacon is
+    /// enabled, to be called on the first block of the epoch before its own
+    /// nonce is accumulated. The epochs without block since the previous
+    /// block are recorded too.
+    pub(super) fn record_epoch_randomness(&mut self, block_date: BlockDate) {
+        let nonce = &self.settings.consensus_nonce;
+        let first = self.date.missed_epochs(block_date).start;
+        if let Some(randomness) = self.randomness.as_mut() {
+            randomness.record(first..=block_date.epoch, nonce);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        certificate::PoolId,
+        chaineval::ConsensusEvalContext,
+        date::BlockDate,
+        testing::{ConfigBuilder, LedgerBuilder},
+    };
+
+    fn praos(nonce: u8) -> ConsensusEvalContext {
+        ConsensusEvalContext::Praos {
+            nonce: PraosNonce::from_output_array([nonce; 32]),
+            pool_creator: PoolId::from([0u8; 32]),
+        }
+    }
+
+    #[test]
+    fn beacon_keeps_the_nonce_of_the_latest_epochs() {
+        let testledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .build()
+            .unwrap();
+        let mut ledger = testledger.ledger.with_randomness_beacon(2);
+        let parameters = ledger.get_ledger_parameters();
+        let mut nonces = Vec::new();
+
+        for epoch in 1..=3 {
+            for slot_id in 0..2 {
+                let block_ledger = ledger
+                    .begin_block(
+                        parameters.clone(),
+                        ledger.chain_length().increase(),
+                        BlockDate { epoch, slot_id },
+                    )
+                    .unwrap();
+                if slot_id == 0 {
+                    nonces.push(ledger.consensus_nonce().clone());
+                }
+                ledger = block_ledger.finish(&praos((epoch * 2 + slot_id) as u8));
+                // rewards are not distributed in this test
+                ledger.leaders_log = Default::default();
+            }
+        }
+
+        let beacon = ledger.randomness_beacon().unwrap();
+        assert_eq!(beacon.nonce(1), None);
+        assert_eq!(beacon.nonce(2), Some(&nonces[1]));
+        assert_eq!(beacon.latest(), Some((3, &nonces[2])));
+        assert_eq!(beacon.iter().count(), 2);
+        assert_ne!(nonces[1], nonces[2]);
+
+        assert_eq!(beacon.derive(2, b"lottery"), beacon.derive(2, b"lottery"));
+        assert_ne!(beacon.derive(2, b"lottery"), beacon.derive(3, b"lottery"));
+        assert_ne!(beacon.derive(3, b"lottery"), beacon.derive(3, b"committee"));
+        assert_eq!(beacon.derive(1, b"lottery"), None);
+    }
+
+    #[test]
+    fn beacon_records_the_epochs_without_block() {
+        let testledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .build()
+            .unwrap();
+        let mut ledger = testledger.ledger.with_randomness_beacon(3);
+        let parameters = ledger.get_ledger_parameters();
+
+        for (chain_date, praos_nonce) in [(1, 1), (5, 2)].iter() {
+            let block_ledger = ledger
+                .begin_block(
+                    parameters.clone(),
+                    ledger.chain_length().increase(),
+                    BlockDate {
+                        epoch: *chain_date,
+                        slot_id: 0,
+                    },
+                )
+                .unwrap();
+            ledger = block_ledger.finish(&praos(*praos_nonce));
+            ledger.leaders_log = Default::default();
+        }
+
+        // the leadership of the epochs 2 to 5 is drawn with the nonce
+        // reached at the end of the epoch 1
+        let nonce = ledger.randomness_beacon().unwrap().nonce(5).cloned();
+        let beacon = ledger.randomness_beacon().unwrap();
+        assert!(nonce.is_some());
+        assert_eq!(beacon.nonce(1), None);
+        assert_eq!(beacon.nonce(2), None);
+        assert_eq!(beacon.nonce(3).cloned(), nonce);
+        assert_eq!(beacon.nonce(4).cloned(), nonce);
+        assert_eq!(beacon.iter().count(), 3);
+        assert_ne!(beacon.derive(3, b"lottery"), beacon.derive(4, b"lottery"));
+    }
+}
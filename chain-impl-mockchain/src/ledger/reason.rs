@@ -0,0 +1,481 @@
+//! Stable numbered reasons of the fragment and block application failures.
+//!
+//! The [`Error`] of the ledger describes a failure for humans, with
+//! messages and contents which may change from one version to the next. The
+//! [`RejectionReason`] of an error is a number node APIs and wallets can
+//! match on programmatically.
+//!
+//! The codes are grouped by hundreds, by the kind of failure. A code is
+//! never reused nor renumbered: new failures get new codes, and the codes
+//! of removed failures are left unassigned.
+
+use super::Error;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Reason of the rejection of a fragment or a block, see the module
+/// documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum RejectionReason {
+    // block
+    WrongChainLength = 100,
+    NonMonotonicDate = 101,
+    InvalidContentSize = 102,
+    InvalidContentWeight = 103,
+    InvalidContentHash = 104,
+    NonCanonicalFragmentOrder = 105,
+    Block0 = 106,
+    Block0OnlyFragmentReceived = 107,
+    ExpectingInitialMessage = 108,
+    // transaction
+    TransactionMalformed = 200,
+    FeeCalculationError = 201,
+    TransactionBalanceInvalid = 202,
+    NotBalanced = 203,
+    ZeroOutput = 204,
+    TokensToNonUtxoAddress = 205,
+    TokensNotBalanced = 206,
+    TokenSupplyInvalid = 207,
+    OutputGroupInvalid = 208,
+    InvalidDiscrimination = 209,
+    ScriptsAddressNotAllowedYet = 210,
+    TransactionNotYetValid = 211,
+    TransactionExpired = 212,
+    // inputs and witnesses
+    UtxoValueNotMatching = 300,
+    Utxo = 301,
+    UtxoInvalidSignature = 302,
+    OldUtxoInvalidSignature = 303,
+    OldUtxoInvalidPublicKey = 304,
+    AccountInvalidSignature = 305,
+    MultisigInvalidSignature = 306,
+    Account = 307,
+    Multisig = 308,
+    AccountIdentifierInvalid = 309,
+    ExpectingAccountWitness = 310,
+    ExpectingUtxoWitness = 311,
+    WitnessInvalidEncoding = 312,
+    // certificates
+    CertificateInvalidSignature = 400,
+    Deposit = 401,
+    Delegation = 402,
+    OwnerStakeDelegationInvalidTransaction = 403,
+    StakeDelegationSignatureFailed = 404,
+    PoolRegistrationHasNoOwner = 405,
+    PoolRegistrationHasTooManyOwners = 406,
+    PoolRegistrationHasTooManyOperators = 407,
+    PoolRegistrationManagementThresholdZero = 408,
+    PoolRegistrationManagementThresholdAbove = 409,
+    PoolRetirementSignatureFailed = 410,
+    PoolUpdateNotAllowedYet = 411,
+    PoolUpdateSignatureFailed = 412,
+    PoolUpdateLastHashDoesntMatch = 413,
+    PoolUpdateFeesNotAllowedYet = 414,
+    HandleRegistrationSignatureFailed = 415,
+    HandleAlreadyRegistered = 416,
+    CertificateInvalidEncoding = 417,
+    // votes
+    VotePlan = 500,
+    VotePlanProofInvalidSignature = 501,
+    VotePlanProofInvalidCommittee = 502,
+    VotePlanInvalidGovernanceParameters = 503,
+    VotePlanInvalidParametersChanges = 504,
+    VoteCastInvalidTransaction = 505,
+    VoteCastBatchInvalidTransaction = 506,
+    VoteCastBatchEmpty = 507,
+    VoteCastBatchDuplicatedProposal = 508,
+    VoteTallyProofFailed = 509,
+    VoteTallyDecryptionFailed = 510,
+    VotingPowerSnapshotProofFailed = 511,
+    VoteResultCertificationProofFailed = 512,
+    VoteResultCertificationInvalid = 513,
+    VotePlanTreasuryActionAboveBudget = 514,
+    CommitteeCandidacyInvalidTransaction = 515,
+    CommitteeSortition = 516,
+    // settings and updates
+    Config = 600,
+    PraosActiveSlotsCoeffInvalid = 601,
+    Update = 602,
+    UpdateNotAllowedYet = 603,
+    // ledger state
+    IncompleteLedger = 700,
+    PotValueInvalid = 701,
+}
+
+impl RejectionReason {
+    /// All the reasons, in the order of their codes
+    pub const ALL: &'static [RejectionReason] = &[
+        RejectionReason::WrongChainLength,
+        RejectionReason::NonMonotonicDate,
+        RejectionReason::InvalidContentSize,
+        RejectionReason::InvalidContentWeight,
+        RejectionReason::InvalidContentHash,
+        RejectionReason::NonCanonicalFragmentOrder,
+        RejectionReason::Block0,
+        RejectionReason::Block0OnlyFragmentReceived,
+        RejectionReason::ExpectingInitialMessage,
+        RejectionReason::TransactionMalformed,
+        RejectionReason::FeeCalculationError,
+        RejectionReason::TransactionBalanceInvalid,
+        RejectionReason::NotBalanced,
+        RejectionReason::ZeroOutput,
+        RejectionReason::TokensToNonUtxoAddress,
+        RejectionReason::TokensNotBalanced,
+        RejectionReason::TokenSupplyInvalid,
+        RejectionReason::OutputGroupInvalid,
+        RejectionReason::InvalidDiscrimination,
+        RejectionReason::ScriptsAddressNotAllowedYet,
+        RejectionReason::TransactionNotYetValid,
+        RejectionReason::TransactionExpired,
+        RejectionReason::UtxoValueNotMatching,
+        RejectionReason::Utxo,
+        RejectionReason::UtxoInvalidSignature,
+        RejectionReason::OldUtxoInvalidSignature,
+        RejectionReason::OldUtxoInvalidPublicKey,
+        RejectionReason::AccountInvalidSignature,
+        RejectionReason::MultisigInvalidSignature,
+        RejectionReason::Account,
+        RejectionReason::Multisig,
+        RejectionReason::AccountIdentifierInvalid,
+        RejectionReason::ExpectingAccountWitness,
+        RejectionReason::ExpectingUtxoWitness,
+        RejectionReason::WitnessInvalidEncoding,
+        RejectionReason::CertificateInvalidSignature,
+        RejectionReason::Deposit,
+        RejectionReason::Delegation,
+        RejectionReason::OwnerStakeDelegationInvalidTransaction,
+        RejectionReason::StakeDelegationSignatureFailed,
+        RejectionReason::PoolRegistrationHasNoOwner,
+        RejectionReason::PoolRegistrationHasTooManyOwners,
+        RejectionReason::PoolRegistrationHasTooManyOperators,
+        RejectionReason::PoolRegistrationManagementThresholdZero,
+        RejectionReason::PoolRegistrationManagementThresholdAbove,
+        RejectionReason::PoolRetirementSignatureFailed,
+        RejectionReason::PoolUpdateNotAllowedYet,
+        RejectionReason::PoolUpdateSignatureFailed,
+        RejectionReason::PoolUpdateLastHashDoesntMatch,
+        RejectionReason::PoolUpdateFeesNotAllowedYet,
+        RejectionReason::HandleRegistrationSignatureFailed,
+        RejectionReason::HandleAlreadyRegistered,
+        RejectionReason::CertificateInvalidEncoding,
+        RejectionReason::VotePlan,
+        RejectionReason::VotePlanProofInvalidSignature,
+        RejectionReason::VotePlanProofInvalidCommittee,
+        RejectionReason::VotePlanInvalidGovernanceParameters,
+        RejectionReason::VotePlanInvalidParametersChanges,
+        RejectionReason::VoteCastInvalidTransaction,
+        RejectionReason::VoteCastBatchInvalidTransaction,
+        RejectionReason::VoteCastBatchEmpty,
+        RejectionReason::VoteCastBatchDuplicatedProposal,
+        RejectionReason::VoteTallyProofFailed,
+        RejectionReason::VoteTallyDecryptionFailed,
+        RejectionReason::VotingPowerSnapshotProofFailed,
+        RejectionReason::VoteResultCertificationProofFailed,
+        RejectionReason::VoteResultCertificationInvalid,
+        RejectionReason::VotePlanTreasuryActionAboveBudget,
+        RejectionReason::CommitteeCandidacyInvalidTransaction,
+        RejectionReason::CommitteeSortition,
+        RejectionReason::Config,
+        RejectionReason::PraosActiveSlotsCoeffInvalid,
+        RejectionReason::Update,
+        RejectionReason::UpdateNotAllowedYet,
+        RejectionReason::IncompleteLedger,
+        RejectionReason::PotValueInvalid,
+    ];
+
+    /// The stable code of the reason
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// A short description of the reason, stable as well
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RejectionReason::WrongChainLength => "wrong chain length",
+            RejectionReason::NonMonotonicDate => "non monotonic block date",
+            RejectionReason::InvalidContentSize => "block content too large",
+            RejectionReason::InvalidContentWeight => "block content too heavy",
+            RejectionReason::InvalidContentHash => "block content hash mismatch",
+            RejectionReason::NonCanonicalFragmentOrder => "fragments not in canonical order",
+            RejectionReason::Block0 => "invalid block0",
+            RejectionReason::Block0OnlyFragmentReceived => "block0 only fragment",
+            RejectionReason::ExpectingInitialMessage => "expected an initial fragment",
+            RejectionReason::TransactionMalformed => "malformed transaction",
+            RejectionReason::FeeCalculationError => "fee calculation error",
+            RejectionReason::TransactionBalanceInvalid => "invalid transaction balance",
+            RejectionReason::NotBalanced => "inputs and outputs not balanced",
+            RejectionReason::ZeroOutput => "empty output",
+            RejectionReason::TokensToNonUtxoAddress => "tokens sent to a non UTxO address",
+            RejectionReason::TokensNotBalanced => "tokens not balanced",
+            RejectionReason::TokenSupplyInvalid => "invalid token supply",
+            RejectionReason::OutputGroupInvalid => "invalid output group",
+            RejectionReason::InvalidDiscrimination => "invalid address discrimination",
+            RejectionReason::ScriptsAddressNotAllowedYet => "script addresses not allowed",
+            RejectionReason::TransactionNotYetValid => "transaction not yet valid",
+            RejectionReason::TransactionExpired => "transaction expired",
+            RejectionReason::UtxoValueNotMatching => "UTxO value mismatch",
+            RejectionReason::Utxo => "invalid UTxO",
+            RejectionReason::UtxoInvalidSignature => "invalid UTxO signature",
+            RejectionReason::OldUtxoInvalidSignature => "invalid legacy UTxO signature",
+            RejectionReason::OldUtxoInvalidPublicKey => "invalid legacy UTxO public key",
+            RejectionReason::AccountInvalidSignature => "invalid account signature",
+            RejectionReason::MultisigInvalidSignature => "invalid multisig signature",
+            RejectionReason::Account => "invalid account",
+            RejectionReason::Multisig => "invalid multisig account",
+            RejectionReason::AccountIdentifierInvalid => "invalid account identifier",
+            RejectionReason::ExpectingAccountWitness => "expected an account witness",
+            RejectionReason::ExpectingUtxoWitness => "expected a UTxO witness",
+            RejectionReason::WitnessInvalidEncoding => "non canonical witness encoding",
+            RejectionReason::CertificateInvalidSignature => "invalid certificate signature",
+            RejectionReason::Deposit => "invalid certificate deposit",
+            RejectionReason::Delegation => "invalid delegation",
+            RejectionReason::OwnerStakeDelegationInvalidTransaction => {
+                "invalid owner stake delegation transaction"
+            }
+            RejectionReason::StakeDelegationSignatureFailed => "invalid stake delegation signature",
+            RejectionReason::PoolRegistrationHasNoOwner => "pool registration without owner",
+            RejectionReason::PoolRegistrationHasTooManyOwners => {
+                "pool registration with too many owners"
+            }
+            RejectionReason::PoolRegistrationHasTooManyOperators => {
+                "pool registration with too many operators"
+            }
+            RejectionReason::PoolRegistrationManagementThresholdZero => {
+                "pool management threshold is zero"
+            }
+            RejectionReason::PoolRegistrationManagementThresholdAbove => {
+                "pool management threshold above owners"
+            }
+            RejectionReason::PoolRetirementSignatureFailed => "invalid pool retirement signature",
+            RejectionReason::PoolUpdateNotAllowedYet => "pool update not allowed",
+            RejectionReason::PoolUpdateSignatureFailed => "invalid pool update signature",
+            RejectionReason::PoolUpdateLastHashDoesntMatch => {
+                "pool update registration hash mismatch"
+            }
+            RejectionReason::PoolUpdateFeesNotAllowedYet => "pool fees update not allowed",
+            RejectionReason::HandleRegistrationSignatureFailed => {
+                "invalid handle registration signature"
+            }
+            RejectionReason::HandleAlreadyRegistered => "handle already registered",
+            RejectionReason::CertificateInvalidEncoding => "non canonical certificate encoding",
+            RejectionReason::VotePlan => "voting error",
+            RejectionReason::VotePlanProofInvalidSignature => "invalid vote plan signature",
+            RejectionReason::VotePlanProofInvalidCommittee => {
+                "vote plan signer not in the committee"
+            }
+            RejectionReason::VotePlanInvalidGovernanceParameters => {
+                "vote plan fails the governance criteria"
+            }
+            RejectionReason::VotePlanInvalidParametersChanges => {
+                "vote plan with invalid parameters changes"
+            }
+            RejectionReason::VoteCastInvalidTransaction => "invalid vote cast transaction",
+            RejectionReason::VoteCastBatchInvalidTransaction => {
+                "invalid vote cast batch transaction"
+            }
+            RejectionReason::VoteCastBatchEmpty => "empty vote cast batch",
+            RejectionReason::VoteCastBatchDuplicatedProposal => {
+                "duplicated proposal in vote cast batch"
+            }
+            RejectionReason::VoteTallyProofFailed => "invalid vote tally proof",
+            RejectionReason::VoteTallyDecryptionFailed => "vote tally decryption failed",
+            RejectionReason::VotingPowerSnapshotProofFailed => {
+                "invalid voting power snapshot signature"
+            }
+            RejectionReason::VoteResultCertificationProofFailed => {
+                "invalid vote result certification signature"
+            }
+            RejectionReason::VoteResultCertificationInvalid => "invalid vote result certification",
+            RejectionReason::VotePlanTreasuryActionAboveBudget => {
+                "vote plan treasury action above the budget"
+            }
+            RejectionReason::CommitteeCandidacyInvalidTransaction => {
+                "invalid committee candidacy transaction"
+            }
+            RejectionReason::CommitteeSortition => "invalid committee candidacy",
+            RejectionReason::Config => "invalid settings",
+            RejectionReason::PraosActiveSlotsCoeffInvalid => "invalid active slots coefficient",
+            RejectionReason::Update => "invalid update",
+            RejectionReason::UpdateNotAllowedYet => "update not allowed",
+            RejectionReason::IncompleteLedger => "incomplete ledger",
+            RejectionReason::PotValueInvalid => "invalid pot value",
+        }
+    }
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.as_str(), self.code())
+    }
+}
+
+/// The code does not match any reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("unknown rejection reason code {0}")]
+pub struct UnknownRejectionReason(pub u16);
+
+impl TryFrom<u16> for RejectionReason {
+    type Error = UnknownRejectionReason;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        Self::ALL
+            .binary_search_by_key(&code, |reason| reason.code())
+            .map(|index| Self::ALL[index])
+            .map_err(|_| UnknownRejectionReason(code))
+    }
+}
+
+impl From<RejectionReason> for u16 {
+    fn from(reason: RejectionReason) -> u16 {
+        reason.code()
+    }
+}
+
+impl Error {
+    /// The stable reason of the failure
+    pub fn reason(&self) -> RejectionReason {
+        match self {
+            Error::WrongChainLength { .. } => RejectionReason::WrongChainLength,
+            Error::NonMonotonicDate { .. } => RejectionReason::NonMonotonicDate,
+            Error::InvalidContentSize { .. } => RejectionReason::InvalidContentSize,
+            Error::InvalidContentWeight { .. } => RejectionReason::InvalidContentWeight,
+            Error::InvalidContentHash { .. } => RejectionReason::InvalidContentHash,
+            Error::NonCanonicalFragmentOrder { .. } => RejectionReason::NonCanonicalFragmentOrder,
+            Error::Block0(_) => RejectionReason::Block0,
+            Error::Block0OnlyFragmentReceived => RejectionReason::Block0OnlyFragmentReceived,
+            Error::ExpectingInitialMessage => RejectionReason::ExpectingInitialMessage,
+            Error::TransactionMalformed(_) => RejectionReason::TransactionMalformed,
+            Error::FeeCalculationError(_) => RejectionReason::FeeCalculationError,
+            Error::TransactionBalanceInvalid(_) => RejectionReason::TransactionBalanceInvalid,
+            Error::NotBalanced { .. } => RejectionReason::NotBalanced,
+            Error::ZeroOutput { .. } => RejectionReason::ZeroOutput,
+            Error::TokensToNonUtxoAddress { .. } => RejectionReason::TokensToNonUtxoAddress,
+            Error::TokensNotBalanced { .. } => RejectionReason::TokensNotBalanced,
+            Error::TokenSupplyInvalid { .. } => RejectionReason::TokenSupplyInvalid,
+            Error::OutputGroupInvalid { .. } => RejectionReason::OutputGroupInvalid,
+            Error::InvalidDiscrimination => RejectionReason::InvalidDiscrimination,
+            Error::ScriptsAddressNotAllowedYet => RejectionReason::ScriptsAddressNotAllowedYet,
+            Error::TransactionNotYetValid { .. } => RejectionReason::TransactionNotYetValid,
+            Error::TransactionExpired { .. } => RejectionReason::TransactionExpired,
+            Error::UtxoValueNotMatching { .. } => RejectionReason::UtxoValueNotMatching,
+            Error::UtxoError(_) => RejectionReason::Utxo,
+            Error::UtxoInvalidSignature { .. } => RejectionReason::UtxoInvalidSignature,
+            Error::OldUtxoInvalidSignature { .. } => RejectionReason::OldUtxoInvalidSignature,
+            Error::OldUtxoInvalidPublicKey { .. } => RejectionReason::OldUtxoInvalidPublicKey,
+            Error::AccountInvalidSignature { .. } => RejectionReason::AccountInvalidSignature,
+            Error::MultisigInvalidSignature { .. } => RejectionReason::MultisigInvalidSignature,
+            Error::Account(_) => RejectionReason::Account,
+            Error::Multisig(_) => RejectionReason::Multisig,
+            Error::AccountIdentifierInvalid => RejectionReason::AccountIdentifierInvalid,
+            Error::ExpectingAccountWitness => RejectionReason::ExpectingAccountWitness,
+            Error::ExpectingUtxoWitness => RejectionReason::ExpectingUtxoWitness,
+            Error::WitnessInvalidEncoding { .. } => RejectionReason::WitnessInvalidEncoding,
+            Error::CertificateInvalidSignature => RejectionReason::CertificateInvalidSignature,
+            Error::Deposit(_) => RejectionReason::Deposit,
+            Error::Delegation(_) => RejectionReason::Delegation,
+            Error::OwnerStakeDelegationInvalidTransaction => {
+                RejectionReason::OwnerStakeDelegationInvalidTransaction
+            }
+            Error::StakeDelegationSignatureFailed => {
+                RejectionReason::StakeDelegationSignatureFailed
+            }
+            Error::PoolRegistrationHasNoOwner => RejectionReason::PoolRegistrationHasNoOwner,
+            Error::PoolRegistrationHasTooManyOwners => {
+                RejectionReason::PoolRegistrationHasTooManyOwners
+            }
+            Error::PoolRegistrationHasTooManyOperators => {
+                RejectionReason::PoolRegistrationHasTooManyOperators
+            }
+            Error::PoolRegistrationManagementThresholdZero => {
+                RejectionReason::PoolRegistrationManagementThresholdZero
+            }
+            Error::PoolRegistrationManagementThresholdAbove => {
+                RejectionReason::PoolRegistrationManagementThresholdAbove
+            }
+            Error::PoolRetirementSignatureFailed => RejectionReason::PoolRetirementSignatureFailed,
+            Error::PoolUpdateNotAllowedYet => RejectionReason::PoolUpdateNotAllowedYet,
+            Error::PoolUpdateSignatureFailed => RejectionReason::PoolUpdateSignatureFailed,
+            Error::PoolUpdateLastHashDoesntMatch => RejectionReason::PoolUpdateLastHashDoesntMatch,
+            Error::PoolUpdateFeesNotAllowedYet => RejectionReason::PoolUpdateFeesNotAllowedYet,
+            Error::HandleRegistrationSignatureFailed => {
+                RejectionReason::HandleRegistrationSignatureFailed
+            }
+            Error::HandleAlreadyRegistered { .. } => RejectionReason::HandleAlreadyRegistered,
+            Error::CertificateInvalidEncoding => RejectionReason::CertificateInvalidEncoding,
+            Error::VotePlan(_) => RejectionReason::VotePlan,
+            Error::VotePlanProofInvalidSignature => RejectionReason::VotePlanProofInvalidSignature,
+            Error::VotePlanProofInvalidCommittee => RejectionReason::VotePlanProofInvalidCommittee,
+            Error::VotePlanInvalidGovernanceParameters => {
+                RejectionReason::VotePlanInvalidGovernanceParameters
+            }
+            Error::VotePlanInvalidParametersChanges(_) => {
+                RejectionReason::VotePlanInvalidParametersChanges
+            }
+            Error::VoteCastInvalidTransaction => RejectionReason::VoteCastInvalidTransaction,
+            Error::VoteCastBatchInvalidTransaction => {
+                RejectionReason::VoteCastBatchInvalidTransaction
+            }
+            Error::VoteCastBatchEmpty => RejectionReason::VoteCastBatchEmpty,
+            Error::VoteCastBatchDuplicatedProposal { .. } => {
+                RejectionReason::VoteCastBatchDuplicatedProposal
+            }
+            Error::VoteTallyProofFailed => RejectionReason::VoteTallyProofFailed,
+            Error::VoteTallyDecryptionFailed => RejectionReason::VoteTallyDecryptionFailed,
+            Error::VotingPowerSnapshotProofFailed => {
+                RejectionReason::VotingPowerSnapshotProofFailed
+            }
+            Error::VoteResultCertificationProofFailed => {
+                RejectionReason::VoteResultCertificationProofFailed
+            }
+            Error::VoteResultCertificationInvalid(_) => {
+                RejectionReason::VoteResultCertificationInvalid
+            }
+            Error::VotePlanTreasuryActionAboveBudget { .. } => {
+                RejectionReason::VotePlanTreasuryActionAboveBudget
+            }
+            Error::CommitteeCandidacyInvalidTransaction => {
+                RejectionReason::CommitteeCandidacyInvalidTransaction
+            }
+            Error::CommitteeSortition(_) => RejectionReason::CommitteeSortition,
+            Error::Config(_) => RejectionReason::Config,
+            Error::PraosActiveSlotsCoeffInvalid { .. } => {
+                RejectionReason::PraosActiveSlotsCoeffInvalid
+            }
+            Error::Update(_) => RejectionReason::Update,
+            Error::UpdateNotAllowedYet => RejectionReason::UpdateNotAllowedYet,
+            Error::IncompleteLedger => RejectionReason::IncompleteLedger,
+            Error::PotValueInvalid { .. } => RejectionReason::PotValueInvalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_sorted_and_round_trip() {
+        for pair in RejectionReason::ALL.windows(2) {
+            assert!(pair[0].code() < pair[1].code());
+        }
+        for reason in RejectionReason::ALL {
+            assert_eq!(RejectionReason::try_from(reason.code()), Ok(*reason));
+        }
+        assert_eq!(RejectionReason::try_from(0), Err(UnknownRejectionReason(0)));
+    }
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(Error::IncompleteLedger.reason().code(), 700);
+        assert_eq!(Error::VoteTallyProofFailed.reason().code(), 509);
+        assert_eq!(
+            Error::NonCanonicalFragmentOrder { index: 1 }.reason(),
+            RejectionReason::NonCanonicalFragmentOrder
+        );
+        assert_eq!(
+            RejectionReason::NotBalanced.to_string(),
+            "inputs and outputs not balanced (203)"
+        );
+    }
+}
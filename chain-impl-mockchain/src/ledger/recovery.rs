@@ -39,27 +39,34 @@ use super::pots;
 use super::{Entry, EntryOwned};
 use crate::account::AccountAlg;
 use crate::accounting::account::{
-    AccountState, DelegationRatio, DelegationType, LastRewards, SpendingCounter,
+    AccountState, DelegationRatio, DelegationType, LastRewards, PendingDelegation, SpendingCounter,
 };
-use crate::certificate::{PoolId, PoolRegistration, Proposal, Proposals, VoteAction, VotePlan};
+use crate::certificate::{
+    Handle, PoolId, PoolRegistration, Proposal, Proposals, VoteAction, VotePlan, VotePlanId,
+};
+use crate::chaineval::PraosNonce;
 use crate::config::ConfigParam;
 use crate::date::BlockDate;
 use crate::fragment::FragmentId;
 use crate::header::{ChainLength, HeaderId};
-use crate::key::serialize_public_key;
-use crate::ledger::{Globals, Ledger, LedgerStaticParameters};
+use crate::key::{serialize_public_key, Hash};
+use crate::ledger::{
+    Deposit, DepositOwner, Globals, HandleRecord, Ledger, LedgerStaticParameters,
+    SettingProvenance, SettingSource,
+};
 use crate::legacy;
 use crate::multisig::{DeclElement, Declaration};
 use crate::stake::{PoolLastRewards, PoolState};
+use crate::tokens::TokenBundle;
 use crate::transaction::Output;
 use crate::update::{UpdateProposal, UpdateProposalId, UpdateProposalState, UpdateVoterId};
 use crate::value::Value;
-use crate::vote;
+use crate::vote::{self, Candidacies, Candidate, CommitteeId};
 use crate::{config, key, multisig, utxo};
 use chain_addr::{Address, Discrimination};
 use chain_core::mempack::{ReadBuf, Readable};
 use chain_crypto::digest::{DigestAlg, DigestOf};
-use chain_crypto::AsymmetricPublicKey;
+use chain_crypto::{AsymmetricPublicKey, Curve25519_2HashDH, PublicKey};
 use chain_ser::deser::{Deserialize, Serialize};
 use chain_ser::packer::Codec;
 use chain_time::era::{pack_time_era, unpack_time_era};
@@ -71,7 +78,9 @@ use std::sync::Arc;
 #[cfg(test)]
 use crate::{
     chaintypes::ConsensusVersion,
-    fee::{LinearFee, PerCertificateFee, PerVoteCertificateFee},
+    fee::{
+        FeeDiscount, GovernanceFeeDiscount, LinearFee, PerCertificateFee, PerVoteCertificateFee,
+    },
     fragment::ConfigParams,
     key::BftLeaderId,
 };
@@ -168,6 +177,8 @@ fn pack_account_state<W: std::io::Write>(
     pack_delegation_type(&account_state.delegation, codec)?;
     codec.put_u64(account_state.value.0)?;
     pack_last_rewards(&account_state.last_rewards, codec)?;
+    pack_pending_delegation(&account_state.pending_delegation, codec)?;
+    pack_last_activity(account_state.last_activity, codec)?;
     Ok(())
 }
 
@@ -178,11 +189,15 @@ fn unpack_account_state<R: std::io::BufRead>(
     let delegation = unpack_delegation_type(codec)?;
     let value = codec.get_u64()?;
     let last_rewards = unpack_last_rewards(codec)?;
+    let pending_delegation = unpack_pending_delegation(codec)?;
+    let last_activity = unpack_last_activity(codec)?;
     Ok(AccountState {
         counter: SpendingCounter(counter),
         delegation,
+        pending_delegation,
         value: Value(value),
         last_rewards,
+        last_activity,
         extra: (),
     })
 }
@@ -260,6 +275,70 @@ fn unpack_delegation_type<R: std::io::BufRead>(
     }
 }
 
+fn pack_pending_delegation<W: std::io::Write>(
+    pending_delegation: &Option<PendingDelegation>,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    match pending_delegation {
+        None => {
+            codec.put_u8(0)?;
+        }
+        Some(pending) => {
+            codec.put_u8(1)?;
+            pack_delegation_type(&pending.delegation, codec)?;
+            codec.put_u32(pending.activation)?;
+        }
+    }
+    Ok(())
+}
+
+fn unpack_pending_delegation<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<Option<PendingDelegation>, std::io::Error> {
+    match codec.get_u8()? {
+        0 => Ok(None),
+        1 => {
+            let delegation = unpack_delegation_type(codec)?;
+            let activation = codec.get_u32()?;
+            Ok(Some(PendingDelegation {
+                delegation,
+                activation,
+            }))
+        }
+        code => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid PendingDelegation type code {}", code),
+        )),
+    }
+}
+
+fn pack_last_activity<W: std::io::Write>(
+    last_activity: Option<BlockDate>,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    match last_activity {
+        None => codec.put_u8(0)?,
+        Some(date) => {
+            codec.put_u8(1)?;
+            pack_block_date(date, codec)?;
+        }
+    }
+    Ok(())
+}
+
+fn unpack_last_activity<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<Option<BlockDate>, std::io::Error> {
+    match codec.get_u8()? {
+        0 => Ok(None),
+        1 => Ok(Some(unpack_block_date(codec)?)),
+        code => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid last activity code {}", code),
+        )),
+    }
+}
+
 fn pack_last_rewards<W: std::io::Write>(
     last_rewards: &LastRewards,
     codec: &mut Codec<W>,
@@ -375,6 +454,8 @@ fn pack_linear_fee<W: std::io::Write>(
     codec.put_u64(linear_fee.certificate)?;
     pack_per_certificate_fee(&linear_fee.per_certificate_fees, codec)?;
     pack_per_vote_certificate_fee(&linear_fee.per_vote_certificate_fees, codec)?;
+    codec.put_u64(linear_fee.per_token)?;
+    pack_governance_fee_discount(&linear_fee.governance_fee_discount, codec)?;
     Ok(())
 }
 
@@ -387,12 +468,16 @@ fn unpack_linear_fee<R: std::io::BufRead>(
     let certificate = codec.get_u64()?;
     let per_certificate_fees = unpack_per_certificate_fee(codec)?;
     let per_vote_certificate_fees = unpack_per_vote_certificate_fee(codec)?;
+    let per_token = codec.get_u64()?;
+    let governance_fee_discount = unpack_governance_fee_discount(codec)?;
     Ok(LinearFee {
         constant,
         coefficient,
         certificate,
         per_certificate_fees,
         per_vote_certificate_fees,
+        per_token,
+        governance_fee_discount,
     })
 }
 
@@ -470,6 +555,37 @@ fn unpack_per_vote_certificate_fee<R: std::io::BufRead>(
     })
 }
 
+#[cfg(test)]
+fn pack_governance_fee_discount<W: std::io::Write>(
+    discount: &GovernanceFeeDiscount,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    codec.put_u16(discount.vote_cast.per_mille())?;
+    codec.put_u16(discount.vote_cast_batch.per_mille())?;
+    codec.put_u16(discount.vote_tally.per_mille())?;
+    Ok(())
+}
+
+#[cfg(test)]
+fn unpack_governance_fee_discount<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<GovernanceFeeDiscount, std::io::Error> {
+    let mut discount = || {
+        let per_mille = codec.get_u16()?;
+        FeeDiscount::new(per_mille).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid fee discount {}", per_mille),
+            )
+        })
+    };
+    Ok(GovernanceFeeDiscount {
+        vote_cast: discount()?,
+        vote_cast_batch: discount()?,
+        vote_tally: discount()?,
+    })
+}
+
 #[allow(dead_code)]
 #[cfg(test)]
 fn pack_config_params<W: std::io::Write>(
@@ -818,6 +934,7 @@ where
 {
     address_packer(&output.address, codec)?;
     codec.put_u64(output.value.0)?;
+    pack_token_bundle(&output.tokens, codec)?;
     Ok(())
 }
 
@@ -830,7 +947,29 @@ where
 {
     let address = address_unpacker(codec)?;
     let value = Value(codec.get_u64()?);
-    Ok(Output { address, value })
+    let tokens = unpack_token_bundle(codec)?;
+    Ok(Output {
+        address,
+        value,
+        tokens,
+    })
+}
+
+fn pack_token_bundle<W: std::io::Write>(
+    tokens: &TokenBundle,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    codec.put_bytes(&tokens.bytes())
+}
+
+fn unpack_token_bundle<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<TokenBundle, std::io::Error> {
+    let nb_tokens = codec.get_u8()?;
+    let mut bytes = vec![nb_tokens];
+    bytes.extend(codec.get_bytes(nb_tokens as usize * (32 + 8))?);
+    TokenBundle::read(&mut ReadBuf::from(&bytes[..]))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
 }
 
 fn pack_old_addr<W: std::io::Write>(
@@ -975,6 +1114,7 @@ fn pack_vote_plan<W: std::io::Write>(
     pack_payload_type(vote_plan.payload_type(), codec)?;
     pack_vote_proposals(vote_plan.proposals(), codec)?;
     pack_committee_public_keys(vote_plan.committee_public_keys(), codec)?;
+    codec.put_u32(vote_plan.enactment_delay())?;
     Ok(())
 }
 
@@ -985,6 +1125,7 @@ fn unpack_vote_plan<R: std::io::BufRead>(codec: &mut Codec<R>) -> Result<VotePla
     let payload_type = unpack_payload_type(codec)?;
     let proposals = unpack_proposals(codec)?;
     let keys = unpack_committee_public_keys(codec)?;
+    let enactment_delay = codec.get_u32()?;
     Ok(VotePlan::new(
         vote_start,
         vote_end,
@@ -992,7 +1133,283 @@ fn unpack_vote_plan<R: std::io::BufRead>(codec: &mut Codec<R>) -> Result<VotePla
         proposals,
         payload_type,
         keys,
-    ))
+    )
+    .with_enactment_delay(enactment_delay))
+}
+
+fn pack_deposit_owner<W: std::io::Write>(
+    owner: &DepositOwner,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    match owner {
+        DepositOwner::StakePool(pool_id) => {
+            codec.put_u8(0)?;
+            pack_digestof(pool_id, codec)?;
+        }
+        DepositOwner::VotePlan(vote_plan_id) => {
+            codec.put_u8(1)?;
+            pack_digestof(vote_plan_id, codec)?;
+        }
+        DepositOwner::Handle(handle) => {
+            codec.put_u8(2)?;
+            pack_handle(handle, codec)?;
+        }
+    }
+    Ok(())
+}
+
+fn unpack_deposit_owner<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<DepositOwner, std::io::Error> {
+    match codec.get_u8()? {
+        0 => Ok(DepositOwner::StakePool(unpack_digestof(codec)?)),
+        1 => Ok(DepositOwner::VotePlan(unpack_digestof(codec)?)),
+        2 => Ok(DepositOwner::Handle(unpack_handle(codec)?)),
+        code => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid deposit owner type code {}", code),
+        )),
+    }
+}
+
+fn pack_deposit<W: std::io::Write>(
+    deposit: &Deposit,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    codec.put_u64(deposit.value.0)?;
+    pack_account_identifier(&deposit.refund_to, codec)
+}
+
+fn unpack_deposit<R: std::io::BufRead>(codec: &mut Codec<R>) -> Result<Deposit, std::io::Error> {
+    let value = Value(codec.get_u64()?);
+    let refund_to = unpack_account_identifier(codec)?;
+    Ok(Deposit { value, refund_to })
+}
+
+fn pack_committee_vote_plans<W: std::io::Write>(
+    committee_id: &CommitteeId,
+    plans: &[VotePlanId],
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    codec.put_bytes(committee_id.as_ref())?;
+    codec.put_u64(plans.len() as u64)?;
+    for plan in plans {
+        pack_digestof(plan, codec)?;
+    }
+    Ok(())
+}
+
+fn unpack_committee_vote_plans<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<(CommitteeId, Vec<VotePlanId>), std::io::Error> {
+    let bytes = codec.get_bytes(CommitteeId::COMMITTEE_ID_SIZE)?;
+    let committee_id = CommitteeId::try_from(&bytes[..])
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let size = codec.get_u64()?;
+    let plans = (0..size)
+        .map(|_| unpack_digestof(codec))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((committee_id, plans))
+}
+
+fn pack_handle<W: std::io::Write>(
+    handle: &Handle,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    let bytes = handle.as_str().as_bytes();
+    codec.put_u8(bytes.len() as u8)?;
+    codec.put_bytes(bytes)
+}
+
+fn unpack_handle<R: std::io::BufRead>(codec: &mut Codec<R>) -> Result<Handle, std::io::Error> {
+    let length = codec.get_u8()? as usize;
+    let bytes = codec.get_bytes(length)?;
+    let handle = String::from_utf8(bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Handle::new(handle).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn pack_handle_record<W: std::io::Write>(
+    record: &HandleRecord,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    pack_account_identifier(&record.account, codec)?;
+    match record.expiry {
+        None => codec.put_u8(0),
+        Some(expiry) => {
+            codec.put_u8(1)?;
+            codec.put_u32(expiry)
+        }
+    }
+}
+
+fn unpack_handle_record<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<HandleRecord, std::io::Error> {
+    let account = unpack_account_identifier(codec)?;
+    let expiry = match codec.get_u8()? {
+        0 => None,
+        1 => Some(codec.get_u32()?),
+        code => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid handle expiry flag {}", code),
+            ))
+        }
+    };
+    Ok(HandleRecord { account, expiry })
+}
+
+fn pack_setting_provenance<W: std::io::Write>(
+    tag: &config::Tag,
+    provenance: &SettingProvenance,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    codec.put_u16(*tag as u16)?;
+    pack_block_date(provenance.date, codec)?;
+    codec.put_u8(match provenance.source {
+        SettingSource::Genesis => 0,
+        SettingSource::UpdateProposal => 1,
+        SettingSource::Vote => 2,
+    })
+}
+
+fn unpack_setting_provenance<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<(config::Tag, SettingProvenance), std::io::Error> {
+    let tag_u16 = codec.get_u16()?;
+    let tag = config::Tag::from_u16(tag_u16).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid config param tag {}", tag_u16),
+        )
+    })?;
+    let date = unpack_block_date(codec)?;
+    let source = match codec.get_u8()? {
+        0 => SettingSource::Genesis,
+        1 => SettingSource::UpdateProposal,
+        2 => SettingSource::Vote,
+        code => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid setting source {}", code),
+            ))
+        }
+    };
+    Ok((tag, SettingProvenance { date, source }))
+}
+
+fn pack_candidate<W: std::io::Write>(
+    candidate: &Candidate,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    codec.put_bytes(candidate.id().as_ref())?;
+    serialize_public_key(candidate.vrf_key(), &mut *codec)?;
+    match candidate.member_key() {
+        None => codec.put_u8(0)?,
+        Some(member_key) => {
+            codec.put_u8(1)?;
+            codec.put_bytes(&member_key.to_bytes())?;
+        }
+    }
+    codec.put_u64(candidate.stake().0)?;
+    match candidate.ticket() {
+        None => codec.put_u8(0),
+        Some(ticket) => {
+            codec.put_u8(1)?;
+            codec.put_bytes(ticket.as_bytes())
+        }
+    }
+}
+
+fn unpack_candidate<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<Candidate, std::io::Error> {
+    let bytes = codec.get_bytes(CommitteeId::COMMITTEE_ID_SIZE)?;
+    let id = CommitteeId::try_from(&bytes[..])
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let bytes = codec.get_bytes(Curve25519_2HashDH::PUBLIC_KEY_SIZE)?;
+    let vrf_key = PublicKey::from_binary(&bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let member_key = match codec.get_u8()? {
+        0 => None,
+        1 => {
+            let bytes = codec.get_bytes(chain_vote::MemberPublicKey::BYTES_LEN)?;
+            let member_key = chain_vote::MemberPublicKey::from_bytes(&bytes).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid member public key of a committee candidate",
+                )
+            })?;
+            Some(member_key)
+        }
+        code => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid member key flag {}", code),
+            ))
+        }
+    };
+    let stake = Value(codec.get_u64()?);
+    let ticket = match codec.get_u8()? {
+        0 => None,
+        1 => {
+            let mut ticket_bytes: [u8; 32] = [0; 32];
+            codec.read_exact(&mut ticket_bytes)?;
+            Some(Hash::from_bytes(ticket_bytes))
+        }
+        code => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid ticket flag {}", code),
+            ))
+        }
+    };
+    Ok(Candidate::restore(id, vrf_key, member_key, stake, ticket))
+}
+
+fn pack_candidacies<W: std::io::Write>(
+    candidacies: &Candidacies,
+    codec: &mut Codec<W>,
+) -> Result<(), std::io::Error> {
+    pack_block_date(candidacies.vote_start(), codec)?;
+    match candidacies.nonce() {
+        None => codec.put_u8(0)?,
+        Some(nonce) => {
+            codec.put_u8(1)?;
+            codec.put_bytes(nonce.as_ref())?;
+        }
+    }
+    codec.put_u64(candidacies.candidates().len() as u64)?;
+    for candidate in candidacies.candidates() {
+        pack_candidate(candidate, codec)?;
+    }
+    Ok(())
+}
+
+fn unpack_candidacies<R: std::io::BufRead>(
+    codec: &mut Codec<R>,
+) -> Result<Candidacies, std::io::Error> {
+    let vote_start = unpack_block_date(codec)?;
+    let nonce = match codec.get_u8()? {
+        0 => None,
+        1 => {
+            let mut nonce_bytes: [u8; 32] = [0; 32];
+            codec.read_exact(&mut nonce_bytes)?;
+            Some(PraosNonce::from_output_array(nonce_bytes))
+        }
+        code => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid draw nonce flag {}", code),
+            ))
+        }
+    };
+    let size = codec.get_u64()?;
+    let candidates = (0..size)
+        .map(|_| unpack_candidate(codec))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Candidacies::restore(vote_start, candidates, nonce))
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -1009,6 +1426,11 @@ enum EntrySerializeCode {
     StakePool = 9,
     LeaderParticipation = 10,
     VotePlan = 11,
+    Deposit = 12,
+    CommitteeVotePlans = 13,
+    Handle = 14,
+    SettingProvenance = 15,
+    CommitteeCandidacies = 16,
     SerializationEnd = 99,
 }
 
@@ -1027,6 +1449,11 @@ impl EntrySerializeCode {
             9 => Some(EntrySerializeCode::StakePool),
             10 => Some(EntrySerializeCode::LeaderParticipation),
             11 => Some(EntrySerializeCode::VotePlan),
+            12 => Some(EntrySerializeCode::Deposit),
+            13 => Some(EntrySerializeCode::CommitteeVotePlans),
+            14 => Some(EntrySerializeCode::Handle),
+            15 => Some(EntrySerializeCode::SettingProvenance),
+            16 => Some(EntrySerializeCode::CommitteeCandidacies),
             99 => Some(EntrySerializeCode::SerializationEnd),
             _ => None,
         }
@@ -1088,10 +1515,34 @@ fn pack_entry<W: std::io::Write>(
             pack_digestof(pool_id, codec)?;
             codec.put_u32(**participation)?;
         }
-        Entry::VotePlan(vote_plan) => {
+        Entry::VotePlan((id, vote_plan)) => {
             codec.put_u8(EntrySerializeCode::VotePlan as u8)?;
+            pack_digestof(id, codec)?;
             pack_vote_plan(vote_plan, codec)?;
         }
+        Entry::Deposit((owner, deposit)) => {
+            codec.put_u8(EntrySerializeCode::Deposit as u8)?;
+            pack_deposit_owner(owner, codec)?;
+            pack_deposit(deposit, codec)?;
+        }
+        Entry::CommitteeVotePlans((committee_id, plans)) => {
+            codec.put_u8(EntrySerializeCode::CommitteeVotePlans as u8)?;
+            pack_committee_vote_plans(committee_id, plans, codec)?;
+        }
+        Entry::Handle((handle, record)) => {
+            codec.put_u8(EntrySerializeCode::Handle as u8)?;
+            pack_handle(handle, codec)?;
+            pack_handle_record(record, codec)?;
+        }
+        Entry::SettingProvenance((tag, provenance)) => {
+            codec.put_u8(EntrySerializeCode::SettingProvenance as u8)?;
+            pack_setting_provenance(tag, provenance, codec)?;
+        }
+        Entry::CommitteeCandidacies((id, candidacies)) => {
+            codec.put_u8(EntrySerializeCode::CommitteeCandidacies as u8)?;
+            pack_digestof(id, codec)?;
+            pack_candidacies(candidacies, codec)?;
+        }
     }
     Ok(())
 }
@@ -1149,8 +1600,30 @@ fn unpack_entry_owned<R: std::io::BufRead>(
             Ok(EntryOwned::LeaderParticipation((pool_id, v)))
         }
         EntrySerializeCode::VotePlan => {
+            let id = unpack_digestof(codec)?;
             let vote_plan = unpack_vote_plan(codec)?;
-            Ok(EntryOwned::VotePlan(vote_plan))
+            Ok(EntryOwned::VotePlan((id, vote_plan)))
+        }
+        EntrySerializeCode::Deposit => {
+            let owner = unpack_deposit_owner(codec)?;
+            let deposit = unpack_deposit(codec)?;
+            Ok(EntryOwned::Deposit((owner, deposit)))
+        }
+        EntrySerializeCode::CommitteeVotePlans => Ok(EntryOwned::CommitteeVotePlans(
+            unpack_committee_vote_plans(codec)?,
+        )),
+        EntrySerializeCode::Handle => {
+            let handle = unpack_handle(codec)?;
+            let record = unpack_handle_record(codec)?;
+            Ok(EntryOwned::Handle((handle, record)))
+        }
+        EntrySerializeCode::SettingProvenance => Ok(EntryOwned::SettingProvenance(
+            unpack_setting_provenance(codec)?,
+        )),
+        EntrySerializeCode::CommitteeCandidacies => {
+            let id = unpack_digestof(codec)?;
+            let candidacies = unpack_candidacies(codec)?;
+            Ok(EntryOwned::CommitteeCandidacies((id, candidacies)))
         }
         EntrySerializeCode::SerializationEnd => Ok(EntryOwned::StopEntry),
     }
@@ -1209,6 +1682,7 @@ impl Deserialize for Ledger {
 pub mod test {
     use super::*;
     use crate::testing::{ConfigBuilder, LedgerBuilder, StakePoolBuilder};
+    use crate::tokens::TokenIdentifier;
     use cardano_legacy_address::Addr;
     use chain_crypto::Blake2b256;
     use quickcheck::{quickcheck, TestResult};
@@ -1321,7 +1795,10 @@ pub mod test {
 
     #[test]
     pub fn account_state_pack_unpack_bijection() -> Result<(), std::io::Error> {
-        let account_state = AccountState::new(Value(256), ());
+        let account_state = AccountState::new(Value(256), ()).set_last_activity(BlockDate {
+            epoch: 3,
+            slot_id: 7,
+        });
         let mut c: Cursor<Vec<u8>> = Cursor::new(Vec::new());
         let mut codec = Codec::new(c);
         pack_account_state(&account_state, &mut codec)?;
@@ -1375,6 +1852,103 @@ pub mod test {
         Ok(())
     }
 
+    #[test]
+    pub fn deposit_pack_unpack_bijection() -> Result<(), std::io::Error> {
+        let deposit = Deposit {
+            value: Value(100),
+            refund_to: crate::testing::TestGen::identifier(),
+        };
+        for owner in [
+            DepositOwner::StakePool(StakePoolBuilder::new().build().id()),
+            DepositOwner::VotePlan(crate::testing::VoteTestGen::vote_plan().to_id()),
+            DepositOwner::Handle(Handle::new("alice").unwrap()),
+        ]
+        .iter()
+        {
+            let mut c: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+            let mut codec = Codec::new(c);
+            pack_deposit_owner(owner, &mut codec)?;
+            pack_deposit(&deposit, &mut codec)?;
+            c = codec.into_inner();
+            c.set_position(0);
+            codec = Codec::new(c);
+            assert_eq!(owner, &unpack_deposit_owner(&mut codec)?);
+            assert_eq!(deposit, unpack_deposit(&mut codec)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn committee_vote_plans_pack_unpack_bijection() -> Result<(), std::io::Error> {
+        let committee_id = CommitteeId::from([1; CommitteeId::COMMITTEE_ID_SIZE]);
+        let plans = vec![
+            crate::testing::VoteTestGen::vote_plan().to_id(),
+            crate::testing::VoteTestGen::vote_plan().to_id(),
+        ];
+        let mut c: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+        let mut codec = Codec::new(c);
+        pack_committee_vote_plans(&committee_id, &plans, &mut codec)?;
+        c = codec.into_inner();
+        c.set_position(0);
+        codec = Codec::new(c);
+        assert_eq!(
+            (committee_id, plans),
+            unpack_committee_vote_plans(&mut codec)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn handle_record_pack_unpack_bijection() -> Result<(), std::io::Error> {
+        let handle = Handle::new("alice").unwrap();
+        for expiry in [None, Some(42)].iter() {
+            let record = HandleRecord {
+                account: crate::testing::TestGen::identifier(),
+                expiry: *expiry,
+            };
+            let mut c: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+            let mut codec = Codec::new(c);
+            pack_handle(&handle, &mut codec)?;
+            pack_handle_record(&record, &mut codec)?;
+            c = codec.into_inner();
+            c.set_position(0);
+            codec = Codec::new(c);
+            assert_eq!(handle, unpack_handle(&mut codec)?);
+            assert_eq!(record, unpack_handle_record(&mut codec)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn setting_provenance_pack_unpack_bijection() -> Result<(), std::io::Error> {
+        for source in [
+            SettingSource::Genesis,
+            SettingSource::UpdateProposal,
+            SettingSource::Vote,
+        ]
+        .iter()
+        {
+            let provenance = SettingProvenance {
+                date: BlockDate {
+                    epoch: 3,
+                    slot_id: 12,
+                },
+                source: *source,
+            };
+            let mut c: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+            let mut codec = Codec::new(c);
+            pack_setting_provenance(&config::Tag::LinearFee, &provenance, &mut codec)?;
+            c = codec.into_inner();
+            c.set_position(0);
+            codec = Codec::new(c);
+            assert_eq!(
+                (config::Tag::LinearFee, provenance),
+                unpack_setting_provenance(&mut codec)?
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     pub fn multisig_identifier_pack_unpack_bijection() -> Result<(), std::io::Error> {
         use std::io::Cursor;
@@ -1443,6 +2017,9 @@ pub mod test {
         let output: Output<()> = Output {
             address: (),
             value: Value(1000),
+            tokens: TokenBundle::new()
+                .with(TokenIdentifier::from([1; 32]), Value(10))
+                .unwrap(),
         };
 
         let mut c = std::io::Cursor::new(Vec::new());
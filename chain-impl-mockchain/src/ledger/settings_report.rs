@@ -0,0 +1,236 @@
+//! Provenance of the parameters of the ledger.
+//!
+//! For every kind of configuration parameter, the ledger keeps the date and
+//! the mechanism of its last change: the initial message of the block0, an
+//! update proposal accepted by the BFT leaders, or a parameters governance
+//! action enacted after a vote. The parameters which were never changed
+//! since the block0 have no record and are reported as coming from the
+//! genesis.
+
+use super::ledger::Ledger;
+use crate::{
+    config::{ConfigParam, Tag},
+    date::BlockDate,
+    fragment::ConfigParams,
+    setting::Settings,
+};
+use imhamt::{Hamt, HamtIter};
+use std::collections::hash_map::DefaultHasher;
+
+/// The mechanism which set a parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingSource {
+    /// the initial message of the block0
+    Genesis,
+    /// an update proposal accepted by the BFT leaders
+    UpdateProposal,
+    /// a parameters governance action of a vote plan
+    Vote,
+}
+
+/// When and how a parameter was last changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingProvenance {
+    /// the date of the block in which the change took effect
+    pub date: BlockDate,
+    pub source: SettingSource,
+}
+
+impl SettingProvenance {
+    pub fn genesis() -> Self {
+        SettingProvenance {
+            date: BlockDate::first(),
+            source: SettingSource::Genesis,
+        }
+    }
+}
+
+/// An active parameter of the ledger with its provenance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingReportEntry {
+    pub param: ConfigParam,
+    pub provenance: SettingProvenance,
+}
+
+/// The last change of every kind of parameter changed since the block0
+#[derive(Clone, PartialEq, Eq)]
+pub struct SettingsProvenance {
+    by_tag: Hamt<DefaultHasher, Tag, SettingProvenance>,
+}
+
+impl Default for SettingsProvenance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn params_with_tag(params: &ConfigParams, tag: Tag) -> Vec<&ConfigParam> {
+    params
+        .iter()
+        .filter(|param| Tag::from(*param) == tag)
+        .collect()
+}
+
+impl SettingsProvenance {
+    pub fn new() -> Self {
+        SettingsProvenance {
+            by_tag: Hamt::new(),
+        }
+    }
+
+    /// The last change of the parameters of the given kind
+    pub fn get(&self, tag: Tag) -> SettingProvenance {
+        self.by_tag
+            .lookup(&tag)
+            .copied()
+            .unwrap_or_else(SettingProvenance::genesis)
+    }
+
+    pub fn set(&self, tag: Tag, provenance: SettingProvenance) -> Self {
+        let by_tag = self
+            .by_tag
+            .insert_or_update_simple(tag, provenance, |_| Some(provenance));
+        SettingsProvenance { by_tag }
+    }
+
+    /// Record the given provenance for every kind of parameter which differs
+    /// between the two settings
+    pub fn record_changes(
+        &self,
+        old: &Settings,
+        new: &Settings,
+        provenance: SettingProvenance,
+    ) -> Self {
+        if old == new {
+            return self.clone();
+        }
+        let old = old.to_config_params();
+        let new = new.to_config_params();
+        let mut changed = Vec::new();
+        for param in old.iter().chain(new.iter()) {
+            let tag = Tag::from(param);
+            if !changed.contains(&tag) && params_with_tag(&old, tag) != params_with_tag(&new, tag) {
+                changed.push(tag);
+            }
+        }
+        changed
+            .into_iter()
+            .fold(self.clone(), |recorded, tag| recorded.set(tag, provenance))
+    }
+
+    pub fn iter(&self) -> HamtIter<'_, Tag, SettingProvenance> {
+        self.by_tag.iter()
+    }
+}
+
+impl std::fmt::Debug for SettingsProvenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.by_tag.iter()).finish()
+    }
+}
+
+impl Ledger {
+    /// All the active parameters of the ledger, with the date and the
+    /// mechanism of their last change
+    pub fn settings_report(&self) -> Vec<SettingReportEntry> {
+        self.settings
+            .to_config_params()
+            .iter()
+            .map(|param| SettingReportEntry {
+                param: param.clone(),
+                provenance: self.settings_provenance.get(Tag::from(param)),
+            })
+            .collect()
+    }
+
+    /// Replace the settings, recording the kinds of parameters which changed
+    pub(super) fn change_settings(
+        &mut self,
+        settings: Settings,
+        date: BlockDate,
+        source: SettingSource,
+    ) {
+        self.settings_provenance = self.settings_provenance.record_changes(
+            &self.settings,
+            &settings,
+            SettingProvenance { date, source },
+        );
+        self.settings = settings;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fee::LinearFee,
+        ledger::governance::ParametersGovernanceAction,
+        testing::{ConfigBuilder, LedgerBuilder},
+        update::UpdateProposal,
+    };
+
+    fn provenance_of(ledger: &Ledger, tag: Tag) -> SettingProvenance {
+        ledger
+            .settings_report()
+            .into_iter()
+            .find(|entry| Tag::from(&entry.param) == tag)
+            .unwrap()
+            .provenance
+    }
+
+    #[test]
+    fn settings_report_tracks_the_last_change() {
+        let mut ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .build()
+            .unwrap()
+            .ledger;
+        assert!(ledger
+            .settings_report()
+            .iter()
+            .all(|entry| entry.provenance == SettingProvenance::genesis()));
+
+        let update_date = BlockDate {
+            epoch: 2,
+            slot_id: 0,
+        };
+        ledger.date = update_date;
+        let mut update = UpdateProposal::new();
+        update
+            .changes
+            .push(ConfigParam::LinearFee(LinearFee::new(123, 4, 5)));
+        update.changes.push(ConfigParam::ProposalExpiration(7));
+        ledger = ledger.apply_update(&update).unwrap();
+
+        let vote_date = BlockDate {
+            epoch: 3,
+            slot_id: 0,
+        };
+        ledger.date = vote_date;
+        let mut changes = ConfigParams::new();
+        changes.push(ConfigParam::ProposalExpiration(9));
+        ledger
+            .governance
+            .parameters
+            .logs_register(ParametersGovernanceAction::UpdateParameters { changes });
+        ledger = ledger.apply_protocol_changes().unwrap();
+
+        assert_eq!(
+            provenance_of(&ledger, Tag::LinearFee),
+            SettingProvenance {
+                date: update_date,
+                source: SettingSource::UpdateProposal,
+            }
+        );
+        assert_eq!(
+            provenance_of(&ledger, Tag::ProposalExpiration),
+            SettingProvenance {
+                date: vote_date,
+                source: SettingSource::Vote,
+            }
+        );
+        assert_eq!(
+            provenance_of(&ledger, Tag::ConsensusVersion),
+            SettingProvenance::genesis()
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Shadow application of the blocks under candidate rules.
+//!
+//! A change of the rules of the ledger, typically a hard fork changing some
+//! parameters, can be tried on the real chain before it is enacted. A
+//! [`ShadowLedger`] applies every block both to the current ledger, which
+//! remains authoritative, and to a candidate ledger whose settings are
+//! changed by the candidate rules. The two applications run in parallel,
+//! and the outcomes are compared:
+//!
+//! * a block accepted under one set of rules only, or rejected for
+//!   different reasons, is a [`Divergence`] of the outcome;
+//! * a block accepted by both ledgers is a divergence of the state if the
+//!   resulting ledgers differ, the parts which differ are reported. The
+//!   settings themselves are expected to differ and are not compared.
+//!
+//! After a divergence the candidate ledger starts again from the current
+//! ledger with the candidate rules, so that each divergence is reported on
+//! the block which caused it. The candidate rules are applied on top of the
+//! settings only then: the update proposals accepted by the chain change
+//! the settings of both ledgers.
+
+use super::{Error, Ledger};
+use crate::{
+    chaineval::HeaderContentEvalContext,
+    date::BlockDate,
+    fragment::{config::ConfigParams, Contents},
+};
+
+/// One of the ledgers of a shadow application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShadowSide {
+    /// the ledger under the current rules
+    Current,
+    /// the ledger under the candidate rules
+    Candidate,
+}
+
+/// A part of the state of the ledger, see [`Divergence::State`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LedgerPart {
+    Utxos,
+    OldUtxos,
+    Accounts,
+    Multisig,
+    Delegation,
+    UpdateProposals,
+    Pots,
+    LeadersLog,
+    VotePlans,
+    CommitteeSortition,
+    Governance,
+    Deposits,
+    Tokens,
+    Handles,
+    Randomness,
+    Date,
+    Era,
+    SettingsProvenance,
+}
+
+/// A difference between the applications of a block under the current and
+/// the candidate rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// the block is rejected under the rules of one side only
+    Rejected { side: ShadowSide, error: Error },
+    /// the block is rejected under both rules, for different reasons
+    DifferentErrors { current: Error, candidate: Error },
+    /// the block is accepted under both rules, but the given parts of the
+    /// resulting ledgers differ
+    State { parts: Vec<LedgerPart> },
+}
+
+/// A divergence with the date of the block causing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowDivergence {
+    pub date: BlockDate,
+    pub divergence: Divergence,
+}
+
+/// The current ledger with its shadow under candidate rules, see the
+/// [module documentation](self)
+#[derive(Clone)]
+pub struct ShadowLedger {
+    current: Ledger,
+    candidate: Ledger,
+    rules: ConfigParams,
+    divergences: Vec<ShadowDivergence>,
+}
+
+impl ShadowLedger {
+    /// Shadow the ledger with the candidate rules, the changes of the
+    /// settings to try
+    ///
+    /// # errors
+    ///
+    /// * if the candidate rules cannot be applied to the settings
+    ///
+    pub fn new(current: Ledger, rules: ConfigParams) -> Result<Self, Error> {
+        let candidate = with_rules(&current, &rules)?;
+        Ok(ShadowLedger {
+            current,
+            candidate,
+            rules,
+            divergences: Vec::new(),
+        })
+    }
+
+    /// the authoritative ledger, under the current rules
+    pub fn current(&self) -> &Ledger {
+        &self.current
+    }
+
+    /// the ledger under the candidate rules
+    pub fn candidate(&self) -> &Ledger {
+        &self.candidate
+    }
+
+    pub fn rules(&self) -> &ConfigParams {
+        &self.rules
+    }
+
+    /// the divergences found so far, in the order of the blocks
+    pub fn divergences(&self) -> &[ShadowDivergence] {
+        &self.divergences
+    }
+
+    /// take the divergences found so far, for them to be reported
+    pub fn take_divergences(&mut self) -> Vec<ShadowDivergence> {
+        std::mem::take(&mut self.divergences)
+    }
+
+    /// Apply the block to both ledgers, each with the ledger parameters of
+    /// its own settings, see [`Ledger::apply_block`]
+    ///
+    /// The result is the one of the current ledger, which is only updated
+    /// if the block is valid under the current rules.
+    pub fn apply_block(
+        &mut self,
+        contents: &Contents,
+        metadata: &HeaderContentEvalContext,
+    ) -> Result<(), Error> {
+        self.apply_step(metadata.block_date, |ledger| {
+            ledger.apply_block(ledger.get_ledger_parameters(), contents, metadata)
+        })
+    }
+
+    /// Apply a step of the chain to both ledgers, like the distribution of
+    /// the rewards at the end of an epoch, and compare the outcomes
+    ///
+    /// The result is the one of the current ledger, which is only updated
+    /// if the step succeeds under the current rules. `date` is the date the
+    /// divergence is reported at.
+    pub fn apply_step<F>(&mut self, date: BlockDate, step: F) -> Result<(), Error>
+    where
+        F: Fn(&Ledger) -> Result<Ledger, Error> + Sync,
+    {
+        let (current, candidate) = rayon::join(|| step(&self.current), || step(&self.candidate));
+
+        let divergence = match (&current, &candidate) {
+            (Ok(current), Ok(candidate)) => {
+                let parts = diff(current, candidate);
+                if parts.is_empty() {
+                    None
+                } else {
+                    Some(Divergence::State { parts })
+                }
+            }
+            (Ok(_), Err(error)) => Some(Divergence::Rejected {
+                side: ShadowSide::Candidate,
+                error: error.clone(),
+            }),
+            (Err(error), Ok(_)) => Some(Divergence::Rejected {
+                side: ShadowSide::Current,
+                error: error.clone(),
+            }),
+            (Err(current), Err(candidate)) if current != candidate => {
+                Some(Divergence::DifferentErrors {
+                    current: current.clone(),
+                    candidate: candidate.clone(),
+                })
+            }
+            (Err(_), Err(_)) => None,
+        };
+
+        let current = match current {
+            Ok(current) => current,
+            Err(error) => {
+                // the step is not part of the chain, the candidate ledger
+                // stays as it was
+                if let Some(divergence) = divergence {
+                    self.divergences.push(ShadowDivergence { date, divergence });
+                }
+                return Err(error);
+            }
+        };
+        match divergence {
+            None => self.candidate = candidate.expect("no divergence in the outcome"),
+            Some(divergence) => {
+                self.candidate = with_rules(&current, &self.rules)?;
+                self.divergences.push(ShadowDivergence { date, divergence });
+            }
+        }
+        self.current = current;
+        Ok(())
+    }
+}
+
+fn with_rules(ledger: &Ledger, rules: &ConfigParams) -> Result<Ledger, Error> {
+    let mut candidate = ledger.clone();
+    candidate.settings = ledger.settings.apply(rules)?;
+    Ok(candidate)
+}
+
+/// the parts of the state which differ between the two ledgers
+fn diff(current: &Ledger, candidate: &Ledger) -> Vec<LedgerPart> {
+    let mut parts = Vec::new();
+    let mut compare = |part, same: bool| {
+        if !same {
+            parts.push(part);
+        }
+    };
+    compare(LedgerPart::Utxos, current.utxos == candidate.utxos);
+    compare(LedgerPart::OldUtxos, current.oldutxos == candidate.oldutxos);
+    compare(LedgerPart::Accounts, current.accounts == candidate.accounts);
+    compare(LedgerPart::Multisig, current.multisig == candidate.multisig);
+    compare(
+        LedgerPart::Delegation,
+        current.delegation == candidate.delegation,
+    );
+    compare(
+        LedgerPart::UpdateProposals,
+        current.updates == candidate.updates,
+    );
+    compare(LedgerPart::Pots, current.pots == candidate.pots);
+    compare(
+        LedgerPart::LeadersLog,
+        current.leaders_log == candidate.leaders_log,
+    );
+    compare(LedgerPart::VotePlans, current.votes == candidate.votes);
+    compare(
+        LedgerPart::CommitteeSortition,
+        current.sortition == candidate.sortition,
+    );
+    compare(
+        LedgerPart::Governance,
+        current.governance == candidate.governance,
+    );
+    compare(LedgerPart::Deposits, current.deposits == candidate.deposits);
+    compare(LedgerPart::Tokens, current.tokens == candidate.tokens);
+    compare(LedgerPart::Handles, current.handles == candidate.handles);
+    compare(
+        LedgerPart::Randomness,
+        current.randomness == candidate.randomness,
+    );
+    compare(
+        LedgerPart::Date,
+        current.date == candidate.date && current.chain_length == candidate.chain_length,
+    );
+    compare(LedgerPart::Era, current.era == candidate.era);
+    compare(
+        LedgerPart::SettingsProvenance,
+        current.settings_provenance == candidate.settings_provenance,
+    );
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ConfigParam,
+        fee::LinearFee,
+        testing::{
+            builders::TestTxBuilder,
+            data::AddressDataValue,
+            ledger::{ConfigBuilder, LedgerBuilder},
+        },
+        value::{Value, ValueError},
+    };
+    use chain_addr::Discrimination;
+
+    #[test]
+    fn candidate_rules_rejecting_a_transaction() {
+        let faucet = AddressDataValue::account(Discrimination::Test, Value(1_000));
+        let receiver = AddressDataValue::account(Discrimination::Test, Value(0));
+        let mut test_ledger =
+            LedgerBuilder::from_config(ConfigBuilder::new(0).with_fee(LinearFee::new(1, 1, 1)))
+                .faucet(&faucet)
+                .build()
+                .unwrap();
+        let fragment = TestTxBuilder::new(test_ledger.block0_hash)
+            .move_funds(&mut test_ledger, &faucet, &receiver, Value(100))
+            .get_fragment();
+
+        let mut rules = ConfigParams::new();
+        rules.push(ConfigParam::LinearFee(LinearFee::new(10, 1, 1)));
+        let mut shadow = ShadowLedger::new(test_ledger.ledger.clone(), rules).unwrap();
+        let date = test_ledger.date();
+
+        // nothing changes
+        shadow
+            .apply_step(date, |ledger| Ok(ledger.clone()))
+            .unwrap();
+        assert!(shadow.divergences().is_empty());
+
+        shadow
+            .apply_step(date, |ledger| {
+                ledger.apply_fragment(&ledger.get_ledger_parameters(), &fragment, date)
+            })
+            .unwrap();
+        let divergences = shadow.take_divergences();
+        assert_eq!(divergences.len(), 1);
+        assert!(matches!(
+            divergences[0].divergence,
+            Divergence::Rejected {
+                side: ShadowSide::Candidate,
+                ..
+            }
+        ));
+        assert!(shadow.divergences().is_empty());
+
+        // the candidate started again from the current ledger
+        let expected = with_rules(shadow.current(), shadow.rules()).unwrap();
+        assert!(shadow.candidate() == &expected);
+        assert!(
+            shadow.current()
+                == &test_ledger
+                    .ledger
+                    .apply_fragment(&test_ledger.parameters, &fragment, date)
+                    .unwrap()
+        );
+
+        // a step failing under both rules for the same reason
+        assert!(shadow
+            .apply_step(date, |_| Err(Error::PotValueInvalid {
+                error: ValueError::Overflow,
+            }))
+            .is_err());
+        assert!(shadow.divergences().is_empty());
+    }
+
+    #[test]
+    fn candidate_rules_changing_the_epoch_length() {
+        let test_ledger =
+            LedgerBuilder::from_config(ConfigBuilder::new(0).with_slots_per_epoch(10))
+                .build()
+                .unwrap();
+
+        let mut rules = ConfigParams::new();
+        rules.push(ConfigParam::SlotsPerEpoch(20));
+        let mut shadow = ShadowLedger::new(test_ledger.ledger.clone(), rules).unwrap();
+
+        // the candidate starts a new era at the next epoch
+        shadow
+            .apply_step(test_ledger.date(), |ledger| {
+                let mut ledger = ledger.clone();
+                ledger.update_era(1);
+                Ok(ledger)
+            })
+            .unwrap();
+        let divergences = shadow.take_divergences();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(
+            divergences[0].divergence,
+            Divergence::State {
+                parts: vec![LedgerPart::Era]
+            }
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Cache of the witnesses already verified.
+//!
+//! When a node switches to another fork, the fragments shared by both branches
+//! are applied again on top of a different ledger, and their witnesses verified
+//! again. A [`SignatureCache`] attached to the ledgers with
+//! [`Ledger::with_signature_cache`] remembers the witnesses that have been
+//! successfully verified, by fragment id and witness index, so that the
+//! cryptographic verification is skipped when they are applied again.
+//!
+//! A witness signs data which depends on the ledger state, like the spending
+//! counter of an account, so the cache also records a digest of the data the
+//! witness has been verified against, and a cached verification is only reused
+//! for the same data. Failed verifications are never cached.
+//!
+//! [`Ledger::with_signature_cache`]: super::Ledger::with_signature_cache
+
+use crate::fragment::FragmentId;
+use crate::key::Hash;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Verified witnesses, shared by all the ledgers the cache is attached to
+#[derive(Clone)]
+pub struct SignatureCache(Arc<Mutex<Inner>>);
+
+struct Inner {
+    capacity: usize,
+    verified: HashMap<(FragmentId, u8), Hash>,
+    // insertion order, the oldest verifications are evicted first
+    order: VecDeque<(FragmentId, u8)>,
+    hits: u64,
+}
+
+impl SignatureCache {
+    /// Create a cache remembering at most `capacity` witnesses
+    pub fn new(capacity: usize) -> Self {
+        SignatureCache(Arc::new(Mutex::new(Inner {
+            capacity,
+            verified: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+        })))
+    }
+
+    /// Number of witnesses in the cache
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().verified.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of verifications skipped thanks to the cache
+    pub fn hits(&self) -> u64 {
+        self.0.lock().unwrap().hits
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.0.lock().unwrap();
+        inner.verified.clear();
+        inner.order.clear();
+    }
+
+    fn is_verified(&self, key: &(FragmentId, u8), data: &Hash) -> bool {
+        let mut inner = self.0.lock().unwrap();
+        let verified = inner.verified.get(key) == Some(data);
+        if verified {
+            inner.hits += 1;
+        }
+        verified
+    }
+
+    fn insert(&self, key: (FragmentId, u8), data: Hash) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+        if inner.verified.insert(key, data).is_none() {
+            inner.order.push_back(key);
+            while inner.order.len() > inner.capacity {
+                let oldest = inner.order.pop_front().unwrap();
+                inner.verified.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for SignatureCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.0.lock().unwrap();
+        f.debug_struct("SignatureCache")
+            .field("capacity", &inner.capacity)
+            .field("len", &inner.verified.len())
+            .field("hits", &inner.hits)
+            .finish()
+    }
+}
+
+// the cache is not part of the state of the ledger
+impl PartialEq for SignatureCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for SignatureCache {}
+
+/// The verification of a witness, going through the cache if any
+#[derive(Clone, Copy)]
+pub(crate) struct WitnessCheck<'a> {
+    slot: Option<(&'a SignatureCache, &'a FragmentId, u8)>,
+}
+
+impl<'a> WitnessCheck<'a> {
+    pub(crate) fn new(
+        cache: Option<&'a SignatureCache>,
+        fragment_id: &'a FragmentId,
+        index: u8,
+    ) -> Self {
+        WitnessCheck {
+            slot: cache.map(|cache| (cache, fragment_id, index)),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn uncached() -> Self {
+        WitnessCheck { slot: None }
+    }
+
+    /// Run `verify`, unless the witness has already been successfully
+    /// verified against the same `data`
+    pub(crate) fn verify<F: FnOnce() -> bool>(self, data: &[u8], verify: F) -> bool {
+        match self.slot {
+            None => verify(),
+            Some((cache, fragment_id, index)) => {
+                let key = (*fragment_id, index);
+                let data = Hash::hash_bytes(data);
+                if cache.is_verified(&key, &data) {
+                    return true;
+                }
+                let verified = verify();
+                if verified {
+                    cache.insert(key, data);
+                }
+                verified
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fragment::Fragment,
+        ledger::Error,
+        testing::{
+            builders::witness_builder::make_witness, data::AddressDataValue, ConfigBuilder,
+            LedgerBuilder,
+        },
+        transaction::TxBuilder,
+        value::Value,
+    };
+    use chain_addr::Discrimination;
+
+    #[test]
+    fn fork_reapplication_skips_verification() {
+        let faucet = AddressDataValue::account(Discrimination::Test, Value(1000));
+        let mut ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .faucet(&faucet)
+            .build()
+            .unwrap();
+        let cache = SignatureCache::new(100);
+        ledger.ledger = ledger.ledger.clone().with_signature_cache(cache.clone());
+
+        let receiver = AddressDataValue::utxo(Discrimination::Test, Value(100));
+        let builder = TxBuilder::new().set_nopayload().set_ios(
+            &[faucet.make_input_with_value(None, Value(100))],
+            &[receiver.make_output()],
+        );
+        let witness = make_witness(
+            ledger.block0_hash(),
+            &faucet.clone().into(),
+            &builder.get_auth_data_for_witness().hash(),
+        );
+        let fragment: Fragment = builder
+            .set_witnesses(&[witness])
+            .set_payload_auth(&())
+            .into();
+
+        let mut fork = ledger.clone();
+        let date = ledger.date();
+        ledger.apply_fragment(&fragment, date).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.hits(), 0);
+
+        fork.apply_fragment(&fragment, date).unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(fork.ledger, ledger.ledger);
+
+        // the spending counter has changed, the cached verification does not apply
+        assert!(matches!(
+            ledger.apply_fragment(&fragment, date),
+            Err(Error::AccountInvalidSignature { .. })
+        ));
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn only_successful_verifications_are_cached() {
+        let cache = SignatureCache::new(10);
+        let fragment_id = Hash::hash_bytes(&[1]);
+        let check = WitnessCheck::new(Some(&cache), &fragment_id, 0);
+
+        assert!(!check.verify(b"data", || false));
+        assert!(cache.is_empty());
+
+        assert!(check.verify(b"data", || true));
+        assert!(check.verify(b"data", || panic!("verified again")));
+        assert_eq!(cache.hits(), 1);
+
+        // same witness checked against other data
+        assert!(!check.verify(b"other data", || false));
+        let other_index = WitnessCheck::new(Some(&cache), &fragment_id, 1);
+        assert!(!other_index.verify(b"data", || false));
+    }
+
+    #[test]
+    fn oldest_verifications_are_evicted() {
+        let cache = SignatureCache::new(2);
+        let fragment_id = Hash::hash_bytes(&[1]);
+        for index in 0..3 {
+            assert!(WitnessCheck::new(Some(&cache), &fragment_id, index).verify(b"data", || true));
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(!WitnessCheck::new(Some(&cache), &fragment_id, 0).verify(b"data", || false));
+        assert!(WitnessCheck::new(Some(&cache), &fragment_id, 2).verify(b"data", || false));
+    }
+}
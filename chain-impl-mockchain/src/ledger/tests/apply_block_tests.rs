@@ -3,6 +3,7 @@ use crate::{
     accounting::account::LedgerError::ValueError,
     chaintypes::ChainLength,
     date::BlockDate,
+    fragment::{Contents, ContentsBuilder, BASE_WEIGHT, SIGNATURE_WEIGHT},
     ledger::{ledger::Error::Account, Error as LedgerError},
     testing::{
         builders::{GenesisPraosBlockBuilder, TestTxBuilder},
@@ -208,3 +209,113 @@ pub fn apply_block_above_max_content_size() {
         ledger.apply_block(block)
     );
 }
+
+#[test]
+pub fn apply_block_above_max_content_weight() {
+    let transaction_weight = BASE_WEIGHT + SIGNATURE_WEIGHT;
+    let block_content_max_weight = 2 * transaction_weight - 1;
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(ConfigBuilder::new(0).with_block_content_max_weight(block_content_max_weight))
+        .with_initials(vec![
+            wallet("Alice").with(1_000).owns("stake_pool"),
+            wallet("Bob").with(1_000),
+        ])
+        .build()
+        .unwrap();
+    let alice = controller.wallet("Alice").unwrap();
+    let bob = controller.wallet("Bob").unwrap();
+    let stake_pool = controller.stake_pool("stake_pool").unwrap();
+    let date = BlockDate {
+        epoch: 1,
+        slot_id: 0,
+    };
+
+    let fragment_factory = FragmentFactory::from_ledger(&ledger);
+    let first = fragment_factory.transaction(&alice, &bob, &mut ledger, 10);
+    let second = fragment_factory.transaction(&bob, &alice, &mut ledger, 10);
+
+    let block = GenesisPraosBlockBuilder::new()
+        .with_date(date)
+        .with_fragments(vec![first.clone(), second])
+        .with_chain_length(ChainLength(0))
+        .with_parent_id(ledger.block0_hash)
+        .build(&stake_pool, ledger.era());
+
+    assert_err!(
+        LedgerError::InvalidContentWeight {
+            actual: 2 * transaction_weight,
+            max: block_content_max_weight
+        },
+        ledger.apply_block(block)
+    );
+
+    let block = GenesisPraosBlockBuilder::new()
+        .with_date(date)
+        .with_fragment(first)
+        .with_chain_length(ChainLength(0))
+        .with_parent_id(ledger.block0_hash)
+        .build(&stake_pool, ledger.era());
+    assert!(ledger.apply_block(block).is_ok());
+}
+
+#[test]
+pub fn apply_block_with_non_canonical_fragment_order() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(ConfigBuilder::new(0).with_canonical_fragment_order())
+        .with_initials(vec![
+            wallet("Alice").with(1_000).owns("stake_pool"),
+            wallet("Bob").with(1_000),
+            wallet("Clarice").with(1_000),
+            wallet("David").with(1_000),
+        ])
+        .build()
+        .unwrap();
+    let alice = controller.wallet("Alice").unwrap();
+    let bob = controller.wallet("Bob").unwrap();
+    let clarice = controller.wallet("Clarice").unwrap();
+    let david = controller.wallet("David").unwrap();
+    let stake_pool = controller.stake_pool("stake_pool").unwrap();
+    let date = BlockDate {
+        epoch: 1,
+        slot_id: 0,
+    };
+
+    let fragment_factory = FragmentFactory::from_ledger(&ledger);
+    let first = fragment_factory.transaction(&alice, &bob, &mut ledger, 10);
+    let second = fragment_factory.transaction(&clarice, &david, &mut ledger, 10);
+    let (lowest, highest) = if first.hash() < second.hash() {
+        (first, second)
+    } else {
+        (second, first)
+    };
+
+    let block = GenesisPraosBlockBuilder::new()
+        .with_date(date)
+        .with_fragments(vec![highest.clone(), lowest.clone()])
+        .with_chain_length(ChainLength(0))
+        .with_parent_id(ledger.block0_hash)
+        .build(&stake_pool, ledger.era());
+    assert_err!(
+        LedgerError::NonCanonicalFragmentOrder { index: 0 },
+        ledger.apply_block(block)
+    );
+
+    let mut contents = ContentsBuilder::new();
+    contents.push_many(vec![highest, lowest]).sort_canonically();
+    let block = GenesisPraosBlockBuilder::new()
+        .with_date(date)
+        .with_fragments(Contents::from(contents).iter().cloned().collect())
+        .with_chain_length(ChainLength(0))
+        .with_parent_id(ledger.block0_hash)
+        .build(&stake_pool, ledger.era());
+    assert!(ledger.apply_block(block).is_ok());
+
+    // fragments touching the same accounts keep the order of the producer
+    let forth = fragment_factory.transaction(&bob, &alice, &mut ledger, 10);
+    let back = fragment_factory.transaction(&alice, &bob, &mut ledger, 10);
+    for fragments in vec![vec![forth.clone(), back.clone()], vec![back, forth]] {
+        let mut contents = ContentsBuilder::new();
+        contents.push_many(fragments);
+        assert_eq!(Contents::from(contents).non_canonical_index(), None);
+    }
+}
@@ -1,22 +1,36 @@
 #![cfg(test)]
 
 use crate::{
+    certificate::{
+        CandidacyStep, Certificate, CommitteeCandidacy, VotePlan, VoteResultCertification,
+    },
+    chaineval::ConsensusEvalContext,
+    chaintypes::ConsensusType,
     config::ConfigParam,
+    date::BlockDate,
     fragment::Fragment,
     key::BftLeaderId,
-    ledger::ledger::{Block0Error, Error},
+    ledger::ledger::{Block0Error, Error, Ledger},
     testing::{
         build_vote,
         builders::{
-            create_initial_vote_cast, create_initial_vote_plan, create_initial_vote_tally,
-            InitialFaultTolerantTxCertBuilder,
+            create_initial_vote_cast, create_initial_vote_plan,
+            create_initial_vote_result_certification, create_initial_vote_tally,
+            InitialFaultTolerantTxCertBuilder, TestTxCertBuilder,
         },
-        data::Wallet,
-        ConfigBuilder, LedgerBuilder, ProposalBuilder, SignedProposalBuilder, TestGen, VoteTestGen,
+        data::{CommitteeMembersManager, Wallet},
+        ConfigBuilder, LedgerBuilder, ProposalBuilder, SignedProposalBuilder, TestGen, TestLedger,
+        VoteTestGen,
     },
     value::*,
+    vote::{ticket_input, CommitteeId, CommitteeSortitionError, PayloadType},
 };
-use chain_core::property::Fragment as _;
+use chain_core::property::{BlockDate as _, Fragment as _};
+use chain_crypto::{vrf_evaluate_and_prove, Curve25519_2HashDH, SecretKey};
+use chain_ser::deser::{Deserialize, Serialize};
+use chain_vote::MemberPublicKey;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
 #[test]
 pub fn vote_plan_in_block0() {
     let alice = Wallet::from_value(Value(100));
@@ -122,6 +136,25 @@ pub fn vote_tally_is_not_allowed_in_block0() {
     );
 }
 
+#[test]
+pub fn vote_result_certification_is_not_allowed_in_block0() {
+    let alice = Wallet::from_value(Value(100));
+    let certification = VoteResultCertification::new(VoteTestGen::vote_plan().to_id(), Vec::new());
+
+    let certification_cert =
+        create_initial_vote_result_certification(&certification, &[alice.clone()]);
+
+    let ledger_builder_result = LedgerBuilder::from_config(ConfigBuilder::new(0))
+        .faucets_wallets(vec![&alice])
+        .certs(&[certification_cert])
+        .build();
+
+    assert_eq!(
+        ledger_builder_result.err().unwrap(),
+        Error::Block0(Block0Error::HasVoteResultCertification)
+    );
+}
+
 #[test]
 pub fn update_vote_is_not_allowed_in_block0() {
     let alice = Wallet::from_value(Value(100));
@@ -177,3 +210,297 @@ pub fn update_proposal_is_not_allowed_in_block0() {
         Error::Block0(Block0Error::HasUpdateProposal)
     );
 }
+
+fn sortition_vote_plan(payload_type: PayloadType, keys: Vec<MemberPublicKey>) -> VotePlan {
+    VotePlan::new(
+        BlockDate::from_epoch_slot_id(4, 0),
+        BlockDate::from_epoch_slot_id(5, 0),
+        BlockDate::from_epoch_slot_id(6, 0),
+        VoteTestGen::proposals(3),
+        payload_type,
+        keys,
+    )
+}
+
+fn sortition_ledger(vote_plan: &VotePlan, owner: &Wallet, wallets: &[&Wallet]) -> TestLedger {
+    let vote_plan_certificate = create_initial_vote_plan(vote_plan, &[owner.clone()]);
+    let config_builder = ConfigBuilder::new(0)
+        .with_consensus_version(ConsensusType::GenesisPraos)
+        .with_committee_sortition_size(2);
+    LedgerBuilder::from_config(config_builder)
+        .faucets_wallets(wallets.to_vec())
+        .certs(&[vote_plan_certificate])
+        .build()
+        .unwrap()
+}
+
+fn candidacy(
+    test_ledger: &TestLedger,
+    wallet: &Wallet,
+    vote_plan: &VotePlan,
+    step: CandidacyStep,
+) -> Fragment {
+    let candidacy: Certificate = CommitteeCandidacy::new(vote_plan.to_id(), step).into();
+    TestTxCertBuilder::new(test_ledger.block0_hash, test_ledger.fee())
+        .make_transaction(&[wallet.clone()], &candidacy)
+}
+
+fn ticket(
+    test_ledger: &TestLedger,
+    wallet: &Wallet,
+    vote_plan: &VotePlan,
+    vrf_key: &SecretKey<Curve25519_2HashDH>,
+) -> Fragment {
+    let nonce = test_ledger
+        .ledger
+        .committee_candidacies(&vote_plan.to_id())
+        .and_then(|candidacies| candidacies.nonce())
+        .unwrap();
+    let input = ticket_input(nonce, &vote_plan.to_id(), &wallet.public_key().into());
+    let proof = vrf_evaluate_and_prove(vrf_key, &input, ChaCha20Rng::from_seed([0; 32]));
+    candidacy(
+        test_ledger,
+        wallet,
+        vote_plan,
+        CandidacyStep::Ticket { proof },
+    )
+}
+
+fn begin_block(test_ledger: &mut TestLedger, date: BlockDate) {
+    test_ledger.ledger = test_ledger
+        .ledger
+        .begin_block(
+            test_ledger.ledger.get_ledger_parameters(),
+            test_ledger.ledger.chain_length().increase(),
+            date,
+        )
+        .unwrap()
+        .finish(&ConsensusEvalContext::Genesis);
+}
+
+#[test]
+pub fn committee_is_drawn_among_the_candidates() {
+    let alice = Wallet::from_value(Value(100));
+    let mut bob = Wallet::from_value(Value(1_000));
+    let mut carol = Wallet::from_value(Value(1_000));
+    let dave = Wallet::from_value(Value(1_000));
+    let vote_plan = sortition_vote_plan(PayloadType::Public, Vec::new());
+    let mut test_ledger = sortition_ledger(&vote_plan, &alice, &[&alice, &bob, &carol, &dave]);
+
+    let mut rng = ChaCha20Rng::from_seed([1; 32]);
+    let vrf_keys: Vec<SecretKey<Curve25519_2HashDH>> =
+        (0..3).map(|_| SecretKey::generate(&mut rng)).collect();
+    let apply = |i: usize| CandidacyStep::Apply {
+        vrf_key: vrf_keys[i].to_public(),
+        member_key: None,
+    };
+
+    let applying = BlockDate::from_epoch_slot_id(1, 0);
+    for (i, wallet) in [&mut bob, &mut carol].iter_mut().enumerate() {
+        let fragment = candidacy(&test_ledger, wallet, &vote_plan, apply(i));
+        test_ledger.apply_fragment(&fragment, applying).unwrap();
+        wallet.confirm_transaction();
+    }
+    let bob_again = candidacy(&test_ledger, &bob, &vote_plan, apply(0));
+    assert!(matches!(
+        test_ledger.apply_fragment(&bob_again, applying),
+        Err(Error::CommitteeSortition(
+            CommitteeSortitionError::AlreadyCandidate { .. }
+        ))
+    ));
+    let dave_late = candidacy(&test_ledger, &dave, &vote_plan, apply(2));
+    assert!(matches!(
+        test_ledger.apply_fragment(&dave_late, BlockDate::from_epoch_slot_id(2, 0)),
+        Err(Error::CommitteeSortition(
+            CommitteeSortitionError::CandidacyClosed { .. }
+        ))
+    ));
+
+    // the nonce of the draw is taken on the epoch before the vote
+    begin_block(&mut test_ledger, BlockDate::from_epoch_slot_id(3, 0));
+    let revealing = BlockDate::from_epoch_slot_id(3, 1);
+    let dave_ticket = ticket(&test_ledger, &dave, &vote_plan, &vrf_keys[2]);
+    assert!(matches!(
+        test_ledger.apply_fragment(&dave_ticket, revealing),
+        Err(Error::CommitteeSortition(
+            CommitteeSortitionError::NotCandidate { .. }
+        ))
+    ));
+    let forged_ticket = ticket(&test_ledger, &carol, &vote_plan, &vrf_keys[0]);
+    assert!(matches!(
+        test_ledger.apply_fragment(&forged_ticket, revealing),
+        Err(Error::CommitteeSortition(
+            CommitteeSortitionError::InvalidTicket { .. }
+        ))
+    ));
+    for (i, wallet) in [&bob, &carol].iter().enumerate() {
+        let fragment = ticket(&test_ledger, wallet, &vote_plan, &vrf_keys[i]);
+        test_ledger.apply_fragment(&fragment, revealing).unwrap();
+    }
+
+    begin_block(&mut test_ledger, vote_plan.vote_start());
+    let committee: Vec<CommitteeId> = vec![bob.public_key().into(), carol.public_key().into()];
+    let plan = test_ledger
+        .ledger
+        .votes
+        .plans
+        .lookup(&vote_plan.to_id())
+        .unwrap();
+    assert!(committee
+        .iter()
+        .all(|member| plan.committee_set().contains(member)));
+    // the candidacies are dropped once drawn
+    assert!(test_ledger
+        .ledger
+        .committee_candidacies(&vote_plan.to_id())
+        .is_none());
+}
+
+#[test]
+pub fn drawn_members_join_the_election_key() {
+    let alice = Wallet::from_value(Value(100));
+    let mut bob = Wallet::from_value(Value(1_000));
+    let mut rng = ChaCha20Rng::from_seed([1; 32]);
+    let members = CommitteeMembersManager::new(&mut rng, b"sortition", 1, 2);
+    let vote_plan = sortition_vote_plan(
+        PayloadType::Private,
+        vec![members.members()[0].public_key()],
+    );
+    let mut test_ledger = sortition_ledger(&vote_plan, &alice, &[&alice, &bob]);
+
+    let vrf_key = SecretKey::<Curve25519_2HashDH>::generate(&mut rng);
+    let applying = BlockDate::from_epoch_slot_id(1, 0);
+    let without_key = CandidacyStep::Apply {
+        vrf_key: vrf_key.to_public(),
+        member_key: None,
+    };
+    let fragment = candidacy(&test_ledger, &bob, &vote_plan, without_key);
+    assert!(matches!(
+        test_ledger.apply_fragment(&fragment, applying),
+        Err(Error::CommitteeSortition(
+            CommitteeSortitionError::InvalidMemberKey { .. }
+        ))
+    ));
+    let with_key = CandidacyStep::Apply {
+        vrf_key: vrf_key.to_public(),
+        member_key: Some(members.members()[1].public_key()),
+    };
+    let fragment = candidacy(&test_ledger, &bob, &vote_plan, with_key);
+    test_ledger.apply_fragment(&fragment, applying).unwrap();
+    bob.confirm_transaction();
+
+    begin_block(&mut test_ledger, BlockDate::from_epoch_slot_id(3, 0));
+    let fragment = ticket(&test_ledger, &bob, &vote_plan, &vrf_key);
+    test_ledger
+        .apply_fragment(&fragment, BlockDate::from_epoch_slot_id(3, 1))
+        .unwrap();
+
+    begin_block(&mut test_ledger, vote_plan.vote_start());
+    let status = test_ledger
+        .ledger
+        .active_vote_plans()
+        .into_iter()
+        .find(|status| status.id == vote_plan.to_id())
+        .unwrap();
+    let keys: Vec<_> = members.members().iter().map(|m| m.public_key()).collect();
+    assert_eq!(status.committee_public_keys, keys);
+}
+
+#[test]
+pub fn candidacies_survive_the_ledger_serialization() {
+    let alice = Wallet::from_value(Value(100));
+    let mut bob = Wallet::from_value(Value(1_000));
+    let carol = Wallet::from_value(Value(1_000));
+    let mut rng = ChaCha20Rng::from_seed([1; 32]);
+    let members = CommitteeMembersManager::new(&mut rng, b"sortition", 1, 3);
+    let vote_plan = sortition_vote_plan(
+        PayloadType::Private,
+        vec![members.members()[0].public_key()],
+    );
+    let mut test_ledger = sortition_ledger(&vote_plan, &alice, &[&alice, &bob, &carol]);
+
+    let vrf_keys: Vec<SecretKey<Curve25519_2HashDH>> =
+        (0..2).map(|_| SecretKey::generate(&mut rng)).collect();
+    let applying = BlockDate::from_epoch_slot_id(1, 0);
+    for (i, wallet) in [&bob, &carol].iter().enumerate() {
+        let apply = CandidacyStep::Apply {
+            vrf_key: vrf_keys[i].to_public(),
+            member_key: Some(members.members()[i + 1].public_key()),
+        };
+        let fragment = candidacy(&test_ledger, wallet, &vote_plan, apply);
+        test_ledger.apply_fragment(&fragment, applying).unwrap();
+    }
+    bob.confirm_transaction();
+
+    // bob reveals its ticket, carol does not
+    begin_block(&mut test_ledger, BlockDate::from_epoch_slot_id(3, 0));
+    let fragment = ticket(&test_ledger, &bob, &vote_plan, &vrf_keys[0]);
+    test_ledger
+        .apply_fragment(&fragment, BlockDate::from_epoch_slot_id(3, 1))
+        .unwrap();
+
+    let mut bytes = Vec::new();
+    test_ledger.ledger.serialize(&mut bytes).unwrap();
+    let restored = Ledger::deserialize(&bytes[..]).unwrap();
+    assert!(restored.sortition == test_ledger.ledger.sortition);
+
+    let candidacies = restored.committee_candidacies(&vote_plan.to_id()).unwrap();
+    assert!(candidacies.nonce().is_some());
+    let candidates = candidacies.candidates();
+    assert_eq!(candidates.len(), 2);
+    assert!(candidates[0].ticket().is_some());
+    assert!(candidates[1].ticket().is_none());
+    assert!(candidates.iter().all(|c| c.stake() > Value::zero()));
+    assert!(candidates.iter().all(|c| c.member_key().is_some()));
+}
+
+#[test]
+pub fn committee_candidacy_requires_the_sortition() {
+    let alice = Wallet::from_value(Value(100));
+    let bob = Wallet::from_value(Value(1_000));
+    let vote_plan = VoteTestGen::vote_plan();
+    let vote_plan_certificate = create_initial_vote_plan(&vote_plan, &[alice.clone()]);
+    let vrf_key = SecretKey::<Curve25519_2HashDH>::generate(ChaCha20Rng::from_seed([0; 32]));
+    let candidacy: Certificate = CommitteeCandidacy::new(
+        vote_plan.to_id(),
+        CandidacyStep::Apply {
+            vrf_key: vrf_key.to_public(),
+            member_key: None,
+        },
+    )
+    .into();
+
+    let leader = BftLeaderId::from(alice.public_key());
+    let config_builder = ConfigBuilder::new(0).with_leaders(&[leader]);
+    let mut test_ledger = LedgerBuilder::from_config(config_builder.clone())
+        .faucets_wallets(vec![&alice, &bob])
+        .certs(&[vote_plan_certificate.clone()])
+        .build()
+        .unwrap();
+    let fragment = TestTxCertBuilder::new(test_ledger.block0_hash, test_ledger.fee())
+        .make_transaction(&[bob.clone()], &candidacy);
+    assert_eq!(
+        test_ledger
+            .apply_fragment(&fragment, BlockDate::first())
+            .err()
+            .unwrap(),
+        Error::CommitteeSortition(CommitteeSortitionError::Disabled)
+    );
+
+    // the nonce of the draw is not random under BFT
+    let mut test_ledger =
+        LedgerBuilder::from_config(config_builder.with_committee_sortition_size(2))
+            .faucets_wallets(vec![&alice, &bob])
+            .certs(&[vote_plan_certificate])
+            .build()
+            .unwrap();
+    let fragment = TestTxCertBuilder::new(test_ledger.block0_hash, test_ledger.fee())
+        .make_transaction(&[bob], &candidacy);
+    assert_eq!(
+        test_ledger
+            .apply_fragment(&fragment, BlockDate::first())
+            .err()
+            .unwrap(),
+        Error::CommitteeSortition(CommitteeSortitionError::BftConsensus)
+    );
+}
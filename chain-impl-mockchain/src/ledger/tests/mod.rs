@@ -5,5 +5,6 @@ pub mod certificate_tests;
 pub mod discrimination_tests;
 pub mod initial_funds_tests;
 pub mod ledger_tests;
+pub mod token_tests;
 pub mod transaction_tests;
 pub mod update_tests;
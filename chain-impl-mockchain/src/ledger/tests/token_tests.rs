@@ -0,0 +1,169 @@
+#![cfg(test)]
+
+use crate::{
+    fee::LinearFee,
+    fragment::Fragment,
+    ledger::Error,
+    testing::{
+        builders::witness_builder::make_witness,
+        data::{AddressData, AddressDataValue},
+        ConfigBuilder, LedgerBuilder, TestLedger,
+    },
+    tokens::{TokenBundle, TokenIdentifier},
+    transaction::*,
+    value::*,
+};
+use chain_addr::{Address, Discrimination};
+
+const PER_TOKEN_FEE: u64 = 5;
+
+fn token_a() -> TokenIdentifier {
+    TokenIdentifier::from([1; 32])
+}
+
+fn token_b() -> TokenIdentifier {
+    TokenIdentifier::from([2; 32])
+}
+
+fn tokens(a: u64, b: u64) -> TokenBundle {
+    TokenBundle::new()
+        .with(token_a(), Value(a))
+        .unwrap()
+        .with(token_b(), Value(b))
+        .unwrap()
+}
+
+fn ledger_with_tokens() -> (TestLedger, AddressDataValue) {
+    let alice = AddressDataValue::utxo(Discrimination::Test, Value(1000));
+    let test_ledger = LedgerBuilder::from_config(
+        ConfigBuilder::new(0)
+            .with_fee(LinearFee::new(1, 1, 0))
+            .with_per_token_fee(PER_TOKEN_FEE),
+    )
+    .prefill_output(Output::with_tokens(
+        alice.address(),
+        Value(1000),
+        tokens(100, 50),
+    ))
+    .build()
+    .expect("cannot build test ledger");
+    (test_ledger, alice)
+}
+
+fn spend_alice_utxo(
+    test_ledger: &TestLedger,
+    alice: &AddressDataValue,
+    outputs: &[Output<Address>],
+) -> Fragment {
+    let utxo = test_ledger
+        .find_utxo_for_address(&alice.clone().into())
+        .unwrap();
+    let builder = TxBuilder::new()
+        .set_nopayload()
+        .set_ios(&[alice.make_input(Some(utxo))], outputs);
+    let witness = make_witness(
+        test_ledger.block0_hash(),
+        &alice.clone().into(),
+        &builder.get_auth_data_for_witness().hash(),
+    );
+    Fragment::Transaction(builder.set_witnesses(&[witness]).set_payload_auth(&()))
+}
+
+#[test]
+pub fn block0_outputs_create_tokens() {
+    let (test_ledger, alice) = ledger_with_tokens();
+
+    assert_eq!(test_ledger.ledger.token_supply(&token_a()), Value(100));
+    assert_eq!(test_ledger.ledger.token_supply(&token_b()), Value(50));
+    let utxo = test_ledger.find_utxo_for_address(&alice.into()).unwrap();
+    assert_eq!(utxo.output.tokens, tokens(100, 50));
+}
+
+#[test]
+pub fn multi_token_transfer() {
+    let (mut test_ledger, alice) = ledger_with_tokens();
+    let bob = AddressData::utxo(Discrimination::Test);
+
+    // 1 input, 2 outputs and 2 distinct tokens
+    let fee = 1 + 3 + 2 * PER_TOKEN_FEE;
+    let outputs = [
+        Output::with_tokens(
+            bob.address.clone(),
+            Value(500),
+            TokenBundle::new().with(token_a(), Value(60)).unwrap(),
+        ),
+        Output::with_tokens(alice.address(), Value(500 - fee), tokens(40, 50)),
+    ];
+    let fragment = spend_alice_utxo(&test_ledger, &alice, &outputs);
+    test_ledger.apply_transaction(fragment).unwrap();
+
+    assert_eq!(test_ledger.ledger.token_supply(&token_a()), Value(100));
+    assert_eq!(test_ledger.ledger.token_supply(&token_b()), Value(50));
+    let bob_utxo = test_ledger.find_utxo_for_address(&bob).unwrap();
+    assert_eq!(bob_utxo.output.tokens.get(&token_a()), Value(60));
+    assert_eq!(bob_utxo.output.tokens.get(&token_b()), Value::zero());
+}
+
+#[test]
+pub fn token_fee_is_required() {
+    let (mut test_ledger, alice) = ledger_with_tokens();
+
+    let fee = 1 + 2;
+    let outputs = [Output::with_tokens(
+        alice.address(),
+        Value(1000 - fee),
+        tokens(100, 50),
+    )];
+    let fragment = spend_alice_utxo(&test_ledger, &alice, &outputs);
+    assert_err_match!(
+        Error::TransactionBalanceInvalid(_),
+        test_ledger.apply_transaction(fragment)
+    );
+}
+
+#[test]
+pub fn unbalanced_tokens_are_rejected() {
+    let (mut test_ledger, alice) = ledger_with_tokens();
+
+    let fee = 1 + 2 + 2 * PER_TOKEN_FEE;
+    let outputs = [Output::with_tokens(
+        alice.address(),
+        Value(1000 - fee),
+        tokens(200, 50),
+    )];
+    let fragment = spend_alice_utxo(&test_ledger, &alice, &outputs);
+    assert_err!(
+        Error::TokensNotBalanced {
+            inputs: tokens(100, 50),
+            outputs: tokens(200, 50),
+        },
+        test_ledger.apply_transaction(fragment)
+    );
+
+    // dropping tokens is not allowed either
+    let fee = 1 + 2;
+    let outputs = [Output::from_address(alice.address(), Value(1000 - fee))];
+    let fragment = spend_alice_utxo(&test_ledger, &alice, &outputs);
+    assert_err_match!(
+        Error::TokensNotBalanced { .. },
+        test_ledger.apply_transaction(fragment)
+    );
+}
+
+#[test]
+pub fn tokens_cannot_be_sent_to_accounts() {
+    let (mut test_ledger, alice) = ledger_with_tokens();
+    let account = AddressData::account(Discrimination::Test);
+
+    let fee = 1 + 2 + 2 * PER_TOKEN_FEE;
+    let outputs = [Output::with_tokens(
+        account.address,
+        Value(1000 - fee),
+        tokens(100, 50),
+    )];
+    let fragment = spend_alice_utxo(&test_ledger, &alice, &outputs);
+    assert_err_match!(
+        Error::TokensToNonUtxoAddress { .. },
+        test_ledger.apply_transaction(fragment)
+    );
+}
@@ -2,6 +2,8 @@
 
 use crate::{
     accounting::account::LedgerError::NonExistent,
+    date::BlockDate,
+    fragment::Fragment,
     ledger::{
         self,
         check::TxVerifyError,
@@ -14,7 +16,8 @@ use crate::{
     transaction::*,
     value::*,
 };
-use chain_addr::Discrimination;
+use chain_addr::{Address, Discrimination};
+use chain_crypto::{Signature, ValidationPolicy};
 
 #[test]
 pub fn transaction_fail_when_255_outputs() {
@@ -25,10 +28,7 @@ pub fn transaction_fail_when_255_outputs() {
 
     // random output repeated 255 times.
     let receiver = AddressData::utxo(Discrimination::Test);
-    let output = Output {
-        address: receiver.address,
-        value: Value(1),
-    };
+    let output = Output::from_address(receiver.address, Value(1));
     let outputs: Vec<_> = std::iter::repeat(output).take(255).collect();
 
     let fragment = TestTxBuilder::new(test_ledger.block0_hash)
@@ -130,3 +130,95 @@ pub fn repeated_account_transaction() {
         .get_fragment();
     assert!(test_ledger.apply_transaction(fragment).is_err());
 }
+
+#[test]
+pub fn transaction_only_valid_in_its_interval() {
+    let faucet = AddressDataValue::account(Discrimination::Test, Value(1000));
+    let receiver = AddressData::account(Discrimination::Test);
+
+    let mut test_ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+        .faucet(&faucet)
+        .build()
+        .expect("cannot build test ledger");
+
+    let date = |epoch, slot_id| BlockDate { epoch, slot_id };
+    let fragment = TestTxBuilder::new(test_ledger.block0_hash)
+        .with_validity(ValidityInterval::new(date(1, 10), date(2, 0)))
+        .move_from_faucet(&mut test_ledger, &receiver.into(), Value(1000))
+        .get_fragment();
+
+    assert_err!(
+        ledger::Error::TransactionNotYetValid {
+            valid_from: date(1, 10),
+            block_date: date(1, 9)
+        },
+        test_ledger.apply_fragment(&fragment, date(1, 9))
+    );
+    assert_err!(
+        ledger::Error::TransactionExpired {
+            valid_until: date(2, 0),
+            block_date: date(2, 1)
+        },
+        test_ledger.apply_fragment(&fragment, date(2, 1))
+    );
+    assert!(test_ledger.apply_fragment(&fragment, date(2, 0)).is_ok());
+}
+
+// add the order of the group to the scalar s of the Ed25519 signature R || s,
+// the signature still verifies but s is not canonically encoded
+fn malleate_signature(signature: &[u8]) -> Vec<u8> {
+    const GROUP_ORDER: [u8; 32] = [
+        0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde,
+        0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x10,
+    ];
+    let mut malleated = signature.to_vec();
+    let mut carry = 0u16;
+    for (byte, order) in malleated[32..].iter_mut().zip(GROUP_ORDER.iter()) {
+        let sum = u16::from(*byte) + u16::from(*order) + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+    malleated
+}
+
+#[test]
+pub fn malleated_witness_is_only_accepted_by_the_legacy_policy() {
+    for policy in &[ValidationPolicy::Legacy, ValidationPolicy::Strict] {
+        let faucet = AddressDataValue::account(Discrimination::Test, Value(1000));
+        let receiver = AddressDataValue::account(Discrimination::Test, Value(0));
+
+        let mut test_ledger =
+            LedgerBuilder::from_config(ConfigBuilder::new(0).with_validation_policy(*policy))
+                .faucet(&faucet)
+                .build()
+                .expect("cannot build test ledger");
+
+        let tx = TestTxBuilder::new(test_ledger.block0_hash)
+            .move_all_funds(&mut test_ledger, &faucet, &receiver)
+            .get_tx();
+        let tx = tx.as_slice();
+        let signature = match tx.witnesses().iter().next() {
+            Some(Witness::Account(signature)) => malleate_signature(signature.as_ref()),
+            _ => panic!("expected an account witness"),
+        };
+        let inputs: Vec<Input> = tx.inputs().iter().collect();
+        let outputs: Vec<Output<Address>> = tx.outputs().iter().collect();
+        let witness = Witness::Account(Signature::from_binary(&signature).unwrap());
+        let malleated = TxBuilder::new()
+            .set_payload(&NoExtra)
+            .set_validity(tx.validity())
+            .set_ios(&inputs, &outputs)
+            .set_witnesses(&[witness])
+            .set_payload_auth(&());
+
+        let result =
+            test_ledger.apply_fragment(&Fragment::Transaction(malleated), test_ledger.date());
+        match policy {
+            ValidationPolicy::Legacy => assert!(result.is_ok()),
+            ValidationPolicy::Strict => {
+                assert_err_match!(ledger::Error::WitnessInvalidEncoding { .. }, result)
+            }
+        }
+    }
+}
@@ -1,16 +1,27 @@
 use crate::{
     block::Block,
-    chaintypes::HeaderId,
+    chaineval::ConsensusEvalContext,
+    chaintypes::{ConsensusType, HeaderId},
+    config::{ConfigParam, RewardParams},
     date::BlockDate,
     fragment::Contents,
     header::{BlockVersion, HeaderBuilderNew},
-    ledger::ledger::Ledger,
+    leadership::Leadership,
+    ledger::{ledger::Ledger, Error},
+    rewards::Ratio,
     testing::arbitrary::update_proposal::UpdateProposalData,
-    testing::{ConfigBuilder, LedgerBuilder},
+    testing::{
+        builders::update_builder::{ProposalBuilder, SignedProposalBuilder, UpdateVoteBuilder},
+        data::LeaderPair,
+        scenario::{prepare_scenario, wallet},
+        ConfigBuilder, LedgerBuilder, TestGen,
+    },
+    update::{self, SignedUpdateProposal},
 };
 use chain_crypto::{Ed25519, SecretKey};
 use quickcheck::TestResult;
 use quickcheck_macros::quickcheck;
+use std::num::{NonZeroU32, NonZeroU64};
 
 #[quickcheck]
 pub fn ledger_adopt_settings_from_update_proposal(
@@ -81,6 +92,175 @@ pub fn ledger_adopt_settings_from_update_proposal(
     }
 }
 
+#[test]
+pub fn ledger_switches_consensus_at_epoch_boundary() {
+    let leader = TestGen::leader_pair();
+    let (testledger, controller) = prepare_scenario()
+        .with_config(ConfigBuilder::new(0).with_leaders(&[leader.id()]))
+        .with_initials(vec![wallet("Alice").with(1_000).owns("stake_pool")])
+        .build()
+        .unwrap();
+    let stake_pool = controller.stake_pool("stake_pool").unwrap();
+    let mut ledger = testledger.ledger;
+    let date = ledger.date();
+
+    let proposal_id = TestGen::hash();
+    ledger = ledger
+        .apply_update_proposal(proposal_id, &switch_to_genesis_praos(&leader), date)
+        .unwrap();
+    let vote = UpdateVoteBuilder::new()
+        .with_proposal_id(proposal_id)
+        .with_voter_id(leader.id())
+        .build();
+    ledger = ledger.apply_update_vote(&vote).unwrap();
+
+    // the current epoch is still led by the BFT leaders
+    assert!(Leadership::new(date.epoch, &ledger)
+        .stake_distribution()
+        .is_none());
+
+    // while the leadership of the next epoch is already the stake pools one
+    let next_leadership = Leadership::new(date.epoch + 1, &ledger);
+    let distribution = next_leadership
+        .stake_distribution()
+        .expect("genesis praos leadership for the next epoch");
+    assert!(distribution.to_pools.contains_key(&stake_pool.id()));
+    assert_eq!(ledger.consensus_version(), ConsensusType::Bft);
+
+    // and the ledger switches on the first block of the next epoch
+    let block = build_block(
+        &ledger,
+        testledger.block0_hash,
+        date,
+        &SecretKey::generate(rand_core::OsRng),
+    );
+    let header_meta = block.header.get_content_eval_context();
+    ledger = ledger
+        .apply_block(
+            next_leadership.ledger_parameters().clone(),
+            &block.contents,
+            &header_meta,
+        )
+        .unwrap();
+    assert_eq!(ledger.consensus_version(), ConsensusType::GenesisPraos);
+    assert!(ledger.updates.proposals.is_empty());
+}
+
+#[test]
+pub fn switch_to_genesis_praos_without_stake_pools_is_rejected() {
+    let leader = TestGen::leader_pair();
+    let cb = ConfigBuilder::new(0).with_leaders(&[leader.id()]);
+    let ledger = LedgerBuilder::from_config(cb)
+        .build()
+        .expect("cannot build test ledger")
+        .ledger;
+    let date = ledger.date();
+
+    match ledger.apply_update_proposal(TestGen::hash(), &switch_to_genesis_praos(&leader), date) {
+        Err(Error::Update(update::Error::NoStakePools)) => {}
+        res => panic!("unexpected result {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+pub fn increasing_halving_reward_params_are_rejected() {
+    let increasing = RewardParams::Halving {
+        constant: 100,
+        ratio: Ratio {
+            numerator: 3,
+            denominator: NonZeroU64::new(2).unwrap(),
+        },
+        epoch_start: 0,
+        epoch_rate: NonZeroU32::new(1).unwrap(),
+    };
+    let leader = TestGen::leader_pair();
+
+    match LedgerBuilder::from_config(
+        ConfigBuilder::new(0)
+            .with_leaders(&[leader.id()])
+            .with_rewards_params(increasing.clone()),
+    )
+    .build()
+    {
+        Err(Error::Update(update::Error::HalvingRatioAboveOne(_))) => {}
+        res => panic!("unexpected result {:?}", res.map(|_| ())),
+    }
+
+    let ledger = LedgerBuilder::from_config(ConfigBuilder::new(0).with_leaders(&[leader.id()]))
+        .build()
+        .expect("cannot build test ledger")
+        .ledger;
+    let date = ledger.date();
+    let proposal = SignedProposalBuilder::new()
+        .with_proposal_update(
+            ProposalBuilder::new()
+                .with_proposal_change(ConfigParam::RewardParams(increasing))
+                .build(),
+        )
+        .with_proposer_id(leader.id())
+        .build();
+
+    match ledger.apply_update_proposal(TestGen::hash(), &proposal, date) {
+        Err(Error::Update(update::Error::HalvingRatioAboveOne(_))) => {}
+        res => panic!("unexpected result {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+pub fn pending_proposal_expires_on_epoch_transition() {
+    let leader = TestGen::leader_pair();
+    let cb = ConfigBuilder::new(0).with_leaders(&[leader.id()]);
+    let mut ledger = LedgerBuilder::from_config(cb)
+        .build()
+        .expect("cannot build test ledger")
+        .ledger;
+    ledger.settings().proposal_expiration = 1;
+    let date = ledger.date();
+
+    let proposal_id = TestGen::hash();
+    ledger = ledger
+        .apply_update_proposal(proposal_id, &switch_to_genesis_praos(&leader), date)
+        .unwrap();
+    assert_eq!(
+        ledger.update_proposal_expiry(&proposal_id),
+        Some(date.epoch + 1)
+    );
+
+    let begin_block = |epoch| {
+        ledger
+            .begin_block(
+                ledger.get_ledger_parameters(),
+                ledger.chain_length().increase(),
+                BlockDate { epoch, slot_id: 0 },
+            )
+            .unwrap()
+    };
+
+    // the proposal can still be voted for during its expiry epoch
+    assert!(begin_block(date.epoch + 1)
+        .update_proposal_events()
+        .is_empty());
+
+    let block = begin_block(date.epoch + 2);
+    assert_eq!(
+        block.update_proposal_events(),
+        &[update::UpdateProposalEvent::Expired(proposal_id)]
+    );
+    let ledger = block.finish(&ConsensusEvalContext::Bft);
+    assert!(ledger.updates.proposals.is_empty());
+    assert_eq!(ledger.update_proposal_expiry(&proposal_id), None);
+}
+
+fn switch_to_genesis_praos(proposer: &LeaderPair) -> SignedUpdateProposal {
+    let proposal = ProposalBuilder::new()
+        .with_proposal_change(ConfigParam::ConsensusVersion(ConsensusType::GenesisPraos))
+        .build();
+    SignedProposalBuilder::new()
+        .with_proposal_update(proposal)
+        .with_proposer_id(proposer.id())
+        .build()
+}
+
 fn build_block(
     ledger: &Ledger,
     block0_hash: HeaderId,
@@ -0,0 +1,118 @@
+//! Supply of the tokens carried by the unspent outputs.
+//!
+//! Tokens are created by the outputs of the block0 transactions only: the
+//! transactions applied afterward must spend as much of every token as their
+//! outputs carry. The distribution is updated with every output added to or
+//! removed from the UTXO set, so it always matches the tokens the UTXOs
+//! carry.
+
+use crate::tokens::{TokenBundle, TokenIdentifier};
+use crate::value::{Value, ValueError};
+use imhamt::{Hamt, HamtIter};
+use std::collections::hash_map::DefaultHasher;
+
+/// Total value of every token carried by the unspent outputs
+#[derive(Clone, PartialEq, Eq)]
+pub struct TokenDistribution {
+    supply: Hamt<DefaultHasher, TokenIdentifier, Value>,
+}
+
+impl Default for TokenDistribution {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenDistribution {
+    pub fn new() -> Self {
+        TokenDistribution {
+            supply: Hamt::new(),
+        }
+    }
+
+    /// Total value of the given token, zero for an unknown token
+    pub fn supply(&self, token: &TokenIdentifier) -> Value {
+        self.supply
+            .lookup(token)
+            .copied()
+            .unwrap_or_else(Value::zero)
+    }
+
+    /// Account for an output carrying the given tokens
+    pub fn add(&self, tokens: &TokenBundle) -> Result<Self, ValueError> {
+        let mut supply = self.supply.clone();
+        for (token, value) in tokens.iter() {
+            supply = supply
+                .insert_or_update(*token, *value, |total| total.checked_add(*value).map(Some))?;
+        }
+        Ok(TokenDistribution { supply })
+    }
+
+    /// Account for an output carrying the given tokens being spent
+    pub fn remove(&self, tokens: &TokenBundle) -> Result<Self, ValueError> {
+        let mut supply = self.supply.clone();
+        for (token, value) in tokens.iter() {
+            let total = supply
+                .lookup(token)
+                .copied()
+                .unwrap_or_else(Value::zero)
+                .checked_sub(*value)?;
+            supply = if total == Value::zero() {
+                supply
+                    .remove(token)
+                    .expect("the token has just been looked up")
+            } else {
+                supply
+                    .replace(token, total)
+                    .expect("the token has just been looked up")
+                    .0
+            };
+        }
+        Ok(TokenDistribution { supply })
+    }
+
+    pub fn iter(&self) -> HamtIter<'_, TokenIdentifier, Value> {
+        self.supply.iter()
+    }
+}
+
+impl std::fmt::Debug for TokenDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.supply.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_remove() {
+        let token_a = TokenIdentifier::from([1; 32]);
+        let token_b = TokenIdentifier::from([2; 32]);
+        let bundle = TokenBundle::new()
+            .with(token_a, Value(10))
+            .unwrap()
+            .with(token_b, Value(3))
+            .unwrap();
+
+        let distribution = TokenDistribution::new()
+            .add(&bundle)
+            .unwrap()
+            .add(&bundle)
+            .unwrap();
+        assert_eq!(distribution.supply(&token_a), Value(20));
+        assert_eq!(distribution.supply(&token_b), Value(6));
+
+        let distribution = distribution.remove(&bundle).unwrap();
+        assert_eq!(distribution.supply(&token_a), Value(10));
+        let distribution = distribution.remove(&bundle).unwrap();
+        assert_eq!(distribution.supply(&token_a), Value::zero());
+        assert_eq!(distribution.iter().count(), 0);
+
+        assert_eq!(
+            distribution.remove(&bundle),
+            Err(ValueError::NegativeAmount)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Dry-run of the transition to the next epoch.
+//!
+//! [`Ledger::preview_epoch_transition`] runs the steps a node takes on the
+//! first block of a new epoch: the rewards of the ending epoch are
+//! distributed, the accepted governance actions and update proposals are
+//! applied, and the leadership of the new epoch is computed from the
+//! resulting state. The ledger itself is left untouched, so operators can
+//! check the effect of a configuration change before the epoch boundary.
+
+use super::{EpochRewardsInfo, Error, Ledger, LedgerParameters, RewardsInfoParameters};
+use crate::{
+    chaineval::PraosNonce, chaintypes::ConsensusType, date::Epoch, key::BftLeaderId,
+    setting::ActiveSlotsCoeff, stake::StakeDistribution,
+};
+
+/// What the leadership of an epoch is computed from
+#[derive(Debug, Clone)]
+pub struct LeadershipInputs {
+    pub consensus_version: ConsensusType,
+    pub consensus_nonce: PraosNonce,
+    pub active_slots_coeff: ActiveSlotsCoeff,
+    pub bft_leaders: Vec<BftLeaderId>,
+    /// the stake distribution the slot leaders are elected from
+    pub stake_distribution: StakeDistribution,
+}
+
+/// The outcome of the transition to the next epoch
+#[derive(Debug, Clone)]
+pub struct EpochTransitionPreview {
+    /// the epoch starting after the transition
+    pub epoch: Epoch,
+    /// the rewards distributed for the ending epoch
+    pub rewards: EpochRewardsInfo,
+    pub leadership: LeadershipInputs,
+    /// the parameters the blocks of the new epoch are validated with
+    pub ledger_parameters: LedgerParameters,
+}
+
+impl Ledger {
+    /// Compute the outcome of the transition to the next epoch without
+    /// modifying the ledger.
+    pub fn preview_epoch_transition(&self) -> Result<EpochTransitionPreview, Error> {
+        let epoch = self.date.epoch + 1;
+        let (rewarded, rewards) = self.distribute_rewards(
+            &self.get_stake_distribution(),
+            &self.get_ledger_parameters(),
+            RewardsInfoParameters::report_all(),
+        )?;
+        let next = rewarded.apply_protocol_changes()?.at_epoch_start(epoch)?;
+
+        let leadership = LeadershipInputs {
+            consensus_version: next.settings.consensus_version,
+            consensus_nonce: next.settings.consensus_nonce.clone(),
+            active_slots_coeff: next.settings.active_slots_coeff,
+            bft_leaders: next.settings.bft_leaders.to_vec(),
+            stake_distribution: next.get_stake_distribution_at(epoch),
+        };
+        Ok(EpochTransitionPreview {
+            epoch,
+            rewards,
+            leadership,
+            ledger_parameters: next.get_ledger_parameters(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{
+            ledger::ConfigBuilder,
+            scenario::{prepare_scenario, stake_pool, wallet},
+        },
+        value::Value,
+    };
+
+    #[test]
+    fn preview_does_not_modify_the_ledger() {
+        let (mut ledger, controller) = prepare_scenario()
+            .with_config(
+                ConfigBuilder::new(0)
+                    .with_rewards(Value(1_000))
+                    .with_treasury(Value(100)),
+            )
+            .with_initials(vec![wallet("Alice").with(1_000).owns("stake_pool")])
+            .with_stake_pools(vec![stake_pool("stake_pool").with_reward_account(true)])
+            .build()
+            .unwrap();
+        let stake_pool = controller.stake_pool("stake_pool").unwrap();
+        ledger.produce_empty_block(&stake_pool).unwrap();
+
+        let before = ledger.ledger.clone();
+        let preview = ledger.ledger.preview_epoch_transition().unwrap();
+        assert_eq!(ledger.ledger.pots, before.pots);
+        assert!(ledger.can_distribute_reward());
+
+        assert_eq!(preview.epoch, ledger.date().epoch + 1);
+        assert!(preview.rewards.drawn > Value::zero());
+
+        ledger.distribute_rewards().unwrap();
+        assert_eq!(
+            preview.leadership.stake_distribution,
+            ledger.ledger.get_stake_distribution_at(preview.epoch)
+        );
+        assert_eq!(
+            ledger.ledger.treasury_value(),
+            (before.treasury_value() + preview.rewards.treasury).unwrap()
+        );
+        assert_eq!(
+            ledger.ledger.remaining_rewards(),
+            (before.remaining_rewards() - preview.rewards.drawn).unwrap()
+        );
+    }
+}
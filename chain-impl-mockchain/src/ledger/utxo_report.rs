@@ -0,0 +1,159 @@
+//! Statistics over the unspent outputs of the ledger.
+//!
+//! The report counts the outputs held by every address (an address holding
+//! several outputs is reused), lists the dust outputs, whose value is lower
+//! than a threshold, and gives the age distribution of the outputs.
+//!
+//! The UTxO set does not record when an output was created: the caller
+//! provides the date of the block which included a given fragment, as known
+//! by its block index. The outputs of unknown fragments are counted apart.
+
+use super::ledger::Ledger;
+use crate::{
+    date::{BlockDate, Epoch},
+    fragment::FragmentId,
+    transaction::UtxoPointer,
+    value::Value,
+};
+use chain_addr::Address;
+use std::collections::{BTreeMap, HashMap};
+
+/// Unspent outputs held by a single address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AddressUtxoStats {
+    pub outputs: usize,
+    pub total: Value,
+    pub dust_outputs: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoReport {
+    /// the threshold under which an output is considered as dust
+    pub dust_threshold: Value,
+    pub outputs: usize,
+    pub total: Value,
+    pub by_address: HashMap<Address, AddressUtxoStats>,
+    /// the outputs whose value is lower than the dust threshold
+    pub dust: Vec<UtxoPointer>,
+    /// number of outputs by age, in epochs elapsed since the epoch of the
+    /// block including them
+    pub ages: BTreeMap<Epoch, usize>,
+    /// number of outputs created by a fragment of unknown date
+    pub unknown_age: usize,
+}
+
+impl UtxoReport {
+    /// Number of addresses holding more than one output
+    pub fn reused_addresses(&self) -> usize {
+        self.by_address
+            .values()
+            .filter(|stats| stats.outputs > 1)
+            .count()
+    }
+
+    /// Total value of the dust outputs
+    pub fn dust_total(&self) -> Value {
+        self.dust.iter().fold(Value::zero(), |total, pointer| {
+            total.saturating_add(pointer.value)
+        })
+    }
+}
+
+impl Ledger {
+    /// Report over the unspent outputs, `fragment_date` gives the date of
+    /// the block in which a fragment was included, if known
+    pub fn utxo_report<F>(&self, dust_threshold: Value, fragment_date: F) -> UtxoReport
+    where
+        F: Fn(&FragmentId) -> Option<BlockDate>,
+    {
+        let current_epoch = self.date().epoch;
+        let mut report = UtxoReport {
+            dust_threshold,
+            outputs: 0,
+            total: Value::zero(),
+            by_address: HashMap::new(),
+            dust: Vec::new(),
+            ages: BTreeMap::new(),
+            unknown_age: 0,
+        };
+
+        for entry in self.utxos() {
+            let value = entry.output.value;
+            let is_dust = value < dust_threshold;
+            report.outputs += 1;
+            report.total = report.total.saturating_add(value);
+
+            let stats = report
+                .by_address
+                .entry(entry.output.address.clone())
+                .or_default();
+            stats.outputs += 1;
+            stats.total = stats.total.saturating_add(value);
+            if is_dust {
+                stats.dust_outputs += 1;
+                report.dust.push(UtxoPointer::new(
+                    entry.fragment_id,
+                    entry.output_index,
+                    value,
+                ));
+            }
+
+            match fragment_date(&entry.fragment_id) {
+                Some(date) => {
+                    *report
+                        .ages
+                        .entry(current_epoch.saturating_sub(date.epoch))
+                        .or_default() += 1
+                }
+                None => report.unknown_age += 1,
+            }
+        }
+
+        report.dust.sort();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{
+            data::AddressData,
+            ledger::{ConfigBuilder, LedgerBuilder},
+        },
+        value::Value,
+    };
+    use chain_addr::Discrimination;
+
+    #[test]
+    fn utxo_report() {
+        let alice = AddressData::utxo(Discrimination::Test);
+        let bob = AddressData::utxo(Discrimination::Test);
+        let test_ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .prefill_outputs(&[alice.make_output(Value(1_000)), alice.make_output(Value(5))])
+            .prefill_output(bob.make_output(Value(500)))
+            .build()
+            .unwrap();
+        let alice_fragment = test_ledger
+            .utxos()
+            .find(|entry| entry.output.address == alice.address)
+            .unwrap()
+            .fragment_id;
+        let date = test_ledger.date();
+
+        let report = test_ledger
+            .ledger
+            .utxo_report(Value(10), |id| Some(date).filter(|_| *id == alice_fragment));
+        assert_eq!(report.outputs, 3);
+        assert_eq!(report.total, Value(1_505));
+        assert_eq!(report.reused_addresses(), 1);
+        assert_eq!(report.by_address[&alice.address].outputs, 2);
+        assert_eq!(report.by_address[&alice.address].dust_outputs, 1);
+        assert_eq!(report.by_address[&bob.address].total, Value(500));
+        assert_eq!(report.dust.len(), 1);
+        assert_eq!(report.dust[0].transaction_id, alice_fragment);
+        assert_eq!(report.dust_total(), Value(5));
+        assert_eq!(report.ages.get(&0), Some(&2));
+        assert_eq!(report.unknown_age, 1);
+    }
+}
@@ -0,0 +1,153 @@
+//! Read-only view of a ledger, to check fragments before broadcasting them.
+//!
+//! A [`LedgerView`] runs exactly the checks the ledger performs when a
+//! fragment is applied (balance, spending counters, fees, witnesses,
+//! certificate rules) and reports the same [`Error`], without ever producing
+//! a new ledger state. Wallets use it to show why a transaction would be
+//! rejected before sending it to the network.
+
+use super::{Error, Ledger, LedgerParameters};
+use crate::{date::BlockDate, fragment::Fragment};
+
+/// A read-only view of a [`Ledger`] at a given date
+#[derive(Clone)]
+pub struct LedgerView<'a> {
+    ledger: &'a Ledger,
+    parameters: LedgerParameters,
+    date: BlockDate,
+}
+
+impl<'a> LedgerView<'a> {
+    /// View the ledger at its current date, with its current parameters
+    pub fn new(ledger: &'a Ledger) -> Self {
+        LedgerView {
+            ledger,
+            parameters: ledger.get_ledger_parameters(),
+            date: ledger.date(),
+        }
+    }
+
+    /// Check the fragments as if they were included in a block at `date`
+    pub fn at_date(self, date: BlockDate) -> Self {
+        LedgerView { date, ..self }
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        self.ledger
+    }
+
+    pub fn parameters(&self) -> &LedgerParameters {
+        &self.parameters
+    }
+
+    pub fn date(&self) -> BlockDate {
+        self.date
+    }
+
+    /// Check that the fragment would be accepted by the ledger, returning
+    /// the error the ledger would report otherwise. The viewed ledger is
+    /// left untouched.
+    pub fn dry_run(&self, fragment: &Fragment) -> Result<(), Error> {
+        self.ledger
+            .apply_fragment(&self.parameters, fragment, self.date)
+            .map(|_| ())
+    }
+}
+
+impl Ledger {
+    /// Read-only view of the ledger, to dry-run fragments against it
+    pub fn view(&self) -> LedgerView<'_> {
+        LedgerView::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ledger::Error,
+        testing::{
+            builders::witness_builder::make_witness, data::AddressDataValue, ConfigBuilder,
+            LedgerBuilder, TestLedger,
+        },
+        transaction::{NoExtra, SetAuthData, TxBuilder, TxBuilderState},
+        value::Value,
+    };
+    use chain_addr::Discrimination;
+
+    fn ledger() -> (TestLedger, AddressDataValue) {
+        let faucet = AddressDataValue::account(Discrimination::Test, Value(1000));
+        let test_ledger = LedgerBuilder::from_config(ConfigBuilder::new(0))
+            .faucet(&faucet)
+            .build()
+            .expect("cannot build test ledger");
+        (test_ledger, faucet)
+    }
+
+    fn transfer(
+        test_ledger: &TestLedger,
+        faucet: &AddressDataValue,
+        input: Value,
+        output: Value,
+    ) -> TxBuilderState<SetAuthData<NoExtra>> {
+        let receiver = AddressDataValue::utxo(Discrimination::Test, output);
+        let builder = TxBuilder::new().set_nopayload().set_ios(
+            &[faucet.make_input_with_value(None, input)],
+            &[receiver.make_output()],
+        );
+        let witness = make_witness(
+            test_ledger.block0_hash(),
+            &faucet.clone().into(),
+            &builder.get_auth_data_for_witness().hash(),
+        );
+        builder.set_witnesses(&[witness])
+    }
+
+    #[test]
+    fn dry_run_accepts_valid_transaction() {
+        let (mut test_ledger, faucet) = ledger();
+        let builder = transfer(&test_ledger, &faucet, Value(100), Value(100));
+
+        builder.dry_run(&test_ledger.ledger.view(), &()).unwrap();
+
+        // the dry run did not spend the input: the very same transaction
+        // is still accepted by the ledger
+        let fragment = builder.set_payload_auth(&()).into();
+        let date = test_ledger.date();
+        test_ledger.apply_fragment(&fragment, date).unwrap();
+    }
+
+    #[test]
+    fn dry_run_reports_ledger_error() {
+        let (mut test_ledger, faucet) = ledger();
+        let builder = transfer(&test_ledger, &faucet, Value(100), Value(90));
+
+        let error = builder
+            .dry_run(&test_ledger.ledger.view(), &())
+            .unwrap_err();
+        assert!(matches!(error, Error::TransactionBalanceInvalid(_)));
+
+        let fragment = builder.set_payload_auth(&()).into();
+        let date = test_ledger.date();
+        assert_eq!(
+            test_ledger
+                .apply_fragment(&fragment, date)
+                .unwrap_err()
+                .to_string(),
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn dry_run_checks_spending_counter() {
+        let (mut test_ledger, faucet) = ledger();
+        let builder = transfer(&test_ledger, &faucet, Value(100), Value(100));
+        let fragment = builder.clone().set_payload_auth(&()).into();
+        let date = test_ledger.date();
+        test_ledger.apply_fragment(&fragment, date).unwrap();
+
+        assert!(matches!(
+            builder.dry_run(&test_ledger.ledger.view(), &()),
+            Err(Error::AccountInvalidSignature { .. })
+        ));
+    }
+}
@@ -3,6 +3,9 @@ use crate::value::Value;
 pub use cardano_legacy_address::Addr as OldAddress;
 pub use cardano_legacy_address::AddressMatchXPub as OldAddressMatchXPub;
 
+use cardano_legacy_address::{AddrType, ExtendedAddr};
+use std::convert::TryFrom;
+
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
 use chain_crypto::{Ed25519, PublicKey};
@@ -12,6 +15,41 @@ pub struct UtxoDeclaration {
     pub addrs: Vec<(OldAddress, Value)>,
 }
 
+/// The kinds of legacy addresses, as far as spending them is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OldAddressKind {
+    /// derived from an extended public key, without derivation path
+    /// (`Ae2...` on the mainnet)
+    PubKey,
+    /// derived from an extended public key, with the encrypted derivation
+    /// path of the wallet (`DdzFF...` on the mainnet)
+    PubKeyWithDerivationPath,
+    /// redemption address of the initial vending, derived from a plain
+    /// public key
+    Redeem,
+    /// script address, which cannot be spent on this ledger
+    Script,
+}
+
+/// Classify a legacy address, `None` if it is not well formed
+pub fn oldaddress_kind(address: &OldAddress) -> Option<OldAddressKind> {
+    let ea = ExtendedAddr::try_from(address.as_ref()).ok()?;
+    let kind = match ea.addr_type {
+        AddrType::PubKey if ea.attributes.derivation_path.is_some() => {
+            OldAddressKind::PubKeyWithDerivationPath
+        }
+        AddrType::PubKey => OldAddressKind::PubKey,
+        AddrType::Redeem => OldAddressKind::Redeem,
+        AddrType::Script => OldAddressKind::Script,
+    };
+    Some(kind)
+}
+
+/// Check that the public key of an old UTxO witness matches the address.
+///
+/// The address is derived from the extended public key made of `pk` and
+/// the chain code `some_bytes`, or from `pk` alone for a redemption
+/// address, in which case `some_bytes` is ignored.
 pub fn oldaddress_from_xpub(
     address: &OldAddress,
     pk: &PublicKey<Ed25519>,
@@ -19,7 +57,11 @@ pub fn oldaddress_from_xpub(
 ) -> OldAddressMatchXPub {
     let mut pkraw = [0u8; 32];
     pkraw.copy_from_slice(&pk.as_ref());
-    address.identical_with_pubkey_raw(&pkraw, some_bytes)
+    match address.addr_type() {
+        Some(AddrType::PubKey) => address.identical_with_pubkey_raw(&pkraw, some_bytes),
+        Some(AddrType::Redeem) => address.identical_with_redeem_key(&pkraw),
+        Some(AddrType::Script) | None => OldAddressMatchXPub::No,
+    }
 }
 
 impl Readable for UtxoDeclaration {
@@ -67,7 +109,7 @@ impl property::Serialize for UtxoDeclaration {
 #[cfg(any(test, feature = "property-test-api"))]
 mod tests {
     use super::*;
-    use cardano_legacy_address::ExtendedAddr;
+    use cardano_legacy_address::Attributes;
     use ed25519_bip32::{XPub, XPUB_SIZE};
     use quickcheck::{Arbitrary, Gen};
 
@@ -98,4 +140,103 @@ mod tests {
             UtxoDeclaration { addrs }
         }
     }
+
+    #[cfg(test)]
+    fn xpub_of(pk: &PublicKey<Ed25519>, chain_code: [u8; 32]) -> XPub {
+        let mut raw = [0u8; XPUB_SIZE];
+        raw[..32].copy_from_slice(pk.as_ref());
+        raw[32..].copy_from_slice(&chain_code);
+        XPub::from_bytes(raw)
+    }
+
+    #[cfg(test)]
+    fn public_key(seed: u8) -> PublicKey<Ed25519> {
+        chain_crypto::SecretKey::<Ed25519>::from_binary(&[seed; 32])
+            .unwrap()
+            .to_public()
+    }
+
+    #[test]
+    fn mainnet_addresses_are_classified() {
+        let daedalus: OldAddress = "DdzFFzCqrhsrcTVhLygT24QwTnNqQqQ8mZrq5jykUzMveU26sxaH529kMpo7VhPrt5pwW3dXeB2k3EEvKcNBRmzCfcQ7dTkyGzTs658C".parse().unwrap();
+        assert_eq!(
+            oldaddress_kind(&daedalus),
+            Some(OldAddressKind::PubKeyWithDerivationPath)
+        );
+        assert_eq!(oldaddress_kind(&OldAddress::new(vec![0])), None);
+
+        // redemption address of the mainnet genesis, with its key
+        let redeem: OldAddress = "Ae2tdPwUPEZHFQnrr2dYB4GEQ8WVKspEyrg29pJ3f7qdjzaxjeShEEokF5f"
+            .parse()
+            .unwrap();
+        assert_eq!(oldaddress_kind(&redeem), Some(OldAddressKind::Redeem));
+        let redeem_key = PublicKey::<Ed25519>::from_binary(&[
+            0xfb, 0x40, 0x49, 0x0e, 0x2f, 0xa0, 0x6a, 0xec, 0xa5, 0x93, 0x82, 0xe9, 0xb5, 0x04,
+            0xe0, 0x8c, 0xc7, 0xa8, 0xee, 0x46, 0x3d, 0x95, 0x30, 0x9b, 0x66, 0xfd, 0x76, 0xbf,
+            0x03, 0x92, 0x4d, 0x99,
+        ])
+        .unwrap();
+        assert_eq!(
+            oldaddress_from_xpub(&redeem, &redeem_key, &[0u8; 32]),
+            OldAddressMatchXPub::Yes
+        );
+        assert_eq!(
+            oldaddress_from_xpub(&redeem, &public_key(1), &[0u8; 32]),
+            OldAddressMatchXPub::No
+        );
+    }
+
+    #[test]
+    fn witness_public_key_matches_every_spendable_kind() {
+        let pk = public_key(1);
+        let other = public_key(2);
+        let chain_code = [9u8; 32];
+        let xpub = xpub_of(&pk, chain_code);
+        let mainnet = Some(764_824_073);
+
+        let mut cases = Vec::new();
+        for network_magic in &[None, mainnet] {
+            cases.push((
+                ExtendedAddr::new_simple(&xpub, *network_magic).to_address(),
+                OldAddressKind::PubKey,
+            ));
+            cases.push((
+                ExtendedAddr::new(
+                    &xpub,
+                    Attributes::new_bootstrap_era(Some(vec![0xa5; 28]), *network_magic),
+                )
+                .to_address(),
+                OldAddressKind::PubKeyWithDerivationPath,
+            ));
+            let mut pkraw = [0u8; 32];
+            pkraw.copy_from_slice(pk.as_ref());
+            cases.push((
+                ExtendedAddr::new_redeem(&pkraw, *network_magic).to_address(),
+                OldAddressKind::Redeem,
+            ));
+        }
+
+        for (address, kind) in cases {
+            assert_eq!(oldaddress_kind(&address), Some(kind));
+            assert_eq!(
+                oldaddress_from_xpub(&address, &pk, &chain_code),
+                OldAddressMatchXPub::Yes,
+                "{:?} {}",
+                kind,
+                address
+            );
+            assert_eq!(
+                oldaddress_from_xpub(&address, &other, &chain_code),
+                OldAddressMatchXPub::No
+            );
+            let expected_with_other_chain_code = match kind {
+                OldAddressKind::Redeem => OldAddressMatchXPub::Yes,
+                _ => OldAddressMatchXPub::No,
+            };
+            assert_eq!(
+                oldaddress_from_xpub(&address, &pk, &[0u8; 32]),
+                expected_with_other_chain_code
+            );
+        }
+    }
 }
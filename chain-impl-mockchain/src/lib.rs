@@ -6,6 +6,7 @@ extern crate quickcheck;
 
 pub mod account;
 pub mod accounting;
+pub mod address;
 pub mod block;
 pub mod certificate;
 pub mod chaineval;
@@ -26,6 +27,7 @@ pub mod multiverse;
 pub mod rewards;
 pub mod setting;
 pub mod stake;
+pub mod tokens;
 pub mod transaction;
 pub mod treasury;
 pub mod update;
@@ -4,6 +4,7 @@ use thiserror::Error;
 
 use super::declaration::{Declaration, DeclarationError, Identifier};
 use crate::accounting::account::{self, DelegationType, Iter, SpendingCounter};
+use crate::date::Epoch;
 use crate::value::{Value, ValueError};
 
 #[derive(Clone, PartialEq, Eq, Default)]
@@ -141,6 +142,24 @@ impl Ledger {
         })
     }
 
+    /// Set the delegation of an account in this ledger, effective from the
+    /// `activation` epoch onward
+    pub fn set_delegation_from(
+        &self,
+        identifier: &Identifier,
+        delegation: &DelegationType,
+        activation: Epoch,
+        now: Epoch,
+    ) -> Result<Self, LedgerError> {
+        let new_accounts = self
+            .accounts
+            .set_delegation_from(identifier, delegation, activation, now)?;
+        Ok(Self {
+            accounts: new_accounts,
+            declarations: self.declarations.clone(),
+        })
+    }
+
     pub fn get_total_value(&self) -> Result<Value, ValueError> {
         self.accounts.get_total_value()
     }
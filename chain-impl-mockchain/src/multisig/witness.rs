@@ -3,7 +3,7 @@ use crate::key::{
 };
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
-use chain_crypto::{Ed25519, PublicKey, Verification};
+use chain_crypto::{Ed25519, PublicKey, ValidationPolicy, Verification};
 
 use std::collections::BTreeMap;
 
@@ -55,6 +55,13 @@ impl Witness {
         };
         true
     }
+
+    /// Check the encoding of the keys and signatures against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        self.0
+            .iter()
+            .all(|(_, pk, sig)| pk.validate(policy).is_ok() && sig.validate(policy).is_ok())
+    }
 }
 
 fn deserialize_index(buf: &mut ReadBuf) -> Result<TreeIndex, ReadError> {
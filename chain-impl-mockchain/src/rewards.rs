@@ -9,6 +9,7 @@ use typed_bytes::ByteBuilder;
 pub enum CompoundingType {
     Linear,
     Halvening,
+    Fixed,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -181,9 +182,9 @@ pub fn rewards_contribution_calculation(
         CompoundingType::Linear => {
             // C - rratio * (#epoch / erate)
             let rr = &params.compounding_ratio;
-            let reduce_by = (rr.numerator * zone) / rr.denominator.get();
-            if params.initial_value >= reduce_by {
-                Value(params.initial_value - reduce_by)
+            let reduce_by = (rr.numerator as u128 * zone as u128) / rr.denominator.get() as u128;
+            if params.initial_value as u128 >= reduce_by {
+                Value(params.initial_value - reduce_by as u64)
             } else {
                 Value::zero()
             }
@@ -196,14 +197,26 @@ pub fn rewards_contribution_calculation(
             let rr = &params.compounding_ratio;
             const SCALE: u128 = 1_000_000_000_000_000_000;
 
+            let numerator = rr.numerator as u128;
+            let denominator = rr.denominator.get() as u128;
+
             let mut acc = params.initial_value as u128 * SCALE;
-            for _ in 0..zone {
-                acc *= rr.numerator as u128;
-                acc /= rr.denominator.get() as u128;
+            if numerator != denominator {
+                for _ in 0..zone {
+                    if acc == 0 {
+                        break;
+                    }
+                    // same as acc * numerator / denominator, without
+                    // overflowing for the ratios up to 1 accepted by the
+                    // settings
+                    acc =
+                        acc / denominator * numerator + acc % denominator * numerator / denominator;
+                }
             }
 
             Value((acc / SCALE) as u64)
         }
+        CompoundingType::Fixed => Value(params.initial_value),
     };
 
     match params.reward_drawing_limit_max {
@@ -352,6 +365,89 @@ mod tests {
         );
     }
 
+    #[cfg(test)]
+    fn drawn_per_epoch(params: &Parameters, epochs: Epoch) -> Vec<u64> {
+        let system_info = SystemInformation {
+            declared_stake: Stake::from_value(Value(100)),
+        };
+        (0..epochs)
+            .map(|epoch| rewards_contribution_calculation(epoch, params, &system_info).0)
+            .collect()
+    }
+
+    #[test]
+    fn rewards_contribution_calculation_fixed_is_constant() {
+        let params = Parameters {
+            initial_value: 10_000,
+            compounding_type: CompoundingType::Fixed,
+            epoch_rate: NonZeroU32::new(2).unwrap(),
+            epoch_start: 10,
+            ..Parameters::zero()
+        };
+        let drawn = drawn_per_epoch(&params, 1_000);
+        assert!(drawn[..10].iter().all(|value| *value == 0));
+        assert!(drawn[10..].iter().all(|value| *value == 10_000));
+    }
+
+    #[test]
+    fn rewards_contribution_calculation_schedules() {
+        let linear = Parameters {
+            initial_value: 10_000,
+            compounding_ratio: Ratio {
+                numerator: 1_000,
+                denominator: NonZeroU64::new(1).unwrap(),
+            },
+            compounding_type: CompoundingType::Linear,
+            epoch_rate: NonZeroU32::new(2).unwrap(),
+            epoch_start: 10,
+            ..Parameters::zero()
+        };
+        let halving = Parameters {
+            compounding_ratio: Ratio {
+                numerator: 1,
+                denominator: NonZeroU64::new(2).unwrap(),
+            },
+            compounding_type: CompoundingType::Halvening,
+            ..linear.clone()
+        };
+
+        assert_eq!(
+            drawn_per_epoch(&linear, 15)[9..],
+            [0, 10_000, 10_000, 9_000, 9_000, 8_000]
+        );
+        assert_eq!(
+            drawn_per_epoch(&halving, 15)[9..],
+            [0, 10_000, 10_000, 5_000, 5_000, 2_500]
+        );
+        // the pot is not drawn from anymore once the formula reaches zero
+        assert!(drawn_per_epoch(&linear, 1_000)[30..]
+            .iter()
+            .all(|value| *value == 0));
+        assert!(drawn_per_epoch(&halving, 1_000)[40..]
+            .iter()
+            .all(|value| *value == 0));
+    }
+
+    #[quickcheck]
+    fn rewards_contribution_calculation_never_increases(
+        params: Parameters,
+        epoch: Epoch,
+    ) -> TestResult {
+        if params.compounding_ratio.numerator > params.compounding_ratio.denominator.get()
+            && params.compounding_type == CompoundingType::Halvening
+        {
+            return TestResult::discard();
+        }
+        let system_info = SystemInformation {
+            declared_stake: Stake::from_value(Value(u64::MAX)),
+        };
+        let epoch = params.epoch_start.saturating_add(epoch % 1_000);
+        let current = rewards_contribution_calculation(epoch, &params, &system_info);
+        let next_zone = epoch.saturating_add(params.epoch_rate.get());
+        let next = rewards_contribution_calculation(next_zone, &params, &system_info);
+        TestResult::from_bool(next <= current)
+    }
+
     impl Arbitrary for TaxType {
         fn arbitrary<G: Gen>(gen: &mut G) -> Self {
             let fixed = Arbitrary::arbitrary(gen);
@@ -404,10 +500,11 @@ mod tests {
 
     impl Arbitrary for CompoundingType {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            let option: u8 = u8::arbitrary(g) % 2;
+            let option: u8 = u8::arbitrary(g) % 3;
             match option {
                 0 => CompoundingType::Linear,
-                2 => CompoundingType::Halvening,
+                1 => CompoundingType::Halvening,
+                2 => CompoundingType::Fixed,
                 _ => unreachable!(),
             }
         }
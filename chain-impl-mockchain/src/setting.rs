@@ -1,18 +1,23 @@
 //! define the Blockchain settings
 //!
 
-use crate::fragment::{config::ConfigParams, BlockContentSize};
+use crate::fragment::{config::ConfigParams, BlockContentSize, BlockContentWeight};
 use crate::milli::Milli;
 use crate::update;
 use crate::{
     chaineval::PraosNonce,
     chaintypes::ConsensusType,
     config::{ConfigParam, RewardParams},
-    fee::LinearFee,
-    key::BftLeaderId,
+    date::Epoch,
+    fee::{GovernanceFeeDiscount, LinearFee},
+    key::{BftLeaderId, HashVersion},
+    ledger::governance::TreasuryBudget,
     rewards,
+    transaction::BindingSignatureContext,
+    value::Value,
     vote::CommitteeId,
 };
+use chain_crypto::ValidationPolicy;
 use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
@@ -28,6 +33,11 @@ pub struct Settings {
     pub epoch_stability_depth: u32,
     pub active_slots_coeff: ActiveSlotsCoeff,
     pub block_content_max_size: BlockContentSize,
+    /// Maximum total weight of the fragments of a block, unlimited if none
+    pub block_content_max_weight: Option<BlockContentWeight>,
+    /// Size of the block content the block producers aim for, below the
+    /// maximum size, see [`Settings::block_content_soft_max_size`]
+    pub block_content_soft_max_size: Option<BlockContentSize>,
     pub bft_leaders: Arc<Box<[BftLeaderId]>>,
     pub linear_fees: LinearFee,
     /// The number of epochs that a proposal remains valid. To be
@@ -41,6 +51,50 @@ pub struct Settings {
     pub rewards_limit: rewards::Limit,
     pub pool_participation_capping: Option<(NonZeroU32, NonZeroU32)>,
     pub committees: Arc<Box<[CommitteeId]>>,
+    /// Value locked when registering a stake pool, refunded on retirement
+    pub pool_registration_deposit: Value,
+    /// Value locked when creating a vote plan, refunded once it is tallied
+    pub vote_plan_deposit: Value,
+    /// Maximum number of vote plans submitted by a committee member which
+    /// have not elapsed, unlimited if none
+    pub max_active_vote_plans_per_committee: Option<u32>,
+    /// Value locked when registering a handle, refunded once the handle has
+    /// expired and is registered by another account
+    pub handle_registration_deposit: Value,
+    /// Number of epochs following the epoch of its registration during which
+    /// a handle remains valid, forever if none
+    pub handle_validity: Option<u32>,
+    /// Whether the fragments of a block have to be in their canonical order,
+    /// see [`ContentsBuilder::sort_canonically`](crate::fragment::ContentsBuilder::sort_canonically)
+    pub canonical_fragment_order: bool,
+    /// Number of epochs without spending from it after which an account
+    /// does not count in the tally of the votes, every account counts if
+    /// none
+    pub voting_inactivity_threshold: Option<u32>,
+    /// Whether the actions accepted by the tally of a vote plan wait for
+    /// the certification of its results by the committee, see
+    /// [`VoteResultCertification`](crate::certificate::VoteResultCertification)
+    pub vote_result_certification: bool,
+    /// Maximum value the treasury actions can draw from the treasury per
+    /// budget period, unlimited if none
+    pub treasury_budget: Option<TreasuryBudget>,
+    /// Number of committee members drawn among the candidates to the
+    /// committee of a vote plan, see
+    /// [`CommitteeSortition`](crate::vote::CommitteeSortition); the
+    /// candidacies are rejected if none
+    pub committee_sortition_size: Option<u32>,
+    /// Epoch from which the binding signatures have to be made in the
+    /// [`TransactionBindingContext`](crate::transaction::TransactionBindingContext),
+    /// the signatures made without context are accepted forever if none
+    pub binding_signature_context_epoch: Option<Epoch>,
+    /// Epoch from which the ids of the fragments, pools and vote plans and
+    /// the hashes signed by the witnesses are computed with
+    /// [`HashVersion::V1`], they stay of the version 0 if none
+    pub hash_version_epoch: Option<Epoch>,
+    /// Checks done on the encodings of the public keys and signatures of
+    /// the witnesses, certificates and BFT blocks on top of the signature
+    /// verification, see [`ValidationPolicy`]
+    pub validation_policy: ValidationPolicy,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -108,6 +162,19 @@ impl Default for FeesGoesTo {
 
 pub const SLOTS_PERCENTAGE_RANGE: u8 = 100;
 
+/// The settings set at block0 which cannot be changed afterward, the ones
+/// `Settings::apply` refuses to change
+pub(crate) fn is_read_only(param: &ConfigParam) -> bool {
+    matches!(
+        param,
+        ConfigParam::Block0Date(_)
+            | ConfigParam::Discrimination(_)
+            | ConfigParam::TreasuryAdd(_)
+            | ConfigParam::RewardPot(_)
+            | ConfigParam::KESUpdateSpeed(_)
+    )
+}
+
 impl Settings {
     pub fn new() -> Self {
         Self {
@@ -118,6 +185,8 @@ impl Settings {
             epoch_stability_depth: 10, // num of block
             active_slots_coeff: ActiveSlotsCoeff::try_from(Milli::HALF).unwrap(),
             block_content_max_size: 102_400,
+            block_content_max_weight: None,
+            block_content_soft_max_size: None,
             bft_leaders: Arc::new(Box::new([])),
             linear_fees: LinearFee::new(0, 0, 0),
             proposal_expiration: 100,
@@ -127,6 +196,19 @@ impl Settings {
             rewards_limit: rewards::Limit::None,
             pool_participation_capping: None,
             committees: Arc::new(Box::new([])),
+            pool_registration_deposit: Value::zero(),
+            vote_plan_deposit: Value::zero(),
+            max_active_vote_plans_per_committee: None,
+            handle_registration_deposit: Value::zero(),
+            handle_validity: None,
+            canonical_fragment_order: false,
+            voting_inactivity_threshold: None,
+            vote_result_certification: false,
+            treasury_budget: None,
+            committee_sortition_size: None,
+            binding_signature_context_epoch: None,
+            hash_version_epoch: None,
+            validation_policy: ValidationPolicy::Legacy,
         }
     }
 
@@ -134,10 +216,38 @@ impl Settings {
         self.linear_fees
     }
 
+    /// The size of the block content the block producers should not go
+    /// beyond. Unlike the maximum size it is not enforced when validating a
+    /// block, it defaults to the maximum size and never exceeds it.
+    pub fn block_content_soft_max_size(&self) -> BlockContentSize {
+        self.block_content_soft_max_size
+            .map_or(self.block_content_max_size, |soft| {
+                soft.min(self.block_content_max_size)
+            })
+    }
+
+    /// Which binding signatures are accepted in the given epoch
+    pub fn binding_signature_context(&self, epoch: Epoch) -> BindingSignatureContext {
+        match self.binding_signature_context_epoch {
+            Some(activation) if epoch >= activation => BindingSignatureContext::Required,
+            _ => BindingSignatureContext::Optional,
+        }
+    }
+
+    /// Which version of the ids and signed hashes is used in the given epoch
+    pub fn hash_version(&self, epoch: Epoch) -> HashVersion {
+        match self.hash_version_epoch {
+            Some(activation) if epoch >= activation => HashVersion::V1,
+            _ => HashVersion::V0,
+        }
+    }
+
     pub fn apply(&self, changes: &ConfigParams) -> Result<Self, update::Error> {
         let mut new_state = self.clone();
         let mut per_certificate_fees = None;
         let mut per_vote_certificate_fees = None;
+        let mut per_token_fee = None;
+        let mut governance_fee_discount = None;
 
         for param in changes.iter() {
             match param {
@@ -166,6 +276,51 @@ impl Settings {
                 ConfigParam::BlockContentMaxSize(d) => {
                     new_state.block_content_max_size = *d;
                 }
+                ConfigParam::BlockContentMaxWeight(d) => {
+                    new_state.block_content_max_weight = Some(*d);
+                }
+                ConfigParam::BlockContentSoftMaxSize(d) => {
+                    new_state.block_content_soft_max_size = Some(*d);
+                }
+                ConfigParam::PoolRegistrationDeposit(d) => {
+                    new_state.pool_registration_deposit = *d;
+                }
+                ConfigParam::VotePlanDeposit(d) => {
+                    new_state.vote_plan_deposit = *d;
+                }
+                ConfigParam::MaxActiveVotePlansPerCommittee(d) => {
+                    new_state.max_active_vote_plans_per_committee = Some(*d);
+                }
+                ConfigParam::HandleRegistrationDeposit(d) => {
+                    new_state.handle_registration_deposit = *d;
+                }
+                ConfigParam::HandleValidity(d) => {
+                    new_state.handle_validity = Some(*d);
+                }
+                ConfigParam::CanonicalFragmentOrder(d) => {
+                    new_state.canonical_fragment_order = *d;
+                }
+                ConfigParam::VotingInactivityThreshold(d) => {
+                    new_state.voting_inactivity_threshold = Some(*d);
+                }
+                ConfigParam::VoteResultCertification(d) => {
+                    new_state.vote_result_certification = *d;
+                }
+                ConfigParam::TreasuryBudget(d) => {
+                    new_state.treasury_budget = Some(*d);
+                }
+                ConfigParam::CommitteeSortitionSize(d) => {
+                    new_state.committee_sortition_size = Some(*d);
+                }
+                ConfigParam::BindingSignatureContextEpoch(d) => {
+                    new_state.binding_signature_context_epoch = Some(*d);
+                }
+                ConfigParam::HashVersionEpoch(d) => {
+                    new_state.hash_version_epoch = Some(*d);
+                }
+                ConfigParam::ValidationPolicy(d) => {
+                    new_state.validation_policy = *d;
+                }
                 ConfigParam::AddBftLeader(d) => {
                     // FIXME: O(n)
                     let mut v = new_state.bft_leaders.to_vec();
@@ -195,6 +350,11 @@ impl Settings {
                     new_state.proposal_expiration = *d;
                 }
                 ConfigParam::RewardParams(rp) => {
+                    if let RewardParams::Halving { ratio, .. } = rp {
+                        if ratio.numerator > ratio.denominator.get() {
+                            return Err(update::Error::HalvingRatioAboveOne(*ratio));
+                        }
+                    }
                     new_state.reward_params = Some(rp.clone());
                 }
                 ConfigParam::TreasuryParams(rp) => {
@@ -206,6 +366,12 @@ impl Settings {
                 ConfigParam::PerVoteCertificateFees(pcf) => {
                     per_vote_certificate_fees = Some(pcf);
                 }
+                ConfigParam::PerTokenFee(fee) => {
+                    per_token_fee = Some(fee);
+                }
+                ConfigParam::GovernanceFeeDiscount(discount) => {
+                    governance_fee_discount = Some(discount);
+                }
                 ConfigParam::FeesInTreasury(value) => {
                     new_state.fees_goes_to = if *value {
                         FeesGoesTo::Treasury
@@ -248,9 +414,41 @@ impl Settings {
             new_state.linear_fees.per_vote_certificate_fees(*pcf);
         }
 
+        if let Some(fee) = per_token_fee {
+            new_state.linear_fees.per_token(*fee);
+        }
+
+        if let Some(discount) = governance_fee_discount {
+            new_state.linear_fees.governance_fee_discount(*discount);
+        }
+
         Ok(new_state)
     }
 
+    /// Check that the selected consensus can elect slot leaders with these
+    /// settings. The consensus version can be changed by an update proposal,
+    /// so this guards against switching to (or staying in) BFT without any
+    /// BFT leader left.
+    pub fn check_consensus(&self) -> Result<(), update::Error> {
+        match self.consensus_version {
+            ConsensusType::Bft if self.bft_leaders.is_empty() => Err(update::Error::NoBftLeaders),
+            ConsensusType::Bft | ConsensusType::GenesisPraos => Ok(()),
+        }
+    }
+
+    /// Check that the settings are coherent with each other, for changes
+    /// applied together (e.g. the slot duration and the number of slots
+    /// per epoch, which together define the duration of an epoch).
+    pub fn check_coherence(&self) -> Result<(), update::Error> {
+        if self.slots_per_epoch == 0 || self.slot_duration == 0 {
+            return Err(update::Error::InvalidEpochDuration {
+                slots_per_epoch: self.slots_per_epoch,
+                slot_duration: self.slot_duration,
+            });
+        }
+        self.check_consensus()
+    }
+
     pub fn to_config_params(&self) -> ConfigParams {
         let mut params = ConfigParams::new();
 
@@ -264,11 +462,62 @@ impl Settings {
         params.push(ConfigParam::BlockContentMaxSize(
             self.block_content_max_size,
         ));
+        if let Some(weight) = self.block_content_max_weight {
+            params.push(ConfigParam::BlockContentMaxWeight(weight));
+        }
+        if let Some(size) = self.block_content_soft_max_size {
+            params.push(ConfigParam::BlockContentSoftMaxSize(size));
+        }
         for bft_leader in self.bft_leaders.iter() {
             params.push(ConfigParam::AddBftLeader(bft_leader.clone()));
         }
         params.push(ConfigParam::LinearFee(self.linear_fees));
+        if self.linear_fees.per_token != 0 {
+            params.push(ConfigParam::PerTokenFee(self.linear_fees.per_token));
+        }
+        if self.linear_fees.governance_fee_discount != GovernanceFeeDiscount::default() {
+            params.push(ConfigParam::GovernanceFeeDiscount(
+                self.linear_fees.governance_fee_discount,
+            ));
+        }
         params.push(ConfigParam::ProposalExpiration(self.proposal_expiration));
+        params.push(ConfigParam::PoolRegistrationDeposit(
+            self.pool_registration_deposit,
+        ));
+        params.push(ConfigParam::VotePlanDeposit(self.vote_plan_deposit));
+        if let Some(max) = self.max_active_vote_plans_per_committee {
+            params.push(ConfigParam::MaxActiveVotePlansPerCommittee(max));
+        }
+        params.push(ConfigParam::HandleRegistrationDeposit(
+            self.handle_registration_deposit,
+        ));
+        if let Some(validity) = self.handle_validity {
+            params.push(ConfigParam::HandleValidity(validity));
+        }
+        if self.canonical_fragment_order {
+            params.push(ConfigParam::CanonicalFragmentOrder(true));
+        }
+        if let Some(threshold) = self.voting_inactivity_threshold {
+            params.push(ConfigParam::VotingInactivityThreshold(threshold));
+        }
+        if self.vote_result_certification {
+            params.push(ConfigParam::VoteResultCertification(true));
+        }
+        if let Some(budget) = self.treasury_budget {
+            params.push(ConfigParam::TreasuryBudget(budget));
+        }
+        if let Some(size) = self.committee_sortition_size {
+            params.push(ConfigParam::CommitteeSortitionSize(size));
+        }
+        if let Some(epoch) = self.binding_signature_context_epoch {
+            params.push(ConfigParam::BindingSignatureContextEpoch(epoch));
+        }
+        if let Some(epoch) = self.hash_version_epoch {
+            params.push(ConfigParam::HashVersionEpoch(epoch));
+        }
+        if self.validation_policy != ValidationPolicy::Legacy {
+            params.push(ConfigParam::ValidationPolicy(self.validation_policy));
+        }
 
         match &self.reward_params {
             Some(p) => params.push(ConfigParam::RewardParams(p.clone())),
@@ -318,6 +567,18 @@ impl Settings {
                 reward_drawing_limit_max,
                 pool_participation_capping,
             },
+            Some(RewardParams::Fixed {
+                constant,
+                epoch_start,
+            }) => rewards::Parameters {
+                initial_value: constant,
+                compounding_ratio: rewards::Ratio::zero(),
+                compounding_type: rewards::CompoundingType::Fixed,
+                epoch_start,
+                epoch_rate: NonZeroU32::new(1).unwrap(),
+                reward_drawing_limit_max,
+                pool_participation_capping,
+            },
         }
     }
 }
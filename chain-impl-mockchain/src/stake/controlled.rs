@@ -57,6 +57,44 @@ impl StakeControl {
         Self::new().update_accounts(accounts).update_utxos(utxos)
     }
 
+    /// Same as [`new_with`](Self::new_with) leaving out the value of the
+    /// accounts not passing the filter. The stake in UTxO is not filtered.
+    pub fn new_with_filter<F>(
+        accounts: &account::Ledger,
+        utxos: &utxo::Ledger<Address>,
+        filter: F,
+    ) -> Self
+    where
+        F: Fn(&Identifier) -> bool,
+    {
+        accounts
+            .iter()
+            .filter(|(identifier, _)| filter(identifier))
+            .fold(Self::new(), |sc, (identifier, account)| {
+                sc.add_to(identifier.clone(), Stake::from_value(account.value()))
+            })
+            .update_utxos(utxos)
+    }
+
+    /// Stake of the accounts of a voting power snapshot. The whole `total`
+    /// of the snapshot is assigned, including the voting power of the
+    /// accounts not given.
+    pub fn new_with_snapshot<I>(total: Stake, powers: I) -> Self
+    where
+        I: IntoIterator<Item = (Identifier, Stake)>,
+    {
+        let control = powers
+            .into_iter()
+            .fold(Hamt::new(), |control, (identifier, stake)| {
+                control.insert_or_update_simple(identifier, stake, |v: &Stake| v.checked_add(stake))
+            });
+        Self {
+            assigned: total,
+            unassigned: Stake::zero(),
+            control,
+        }
+    }
+
     pub fn total(&self) -> Stake {
         self.assigned + self.unassigned
     }
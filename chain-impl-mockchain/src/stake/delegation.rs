@@ -1,5 +1,6 @@
 use crate::certificate::{PoolId, PoolRegistration, PoolRegistrationHash};
 use crate::date::Epoch;
+use crate::key::HashVersion;
 use crate::value::Value;
 use imhamt::Hamt;
 use std::collections::hash_map::DefaultHasher;
@@ -49,8 +50,10 @@ impl PoolState {
         }
     }
 
-    pub fn current_pool_registration_hash(&self) -> PoolRegistrationHash {
-        self.registration.to_id()
+    /// The hash of the registration, computed with the version of the date
+    /// of the update referring to it
+    pub fn current_pool_registration_hash(&self, version: HashVersion) -> PoolRegistrationHash {
+        self.registration.to_id_with_version(version)
     }
 }
 
@@ -161,7 +164,17 @@ impl PoolsState {
     }
 
     pub fn register_stake_pool(&self, owner: PoolRegistration) -> Result<Self, PoolError> {
-        let id = owner.to_id();
+        self.register_stake_pool_with_version(owner, HashVersion::CURRENT)
+    }
+
+    /// Register the pool under its id computed with the given version, it
+    /// keeps this id afterwards
+    pub fn register_stake_pool_with_version(
+        &self,
+        owner: PoolRegistration,
+        version: HashVersion,
+    ) -> Result<Self, PoolError> {
+        let id = owner.to_id_with_version(version);
         let new_pools = self
             .stake_pools
             .insert(id.clone(), PoolState::new(owner))
@@ -3,6 +3,7 @@ use crate::{
     account,
     accounting::account::DelegationType,
     certificate::{PoolId, PoolRegistration},
+    date::Epoch,
     utxo,
 };
 use chain_addr::{Address, Kind};
@@ -157,10 +158,14 @@ fn assign_account_value(
 /// Everything that is linked to a stake pool that doesn't exist, will be added to dangling stake,
 /// whereas all the utxo / accounts that doesn't have any delegation setup, will be counted towards
 /// the unassigned stake.
+///
+/// Delegations are taken as they are in effect during `epoch`, pending delegations
+/// with a later activation epoch are ignored.
 pub fn get_distribution(
     accounts: &account::Ledger,
     dstate: &PoolsState,
     utxos: &utxo::Ledger<Address>,
+    epoch: Epoch,
 ) -> StakeDistribution {
     let mut distribution = StakeDistribution {
         unassigned: Stake::zero(),
@@ -184,7 +189,7 @@ pub fn get_distribution(
         assign_account_value(
             &mut distribution,
             identifier,
-            &account_state.delegation(),
+            account_state.delegation_at(epoch),
             Stake::from_value(account_state.value()),
         )
     }
@@ -212,7 +217,7 @@ pub fn get_distribution(
                     Ok(st) => assign_account_value(
                         &mut distribution,
                         &identifier,
-                        &st.delegation(),
+                        st.delegation_at(epoch),
                         Stake::from_value(output.value),
                     ),
                 }
@@ -473,7 +478,7 @@ mod tests {
         }
 
         // verify
-        let distribution = super::get_distribution(&accounts, &dstate, &utxos);
+        let distribution = super::get_distribution(&accounts, &dstate, &utxos, 0);
 
         if distribution.unassigned != stake_distribution_data.calculate_unassigned() {
             return TestResult::error(format!(
@@ -0,0 +1,149 @@
+//! Canonical serialization of the stake distribution of an epoch.
+//!
+//! The stake distribution is kept in hash maps whose iteration order is not
+//! the same from a node to another. External snapshot tools get the
+//! distribution of an epoch as a [`StakeDistributionSnapshot`] instead,
+//! whose encoding only depends on the content of the distribution, so that
+//! the snapshots of all the nodes are byte identical and can be compared by
+//! their digest.
+//!
+//! The encoding, all integers being big endian, is:
+//!
+//! * the version of the encoding, as a `u8`
+//! * the epoch, as a `u32`
+//! * the unassigned stake and the dangling stake, as `u64`
+//! * the number of pools, as a `u32`, then for each pool, by increasing pool
+//!   ID: the pool ID, the total stake of the pool as a `u64`, the number of
+//!   accounts delegating to the pool as a `u32`, then for each account, by
+//!   increasing identifier: the account identifier and its stake as a `u64`
+//!
+//! The registrations of the pools are not part of the snapshot, they are
+//! identified by their ID.
+
+use super::distribution::StakeDistribution;
+use crate::{account, date::Epoch};
+use chain_crypto::{digest::DigestOf, Blake2b256, PublicKey};
+use std::convert::TryInto;
+use typed_bytes::{ByteArray, ByteBuilder};
+
+/// Version of the encoding of the snapshots
+pub const STAKE_SNAPSHOT_VERSION: u8 = 1;
+
+pub type StakeDistributionSnapshotId = DigestOf<Blake2b256, StakeDistributionSnapshot>;
+
+/// The stake distribution of an epoch in its canonical encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeDistributionSnapshot {
+    epoch: Epoch,
+    bytes: ByteArray<StakeDistributionSnapshot>,
+}
+
+impl StakeDistributionSnapshot {
+    pub fn new(epoch: Epoch, distribution: &StakeDistribution) -> Self {
+        let mut pools: Vec<_> = distribution.to_pools.iter().collect();
+        pools.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let bytes = ByteBuilder::new()
+            .u8(STAKE_SNAPSHOT_VERSION)
+            .u32(epoch)
+            .u64(distribution.unassigned.0)
+            .u64(distribution.dangling.0)
+            .u32(pools.len().try_into().unwrap())
+            .fold(pools.into_iter(), |bb, (pool_id, pool)| {
+                let mut accounts: Vec<_> = pool.stake.iter().collect();
+                accounts.sort_by(|(a, _), (b, _)| a.cmp(b));
+                bb.bytes(pool_id.as_ref())
+                    .u64(pool.stake.total.0)
+                    .u32(accounts.len().try_into().unwrap())
+                    .fold(accounts.into_iter(), |bb, (id, stake)| {
+                        let public_key: &PublicKey<account::AccountAlg> = id.as_ref();
+                        bb.bytes(public_key.as_ref()).u64(stake.0)
+                    })
+            })
+            .finalize();
+        Self { epoch, bytes }
+    }
+
+    /// the epoch the stake distribution is in effect for
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    pub fn digest(&self) -> StakeDistributionSnapshotId {
+        DigestOf::digest_byteslice(&self.bytes.as_byteslice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::Identifier,
+        certificate::PoolId,
+        stake::{PoolStakeDistribution, PoolStakeInformation, Stake},
+        testing::TestGen,
+    };
+    use std::collections::HashMap;
+
+    fn distribution(pools: &[(PoolId, Vec<(Identifier, u64)>)]) -> StakeDistribution {
+        let mut to_pools = HashMap::new();
+        for (pool_id, accounts) in pools {
+            let mut stake = PoolStakeDistribution::new();
+            for (id, value) in accounts {
+                stake.add(id.clone(), Stake(*value));
+            }
+            to_pools.insert(
+                pool_id.clone(),
+                PoolStakeInformation {
+                    registration: None,
+                    stake,
+                },
+            );
+        }
+        StakeDistribution {
+            unassigned: Stake(10),
+            dangling: Stake(20),
+            to_pools,
+        }
+    }
+
+    #[test]
+    fn snapshot_does_not_depend_on_the_insertion_order() {
+        let (first, second) = (TestGen::stake_pool().id(), TestGen::stake_pool().id());
+        let accounts: Vec<_> = (0..10u64)
+            .map(|value| (TestGen::identifier(), value))
+            .collect();
+        let mut reversed = accounts.clone();
+        reversed.reverse();
+
+        let one = StakeDistributionSnapshot::new(
+            1,
+            &distribution(&[
+                (first.clone(), accounts.clone()),
+                (second.clone(), Vec::new()),
+            ]),
+        );
+        let other = StakeDistributionSnapshot::new(
+            1,
+            &distribution(&[(second.clone(), Vec::new()), (first.clone(), reversed)]),
+        );
+        assert_eq!(one.as_bytes(), other.as_bytes());
+        assert_eq!(one.digest(), other.digest());
+
+        let next_epoch = StakeDistributionSnapshot::new(
+            2,
+            &distribution(&[(first, accounts), (second, Vec::new())]),
+        );
+        assert_ne!(one.digest(), next_epoch.digest());
+        // version, epoch, unassigned, dangling, pools, then for each pool its
+        // ID, total and number of accounts, then each account
+        assert_eq!(
+            one.as_bytes().len(),
+            1 + 4 + 8 + 8 + 4 + 2 * (32 + 8 + 4) + 10 * (32 + 8)
+        );
+    }
+}
@@ -63,10 +63,7 @@ impl From<AverageValue> for Value {
 
 impl Arbitrary for Output<Address> {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
-        Output {
-            address: Arbitrary::arbitrary(g),
-            value: Arbitrary::arbitrary(g),
-        }
+        Output::from_address(Arbitrary::arbitrary(g), Arbitrary::arbitrary(g))
     }
 }
 
@@ -1,9 +1,11 @@
 use crate::{
     account::{DelegationType, Identifier},
     certificate::{
-        Certificate, OwnerStakeDelegation, PoolId, PoolRegistration, PoolRetirement, PoolUpdate,
-        StakeDelegation, VotePlanId, VoteTally,
+        Certificate, DeferredOwnerStakeDelegation, DeferredStakeDelegation, Handle,
+        HandleRegistration, OwnerStakeDelegation, PoolId, PoolKeysRotation, PoolRegistration,
+        PoolRetirement, PoolUpdate, StakeDelegation, VotePlanId, VoteTally,
     },
+    date::Epoch,
     testing::data::AddressData,
     transaction::UnspecifiedAccountIdentifier,
 };
@@ -22,6 +24,29 @@ pub fn build_stake_delegation_cert(
     })
 }
 
+pub fn build_stake_delegation_cert_from(
+    stake_pool: &PoolRegistration,
+    delegate_from: &AddressData,
+    activation: Epoch,
+) -> Certificate {
+    let account_id = UnspecifiedAccountIdentifier::from_single_account(Identifier::from(
+        delegate_from.delegation_key(),
+    ));
+    Certificate::DeferredStakeDelegation(DeferredStakeDelegation {
+        delegation: StakeDelegation {
+            account_id,
+            delegation: DelegationType::Full(stake_pool.to_id()),
+        },
+        activation,
+    })
+}
+
+pub fn build_handle_registration_cert(handle: Handle, owner: &AddressData) -> Certificate {
+    let account_id =
+        UnspecifiedAccountIdentifier::from_single_account(Identifier::from(owner.delegation_key()));
+    Certificate::HandleRegistration(HandleRegistration { handle, account_id })
+}
+
 pub fn build_stake_pool_registration_cert(stake_pool: &PoolRegistration) -> Certificate {
     Certificate::PoolRegistration(stake_pool.clone())
 }
@@ -30,6 +55,10 @@ pub fn build_stake_pool_update_cert(stake_pool: &PoolUpdate) -> Certificate {
     Certificate::PoolUpdate(stake_pool.clone())
 }
 
+pub fn build_stake_pool_keys_rotation_cert(rotation: &PoolKeysRotation) -> Certificate {
+    Certificate::PoolKeysRotation(rotation.clone())
+}
+
 pub fn build_owner_stake_full_delegation(stake_pool: PoolId) -> Certificate {
     Certificate::OwnerStakeDelegation(OwnerStakeDelegation {
         delegation: DelegationType::Full(stake_pool),
@@ -48,6 +77,18 @@ pub fn build_owner_stake_delegation(delegation_type: DelegationType) -> Certific
     })
 }
 
+pub fn build_owner_stake_delegation_from(
+    delegation_type: DelegationType,
+    activation: Epoch,
+) -> Certificate {
+    Certificate::DeferredOwnerStakeDelegation(DeferredOwnerStakeDelegation {
+        delegation: OwnerStakeDelegation {
+            delegation: delegation_type,
+        },
+        activation,
+    })
+}
+
 pub fn build_stake_pool_retirement_cert(pool_id: PoolId, start_validity: u64) -> Certificate {
     let retirement = PoolRetirement {
         pool_id,
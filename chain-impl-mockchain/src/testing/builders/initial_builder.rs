@@ -1,6 +1,8 @@
 use crate::{
     account::DelegationType,
-    certificate::{Certificate, PoolUpdate, VoteCast, VotePlan, VoteTally},
+    certificate::{
+        Certificate, PoolUpdate, VoteCast, VotePlan, VoteResultCertification, VoteTally,
+    },
     fragment::Fragment,
     key::EitherEd25519SecretKey,
     ledger::ledger::OutputAddress,
@@ -71,6 +73,19 @@ pub fn create_initial_vote_tally(vote_tally: &VoteTally, owners: &[Wallet]) -> F
     fragment(cert, keys, &[], &[])
 }
 
+pub fn create_initial_vote_result_certification(
+    certification: &VoteResultCertification,
+    owners: &[Wallet],
+) -> Fragment {
+    let cert: Certificate = certification.clone().into();
+    let keys: Vec<EitherEd25519SecretKey> = owners
+        .iter()
+        .cloned()
+        .map(|owner| owner.private_key())
+        .collect();
+    fragment(cert, keys, &[], &[])
+}
+
 pub fn create_initial_transaction(wallet: &Wallet) -> Fragment {
     let tx = TxBuilder::new()
         .set_nopayload()
@@ -111,11 +126,19 @@ fn fragment(
         Certificate::StakeDelegation(s) => {
             let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
             let signature = AccountBindingSignature::new_single(&builder.get_auth_data(), |d| {
-                keys[0].sign_slice(&d.0)
+                keys[0].sign_slice_with_context(&d.0)
             });
             let tx = builder.set_payload_auth(&signature);
             Fragment::StakeDelegation(tx)
         }
+        Certificate::DeferredStakeDelegation(s) => {
+            let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
+            let signature = AccountBindingSignature::new_single(&builder.get_auth_data(), |d| {
+                keys[0].sign_slice_with_context(&d.0)
+            });
+            let tx = builder.set_payload_auth(&signature);
+            Fragment::DeferredStakeDelegation(tx)
+        }
         Certificate::PoolRegistration(s) => {
             let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
             let signature = pool_owner_sign(&keys, &builder);
@@ -128,6 +151,12 @@ fn fragment(
             let tx = builder.set_payload_auth(&signature);
             Fragment::PoolUpdate(tx)
         }
+        Certificate::PoolKeysRotation(s) => {
+            let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
+            let signature = pool_owner_sign(&keys, &builder);
+            let tx = builder.set_payload_auth(&signature);
+            Fragment::PoolKeysRotation(tx)
+        }
         Certificate::VotePlan(s) => {
             let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
             let signature = plan_sign(&keys, &builder);
@@ -139,6 +168,11 @@ fn fragment(
             let tx = builder.set_payload_auth(&());
             Fragment::VoteCast(tx)
         }
+        Certificate::VoteCastBatch(s) => {
+            let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
+            let tx = builder.set_payload_auth(&());
+            Fragment::VoteCastBatch(tx)
+        }
         Certificate::VoteTally(s) => {
             let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
             let signature = tally_sign(&keys, &s, &builder);
@@ -150,6 +184,26 @@ fn fragment(
             let tx = builder.set_payload_auth(&());
             Fragment::OwnerStakeDelegation(tx)
         }
+        Certificate::HandleRegistration(s) => {
+            let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
+            let signature = AccountBindingSignature::new_single(&builder.get_auth_data(), |d| {
+                keys[0].sign_slice_with_context(&d.0)
+            });
+            let tx = builder.set_payload_auth(&signature);
+            Fragment::HandleRegistration(tx)
+        }
+        Certificate::VotingPowerSnapshot(s) => {
+            let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
+            let signature = plan_sign(&keys, &builder);
+            let tx = builder.set_payload_auth(&signature);
+            Fragment::VotingPowerSnapshot(tx)
+        }
+        Certificate::VoteResultCertification(s) => {
+            let builder = set_initial_ios(TxBuilder::new().set_payload(&s), inputs, outputs);
+            let signatures = certification_sign(&keys, &builder);
+            let tx = builder.set_payload_auth(&signatures);
+            Fragment::VoteResultCertification(tx)
+        }
         _ => unreachable!(),
     }
 }
@@ -16,6 +16,7 @@ pub struct StakePoolBuilder {
     operators: Vec<PublicKey<Ed25519>>,
     pool_permissions: Option<PoolPermissions>,
     reward_account: bool,
+    delegators_account: bool,
     tax_type: TaxType,
     alias: String,
 }
@@ -34,6 +35,7 @@ impl StakePoolBuilder {
             alias: "".to_owned(),
             pool_permissions: None,
             reward_account: false,
+            delegators_account: false,
             tax_type: TaxType {
                 fixed: Value(1),
                 ratio: Ratio {
@@ -70,6 +72,11 @@ impl StakePoolBuilder {
         self
     }
 
+    pub fn with_delegators_account(&mut self, delegators_account: bool) -> &mut Self {
+        self.delegators_account = delegators_account;
+        self
+    }
+
     pub fn with_ratio_tax_type(
         &mut self,
         numerator: u64,
@@ -110,6 +117,14 @@ impl StakePoolBuilder {
             (None, None)
         };
 
+        let (delegators_account, delegators_identifier) = if self.delegators_account {
+            let account = AddressData::account(Discrimination::Test);
+            let transaction_account = AccountIdentifier::Single(account.to_id());
+            (Some(account), Some(transaction_account))
+        } else {
+            (None, None)
+        };
+
         let pool_info = PoolRegistration {
             serial: 0,
             owners: self.owners.clone(),
@@ -118,6 +133,7 @@ impl StakePoolBuilder {
             permissions,
             rewards: self.tax_type,
             reward_account: reward_identifier,
+            delegators_account: delegators_identifier,
             keys: GenesisPraosLeader {
                 vrf_public_key: pool_vrf.public_key().clone(),
                 kes_public_key: pool_kes.public_key().clone(),
@@ -130,6 +146,7 @@ impl StakePoolBuilder {
             pool_kes,
             pool_info,
             reward_account,
+            delegators_account,
         )
     }
 }
@@ -7,7 +7,7 @@ use crate::{
     },
     transaction::{
         Input, NoExtra, Output, OutputsSlice, Transaction, TransactionSignDataHash,
-        TransactionSlice, TxBuilder, Witness, WitnessesSlice,
+        TransactionSlice, TxBuilder, ValidityInterval, Witness, WitnessesSlice,
     },
     value::Value,
 };
@@ -15,6 +15,7 @@ use chain_addr::Address;
 
 pub struct TestTxBuilder {
     block0_hash: HeaderId,
+    validity: ValidityInterval,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -58,7 +59,15 @@ impl TestTx {
 
 impl TestTxBuilder {
     pub fn new(block0_hash: HeaderId) -> Self {
-        Self { block0_hash }
+        Self {
+            block0_hash,
+            validity: ValidityInterval::unbounded(),
+        }
+    }
+
+    pub fn with_validity(mut self, validity: ValidityInterval) -> Self {
+        self.validity = validity;
+        self
     }
 
     pub fn move_from_faucet(
@@ -86,12 +95,10 @@ impl TestTxBuilder {
             test_ledger.find_utxo_for_address(&faucet.clone().into()),
             value,
         )];
-        let outputs = vec![Output {
-            address: destination.clone(),
-            value: output_value,
-        }];
+        let outputs = vec![Output::from_address(destination.clone(), output_value)];
         let tx_builder = TxBuilder::new()
             .set_payload(&NoExtra)
+            .set_validity(self.validity)
             .set_ios(&inputs, &outputs);
 
         let witness =
@@ -125,6 +132,7 @@ impl TestTxBuilder {
         )];
         let tx_builder = TxBuilder::new()
             .set_payload(&NoExtra)
+            .set_validity(self.validity)
             .set_ios(&inputs, &destination);
 
         let witness =
@@ -186,6 +194,7 @@ impl TestTxBuilder {
             .collect();
         let tx_builder = TxBuilder::new()
             .set_payload(&NoExtra)
+            .set_validity(self.validity)
             .set_ios(&inputs, &destinations);
 
         let witnesses: Vec<Witness> = sources
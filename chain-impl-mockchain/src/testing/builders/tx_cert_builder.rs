@@ -2,13 +2,14 @@ use crate::certificate::EncryptedVoteTally;
 use crate::{
     certificate::{
         Certificate, CertificatePayload, EncryptedVoteTallyProof, PoolOwnersSigned, PoolSignature,
-        TallyProof, VotePlan, VotePlanProof, VoteTally,
+        TallyProof, VotePlanProof, VoteResultCertification, VoteResultCertificationProof,
+        VoteTally,
     },
     chaintypes::HeaderId,
     fee::FeeAlgorithm,
     fee::LinearFee,
     fragment::Fragment,
-    key::EitherEd25519SecretKey,
+    key::{EitherEd25519SecretKey, HashVersion},
     ledger::ledger::OutputAddress,
     testing::{data::Wallet, make_witness},
     transaction::{
@@ -24,11 +25,31 @@ use std::iter;
 pub struct TestTxCertBuilder {
     block0_hash: HeaderId,
     fee: LinearFee,
+    deposit: Value,
+    hash_version: HashVersion,
 }
 
 impl TestTxCertBuilder {
     pub fn new(block0_hash: HeaderId, fee: LinearFee) -> Self {
-        Self { block0_hash, fee }
+        Self {
+            block0_hash,
+            fee,
+            deposit: Value::zero(),
+            hash_version: HashVersion::CURRENT,
+        }
+    }
+
+    /// Have the funder also pay for the deposit locked by the certificate
+    pub fn with_deposit(mut self, deposit: Value) -> Self {
+        self.deposit = deposit;
+        self
+    }
+
+    /// Sign the transaction for a ledger using the given version of the
+    /// signed hashes
+    pub fn with_hash_version(mut self, hash_version: HashVersion) -> Self {
+        self.hash_version = hash_version;
+        self
     }
 
     pub fn block0_hash(&self) -> &HeaderId {
@@ -56,7 +77,9 @@ impl TestTxCertBuilder {
                 let witness = make_witness(
                     self.block0_hash(),
                     &funder.as_account_data(),
-                    &builder.get_auth_data_for_witness().hash(),
+                    &builder
+                        .get_auth_data_for_witness()
+                        .hash_with_version(self.hash_version),
                 );
                 vec![witness]
             } else {
@@ -86,7 +109,7 @@ impl TestTxCertBuilder {
                 );
                 let signature =
                     AccountBindingSignature::new_single(&builder.get_auth_data(), |d| {
-                        keys[0].sign_slice(&d.0)
+                        keys[0].sign_slice_with_context(&d.0)
                     });
                 let tx = builder.set_payload_auth(&signature);
                 Fragment::StakeDelegation(tx)
@@ -127,6 +150,18 @@ impl TestTxCertBuilder {
                 let tx = builder.set_payload_auth(&signature);
                 Fragment::PoolUpdate(tx)
             }
+            Certificate::PoolKeysRotation(s) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(s),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let signature = pool_owner_sign(&keys, &builder);
+                let tx = builder.set_payload_auth(&signature);
+                Fragment::PoolKeysRotation(tx)
+            }
             Certificate::OwnerStakeDelegation(s) => {
                 let builder = self.set_initial_ios(
                     TxBuilder::new().set_payload(s),
@@ -138,6 +173,32 @@ impl TestTxCertBuilder {
                 let tx = builder.set_payload_auth(&());
                 Fragment::OwnerStakeDelegation(tx)
             }
+            Certificate::DeferredStakeDelegation(s) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(s),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let signature =
+                    AccountBindingSignature::new_single(&builder.get_auth_data(), |d| {
+                        keys[0].sign_slice_with_context(&d.0)
+                    });
+                let tx = builder.set_payload_auth(&signature);
+                Fragment::DeferredStakeDelegation(tx)
+            }
+            Certificate::DeferredOwnerStakeDelegation(s) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(s),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let tx = builder.set_payload_auth(&());
+                Fragment::DeferredOwnerStakeDelegation(tx)
+            }
             Certificate::VotePlan(vp) => {
                 let builder = self.set_initial_ios(
                     TxBuilder::new().set_payload(vp),
@@ -161,6 +222,28 @@ impl TestTxCertBuilder {
                 let tx = builder.set_payload_auth(&());
                 Fragment::VoteCast(tx)
             }
+            Certificate::CommitteeCandidacy(candidacy) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(candidacy),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let tx = builder.set_payload_auth(&());
+                Fragment::CommitteeCandidacy(tx)
+            }
+            Certificate::VoteCastBatch(batch) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(batch),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let tx = builder.set_payload_auth(&());
+                Fragment::VoteCastBatch(tx)
+            }
             Certificate::VoteTally(vt) => {
                 let builder = self.set_initial_ios(
                     TxBuilder::new().set_payload(vt),
@@ -185,6 +268,45 @@ impl TestTxCertBuilder {
                 let tx = builder.set_payload_auth(&committee_signature);
                 Fragment::EncryptedVoteTally(tx)
             }
+            Certificate::HandleRegistration(s) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(s),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let signature =
+                    AccountBindingSignature::new_single(&builder.get_auth_data(), |d| {
+                        keys[0].sign_slice_with_context(&d.0)
+                    });
+                let tx = builder.set_payload_auth(&signature);
+                Fragment::HandleRegistration(tx)
+            }
+            Certificate::VotingPowerSnapshot(snapshot) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(snapshot),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let committee_signature = plan_sign(&keys, &builder);
+                let tx = builder.set_payload_auth(&committee_signature);
+                Fragment::VotingPowerSnapshot(tx)
+            }
+            Certificate::VoteResultCertification(certification) => {
+                let builder = self.set_initial_ios(
+                    TxBuilder::new().set_payload(certification),
+                    &funder,
+                    inputs,
+                    outputs,
+                    make_witness,
+                );
+                let committee_signatures = certification_sign(&keys, &builder);
+                let tx = builder.set_payload_auth(&committee_signatures);
+                Fragment::VoteResultCertification(tx)
+            }
         }
     }
 
@@ -211,7 +333,8 @@ impl TestTxCertBuilder {
         T: IntoIterator<Item = &'a Wallet>,
     {
         let keys = signers.into_iter().map(|x| x.private_key()).collect();
-        let input = funder.make_input_with_value(self.fee(certificate));
+        let value = (self.fee(certificate) + self.deposit).unwrap();
+        let input = funder.make_input_with_value(value);
         self.fragment(certificate, keys, &[input], &[], true, funder)
     }
 }
@@ -227,7 +350,8 @@ pub fn tally_sign(
     let id = key.to_public().into();
 
     let auth_data = builder.get_auth_data();
-    let signature = SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice(&d.0));
+    let signature =
+        SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice_with_context(&d.0));
 
     match payload_type {
         PayloadType::Public => TallyProof::Public { id, signature },
@@ -243,19 +367,40 @@ pub fn encrypted_tally_sign(
     let id = key.to_public().into();
 
     let auth_data = builder.get_auth_data();
-    let signature = SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice(&d.0));
+    let signature =
+        SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice_with_context(&d.0));
     EncryptedVoteTallyProof { id, signature }
 }
 
-pub fn plan_sign(
+/// signatures of all the signers, each of them a committee member
+pub fn certification_sign(
     keys: &[EitherEd25519SecretKey],
-    builder: &TxBuilderState<SetAuthData<VotePlan>>,
+    builder: &TxBuilderState<SetAuthData<VoteResultCertification>>,
+) -> VoteResultCertificationProof {
+    let auth_data = builder.get_auth_data();
+    let signatures = keys
+        .iter()
+        .map(|key| {
+            let signature = SingleAccountBindingSignature::new(&auth_data, |d| {
+                key.sign_slice_with_context(&d.0)
+            });
+            (key.to_public().into(), signature)
+        })
+        .collect();
+    VoteResultCertificationProof::new(signatures)
+}
+
+/// committee signature of a vote plan or of a voting power snapshot
+pub fn plan_sign<P: Payload>(
+    keys: &[EitherEd25519SecretKey],
+    builder: &TxBuilderState<SetAuthData<P>>,
 ) -> VotePlanProof {
     let key: EitherEd25519SecretKey = keys[0].clone();
     let id = key.to_public().into();
 
     let auth_data = builder.get_auth_data();
-    let signature = SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice(&d.0));
+    let signature =
+        SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice_with_context(&d.0));
 
     VotePlanProof { id, signature }
 }
@@ -275,7 +420,8 @@ pub fn pool_owner_signed<P: Payload>(
     let auth_data = builder.get_auth_data();
     let mut sigs = Vec::new();
     for (i, key) in keys.iter().enumerate() {
-        let sig = SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice(&d.0));
+        let sig =
+            SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice_with_context(&d.0));
         sigs.push((i as u8, sig))
     }
     PoolOwnersSigned { signatures: sigs }
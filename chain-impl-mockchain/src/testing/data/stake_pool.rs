@@ -15,6 +15,7 @@ pub struct StakePool {
     kes: KeyPair<SumEd25519_12>,
     pool_info: PoolRegistration,
     reward_account: Option<AddressData>,
+    delegators_account: Option<AddressData>,
 }
 
 impl StakePool {
@@ -25,6 +26,7 @@ impl StakePool {
         kes: KeyPair<SumEd25519_12>,
         pool_info: PoolRegistration,
         reward_account: Option<AddressData>,
+        delegators_account: Option<AddressData>,
     ) -> Self {
         StakePool {
             alias: alias.to_owned(),
@@ -33,6 +35,7 @@ impl StakePool {
             kes,
             pool_info,
             reward_account,
+            delegators_account,
         }
     }
 
@@ -63,6 +66,10 @@ impl StakePool {
     pub fn reward_account(&self) -> Option<&AddressData> {
         self.reward_account.as_ref()
     }
+
+    pub fn delegators_account(&self) -> Option<&AddressData> {
+        self.delegators_account.as_ref()
+    }
 }
 
 impl Arbitrary for StakePool {
@@ -0,0 +1,207 @@
+use crate::{
+    certificate::VoteTally,
+    chaineval::ConsensusEvalContext,
+    fee::LinearFee,
+    header::BlockDate,
+    ledger::Error as LedgerError,
+    testing::{
+        builders::{build_stake_pool_registration_cert, StakePoolBuilder, TestTxCertBuilder},
+        ledger::ConfigBuilder,
+        scenario::{prepare_scenario, wallet},
+        verifiers::LedgerStateVerifier,
+        VoteTestGen,
+    },
+    value::Value,
+};
+
+const ALICE: &str = "Alice";
+const POOL_DEPOSIT: u64 = 100;
+const VOTE_PLAN_DEPOSIT: u64 = 50;
+// one input and a certificate with LinearFee::new(1, 1, 1)
+const CERTIFICATE_FEE: u64 = 3;
+
+fn config() -> ConfigBuilder {
+    ConfigBuilder::new(0)
+        .with_fee(LinearFee::new(1, 1, 1))
+        .with_pool_registration_deposit(Value(POOL_DEPOSIT))
+        .with_vote_plan_deposit(Value(VOTE_PLAN_DEPOSIT))
+}
+
+#[test]
+pub fn pool_deposit_is_locked_and_refunded_on_retirement() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let stake_pool = StakePoolBuilder::new()
+        .with_owners(vec![alice.public_key()])
+        .build();
+    let total_funds = ledger.total_funds();
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(POOL_DEPOSIT))
+        .make_transaction(
+            Some(&alice),
+            &build_stake_pool_registration_cert(&stake_pool.info()),
+        );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    let deposit = ledger.ledger.pool_deposit(&stake_pool.id()).unwrap();
+    assert_eq!(deposit.value, Value(POOL_DEPOSIT));
+    assert_eq!(deposit.refund_to, alice.public_key().into());
+    assert_eq!(ledger.ledger.deposits().total(), Value(POOL_DEPOSIT));
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.clone().into());
+    ledger_verifier
+        .info("deposit is locked")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - CERTIFICATE_FEE - POOL_DEPOSIT));
+    ledger_verifier.total_value_is(&total_funds);
+
+    controller
+        .retire(Some(&alice), &stake_pool, &mut ledger)
+        .unwrap();
+
+    assert!(ledger.ledger.pool_deposit(&stake_pool.id()).is_none());
+    assert_eq!(ledger.ledger.deposits().total(), Value::zero());
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.into());
+    ledger_verifier
+        .info("deposit is refunded")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - 2 * CERTIFICATE_FEE));
+    ledger_verifier.total_value_is(&total_funds);
+}
+
+#[test]
+pub fn pool_registration_without_deposit_is_rejected() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000)])
+        .build()
+        .unwrap();
+
+    let alice = controller.wallet(ALICE).unwrap();
+    let stake_pool = StakePoolBuilder::new()
+        .with_owners(vec![alice.public_key()])
+        .build();
+
+    assert!(matches!(
+        controller.register(&alice, &stake_pool, &mut ledger),
+        Err(LedgerError::TransactionBalanceInvalid(_))
+    ));
+    assert!(ledger.ledger.pool_deposit(&stake_pool.id()).is_none());
+}
+
+#[test]
+pub fn vote_plan_deposit_is_locked_and_refunded_on_tally() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = VoteTestGen::vote_plan();
+    let total_funds = ledger.total_funds();
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(VOTE_PLAN_DEPOSIT))
+        .make_transaction(Some(&alice), &vote_plan.clone().into());
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    let deposit = ledger.ledger.vote_plan_deposit(&vote_plan.to_id()).unwrap();
+    assert_eq!(deposit.value, Value(VOTE_PLAN_DEPOSIT));
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.clone().into());
+    ledger_verifier
+        .info("deposit is locked")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - CERTIFICATE_FEE - VOTE_PLAN_DEPOSIT));
+    ledger_verifier.total_value_is(&total_funds);
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 2,
+        slot_id: 1,
+    });
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee()).make_transaction(
+        Some(&alice),
+        &VoteTally::new_public(vote_plan.to_id()).into(),
+    );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+
+    assert!(ledger
+        .ledger
+        .vote_plan_deposit(&vote_plan.to_id())
+        .is_none());
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.into());
+    ledger_verifier
+        .info("deposit is refunded")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - 2 * CERTIFICATE_FEE));
+    ledger_verifier.total_value_is(&total_funds);
+}
+
+#[test]
+pub fn vote_plan_deposit_is_refunded_after_committee_end_without_tally() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = VoteTestGen::vote_plan();
+    let total_funds = ledger.total_funds();
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(VOTE_PLAN_DEPOSIT))
+        .make_transaction(Some(&alice), &vote_plan.clone().into());
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    let begin_epoch = |ledger: &crate::ledger::Ledger, epoch| {
+        ledger
+            .begin_block(
+                ledger.get_ledger_parameters(),
+                ledger.chain_length().increase(),
+                BlockDate { epoch, slot_id: 0 },
+            )
+            .unwrap()
+            .finish(&ConsensusEvalContext::Bft)
+    };
+
+    // the plan can still be tallied until the end of its committee period
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+    let before_end = begin_epoch(&ledger.ledger, vote_plan.committee_end().epoch - 1);
+    assert!(before_end.vote_plan_deposit(&vote_plan.to_id()).is_some());
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: vote_plan.committee_end().epoch - 1,
+        slot_id: 1,
+    });
+    ledger.ledger = begin_epoch(&ledger.ledger, vote_plan.committee_end().epoch);
+
+    assert!(ledger
+        .ledger
+        .vote_plan_deposit(&vote_plan.to_id())
+        .is_none());
+    assert_eq!(ledger.ledger.deposits().total(), Value::zero());
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.into());
+    ledger_verifier
+        .info("deposit is refunded")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - CERTIFICATE_FEE));
+    ledger_verifier.total_value_is(&total_funds);
+}
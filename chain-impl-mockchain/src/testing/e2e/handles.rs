@@ -0,0 +1,239 @@
+use crate::{
+    certificate::Handle,
+    fee::LinearFee,
+    fragment::Fragment,
+    header::BlockDate,
+    ledger::Error as LedgerError,
+    testing::{
+        builders::{build_handle_registration_cert, TestTxCertBuilder},
+        data::Wallet,
+        ledger::ConfigBuilder,
+        scenario::{prepare_scenario, wallet},
+        verifiers::LedgerStateVerifier,
+    },
+    transaction::{AccountBindingSignature, SingleAccountBindingSignature, TxBuilder},
+    value::Value,
+};
+
+const ALICE: &str = "Alice";
+const BOB: &str = "Bob";
+const HANDLE_DEPOSIT: u64 = 20;
+const HANDLE_VALIDITY: u32 = 2;
+// one input and a certificate with LinearFee::new(1, 1, 1)
+const CERTIFICATE_FEE: u64 = 3;
+
+fn config() -> ConfigBuilder {
+    ConfigBuilder::new(0)
+        .with_fee(LinearFee::new(1, 1, 1))
+        .with_slots_per_epoch(10)
+        .with_handle_registration_deposit(Value(HANDLE_DEPOSIT))
+        .with_handle_validity(HANDLE_VALIDITY)
+}
+
+#[test]
+pub fn handle_is_registered_and_resolved() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000), wallet(BOB).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let bob = controller.wallet(BOB).unwrap();
+    let handle = Handle::new("alice").unwrap();
+    let total_funds = ledger.total_funds();
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(
+            Some(&alice),
+            &build_handle_registration_cert(handle.clone(), &alice.as_account_data()),
+        );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    let alice_id = alice.public_key().into();
+    assert_eq!(ledger.ledger.resolve_handle(&handle), Some(&alice_id));
+    assert_eq!(ledger.ledger.handles().handles_of(&alice_id, 0).count(), 1);
+    let deposit = ledger.ledger.handle_deposit(&handle).unwrap();
+    assert_eq!(deposit.value, Value(HANDLE_DEPOSIT));
+    assert_eq!(deposit.refund_to, alice_id);
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.clone().into());
+    ledger_verifier
+        .info("deposit is locked")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - CERTIFICATE_FEE - HANDLE_DEPOSIT));
+    ledger_verifier.total_value_is(&total_funds);
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(
+            Some(&bob),
+            &build_handle_registration_cert(handle.clone(), &bob.as_account_data()),
+        );
+    assert_eq!(
+        ledger.apply_fragment(&fragment, ledger.date()),
+        Err(LedgerError::HandleAlreadyRegistered { handle })
+    );
+}
+
+#[test]
+pub fn renewal_does_not_lock_another_deposit() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let handle = Handle::new("alice").unwrap();
+    let certificate = build_handle_registration_cert(handle.clone(), &alice.as_account_data());
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(Some(&alice), &certificate);
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: HANDLE_VALIDITY,
+        slot_id: 0,
+    });
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .make_transaction(Some(&alice), &certificate);
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    let record = ledger.ledger.handles().get(&handle).unwrap();
+    assert_eq!(record.expiry, Some(2 * HANDLE_VALIDITY));
+    assert_eq!(ledger.ledger.deposits().total(), Value(HANDLE_DEPOSIT));
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.into());
+    ledger_verifier
+        .info("only the first registration locks a deposit")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - 2 * CERTIFICATE_FEE - HANDLE_DEPOSIT));
+}
+
+#[test]
+pub fn expired_handle_is_taken_over_and_deposit_refunded() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000), wallet(BOB).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let mut bob = controller.wallet(BOB).unwrap();
+    let handle = Handle::new("alice").unwrap();
+    let total_funds = ledger.total_funds();
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(
+            Some(&alice),
+            &build_handle_registration_cert(handle.clone(), &alice.as_account_data()),
+        );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: HANDLE_VALIDITY + 1,
+        slot_id: 0,
+    });
+    assert_eq!(ledger.ledger.resolve_handle(&handle), None);
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(
+            Some(&bob),
+            &build_handle_registration_cert(handle.clone(), &bob.as_account_data()),
+        );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    bob.confirm_transaction();
+
+    let bob_id = bob.public_key().into();
+    assert_eq!(ledger.ledger.resolve_handle(&handle), Some(&bob_id));
+    assert_eq!(
+        ledger.ledger.handle_deposit(&handle).unwrap().refund_to,
+        bob_id
+    );
+    assert_eq!(ledger.ledger.deposits().total(), Value(HANDLE_DEPOSIT));
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.into());
+    ledger_verifier
+        .info("deposit of the previous owner is refunded")
+        .account(alice.as_account_data())
+        .has_value(&Value(1_000 - CERTIFICATE_FEE));
+    ledger_verifier
+        .account(bob.as_account_data())
+        .has_value(&Value(1_000 - CERTIFICATE_FEE - HANDLE_DEPOSIT));
+    ledger_verifier.total_value_is(&total_funds);
+}
+
+// the registration signed without context, as before the binding
+// signatures had one
+fn with_legacy_binding_signature(fragment: Fragment, signer: &Wallet) -> Fragment {
+    let tx = match fragment {
+        Fragment::HandleRegistration(tx) => tx,
+        _ => unreachable!("not a handle registration"),
+    };
+    let tx = tx.as_slice();
+    let inputs: Vec<_> = tx.inputs().iter().collect();
+    let outputs: Vec<_> = tx.outputs().iter().collect();
+    let witnesses: Vec<_> = tx.witnesses().iter().collect();
+    let builder = TxBuilder::new()
+        .set_payload(&tx.payload().into_payload())
+        .set_validity(tx.validity())
+        .set_ios(&inputs, &outputs)
+        .set_witnesses(&witnesses);
+    let signature = AccountBindingSignature::Single(SingleAccountBindingSignature(
+        signer.private_key().sign_slice(builder.get_auth_data().0),
+    ));
+    Fragment::HandleRegistration(builder.set_payload_auth(&signature))
+}
+
+#[test]
+pub fn legacy_binding_signature_is_rejected_from_the_context_epoch() {
+    const CONTEXT_EPOCH: u32 = 1;
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config().with_binding_signature_context_epoch(CONTEXT_EPOCH))
+        .with_initials(vec![wallet(ALICE).with(1_000), wallet(BOB).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let bob = controller.wallet(BOB).unwrap();
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(
+            Some(&alice),
+            &build_handle_registration_cert(
+                Handle::new("alice").unwrap(),
+                &alice.as_account_data(),
+            ),
+        );
+    let fragment = with_legacy_binding_signature(fragment, &alice);
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: CONTEXT_EPOCH,
+        slot_id: 0,
+    });
+    let certificate =
+        build_handle_registration_cert(Handle::new("bob").unwrap(), &bob.as_account_data());
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_deposit(Value(HANDLE_DEPOSIT))
+        .make_transaction(Some(&bob), &certificate);
+    assert_eq!(
+        ledger.apply_fragment(
+            &with_legacy_binding_signature(fragment.clone(), &bob),
+            ledger.date()
+        ),
+        Err(LedgerError::HandleRegistrationSignatureFailed)
+    );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+}
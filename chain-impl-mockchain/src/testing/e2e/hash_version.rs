@@ -0,0 +1,73 @@
+use crate::{
+    header::BlockDate,
+    key::HashVersion,
+    ledger::Error as LedgerError,
+    testing::{
+        builders::{build_stake_pool_registration_cert, StakePoolBuilder, TestTxCertBuilder},
+        ledger::ConfigBuilder,
+        scenario::{prepare_scenario, wallet},
+    },
+};
+
+const ALICE: &str = "Alice";
+const HASH_VERSION_EPOCH: u32 = 1;
+
+fn config() -> ConfigBuilder {
+    ConfigBuilder::new(0)
+        .with_slots_per_epoch(10)
+        .with_hash_version_epoch(HASH_VERSION_EPOCH)
+}
+
+#[test]
+pub fn ids_and_signed_hashes_follow_the_hash_version_epoch() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(config())
+        .with_initials(vec![wallet(ALICE).with(1_000)])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let first_pool = StakePoolBuilder::new()
+        .with_owners(vec![alice.public_key()])
+        .build();
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee()).make_transaction(
+        Some(&alice),
+        &build_stake_pool_registration_cert(&first_pool.info()),
+    );
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+    assert!(ledger
+        .delegation()
+        .stake_pool_exists(&first_pool.info().to_id_with_version(HashVersion::V0)));
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: HASH_VERSION_EPOCH,
+        slot_id: 0,
+    });
+    let second_pool = StakePoolBuilder::new()
+        .with_owners(vec![alice.public_key()])
+        .build();
+    let certificate = build_stake_pool_registration_cert(&second_pool.info());
+
+    // the witness signed the hash of the version 0
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .make_transaction(Some(&alice), &certificate);
+    assert!(matches!(
+        ledger.apply_fragment(&fragment, ledger.date()),
+        Err(LedgerError::AccountInvalidSignature { .. })
+    ));
+
+    let fragment = TestTxCertBuilder::new(ledger.block0_hash, ledger.fee())
+        .with_hash_version(HashVersion::V1)
+        .make_transaction(Some(&alice), &certificate);
+    ledger.apply_fragment(&fragment, ledger.date()).unwrap();
+    alice.confirm_transaction();
+
+    let second_id = second_pool.info().to_id_with_version(HashVersion::V1);
+    assert_ne!(second_id, second_pool.info().to_id());
+    assert!(ledger.delegation().stake_pool_exists(&second_id));
+    // the pool registered before the activation keeps its id
+    assert!(ledger
+        .delegation()
+        .stake_pool_exists(&first_pool.info().to_id()));
+}
@@ -1,4 +1,7 @@
+pub mod deposits;
 pub mod fees;
+pub mod handles;
+pub mod hash_version;
 pub mod management_threshold;
 pub mod owner_delegation;
 pub mod pool_update;
@@ -5,6 +5,7 @@ use crate::{
         ledger::ConfigBuilder,
         scenario::{prepare_scenario, stake_pool, wallet},
         verifiers::LedgerStateVerifier,
+        TestGen,
     },
     value::Value,
 };
@@ -265,3 +266,47 @@ pub fn pool_update_revert_changes() {
         .stake_pools()
         .is_retired(&new_stake_pool);
 }
+
+#[test]
+pub fn pool_keys_rotation_preserves_delegations() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(ConfigBuilder::new(0).with_fee(LinearFee::new(1, 1, 1)))
+        .with_initials(vec![
+            wallet("Alice").with(1_000).owns("stake_pool"),
+            wallet("Bob").with(1_000).delegates_to("stake_pool"),
+        ])
+        .build()
+        .unwrap();
+
+    let alice = controller.wallet("Alice").unwrap();
+    let bob = controller.wallet("Bob").unwrap();
+    let stake_pool = controller.stake_pool("stake_pool").unwrap();
+    let new_keys = TestGen::stake_pool().info().keys;
+
+    assert!(controller
+        .rotate_keys(&stake_pool, new_keys.clone(), vec![&bob], &mut ledger)
+        .is_err());
+    assert!(controller
+        .rotate_keys(&stake_pool, new_keys.clone(), vec![&alice], &mut ledger)
+        .is_ok());
+
+    let registration = ledger
+        .delegation()
+        .lookup_reg(&stake_pool.id())
+        .cloned()
+        .unwrap();
+    assert_eq!(registration.keys, new_keys);
+    assert_eq!(registration.owners, stake_pool.info().owners);
+    assert_eq!(registration.serial, stake_pool.info().serial);
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("delegation is the same")
+        .account(bob.as_account_data())
+        .delegation()
+        .is_fully_delegated_to(stake_pool.id());
+
+    // the rotation changed the registration, the old hash is stale
+    assert!(controller
+        .rotate_keys(&stake_pool, new_keys, vec![&alice], &mut ledger)
+        .is_err());
+}
@@ -1,10 +1,11 @@
 use crate::{
     certificate::PoolId,
     config::RewardParams,
+    date::BlockDate,
     fee::LinearFee,
     rewards::Ratio,
     testing::{
-        builders::StakePoolBuilder,
+        builders::{GenesisPraosBlockBuilder, StakePoolBuilder},
         ledger::{ConfigBuilder, TestLedger},
         scenario::{prepare_scenario, stake_pool, wallet},
         verifiers::LedgerStateVerifier,
@@ -136,6 +137,56 @@ pub fn rewards_owners_split() {
         .has_value(&Value(1003));
 }
 
+#[test]
+pub fn rewards_across_empty_epochs() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_rewards(Value(100))
+                .with_treasury(Value(0))
+                .with_rewards_params(RewardParams::Linear {
+                    constant: 10,
+                    ratio: Ratio {
+                        numerator: 1,
+                        denominator: NonZeroU64::new(1).unwrap(),
+                    },
+                    epoch_start: 0,
+                    epoch_rate: NonZeroU32::new(1).unwrap(),
+                }),
+        )
+        .with_initials(vec![wallet("Alice").with(1_000).owns("stake_pool")])
+        .with_stake_pools(vec![stake_pool("stake_pool").tax_ratio(1, 1)])
+        .build()
+        .unwrap();
+    let stake_pool = controller.stake_pool("stake_pool").unwrap();
+
+    assert!(ledger.produce_empty_block(&stake_pool).is_ok());
+    ledger.distribute_rewards().unwrap();
+    let pots = ledger.pots();
+
+    // the epochs without block are not rewarded
+    assert!(!ledger.can_distribute_reward());
+    ledger.distribute_rewards().unwrap();
+    assert_eq!(ledger.pots(), pots);
+
+    // the next block comes after two epochs without block
+    let last_slot = ledger.era().slots_per_epoch() - 1;
+    let block = GenesisPraosBlockBuilder::new()
+        .with_date(BlockDate {
+            epoch: 2,
+            slot_id: last_slot,
+        })
+        .with_chain_length(ledger.chain_length())
+        .with_parent_id(*ledger.block0_hash())
+        .build(&stake_pool, ledger.era());
+    let previous_date = ledger.date();
+    ledger.apply_block(block).unwrap();
+    assert_eq!(previous_date.missed_epochs(ledger.date()), 1..3);
+    assert!(ledger.can_distribute_reward());
+    ledger.distribute_rewards().unwrap();
+    assert!(ledger.pots().rewards < pots.rewards);
+}
+
 #[test]
 pub fn rewards_owners_uneven_split() {
     let (mut ledger, controller) = prepare_scenario()
@@ -283,6 +334,55 @@ pub fn rewards_reward_account() {
         .has_last_reward(&Value(9));
 }
 
+#[test]
+pub fn rewards_delegators_share_goes_to_delegators_account() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_rewards(Value(1000))
+                .with_treasury(Value(0))
+                .with_rewards_params(RewardParams::Linear {
+                    constant: 100,
+                    ratio: Ratio {
+                        numerator: 1,
+                        denominator: NonZeroU64::new(1).unwrap(),
+                    },
+                    epoch_start: 0,
+                    epoch_rate: NonZeroU32::new(1).unwrap(),
+                }),
+        )
+        .with_initials(vec![wallet("Alice").with(1_000).owns("stake_pool")])
+        .with_stake_pools(vec![stake_pool("stake_pool")
+            .with_reward_account(true)
+            .with_delegators_account(true)
+            .tax_ratio(1, 10)])
+        .build()
+        .unwrap();
+    let stake_pool = controller.stake_pool("stake_pool").unwrap();
+
+    assert!(ledger.produce_empty_block(&stake_pool).is_ok());
+    ledger.distribute_rewards().unwrap();
+
+    let mut ledger_verifier = LedgerStateVerifier::new(ledger.into());
+    ledger_verifier.info("after rewards distribution to the delegators account");
+
+    ledger_verifier
+        .pots()
+        .has_treasury_equals_to(&Value::zero())
+        .and()
+        .has_remaining_rewards_equals_to(&Value(901));
+
+    ledger_verifier
+        .account(stake_pool.reward_account().unwrap().clone())
+        .has_value(&Value(9));
+
+    ledger_verifier
+        .account(stake_pool.delegators_account().unwrap().clone())
+        .has_value(&Value(90))
+        .and()
+        .has_last_reward(&Value(90));
+}
+
 #[test]
 pub fn rewards_goes_to_treasury_if_stake_pool_is_retired() {
     let (mut ledger, controller) = prepare_scenario()
@@ -1,5 +1,6 @@
 use crate::{
     config::RewardParams,
+    date::BlockDate,
     fee::LinearFee,
     rewards::Ratio,
     stake::Stake,
@@ -106,3 +107,103 @@ pub fn stake_distribution_changes_after_rewards_are_collected() {
         .unassigned_is(Stake::from_value(Value(2000)))
         .pools_distribution_is(vec![(alice_stake_pool.id(), Value(1009))]);
 }
+
+#[test]
+pub fn stake_distribution_follows_delegation_activation_epoch() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_discrimination(Discrimination::Test)
+                .with_fee(LinearFee::new(1, 1, 1)),
+        )
+        .with_initials(vec![
+            wallet("Alice").with(1_000).owns("alice_stake_pool"),
+            wallet("Bob").with(1_000).owns("bob_stake_pool"),
+            wallet("Clarice").with(1_006),
+        ])
+        .build()
+        .unwrap();
+
+    let alice_stake_pool = controller.stake_pool("alice_stake_pool").unwrap();
+    let bob_stake_pool = controller.stake_pool("bob_stake_pool").unwrap();
+    let mut clarice = controller.wallet("Clarice").unwrap();
+
+    controller
+        .delegates(&clarice, &alice_stake_pool, &mut ledger)
+        .unwrap();
+    clarice.confirm_transaction();
+    controller
+        .delegates_from(&clarice, &bob_stake_pool, 2, &mut ledger)
+        .unwrap();
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("before delegation activation")
+        .distribution()
+        .pools_distribution_is(vec![
+            (alice_stake_pool.id(), Value(1000)),
+            (bob_stake_pool.id(), Value(0)),
+        ]);
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 2,
+        slot_id: 0,
+    });
+
+    LedgerStateVerifier::new(ledger.into())
+        .info("after delegation activation")
+        .distribution()
+        .pools_distribution_is(vec![
+            (alice_stake_pool.id(), Value(0)),
+            (bob_stake_pool.id(), Value(1000)),
+        ]);
+}
+
+#[test]
+pub fn stake_distribution_follows_owner_delegation_activation_epoch() {
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_discrimination(Discrimination::Test)
+                .with_fee(LinearFee::new(1, 1, 1)),
+        )
+        .with_initials(vec![
+            wallet("Alice").with(1_000).owns("alice_stake_pool"),
+            wallet("Bob").with(1_000).owns("bob_stake_pool"),
+            wallet("Clarice").with(1_006),
+        ])
+        .build()
+        .unwrap();
+
+    let alice_stake_pool = controller.stake_pool("alice_stake_pool").unwrap();
+    let bob_stake_pool = controller.stake_pool("bob_stake_pool").unwrap();
+    let mut clarice = controller.wallet("Clarice").unwrap();
+
+    controller
+        .owner_delegates(&clarice, &alice_stake_pool, &mut ledger)
+        .unwrap();
+    clarice.confirm_transaction();
+    controller
+        .owner_delegates_from(&clarice, &bob_stake_pool, 2, &mut ledger)
+        .unwrap();
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("before owner delegation activation")
+        .distribution()
+        .pools_distribution_is(vec![
+            (alice_stake_pool.id(), Value(1000)),
+            (bob_stake_pool.id(), Value(0)),
+        ]);
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 2,
+        slot_id: 0,
+    });
+
+    LedgerStateVerifier::new(ledger.into())
+        .info("after owner delegation activation")
+        .distribution()
+        .pools_distribution_is(vec![
+            (alice_stake_pool.id(), Value(0)),
+            (bob_stake_pool.id(), Value(1000)),
+        ]);
+}
@@ -1,16 +1,25 @@
 use crate::testing::VoteTestGen;
 use crate::{
-    fee::{LinearFee, PerCertificateFee, PerVoteCertificateFee},
+    certificate::VotePlan,
+    config::ConfigParam,
+    fee::{
+        FeeDiscount, GovernanceFeeDiscount, LinearFee, PerCertificateFee, PerVoteCertificateFee,
+    },
     header::BlockDate,
+    ledger::{
+        governance::{TreasuryBudget, VoteResultCertificationError},
+        Error as LedgerError,
+    },
     testing::{
-        ledger::ConfigBuilder,
+        ledger::{ConfigBuilder, TestLedger},
         scenario::{prepare_scenario, proposal, vote_plan, wallet},
         verifiers::LedgerStateVerifier,
     },
     value::Value,
     vote::Choice,
 };
-use core::num::NonZeroU64;
+use chain_addr::Discrimination;
+use core::num::{NonZeroU32, NonZeroU64};
 
 const ALICE: &str = "Alice";
 const BOB: &str = "Bob";
@@ -412,3 +421,612 @@ pub fn votes_with_fees() {
         .info("total value is the same")
         .total_value_is(&expected_ada_after);
 }
+
+#[test]
+pub fn votes_with_governance_fee_discount() {
+    let favorable = Choice::new(1);
+    let discount = GovernanceFeeDiscount::new(
+        FeeDiscount::EXEMPTION,
+        FeeDiscount::NONE,
+        FeeDiscount::new(500).unwrap(),
+    );
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_fee(LinearFee::new(1, 1, 1))
+                .with_governance_fee_discount(discount),
+        )
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![vote_plan(VOTE_PLAN)
+            .owner(ALICE)
+            .consecutive_epoch_dates()
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_off_chain(),
+            )])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    let proposal = vote_plan.proposal(0);
+
+    controller
+        .cast_vote_public(&alice, &vote_plan, &proposal.id(), favorable, &mut ledger)
+        .unwrap();
+    alice.confirm_transaction();
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("the ballot is exempted of fees")
+        .pots()
+        .has_fee_equals_to(&Value::zero());
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    controller
+        .tally_vote_public(&alice, &vote_plan, &mut ledger)
+        .unwrap();
+
+    // 1 constant + 1 input + 1 certificate, half of which is discounted
+    LedgerStateVerifier::new(ledger.into())
+        .info("the tally has half of its fees discounted")
+        .pots()
+        .has_fee_equals_to(&Value(2));
+}
+
+#[test]
+pub fn vote_cast_batch_action_transfer_to_rewards() {
+    let favorable = Choice::new(1);
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_fee(LinearFee::new(1, 1, 1))
+                .with_rewards(Value(1000)),
+        )
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![vote_plan(VOTE_PLAN)
+            .owner(ALICE)
+            .consecutive_epoch_dates()
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_transfer_to_rewards(100),
+            )
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_transfer_to_rewards(100),
+            )])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    let choices = vec![
+        (vote_plan.proposal(0).id(), favorable),
+        (vote_plan.proposal(1).id(), favorable),
+    ];
+
+    controller
+        .cast_votes_public_batch(&alice, &vote_plan, &choices, &mut ledger)
+        .unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    controller
+        .tally_vote_public(&alice, &vote_plan, &mut ledger)
+        .unwrap();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    ledger.apply_protocol_changes().unwrap();
+
+    LedgerStateVerifier::new(ledger.into())
+        .info("rewards pot is increased by both proposals")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1200));
+}
+
+#[test]
+pub fn vote_tally_skips_the_treasury_actions_above_the_budget() {
+    let favorable = Choice::new(1);
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_fee(LinearFee::new(1, 1, 1))
+                .with_rewards(Value(1000))
+                .with_treasury_budget(TreasuryBudget {
+                    epochs: NonZeroU32::new(2).unwrap(),
+                    cap: Value(150),
+                }),
+        )
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![vote_plan(VOTE_PLAN)
+            .owner(ALICE)
+            .consecutive_epoch_dates()
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_transfer_to_rewards(100),
+            )
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_transfer_to_rewards(100),
+            )])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    let choices = vec![
+        (vote_plan.proposal(0).id(), favorable),
+        (vote_plan.proposal(1).id(), favorable),
+    ];
+
+    controller
+        .cast_votes_public_batch(&alice, &vote_plan, &choices, &mut ledger)
+        .unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    controller
+        .tally_vote_public(&alice, &vote_plan, &mut ledger)
+        .unwrap();
+    ledger.apply_protocol_changes().unwrap();
+
+    // the second action draws more than the 50 left in the budget
+    let skipped = ledger.ledger.treasury_skipped_actions();
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].vote_plan, VotePlan::from(vote_plan).to_id());
+    assert_eq!(skipped[0].value, Value(100));
+    assert_eq!(skipped[0].remaining_budget, Value(50));
+    assert_eq!(ledger.ledger.treasury_spent(), Value(100));
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("rewards pot is only increased by the first proposal")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1100));
+
+    // the skipped actions are forgotten with the budget period
+    ledger.fast_forward_to(BlockDate {
+        epoch: 2,
+        slot_id: 0,
+    });
+    assert!(ledger.ledger.treasury_skipped_actions().is_empty());
+}
+
+#[test]
+pub fn vote_cast_batch_duplicated_proposal() {
+    let favorable = Choice::new(1);
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(ConfigBuilder::new(0).with_fee(LinearFee::new(1, 1, 1)))
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![vote_plan(VOTE_PLAN)
+            .owner(ALICE)
+            .consecutive_epoch_dates()
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_parameters_no_op(),
+            )])
+        .build()
+        .unwrap();
+
+    let alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    let proposal = vote_plan.proposal(0);
+    let choices = vec![(proposal.id(), favorable), (proposal.id(), favorable)];
+
+    assert_eq!(
+        controller
+            .cast_votes_public_batch(&alice, &vote_plan, &choices, &mut ledger)
+            .err()
+            .unwrap(),
+        LedgerError::VoteCastBatchDuplicatedProposal { proposal_index: 0 }
+    );
+}
+
+/// Vote in favor of a parameters update per package of changes, tally the
+/// votes and apply the protocol changes
+fn update_parameters_by_vote(packages: Vec<Vec<ConfigParam>>) -> TestLedger {
+    let favorable = Choice::new(1);
+
+    let mut vote_plan_builder = vote_plan(VOTE_PLAN);
+    vote_plan_builder.owner(ALICE).consecutive_epoch_dates();
+    for changes in packages.iter() {
+        vote_plan_builder.with_proposal(
+            proposal(VoteTestGen::external_proposal_id())
+                .options(3)
+                .action_update_parameters(changes.clone()),
+        );
+    }
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(ConfigBuilder::new(0).with_fee(LinearFee::new(1, 1, 1)))
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![&mut vote_plan_builder])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    for index in 0..packages.len() {
+        let proposal = vote_plan.proposal(index);
+        controller
+            .cast_vote_public(&alice, &vote_plan, &proposal.id(), favorable, &mut ledger)
+            .unwrap();
+        alice.confirm_transaction();
+    }
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    controller
+        .tally_vote_public(&alice, &vote_plan, &mut ledger)
+        .unwrap();
+
+    ledger.apply_protocol_changes().unwrap();
+    ledger
+}
+
+#[test]
+pub fn vote_cast_action_update_parameters() {
+    let ledger = update_parameters_by_vote(vec![vec![
+        ConfigParam::SlotDuration(5),
+        ConfigParam::SlotsPerEpoch(200),
+        ConfigParam::ProposalExpiration(7),
+    ]]);
+
+    let settings = &ledger.ledger.settings;
+    assert_eq!(settings.slot_duration, 5);
+    assert_eq!(settings.slots_per_epoch, 200);
+    assert_eq!(settings.proposal_expiration, 7);
+}
+
+#[test]
+#[should_panic]
+pub fn vote_plan_with_read_only_parameters_update() {
+    let _ = update_parameters_by_vote(vec![vec![
+        ConfigParam::SlotDuration(5),
+        ConfigParam::Discrimination(Discrimination::Test),
+    ]]);
+}
+
+#[test]
+pub fn vote_cast_action_update_parameters_is_atomic() {
+    let initial = update_parameters_by_vote(vec![]).ledger.settings;
+
+    // the epoch duration would be zero: the slot duration is not changed
+    // either
+    let ledger = update_parameters_by_vote(vec![vec![
+        ConfigParam::SlotDuration(5),
+        ConfigParam::SlotsPerEpoch(0),
+    ]]);
+
+    let settings = &ledger.ledger.settings;
+    assert_eq!(settings.slot_duration, initial.slot_duration);
+    assert_eq!(settings.slots_per_epoch, initial.slots_per_epoch);
+}
+
+#[test]
+pub fn vote_cast_action_update_parameters_partial_failure() {
+    let initial = update_parameters_by_vote(vec![]).ledger.settings;
+
+    let ledger = update_parameters_by_vote(vec![
+        vec![ConfigParam::SlotsPerEpoch(200)],
+        vec![
+            ConfigParam::SlotDuration(0),
+            ConfigParam::ProposalExpiration(7),
+        ],
+        vec![ConfigParam::SlotDuration(5)],
+    ]);
+
+    // the invalid update in the middle is dropped, the others are applied
+    let settings = &ledger.ledger.settings;
+    assert_eq!(settings.slots_per_epoch, 200);
+    assert_eq!(settings.slot_duration, 5);
+    assert_eq!(settings.proposal_expiration, initial.proposal_expiration);
+}
+
+#[test]
+pub fn vote_tally_action_enactment_is_delayed() {
+    let favorable = Choice::new(1);
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_fee(LinearFee::new(1, 1, 1))
+                .with_rewards(Value(1000)),
+        )
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![vote_plan(VOTE_PLAN)
+            .owner(ALICE)
+            .consecutive_epoch_dates()
+            .enactment_delay(1)
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_transfer_to_rewards(100),
+            )])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    let proposal = vote_plan.proposal(0);
+
+    controller
+        .cast_vote_public(&alice, &vote_plan, &proposal.id(), favorable, &mut ledger)
+        .unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    controller
+        .tally_vote_public(&alice, &vote_plan, &mut ledger)
+        .unwrap();
+
+    ledger.apply_protocol_changes().unwrap();
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("rewards pot is not increased before the enactment")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1000));
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 2,
+        slot_id: 1,
+    });
+
+    ledger.apply_protocol_changes().unwrap();
+
+    LedgerStateVerifier::new(ledger.into())
+        .info("rewards pot is increased at the enactment")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1100));
+}
+
+#[test]
+pub fn vote_tally_action_enactment_is_cancelled() {
+    const CANCEL_VOTE_PLAN: &str = "fund1_cancel";
+    let favorable = Choice::new(1);
+
+    let mut delayed_vote_plan = vote_plan(VOTE_PLAN);
+    delayed_vote_plan
+        .owner(ALICE)
+        .consecutive_epoch_dates()
+        .enactment_delay(1)
+        .with_proposal(
+            proposal(VoteTestGen::external_proposal_id())
+                .options(3)
+                .action_transfer_to_rewards(100),
+        );
+    let delayed_vote_plan_id = {
+        let vote_plan: VotePlan = delayed_vote_plan.clone().build().into();
+        vote_plan.to_id()
+    };
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_fee(LinearFee::new(1, 1, 1))
+                .with_rewards(Value(1000)),
+        )
+        .with_initials(vec![wallet(ALICE)
+            .with(1_000)
+            .owns(STAKE_POOL)
+            .committee_member()])
+        .with_vote_plans(vec![
+            &mut delayed_vote_plan,
+            vote_plan(CANCEL_VOTE_PLAN)
+                .owner(ALICE)
+                .consecutive_epoch_dates()
+                .with_proposal(
+                    proposal(VoteTestGen::external_proposal_id())
+                        .options(3)
+                        .action_cancel_enactment(delayed_vote_plan_id),
+                ),
+        ])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    for alias in &[VOTE_PLAN, CANCEL_VOTE_PLAN] {
+        let vote_plan = controller.vote_plan(alias).unwrap();
+        let proposal = vote_plan.proposal(0);
+        controller
+            .cast_vote_public(&alice, &vote_plan, &proposal.id(), favorable, &mut ledger)
+            .unwrap();
+        alice.confirm_transaction();
+    }
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    for alias in &[VOTE_PLAN, CANCEL_VOTE_PLAN] {
+        let vote_plan = controller.vote_plan(alias).unwrap();
+        controller
+            .tally_vote_public(&alice, &vote_plan, &mut ledger)
+            .unwrap();
+        alice.confirm_transaction();
+    }
+
+    ledger.apply_protocol_changes().unwrap();
+    ledger.fast_forward_to(BlockDate {
+        epoch: 2,
+        slot_id: 1,
+    });
+    ledger.apply_protocol_changes().unwrap();
+
+    LedgerStateVerifier::new(ledger.into())
+        .info("rewards pot is not increased by the cancelled action")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1000));
+}
+
+#[test]
+pub fn vote_tally_actions_wait_for_the_result_certification() {
+    const CLARICE: &str = "Clarice";
+    let favorable = Choice::new(1);
+
+    let (mut ledger, controller) = prepare_scenario()
+        .with_config(
+            ConfigBuilder::new(0)
+                .with_fee(LinearFee::new(1, 1, 1))
+                .with_rewards(Value(1000))
+                .with_vote_result_certification(),
+        )
+        .with_initials(vec![
+            wallet(ALICE)
+                .with(1_000)
+                .owns(STAKE_POOL)
+                .committee_member(),
+            wallet(BOB).with(100).committee_member(),
+            wallet(CLARICE).with(100),
+        ])
+        .with_vote_plans(vec![vote_plan(VOTE_PLAN)
+            .owner(ALICE)
+            .consecutive_epoch_dates()
+            .with_proposal(
+                proposal(VoteTestGen::external_proposal_id())
+                    .options(3)
+                    .action_transfer_to_rewards(100),
+            )])
+        .build()
+        .unwrap();
+
+    let mut alice = controller.wallet(ALICE).unwrap();
+    let mut bob = controller.wallet(BOB).unwrap();
+    let clarice = controller.wallet(CLARICE).unwrap();
+    let vote_plan = controller.vote_plan(VOTE_PLAN).unwrap();
+    let proposal = vote_plan.proposal(0);
+
+    controller
+        .cast_vote_public(&alice, &vote_plan, &proposal.id(), favorable, &mut ledger)
+        .unwrap();
+    alice.confirm_transaction();
+
+    ledger.fast_forward_to(BlockDate {
+        epoch: 1,
+        slot_id: 1,
+    });
+
+    controller
+        .tally_vote_public(&alice, &vote_plan, &mut ledger)
+        .unwrap();
+    alice.confirm_transaction();
+    ledger.apply_protocol_changes().unwrap();
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("rewards pot is not increased before the certification")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1000));
+
+    let vote_plan_id = VotePlan::from(vote_plan.clone()).to_id();
+    let results: Vec<Box<[u64]>> = ledger
+        .ledger
+        .active_vote_plans()
+        .into_iter()
+        .find(|status| status.id == vote_plan_id)
+        .unwrap()
+        .proposals
+        .iter()
+        .map(|proposal| {
+            let result = proposal.tally.as_ref().unwrap().result().unwrap();
+            result.results().iter().map(|w| u64::from(*w)).collect()
+        })
+        .collect();
+    let mut tampered = results.clone();
+    tampered[0][2] += 1;
+
+    assert!(matches!(
+        controller.certify_vote_result(&[&alice], &vote_plan, results.clone(), &mut ledger),
+        Err(LedgerError::VoteResultCertificationInvalid(
+            VoteResultCertificationError::NoMajority { .. }
+        ))
+    ));
+    assert!(matches!(
+        controller.certify_vote_result(
+            &[&alice, &clarice],
+            &vote_plan,
+            results.clone(),
+            &mut ledger
+        ),
+        Err(LedgerError::VoteResultCertificationInvalid(
+            VoteResultCertificationError::SignerNotInCommittee(_)
+        ))
+    ));
+    assert!(matches!(
+        controller.certify_vote_result(&[&alice, &bob], &vote_plan, tampered, &mut ledger),
+        Err(LedgerError::VoteResultCertificationInvalid(
+            VoteResultCertificationError::ResultsMismatch(_)
+        ))
+    ));
+
+    controller
+        .certify_vote_result(&[&bob, &alice], &vote_plan, results.clone(), &mut ledger)
+        .unwrap();
+    bob.confirm_transaction();
+    assert!(ledger
+        .ledger
+        .governance
+        .certifications
+        .is_certified(&vote_plan_id));
+
+    LedgerStateVerifier::new(ledger.clone().into())
+        .info("rewards pot is increased by the certification")
+        .pots()
+        .has_remaining_rewards_equals_to(&Value(1100));
+
+    assert!(matches!(
+        controller.certify_vote_result(&[&bob, &alice], &vote_plan, results, &mut ledger),
+        Err(LedgerError::VoteResultCertificationInvalid(
+            VoteResultCertificationError::AlreadyCertified(_)
+        ))
+    ));
+}
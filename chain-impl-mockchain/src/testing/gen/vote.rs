@@ -10,7 +10,6 @@ use crate::{
 };
 use chain_core::property::BlockDate as BlockDateProp;
 use chain_crypto::digest::DigestOf;
-use chain_vote::CRS;
 use rand_core::{CryptoRng, RngCore};
 use typed_bytes::ByteBuilder;
 
@@ -107,7 +106,7 @@ impl VoteTestGen {
         let encrypting_key =
             chain_vote::EncryptingVoteKey::from_participants(vote_plan.committee_public_keys());
 
-        let crs = CRS::from_hash(&vote_plan.to_id().as_ref());
+        let crs = vote_plan.crs();
         let (encrypted_vote, proof) = chain_vote::encrypt_vote(
             rng,
             &crs,
@@ -6,14 +6,14 @@ use crate::{
     certificate::PoolId,
     chaintypes::{ChainLength, ConsensusType, ConsensusVersion, HeaderId},
     config::{Block0Date, ConfigParam, RewardParams},
-    date::BlockDate,
-    fee::{LinearFee, PerCertificateFee, PerVoteCertificateFee},
+    date::{BlockDate, Epoch},
+    fee::{GovernanceFeeDiscount, LinearFee, PerCertificateFee, PerVoteCertificateFee},
     fragment::{config::ConfigParams, Fragment, FragmentId},
     key::BftLeaderId,
     leadership::genesis::LeadershipData,
     ledger::{
-        check::CHECK_TX_MAXIMUM_INPUTS, Error, LeadersParticipationRecord, Ledger,
-        LedgerParameters, Pots, RewardsInfoParameters,
+        check::CHECK_TX_MAXIMUM_INPUTS, governance::TreasuryBudget, Error,
+        LeadersParticipationRecord, Ledger, LedgerParameters, Pots, RewardsInfoParameters,
     },
     milli::Milli,
     rewards::{Ratio, TaxType},
@@ -44,6 +44,8 @@ pub struct ConfigBuilder {
     linear_fee: Option<LinearFee>,
     per_certificate_fee: Option<PerCertificateFee>,
     per_vote_certificate_fee: Option<PerVoteCertificateFee>,
+    per_token_fee: Option<u64>,
+    governance_fee_discount: Option<GovernanceFeeDiscount>,
     leaders: Vec<BftLeaderId>,
     seed: u64,
     committees_ids: Vec<CommitteeId>,
@@ -53,6 +55,21 @@ pub struct ConfigBuilder {
     treasury_params: TaxType,
     reward_params: RewardParams,
     block_content_max_size: Option<u32>,
+    block_content_max_weight: Option<u32>,
+    block_content_soft_max_size: Option<u32>,
+    pool_registration_deposit: Option<Value>,
+    vote_plan_deposit: Option<Value>,
+    max_active_vote_plans_per_committee: Option<u32>,
+    handle_registration_deposit: Option<Value>,
+    handle_validity: Option<u32>,
+    canonical_fragment_order: bool,
+    voting_inactivity_threshold: Option<u32>,
+    vote_result_certification: bool,
+    treasury_budget: Option<TreasuryBudget>,
+    committee_sortition_size: Option<u32>,
+    binding_signature_context_epoch: Option<Epoch>,
+    hash_version_epoch: Option<Epoch>,
+    validation_policy: Option<ValidationPolicy>,
     kes_update_speed: u32,
     block0_date: Block0Date,
     consensus_version: ConsensusVersion,
@@ -70,6 +87,8 @@ impl ConfigBuilder {
             linear_fee: None,
             per_certificate_fee: None,
             per_vote_certificate_fee: None,
+            per_token_fee: None,
+            governance_fee_discount: None,
             committees_ids: Vec::new(),
             pool_capping_ratio: Ratio {
                 numerator: 0,
@@ -90,6 +109,21 @@ impl ConfigBuilder {
             treasury_params: TaxType::zero(),
             treasury: Value(1_000),
             block_content_max_size: None,
+            block_content_max_weight: None,
+            block_content_soft_max_size: None,
+            pool_registration_deposit: None,
+            vote_plan_deposit: None,
+            max_active_vote_plans_per_committee: None,
+            handle_registration_deposit: None,
+            handle_validity: None,
+            canonical_fragment_order: false,
+            voting_inactivity_threshold: None,
+            vote_result_certification: false,
+            treasury_budget: None,
+            committee_sortition_size: None,
+            binding_signature_context_epoch: None,
+            hash_version_epoch: None,
+            validation_policy: None,
             kes_update_speed: 3600 * 12,
             block0_date: Block0Date(0),
             consensus_version: ConsensusVersion::Bft,
@@ -171,6 +205,16 @@ impl ConfigBuilder {
         self
     }
 
+    pub fn with_per_token_fee(mut self, per_token_fee: u64) -> Self {
+        self.per_token_fee = Some(per_token_fee);
+        self
+    }
+
+    pub fn with_governance_fee_discount(mut self, discount: GovernanceFeeDiscount) -> Self {
+        self.governance_fee_discount = Some(discount);
+        self
+    }
+
     pub fn with_slots_per_epoch(mut self, slots_per_epoch: u32) -> Self {
         self.slots_per_epoch = slots_per_epoch;
         self
@@ -186,6 +230,81 @@ impl ConfigBuilder {
         self
     }
 
+    pub fn with_block_content_max_weight(mut self, block_content_max_weight: u32) -> Self {
+        self.block_content_max_weight = Some(block_content_max_weight);
+        self
+    }
+
+    pub fn with_block_content_soft_max_size(mut self, block_content_soft_max_size: u32) -> Self {
+        self.block_content_soft_max_size = Some(block_content_soft_max_size);
+        self
+    }
+
+    pub fn with_pool_registration_deposit(mut self, deposit: Value) -> Self {
+        self.pool_registration_deposit = Some(deposit);
+        self
+    }
+
+    pub fn with_vote_plan_deposit(mut self, deposit: Value) -> Self {
+        self.vote_plan_deposit = Some(deposit);
+        self
+    }
+
+    pub fn with_max_active_vote_plans_per_committee(mut self, max: u32) -> Self {
+        self.max_active_vote_plans_per_committee = Some(max);
+        self
+    }
+
+    pub fn with_handle_registration_deposit(mut self, deposit: Value) -> Self {
+        self.handle_registration_deposit = Some(deposit);
+        self
+    }
+
+    pub fn with_handle_validity(mut self, epochs: u32) -> Self {
+        self.handle_validity = Some(epochs);
+        self
+    }
+
+    pub fn with_canonical_fragment_order(mut self) -> Self {
+        self.canonical_fragment_order = true;
+        self
+    }
+
+    pub fn with_voting_inactivity_threshold(mut self, epochs: u32) -> Self {
+        self.voting_inactivity_threshold = Some(epochs);
+        self
+    }
+
+    pub fn with_vote_result_certification(mut self) -> Self {
+        self.vote_result_certification = true;
+        self
+    }
+
+    pub fn with_treasury_budget(mut self, budget: TreasuryBudget) -> Self {
+        self.treasury_budget = Some(budget);
+        self
+    }
+
+    pub fn with_committee_sortition_size(mut self, size: u32) -> Self {
+        self.committee_sortition_size = Some(size);
+        self
+    }
+
+    pub fn with_binding_signature_context_epoch(mut self, epoch: Epoch) -> Self {
+        self.binding_signature_context_epoch = Some(epoch);
+        self
+    }
+
+    pub fn with_hash_version_epoch(mut self, epoch: Epoch) -> Self {
+        self.hash_version_epoch = Some(epoch);
+        self
+    }
+
+    pub fn with_validation_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.validation_policy = Some(policy);
+        self
+    }
+
     pub fn with_kes_update_speed(mut self, kes_update_speed: u32) -> Self {
         self.kes_update_speed = kes_update_speed;
         self
@@ -244,6 +363,68 @@ impl ConfigBuilder {
             ie.push(ConfigParam::BlockContentMaxSize(block_content_max_size));
         }
 
+        if let Some(block_content_max_weight) = self.block_content_max_weight {
+            ie.push(ConfigParam::BlockContentMaxWeight(block_content_max_weight));
+        }
+
+        if let Some(block_content_soft_max_size) = self.block_content_soft_max_size {
+            ie.push(ConfigParam::BlockContentSoftMaxSize(
+                block_content_soft_max_size,
+            ));
+        }
+
+        if let Some(deposit) = self.pool_registration_deposit {
+            ie.push(ConfigParam::PoolRegistrationDeposit(deposit));
+        }
+
+        if let Some(deposit) = self.vote_plan_deposit {
+            ie.push(ConfigParam::VotePlanDeposit(deposit));
+        }
+
+        if let Some(max) = self.max_active_vote_plans_per_committee {
+            ie.push(ConfigParam::MaxActiveVotePlansPerCommittee(max));
+        }
+
+        if let Some(deposit) = self.handle_registration_deposit {
+            ie.push(ConfigParam::HandleRegistrationDeposit(deposit));
+        }
+
+        if let Some(validity) = self.handle_validity {
+            ie.push(ConfigParam::HandleValidity(validity));
+        }
+
+        if self.canonical_fragment_order {
+            ie.push(ConfigParam::CanonicalFragmentOrder(true));
+        }
+
+        if let Some(threshold) = self.voting_inactivity_threshold {
+            ie.push(ConfigParam::VotingInactivityThreshold(threshold));
+        }
+
+        if self.vote_result_certification {
+            ie.push(ConfigParam::VoteResultCertification(true));
+        }
+
+        if let Some(budget) = self.treasury_budget {
+            ie.push(ConfigParam::TreasuryBudget(budget));
+        }
+
+        if let Some(size) = self.committee_sortition_size {
+            ie.push(ConfigParam::CommitteeSortitionSize(size));
+        }
+
+        if let Some(epoch) = self.binding_signature_context_epoch {
+            ie.push(ConfigParam::BindingSignatureContextEpoch(epoch));
+        }
+
+        if let Some(epoch) = self.hash_version_epoch {
+            ie.push(ConfigParam::HashVersionEpoch(epoch));
+        }
+
+        if let Some(policy) = self.validation_policy {
+            ie.push(ConfigParam::ValidationPolicy(policy));
+        }
+
         if self.per_certificate_fee.is_some() {
             ie.push(ConfigParam::PerCertificateFees(
                 self.per_certificate_fee.clone().unwrap(),
@@ -256,6 +437,14 @@ impl ConfigBuilder {
             ));
         }
 
+        if let Some(fee) = self.per_token_fee {
+            ie.push(ConfigParam::PerTokenFee(fee));
+        }
+
+        if let Some(discount) = self.governance_fee_discount {
+            ie.push(ConfigParam::GovernanceFeeDiscount(discount));
+        }
+
         for committee_id in self.committees_ids {
             ie.push(ConfigParam::AddCommitteeId(committee_id));
         }
@@ -333,7 +522,7 @@ impl LedgerBuilder {
 
     // add a fragment that pre-fill the address with a specific value at ledger start
     pub fn prefill_address(self, address: Address, value: Value) -> Self {
-        self.prefill_output(Output { address, value })
+        self.prefill_output(Output::from_address(address, value))
     }
 
     pub fn prefill_output(self, output: Output<Address>) -> Self {
@@ -1,10 +1,11 @@
 use crate::{
     certificate::{
         DecryptedPrivateTally, EncryptedVoteTally, ExternalProposalId, Proposal, VoteCast,
-        VotePlan, VoteTally,
+        VoteCastBatch, VotePlan, VoteResultCertification, VoteTally,
     },
+    date::Epoch,
     fee::LinearFee,
-    key::Hash,
+    key::{GenesisPraosLeader, Hash},
     ledger::Error as LedgerError,
     testing::{
         data::{StakePool, Wallet},
@@ -138,6 +139,19 @@ impl Controller {
         test_ledger.apply_fragment(&fragment, test_ledger.date())
     }
 
+    pub fn delegates_from(
+        &self,
+        from: &Wallet,
+        stake_pool: &StakePool,
+        activation: Epoch,
+        test_ledger: &mut TestLedger,
+    ) -> Result<(), LedgerError> {
+        let fragment = self
+            .fragment_factory
+            .delegation_from(from, stake_pool, activation);
+        test_ledger.apply_fragment(&fragment, test_ledger.date())
+    }
+
     pub fn delegates_different_funder(
         &self,
         funder: &Wallet,
@@ -180,6 +194,19 @@ impl Controller {
         test_ledger.apply_fragment(&fragment, test_ledger.date())
     }
 
+    pub fn owner_delegates_from(
+        &self,
+        from: &Wallet,
+        stake_pool: &StakePool,
+        activation: Epoch,
+        test_ledger: &mut TestLedger,
+    ) -> Result<(), LedgerError> {
+        let fragment = self
+            .fragment_factory
+            .owner_delegation_from(from, stake_pool, activation);
+        test_ledger.apply_fragment(&fragment, test_ledger.date())
+    }
+
     pub fn retire<'a>(
         &'a self,
         owners: impl IntoIterator<Item = &'a Wallet>,
@@ -203,6 +230,19 @@ impl Controller {
         test_ledger.apply_fragment(&fragment, test_ledger.date())
     }
 
+    pub fn rotate_keys<'a>(
+        &'a self,
+        stake_pool: &'a StakePool,
+        keys: GenesisPraosLeader,
+        owners: impl IntoIterator<Item = &'a Wallet>,
+        test_ledger: &'a mut TestLedger,
+    ) -> Result<(), LedgerError> {
+        let fragment = self
+            .fragment_factory
+            .stake_pool_keys_rotation(owners, stake_pool, keys);
+        test_ledger.apply_fragment(&fragment, test_ledger.date())
+    }
+
     pub fn cast_vote_public(
         &self,
         owner: &Wallet,
@@ -274,6 +314,32 @@ impl Controller {
         test_ledger.apply_fragment(&fragment, test_ledger.date())
     }
 
+    pub fn cast_votes_public_batch(
+        &self,
+        owner: &Wallet,
+        vote_plan_def: &VotePlanDef,
+        choices: &[(ExternalProposalId, Choice)],
+        test_ledger: &mut TestLedger,
+    ) -> Result<(), LedgerError> {
+        let vote_plan: VotePlan = vote_plan_def.clone().into();
+        let ballots = choices
+            .iter()
+            .map(|(id, choice)| {
+                let index = vote_plan
+                    .proposals()
+                    .iter()
+                    .position(|x| *x.external_id() == *id)
+                    .expect("cannot find proposal");
+                (index as u8, Payload::public(*choice))
+            })
+            .collect();
+        let vote_cast_batch = VoteCastBatch::new(vote_plan.to_id(), ballots);
+        let fragment = self
+            .fragment_factory
+            .vote_cast_batch(owner, vote_cast_batch);
+        test_ledger.apply_fragment(&fragment, test_ledger.date())
+    }
+
     pub fn encrypted_tally(
         &self,
         owner: &Wallet,
@@ -314,6 +380,22 @@ impl Controller {
         let fragment = self.fragment_factory.vote_tally(owner, vote_tally);
         test_ledger.apply_fragment(&fragment, test_ledger.date())
     }
+
+    pub fn certify_vote_result(
+        &self,
+        committee_members: &[&Wallet],
+        vote_plan_def: &VotePlanDef,
+        results: Vec<Box<[u64]>>,
+        test_ledger: &mut TestLedger,
+    ) -> Result<(), LedgerError> {
+        let vote_plan: VotePlan = vote_plan_def.clone().into();
+        let certification = VoteResultCertification::new(vote_plan.to_id(), results);
+
+        let fragment = self
+            .fragment_factory
+            .vote_result_certification(committee_members.iter().copied(), certification);
+        test_ledger.apply_fragment(&fragment, test_ledger.date())
+    }
 }
 
 #[cfg(test)]
@@ -1,17 +1,21 @@
 use crate::{
     accounting::account::{DelegationRatio, DelegationType},
     certificate::{
-        Certificate, EncryptedVoteTally, PoolId, PoolUpdate, VoteCast, VotePlan, VoteTally,
+        Certificate, EncryptedVoteTally, PoolId, PoolKeysRotation, PoolUpdate, VoteCast,
+        VoteCastBatch, VotePlan, VoteResultCertification, VoteTally,
     },
+    date::Epoch,
     fee::LinearFee,
     fragment::Fragment,
-    key::Hash,
+    key::{GenesisPraosLeader, Hash},
     testing::{
         builders::{
             build_no_stake_delegation, build_owner_stake_delegation,
-            build_owner_stake_full_delegation, build_stake_delegation_cert,
-            build_stake_pool_registration_cert, build_stake_pool_retirement_cert,
-            build_stake_pool_update_cert, TestTxBuilder, TestTxCertBuilder,
+            build_owner_stake_delegation_from, build_owner_stake_full_delegation,
+            build_stake_delegation_cert, build_stake_delegation_cert_from,
+            build_stake_pool_keys_rotation_cert, build_stake_pool_registration_cert,
+            build_stake_pool_retirement_cert, build_stake_pool_update_cert, TestTxBuilder,
+            TestTxCertBuilder,
         },
         data::{StakePool, Wallet},
         ledger::TestLedger,
@@ -61,6 +65,20 @@ impl FragmentFactory {
         self.transaction_with_cert(Some(from), &cert)
     }
 
+    pub fn delegation_from(
+        &self,
+        from: &Wallet,
+        stake_pool: &StakePool,
+        activation: Epoch,
+    ) -> Fragment {
+        let cert = build_stake_delegation_cert_from(
+            &stake_pool.info(),
+            &from.as_account_data(),
+            activation,
+        );
+        self.transaction_with_cert(Some(from), &cert)
+    }
+
     pub fn delegation_different_funder(
         &self,
         funder: &Wallet,
@@ -94,6 +112,17 @@ impl FragmentFactory {
         self.transaction_with_cert(Some(from), &cert)
     }
 
+    pub fn owner_delegation_from(
+        &self,
+        from: &Wallet,
+        stake_pool: &StakePool,
+        activation: Epoch,
+    ) -> Fragment {
+        let cert =
+            build_owner_stake_delegation_from(DelegationType::Full(stake_pool.id()), activation);
+        self.transaction_with_cert(Some(from), &cert)
+    }
+
     pub fn stake_pool_retire<'a>(
         &self,
         owners: impl IntoIterator<Item = &'a Wallet>,
@@ -118,6 +147,22 @@ impl FragmentFactory {
         self.transaction_with_cert(owners, &certificate)
     }
 
+    pub fn stake_pool_keys_rotation<'a>(
+        &self,
+        owners: impl IntoIterator<Item = &'a Wallet>,
+        stake_pool: &StakePool,
+        keys: GenesisPraosLeader,
+    ) -> Fragment {
+        let rotation = PoolKeysRotation {
+            pool_id: stake_pool.id(),
+            last_pool_reg_hash: stake_pool.info().to_id(),
+            start_validity: stake_pool.info().start_validity,
+            keys,
+        };
+        let certificate = build_stake_pool_keys_rotation_cert(&rotation);
+        self.transaction_with_cert(owners, &certificate)
+    }
+
     pub fn vote_plan(&self, owner: &Wallet, vote_plan: VotePlan) -> Fragment {
         self.transaction_with_cert(Some(owner), &vote_plan.into())
     }
@@ -126,6 +171,10 @@ impl FragmentFactory {
         self.transaction_with_cert(Some(owner), &vote_cast.into())
     }
 
+    pub fn vote_cast_batch(&self, owner: &Wallet, vote_cast_batch: VoteCastBatch) -> Fragment {
+        self.transaction_with_cert(Some(owner), &vote_cast_batch.into())
+    }
+
     pub fn vote_encrypted_tally(
         &self,
         owner: &Wallet,
@@ -138,6 +187,15 @@ impl FragmentFactory {
         self.transaction_with_cert(Some(owner), &vote_tally.into())
     }
 
+    /// the first of the committee members pays for the transaction
+    pub fn vote_result_certification<'a>(
+        &self,
+        committee_members: impl IntoIterator<Item = &'a Wallet>,
+        certification: VoteResultCertification,
+    ) -> Fragment {
+        self.transaction_with_cert(committee_members, &certification.into())
+    }
+
     fn transaction_with_cert<'a>(
         &self,
         wallets: impl IntoIterator<Item = &'a Wallet>,
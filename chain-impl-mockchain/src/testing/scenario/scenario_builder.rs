@@ -230,6 +230,7 @@ impl ScenarioBuilder {
                     builder.with_tax_type(tax_type);
                 }
                 builder.with_reward_account(stake_pool_def.has_reward_account);
+                builder.with_delegators_account(stake_pool_def.has_delegators_account);
             }
         }
         builder.build()
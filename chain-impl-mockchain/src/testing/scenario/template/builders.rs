@@ -1,11 +1,13 @@
 use super::{StakePoolTemplate, WalletTemplate};
-use crate::certificate::VoteAction;
+use crate::certificate::{VoteAction, VotePlanId};
 use crate::ledger::governance::{ParametersGovernanceAction, TreasuryGovernanceAction};
 use crate::testing::scenario::template::ExternalProposalId;
 use crate::testing::scenario::template::ProposalDef;
 use crate::testing::scenario::template::VotePlanDef;
 use crate::{
+    config::ConfigParam,
     date::BlockDate,
+    fragment::ConfigParams,
     rewards::{Ratio, TaxType},
     testing::data::Wallet,
     testing::scenario::{scenario_builder::ScenarioBuilderError, template::StakePoolDef},
@@ -166,6 +168,7 @@ pub struct StakePoolDefBuilder {
     alias: String,
     permissions_threshold: u8,
     reward_account: bool,
+    delegators_account: bool,
     tax_type: Option<TaxType>,
 }
 
@@ -175,6 +178,7 @@ impl StakePoolDefBuilder {
             alias: alias.to_owned(),
             permissions_threshold: 1u8,
             reward_account: false,
+            delegators_account: false,
             tax_type: None,
         }
     }
@@ -189,6 +193,11 @@ impl StakePoolDefBuilder {
         self
     }
 
+    pub fn with_delegators_account(&mut self, delegators_account: bool) -> &mut Self {
+        self.delegators_account = delegators_account;
+        self
+    }
+
     pub fn tax_ratio(&mut self, numerator: u64, denominator: u64) -> &mut Self {
         self.tax_type = Some(TaxType {
             fixed: Value(0),
@@ -228,6 +237,7 @@ impl StakePoolDefBuilder {
             alias: self.alias.clone(),
             permissions_threshold: Some(self.permissions_threshold),
             has_reward_account: self.reward_account,
+            has_delegators_account: self.delegators_account,
             tax_type: self.tax_type,
         }
     }
@@ -243,6 +253,7 @@ pub struct VotePlanDefBuilder {
     end_tally_date: Option<BlockDate>,
     committee_keys: Vec<MemberPublicKey>,
     proposals: Vec<ProposalDef>,
+    enactment_delay: u32,
 }
 
 impl VotePlanDefBuilder {
@@ -256,6 +267,7 @@ impl VotePlanDefBuilder {
             end_tally_date: Option::None,
             committee_keys: Vec::new(),
             proposals: Vec::new(),
+            enactment_delay: 0,
         }
     }
 
@@ -290,6 +302,18 @@ impl VotePlanDefBuilder {
         self
     }
 
+    pub fn vote_dates(
+        &mut self,
+        vote_date: BlockDate,
+        tally_date: BlockDate,
+        end_tally_date: BlockDate,
+    ) -> &mut Self {
+        self.vote_date = Some(vote_date);
+        self.tally_date = Some(tally_date);
+        self.end_tally_date = Some(end_tally_date);
+        self
+    }
+
     pub fn consecutive_epoch_dates(&mut self) -> &mut Self {
         self.vote_date = Some(BlockDate {
             epoch: 0,
@@ -306,6 +330,11 @@ impl VotePlanDefBuilder {
         self
     }
 
+    pub fn enactment_delay(&mut self, epochs: u32) -> &mut Self {
+        self.enactment_delay = epochs;
+        self
+    }
+
     pub fn with_proposal(&mut self, proposal_builder: &mut ProposalDefBuilder) -> &mut Self {
         self.proposals.push(proposal_builder.clone().build());
         self
@@ -321,6 +350,7 @@ impl VotePlanDefBuilder {
             end_tally_date: self.end_tally_date.unwrap(),
             proposals: self.proposals,
             committee_keys: self.committee_keys,
+            enactment_delay: self.enactment_delay,
         }
     }
 }
@@ -376,6 +406,24 @@ impl ProposalDefBuilder {
         self
     }
 
+    pub fn action_cancel_enactment(&mut self, vote_plan: VotePlanId) -> &mut Self {
+        self.action_type = VoteAction::Parameters {
+            action: ParametersGovernanceAction::CancelEnactment { vote_plan },
+        };
+        self
+    }
+
+    pub fn action_update_parameters(&mut self, changes: Vec<ConfigParam>) -> &mut Self {
+        let mut params = ConfigParams::new();
+        for change in changes {
+            params.push(change);
+        }
+        self.action_type = VoteAction::Parameters {
+            action: ParametersGovernanceAction::UpdateParameters { changes: params },
+        };
+        self
+    }
+
     pub fn build(self) -> ProposalDef {
         ProposalDef {
             id: self.id,
@@ -84,6 +84,7 @@ pub struct StakePoolDef {
     pub alias: String,
     pub permissions_threshold: Option<u8>,
     pub has_reward_account: bool,
+    pub has_delegators_account: bool,
     pub tax_type: Option<TaxType>,
 }
 
@@ -106,6 +107,7 @@ pub struct VotePlanDef {
     end_tally_date: BlockDate,
     committee_keys: Vec<MemberPublicKey>,
     proposals: Vec<ProposalDef>,
+    enactment_delay: u32,
 }
 
 impl VotePlanDef {
@@ -154,6 +156,7 @@ impl Into<VotePlan> for VotePlanDef {
             self.payload_type,
             self.committee_keys,
         )
+        .with_enactment_delay(self.enactment_delay)
     }
 }
 
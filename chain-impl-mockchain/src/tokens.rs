@@ -0,0 +1,272 @@
+//! Tokens carried by the transaction outputs alongside the native value.
+//!
+//! An output may carry a [`TokenBundle`]: a set of (token, value) pairs with
+//! at most one entry per token. The bundle is encoded after the native value
+//! of the output as the number of tokens followed by the entries sorted by
+//! token identifier, none of them of zero value, so that a bundle only has
+//! one valid encoding.
+
+use crate::key::Hash;
+use crate::value::{Value, ValueError};
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum number of tokens carried by a single bundle
+pub const TOKEN_BUNDLE_MAX_TOKENS: usize = 255;
+
+/// Identifier of a token
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenIdentifier(Hash);
+
+impl TokenIdentifier {
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl From<Hash> for TokenIdentifier {
+    fn from(hash: Hash) -> Self {
+        TokenIdentifier(hash)
+    }
+}
+
+impl From<[u8; 32]> for TokenIdentifier {
+    fn from(bytes: [u8; 32]) -> Self {
+        TokenIdentifier(Hash::from_bytes(bytes))
+    }
+}
+
+impl AsRef<[u8]> for TokenIdentifier {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl fmt::Display for TokenIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for TokenIdentifier {
+    type Err = <Hash as FromStr>::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Hash::from_str(s).map(TokenIdentifier)
+    }
+}
+
+impl Readable for TokenIdentifier {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        Hash::read(buf).map(TokenIdentifier)
+    }
+}
+
+/// Values of the tokens carried by an output or a set of outputs
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TokenBundle(BTreeMap<TokenIdentifier, Value>);
+
+impl TokenBundle {
+    pub fn new() -> Self {
+        TokenBundle(BTreeMap::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Number of distinct tokens in the bundle
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Value of the given token in the bundle, zero if absent
+    pub fn get(&self, token: &TokenIdentifier) -> Value {
+        self.0.get(token).copied().unwrap_or_else(Value::zero)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&TokenIdentifier, &Value)> {
+        self.0.iter()
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = &TokenIdentifier> {
+        self.0.keys()
+    }
+
+    /// Add some value of the given token to the bundle. Adding a zero value
+    /// leaves the bundle untouched.
+    pub fn add(&mut self, token: TokenIdentifier, value: Value) -> Result<(), ValueError> {
+        if value == Value::zero() {
+            return Ok(());
+        }
+        let total = self.get(&token).checked_add(value)?;
+        self.0.insert(token, total);
+        Ok(())
+    }
+
+    /// Same as [`add`](Self::add), consuming and returning the bundle
+    pub fn with(mut self, token: TokenIdentifier, value: Value) -> Result<Self, ValueError> {
+        self.add(token, value)?;
+        Ok(self)
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Result<Self, ValueError> {
+        let mut sum = self.clone();
+        for (token, value) in other.iter() {
+            sum.add(*token, *value)?;
+        }
+        Ok(sum)
+    }
+
+    /// Remove the tokens of `other` from this bundle, failing with
+    /// [`ValueError::NegativeAmount`] if this bundle does not carry enough
+    /// of one of them
+    pub fn checked_sub(&self, other: &Self) -> Result<Self, ValueError> {
+        let mut remaining = self.clone();
+        for (token, value) in other.iter() {
+            let left = remaining.get(token).checked_sub(*value)?;
+            if left == Value::zero() {
+                remaining.0.remove(token);
+            } else {
+                remaining.0.insert(*token, left);
+            }
+        }
+        Ok(remaining)
+    }
+
+    pub fn sum<'a, I>(mut bundles: I) -> Result<Self, ValueError>
+    where
+        I: Iterator<Item = &'a Self>,
+    {
+        bundles.try_fold(TokenBundle::new(), |acc, bundle| acc.checked_add(bundle))
+    }
+
+    /// Encode the bundle as it appears in the outputs.
+    ///
+    /// This will assert if the bundle carries more than
+    /// [`TOKEN_BUNDLE_MAX_TOKENS`] tokens, as the number of tokens is
+    /// encoded as u8.
+    pub fn bytes(&self) -> Vec<u8> {
+        assert!(self.len() <= TOKEN_BUNDLE_MAX_TOKENS);
+        let mut bytes = Vec::with_capacity(1 + self.len() * (32 + 8));
+        bytes.push(self.len() as u8);
+        for (token, value) in self.iter() {
+            bytes.extend_from_slice(token.as_bytes());
+            bytes.extend_from_slice(&value.bytes());
+        }
+        bytes
+    }
+}
+
+impl Readable for TokenBundle {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let nb_tokens = buf.get_u8()?;
+        let mut bundle = BTreeMap::new();
+        let mut previous = None;
+        for _ in 0..nb_tokens {
+            let token = TokenIdentifier::read(buf)?;
+            let value = Value::read(buf)?;
+            if matches!(previous, Some(previous) if previous >= token) {
+                return Err(ReadError::StructureInvalid(
+                    "token bundle entries are not strictly sorted".to_string(),
+                ));
+            }
+            if value == Value::zero() {
+                return Err(ReadError::StructureInvalid(format!(
+                    "token {} has a zero value in the bundle",
+                    token
+                )));
+            }
+            previous = Some(token);
+            bundle.insert(token, value);
+        }
+        Ok(TokenBundle(bundle))
+    }
+}
+
+impl fmt::Display for TokenBundle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (token, value)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", token, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(test, feature = "property-test-api"))]
+mod test {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+
+    impl Arbitrary for TokenIdentifier {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            TokenIdentifier(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for TokenBundle {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let mut bundle = TokenBundle::new();
+            for _ in 0..(u8::arbitrary(g) % 4) {
+                let value = Value(u64::arbitrary(g) % 1_000_000);
+                bundle.add(Arbitrary::arbitrary(g), value).unwrap();
+            }
+            bundle
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use quickcheck_macros::quickcheck;
+
+        fn token(byte: u8) -> TokenIdentifier {
+            TokenIdentifier::from([byte; 32])
+        }
+
+        #[quickcheck]
+        fn bundle_bytes_roundtrip(bundle: TokenBundle) -> bool {
+            let bytes = bundle.bytes();
+            let mut buf = ReadBuf::from(&bytes[..]);
+            TokenBundle::read(&mut buf).unwrap() == bundle && buf.is_end()
+        }
+
+        #[test]
+        fn non_canonical_bundles_are_rejected() {
+            let mut unsorted = vec![2];
+            unsorted.extend_from_slice(token(2).as_bytes());
+            unsorted.extend_from_slice(&Value(1).bytes());
+            unsorted.extend_from_slice(token(1).as_bytes());
+            unsorted.extend_from_slice(&Value(1).bytes());
+            assert!(TokenBundle::read(&mut ReadBuf::from(&unsorted[..])).is_err());
+
+            let mut zero = vec![1];
+            zero.extend_from_slice(token(1).as_bytes());
+            zero.extend_from_slice(&Value(0).bytes());
+            assert!(TokenBundle::read(&mut ReadBuf::from(&zero[..])).is_err());
+        }
+
+        #[test]
+        fn add_and_sub() {
+            let a = TokenBundle::new()
+                .with(token(1), Value(10))
+                .unwrap()
+                .with(token(2), Value(5))
+                .unwrap();
+            let b = TokenBundle::new().with(token(2), Value(5)).unwrap();
+
+            let sum = a.checked_add(&b).unwrap();
+            assert_eq!(sum.get(&token(2)), Value(10));
+            assert_eq!(sum.checked_sub(&b).unwrap(), a);
+
+            let remaining = a.checked_sub(&b).unwrap();
+            assert_eq!(remaining.len(), 1);
+            assert_eq!(remaining.get(&token(2)), Value::zero());
+            assert_eq!(b.checked_sub(&a), Err(ValueError::NegativeAmount));
+        }
+    }
+}
@@ -3,8 +3,11 @@ use super::payload::{NoExtra, Payload};
 use super::transaction::{
     Transaction, TransactionAuthData, TransactionBindingAuthData, TransactionStruct,
 };
-use super::transfer::Output;
+use super::transfer::{Output, OUTPUT_WITH_TOKENS_TAG};
+use super::validity::ValidityInterval;
 use super::witness::Witness;
+use crate::fragment::Fragment;
+use crate::ledger::{self, LedgerView};
 use chain_addr::Address;
 use std::marker::PhantomData;
 
@@ -52,6 +55,7 @@ impl TxBuilder {
                 sz: 0,
                 nb_inputs: 0,
                 nb_outputs: 0,
+                validity: None,
                 inputs: 0,
                 outputs: 0,
                 witnesses: 0,
@@ -69,7 +73,10 @@ impl<State> TxBuilderState<State> {
 }
 
 impl TxBuilderState<SetPayload> {
-    /// Set the payload of this transaction
+    /// Set the payload of this transaction, which is valid in all the blocks
+    /// unless [`set_validity`] is called
+    ///
+    /// [`set_validity`]: TxBuilderState::set_validity
     pub fn set_payload<P: Payload>(mut self, payload: &P) -> TxBuilderState<SetIOs<P>> {
         if P::HAS_DATA {
             self.data.extend_from_slice(payload.payload_data().as_ref());
@@ -88,10 +95,28 @@ impl TxBuilderState<SetPayload> {
 }
 
 impl<P> TxBuilderState<SetIOs<P>> {
+    /// Set the dates of the blocks the transaction can be included in
+    ///
+    /// A bounded interval makes the transaction use the layout
+    /// [`TransactionLayout::V1`], an unbounded one keeps the legacy layout.
+    ///
+    /// [`TransactionLayout::V1`]: super::TransactionLayout::V1
+    pub fn set_validity(mut self, validity: ValidityInterval) -> Self {
+        if let Some(start) = self.tstruct.validity.take() {
+            self.data.truncate(FRAGMENT_OVERHEAD + start);
+        }
+        if validity != ValidityInterval::unbounded() {
+            self.tstruct.validity = Some(self.current_pos());
+            self.data.extend_from_slice(&validity.bytes());
+        }
+        self
+    }
+
     /// Set the inputs and outputs of this transaction
     ///
-    /// This cannot accept more than 255 inputs, 255 outputs, since
-    /// the length is encoded as u8, and hence will assert.
+    /// This cannot accept more than 255 inputs, 255 outputs, or outputs
+    /// carrying more than 255 tokens, since the lengths are encoded as u8,
+    /// and hence will assert.
     ///
     /// Note that further restriction apply to the ledger,
     /// which only accept up to 254 outputs
@@ -121,8 +146,14 @@ impl<P> TxBuilderState<SetIOs<P>> {
         self.tstruct.outputs = self.current_pos();
 
         for o in outputs {
+            if !o.tokens.is_empty() {
+                self.data.push(OUTPUT_WITH_TOKENS_TAG);
+            }
             self.data.extend_from_slice(&o.address.to_bytes());
             self.data.extend_from_slice(&o.value.bytes());
+            if !o.tokens.is_empty() {
+                self.data.extend_from_slice(&o.tokens.bytes());
+            }
         }
 
         TxBuilderState {
@@ -193,6 +224,17 @@ impl<P: Payload> TxBuilderState<SetAuthData<P>> {
             phantom: PhantomData,
         }
     }
+
+    /// Check the transaction that would be built with the given authenticated
+    /// data against a view of the ledger, returning the error the ledger
+    /// would report when applying it.
+    pub fn dry_run(&self, view: &LedgerView, auth_data: &P::Auth) -> Result<(), ledger::Error>
+    where
+        Fragment: From<Transaction<P>>,
+    {
+        let fragment = self.clone().set_payload_auth(auth_data).into();
+        view.dry_run(&fragment)
+    }
 }
 
 #[cfg(test)]
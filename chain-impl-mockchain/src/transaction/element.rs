@@ -1,8 +1,12 @@
-use crate::key::deserialize_signature;
+use crate::key::{chain_crypto_sig_err, deserialize_signature};
 use crate::transaction::TransactionBindingAuthData;
 use crate::value::{Value, ValueError};
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
-use chain_crypto::{digest::DigestOf, Blake2b256, Ed25519, PublicKey, Signature, Verification};
+use chain_crypto::{
+    digest::DigestOf, AnyPublicKey, AnySignature, Blake2b256, ContextSignature, Ed25519, PublicKey,
+    Signature, SignatureAlgorithmId, SignatureError, SigningContext, ValidationPolicy,
+    Verification,
+};
 use thiserror::Error;
 use typed_bytes::ByteBuilder;
 
@@ -22,34 +26,87 @@ impl AsRef<[u8]> for TransactionSignData {
 
 pub type TransactionSignDataHash = DigestOf<Blake2b256, TransactionSignData>;
 
+/// Domain separation tag of the hashes signed by the witnesses, see
+/// [`HashVersion`](crate::key::HashVersion)
+pub const TRANSACTION_SIGN_DATA_DOMAIN: &[u8] = b"chain-libs transaction sign data";
+
 // cannot use TransactionSignDataHash<'a> as a phantom in the signature,
 // as the lifetime is still kept behind, so invent a new phantom type
 // to track the SignDataHash
 #[derive(Debug, Clone)]
 pub struct TransactionBindingAuthDataPhantom();
 
+/// The signing context of the transaction binding signatures
+pub struct TransactionBindingContext;
+
+impl SigningContext for TransactionBindingContext {
+    const CONTEXT: &'static [u8] = b"chain-libs transaction binding";
+}
+
+pub type TransactionBindingSignature =
+    ContextSignature<TransactionBindingContext, TransactionBindingAuthDataPhantom, Ed25519>;
+
+/// Whether the binding signatures made without context are accepted, see
+/// [`Settings::binding_signature_context`](crate::setting::Settings::binding_signature_context)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingSignatureContext {
+    /// the signatures made without context before the
+    /// [`TransactionBindingContext`] was introduced are still accepted
+    Optional,
+    /// only the signatures made in the [`TransactionBindingContext`] are
+    /// accepted
+    Required,
+}
+
+/// Binding signature of an account.
+///
+/// The signatures are made in the [`TransactionBindingContext`], the
+/// signatures made without context before it was introduced are accepted
+/// as long as the context is [`BindingSignatureContext::Optional`].
 #[derive(Debug, Clone)]
 pub struct SingleAccountBindingSignature(
     pub(crate) Signature<TransactionBindingAuthDataPhantom, Ed25519>,
 );
 
+fn verify_ed25519_binding(
+    signature: &Signature<TransactionBindingAuthDataPhantom, Ed25519>,
+    pk: &PublicKey<Ed25519>,
+    data: &TransactionBindingAuthData,
+    context: BindingSignatureContext,
+) -> Verification {
+    let in_context = TransactionBindingSignature::from_binary(signature.as_ref())
+        .map(|signature| signature.verify_slice(pk, data.0));
+    match (in_context, context) {
+        (Ok(Verification::Success), _) => Verification::Success,
+        // signature made before the binding signatures had a context
+        (_, BindingSignatureContext::Optional) => signature.verify_slice(pk, data.0),
+        (_, BindingSignatureContext::Required) => Verification::Failed,
+    }
+}
+
 impl SingleAccountBindingSignature {
     pub fn verify_slice<'a>(
         &self,
         pk: &PublicKey<Ed25519>,
         data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
     ) -> Verification {
-        self.0.verify_slice(pk, data.0)
+        verify_ed25519_binding(&self.0, pk, data, context)
+    }
+
+    /// Check the encoding of the signature against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> Result<(), SignatureError> {
+        self.0.validate(policy)
     }
 
     pub fn new<'a, F>(data: &TransactionBindingAuthData<'a>, sign: F) -> Self
     where
-        F: FnOnce(
-            &TransactionBindingAuthData<'a>,
-        ) -> Signature<TransactionBindingAuthDataPhantom, Ed25519>,
+        F: FnOnce(&TransactionBindingAuthData<'a>) -> TransactionBindingSignature,
     {
         let sig = sign(data);
-        SingleAccountBindingSignature(sig) // sk.sign_slice(data.0))
+        SingleAccountBindingSignature(
+            Signature::from_binary(sig.as_ref()).expect("signature of the same algorithm"),
+        )
     }
 }
 
@@ -69,18 +126,58 @@ impl Readable for SingleAccountBindingSignature {
 pub enum AccountBindingSignature {
     Single(SingleAccountBindingSignature),
     Multi(u32), // TODO
+    /// Signature of any of the algorithms of [`SignatureAlgorithmId`], the
+    /// Ed25519 ones are verified like the [`SingleAccountBindingSignature`]
+    ///
+    /// The accounts are identified by ed25519 keys, so only the Ed25519
+    /// signatures are accepted by the ledger for now. The other signatures
+    /// of the certificates (pool owners and operators, vote plan and tally
+    /// proofs) are still ed25519 only, their encoding has no room for an
+    /// algorithm identifier.
+    Any(AnySignature<TransactionBindingAuthDataPhantom>),
 }
 
 impl AccountBindingSignature {
     pub fn new_single<'a, F>(data: &TransactionBindingAuthData<'a>, sign: F) -> Self
     where
-        F: FnOnce(
-            &TransactionBindingAuthData<'a>,
-        ) -> Signature<TransactionBindingAuthDataPhantom, Ed25519>,
+        F: FnOnce(&TransactionBindingAuthData<'a>) -> TransactionBindingSignature,
     {
         AccountBindingSignature::Single(SingleAccountBindingSignature::new(data, sign))
     }
 
+    /// Verify the signature with the key of the signing account, fails if
+    /// the key is not of the algorithm of the signature
+    pub fn verify_slice<'a>(
+        &self,
+        pk: &AnyPublicKey,
+        data: &TransactionBindingAuthData<'a>,
+        context: BindingSignatureContext,
+    ) -> Verification {
+        match (self, pk) {
+            (AccountBindingSignature::Single(sig), AnyPublicKey::Ed25519(pk)) => {
+                sig.verify_slice(pk, data, context)
+            }
+            (
+                AccountBindingSignature::Any(AnySignature::Ed25519(sig)),
+                AnyPublicKey::Ed25519(pk),
+            ) => verify_ed25519_binding(sig, pk, data, context),
+            (AccountBindingSignature::Any(sig), pk) => sig.verify_slice(pk, data.0),
+            // TODO
+            (AccountBindingSignature::Multi(_), _) => Verification::Failed,
+            (AccountBindingSignature::Single(_), _) => Verification::Failed,
+        }
+    }
+
+    /// Check the encoding of the signature against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> Result<(), SignatureError> {
+        match self {
+            AccountBindingSignature::Single(sig) => sig.validate(policy),
+            AccountBindingSignature::Any(sig) => sig.validate(policy),
+            // TODO
+            AccountBindingSignature::Multi(_) => Ok(()),
+        }
+    }
+
     pub fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
         match self {
             AccountBindingSignature::Single(sig) => bb.u8(1).bytes(sig.as_ref()),
@@ -88,6 +185,9 @@ impl AccountBindingSignature {
                 bb.u8(2);
                 unimplemented!()
             }
+            AccountBindingSignature::Any(sig) => {
+                bb.u8(3).u8(sig.algorithm().to_u8()).bytes(sig.as_ref())
+            }
         }
     }
 }
@@ -100,6 +200,15 @@ impl Readable for AccountBindingSignature {
                 Ok(AccountBindingSignature::Single(sig))
             }
             2 => unimplemented!(),
+            3 => {
+                let tag = buf.get_u8()?;
+                let algorithm =
+                    SignatureAlgorithmId::from_u8(tag).ok_or(ReadError::UnknownTag(tag as u32))?;
+                let bytes = buf.get_slice(algorithm.signature_size())?;
+                let sig =
+                    AnySignature::from_binary(algorithm, bytes).map_err(chain_crypto_sig_err)?;
+                Ok(AccountBindingSignature::Any(sig))
+            }
             n => Err(ReadError::UnknownTag(n as u32)),
         }
     }
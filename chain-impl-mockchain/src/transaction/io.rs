@@ -1,7 +1,9 @@
 use super::{Balance, Input, Output, Payload, PayloadSlice};
 use crate::fee::FeeAlgorithm;
+use crate::tokens::{TokenBundle, TOKEN_BUNDLE_MAX_TOKENS};
 use crate::value::{Value, ValueError};
 use chain_addr::Address;
+use std::collections::BTreeSet;
 use std::error;
 use std::fmt;
 
@@ -24,6 +26,7 @@ pub enum Error {
     TxInvalidNoOutput,
     TxTooManyInputs,
     TxTooManyOutputs,
+    TxTooManyTokens,
     TxNotEnoughTotalInput,
     TxTooMuchTotalInput,
     MathErr(ValueError),
@@ -36,6 +39,7 @@ impl fmt::Display for Error {
             Error::TxInvalidNoOutput => write!(f, "transaction has no outputs"),
             Error::TxTooManyInputs => write!(f, "transaction has too many inputs"),
             Error::TxTooManyOutputs => write!(f, "transaction has too many outputs"),
+            Error::TxTooManyTokens => write!(f, "transaction output has too many tokens"),
             Error::TxNotEnoughTotalInput => write!(f, "not enough input for making transaction"),
             Error::TxTooMuchTotalInput => write!(f, "too muny input value for making transaction"),
             Error::MathErr(v) => write!(f, "error in arithmetics {:?}", v),
@@ -110,10 +114,30 @@ impl InputOutputBuilder {
     ///
     /// Each output may extend the size of the required fee.
     pub fn add_output(&mut self, address: Address, value: Value) -> Result<(), Error> {
+        self.add_output_with_tokens(address, value, TokenBundle::new())
+    }
+
+    /// Add additional output carrying some tokens along with the value.
+    ///
+    /// Each output and each token not yet carried by the other outputs may
+    /// extend the size of the required fee.
+    pub fn add_output_with_tokens(
+        &mut self,
+        address: Address,
+        value: Value,
+        tokens: TokenBundle,
+    ) -> Result<(), Error> {
         if self.outputs.len() == 255 {
             return Err(Error::TxTooManyOutputs);
         }
-        self.outputs.push(Output { address, value });
+        if tokens.len() > TOKEN_BUNDLE_MAX_TOKENS {
+            return Err(Error::TxTooManyTokens);
+        }
+        self.outputs.push(Output {
+            address,
+            value,
+            tokens,
+        });
         Ok(())
     }
 
@@ -143,16 +167,27 @@ impl InputOutputBuilder {
         }
     }
 
+    /// Number of distinct tokens carried by the outputs
+    fn nb_distinct_tokens(&self) -> usize {
+        let tokens: BTreeSet<_> = self
+            .outputs
+            .iter()
+            .flat_map(|output| output.tokens.tokens())
+            .collect();
+        tokens.len()
+    }
+
     /// Calculate the fees on a given fee algorithm for the current transaction
     pub fn estimate_fee<'a, P: Payload, F: FeeAlgorithm>(
         &self,
         payload: PayloadSlice<'a, P>,
         fee_algorithm: &F,
     ) -> Value {
-        fee_algorithm.calculate(
+        fee_algorithm.calculate_with_tokens(
             payload.into_certificate_slice(),
             self.inputs.len() as u8,
             self.outputs.len() as u8,
+            self.nb_distinct_tokens(),
         )
     }
 
@@ -184,7 +219,12 @@ impl InputOutputBuilder {
         let nb_inputs = self.inputs.len() as u8 + inputs_placeholders;
         let nb_outputs = self.outputs.len() as u8 + outputs_placeholders;
 
-        let fee = fee_algorithm.calculate(payload.into_certificate_slice(), nb_inputs, nb_outputs);
+        let fee = fee_algorithm.calculate_with_tokens(
+            payload.into_certificate_slice(),
+            nb_inputs,
+            nb_outputs,
+            self.nb_distinct_tokens(),
+        );
         self.balance(fee).map_err(Error::MathErr)
     }
 
@@ -250,7 +290,7 @@ impl InputOutputBuilder {
                 match self.balance(fee) {
                     Ok(Balance::Positive(value)) => {
                         let _ = self.outputs.pop();
-                        let output = Output { address, value };
+                        let output = Output::from_address(address, value);
                         self.outputs.push(output.clone());
                         Ok((Balance::Zero, vec![output], self.build()))
                     }
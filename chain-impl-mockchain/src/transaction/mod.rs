@@ -7,6 +7,7 @@ mod payload;
 mod transaction;
 mod transfer;
 mod utxo;
+mod validity;
 mod witness;
 
 #[cfg(any(test, feature = "property-test-api"))]
@@ -24,6 +25,7 @@ pub use payload::{NoExtra, Payload, PayloadAuthData, PayloadAuthSlice, PayloadDa
 pub use transaction::*;
 pub use transfer::*;
 pub use utxo::*;
+pub use validity::*;
 pub use witness::*;
 
 impl<Extra: Payload> property::Serialize for Transaction<Extra> {
@@ -33,15 +35,25 @@ impl<Extra: Payload> property::Serialize for Transaction<Extra> {
     }
 }
 
-impl<Extra: Payload> Readable for Transaction<Extra> {
-    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+impl<Extra: Payload> Transaction<Extra> {
+    /// Read a transaction serialized with the given layout
+    pub fn read_with_layout(
+        buf: &mut ReadBuf,
+        layout: TransactionLayout,
+    ) -> Result<Self, ReadError> {
         let utx = UnverifiedTransactionSlice::from(buf.get_slice_end());
-        match utx.check() {
+        match utx.check_with_layout(layout) {
             Ok(tx) => Ok(tx.to_owned()),
             Err(_) => Err(ReadError::StructureInvalid("transaction".to_string())),
         }
     }
 }
 
+impl<Extra: Payload> Readable for Transaction<Extra> {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        Self::read_with_layout(buf, TransactionLayout::Legacy)
+    }
+}
+
 // TEMPORARY
 pub type AuthenticatedTransaction<P> = Transaction<P>;
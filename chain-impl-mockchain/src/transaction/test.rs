@@ -1,22 +1,46 @@
+use super::transfer::Output;
 use super::{
-    element::SingleAccountBindingSignature, AccountBindingSignature, AccountIdentifier, Input,
-    NoExtra, Payload, Transaction, TxBuilder, UnspecifiedAccountIdentifier, UtxoPointer, Witness,
+    element::SingleAccountBindingSignature, AccountBindingSignature, AccountIdentifier,
+    BindingSignatureContext, Input, NoExtra, Payload, Transaction, TransactionBindingAuthData,
+    TransactionBindingContext, TransactionLayout, TxBuilder, UnspecifiedAccountIdentifier,
+    UtxoPointer, ValidityInterval, Witness, VALIDITY_INTERVAL_SIZE,
 };
 #[cfg(test)]
 use crate::certificate::OwnerStakeDelegation;
 use crate::key::{EitherEd25519SecretKey, SpendingSignature};
-use chain_crypto::{testing::arbitrary_secret_key, Ed25519, SecretKey, Signature};
+use crate::tokens::TokenBundle;
+use crate::value::Value;
+use chain_addr::Address;
+use chain_core::mempack::{ReadBuf, Readable};
+use chain_crypto::{
+    testing::arbitrary_secret_key, AnyPublicKey, AnySignature, Ed25519, Ed25519Bip32, KeyPair,
+    SecretKey, Signature, SigningContext, Verification,
+};
 #[cfg(test)]
 use quickcheck::TestResult;
 use quickcheck::{Arbitrary, Gen};
 use quickcheck_macros::quickcheck;
 
+/// The layout of a transaction is given by the tag of its fragment, so the
+/// transaction is read back with its own layout
+#[cfg(test)]
+fn layout_bijection<P: Payload>(transaction: Transaction<P>) -> TestResult {
+    let mut buf = ReadBuf::from(transaction.as_ref());
+    let decoded = match Transaction::<P>::read_with_layout(&mut buf, transaction.layout()) {
+        Err(error) => {
+            return TestResult::error(format!("deserialization: {:?}\n{}", error, buf.debug()))
+        }
+        Ok(decoded) => decoded,
+    };
+    TestResult::from_bool(buf.expect_end().is_ok() && decoded == transaction)
+}
+
 quickcheck! {
     fn transaction_encode_decode(transaction: Transaction<NoExtra>) -> TestResult {
-        chain_test_utils::property::serialization_bijection_r(transaction)
+        layout_bijection(transaction)
     }
     fn stake_owner_delegation_tx_encode_decode(transaction: Transaction<OwnerStakeDelegation>) -> TestResult {
-        chain_test_utils::property::serialization_bijection_r(transaction)
+        layout_bijection(transaction)
     }
     /*
     fn certificate_tx_encode_decode(transaction: Transaction<Address, Certificate>) -> TestResult {
@@ -24,10 +48,51 @@ quickcheck! {
     }
     */
     fn signed_transaction_encode_decode(transaction: Transaction<NoExtra>) -> TestResult {
-        chain_test_utils::property::serialization_bijection_r(transaction)
+        layout_bijection(transaction)
     }
 }
 
+#[quickcheck]
+fn transaction_layout_follows_the_validity(validity: ValidityInterval) -> TestResult {
+    let tx = TxBuilder::new()
+        .set_nopayload()
+        .set_validity(validity)
+        .set_ios(&[], &[])
+        .set_witnesses(&[])
+        .set_payload_auth(&());
+    let legacy = TxBuilder::new()
+        .set_nopayload()
+        .set_ios(&[], &[])
+        .set_witnesses(&[])
+        .set_payload_auth(&());
+    if validity == ValidityInterval::unbounded() {
+        return TestResult::from_bool(tx == legacy && tx.layout() == TransactionLayout::Legacy);
+    }
+
+    // the unbounded interval is only written with the legacy layout
+    let unbounded = TxBuilder::new()
+        .set_nopayload()
+        .set_validity(validity)
+        .set_validity(ValidityInterval::unbounded())
+        .set_ios(&[], &[])
+        .set_witnesses(&[])
+        .set_payload_auth(&());
+    let mut v1_unbounded = vec![];
+    v1_unbounded.extend_from_slice(&ValidityInterval::unbounded().bytes());
+    v1_unbounded.extend_from_slice(legacy.as_ref());
+    TestResult::from_bool(
+        tx.layout() == TransactionLayout::V1
+            && tx.validity() == validity
+            && tx.as_ref().len() == legacy.as_ref().len() + VALIDITY_INTERVAL_SIZE
+            && unbounded == legacy
+            && Transaction::<NoExtra>::read_with_layout(
+                &mut ReadBuf::from(&v1_unbounded),
+                TransactionLayout::V1,
+            )
+            .is_err(),
+    )
+}
+
 #[cfg(test)]
 fn check_eq<X>(s1: &str, x1: X, s2: &str, x2: X, s: &str) -> Result<(), String>
 where
@@ -113,6 +178,91 @@ pub fn check_transaction_accessor_consistent(tx: Transaction<NoExtra>) -> TestRe
     }
 }
 
+struct OtherContext;
+
+impl SigningContext for OtherContext {
+    const CONTEXT: &'static [u8] = b"other";
+}
+
+#[quickcheck]
+fn binding_signature_domain(key: TransactionSigningKey, data: Vec<u8>) -> bool {
+    let key = key.0;
+    let pk = key.to_public();
+    let auth_data = TransactionBindingAuthData(&data);
+
+    let in_context =
+        SingleAccountBindingSignature::new(&auth_data, |d| key.sign_slice_with_context(d.0));
+    let legacy = SingleAccountBindingSignature(key.sign_slice(&data));
+    let other_context = SingleAccountBindingSignature(
+        Signature::from_binary(
+            key.sign_slice_with_context::<OtherContext, [u8]>(&data)
+                .as_ref(),
+        )
+        .unwrap(),
+    );
+
+    let verifies = |signature: &SingleAccountBindingSignature, context| {
+        signature.verify_slice(&pk, &auth_data, context) == Verification::Success
+    };
+    verifies(&in_context, BindingSignatureContext::Optional)
+        && verifies(&in_context, BindingSignatureContext::Required)
+        && verifies(&legacy, BindingSignatureContext::Optional)
+        && !verifies(&legacy, BindingSignatureContext::Required)
+        && !verifies(&other_context, BindingSignatureContext::Optional)
+        && !verifies(&other_context, BindingSignatureContext::Required)
+}
+
+#[quickcheck]
+fn any_binding_signature_algorithm(
+    key: TransactionSigningKey,
+    bip32_key: KeyPair<Ed25519Bip32>,
+    data: Vec<u8>,
+) -> bool {
+    let key = key.0;
+    let pk = AnyPublicKey::Ed25519(key.to_public());
+    let bip32_pk = AnyPublicKey::Ed25519Bip32(bip32_key.public_key().clone());
+    let auth_data = TransactionBindingAuthData(&data);
+
+    let in_context = AccountBindingSignature::Any(AnySignature::Ed25519(
+        Signature::from_binary(
+            key.sign_slice_with_context::<TransactionBindingContext, [u8]>(&data)
+                .as_ref(),
+        )
+        .unwrap(),
+    ));
+    let bip32 = AccountBindingSignature::Any(AnySignature::Ed25519Bip32(
+        bip32_key.private_key().sign_slice(&data),
+    ));
+
+    let context = BindingSignatureContext::Required;
+    in_context.verify_slice(&pk, &auth_data, context) == Verification::Success
+        && in_context.verify_slice(&bip32_pk, &auth_data, context) == Verification::Failed
+        && bip32.verify_slice(&bip32_pk, &auth_data, context) == Verification::Success
+        && bip32.verify_slice(&pk, &auth_data, context) == Verification::Failed
+}
+
+#[quickcheck]
+fn output_encoding(address: Address, value: Value, tokens: TokenBundle) -> TestResult {
+    let mut legacy = address.to_bytes();
+    legacy.extend_from_slice(&value.bytes());
+    let mut rb = ReadBuf::from(&legacy);
+    let output = Output::<Address>::read(&mut rb).unwrap();
+    if output != Output::from_address(address.clone(), value) || !rb.is_end() {
+        return TestResult::error("output without tokens does not keep the legacy encoding");
+    }
+
+    if tokens.is_empty() {
+        return TestResult::discard();
+    }
+    let output = Output::with_tokens(address, value, tokens);
+    let tx = TxBuilder::new()
+        .set_nopayload()
+        .set_ios(&[], &[output.clone()])
+        .set_witnesses(&[])
+        .set_payload_auth(&());
+    TestResult::from_bool(tx.as_slice().outputs().iter().collect::<Vec<_>>() == vec![output])
+}
+
 impl Arbitrary for UtxoPointer {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         UtxoPointer {
@@ -158,12 +308,24 @@ where
 
         TxBuilder::new()
             .set_payload(&payload)
+            .set_validity(if bool::arbitrary(g) {
+                Arbitrary::arbitrary(g)
+            } else {
+                ValidityInterval::unbounded()
+            })
             .set_ios(&inputs, &outputs)
             .set_witnesses(&witnesses)
             .set_payload_auth(&payload_auth)
     }
 }
 
+impl Arbitrary for ValidityInterval {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let (a, b) = (Arbitrary::arbitrary(g), Arbitrary::arbitrary(g));
+        ValidityInterval::new(std::cmp::min(a, b), std::cmp::max(a, b))
+    }
+}
+
 impl Arbitrary for SingleAccountBindingSignature {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         SingleAccountBindingSignature(Arbitrary::arbitrary(g))
@@ -172,7 +334,11 @@ impl Arbitrary for SingleAccountBindingSignature {
 
 impl Arbitrary for AccountBindingSignature {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
-        AccountBindingSignature::Single(Arbitrary::arbitrary(g))
+        match u8::arbitrary(g) % 3 {
+            0 => AccountBindingSignature::Single(Arbitrary::arbitrary(g)),
+            1 => AccountBindingSignature::Any(AnySignature::Ed25519(Arbitrary::arbitrary(g))),
+            _ => AccountBindingSignature::Any(AnySignature::Ed25519Bip32(Arbitrary::arbitrary(g))),
+        }
     }
 }
 
@@ -1,13 +1,19 @@
 use super::builder::{SetAuthData, TxBuilder, TxBuilderState};
-use super::element::{Balance, BalanceError, TransactionSignDataHash};
+use super::element::{
+    Balance, BalanceError, TransactionSignDataHash, TRANSACTION_SIGN_DATA_DOMAIN,
+};
 use super::input::{Input, INPUT_SIZE};
 use super::payload::{Payload, PayloadAuthSlice, PayloadSlice};
 use super::transfer::Output;
+use super::validity::{ValidityInterval, VALIDITY_INTERVAL_SIZE};
 use super::witness::Witness;
+use crate::key::HashVersion;
+use crate::tokens::TokenBundle;
 use crate::value::{Value, ValueError};
 use chain_addr::Address;
 use chain_core::mempack::{ReadBuf, Readable};
 use chain_crypto::digest::Digest;
+use std::collections::BTreeSet;
 use std::fmt::{self, Debug};
 use std::marker::PhantomData;
 
@@ -32,6 +38,7 @@ impl<P> Debug for Transaction<P> {
         let tx = self.as_slice();
         f.debug_struct("Transaction")
             .field("payload", &tx.payload().0)
+            .field("validity", &tx.validity())
             .field("nb_inputs", &tx.nb_inputs())
             .field("nb_outputs", &tx.nb_outputs())
             .field("nb_witnesses", &tx.nb_witnesses())
@@ -59,12 +66,29 @@ pub struct UnverifiedTransactionSlice<'a, P: ?Sized> {
     phantom: PhantomData<P>,
 }
 
+/// The layout of a serialized transaction, given by the tag of its fragment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionLayout {
+    /// the inputs and outputs follow the payload, the transaction is valid in
+    /// all the blocks
+    Legacy,
+    /// a bounded validity interval sits between the payload and the inputs
+    /// and outputs
+    V1,
+}
+
 #[derive(Clone)]
 pub struct TransactionAuthData<'a>(pub &'a [u8]);
 
 impl<'a> TransactionAuthData<'a> {
     pub fn hash(&self) -> TransactionSignDataHash {
-        Digest::digest(self.0).into()
+        self.hash_with_version(HashVersion::CURRENT)
+    }
+
+    /// The hash to sign for a ledger using the given version, see
+    /// [`Settings::hash_version`](crate::setting::Settings::hash_version)
+    pub fn hash_with_version(&self, version: HashVersion) -> TransactionSignDataHash {
+        Digest::digest(&version.hashed_bytes(TRANSACTION_SIGN_DATA_DOMAIN, self.0)).into()
     }
 }
 
@@ -192,6 +216,23 @@ impl<'a> OutputsSlice<'a> {
             slice: self,
         }
     }
+
+    /// Total of the tokens carried by the outputs, the native value excluded
+    pub fn total_tokens(self) -> Result<TokenBundle, ValueError> {
+        self.iter().try_fold(TokenBundle::new(), |total, output| {
+            total.checked_add(&output.tokens)
+        })
+    }
+
+    /// Number of distinct tokens carried by the outputs, the native value
+    /// excluded
+    pub fn nb_distinct_tokens(self) -> usize {
+        let tokens: BTreeSet<_> = self
+            .iter()
+            .flat_map(|output| output.tokens.tokens().copied().collect::<Vec<_>>())
+            .collect();
+        tokens.len()
+    }
 }
 
 impl<'a> WitnessesSlice<'a> {
@@ -225,6 +266,7 @@ pub enum TransactionStructError {
     CannotReadNbInputs,
     CannotReadNbOutputs,
     PayloadInvalid,
+    ValidityInvalid,
     InputsInvalid,
     OutputsInvalid,
     WitnessesInvalid,
@@ -238,6 +280,7 @@ pub(super) struct TransactionStruct {
     pub(super) sz: usize,
     pub(super) nb_inputs: u8,
     pub(super) nb_outputs: u8,
+    pub(super) validity: Option<usize>,
     pub(super) inputs: usize,
     pub(super) outputs: usize,
     pub(super) witnesses: usize,
@@ -245,7 +288,10 @@ pub(super) struct TransactionStruct {
 }
 
 /// Verify the structure of the transaction and return all the offsets
-fn get_spine<P: Payload>(slice: &[u8]) -> Result<TransactionStruct, TransactionStructError> {
+fn get_spine<P: Payload>(
+    slice: &[u8],
+    layout: TransactionLayout,
+) -> Result<TransactionStruct, TransactionStructError> {
     let sz = slice.len();
     let mut rb = ReadBuf::from(slice);
 
@@ -254,6 +300,21 @@ fn get_spine<P: Payload>(slice: &[u8]) -> Result<TransactionStruct, TransactionS
         P::read_validate(&mut rb).map_err(|_| TransactionStructError::PayloadInvalid)?;
     }
 
+    // read validity, an unbounded interval is only written with the legacy
+    // layout
+    let validity_pos = match layout {
+        TransactionLayout::Legacy => None,
+        TransactionLayout::V1 => {
+            let validity_pos = rb.position();
+            let validity = ValidityInterval::read(&mut rb)
+                .map_err(|_| TransactionStructError::ValidityInvalid)?;
+            if validity == ValidityInterval::unbounded() {
+                return Err(TransactionStructError::ValidityInvalid);
+            }
+            Some(validity_pos)
+        }
+    };
+
     // read input and outputs
     let nb_inputs = rb
         .get_u8()
@@ -293,6 +354,7 @@ fn get_spine<P: Payload>(slice: &[u8]) -> Result<TransactionStruct, TransactionS
         sz,
         nb_inputs,
         nb_outputs,
+        validity: validity_pos,
         inputs: inputs_pos,
         outputs: outputs_pos,
         witnesses: witnesses_pos,
@@ -311,7 +373,14 @@ impl<'a, P: Payload> From<&'a [u8]> for UnverifiedTransactionSlice<'a, P> {
 
 impl<'a, P: Payload> UnverifiedTransactionSlice<'a, P> {
     pub fn check(self) -> Result<TransactionSlice<'a, P>, TransactionStructError> {
-        let tstruct = get_spine::<P>(&self.data)?;
+        self.check_with_layout(TransactionLayout::Legacy)
+    }
+
+    pub fn check_with_layout(
+        self,
+        layout: TransactionLayout,
+    ) -> Result<TransactionSlice<'a, P>, TransactionStructError> {
+        let tstruct = get_spine::<P>(&self.data, layout)?;
         Ok(TransactionSlice {
             data: self.data,
             tstruct,
@@ -337,6 +406,14 @@ impl<P> Transaction<P> {
         self.tstruct.nb_inputs
     }
 
+    pub fn validity(&self) -> ValidityInterval {
+        self.as_slice().validity()
+    }
+
+    pub fn layout(&self) -> TransactionLayout {
+        self.as_slice().layout()
+    }
+
     /// Create a specific block0 payload transaction
     pub fn block0_payload(payload: &P, payload_auth: &P::Auth) -> Transaction<P>
     where
@@ -434,7 +511,16 @@ impl<'a, P> TransactionSlice<'a, P> {
     }
 
     pub fn transaction_sign_data_hash(&self) -> TransactionSignDataHash {
-        Digest::digest(self.transaction_auth_data().0).into()
+        self.transaction_sign_data_hash_with_version(HashVersion::CURRENT)
+    }
+
+    /// The hash signed by the witnesses, computed with the given version of
+    /// the scheme
+    pub fn transaction_sign_data_hash_with_version(
+        &self,
+        version: HashVersion,
+    ) -> TransactionSignDataHash {
+        self.transaction_auth_data().hash_with_version(version)
     }
 
     pub fn transaction_binding_auth_data(&self) -> TransactionBindingAuthData<'a> {
@@ -442,7 +528,27 @@ impl<'a, P> TransactionSlice<'a, P> {
     }
 
     pub fn payload(&self) -> PayloadSlice<'a, P> {
-        PayloadSlice(&self.data[0..self.tstruct.inputs], PhantomData)
+        let end = self.tstruct.validity.unwrap_or(self.tstruct.inputs);
+        PayloadSlice(&self.data[0..end], PhantomData)
+    }
+
+    /// The dates of the blocks the transaction can be included in
+    pub fn validity(&self) -> ValidityInterval {
+        match self.tstruct.validity {
+            None => ValidityInterval::unbounded(),
+            Some(start) => {
+                let validity = &self.data[start..start + VALIDITY_INTERVAL_SIZE];
+                ValidityInterval::read(&mut ReadBuf::from(validity)).unwrap()
+            }
+        }
+    }
+
+    /// The layout of the transaction, the one of the tag of its fragment
+    pub fn layout(&self) -> TransactionLayout {
+        match self.tstruct.validity {
+            None => TransactionLayout::Legacy,
+            Some(_) => TransactionLayout::V1,
+        }
     }
 
     pub fn nb_inputs(&self) -> u8 {
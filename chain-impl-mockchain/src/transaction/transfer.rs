@@ -1,32 +1,75 @@
 use crate::legacy::OldAddress;
+use crate::tokens::TokenBundle;
 use crate::value::*;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 
+/// Tag starting the encoding of an output carrying tokens. It is not a
+/// valid address header (the address kinds start at 3), the outputs without
+/// tokens keep the legacy `ADDRESS VALUE` encoding.
+pub const OUTPUT_WITH_TOKENS_TAG: u8 = 0x00;
+
 /// Information how tokens are spent.
-/// A value of tokens is sent to the address.
+/// A value of tokens is sent to the address, optionally along with a bundle
+/// of other tokens.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Output<Address> {
     pub address: Address,
     pub value: Value,
+    pub tokens: TokenBundle,
 }
 
 impl<Address: Readable> Output<Address> {
     pub fn from_address(address: Address, value: Value) -> Self {
-        Output { address, value }
+        Output {
+            address,
+            value,
+            tokens: TokenBundle::new(),
+        }
+    }
+
+    pub fn with_tokens(address: Address, value: Value, tokens: TokenBundle) -> Self {
+        Output {
+            address,
+            value,
+            tokens,
+        }
     }
 }
 
 impl<Address: Readable> Readable for Output<Address> {
     fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let with_tokens = buf.peek_u8()? == OUTPUT_WITH_TOKENS_TAG;
+        if with_tokens {
+            buf.skip_bytes(1)?;
+        }
         let address = Address::read(buf)?;
         let value = Value::read(buf)?;
-        Ok(Output { address, value })
+        let tokens = if with_tokens {
+            let tokens = TokenBundle::read(buf)?;
+            if tokens.is_empty() {
+                return Err(ReadError::StructureInvalid(
+                    "output tagged with tokens has an empty token bundle".to_string(),
+                ));
+            }
+            tokens
+        } else {
+            TokenBundle::new()
+        };
+        Ok(Output {
+            address,
+            value,
+            tokens,
+        })
     }
 }
 
 impl std::fmt::Display for Output<chain_addr::Address> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}.{}", self.address.base32(), self.value)
+        write!(f, "{}.{}", self.address.base32(), self.value)?;
+        if !self.tokens.is_empty() {
+            write!(f, "[{}]", self.tokens)?;
+        }
+        Ok(())
     }
 }
 
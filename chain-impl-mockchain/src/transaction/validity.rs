@@ -0,0 +1,86 @@
+use crate::date::BlockDate;
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+
+pub const VALIDITY_INTERVAL_SIZE: usize = 16;
+
+/// The dates of the blocks a transaction can be included in, both ends
+/// included.
+///
+/// A transaction is not valid before `valid_from`, which allows to schedule
+/// it, and expires after `valid_until`. A transaction is by default valid
+/// in all the blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValidityInterval {
+    pub valid_from: BlockDate,
+    pub valid_until: BlockDate,
+}
+
+impl Default for ValidityInterval {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl ValidityInterval {
+    pub fn new(valid_from: BlockDate, valid_until: BlockDate) -> Self {
+        ValidityInterval {
+            valid_from,
+            valid_until,
+        }
+    }
+
+    /// Valid in all the blocks
+    pub fn unbounded() -> Self {
+        Self::new(
+            BlockDate::first(),
+            BlockDate {
+                epoch: u32::MAX,
+                slot_id: u32::MAX,
+            },
+        )
+    }
+
+    /// Valid from the given date onward
+    pub fn starting_at(valid_from: BlockDate) -> Self {
+        Self {
+            valid_from,
+            ..Self::unbounded()
+        }
+    }
+
+    /// Valid until the given date, included
+    pub fn expiring_at(valid_until: BlockDate) -> Self {
+        Self {
+            valid_until,
+            ..Self::unbounded()
+        }
+    }
+
+    /// Whether a transaction can be included in a block of the given date
+    pub fn contains(&self, date: BlockDate) -> bool {
+        self.valid_from <= date && date <= self.valid_until
+    }
+
+    pub fn bytes(&self) -> [u8; VALIDITY_INTERVAL_SIZE] {
+        let mut bytes = [0u8; VALIDITY_INTERVAL_SIZE];
+        bytes[0..4].copy_from_slice(&self.valid_from.epoch.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.valid_from.slot_id.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.valid_until.epoch.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.valid_until.slot_id.to_be_bytes());
+        bytes
+    }
+}
+
+impl Readable for ValidityInterval {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let valid_from = BlockDate {
+            epoch: buf.get_u32()?,
+            slot_id: buf.get_u32()?,
+        };
+        let valid_until = BlockDate {
+            epoch: buf.get_u32()?,
+            slot_id: buf.get_u32()?,
+        };
+        Ok(Self::new(valid_from, valid_until))
+    }
+}
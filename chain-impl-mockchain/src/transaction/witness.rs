@@ -8,7 +8,7 @@ use crate::key::{
 use crate::multisig;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
-use chain_crypto::{Ed25519, PublicKey, Signature};
+use chain_crypto::{Ed25519, PublicKey, Signature, ValidationPolicy};
 
 /// Structure that proofs that certain user agrees with
 /// some data. This structure is used to sign `Transaction`
@@ -180,6 +180,18 @@ impl Witness {
         self.serialize_as_vec()
             .expect("memory serialize is expected to just work")
     }
+
+    /// Check the encoding of the keys and signatures against the policy
+    pub fn validate(&self, policy: ValidationPolicy) -> bool {
+        match self {
+            Witness::Utxo(sig) => sig.validate(policy).is_ok(),
+            Witness::Account(sig) => sig.validate(policy).is_ok(),
+            Witness::OldUtxo(pk, _, sig) => {
+                pk.validate(policy).is_ok() && sig.validate(policy).is_ok()
+            }
+            Witness::Multisig(msig) => msig.validate(policy),
+        }
+    }
 }
 
 const WITNESS_TAG_OLDUTXO: u8 = 0u8;
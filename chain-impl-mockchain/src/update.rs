@@ -1,7 +1,8 @@
 //use crate::certificate::{verify_certificate, HasPublicKeys, SignatureRaw};
-use crate::date::BlockDate;
+use crate::date::{BlockDate, Epoch};
 use crate::fragment::config::ConfigParams;
 use crate::key::BftLeaderId;
+use crate::rewards::Ratio;
 use crate::setting::{ActiveSlotsCoeffError, Settings};
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
@@ -44,6 +45,13 @@ impl UpdateState {
 
         let proposal = &proposal.proposal.proposal;
 
+        // reject early the proposals that would leave the chain without
+        // slot leaders, the settings are checked again once the proposal
+        // is accepted.
+        if let Ok(new_settings) = settings.apply(&proposal.changes) {
+            new_settings.check_consensus()?;
+        }
+
         if self
             .proposals
             .insert(
@@ -94,13 +102,22 @@ impl UpdateState {
         }
     }
 
+    /// Apply the accepted proposals and delete the expired ones when
+    /// `new_date` enters a new epoch, see `UpdateProposalState::expiry_epoch`.
+    ///
+    /// The settings, including the consensus version, hence only change on
+    /// the first block of an epoch: the leadership of that epoch has to be
+    /// computed from the resulting settings (see `Ledger::at_epoch_start`).
+    /// An accepted proposal that would leave the chain without slot leaders
+    /// is dropped without being applied. The accepted, rejected and expired
+    /// proposals are reported in the order they were processed.
     pub fn process_proposals(
         mut self,
         mut settings: Settings,
         prev_date: BlockDate,
         new_date: BlockDate,
-    ) -> Result<(Self, Settings), Error> {
-        let mut expired_ids = vec![];
+    ) -> Result<(Self, Settings, Vec<UpdateProposalEvent>), Error> {
+        let mut events = vec![];
 
         assert!(prev_date < new_date);
 
@@ -115,21 +132,28 @@ impl UpdateState {
                 // ID. FIXME: delay the effectuation of the proposal
                 // for some number of epochs.
                 if proposal_state.votes.len() > settings.bft_leaders.len() / 2 {
-                    settings = settings.apply(&proposal_state.proposal.changes)?;
-                    expired_ids.push(*proposal_id);
-                } else if proposal_state.proposal_date.epoch + settings.proposal_expiration
-                    < new_date.epoch
-                {
-                    expired_ids.push(*proposal_id);
+                    let new_settings = settings.apply(&proposal_state.proposal.changes)?;
+                    // the proposal was checked when submitted but an earlier
+                    // proposal may have changed the settings since then, do
+                    // not halt the chain on a proposal that would leave it
+                    // without slot leaders.
+                    if new_settings.check_consensus().is_ok() {
+                        settings = new_settings;
+                        events.push(UpdateProposalEvent::Accepted(*proposal_id));
+                    } else {
+                        events.push(UpdateProposalEvent::Rejected(*proposal_id));
+                    }
+                } else if proposal_state.expiry_epoch(&settings) < new_date.epoch {
+                    events.push(UpdateProposalEvent::Expired(*proposal_id));
                 }
             }
 
-            for proposal_id in expired_ids {
-                self.proposals.remove(&proposal_id);
+            for event in &events {
+                self.proposals.remove(event.proposal_id());
             }
         }
 
-        Ok((self, settings))
+        Ok((self, settings, events))
     }
 }
 
@@ -146,6 +170,44 @@ pub struct UpdateProposalState {
     pub votes: HashSet<UpdateVoterId>,
 }
 
+impl UpdateProposalState {
+    /// The last epoch the proposal can be accepted in. The proposal is
+    /// deleted on the first block of the following epoch if it has not
+    /// gathered the votes of a majority of the BFT leaders by then.
+    ///
+    /// The expiry follows the current `ProposalExpiration` setting, a change
+    /// of the setting applies to the pending proposals too.
+    pub fn expiry_epoch(&self, settings: &Settings) -> Epoch {
+        self.proposal_date
+            .epoch
+            .saturating_add(settings.proposal_expiration)
+    }
+}
+
+/// What happened to a pending proposal on an epoch transition, see
+/// `UpdateState::process_proposals`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateProposalEvent {
+    /// a majority of the BFT leaders voted for the proposal, its changes
+    /// were applied to the settings
+    Accepted(UpdateProposalId),
+    /// the proposal was accepted but deleted without being applied, its
+    /// changes would have left the chain without slot leaders
+    Rejected(UpdateProposalId),
+    /// the proposal was deleted after its expiry epoch
+    Expired(UpdateProposalId),
+}
+
+impl UpdateProposalEvent {
+    pub fn proposal_id(&self) -> &UpdateProposalId {
+        match self {
+            UpdateProposalEvent::Accepted(id)
+            | UpdateProposalEvent::Rejected(id)
+            | UpdateProposalEvent::Expired(id) => id,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     /*
@@ -162,6 +224,13 @@ pub enum Error {
     ReadOnlySetting,
     BadBftSlotsRatio(crate::milli::Milli),
     BadConsensusGenesisPraosActiveSlotsCoeff(ActiveSlotsCoeffError),
+    NoBftLeaders,
+    NoStakePools,
+    InvalidEpochDuration {
+        slots_per_epoch: u32,
+        slot_duration: u8,
+    },
+    HalvingRatioAboveOne(Ratio),
 }
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -220,6 +289,24 @@ impl std::fmt::Display for Error {
                 "Cannot set consensus genesis praos active slots coefficient: {}",
                 err
             ),
+            Error::NoBftLeaders => write!(f, "Cannot use the BFT consensus without any BFT leader"),
+            Error::NoStakePools => write!(
+                f,
+                "Cannot switch to the genesis praos consensus without any registered stake pool"
+            ),
+            Error::InvalidEpochDuration {
+                slots_per_epoch,
+                slot_duration,
+            } => write!(
+                f,
+                "Cannot use epochs of {} slots of {} seconds",
+                slots_per_epoch, slot_duration
+            ),
+            Error::HalvingRatioAboveOne(ratio) => write!(
+                f,
+                "Cannot draw the rewards with a halving ratio of {}/{}, above 1",
+                ratio.numerator, ratio.denominator
+            ),
         }
     }
 }
@@ -397,6 +484,7 @@ mod tests {
     use super::*;
     #[cfg(test)]
     use crate::{
+        chaintypes::ConsensusType,
         config::ConfigParam,
         testing::{
             builders::update_builder::{ProposalBuilder, SignedProposalBuilder, UpdateVoteBuilder},
@@ -773,9 +861,13 @@ mod tests {
 
         let last_proposal_id = update_state.proposals.keys().cloned().last().unwrap();
 
-        let (update_state, settings) = update_state
+        let (update_state, settings, events) = update_state
             .process_proposals(settings, block_date, block_date.next_epoch())
             .expect("error while processing proposal");
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, UpdateProposalEvent::Accepted(_))));
 
         if first_proposal_id == last_proposal_id {
             assert_eq!(settings.slots_per_epoch, 100);
@@ -786,6 +878,106 @@ mod tests {
         assert_eq!(update_state.proposals.len(), 0);
     }
 
+    #[test]
+    pub fn proposal_leaving_bft_without_leaders_should_return_error() {
+        let update_state = UpdateState::new();
+        let proposal_id = TestGen::hash();
+        let proposer = TestGen::leader_pair();
+        let block_date = BlockDate::first();
+        let settings = TestGen::settings(vec![proposer.clone()]);
+
+        assert_eq!(
+            apply_update_proposal(
+                update_state,
+                proposal_id,
+                &ConfigParam::RemoveBftLeader(proposer.id()),
+                &proposer,
+                &settings,
+                block_date,
+            ),
+            Err(Error::NoBftLeaders)
+        );
+    }
+
+    #[test]
+    pub fn consensus_switch_is_applied_at_epoch_boundary() {
+        let mut update_state = UpdateState::new();
+        let proposal_id = TestGen::hash();
+        let proposer = TestGen::leader_pair();
+        let block_date = BlockDate::first();
+        let switch = ConfigParam::ConsensusVersion(ConsensusType::GenesisPraos);
+
+        let settings = TestGen::settings(vec![proposer.clone()]);
+        assert_eq!(settings.consensus_version, ConsensusType::Bft);
+
+        update_state = apply_update_proposal(
+            update_state,
+            proposal_id,
+            &switch,
+            &proposer,
+            &settings,
+            block_date,
+        )
+        .expect("failed while applying proposal");
+        update_state = apply_update_vote(update_state, proposal_id, &proposer, &settings)
+            .expect("failed while applying vote");
+
+        // still in the same epoch, nothing changes
+        let later_date = BlockDate {
+            epoch: block_date.epoch,
+            slot_id: block_date.slot_id + 1,
+        };
+        let (update_state, same_epoch_settings, _) = update_state
+            .process_proposals(settings.clone(), block_date, later_date)
+            .expect("error while processing proposal");
+        assert_eq!(same_epoch_settings.consensus_version, ConsensusType::Bft);
+        assert_eq!(update_state.proposals.len(), 1);
+
+        let (update_state, next_epoch_settings, _) = update_state
+            .process_proposals(settings, later_date, block_date.next_epoch())
+            .expect("error while processing proposal");
+        assert_eq!(
+            next_epoch_settings.consensus_version,
+            ConsensusType::GenesisPraos
+        );
+        assert_eq!(update_state.proposals.len(), 0);
+    }
+
+    #[test]
+    pub fn process_proposals_leaving_bft_without_leaders_should_reject_proposal() {
+        let mut update_state = UpdateState::new();
+        let proposal_id = TestGen::hash();
+        let proposer = TestGen::leader_pair();
+        let block_date = BlockDate::first();
+
+        let mut praos_settings = TestGen::settings(vec![proposer.clone()]);
+        praos_settings.consensus_version = ConsensusType::GenesisPraos;
+
+        // the last leader can be removed while running genesis praos...
+        update_state = apply_update_proposal(
+            update_state,
+            proposal_id,
+            &ConfigParam::RemoveBftLeader(proposer.id()),
+            &proposer,
+            &praos_settings,
+            block_date,
+        )
+        .expect("failed while applying proposal");
+        update_state = apply_update_vote(update_state, proposal_id, &proposer, &praos_settings)
+            .expect("failed while applying vote");
+
+        // ...but not if the chain went back to BFT in the meantime, the
+        // proposal is then dropped and the chain keeps its leaders
+        let mut bft_settings = praos_settings;
+        bft_settings.consensus_version = ConsensusType::Bft;
+        let (update_state, settings, events) = update_state
+            .process_proposals(bft_settings.clone(), block_date, block_date.next_epoch())
+            .expect("error while processing proposal");
+        assert_eq!(events, vec![UpdateProposalEvent::Rejected(proposal_id)]);
+        assert_eq!(settings.bft_leaders, bft_settings.bft_leaders);
+        assert_eq!(update_state.proposals.len(), 0);
+    }
+
     #[derive(Debug, Copy, Clone)]
     pub struct ExpiryBlockDate {
         pub block_date: BlockDate,
@@ -851,7 +1043,7 @@ mod tests {
         // if proposal expiration period is not exceeded after that
         // proposal should be removed from proposal collection
         for _i in 0..expiry_block_data.get_last_epoch() {
-            let (update_state, _settings) = update_state
+            let (update_state, _settings, events) = update_state
                 .clone()
                 .process_proposals(
                     settings.clone(),
@@ -860,10 +1052,17 @@ mod tests {
                 )
                 .expect("error while processing proposal");
 
-            if proposal_date.epoch + proposal_expiration <= current_block_date.epoch {
+            let expiry_epoch = proposal_date.epoch + proposal_expiration;
+            if expiry_epoch <= current_block_date.epoch {
                 assert_eq!(update_state.proposals.len(), 0);
+                assert_eq!(events, vec![UpdateProposalEvent::Expired(proposal_id)]);
             } else {
                 assert_eq!(update_state.proposals.len(), 1);
+                assert_eq!(
+                    update_state.proposals[&proposal_id].expiry_epoch(&settings),
+                    expiry_epoch
+                );
+                assert!(events.is_empty());
             }
             current_block_date = current_block_date.next_epoch()
         }
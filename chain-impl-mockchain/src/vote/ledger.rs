@@ -1,12 +1,14 @@
-use crate::certificate::EncryptedVoteTally;
+use crate::certificate::{EncryptedVoteTally, VotingPowerSnapshot};
 use crate::{
     certificate::{TallyProof, VoteAction, VoteCast, VotePlan, VotePlanId, VoteTally},
     date::BlockDate,
+    key::HashVersion,
     ledger::governance::Governance,
     stake::StakeControl,
     transaction::UnspecifiedAccountIdentifier,
     vote::{CommitteeId, PayloadType, VoteError, VotePlanManager},
 };
+use chain_vote::MemberPublicKey;
 use imhamt::{Hamt, InsertError, UpdateError};
 use std::collections::{hash_map::DefaultHasher, HashSet};
 use thiserror::Error;
@@ -14,6 +16,9 @@ use thiserror::Error;
 #[derive(Clone, PartialEq, Eq)]
 pub struct VotePlanLedger {
     pub(crate) plans: Hamt<DefaultHasher, VotePlanId, VotePlanManager>,
+    /// vote plans indexed by the committee member who submitted them, in
+    /// order of submission
+    pub(crate) by_committee: Hamt<DefaultHasher, CommitteeId, Vec<VotePlanId>>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -47,11 +52,38 @@ pub enum VotePlanLedgerError {
 
     #[error("Private vote plan must contain at least one committee member key")]
     VotePlanMissingCommitteeMemberKey,
+
+    #[error("Committee member {committee_id} already has {max} active vote plans")]
+    TooManyActiveVotePlans { committee_id: CommitteeId, max: u32 },
 }
 
 impl VotePlanLedger {
     pub fn new() -> Self {
-        Self { plans: Hamt::new() }
+        Self {
+            plans: Hamt::new(),
+            by_committee: Hamt::new(),
+        }
+    }
+
+    /// the vote plans submitted by the given committee member, in order of
+    /// submission
+    pub fn vote_plans_by_committee<'a>(
+        &'a self,
+        committee_id: &CommitteeId,
+    ) -> impl Iterator<Item = &'a VotePlanManager> {
+        self.by_committee
+            .lookup(committee_id)
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.plans.lookup(id))
+    }
+
+    /// number of vote plans submitted by the given committee member which
+    /// have not elapsed at the given date
+    pub fn active_vote_plans_count(&self, committee_id: &CommitteeId, date: BlockDate) -> usize {
+        self.vote_plans_by_committee(committee_id)
+            .filter(|plan| !plan.vote_plan_elapsed(date))
+            .count()
     }
 
     /// attempt to apply the vote to the appropriate Vote Proposal
@@ -78,7 +110,10 @@ impl VotePlanLedger {
 
         match r {
             Err(reason) => Err(VotePlanLedgerError::VoteError { reason, id }),
-            Ok(plans) => Ok(Self { plans }),
+            Ok(plans) => Ok(Self {
+                plans,
+                by_committee: self.by_committee.clone(),
+            }),
         }
     }
 
@@ -91,13 +126,20 @@ impl VotePlanLedger {
     ///
     /// * the vote_plan is set to finished votes in the past
     /// * the vote_plan has already started
+    /// * the submitter already has `max_active_plans` vote plans which have
+    ///   not elapsed
+    ///
+    /// The vote plan is registered under its id computed with `hash_version`.
     ///
     #[must_use = "This function does not modify the object, the result contains the resulted new version of the vote plan ledger"]
     pub fn add_vote_plan(
         &self,
         current_date: BlockDate,
         vote_plan: VotePlan,
+        hash_version: HashVersion,
         committee: HashSet<CommitteeId>,
+        submitter: CommitteeId,
+        max_active_plans: Option<u32>,
     ) -> Result<Self, VotePlanLedgerError> {
         if current_date > vote_plan.vote_end() {
             return Err(VotePlanLedgerError::VotePlanVoteEndPassed {
@@ -119,13 +161,35 @@ impl VotePlanLedger {
             }
         }
 
-        let id = vote_plan.to_id();
-        let manager = VotePlanManager::new(vote_plan, committee);
-
-        match self.plans.insert(id.clone(), manager) {
-            Err(reason) => Err(VotePlanLedgerError::VotePlanInsertionError { id, reason }),
-            Ok(plans) => Ok(Self { plans }),
+        if let Some(max) = max_active_plans {
+            if self.active_vote_plans_count(&submitter, current_date) >= max as usize {
+                return Err(VotePlanLedgerError::TooManyActiveVotePlans {
+                    committee_id: submitter,
+                    max,
+                });
+            }
         }
+
+        let id = vote_plan.to_id_with_version(hash_version);
+        let manager = VotePlanManager::with_id(id.clone(), vote_plan, committee);
+
+        let plans = self.plans.insert(id.clone(), manager).map_err(|reason| {
+            VotePlanLedgerError::VotePlanInsertionError {
+                id: id.clone(),
+                reason,
+            }
+        })?;
+        let by_committee =
+            self.by_committee
+                .insert_or_update_simple(submitter, vec![id.clone()], |ids| {
+                    let mut ids = ids.clone();
+                    ids.push(id);
+                    Some(ids)
+                });
+        Ok(Self {
+            plans,
+            by_committee,
+        })
     }
 
     /// apply the committee result for the associated vote plan
@@ -167,7 +231,42 @@ impl VotePlanLedger {
 
         match r {
             Err(reason) => Err(VotePlanLedgerError::VoteError { reason, id }),
-            Ok(plans) => Ok(Self { plans }),
+            Ok(plans) => Ok(Self {
+                plans,
+                by_committee: self.by_committee.clone(),
+            }),
+        }
+    }
+
+    /// import the voting power snapshot of the associated vote plan
+    ///
+    /// # Errors
+    ///
+    /// This function may fail:
+    ///
+    /// * if the vote plan does not exist
+    /// * if the vote has started already
+    /// * if a snapshot was imported already
+    ///
+    pub fn apply_voting_power_snapshot(
+        &self,
+        block_date: BlockDate,
+        snapshot: &VotingPowerSnapshot,
+        committee_id: CommitteeId,
+    ) -> Result<Self, VotePlanLedgerError> {
+        let id = snapshot.vote_plan().clone();
+
+        let r = self.plans.update(&id, move |v| {
+            v.import_voting_power_snapshot(block_date, snapshot, committee_id)
+                .map(Some)
+        });
+
+        match r {
+            Err(reason) => Err(VotePlanLedgerError::VoteError { reason, id }),
+            Ok(plans) => Ok(Self {
+                plans,
+                by_committee: self.by_committee.clone(),
+            }),
         }
     }
 
@@ -196,7 +295,38 @@ impl VotePlanLedger {
 
         match r {
             Err(reason) => Err(VotePlanLedgerError::VoteError { reason, id }),
-            Ok(plans) => Ok(Self { plans }),
+            Ok(plans) => Ok(Self {
+                plans,
+                by_committee: self.by_committee.clone(),
+            }),
+        }
+    }
+
+    /// add the given members to the committee of the vote plan, along with
+    /// their election key if the vote is private
+    ///
+    /// # Errors
+    ///
+    /// * if the vote plan does not exist
+    ///
+    pub fn add_committee_members(
+        &self,
+        id: &VotePlanId,
+        members: Vec<(CommitteeId, Option<MemberPublicKey>)>,
+    ) -> Result<Self, VotePlanLedgerError> {
+        let r = self.plans.update(id, move |v| {
+            Ok::<_, VoteError>(Some(v.with_committee_members(members)))
+        });
+
+        match r {
+            Err(reason) => Err(VotePlanLedgerError::VoteError {
+                reason,
+                id: id.clone(),
+            }),
+            Ok(plans) => Ok(Self {
+                plans,
+                by_committee: self.by_committee.clone(),
+            }),
         }
     }
 }
@@ -206,3 +336,136 @@ impl Default for VotePlanLedger {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::VoteTestGen;
+    use chain_core::property::BlockDate as _;
+
+    fn vote_plan(vote_start: u32, committee_end: u32) -> VotePlan {
+        VotePlan::new(
+            BlockDate::from_epoch_slot_id(vote_start, 0),
+            BlockDate::from_epoch_slot_id(vote_start + 1, 0),
+            BlockDate::from_epoch_slot_id(committee_end, 0),
+            VoteTestGen::proposals(1),
+            PayloadType::Public,
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn vote_plans_are_indexed_by_submitter() {
+        let alice = CommitteeId::from([1; CommitteeId::COMMITTEE_ID_SIZE]);
+        let bob = CommitteeId::from([2; CommitteeId::COMMITTEE_ID_SIZE]);
+        let date = BlockDate::first();
+        let (first, second, other) = (vote_plan(1, 3), vote_plan(1, 3), vote_plan(1, 3));
+
+        let ledger = VotePlanLedger::new()
+            .add_vote_plan(
+                date,
+                first.clone(),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                alice,
+                None,
+            )
+            .unwrap()
+            .add_vote_plan(
+                date,
+                other.clone(),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                bob,
+                None,
+            )
+            .unwrap()
+            .add_vote_plan(
+                date,
+                second.clone(),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                alice,
+                None,
+            )
+            .unwrap();
+
+        let ids = |committee_id| {
+            ledger
+                .vote_plans_by_committee(&committee_id)
+                .map(|plan| plan.id().clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(alice), vec![first.to_id(), second.to_id()]);
+        assert_eq!(ids(bob), vec![other.to_id()]);
+        assert!(ids(CommitteeId::from([3; CommitteeId::COMMITTEE_ID_SIZE])).is_empty());
+    }
+
+    #[test]
+    fn active_vote_plans_are_capped_per_submitter() {
+        let alice = CommitteeId::from([1; CommitteeId::COMMITTEE_ID_SIZE]);
+        let bob = CommitteeId::from([2; CommitteeId::COMMITTEE_ID_SIZE]);
+        let date = BlockDate::first();
+
+        let ledger = VotePlanLedger::new()
+            .add_vote_plan(
+                date,
+                vote_plan(1, 3),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                alice,
+                Some(2),
+            )
+            .unwrap()
+            .add_vote_plan(
+                date,
+                vote_plan(1, 3),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                alice,
+                Some(2),
+            )
+            .unwrap();
+        assert_eq!(
+            ledger
+                .add_vote_plan(
+                    date,
+                    vote_plan(1, 3),
+                    HashVersion::CURRENT,
+                    HashSet::new(),
+                    alice,
+                    Some(2)
+                )
+                .err(),
+            Some(VotePlanLedgerError::TooManyActiveVotePlans {
+                committee_id: alice,
+                max: 2
+            })
+        );
+        ledger
+            .add_vote_plan(
+                date,
+                vote_plan(1, 3),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                bob,
+                Some(2),
+            )
+            .unwrap();
+
+        // the plans of alice have elapsed and no longer count
+        let date = BlockDate::from_epoch_slot_id(4, 0);
+        let ledger = ledger
+            .add_vote_plan(
+                date,
+                vote_plan(5, 7),
+                HashVersion::CURRENT,
+                HashSet::new(),
+                alice,
+                Some(2),
+            )
+            .unwrap();
+        assert_eq!(ledger.active_vote_plans_count(&alice, date), 1);
+        assert_eq!(ledger.vote_plans_by_committee(&alice).count(), 3);
+    }
+}
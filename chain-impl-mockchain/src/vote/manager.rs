@@ -3,18 +3,25 @@ use crate::{
     vote::{Choice, Payload, TallyError},
 };
 use crate::{
-    certificate::{DecryptedPrivateTally, Proposal, VoteAction, VoteCast, VotePlan, VotePlanId},
+    certificate::{
+        DecryptedPrivateTally, Proposal, VoteAction, VoteCast, VotePlan, VotePlanId,
+        VotingPowerSnapshot,
+    },
     date::BlockDate,
+    key::Hash,
     ledger::governance::{Governance, GovernanceAcceptanceCriteria},
     rewards::Ratio,
     stake::{Stake, StakeControl},
     transaction::UnspecifiedAccountIdentifier,
-    vote::{self, CommitteeId, Options, Tally, TallyResult, VotePlanStatus, VoteProposalStatus},
+    vote::{
+        self, CommitteeId, Options, Tally, TallyResult, VotePlanStatus, VoteProposalStatus, Voters,
+    },
 };
-use chain_vote::{EncryptedTally, CRS};
+use chain_vote::{EncryptedTally, MemberPublicKey};
 use imhamt::Hamt;
 use thiserror::Error;
 
+use std::borrow::Cow;
 use std::collections::{hash_map::DefaultHasher, HashSet};
 use std::convert::TryFrom;
 use std::num::NonZeroU64;
@@ -29,8 +36,23 @@ pub struct VotePlanManager {
     id: VotePlanId,
     plan: Arc<VotePlan>,
     committee: Arc<HashSet<CommitteeId>>,
+    /// the election keys of the committee members drawn among the
+    /// candidates, see [`CommitteeSortition`](super::CommitteeSortition)
+    drawn_public_keys: Arc<Vec<MemberPublicKey>>,
 
     proposal_managers: ProposalManagers,
+    voting_powers: Option<VotingPowers>,
+    /// the accounts which voted, committed once the vote is over: no vote
+    /// can be cast after the start of the tally
+    voters: Option<Arc<Voters>>,
+}
+
+/// the voting power snapshot imported for the vote plan, with the voting
+/// power proven by each of the voters
+#[derive(Clone, PartialEq, Eq)]
+struct VotingPowers {
+    snapshot: VotingPowerSnapshot,
+    by_voters: Hamt<DefaultHasher, UnspecifiedAccountIdentifier, u64>,
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -91,6 +113,24 @@ pub enum VoteError {
 
     #[error("Error during private tallying {0}")]
     PrivateTallyError(String),
+
+    #[error("The voting power snapshot must be imported before the start of the vote ({start})")]
+    NotSnapshotTime { start: BlockDate },
+
+    #[error("Unexpected VotingPowerSnapshotProof's public ID, expected one of the committee")]
+    InvalidSnapshotCommittee,
+
+    #[error("A voting power snapshot is already imported for the vote plan")]
+    VotingPowerSnapshotAlreadyImported,
+
+    #[error("The vote plan has a voting power snapshot, the vote must carry a voting power proof")]
+    MissingVotingPowerProof,
+
+    #[error("The vote plan has no voting power snapshot, unexpected voting power proof")]
+    UnexpectedVotingPowerProof,
+
+    #[error("Invalid voting power proof")]
+    InvalidVotingPowerProof,
 }
 
 impl ProposalManager {
@@ -475,7 +515,12 @@ impl ProposalManagers {
 
 impl VotePlanManager {
     pub fn new(plan: VotePlan, committee: HashSet<CommitteeId>) -> Self {
-        let id = plan.to_id();
+        Self::with_id(plan.to_id(), plan, committee)
+    }
+
+    /// The manager of a plan registered under the given id, which may be of
+    /// another version than the id computed by [`VotePlan::to_id`]
+    pub fn with_id(id: VotePlanId, plan: VotePlan, committee: HashSet<CommitteeId>) -> Self {
         let proposal_managers = ProposalManagers::new(&plan);
 
         Self {
@@ -483,6 +528,9 @@ impl VotePlanManager {
             plan: Arc::new(plan),
             proposal_managers,
             committee: Arc::new(committee),
+            drawn_public_keys: Arc::new(Vec::new()),
+            voting_powers: None,
+            voters: None,
         }
     }
 
@@ -510,7 +558,7 @@ impl VotePlanManager {
             })
             .collect();
 
-        let committee_public_keys = self.plan().committee_public_keys().to_vec();
+        let committee_public_keys = self.committee_public_keys();
 
         VotePlanStatus {
             id: self.id.clone(),
@@ -520,9 +568,33 @@ impl VotePlanManager {
             committee_end: self.plan().committee_end(),
             committee_public_keys,
             proposals,
+            voters_root: self.voters_root(),
+        }
+    }
+
+    /// the accounts which cast a vote for at least one of the proposals
+    ///
+    /// the set is committed at the start of the tally, until then it is
+    /// built from the votes cast so far.
+    pub fn voters(&self) -> Arc<Voters> {
+        match &self.voters {
+            Some(voters) => Arc::clone(voters),
+            None => Arc::new(Voters::from_voters(
+                self.proposal_managers.0.iter().flat_map(|manager| {
+                    manager
+                        .votes_by_voters
+                        .iter()
+                        .map(|(voter, _)| voter.clone())
+                }),
+            )),
         }
     }
 
+    /// the root of the accounts which voted, none until the tally starts
+    pub fn voters_root(&self) -> Option<Hash> {
+        self.voters.as_ref().map(|voters| voters.root())
+    }
+
     pub fn can_vote(&self, date: BlockDate) -> bool {
         self.plan().can_vote(date)
     }
@@ -535,6 +607,36 @@ impl VotePlanManager {
         &self.committee
     }
 
+    /// the keys the private votes are encrypted to: the keys of the vote
+    /// plan followed by the keys of the drawn committee members
+    pub fn committee_public_keys(&self) -> Vec<MemberPublicKey> {
+        self.plan()
+            .committee_public_keys()
+            .iter()
+            .chain(self.drawn_public_keys.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// add the given members to the committee of the vote plan, along with
+    /// their election key if the vote is private
+    pub fn with_committee_members<I>(&self, members: I) -> Self
+    where
+        I: IntoIterator<Item = (CommitteeId, Option<MemberPublicKey>)>,
+    {
+        let mut committee = self.committee_set().clone();
+        let mut drawn_public_keys = self.drawn_public_keys.as_ref().clone();
+        for (member, public_key) in members {
+            committee.insert(member);
+            drawn_public_keys.extend(public_key);
+        }
+        Self {
+            committee: Arc::new(committee),
+            drawn_public_keys: Arc::new(drawn_public_keys),
+            ..self.clone()
+        }
+    }
+
     /// return true if the vote plan has elapsed i.e. the vote is
     /// no longer interesting to track in the ledger and it can be
     /// GCed.
@@ -546,6 +648,107 @@ impl VotePlanManager {
         self.committee_set().contains(id)
     }
 
+    /// the voting power snapshot imported for the vote plan, if any
+    pub fn voting_power_snapshot(&self) -> Option<&VotingPowerSnapshot> {
+        self.voting_powers.as_ref().map(|powers| &powers.snapshot)
+    }
+
+    /// import the voting power of the voters, computed off-chain
+    ///
+    /// # errors
+    ///
+    /// * if the vote has started already
+    /// * if the signer is not a member of the committee
+    /// * if a snapshot was imported already
+    ///
+    pub fn import_voting_power_snapshot(
+        &self,
+        block_date: BlockDate,
+        snapshot: &VotingPowerSnapshot,
+        sig: CommitteeId,
+    ) -> Result<Self, VoteError> {
+        if block_date >= self.plan().vote_start() {
+            return Err(VoteError::NotSnapshotTime {
+                start: self.plan().vote_start(),
+            });
+        }
+
+        if !self.valid_committee(&sig) {
+            return Err(VoteError::InvalidSnapshotCommittee);
+        }
+
+        if self.voting_powers.is_some() {
+            return Err(VoteError::VotingPowerSnapshotAlreadyImported);
+        }
+
+        Ok(Self {
+            proposal_managers: self.proposal_managers.clone(),
+            plan: Arc::clone(&self.plan),
+            id: self.id.clone(),
+            committee: Arc::clone(&self.committee),
+            drawn_public_keys: Arc::clone(&self.drawn_public_keys),
+            voting_powers: Some(VotingPowers {
+                snapshot: snapshot.clone(),
+                by_voters: Hamt::new(),
+            }),
+            voters: None,
+        })
+    }
+
+    /// check the voting power proof of the vote against the snapshot, if
+    /// any, and record the voting power of the voter
+    fn prove_voting_power(
+        &self,
+        identifier: &UnspecifiedAccountIdentifier,
+        cast: &VoteCast,
+    ) -> Result<Option<VotingPowers>, VoteError> {
+        let powers = match (&self.voting_powers, cast.voting_power_proof()) {
+            (None, None) => return Ok(None),
+            (None, Some(_)) => return Err(VoteError::UnexpectedVotingPowerProof),
+            (Some(_), None) => return Err(VoteError::MissingVotingPowerProof),
+            (Some(powers), Some(proof)) => {
+                let snapshot = &powers.snapshot;
+                let verified = identifier.to_single_account().map_or(false, |account| {
+                    proof.verify(
+                        &account,
+                        snapshot.root(),
+                        snapshot.voters(),
+                        snapshot.total(),
+                    )
+                });
+                if !verified {
+                    return Err(VoteError::InvalidVotingPowerProof);
+                }
+                let power = proof.voting_power;
+                VotingPowers {
+                    snapshot: snapshot.clone(),
+                    by_voters: powers.by_voters.insert_or_update_simple(
+                        identifier.clone(),
+                        power,
+                        |_| Some(power),
+                    ),
+                }
+            }
+        };
+        Ok(Some(powers))
+    }
+
+    /// the stake the votes are tallied with: the voting power proven by the
+    /// voters if a snapshot is imported, the given stake otherwise
+    fn tally_stake<'a>(&self, stake: &'a StakeControl) -> Cow<'a, StakeControl> {
+        match &self.voting_powers {
+            None => Cow::Borrowed(stake),
+            Some(powers) => Cow::Owned(StakeControl::new_with_snapshot(
+                Stake(powers.snapshot.total()),
+                powers.by_voters.iter().filter_map(|(voter, power)| {
+                    voter
+                        .to_single_account()
+                        .map(|account| (account, Stake(*power)))
+                }),
+            )),
+        }
+    }
+
     /// attempt to apply the vote to one of the proposals
     ///
     /// If the given identifier already had a vote, the previous vote will
@@ -588,12 +791,11 @@ impl VotePlanManager {
                 encrypted_vote,
                 proof,
             } => {
-                let crs = CRS::from_hash(&self.plan.as_ref().to_id().as_ref());
+                let crs = self.plan.crs();
                 let ciphertext = encrypted_vote.as_inner();
                 self.proposal_managers.validate_vote(&cast)?;
-                let pk = chain_vote::EncryptingVoteKey::from_participants(
-                    self.plan.committee_public_keys(),
-                );
+                let pk =
+                    chain_vote::EncryptingVoteKey::from_participants(&self.committee_public_keys());
                 if !chain_vote::verify_vote(&crs, &pk, ciphertext, proof.as_inner()) {
                     Err(VoteError::VoteVerificationError)
                 } else {
@@ -603,6 +805,7 @@ impl VotePlanManager {
         } {
             Err(e)
         } else {
+            let voting_powers = self.prove_voting_power(&identifier, &cast)?;
             let proposal_managers = self.proposal_managers.vote(identifier, cast)?;
 
             Ok(Self {
@@ -610,6 +813,9 @@ impl VotePlanManager {
                 plan: Arc::clone(&self.plan),
                 id: self.id.clone(),
                 committee: Arc::clone(&self.committee),
+                drawn_public_keys: Arc::clone(&self.drawn_public_keys),
+                voting_powers,
+                voters: None,
             })
         }
     }
@@ -640,13 +846,17 @@ impl VotePlanManager {
             return Err(TallyError::InvalidPrivacy.into());
         }
 
-        let proposal_managers = self.proposal_managers.public_tally(stake, governance, f)?;
+        let stake = self.tally_stake(stake);
+        let proposal_managers = self.proposal_managers.public_tally(&stake, governance, f)?;
 
         Ok(Self {
             proposal_managers,
             plan: Arc::clone(&self.plan),
             id: self.id.clone(),
             committee: Arc::clone(&self.committee),
+            drawn_public_keys: Arc::clone(&self.drawn_public_keys),
+            voting_powers: self.voting_powers.clone(),
+            voters: Some(self.voters()),
         })
     }
 
@@ -671,13 +881,17 @@ impl VotePlanManager {
             return Err(TallyError::InvalidPrivacy.into());
         }
 
-        let proposal_managers = self.proposal_managers.start_private_tally(stake)?;
+        let stake = self.tally_stake(stake);
+        let proposal_managers = self.proposal_managers.start_private_tally(&stake)?;
 
         Ok(Self {
             proposal_managers,
             plan: Arc::clone(&self.plan),
             id: self.id.clone(),
             committee: Arc::clone(&self.committee),
+            drawn_public_keys: Arc::clone(&self.drawn_public_keys),
+            voting_powers: self.voting_powers.clone(),
+            voters: Some(self.voters()),
         })
     }
 
@@ -698,6 +912,9 @@ impl VotePlanManager {
             plan: Arc::clone(&self.plan),
             id: self.id.clone(),
             committee: Arc::clone(&self.committee),
+            drawn_public_keys: Arc::clone(&self.drawn_public_keys),
+            voting_powers: self.voting_powers.clone(),
+            voters: self.voters.clone(),
         })
     }
 }
@@ -864,7 +1081,9 @@ mod tests {
             TallyProof::Public { id, .. } => id,
             TallyProof::Private { id, .. } => id,
         };
-        vote_plan_manager
+        assert_eq!(vote_plan_manager.statuses().voters_root, None);
+        let voters_root = vote_plan_manager.voters().root();
+        let vote_plan_manager = vote_plan_manager
             .public_tally(
                 block_date,
                 &stake_controlled,
@@ -873,7 +1092,8 @@ mod tests {
                 |_| action_hit = true,
             )
             .unwrap();
-        assert!(action_hit)
+        assert!(action_hit);
+        assert_eq!(vote_plan_manager.statuses().voters_root, Some(voters_root));
     }
 
     #[test]
@@ -1088,6 +1308,8 @@ mod tests {
         Governance {
             treasury: treasury_governance,
             parameters: parameters_governance,
+            enactments: Default::default(),
+            certifications: Default::default(),
         }
     }
 
@@ -1340,4 +1562,172 @@ mod tests {
             )
             .is_ok());
     }
+
+    #[test]
+    pub fn vote_manager_voters() {
+        let vote_plan = VotePlan::new(
+            BlockDate::from_epoch_slot_id(1, 0),
+            BlockDate::from_epoch_slot_id(2, 0),
+            BlockDate::from_epoch_slot_id(3, 0),
+            VoteTestGen::proposals(3),
+            vote::PayloadType::Public,
+            Vec::new(),
+        );
+        let date = BlockDate::from_epoch_slot_id(1, 1);
+        let alice = TestGen::unspecified_account_identifier();
+        let bob = TestGen::unspecified_account_identifier();
+        let clarice = TestGen::unspecified_account_identifier();
+
+        let vote_plan_manager = VotePlanManager::new(vote_plan.clone(), HashSet::new())
+            .vote(
+                date,
+                alice.clone(),
+                VoteCast::new(vote_plan.to_id(), 0, VoteTestGen::vote_cast_payload()),
+            )
+            .unwrap()
+            .vote(
+                date,
+                bob.clone(),
+                VoteCast::new(vote_plan.to_id(), 1, VoteTestGen::vote_cast_payload()),
+            )
+            .unwrap()
+            .vote(
+                date,
+                alice.clone(),
+                VoteCast::new(vote_plan.to_id(), 2, VoteTestGen::vote_cast_payload()),
+            )
+            .unwrap();
+
+        let voters = vote_plan_manager.voters();
+        let root = voters.root();
+        assert_eq!(voters.len(), 2);
+        assert!(voters.proof(&alice).unwrap().verify(&root));
+        assert!(voters.proof(&bob).unwrap().verify(&root));
+        assert!(voters
+            .non_voter_proof(&clarice)
+            .unwrap()
+            .verify(&clarice, &root));
+    }
+
+    #[test]
+    pub fn vote_plan_manager_voting_power_snapshot() {
+        use crate::vote::{VotingPowerTree, Weight};
+
+        let favorable = Choice::new(1);
+        let committee = Wallet::from_value(Value(100));
+        let alice = Wallet::from_value(Value(100));
+        let bob = Wallet::from_value(Value(100));
+        let proposals = VoteTestGen::proposals_with_action(
+            VoteAction::Treasury {
+                action: TreasuryGovernanceAction::TransferToRewards { value: Value(30) },
+            },
+            1,
+        );
+        let vote_plan = VotePlan::new(
+            BlockDate::from_epoch_slot_id(1, 0),
+            BlockDate::from_epoch_slot_id(2, 0),
+            BlockDate::from_epoch_slot_id(3, 0),
+            proposals,
+            vote::PayloadType::Public,
+            Vec::new(),
+        );
+        let committee_id: CommitteeId = committee.public_key().into();
+        let mut committee_ids = HashSet::new();
+        committee_ids.insert(committee_id.clone());
+        let vote_plan_manager = VotePlanManager::new(vote_plan.clone(), committee_ids);
+
+        let tree = VotingPowerTree::from_entries(vec![
+            (alice.public_key().into(), 30),
+            (bob.public_key().into(), 70),
+        ])
+        .unwrap();
+        let snapshot =
+            VotingPowerSnapshot::new(vote_plan.to_id(), tree.root(), tree.len(), tree.total());
+
+        let before_vote = BlockDate::from_epoch_slot_id(0, 10);
+        assert_eq!(
+            vote_plan_manager
+                .import_voting_power_snapshot(
+                    vote_plan.vote_start(),
+                    &snapshot,
+                    committee_id.clone()
+                )
+                .err(),
+            Some(VoteError::NotSnapshotTime {
+                start: vote_plan.vote_start()
+            })
+        );
+        assert_eq!(
+            vote_plan_manager
+                .import_voting_power_snapshot(before_vote, &snapshot, alice.public_key().into())
+                .err(),
+            Some(VoteError::InvalidSnapshotCommittee)
+        );
+        let vote_plan_manager = vote_plan_manager
+            .import_voting_power_snapshot(before_vote, &snapshot, committee_id.clone())
+            .unwrap();
+        assert_eq!(vote_plan_manager.voting_power_snapshot(), Some(&snapshot));
+        assert_eq!(
+            vote_plan_manager
+                .import_voting_power_snapshot(before_vote, &snapshot, committee_id.clone())
+                .err(),
+            Some(VoteError::VotingPowerSnapshotAlreadyImported)
+        );
+
+        let vote_date = BlockDate::from_epoch_slot_id(1, 10);
+        let alice_id = UnspecifiedAccountIdentifier::from_single_account(alice.public_key().into());
+        let vote_cast = VoteCast::new(
+            vote_plan.to_id(),
+            0,
+            VoteTestGen::vote_cast_payload_for(&favorable),
+        );
+        assert_eq!(
+            vote_plan_manager
+                .vote(vote_date, alice_id.clone(), vote_cast.clone())
+                .err(),
+            Some(VoteError::MissingVotingPowerProof)
+        );
+        let bob_proof = tree.proof(&bob.public_key().into()).unwrap();
+        assert_eq!(
+            vote_plan_manager
+                .vote(
+                    vote_date,
+                    alice_id.clone(),
+                    vote_cast.clone().with_voting_power_proof(bob_proof)
+                )
+                .err(),
+            Some(VoteError::InvalidVotingPowerProof)
+        );
+        let alice_proof = tree.proof(&alice.public_key().into()).unwrap();
+        let vote_plan_manager = vote_plan_manager
+            .vote(
+                vote_date,
+                alice_id,
+                vote_cast.with_voting_power_proof(alice_proof),
+            )
+            .unwrap();
+
+        // the stake of the ledger is not used, only the snapshot is
+        let mut action_hit = false;
+        let vote_plan_manager = vote_plan_manager
+            .public_tally(
+                BlockDate::from_epoch_slot_id(2, 10),
+                &StakeControl::new(),
+                &governance_50_percent(Choice::new(0), favorable, Choice::new(2)),
+                committee_id,
+                |_| action_hit = true,
+            )
+            .unwrap();
+        // 30 out of 100 of the voting power is below the participation
+        // threshold
+        assert!(!action_hit);
+        let status = vote_plan_manager.statuses();
+        let result = status.proposals[0]
+            .tally
+            .as_ref()
+            .unwrap()
+            .result()
+            .unwrap();
+        assert_eq!(result.results()[1], Weight::from(30u64));
+    }
 }
@@ -0,0 +1,90 @@
+//! Merkle tree shared by the commitments to the voters of a vote plan and to
+//! the voting power snapshots.
+//!
+//! The leaves are hashed by the users of the tree, with a prefix of their
+//! own, and so is the root, which commits to the number of leaves along with
+//! the top node. A node without sibling is moved up unchanged, so the path
+//! of a leaf only holds the siblings it has.
+
+use crate::key::Hash;
+
+pub(super) const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+pub(super) const ROOT_PREFIX: u8 = 2;
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut buf = Vec::with_capacity(65);
+    buf.push(NODE_PREFIX);
+    buf.extend_from_slice(left.as_bytes());
+    buf.extend_from_slice(right.as_bytes());
+    Hash::hash_bytes(&buf)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct MerkleTree {
+    /// `levels[h]` contains the nodes of height `h`, from left to right
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    pub(super) fn new(leaves: Vec<Hash>) -> Self {
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+        MerkleTree { levels }
+    }
+
+    /// the top node, none if the tree is empty
+    pub(super) fn top(&self) -> Option<&Hash> {
+        self.levels[self.levels.len() - 1].first()
+    }
+
+    /// the siblings of the `index`-th leaf, from the leaf up to the top
+    pub(super) fn path(&self, index: usize) -> Vec<Hash> {
+        let mut path = Vec::new();
+        let mut position = index;
+        for level in self.levels.iter() {
+            if let Some(sibling) = level.get(position ^ 1) {
+                path.push(*sibling);
+            }
+            position >>= 1;
+        }
+        path
+    }
+}
+
+/// the top node of a tree of `leaf_count` leaves whose `index`-th leaf is
+/// `leaf` and has the given path, none if the path does not fit the tree
+pub(super) fn path_top(leaf: Hash, index: u64, leaf_count: u64, path: &[Hash]) -> Option<Hash> {
+    if index >= leaf_count {
+        return None;
+    }
+    let mut node = leaf;
+    let mut path = path.iter();
+    let mut position = index;
+    let mut level_len = leaf_count;
+    while level_len > 1 {
+        let sibling = position ^ 1;
+        if sibling < level_len {
+            node = match path.next()? {
+                hash if position % 2 == 0 => hash_node(&node, hash),
+                hash => hash_node(hash, &node),
+            };
+        }
+        position >>= 1;
+        level_len = (level_len + 1) / 2;
+    }
+    match path.next() {
+        None => Some(node),
+        Some(_) => None,
+    }
+}
@@ -7,10 +7,14 @@ mod choice;
 mod committee;
 mod ledger;
 mod manager;
+mod merkle;
 mod payload;
 mod privacy;
+mod snapshot;
+mod sortition;
 mod status;
 mod tally;
+mod voters;
 
 pub use self::{
     choice::{Choice, Options},
@@ -19,6 +23,12 @@ pub use self::{
     manager::{VoteError, VotePlanManager},
     payload::{EncryptedVote, Payload, PayloadType, ProofOfCorrectVote, TryFromIntError},
     privacy::encrypt_vote,
+    snapshot::{VotingPowerProof, VotingPowerTree},
+    sortition::{
+        draw_committee, ticket_input, Candidacies, Candidate, CommitteeSortition,
+        CommitteeSortitionError,
+    },
     status::{VotePlanStatus, VoteProposalStatus},
     tally::{PrivateTallyState, Tally, TallyError, TallyResult, Weight},
+    voters::{NonVoterProof, VoterProof, Voters},
 };
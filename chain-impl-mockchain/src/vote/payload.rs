@@ -69,9 +69,15 @@ impl Payload {
     }
 
     pub(crate) fn serialize_in<T>(&self, bb: ByteBuilder<T>) -> ByteBuilder<T> {
+        self.serialize_flagged_in(bb, 0)
+    }
+
+    /// serialize the payload with the `flags` set on the payload type byte,
+    /// whose high bit is free for the encoding containing the payload
+    pub(crate) fn serialize_flagged_in<T>(&self, bb: ByteBuilder<T>, flags: u8) -> ByteBuilder<T> {
         let payload_type = self.payload_type();
 
-        let bb = bb.u8(payload_type as u8);
+        let bb = bb.u8(payload_type as u8 | flags);
 
         match self {
             Self::Public { choice } => bb.u8(choice.as_byte()),
@@ -85,22 +91,30 @@ impl Payload {
     }
 
     pub(crate) fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
-        let t = buf
-            .get_u8()?
+        Self::read_flagged(buf, 0).map(|(payload, _)| payload)
+    }
+
+    /// read a payload serialized with some of the `mask` flags set, which
+    /// are returned along with the payload
+    pub(crate) fn read_flagged(buf: &mut ReadBuf, mask: u8) -> Result<(Self, u8), ReadError> {
+        let t = buf.get_u8()?;
+        let flags = t & mask;
+        let t = (t & !mask)
             .try_into()
             .map_err(|e: TryFromIntError| ReadError::StructureInvalid(e.to_string()))?;
 
-        match t {
-            PayloadType::Public => buf.get_u8().map(Choice::new).map(Self::public),
+        let payload = match t {
+            PayloadType::Public => buf.get_u8().map(Choice::new).map(Self::public)?,
             PayloadType::Private => {
                 let encrypted_vote = EncryptedVote::read(buf)?;
                 let proof = ProofOfCorrectVote::read(buf)?;
-                Ok(Self::Private {
+                Self::Private {
                     encrypted_vote,
                     proof,
-                })
+                }
             }
-        }
+        };
+        Ok((payload, flags))
     }
 }
 
@@ -130,6 +144,11 @@ impl ProofOfCorrectVote {
         self.serialize_in(ByteBuilder::new()).finalize()
     }
 
+    /// Length of `serialize`, known without serializing the proof
+    pub fn serialized_len(&self) -> usize {
+        self.0.serialized_len()
+    }
+
     pub(crate) fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
         let bits = buf.get_u8()? as usize;
         let mut ibas = Vec::with_capacity(bits);
@@ -173,6 +192,11 @@ impl EncryptedVote {
         &self.0
     }
 
+    /// number of options of the ballot, one ciphertext each
+    pub fn options(&self) -> usize {
+        self.0.len()
+    }
+
     pub(crate) fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
         bb.iter8(&self.0, |bb, ct| {
             let buffer = ct.to_bytes();
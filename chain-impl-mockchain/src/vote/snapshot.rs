@@ -0,0 +1,246 @@
+//! Voting power snapshots computed off-chain.
+//!
+//! The voting power of the accounts allowed to vote in a vote plan may be
+//! computed outside of the ledger, for example from registrations made on
+//! another chain. The accounts are sorted with their voting power in a
+//! Merkle tree, and a [`VotingPowerSnapshot`] certificate imports its root
+//! and the total voting power for the vote plan. From then on, every ballot
+//! of the vote plan carries a [`VotingPowerProof`] of the voting power of
+//! its account, which is the weight of the ballot in the tally instead of
+//! the stake of the account.
+//!
+//! [`VotingPowerSnapshot`]: crate::certificate::VotingPowerSnapshot
+
+use super::merkle::{path_top, MerkleTree, LEAF_PREFIX, ROOT_PREFIX};
+use crate::{account::Identifier, key::Hash};
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use typed_bytes::ByteBuilder;
+
+/// Maximum length of the path of a proof, enough for `u32::MAX` accounts
+const MAX_PATH_LEN: usize = 32;
+
+fn hash_leaf(account: &Identifier, voting_power: u64) -> Hash {
+    let mut buf = Vec::with_capacity(41);
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(account.as_ref().as_ref());
+    buf.extend_from_slice(&voting_power.to_be_bytes());
+    Hash::hash_bytes(&buf)
+}
+
+fn hash_root(leaf_count: u32, total: u64, top: Option<&Hash>) -> Hash {
+    let mut buf = vec![ROOT_PREFIX];
+    buf.extend_from_slice(&leaf_count.to_be_bytes());
+    buf.extend_from_slice(&total.to_be_bytes());
+    if let Some(top) = top {
+        buf.extend_from_slice(top.as_bytes());
+    }
+    Hash::hash_bytes(&buf)
+}
+
+/// Merkle tree of the voting power of the accounts, built by the producer
+/// of a snapshot to get its root and the proofs of the voters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VotingPowerTree {
+    entries: Vec<(Identifier, u64)>,
+    total: u64,
+    tree: MerkleTree,
+}
+
+/// Proof that an account has `voting_power` in a snapshot, as the
+/// `index`-th account of the tree
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VotingPowerProof {
+    pub voting_power: u64,
+    pub index: u32,
+    /// siblings from the leaf up to the top of the tree
+    pub path: Vec<Hash>,
+}
+
+impl VotingPowerTree {
+    /// Build the tree of the given voting powers, the powers of an account
+    /// listed more than once are added up. There cannot be more than
+    /// `u32::MAX` accounts and the total voting power cannot exceed
+    /// `u64::MAX`, none is returned otherwise.
+    pub fn from_entries<I>(entries: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (Identifier, u64)>,
+    {
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.as_ref().as_ref().cmp(b.0.as_ref().as_ref()));
+        let mut merged: Vec<(Identifier, u64)> = Vec::with_capacity(entries.len());
+        for (account, voting_power) in entries {
+            match merged.last_mut() {
+                Some((last, power)) if *last == account => {
+                    *power = power.checked_add(voting_power)?;
+                }
+                _ => merged.push((account, voting_power)),
+            }
+        }
+        if merged.len() > u32::MAX as usize {
+            return None;
+        }
+        let total = merged
+            .iter()
+            .try_fold(0u64, |total, (_, power)| total.checked_add(*power))?;
+
+        let tree = MerkleTree::new(
+            merged
+                .iter()
+                .map(|(account, power)| hash_leaf(account, *power))
+                .collect(),
+        );
+
+        Some(VotingPowerTree {
+            entries: merged,
+            total,
+            tree,
+        })
+    }
+
+    /// Number of accounts in the tree
+    pub fn len(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Commitment to the accounts, their voting power and the total
+    pub fn root(&self) -> Hash {
+        hash_root(self.len(), self.total, self.tree.top())
+    }
+
+    /// Proof of the voting power of `account`, none if it is not in the tree
+    pub fn proof(&self, account: &Identifier) -> Option<VotingPowerProof> {
+        let index = self
+            .entries
+            .binary_search_by(|(entry, _)| entry.as_ref().as_ref().cmp(account.as_ref().as_ref()))
+            .ok()?;
+        Some(VotingPowerProof {
+            voting_power: self.entries[index].1,
+            index: index as u32,
+            path: self.tree.path(index),
+        })
+    }
+}
+
+impl VotingPowerProof {
+    /// Check that `account` has `self.voting_power` in the snapshot of
+    /// `leaf_count` accounts and `total` voting power committed by `root`
+    pub fn verify(&self, account: &Identifier, root: &Hash, leaf_count: u32, total: u64) -> bool {
+        if self.voting_power > total {
+            return false;
+        }
+        path_top(
+            hash_leaf(account, self.voting_power),
+            self.index as u64,
+            leaf_count as u64,
+            &self.path,
+        )
+        .map_or(false, |top| {
+            hash_root(leaf_count, total, Some(&top)) == *root
+        })
+    }
+
+    pub fn serialize_in<T>(&self, bb: ByteBuilder<T>) -> ByteBuilder<T> {
+        bb.u64(self.voting_power)
+            .u32(self.index)
+            .iter8(&self.path, |bb, hash| bb.bytes(hash.as_ref()))
+    }
+}
+
+impl Readable for VotingPowerProof {
+    fn read(buf: &mut ReadBuf) -> Result<Self, ReadError> {
+        let voting_power = buf.get_u64()?;
+        let index = buf.get_u32()?;
+        let len = buf.get_u8()? as usize;
+        if len > MAX_PATH_LEN {
+            return Err(ReadError::SizeTooBig(len, MAX_PATH_LEN));
+        }
+        let path = (0..len)
+            .map(|_| Hash::read(buf))
+            .collect::<Result<_, _>>()?;
+        Ok(VotingPowerProof {
+            voting_power,
+            index,
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_crypto::{Ed25519, PublicKey};
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+
+    fn account(seed: u8) -> Identifier {
+        PublicKey::<Ed25519>::from_binary(&[seed; 32])
+            .unwrap()
+            .into()
+    }
+
+    #[quickcheck]
+    fn voters_have_proofs(powers: Vec<(u8, u32)>) -> TestResult {
+        let tree = match VotingPowerTree::from_entries(
+            powers
+                .iter()
+                .map(|(seed, power)| (account(*seed), *power as u64)),
+        ) {
+            Some(tree) => tree,
+            None => return TestResult::discard(),
+        };
+        let root = tree.root();
+        for (seed, _) in powers.iter() {
+            let expected: u64 = powers
+                .iter()
+                .filter(|(s, _)| s == seed)
+                .map(|(_, power)| *power as u64)
+                .sum();
+            let proof = tree.proof(&account(*seed)).unwrap();
+            if proof.voting_power != expected
+                || !proof.verify(&account(*seed), &root, tree.len(), tree.total())
+            {
+                return TestResult::error(format!("invalid proof for account {}", seed));
+            }
+        }
+        TestResult::passed()
+    }
+
+    #[test]
+    fn proofs_are_bound_to_the_snapshot() {
+        let tree = VotingPowerTree::from_entries(vec![
+            (account(1), 10),
+            (account(2), 20),
+            (account(3), 30),
+        ])
+        .unwrap();
+        let root = tree.root();
+        assert_eq!(tree.total(), 60);
+        assert!(tree.proof(&account(4)).is_none());
+
+        let proof = tree.proof(&account(2)).unwrap();
+        assert!(proof.verify(&account(2), &root, 3, 60));
+        assert!(!proof.verify(&account(1), &root, 3, 60));
+        assert!(!proof.verify(&account(2), &root, 4, 60));
+        assert!(!proof.verify(&account(2), &root, 3, 61));
+
+        let inflated = VotingPowerProof {
+            voting_power: 25,
+            ..proof.clone()
+        };
+        assert!(!inflated.verify(&account(2), &root, 3, 60));
+
+        let bytes = proof
+            .serialize_in(ByteBuilder::<VotingPowerProof>::new())
+            .finalize_as_vec();
+        let decoded = VotingPowerProof::read(&mut ReadBuf::from(&bytes)).unwrap();
+        assert_eq!(decoded, proof);
+    }
+}
@@ -0,0 +1,633 @@
+//! Committee sortition of the vote plans.
+//!
+//! Besides the committee members declared in the settings of the ledger, the
+//! committee of a vote plan can be drawn among candidates. The draw goes
+//! through the following steps, with `E` the epoch of the start of the vote:
+//!
+//! 1. until the start of the epoch `E - 2`, the accounts apply to the
+//!    committee of the vote plan with a
+//!    [`CommitteeCandidacy`](crate::certificate::CommitteeCandidacy), giving
+//!    the VRF key their ticket is evaluated with, and their election key if
+//!    the vote is private;
+//! 2. on the first block of the epoch `E - 1`, the ledger takes the nonce of
+//!    the epoch as the nonce of the draw, accumulated over a whole epoch
+//!    after the candidacies closed, along with the stake of the candidates,
+//!    the value of their account;
+//! 3. until the vote starts, the candidates reveal their ticket, the VRF
+//!    evaluation of the nonce of the draw, the vote plan and the candidate,
+//!    with its proof;
+//! 4. on the first block of the voting period, the ledger draws the
+//!    committee among the candidates with a revealed ticket and some stake.
+//!    The drawn members join the committee of the vote plan, their election
+//!    keys are added to the keys the votes are encrypted to, and the
+//!    candidacies are dropped.
+//!
+//! The draw is an exponential race: the ticket is mapped to `u` in the unit
+//! interval and the candidate finishes at `-ln(u) / stake`, so that each
+//! candidate comes first with a probability proportional to its stake. The
+//! [`CommitteeSortitionSize`](crate::config::ConfigParam::CommitteeSortitionSize)
+//! first candidates to finish are drawn.
+//!
+//! The nonce of the draw is only random under Genesis-Praos, the candidacies
+//! are rejected under BFT.
+
+use crate::{
+    certificate::VotePlanId, chaineval::PraosNonce, date::BlockDate, key::Hash, value::Value,
+    vote::CommitteeId,
+};
+use chain_crypto::{
+    algorithms::vrf::{range, ProvenOutputSeed},
+    vrf_verified_get_output, vrf_verify, Curve25519_2HashDH, PublicKey, VRFVerification,
+};
+use chain_vote::MemberPublicKey;
+use imhamt::{Hamt, HamtIter, UpdateError};
+use std::collections::hash_map::DefaultHasher;
+use thiserror::Error;
+
+const DOMAIN: &[u8] = b"committee sortition";
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CommitteeSortitionError {
+    #[error("The committee sortition is not enabled")]
+    Disabled,
+
+    #[error("The committee sortition needs the nonce of Genesis-Praos, the consensus is BFT")]
+    BftConsensus,
+
+    #[error("Vote plan {id} does not exist")]
+    UnknownVotePlan { id: VotePlanId },
+
+    #[error("Candidacies closed at {closed}, current date {current_date}")]
+    CandidacyClosed {
+        current_date: BlockDate,
+        closed: BlockDate,
+    },
+
+    #[error("{candidate} is already a candidate to the committee of the vote plan {id}")]
+    AlreadyCandidate {
+        id: VotePlanId,
+        candidate: CommitteeId,
+    },
+
+    #[error("The candidates to the committee of the vote plan {id} give an election key iff the vote is private")]
+    InvalidMemberKey { id: VotePlanId },
+
+    #[error("{candidate} is not a candidate to the committee of the vote plan {id}")]
+    NotCandidate {
+        id: VotePlanId,
+        candidate: CommitteeId,
+    },
+
+    #[error("The nonce of the draw of the vote plan {id} is not known yet")]
+    TicketTooEarly { id: VotePlanId },
+
+    #[error("Tickets closed when the vote started ({vote_start}), current date {current_date}")]
+    TicketTooLate {
+        current_date: BlockDate,
+        vote_start: BlockDate,
+    },
+
+    #[error("{candidate} already revealed its ticket for the vote plan {id}")]
+    TicketAlreadyRevealed {
+        id: VotePlanId,
+        candidate: CommitteeId,
+    },
+
+    #[error("Invalid ticket proof of {candidate} for the vote plan {id}")]
+    InvalidTicket {
+        id: VotePlanId,
+        candidate: CommitteeId,
+    },
+}
+
+/// A candidate to the committee of a vote plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    id: CommitteeId,
+    vrf_key: PublicKey<Curve25519_2HashDH>,
+    member_key: Option<MemberPublicKey>,
+    stake: Value,
+    ticket: Option<Hash>,
+}
+
+impl Candidate {
+    pub(crate) fn restore(
+        id: CommitteeId,
+        vrf_key: PublicKey<Curve25519_2HashDH>,
+        member_key: Option<MemberPublicKey>,
+        stake: Value,
+        ticket: Option<Hash>,
+    ) -> Self {
+        Self {
+            id,
+            vrf_key,
+            member_key,
+            stake,
+            ticket,
+        }
+    }
+
+    pub fn id(&self) -> &CommitteeId {
+        &self.id
+    }
+
+    pub fn vrf_key(&self) -> &PublicKey<Curve25519_2HashDH> {
+        &self.vrf_key
+    }
+
+    /// the election key of the candidate, for the private votes
+    pub fn member_key(&self) -> Option<&MemberPublicKey> {
+        self.member_key.as_ref()
+    }
+
+    /// the stake of the candidate, zero until the nonce of the draw is taken
+    pub fn stake(&self) -> Value {
+        self.stake
+    }
+
+    /// the ticket of the candidate, once revealed
+    pub fn ticket(&self) -> Option<&Hash> {
+        self.ticket.as_ref()
+    }
+}
+
+/// The candidates to the committee of a vote plan, until the draw
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidacies {
+    vote_start: BlockDate,
+    candidates: Vec<Candidate>,
+    nonce: Option<PraosNonce>,
+}
+
+impl Candidacies {
+    pub(crate) fn restore(
+        vote_start: BlockDate,
+        candidates: Vec<Candidate>,
+        nonce: Option<PraosNonce>,
+    ) -> Self {
+        Self {
+            vote_start,
+            candidates,
+            nonce,
+        }
+    }
+
+    /// the start of the vote, when the committee is drawn
+    pub fn vote_start(&self) -> BlockDate {
+        self.vote_start
+    }
+
+    /// the candidates, in order of registration
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// the nonce the tickets are evaluated with, once taken
+    pub fn nonce(&self) -> Option<&PraosNonce> {
+        self.nonce.as_ref()
+    }
+}
+
+/// The candidacies to the committees of the vote plans, see the
+/// [module documentation](self)
+#[derive(Clone, PartialEq, Eq)]
+pub struct CommitteeSortition {
+    plans: Hamt<DefaultHasher, VotePlanId, Candidacies>,
+}
+
+/// the date the candidacies to the committee of a vote plan close
+fn candidacy_close(vote_start: BlockDate) -> BlockDate {
+    BlockDate {
+        epoch: vote_start.epoch.saturating_sub(2),
+        slot_id: 0,
+    }
+}
+
+/// the VRF input a candidate evaluates its ticket on, with the nonce of the
+/// draw given by [`Candidacies::nonce`]
+pub fn ticket_input(
+    nonce: &PraosNonce,
+    vote_plan: &VotePlanId,
+    candidate: &CommitteeId,
+) -> Vec<u8> {
+    let mut input = Vec::with_capacity(DOMAIN.len() + 3 * 32);
+    input.extend_from_slice(DOMAIN);
+    input.extend_from_slice(nonce.as_ref());
+    input.extend_from_slice(vote_plan.as_ref());
+    input.extend_from_slice(candidate.as_ref());
+    input
+}
+
+impl CommitteeSortition {
+    pub fn new() -> Self {
+        Self { plans: Hamt::new() }
+    }
+
+    /// rebuild the candidacies of the vote plans, as listed by `iter`
+    pub(crate) fn restore<I>(plans: I) -> Self
+    where
+        I: IntoIterator<Item = (VotePlanId, Candidacies)>,
+    {
+        Self {
+            plans: plans.into_iter().collect(),
+        }
+    }
+
+    /// the candidacies by vote plan, until their committee is drawn
+    pub(crate) fn iter(&self) -> HamtIter<'_, VotePlanId, Candidacies> {
+        self.plans.iter()
+    }
+
+    /// the candidacies to the committee of the given vote plan, none if
+    /// there is none or once the committee is drawn
+    pub fn candidacies(&self, id: &VotePlanId) -> Option<&Candidacies> {
+        self.plans.lookup(id)
+    }
+
+    /// register the candidate to the committee of the vote plan
+    ///
+    /// # errors
+    ///
+    /// * if the candidacies are closed
+    /// * if the candidate already applied to the committee of the vote plan
+    ///
+    pub fn add_candidate(
+        &self,
+        current_date: BlockDate,
+        id: &VotePlanId,
+        vote_start: BlockDate,
+        candidate: CommitteeId,
+        vrf_key: PublicKey<Curve25519_2HashDH>,
+        member_key: Option<MemberPublicKey>,
+    ) -> Result<Self, CommitteeSortitionError> {
+        let closed = candidacy_close(vote_start);
+        if current_date >= closed {
+            return Err(CommitteeSortitionError::CandidacyClosed {
+                current_date,
+                closed,
+            });
+        }
+
+        let candidate = Candidate {
+            id: candidate,
+            vrf_key,
+            member_key,
+            stake: Value::zero(),
+            ticket: None,
+        };
+        let first = Candidacies {
+            vote_start,
+            candidates: vec![candidate.clone()],
+            nonce: None,
+        };
+        let plans = self
+            .plans
+            .insert_or_update(id.clone(), first, |candidacies| {
+                if candidacies.candidates.iter().any(|c| c.id == candidate.id) {
+                    return Err(CommitteeSortitionError::AlreadyCandidate {
+                        id: id.clone(),
+                        candidate: candidate.id,
+                    });
+                }
+                let mut candidacies = candidacies.clone();
+                candidacies.candidates.push(candidate.clone());
+                Ok(Some(candidacies))
+            })?;
+        Ok(Self { plans })
+    }
+
+    /// verify and record the ticket of the candidate to the committee of the
+    /// vote plan
+    ///
+    /// # errors
+    ///
+    /// * if the nonce of the draw is not taken yet or the vote has started
+    /// * if the account is not a candidate or already revealed its ticket
+    /// * if the proof does not verify against the VRF key of the candidate
+    ///
+    pub fn reveal_ticket(
+        &self,
+        current_date: BlockDate,
+        id: &VotePlanId,
+        candidate: CommitteeId,
+        proof: &ProvenOutputSeed,
+    ) -> Result<Self, CommitteeSortitionError> {
+        let plans = self.plans.update(id, |candidacies| {
+            if current_date >= candidacies.vote_start {
+                return Err(CommitteeSortitionError::TicketTooLate {
+                    current_date,
+                    vote_start: candidacies.vote_start,
+                });
+            }
+            let nonce = candidacies
+                .nonce
+                .as_ref()
+                .ok_or_else(|| CommitteeSortitionError::TicketTooEarly { id: id.clone() })?;
+            let index = candidacies
+                .candidates
+                .iter()
+                .position(|c| c.id == candidate)
+                .ok_or_else(|| CommitteeSortitionError::NotCandidate {
+                    id: id.clone(),
+                    candidate,
+                })?;
+            if candidacies.candidates[index].ticket.is_some() {
+                return Err(CommitteeSortitionError::TicketAlreadyRevealed {
+                    id: id.clone(),
+                    candidate,
+                });
+            }
+
+            let input = ticket_input(nonce, id, &candidate);
+            let vrf_key = &candidacies.candidates[index].vrf_key;
+            if vrf_verify(vrf_key, &input, proof) != VRFVerification::Success {
+                return Err(CommitteeSortitionError::InvalidTicket {
+                    id: id.clone(),
+                    candidate,
+                });
+            }
+            let output = vrf_verified_get_output::<Curve25519_2HashDH>(proof);
+
+            let mut candidacies = candidacies.clone();
+            candidacies.candidates[index].ticket = Some(output.to_output(&input, DOMAIN).into());
+            Ok(Some(candidacies))
+        });
+        match plans {
+            Ok(plans) => Ok(Self { plans }),
+            Err(UpdateError::KeyNotFound) => Err(CommitteeSortitionError::NotCandidate {
+                id: id.clone(),
+                candidate,
+            }),
+            Err(UpdateError::ValueCallbackError(error)) => Err(error),
+        }
+    }
+
+    /// take the nonce of the draw and the stake of the candidates for the
+    /// vote plans whose vote starts by the next epoch, to be called on the
+    /// first block of the epoch of `date` before its own nonce is accumulated
+    pub(crate) fn take_nonces<F>(&self, date: BlockDate, nonce: &PraosNonce, stake: F) -> Self
+    where
+        F: Fn(&CommitteeId) -> Value,
+    {
+        let mut plans = self.plans.clone();
+        for (id, candidacies) in self.plans.iter() {
+            if candidacies.nonce.is_some() || date.epoch + 1 < candidacies.vote_start.epoch {
+                continue;
+            }
+            let mut candidacies = candidacies.clone();
+            candidacies.nonce = Some(nonce.clone());
+            for candidate in candidacies.candidates.iter_mut() {
+                candidate.stake = stake(&candidate.id);
+            }
+            plans = plans
+                .replace(id, candidacies)
+                .expect("the vote plan has candidacies")
+                .0;
+        }
+        Self { plans }
+    }
+
+    /// draw the committees of the vote plans whose vote has started at the
+    /// given date, and return the drawn members by vote plan. The
+    /// candidacies are dropped once drawn.
+    pub(crate) fn draw(
+        &self,
+        date: BlockDate,
+        size: u32,
+    ) -> (Self, Vec<(VotePlanId, Vec<Candidate>)>) {
+        let mut plans = self.plans.clone();
+        let mut drawn = Vec::new();
+        for (id, candidacies) in self.plans.iter() {
+            if candidacies.vote_start > date {
+                continue;
+            }
+            let members = draw_committee(&candidacies.candidates, size);
+            plans = plans.remove(id).expect("the vote plan has candidacies");
+            drawn.push((id.clone(), members));
+        }
+        (Self { plans }, drawn)
+    }
+}
+
+impl Default for CommitteeSortition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the finishing time of the candidate in the exponential race, none if it
+/// did not reveal its ticket or has no stake
+fn finish_time(candidate: &Candidate) -> Option<f64> {
+    match (candidate.ticket.as_ref(), candidate.stake) {
+        (None, _) | (_, Value(0)) => None,
+        (Some(ticket), Value(stake)) => {
+            let u = range::to_unit_f64(ticket.as_bytes());
+            Some(-u.ln() / stake as f64)
+        }
+    }
+}
+
+/// draw at most `size` committee members among the candidates with a
+/// revealed ticket and some stake, first to finish the race first
+pub fn draw_committee(candidates: &[Candidate], size: u32) -> Vec<Candidate> {
+    let mut ranked: Vec<(f64, &Candidate)> = candidates
+        .iter()
+        .filter_map(|candidate| finish_time(candidate).map(|time| (time, candidate)))
+        .collect();
+    ranked.sort_by(|(time_a, a), (time_b, b)| {
+        time_a
+            .partial_cmp(time_b)
+            .expect("the finishing times are not NaN")
+            .then_with(|| a.id.as_ref().cmp(b.id.as_ref()))
+    });
+    ranked
+        .into_iter()
+        .take(size as usize)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_crypto::{vrf_evaluate_and_prove, SecretKey};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn candidate(i: u8) -> CommitteeId {
+        CommitteeId::from([i; CommitteeId::COMMITTEE_ID_SIZE])
+    }
+
+    fn vrf_key(i: u8) -> SecretKey<Curve25519_2HashDH> {
+        SecretKey::generate(ChaCha20Rng::from_seed([i; 32]))
+    }
+
+    fn vote_plan_id(i: u8) -> VotePlanId {
+        [i; 32].into()
+    }
+
+    fn date(epoch: u32, slot_id: u32) -> BlockDate {
+        BlockDate { epoch, slot_id }
+    }
+
+    fn ticket(
+        nonce: &PraosNonce,
+        id: &VotePlanId,
+        i: u8,
+        key: &SecretKey<Curve25519_2HashDH>,
+    ) -> ProvenOutputSeed {
+        let input = ticket_input(nonce, id, &candidate(i));
+        vrf_evaluate_and_prove(key, &input, ChaCha20Rng::from_seed([0; 32]))
+    }
+
+    #[test]
+    fn candidacies_follow_the_steps_of_the_draw() {
+        let id = vote_plan_id(1);
+        let vote_start = date(4, 10);
+        let apply = |sortition: &CommitteeSortition, date, i| {
+            sortition.add_candidate(
+                date,
+                &id,
+                vote_start,
+                candidate(i),
+                vrf_key(i).to_public(),
+                None,
+            )
+        };
+
+        let sortition = apply(&CommitteeSortition::new(), date(1, 5), 1).unwrap();
+        let sortition = apply(&sortition, date(1, 6), 2).unwrap();
+        assert_eq!(
+            apply(&sortition, date(1, 7), 1).err(),
+            Some(CommitteeSortitionError::AlreadyCandidate {
+                id: id.clone(),
+                candidate: candidate(1)
+            })
+        );
+        assert_eq!(
+            apply(&sortition, date(2, 0), 3).err(),
+            Some(CommitteeSortitionError::CandidacyClosed {
+                current_date: date(2, 0),
+                closed: date(2, 0),
+            })
+        );
+
+        // the nonce of the draw is taken on the epoch before the vote
+        let nonce = PraosNonce::from_output_array([7; 32]);
+        let proof = ticket(&nonce, &id, 1, &vrf_key(1));
+        assert_eq!(
+            sortition
+                .reveal_ticket(date(2, 5), &id, candidate(1), &proof)
+                .err(),
+            Some(CommitteeSortitionError::TicketTooEarly { id: id.clone() })
+        );
+        let stake = |c: &CommitteeId| Value(c.as_ref()[0] as u64);
+        let sortition = sortition.take_nonces(date(2, 5), &nonce, stake);
+        assert_eq!(sortition.candidacies(&id).unwrap().nonce(), None);
+        let sortition = sortition.take_nonces(date(3, 0), &nonce, stake);
+        let candidacies = sortition.candidacies(&id).unwrap();
+        assert_eq!(candidacies.nonce(), Some(&nonce));
+        assert_eq!(candidacies.candidates()[1].stake(), Value(2));
+
+        assert_eq!(
+            sortition
+                .reveal_ticket(date(3, 1), &id, candidate(2), &proof)
+                .err(),
+            Some(CommitteeSortitionError::InvalidTicket {
+                id: id.clone(),
+                candidate: candidate(2)
+            })
+        );
+        assert_eq!(
+            sortition
+                .reveal_ticket(date(3, 1), &id, candidate(3), &proof)
+                .err(),
+            Some(CommitteeSortitionError::NotCandidate {
+                id: id.clone(),
+                candidate: candidate(3)
+            })
+        );
+        assert_eq!(
+            sortition
+                .reveal_ticket(vote_start, &id, candidate(1), &proof)
+                .err(),
+            Some(CommitteeSortitionError::TicketTooLate {
+                current_date: vote_start,
+                vote_start
+            })
+        );
+        let sortition = sortition
+            .reveal_ticket(date(3, 1), &id, candidate(1), &proof)
+            .unwrap();
+        assert_eq!(
+            sortition
+                .reveal_ticket(date(3, 2), &id, candidate(1), &proof)
+                .err(),
+            Some(CommitteeSortitionError::TicketAlreadyRevealed {
+                id: id.clone(),
+                candidate: candidate(1)
+            })
+        );
+
+        let (sortition, drawn) = sortition.draw(date(4, 9), 2);
+        assert!(drawn.is_empty());
+        // only the candidate who revealed its ticket is drawn, and the
+        // candidacies are dropped
+        let (sortition, drawn) = sortition.draw(vote_start, 2);
+        assert_eq!(drawn.len(), 1);
+        assert_eq!(drawn[0].0, id);
+        let members: Vec<_> = drawn[0].1.iter().map(|c| *c.id()).collect();
+        assert_eq!(members, vec![candidate(1)]);
+        assert!(sortition.candidacies(&id).is_none());
+        let (_, drawn) = sortition.draw(vote_start.next_epoch(), 2);
+        assert!(drawn.is_empty());
+    }
+
+    #[test]
+    fn draw_is_proportional_to_stake() {
+        let with_ticket = |i: u8, stake: u64, ticket: Hash| Candidate {
+            id: candidate(i),
+            vrf_key: vrf_key(i).to_public(),
+            member_key: None,
+            stake: Value(stake),
+            ticket: Some(ticket),
+        };
+        let draws = 3000u32;
+
+        let mut drawn_heavy = 0;
+        for draw in 0..draws {
+            let ticket = |i: u8| {
+                let mut bytes = draw.to_be_bytes().to_vec();
+                bytes.push(i);
+                Hash::hash_bytes(&bytes)
+            };
+            let candidates = vec![
+                with_ticket(1, 0, ticket(1)),
+                with_ticket(2, 1, ticket(2)),
+                with_ticket(3, 2, ticket(3)),
+            ];
+            let drawn = draw_committee(&candidates, 1);
+            assert_eq!(drawn, draw_committee(&candidates, 1));
+            assert_eq!(drawn.len(), 1);
+            assert_ne!(drawn[0].id(), &candidate(1));
+            if drawn[0].id() == &candidate(3) {
+                drawn_heavy += 1;
+            }
+        }
+        // 2 times the stake wins 2/3 of the draws
+        let ratio = drawn_heavy as f64 / draws as f64;
+        assert!(0.63 < ratio && ratio < 0.70, "ratio {}", ratio);
+
+        // every candidate with stake and a ticket is drawn if there are not
+        // enough of them
+        let mut candidates = vec![
+            with_ticket(1, 0, Hash::hash_bytes(&[1])),
+            with_ticket(2, 1, Hash::hash_bytes(&[2])),
+            with_ticket(3, 2, Hash::hash_bytes(&[3])),
+        ];
+        candidates[2].ticket = None;
+        let drawn = draw_committee(&candidates, 10);
+        assert_eq!(drawn.len(), 1);
+        assert_eq!(drawn[0].id(), &candidate(2));
+    }
+}
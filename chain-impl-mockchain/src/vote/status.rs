@@ -1,6 +1,7 @@
 use crate::{
     certificate::{ExternalProposalId, VotePlanId},
     date::BlockDate,
+    key::Hash,
     transaction::UnspecifiedAccountIdentifier,
     vote::{Options, Payload, PayloadType, Tally},
 };
@@ -16,6 +17,9 @@ pub struct VotePlanStatus {
     pub committee_end: BlockDate,
     pub committee_public_keys: Vec<MemberPublicKey>,
     pub proposals: Vec<VoteProposalStatus>,
+    /// the root of the accounts which voted in the plan, set once the tally
+    /// has started
+    pub voters_root: Option<Hash>,
 }
 
 pub struct VoteProposalStatus {
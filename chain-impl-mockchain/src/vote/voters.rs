@@ -0,0 +1,221 @@
+//! Commitment to the accounts which voted in a vote plan.
+//!
+//! The identifiers of all the accounts which cast a vote for at least one of
+//! the proposals of a vote plan (public or private) are sorted and put in a
+//! Merkle tree. Given a trusted root, a [`VoterProof`] shows that an account
+//! voted and a [`NonVoterProof`] shows that it did not: the proof carries the
+//! two voters surrounding the account in the sorted order, which have to be
+//! neighbours in the tree. This lets a reward system pay (or not pay) voters
+//! without trusting an indexer.
+
+use super::merkle::{path_top, MerkleTree, LEAF_PREFIX, ROOT_PREFIX};
+use crate::{key::Hash, transaction::UnspecifiedAccountIdentifier};
+
+fn hash_leaf(voter: &UnspecifiedAccountIdentifier) -> Hash {
+    let mut buf = Vec::with_capacity(33);
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(voter.as_ref());
+    Hash::hash_bytes(&buf)
+}
+
+fn hash_root(leaf_count: u64, top: Option<&Hash>) -> Hash {
+    let mut buf = vec![ROOT_PREFIX];
+    buf.extend_from_slice(&leaf_count.to_be_bytes());
+    if let Some(top) = top {
+        buf.extend_from_slice(top.as_bytes());
+    }
+    Hash::hash_bytes(&buf)
+}
+
+/// Sorted set of the accounts which voted in a vote plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Voters {
+    voters: Vec<UnspecifiedAccountIdentifier>,
+    tree: MerkleTree,
+}
+
+/// Proof that `voter` is the `index`-th voter of a set of `leaf_count` voters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoterProof {
+    pub voter: UnspecifiedAccountIdentifier,
+    pub index: u64,
+    pub leaf_count: u64,
+    /// siblings from the leaf up to the top of the tree
+    pub path: Vec<Hash>,
+}
+
+/// Proof that an account is not part of a set of `leaf_count` voters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonVoterProof {
+    pub leaf_count: u64,
+    /// the greatest voter lower than the account, none if there is no such
+    /// voter
+    pub lower: Option<VoterProof>,
+    /// the lowest voter greater than the account, none if there is no such
+    /// voter
+    pub upper: Option<VoterProof>,
+}
+
+impl Voters {
+    /// Build the set of the given voters, duplicates are ignored
+    pub fn from_voters<I: IntoIterator<Item = UnspecifiedAccountIdentifier>>(voters: I) -> Self {
+        let mut voters: Vec<_> = voters.into_iter().collect();
+        voters.sort_unstable_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        voters.dedup();
+
+        let tree = MerkleTree::new(voters.iter().map(hash_leaf).collect());
+
+        Voters { voters, tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.voters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voters.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &UnspecifiedAccountIdentifier> {
+        self.voters.iter()
+    }
+
+    fn position(&self, account: &UnspecifiedAccountIdentifier) -> Result<usize, usize> {
+        self.voters
+            .binary_search_by(|voter| voter.as_ref().cmp(account.as_ref()))
+    }
+
+    pub fn contains(&self, account: &UnspecifiedAccountIdentifier) -> bool {
+        self.position(account).is_ok()
+    }
+
+    /// Commitment to the whole set of voters
+    pub fn root(&self) -> Hash {
+        hash_root(self.len() as u64, self.tree.top())
+    }
+
+    fn proof_at(&self, index: usize) -> VoterProof {
+        VoterProof {
+            voter: self.voters[index].clone(),
+            index: index as u64,
+            leaf_count: self.len() as u64,
+            path: self.tree.path(index),
+        }
+    }
+
+    /// Proof that `account` voted, none if it did not
+    pub fn proof(&self, account: &UnspecifiedAccountIdentifier) -> Option<VoterProof> {
+        self.position(account)
+            .ok()
+            .map(|index| self.proof_at(index))
+    }
+
+    /// Proof that `account` did not vote, none if it did
+    pub fn non_voter_proof(&self, account: &UnspecifiedAccountIdentifier) -> Option<NonVoterProof> {
+        let index = self.position(account).err()?;
+        Some(NonVoterProof {
+            leaf_count: self.len() as u64,
+            lower: index.checked_sub(1).map(|lower| self.proof_at(lower)),
+            upper: if index < self.len() {
+                Some(self.proof_at(index))
+            } else {
+                None
+            },
+        })
+    }
+}
+
+impl VoterProof {
+    /// Check that `self.voter` is part of the set of voters committed by
+    /// `root`
+    pub fn verify(&self, root: &Hash) -> bool {
+        path_top(
+            hash_leaf(&self.voter),
+            self.index,
+            self.leaf_count,
+            &self.path,
+        )
+        .map_or(false, |top| hash_root(self.leaf_count, Some(&top)) == *root)
+    }
+}
+
+impl NonVoterProof {
+    /// Check that `account` is not part of the set of voters committed by
+    /// `root`
+    pub fn verify(&self, account: &UnspecifiedAccountIdentifier, root: &Hash) -> bool {
+        let valid = |proof: &VoterProof| proof.leaf_count == self.leaf_count && proof.verify(root);
+        match (&self.lower, &self.upper) {
+            (None, None) => self.leaf_count == 0 && hash_root(0, None) == *root,
+            (Some(lower), None) => {
+                valid(lower)
+                    && lower.index + 1 == self.leaf_count
+                    && lower.voter.as_ref() < account.as_ref()
+            }
+            (None, Some(upper)) => {
+                valid(upper) && upper.index == 0 && account.as_ref() < upper.voter.as_ref()
+            }
+            (Some(lower), Some(upper)) => {
+                valid(lower)
+                    && valid(upper)
+                    && lower.index + 1 == upper.index
+                    && lower.voter.as_ref() < account.as_ref()
+                    && account.as_ref() < upper.voter.as_ref()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+
+    fn account(seed: u8) -> UnspecifiedAccountIdentifier {
+        UnspecifiedAccountIdentifier::from([seed; 32])
+    }
+
+    #[quickcheck]
+    fn voters_have_proofs(seeds: Vec<u8>) -> TestResult {
+        let voters = Voters::from_voters(seeds.iter().map(|seed| account(*seed)));
+        let root = voters.root();
+        for seed in 0..=u8::MAX {
+            let account = account(seed);
+            let voted = seeds.contains(&seed);
+            if voters.contains(&account) != voted {
+                return TestResult::error(format!("account {} is misplaced", seed));
+            }
+            let valid = match (voters.proof(&account), voters.non_voter_proof(&account)) {
+                (Some(proof), None) => voted && proof.verify(&root),
+                (None, Some(proof)) => !voted && proof.verify(&account, &root),
+                _ => false,
+            };
+            if !valid {
+                return TestResult::error(format!("invalid proof for account {}", seed));
+            }
+        }
+        TestResult::passed()
+    }
+
+    #[test]
+    fn non_voter_proof_rejects_voters() {
+        let voters = Voters::from_voters(vec![account(1), account(3), account(5)]);
+        let root = voters.root();
+        let proof = voters.non_voter_proof(&account(2)).unwrap();
+        assert!(proof.verify(&account(2), &root));
+        assert!(!proof.verify(&account(1), &root));
+        assert!(!proof.verify(&account(3), &root));
+        assert!(!proof.verify(&account(4), &root));
+
+        // the voters around the account have to be neighbours
+        let gap = NonVoterProof {
+            leaf_count: 3,
+            lower: voters.proof(&account(1)),
+            upper: voters.proof(&account(5)),
+        };
+        assert!(!gap.verify(&account(3), &root));
+
+        let more_voters = Voters::from_voters(vec![account(1), account(2), account(3)]);
+        assert!(!proof.verify(&account(2), &more_voters.root()));
+    }
+}
@@ -0,0 +1,741 @@
+//! Resolution of the bootstrap peers from the DNS.
+//!
+//! A node is configured with a list of [`BootstrapSource`]s, each naming a
+//! DNS record which lists some of the bootstrap peers of the network:
+//!
+//! * `A`/`AAAA` records of a host name, with the port of the peers;
+//! * `SRV` records, whose targets are resolved in turn;
+//! * `TXT` records holding whitespace separated multiaddrs, like
+//!   `/ip4/192.0.2.1/tcp/3000` or `/dns4/peer.example.com/tcp/3000`, or
+//!   `/ip4/192.0.2.1/udp/3000/quic` for the peers reached over QUIC. The
+//!   strings which do not start with a `/` are not meant for us and ignored.
+//!
+//! [`BootstrapResolver`] caches the peers of every source for the time to
+//! live of the records, clamped to configurable bounds, and returns all the
+//! peers shuffled so that the nodes do not all connect to the same ones.
+//! The sources, `SRV` targets and multiaddrs which fail to resolve are
+//! skipped, and their errors kept for the node to log: the resolution only
+//! fails when no peer at all is found.
+//!
+//! The DNS lookups go through the [`DnsResolver`] trait, implemented for
+//! the resolver of `trust-dns` with the `dns` feature.
+
+use crate::data::{Peer, Transport};
+use async_trait::async_trait;
+use rand_core::RngCore;
+
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// A DNS record listing bootstrap peers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BootstrapSource {
+    /// The addresses of a host name, all listening on the same port.
+    Host { name: String, port: u16 },
+    /// The targets of the `SRV` records of the name.
+    Srv { name: String },
+    /// The multiaddrs in the `TXT` records of the name.
+    Txt { name: String },
+}
+
+impl BootstrapSource {
+    fn name(&self) -> &str {
+        match self {
+            BootstrapSource::Host { name, .. }
+            | BootstrapSource::Srv { name }
+            | BootstrapSource::Txt { name } => name,
+        }
+    }
+}
+
+/// The records of a DNS lookup, with their time to live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookup<T> {
+    pub records: Vec<T>,
+    pub ttl: Duration,
+}
+
+/// The fields of a `SRV` record used to find the bootstrap peers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub target: String,
+    pub port: u16,
+}
+
+/// The DNS lookups needed to resolve the bootstrap sources.
+#[async_trait]
+pub trait DnsResolver {
+    type Error: error::Error + Send + Sync + 'static;
+
+    /// The `A` and `AAAA` records of the name.
+    async fn lookup_ip(&self, name: &str) -> Result<Lookup<IpAddr>, Self::Error>;
+
+    async fn lookup_srv(&self, name: &str) -> Result<Lookup<SrvRecord>, Self::Error>;
+
+    /// The `TXT` records of the name, the strings of each record being
+    /// concatenated.
+    async fn lookup_txt(&self, name: &str) -> Result<Lookup<String>, Self::Error>;
+}
+
+/// Error resolving a bootstrap source.
+#[derive(Debug, thiserror::Error)]
+pub enum BootstrapError {
+    #[error("failed to look up `{name}`")]
+    Lookup {
+        name: String,
+        #[source]
+        source: Box<dyn error::Error + Send + Sync>,
+    },
+    #[error("invalid multiaddr `{0}` in the TXT records")]
+    InvalidMultiaddr(String),
+}
+
+/// Address family restriction of a `/dns4` or `/dns6` multiaddr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    Any,
+    V4,
+    V6,
+}
+
+impl Family {
+    fn accepts(self, ip: &IpAddr) -> bool {
+        match self {
+            Family::Any => true,
+            Family::V4 => ip.is_ipv4(),
+            Family::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Multiaddr {
+    Socket(Peer),
+    Dns {
+        name: String,
+        family: Family,
+        port: u16,
+        transport: Transport,
+    },
+}
+
+fn parse_multiaddr(s: &str) -> Result<Multiaddr, BootstrapError> {
+    let invalid = || BootstrapError::InvalidMultiaddr(s.to_owned());
+    let components: Vec<&str> = s.split('/').collect();
+    let (protocol, host, port, transport) = match components.as_slice() {
+        ["", protocol, host, "tcp", port] => (*protocol, *host, *port, Transport::Tcp),
+        ["", protocol, host, "udp", port, "quic"] => (*protocol, *host, *port, Transport::Quic),
+        _ => return Err(invalid()),
+    };
+    let port = port.parse().map_err(|_| invalid())?;
+    let family = match protocol {
+        "ip4" | "ip6" => {
+            let ip: IpAddr = host.parse().map_err(|_| invalid())?;
+            if ip.is_ipv4() != (protocol == "ip4") {
+                return Err(invalid());
+            }
+            let addr = SocketAddr::new(ip, port);
+            return Ok(Multiaddr::Socket(Peer::new(addr, transport)));
+        }
+        "dns" => Family::Any,
+        "dns4" => Family::V4,
+        "dns6" => Family::V6,
+        _ => return Err(invalid()),
+    };
+    Ok(Multiaddr::Dns {
+        name: host.to_owned(),
+        family,
+        port,
+        transport,
+    })
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    peers: Vec<Peer>,
+    expires: Instant,
+}
+
+/// Resolves and caches the bootstrap peers.
+pub struct BootstrapResolver<R> {
+    resolver: R,
+    sources: Vec<BootstrapSource>,
+    min_ttl: Duration,
+    max_ttl: Duration,
+    cache: HashMap<BootstrapSource, CacheEntry>,
+    errors: Vec<BootstrapError>,
+}
+
+impl<R: DnsResolver + Sync> BootstrapResolver<R> {
+    /// The peers are cached between one minute and one hour, depending on
+    /// the time to live of the records.
+    pub fn new(resolver: R, sources: Vec<BootstrapSource>) -> Self {
+        BootstrapResolver {
+            resolver,
+            sources,
+            min_ttl: Duration::from_secs(60),
+            max_ttl: Duration::from_secs(3600),
+            cache: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Bounds of the time the peers of a source are cached, whatever the
+    /// time to live of its records.
+    pub fn with_ttl_bounds(mut self, min_ttl: Duration, max_ttl: Duration) -> Self {
+        self.min_ttl = min_ttl;
+        self.max_ttl = max_ttl.max(min_ttl);
+        self
+    }
+
+    pub fn sources(&self) -> &[BootstrapSource] {
+        &self.sources
+    }
+
+    /// Forget the cached peers, the sources are looked up again on the next
+    /// resolution.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear()
+    }
+
+    /// The errors skipped by the last resolution, for the node to log them.
+    /// The error returned by a failed resolution is not included.
+    pub fn errors(&self) -> &[BootstrapError] {
+        &self.errors
+    }
+
+    /// The peers of all the sources, without duplicates and shuffled.
+    ///
+    /// The sources and records which fail to resolve are skipped, the first
+    /// error is only returned when no peer at all could be found.
+    pub async fn resolve<G: RngCore>(&mut self, rng: &mut G) -> Result<Vec<Peer>, BootstrapError> {
+        self.resolve_at(Instant::now(), rng).await
+    }
+
+    async fn resolve_at<G: RngCore>(
+        &mut self,
+        now: Instant,
+        rng: &mut G,
+    ) -> Result<Vec<Peer>, BootstrapError> {
+        let mut peers = Vec::new();
+        let mut seen = HashSet::new();
+        let mut errors = Vec::new();
+        for source in self.sources.clone() {
+            let cached = self
+                .cache
+                .get(&source)
+                .filter(|entry| entry.expires > now)
+                .map(|entry| entry.peers.clone());
+            let source_peers = match cached {
+                Some(peers) => peers,
+                None => {
+                    let skipped = errors.len();
+                    match self.lookup_source(&source, &mut errors).await {
+                        // a source whose records all failed is not cached, to
+                        // be looked up again on the next resolution
+                        Ok((source_peers, _))
+                            if source_peers.is_empty() && errors.len() > skipped =>
+                        {
+                            continue
+                        }
+                        Ok((source_peers, ttl)) => {
+                            let ttl = ttl.max(self.min_ttl).min(self.max_ttl);
+                            let entry = CacheEntry {
+                                peers: source_peers.clone(),
+                                expires: now + ttl,
+                            };
+                            self.cache.insert(source, entry);
+                            source_peers
+                        }
+                        Err(e) => {
+                            errors.push(e);
+                            continue;
+                        }
+                    }
+                }
+            };
+            peers.extend(
+                source_peers
+                    .into_iter()
+                    .filter(|peer| seen.insert(peer.clone())),
+            );
+        }
+        let result = if peers.is_empty() && !errors.is_empty() {
+            Err(errors.remove(0))
+        } else {
+            shuffle(&mut peers, rng);
+            Ok(peers)
+        };
+        self.errors = errors;
+        result
+    }
+
+    /// The failures of the records of the source are pushed to `errors`,
+    /// the source only fails when its own lookup does.
+    async fn lookup_source(
+        &self,
+        source: &BootstrapSource,
+        errors: &mut Vec<BootstrapError>,
+    ) -> Result<(Vec<Peer>, Duration), BootstrapError> {
+        match source {
+            BootstrapSource::Host { name, port } => {
+                self.lookup_host(name, Family::Any, *port, Transport::Tcp)
+                    .await
+            }
+            BootstrapSource::Srv { name } => {
+                let lookup = self
+                    .resolver
+                    .lookup_srv(name)
+                    .await
+                    .map_err(|e| lookup_error(source.name(), e))?;
+                let mut peers = Vec::new();
+                let mut ttl = lookup.ttl;
+                for record in lookup.records {
+                    match self
+                        .lookup_host(&record.target, Family::Any, record.port, Transport::Tcp)
+                        .await
+                    {
+                        Ok((target_peers, target_ttl)) => {
+                            peers.extend(target_peers);
+                            ttl = ttl.min(target_ttl);
+                        }
+                        Err(e) => errors.push(e),
+                    }
+                }
+                Ok((peers, ttl))
+            }
+            BootstrapSource::Txt { name } => {
+                let lookup = self
+                    .resolver
+                    .lookup_txt(name)
+                    .await
+                    .map_err(|e| lookup_error(source.name(), e))?;
+                let mut peers = Vec::new();
+                let mut ttl = lookup.ttl;
+                let multiaddrs = lookup
+                    .records
+                    .iter()
+                    .flat_map(|record| record.split_whitespace())
+                    .filter(|s| s.starts_with('/'))
+                    .map(parse_multiaddr);
+                for multiaddr in multiaddrs {
+                    match multiaddr {
+                        Ok(Multiaddr::Socket(peer)) => peers.push(peer),
+                        Ok(Multiaddr::Dns {
+                            name,
+                            family,
+                            port,
+                            transport,
+                        }) => match self.lookup_host(&name, family, port, transport).await {
+                            Ok((host_peers, host_ttl)) => {
+                                peers.extend(host_peers);
+                                ttl = ttl.min(host_ttl);
+                            }
+                            Err(e) => errors.push(e),
+                        },
+                        Err(e) => errors.push(e),
+                    }
+                }
+                Ok((peers, ttl))
+            }
+        }
+    }
+
+    async fn lookup_host(
+        &self,
+        name: &str,
+        family: Family,
+        port: u16,
+        transport: Transport,
+    ) -> Result<(Vec<Peer>, Duration), BootstrapError> {
+        let lookup = self
+            .resolver
+            .lookup_ip(name)
+            .await
+            .map_err(|e| lookup_error(name, e))?;
+        let peers = lookup
+            .records
+            .into_iter()
+            .filter(|ip| family.accepts(ip))
+            .map(|ip| Peer::new(SocketAddr::new(ip, port), transport))
+            .collect();
+        Ok((peers, lookup.ttl))
+    }
+}
+
+fn lookup_error<E>(name: &str, source: E) -> BootstrapError
+where
+    E: error::Error + Send + Sync + 'static,
+{
+    BootstrapError::Lookup {
+        name: name.to_owned(),
+        source: Box::new(source),
+    }
+}
+
+// Fisher-Yates, the modulo bias is irrelevant for spreading the load
+fn shuffle<T, G: RngCore>(items: &mut [T], rng: &mut G) {
+    for i in (1..items.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(feature = "dns")]
+mod trust_dns {
+    use super::{DnsResolver, Lookup, SrvRecord};
+    use async_trait::async_trait;
+    use trust_dns_resolver::{error::ResolveError, TokioAsyncResolver};
+
+    use std::net::IpAddr;
+    use std::time::{Duration, Instant};
+
+    fn ttl(valid_until: Instant) -> Duration {
+        valid_until.saturating_duration_since(Instant::now())
+    }
+
+    #[async_trait]
+    impl DnsResolver for TokioAsyncResolver {
+        type Error = ResolveError;
+
+        async fn lookup_ip(&self, name: &str) -> Result<Lookup<IpAddr>, ResolveError> {
+            let lookup = TokioAsyncResolver::lookup_ip(self, name).await?;
+            Ok(Lookup {
+                records: lookup.iter().collect(),
+                ttl: ttl(lookup.valid_until()),
+            })
+        }
+
+        async fn lookup_srv(&self, name: &str) -> Result<Lookup<SrvRecord>, ResolveError> {
+            let lookup = self.srv_lookup(name).await?;
+            Ok(Lookup {
+                records: lookup
+                    .iter()
+                    .map(|srv| SrvRecord {
+                        target: srv.target().to_utf8(),
+                        port: srv.port(),
+                    })
+                    .collect(),
+                ttl: ttl(lookup.valid_until()),
+            })
+        }
+
+        async fn lookup_txt(&self, name: &str) -> Result<Lookup<String>, ResolveError> {
+            let lookup = self.txt_lookup(name).await?;
+            Ok(Lookup {
+                records: lookup
+                    .iter()
+                    .map(|txt| {
+                        txt.txt_data()
+                            .iter()
+                            .map(|data| String::from_utf8_lossy(data))
+                            .collect()
+                    })
+                    .collect(),
+                ttl: ttl(lookup.valid_until()),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Mutex;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("no such record")]
+    struct NotFound;
+
+    #[derive(Default)]
+    struct MockResolver {
+        ips: HashMap<String, Lookup<IpAddr>>,
+        srvs: HashMap<String, Lookup<SrvRecord>>,
+        txts: HashMap<String, Lookup<String>>,
+        lookups: Mutex<u32>,
+    }
+
+    impl MockResolver {
+        fn lookups(&self) -> u32 {
+            *self.lookups.lock().unwrap()
+        }
+
+        fn get<T: Clone>(
+            &self,
+            records: &HashMap<String, Lookup<T>>,
+            name: &str,
+        ) -> Result<Lookup<T>, NotFound> {
+            *self.lookups.lock().unwrap() += 1;
+            records.get(name).cloned().ok_or(NotFound)
+        }
+    }
+
+    #[async_trait]
+    impl DnsResolver for MockResolver {
+        type Error = NotFound;
+
+        async fn lookup_ip(&self, name: &str) -> Result<Lookup<IpAddr>, NotFound> {
+            self.get(&self.ips, name)
+        }
+
+        async fn lookup_srv(&self, name: &str) -> Result<Lookup<SrvRecord>, NotFound> {
+            self.get(&self.srvs, name)
+        }
+
+        async fn lookup_txt(&self, name: &str) -> Result<Lookup<String>, NotFound> {
+            self.get(&self.txts, name)
+        }
+    }
+
+    fn lookup<T>(records: Vec<T>, ttl: u64) -> Lookup<T> {
+        Lookup {
+            records,
+            ttl: Duration::from_secs(ttl),
+        }
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn peer(s: &str) -> Peer {
+        s.parse::<SocketAddr>().unwrap().into()
+    }
+
+    fn sorted(mut peers: Vec<Peer>) -> Vec<Peer> {
+        peers.sort_by_key(|peer| peer.addr());
+        peers
+    }
+
+    fn resolver() -> MockResolver {
+        let mut resolver = MockResolver::default();
+        resolver.ips.insert(
+            "a.example.com".into(),
+            lookup(vec![ip("192.0.2.1"), ip("2001:db8::1")], 300),
+        );
+        resolver
+            .ips
+            .insert("b.example.com".into(), lookup(vec![ip("192.0.2.2")], 120));
+        resolver.srvs.insert(
+            "_jormungandr._tcp.example.com".into(),
+            lookup(
+                vec![
+                    SrvRecord {
+                        target: "a.example.com".into(),
+                        port: 3000,
+                    },
+                    SrvRecord {
+                        target: "b.example.com".into(),
+                        port: 3001,
+                    },
+                ],
+                600,
+            ),
+        );
+        resolver.txts.insert(
+            "peers.example.com".into(),
+            lookup(
+                vec![
+                    "v=spf1 -all".into(),
+                    "/ip4/198.51.100.1/tcp/3000 /dns6/a.example.com/tcp/3002".into(),
+                    "/ip4/198.51.100.2/udp/3000/quic".into(),
+                ],
+                900,
+            ),
+        );
+        resolver
+    }
+
+    #[test]
+    fn multiaddrs() {
+        assert_eq!(
+            parse_multiaddr("/ip6/2001:db8::2/tcp/80").unwrap(),
+            Multiaddr::Socket(peer("[2001:db8::2]:80"))
+        );
+        assert_eq!(
+            parse_multiaddr("/ip4/192.0.2.1/udp/80/quic").unwrap(),
+            Multiaddr::Socket(Peer::new("192.0.2.1:80".parse().unwrap(), Transport::Quic))
+        );
+        assert_eq!(
+            parse_multiaddr("/dns4/example.com/tcp/3000").unwrap(),
+            Multiaddr::Dns {
+                name: "example.com".into(),
+                family: Family::V4,
+                port: 3000,
+                transport: Transport::Tcp,
+            }
+        );
+        assert_eq!(
+            parse_multiaddr("/dns/example.com/udp/3000/quic").unwrap(),
+            Multiaddr::Dns {
+                name: "example.com".into(),
+                family: Family::Any,
+                port: 3000,
+                transport: Transport::Quic,
+            }
+        );
+        for invalid in &[
+            "/ip4/2001:db8::2/tcp/80",
+            "/ip4/192.0.2.1/udp/80",
+            "/ip4/192.0.2.1/tcp/80/quic",
+            "/ip4/192.0.2.1/tcp/99999",
+            "/onion3/abc/tcp/80",
+            "/ip4/192.0.2.1",
+        ] {
+            assert!(parse_multiaddr(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn all_record_types_are_resolved() {
+        let sources = vec![
+            BootstrapSource::Host {
+                name: "b.example.com".into(),
+                port: 3001,
+            },
+            BootstrapSource::Srv {
+                name: "_jormungandr._tcp.example.com".into(),
+            },
+            BootstrapSource::Txt {
+                name: "peers.example.com".into(),
+            },
+        ];
+        let mut bootstrap = BootstrapResolver::new(resolver(), sources);
+        let mut rng = StdRng::seed_from_u64(0);
+        let peers = block_on(bootstrap.resolve(&mut rng)).unwrap();
+        assert_eq!(
+            sorted(peers),
+            vec![
+                peer("192.0.2.1:3000"),
+                peer("192.0.2.2:3001"),
+                peer("198.51.100.1:3000"),
+                Peer::new("198.51.100.2:3000".parse().unwrap(), Transport::Quic),
+                peer("[2001:db8::1]:3000"),
+                peer("[2001:db8::1]:3002"),
+            ]
+        );
+    }
+
+    #[test]
+    fn peers_are_cached_for_the_ttl() {
+        let sources = vec![
+            BootstrapSource::Srv {
+                name: "_jormungandr._tcp.example.com".into(),
+            },
+            BootstrapSource::Txt {
+                name: "peers.example.com".into(),
+            },
+        ];
+        let mut bootstrap = BootstrapResolver::new(resolver(), sources);
+        let mut rng = StdRng::seed_from_u64(0);
+        let start = Instant::now();
+
+        let peers = block_on(bootstrap.resolve_at(start, &mut rng)).unwrap();
+        assert_eq!(bootstrap.resolver.lookups(), 5);
+
+        // the SRV source expires with its shortest record, after 120s
+        let cached = block_on(bootstrap.resolve_at(start + Duration::from_secs(119), &mut rng));
+        assert_eq!(sorted(cached.unwrap()), sorted(peers.clone()));
+        assert_eq!(bootstrap.resolver.lookups(), 5);
+        block_on(bootstrap.resolve_at(start + Duration::from_secs(120), &mut rng)).unwrap();
+        assert_eq!(bootstrap.resolver.lookups(), 8);
+
+        // the TTL is clamped
+        let mut bootstrap =
+            bootstrap.with_ttl_bounds(Duration::from_secs(0), Duration::from_secs(10));
+        bootstrap.clear_cache();
+        block_on(bootstrap.resolve_at(start, &mut rng)).unwrap();
+        block_on(bootstrap.resolve_at(start + Duration::from_secs(10), &mut rng)).unwrap();
+        assert_eq!(bootstrap.resolver.lookups(), 18);
+    }
+
+    #[test]
+    fn failing_sources_are_skipped() {
+        let missing = BootstrapSource::Host {
+            name: "missing.example.com".into(),
+            port: 3000,
+        };
+        let mut bootstrap = BootstrapResolver::new(
+            resolver(),
+            vec![
+                missing.clone(),
+                BootstrapSource::Host {
+                    name: "b.example.com".into(),
+                    port: 3000,
+                },
+            ],
+        );
+        let mut rng = StdRng::seed_from_u64(0);
+        let peers = block_on(bootstrap.resolve(&mut rng)).unwrap();
+        assert_eq!(peers, vec![peer("192.0.2.2:3000")]);
+        assert_eq!(bootstrap.errors().len(), 1);
+
+        let mut bootstrap = BootstrapResolver::new(resolver(), vec![missing]);
+        match block_on(bootstrap.resolve(&mut rng)) {
+            Err(BootstrapError::Lookup { name, .. }) => assert_eq!(name, "missing.example.com"),
+            other => panic!("unexpected resolution {:?}", other),
+        }
+        assert!(bootstrap.errors().is_empty());
+    }
+
+    #[test]
+    fn failing_records_are_skipped() {
+        let mut resolver = resolver();
+        resolver.srvs.insert(
+            "_partial._tcp.example.com".into(),
+            lookup(
+                vec![
+                    SrvRecord {
+                        target: "missing.example.com".into(),
+                        port: 3000,
+                    },
+                    SrvRecord {
+                        target: "b.example.com".into(),
+                        port: 3001,
+                    },
+                ],
+                600,
+            ),
+        );
+        resolver.txts.insert(
+            "partial.example.com".into(),
+            lookup(
+                vec![
+                    "/ip4/198.51.100.1/tcp/3000 /ip4/198.51.100.1/tcp/x".into(),
+                    "/dns4/missing.example.com/tcp/3000".into(),
+                ],
+                900,
+            ),
+        );
+        let sources = vec![
+            BootstrapSource::Srv {
+                name: "_partial._tcp.example.com".into(),
+            },
+            BootstrapSource::Txt {
+                name: "partial.example.com".into(),
+            },
+        ];
+        let mut bootstrap = BootstrapResolver::new(resolver, sources);
+        let mut rng = StdRng::seed_from_u64(0);
+        let peers = block_on(bootstrap.resolve(&mut rng)).unwrap();
+        assert_eq!(
+            sorted(peers),
+            vec![peer("192.0.2.2:3001"), peer("198.51.100.1:3000")]
+        );
+        assert_eq!(bootstrap.errors().len(), 3);
+    }
+
+    #[test]
+    fn peers_are_shuffled() {
+        let mut peers: Vec<u32> = (0..32).collect();
+        let mut rng = StdRng::seed_from_u64(0);
+        shuffle(&mut peers, &mut rng);
+        assert_ne!(peers, (0..32).collect::<Vec<_>>());
+        peers.sort_unstable();
+        assert_eq!(peers, (0..32).collect::<Vec<_>>());
+    }
+}
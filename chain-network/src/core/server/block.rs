@@ -1,4 +1,5 @@
 use super::PushStream;
+use crate::data::block::WindowUpdate;
 use crate::data::{Block, BlockEvent, BlockId, BlockIds, Header, Peer};
 use crate::error::Error;
 use async_trait::async_trait;
@@ -86,4 +87,19 @@ pub trait BlockService {
         subscriber: Peer,
         stream: PushStream<Header>,
     ) -> Result<Self::SubscriptionStream, Error>;
+
+    /// The type of outbound asynchronous streams returned by the
+    /// `header_subscription` method.
+    type HeaderSubscriptionStream: Stream<Item = Result<Header, Error>> + Send + Sync;
+
+    /// Called by the protocol implementation to establish a flow controlled
+    /// stream of the headers of new blocks.
+    /// The inbound stream carries the window updates of the subscriber,
+    /// see the [`header_subscription`](super::header_subscription()) function
+    /// for a stream applying them.
+    async fn header_subscription(
+        &self,
+        subscriber: Peer,
+        stream: PushStream<WindowUpdate>,
+    ) -> Result<Self::HeaderSubscriptionStream, Error>;
 }
@@ -0,0 +1,347 @@
+//! Header subscription stream with explicit flow control.
+//!
+//! The subscriber grants the server a window of headers it is ready to
+//! receive, and extends it with window updates as it processes them, sent
+//! over the inbound stream of the `HeaderSubscription` method. The server
+//! only yields headers while the window is open; the headers produced in the
+//! meantime are queued in a buffer of bounded size. When the buffer is full,
+//! the [`OverflowPolicy`] decides whether the subscription is terminated with
+//! a `ResourceExhausted` error, which is the default, or whether the oldest
+//! headers are dropped (the subscriber then has to fetch the missing ones
+//! with a pull request, as the latest header announces the tip).
+//!
+//! A slow subscriber thus costs the server at most `max_buffered` headers.
+
+use super::PushStream;
+use crate::data::block::WindowUpdate;
+use crate::data::Header;
+use crate::error::{Code, Error};
+use futures::stream::{FusedStream, Stream, TryStreamExt};
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// What to do with a new header when the buffer of a subscription is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Terminate the subscription with an error.
+    Terminate,
+    /// Drop the oldest buffered header to make room for the new one.
+    DropOldest,
+}
+
+/// Flow control parameters of a header subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionWindow {
+    /// The window granted to the subscriber before any update.
+    pub initial_window: u32,
+    /// The maximum number of headers waiting for the window to open, at
+    /// least one.
+    pub max_buffered: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for SubscriptionWindow {
+    fn default() -> Self {
+        SubscriptionWindow {
+            initial_window: 16,
+            max_buffered: 64,
+            overflow: OverflowPolicy::Terminate,
+        }
+    }
+}
+
+/// Error returned by [`HeaderSender::send`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SendError {
+    #[error("the header subscription is closed")]
+    Closed,
+    #[error("the header subscription buffer is full")]
+    Overflow,
+}
+
+#[derive(Debug)]
+struct State {
+    buffer: VecDeque<Header>,
+    window: u64,
+    dropped: u64,
+    overflowed: bool,
+    senders: usize,
+    receiver_alive: bool,
+    waker: Option<Waker>,
+}
+
+impl State {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    params: SubscriptionWindow,
+    state: Mutex<State>,
+}
+
+/// Create a flow controlled header subscription, returning the server side
+/// handle producing the headers and applying the window updates, and the
+/// outbound stream of headers.
+pub fn header_subscription(params: SubscriptionWindow) -> (HeaderSender, HeaderSubscription) {
+    let shared = Arc::new(Shared {
+        params,
+        state: Mutex::new(State {
+            buffer: VecDeque::new(),
+            window: params.initial_window.into(),
+            dropped: 0,
+            overflowed: false,
+            senders: 1,
+            receiver_alive: true,
+            waker: None,
+        }),
+    });
+    (
+        HeaderSender {
+            shared: shared.clone(),
+        },
+        HeaderSubscription {
+            shared,
+            terminated: false,
+        },
+    )
+}
+
+/// Server side handle of a header subscription.
+///
+/// The subscription stream ends once all the senders are dropped and the
+/// headers which the window allows have been yielded.
+#[derive(Debug)]
+pub struct HeaderSender {
+    shared: Arc<Shared>,
+}
+
+impl HeaderSender {
+    /// Queue a header for the subscriber.
+    ///
+    /// Fails if the subscription stream has been dropped, or if the buffer
+    /// is full and the overflow policy is to terminate the subscription.
+    pub fn send(&self, header: Header) -> Result<(), SendError> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.receiver_alive {
+            return Err(SendError::Closed);
+        }
+        if state.overflowed {
+            return Err(SendError::Overflow);
+        }
+        if state.buffer.len() >= self.shared.params.max_buffered.max(1) {
+            match self.shared.params.overflow {
+                OverflowPolicy::DropOldest => {
+                    state.buffer.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::Terminate => {
+                    state.overflowed = true;
+                    state.wake();
+                    return Err(SendError::Overflow);
+                }
+            }
+        }
+        state.buffer.push_back(header);
+        state.wake();
+        Ok(())
+    }
+
+    /// Apply a window update received from the subscriber.
+    pub fn grant(&self, update: WindowUpdate) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.window = state.window.saturating_add(update.0.into());
+        state.wake();
+    }
+
+    /// Apply the window updates received from the subscriber until its
+    /// stream ends, typically spawned along with the subscription.
+    pub async fn grant_from(&self, mut updates: PushStream<WindowUpdate>) -> Result<(), Error> {
+        while let Some(update) = updates.try_next().await? {
+            self.grant(update);
+        }
+        Ok(())
+    }
+
+    /// Number of headers waiting for the window to open.
+    pub fn buffered(&self) -> usize {
+        self.shared.state.lock().unwrap().buffer.len()
+    }
+
+    /// Number of headers the subscriber is currently ready to receive.
+    pub fn window(&self) -> u64 {
+        self.shared.state.lock().unwrap().window
+    }
+
+    /// Number of headers dropped because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.state.lock().unwrap().dropped
+    }
+
+    /// Whether the subscription stream has been dropped.
+    pub fn is_closed(&self) -> bool {
+        !self.shared.state.lock().unwrap().receiver_alive
+    }
+}
+
+impl Clone for HeaderSender {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        HeaderSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for HeaderSender {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            state.wake();
+        }
+    }
+}
+
+/// Outbound stream of a flow controlled header subscription, suitable as
+/// the `SubscriptionStream` of a service.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct HeaderSubscription {
+    shared: Arc<Shared>,
+    terminated: bool,
+}
+
+impl Stream for HeaderSubscription {
+    type Item = Result<Header, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
+        let mut state = self.shared.state.lock().unwrap();
+        if state.overflowed {
+            drop(state);
+            self.terminated = true;
+            return Poll::Ready(Some(Err(Error::new(
+                Code::ResourceExhausted,
+                "the subscriber did not open its window in time",
+            ))));
+        }
+        if state.window > 0 {
+            if let Some(header) = state.buffer.pop_front() {
+                state.window -= 1;
+                return Poll::Ready(Some(Ok(header)));
+            }
+        }
+        if state.senders == 0 && (state.buffer.is_empty() || state.window == 0) {
+            drop(state);
+            self.terminated = true;
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl FusedStream for HeaderSubscription {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl Drop for HeaderSubscription {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().receiver_alive = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::prelude::*;
+
+    fn header(n: u8) -> Header {
+        Header::from_bytes(vec![n])
+    }
+
+    fn params(initial_window: u32, overflow: OverflowPolicy) -> SubscriptionWindow {
+        SubscriptionWindow {
+            initial_window,
+            max_buffered: 2,
+            overflow,
+        }
+    }
+
+    fn next_byte(subscription: &mut HeaderSubscription) -> Option<u8> {
+        subscription
+            .next()
+            .now_or_never()
+            .map(|item| item.unwrap().unwrap().as_bytes()[0])
+    }
+
+    #[test]
+    fn headers_are_yielded_within_the_window() {
+        let (sender, mut subscription) = header_subscription(params(1, OverflowPolicy::DropOldest));
+        sender.send(header(1)).unwrap();
+        sender.send(header(2)).unwrap();
+        assert_eq!(next_byte(&mut subscription), Some(1));
+        assert_eq!(next_byte(&mut subscription), None);
+        assert_eq!(sender.buffered(), 1);
+
+        sender.grant(WindowUpdate(2));
+        assert_eq!(next_byte(&mut subscription), Some(2));
+        assert_eq!(sender.window(), 1);
+        drop(sender);
+        assert!(block_on(subscription.next()).is_none());
+        assert!(subscription.is_terminated());
+    }
+
+    #[test]
+    fn window_updates_are_applied_from_the_inbound_stream() {
+        let (sender, mut subscription) = header_subscription(params(0, OverflowPolicy::Terminate));
+        sender.send(header(1)).unwrap();
+        sender.send(header(2)).unwrap();
+        assert_eq!(next_byte(&mut subscription), None);
+
+        let updates = stream::iter(vec![Ok(WindowUpdate(1)), Ok(WindowUpdate(1))]).boxed();
+        block_on(sender.grant_from(updates)).unwrap();
+        assert_eq!(next_byte(&mut subscription), Some(1));
+        assert_eq!(next_byte(&mut subscription), Some(2));
+        assert_eq!(sender.window(), 0);
+    }
+
+    #[test]
+    fn oldest_headers_are_dropped_on_overflow() {
+        let (sender, subscription) = header_subscription(params(0, OverflowPolicy::DropOldest));
+        for n in 1..=4 {
+            sender.send(header(n)).unwrap();
+        }
+        assert_eq!(sender.dropped(), 2);
+        sender.grant(WindowUpdate(10));
+        drop(sender);
+        let headers: Vec<_> = block_on(subscription.map(|h| h.unwrap().into_bytes()).collect());
+        assert_eq!(headers, vec![vec![3], vec![4]]);
+    }
+
+    #[test]
+    fn subscription_is_terminated_on_overflow() {
+        let (sender, mut subscription) = header_subscription(params(0, OverflowPolicy::Terminate));
+        sender.send(header(1)).unwrap();
+        sender.send(header(2)).unwrap();
+        assert_eq!(sender.send(header(3)), Err(SendError::Overflow));
+        let error = block_on(subscription.next()).unwrap().unwrap_err();
+        assert_eq!(error.code(), Code::ResourceExhausted);
+        assert!(block_on(subscription.next()).is_none());
+        drop(subscription);
+        assert!(sender.is_closed());
+    }
+}
@@ -1,13 +1,23 @@
 mod block;
 mod fragment;
 mod gossip;
+mod header_subscription;
 mod node;
 mod push;
+#[cfg(feature = "storage")]
+mod storage;
 
 pub use block::BlockService;
 pub use fragment::FragmentService;
 pub use gossip::GossipService;
+pub use header_subscription::{
+    header_subscription, HeaderSender, HeaderSubscription, OverflowPolicy, SendError,
+    SubscriptionWindow,
+};
 
 pub use node::Node;
 
 pub use push::PushStream;
+
+#[cfg(feature = "storage")]
+pub use storage::{BlockFormat, BlockStream, StorageBlockService, DEFAULT_TIP_TAG};
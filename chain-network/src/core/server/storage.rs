@@ -0,0 +1,476 @@
+//! Reference implementation of [`BlockService`] backed by a
+//! [`chain_storage::BlockStore`].
+//!
+//! The storage deals with opaque blocks, so the chain-specific parts
+//! (extracting the header of a block and the identifiers it refers to) are
+//! delegated to a [`BlockFormat`] implementation.
+//!
+//! The storage calls are blocking: they are expected to be fast enough for
+//! the storage to be used from the async methods directly, as sled keeps the
+//! recently used data in memory.
+
+use super::{BlockService, PushStream};
+use crate::data::block::WindowUpdate;
+use crate::data::{Block, BlockEvent, BlockId, BlockIds, Header, Peer};
+use crate::error::{Code, Error};
+use async_trait::async_trait;
+use chain_storage::{BlockInfo, BlockStore, Error as StorageError, StorageIterator, Value};
+use futures::channel::mpsc;
+use futures::prelude::*;
+use futures::stream;
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// Default name of the storage tag pointing to the tip of the chain.
+pub const DEFAULT_TIP_TAG: &str = "tip";
+
+/// Chain-specific decoding needed to serve blocks from the storage.
+pub trait BlockFormat: Send + Sync + 'static {
+    /// Extracts the header of a serialized block.
+    fn header(&self, block: &Block) -> Result<Header, Error>;
+
+    /// Decodes the identifier of the block, the identifier of its parent and
+    /// its chain length from a serialized header.
+    ///
+    /// This is also the place to reject the headers and blocks pushed by the
+    /// peers that should not be accepted.
+    fn header_info(&self, header: &Header) -> Result<BlockInfo, Error>;
+}
+
+/// A [`BlockService`] serving the blocks of a [`BlockStore`].
+///
+/// * The tip is the block pointed to by a storage tag, [`DEFAULT_TIP_TAG`]
+///   unless set otherwise. The node remains in charge of moving the tag.
+/// * The blocks uploaded by the peers are added to the storage, once
+///   [`BlockFormat::header_info`] has accepted their headers.
+/// * The headers pushed by the peers are checked to form a chain starting
+///   from a stored block, and passed to the node through the channel given to
+///   [`with_header_sink`](Self::with_header_sink).
+/// * Block and header subscriptions depend on how the node announces new
+///   blocks and are not supported.
+pub struct StorageBlockService<F> {
+    store: BlockStore,
+    format: Arc<F>,
+    tip_tag: String,
+    header_sink: Option<mpsc::Sender<Header>>,
+}
+
+impl<F: BlockFormat> StorageBlockService<F> {
+    pub fn new(store: BlockStore, format: F) -> Self {
+        StorageBlockService {
+            store,
+            format: Arc::new(format),
+            tip_tag: DEFAULT_TIP_TAG.to_owned(),
+            header_sink: None,
+        }
+    }
+
+    /// Sets the name of the storage tag pointing to the tip of the chain.
+    pub fn with_tip_tag<S: Into<String>>(self, tip_tag: S) -> Self {
+        StorageBlockService {
+            tip_tag: tip_tag.into(),
+            ..self
+        }
+    }
+
+    /// Sets the channel receiving the headers pushed by the peers.
+    pub fn with_header_sink(self, sink: mpsc::Sender<Header>) -> Self {
+        StorageBlockService {
+            header_sink: Some(sink),
+            ..self
+        }
+    }
+
+    pub fn store(&self) -> &BlockStore {
+        &self.store
+    }
+
+    fn tip_id(&self) -> Result<Value, Error> {
+        self.store
+            .get_tag(&self.tip_tag)
+            .map_err(storage_error)?
+            .ok_or_else(|| Error::new(Code::Unavailable, "the tip of the chain is not set"))
+    }
+
+    fn get_block(&self, id: &[u8]) -> Result<Block, Error> {
+        self.store
+            .get_block(id)
+            .map(|block| Block::from_bytes(block.as_ref()))
+            .map_err(storage_error)
+    }
+
+    /// Iterates over the blocks following the closest of the checkpoints
+    /// that is an ancestor of `to`, up to `to` included.
+    fn blocks_range(&self, from: &[BlockId], to: &[u8]) -> Result<BlockStream, Error> {
+        let checkpoints: Vec<_> = from
+            .iter()
+            .map(|checkpoint| checkpoint.as_bytes())
+            .collect();
+        let blocks = self
+            .store
+            .iter_from_checkpoints(&checkpoints, to)
+            .map_err(storage_error)?;
+        Ok(BlockStream::new(blocks))
+    }
+
+    fn headers_range(&self, from: &[BlockId], to: &[u8]) -> Result<HeaderStream, Error> {
+        let format = self.format.clone();
+        Ok(self.blocks_range(from, to)?.map(Box::new(move |block| {
+            block.and_then(|block| format.header(&block))
+        })))
+    }
+}
+
+/// Converts a storage error to a protocol error.
+fn storage_error(e: StorageError) -> Error {
+    let code = match e {
+        StorageError::BlockNotFound
+        | StorageError::BranchNotFound
+        | StorageError::CheckpointNotFound => Code::NotFound,
+        StorageError::MissingParent => Code::FailedPrecondition,
+        StorageError::CannotIterate => Code::InvalidArgument,
+        _ => Code::Internal,
+    };
+    Error::new(code, e)
+}
+
+/// Blocks read from the storage as the stream is polled.
+pub struct BlockStream {
+    // the mutex is only there to make the stream `Sync`, it is never
+    // contended since polling requires a mutable reference
+    blocks: Mutex<StorageIterator>,
+}
+
+impl BlockStream {
+    fn new(blocks: StorageIterator) -> Self {
+        BlockStream {
+            blocks: Mutex::new(blocks),
+        }
+    }
+}
+
+impl Stream for BlockStream {
+    type Item = Result<Block, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let blocks = self
+            .get_mut()
+            .blocks
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        Poll::Ready(blocks.next().map(|block| {
+            block
+                .map(|block| Block::from_bytes(block.as_ref()))
+                .map_err(storage_error)
+        }))
+    }
+}
+
+type HeaderStream = stream::Map<
+    BlockStream,
+    Box<dyn FnMut(Result<Block, Error>) -> Result<Header, Error> + Send + Sync>,
+>;
+
+type ListStream<T> = stream::Iter<std::vec::IntoIter<Result<T, Error>>>;
+
+#[async_trait]
+impl<F: BlockFormat> BlockService for StorageBlockService<F> {
+    async fn tip(&self) -> Result<Header, Error> {
+        let block = self.get_block(self.tip_id()?.as_ref())?;
+        self.format.header(&block)
+    }
+
+    type GetBlocksStream = ListStream<Block>;
+
+    async fn get_blocks(&self, ids: BlockIds) -> Result<Self::GetBlocksStream, Error> {
+        let blocks: Vec<_> = ids.iter().map(|id| self.get_block(id.as_bytes())).collect();
+        Ok(stream::iter(blocks))
+    }
+
+    type GetHeadersStream = ListStream<Header>;
+
+    async fn get_headers(&self, ids: BlockIds) -> Result<Self::GetHeadersStream, Error> {
+        let headers: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                self.get_block(id.as_bytes())
+                    .and_then(|block| self.format.header(&block))
+            })
+            .collect();
+        Ok(stream::iter(headers))
+    }
+
+    type PullHeadersStream = HeaderStream;
+
+    async fn pull_headers(
+        &self,
+        from: BlockIds,
+        to: BlockId,
+    ) -> Result<Self::PullHeadersStream, Error> {
+        self.headers_range(&from, to.as_bytes())
+    }
+
+    type PullBlocksStream = BlockStream;
+
+    async fn pull_blocks(
+        &self,
+        from: BlockIds,
+        to: BlockId,
+    ) -> Result<Self::PullBlocksStream, Error> {
+        self.blocks_range(&from, to.as_bytes())
+    }
+
+    type PullBlocksToTipStream = BlockStream;
+
+    async fn pull_blocks_to_tip(
+        &self,
+        from: BlockIds,
+    ) -> Result<Self::PullBlocksToTipStream, Error> {
+        self.blocks_range(&from, self.tip_id()?.as_ref())
+    }
+
+    async fn push_headers(&self, mut stream: PushStream<Header>) -> Result<(), Error> {
+        let mut sink = match &self.header_sink {
+            Some(sink) => sink.clone(),
+            None => {
+                return Err(Error::new(
+                    Code::Unimplemented,
+                    "this node does not accept pushed headers",
+                ))
+            }
+        };
+        let mut previous: Option<BlockInfo> = None;
+        while let Some(header) = stream.try_next().await? {
+            let info = self.format.header_info(&header)?;
+            let connected = match &previous {
+                Some(previous) => {
+                    previous.id() == info.parent_id()
+                        && previous.chain_length() + 1 == info.chain_length()
+                }
+                None => self
+                    .store
+                    .block_exists(info.parent_id().as_ref())
+                    .map_err(storage_error)?,
+            };
+            if !connected {
+                return Err(Error::new(
+                    Code::InvalidArgument,
+                    "the pushed headers do not form a chain from a stored block",
+                ));
+            }
+            sink.send(header)
+                .await
+                .map_err(|e| Error::new(Code::Aborted, e))?;
+            previous = Some(info);
+        }
+        Ok(())
+    }
+
+    async fn upload_blocks(&self, mut stream: PushStream<Block>) -> Result<(), Error> {
+        while let Some(block) = stream.try_next().await? {
+            let header = self.format.header(&block)?;
+            let info = self.format.header_info(&header)?;
+            match self.store.put_block(block.as_bytes(), info) {
+                Ok(()) | Err(StorageError::BlockAlreadyPresent) => {}
+                Err(e) => return Err(storage_error(e)),
+            }
+        }
+        Ok(())
+    }
+
+    type SubscriptionStream = stream::Empty<Result<BlockEvent, Error>>;
+
+    async fn block_subscription(
+        &self,
+        _subscriber: Peer,
+        _stream: PushStream<Header>,
+    ) -> Result<Self::SubscriptionStream, Error> {
+        Err(Error::new(
+            Code::Unimplemented,
+            "block subscriptions are not supported by the storage block service",
+        ))
+    }
+
+    type HeaderSubscriptionStream = stream::Empty<Result<Header, Error>>;
+
+    async fn header_subscription(
+        &self,
+        _subscriber: Peer,
+        _stream: PushStream<WindowUpdate>,
+    ) -> Result<Self::HeaderSubscriptionStream, Error> {
+        Err(Error::new(
+            Code::Unimplemented,
+            "header subscriptions are not supported by the storage block service",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::convert::{TryFrom, TryInto};
+
+    const ID_SIZE: usize = 32;
+    const HEADER_SIZE: usize = 2 * ID_SIZE + 4;
+    const ROOT_ID: [u8; ID_SIZE] = [0; ID_SIZE];
+
+    /// Blocks made of the block id, the parent id and the big-endian chain
+    /// length, followed by an arbitrary payload.
+    struct TestFormat;
+
+    impl BlockFormat for TestFormat {
+        fn header(&self, block: &Block) -> Result<Header, Error> {
+            let bytes = block.as_bytes();
+            if bytes.len() < HEADER_SIZE {
+                return Err(Error::new(Code::InvalidArgument, "block is too short"));
+            }
+            Ok(Header::from_bytes(&bytes[..HEADER_SIZE]))
+        }
+
+        fn header_info(&self, header: &Header) -> Result<BlockInfo, Error> {
+            let bytes = header.as_bytes();
+            if bytes.len() != HEADER_SIZE {
+                return Err(Error::new(Code::InvalidArgument, "invalid header size"));
+            }
+            let chain_length = u32::from_be_bytes(bytes[2 * ID_SIZE..].try_into().unwrap());
+            Ok(BlockInfo::new(
+                bytes[..ID_SIZE].to_vec(),
+                bytes[ID_SIZE..2 * ID_SIZE].to_vec(),
+                chain_length,
+            ))
+        }
+    }
+
+    fn block_id(n: u8) -> [u8; ID_SIZE] {
+        [n; ID_SIZE]
+    }
+
+    fn make_block(id: u8, parent: u8, chain_length: u32) -> Block {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&block_id(id));
+        bytes.extend_from_slice(&block_id(parent));
+        bytes.extend_from_slice(&chain_length.to_be_bytes());
+        bytes.extend_from_slice(b"payload");
+        Block::from_bytes(bytes)
+    }
+
+    fn ids(ids: &[u8]) -> BlockIds {
+        ids.iter()
+            .map(|id| BlockId::try_from(&block_id(*id)[..]).unwrap())
+            .collect()
+    }
+
+    /// A chain of blocks 1 to 5 with the tip set to 5.
+    fn service() -> StorageBlockService<TestFormat> {
+        let store = BlockStore::memory(ROOT_ID.to_vec()).unwrap();
+        for n in 1..=5u8 {
+            let block = make_block(n, n - 1, n as u32);
+            let info = TestFormat
+                .header_info(&TestFormat.header(&block).unwrap())
+                .unwrap();
+            store.put_block(block.as_bytes(), info).unwrap();
+        }
+        store.put_tag(DEFAULT_TIP_TAG, &block_id(5)).unwrap();
+        StorageBlockService::new(store, TestFormat)
+    }
+
+    fn chain_lengths<S>(stream: S) -> Vec<u32>
+    where
+        S: Stream<Item = Result<Block, Error>>,
+    {
+        block_on(
+            stream
+                .map_ok(|block| block.as_bytes()[2 * ID_SIZE + 3] as u32)
+                .try_collect(),
+        )
+        .unwrap()
+    }
+
+    fn push<T: Send + 'static>(items: Vec<T>) -> PushStream<T> {
+        stream::iter(items.into_iter().map(Ok)).boxed()
+    }
+
+    #[test]
+    fn tip_and_get_blocks() {
+        let service = service();
+        let tip = block_on(service.tip()).unwrap();
+        assert_eq!(
+            tip.as_bytes(),
+            &make_block(5, 4, 5).as_bytes()[..HEADER_SIZE]
+        );
+
+        let blocks = block_on(service.get_blocks(ids(&[2, 4]))).unwrap();
+        assert_eq!(chain_lengths(blocks), vec![2, 4]);
+
+        let mut headers = block_on(service.get_headers(ids(&[9]))).unwrap();
+        let error = block_on(headers.next()).unwrap().unwrap_err();
+        assert_eq!(error.code(), Code::NotFound);
+    }
+
+    #[test]
+    fn pull_from_closest_checkpoint() {
+        let service = service();
+        let blocks = block_on(service.pull_blocks(ids(&[1, 3, 9]), ids(&[4])[0])).unwrap();
+        assert_eq!(chain_lengths(blocks), vec![4]);
+
+        let blocks = block_on(service.pull_blocks_to_tip(ids(&[2]))).unwrap();
+        assert_eq!(chain_lengths(blocks), vec![3, 4, 5]);
+
+        let blocks = block_on(service.pull_blocks_to_tip(ids(&[0]))).unwrap();
+        assert_eq!(chain_lengths(blocks), vec![1, 2, 3, 4, 5]);
+
+        let headers = block_on(service.pull_headers(ids(&[3]), ids(&[5])[0])).unwrap();
+        let headers: Vec<Header> = block_on(headers.try_collect()).unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[1].as_bytes().len(), HEADER_SIZE);
+
+        let error = block_on(service.pull_blocks_to_tip(ids(&[9])))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), Code::NotFound);
+    }
+
+    #[test]
+    fn upload_blocks() {
+        let service = service();
+        let blocks = vec![make_block(5, 4, 5), make_block(6, 5, 6)];
+        block_on(service.upload_blocks(push(blocks))).unwrap();
+        assert!(service.store().block_exists(&block_id(6)).unwrap());
+
+        let error = block_on(service.upload_blocks(push(vec![make_block(8, 7, 8)])))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn push_headers() {
+        let header = |id, parent, chain_length| {
+            TestFormat
+                .header(&make_block(id, parent, chain_length))
+                .unwrap()
+        };
+
+        let error = block_on(service().push_headers(push(vec![header(6, 5, 6)])))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), Code::Unimplemented);
+
+        let (sink, received) = mpsc::channel(10);
+        let service = service().with_header_sink(sink);
+        block_on(service.push_headers(push(vec![header(6, 5, 6), header(7, 6, 7)]))).unwrap();
+
+        let error = block_on(service.push_headers(push(vec![header(6, 5, 6), header(8, 7, 8)])))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), Code::InvalidArgument);
+
+        drop(service);
+        let received: Vec<Header> = block_on(received.collect());
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[1].as_bytes(), header(7, 6, 7).as_bytes());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::error::{Code, Error};
+use chain_crypto::{digest::Context, Blake2b256};
+
+use std::convert::TryFrom;
+use std::fmt;
+
+const CHECKSUM_LEN: usize = 32;
+
+/// Blake2b-256 checksum of a block sent in a stream, or digest of
+/// a whole block stream.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Checksum([u8; CHECKSUM_LEN]);
+
+impl fmt::Debug for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Checksum(0x")?;
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl Checksum {
+    /// Computes the checksum of the serialized content of a block.
+    pub fn compute(content: &[u8]) -> Self {
+        Checksum(*Blake2b256::new(content).as_hash_bytes())
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Checksum {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl TryFrom<&[u8]> for Checksum {
+    type Error = Error;
+
+    fn try_from(src: &[u8]) -> Result<Self, Error> {
+        match TryFrom::try_from(src) {
+            Ok(data) => Ok(Checksum(data)),
+            Err(_) => Err(Error::new(
+                Code::InvalidArgument,
+                format!("checksum must be {} bytes long", CHECKSUM_LEN),
+            )),
+        }
+    }
+}
+
+/// Accumulates the checksums of the blocks of a stream to compute
+/// the end-of-stream digest.
+///
+/// The digest is the Blake2b-256 hash of the checksums of all the blocks,
+/// in the order of the stream, followed by the number of blocks as
+/// a big-endian 64-bit integer.
+#[derive(Clone, Default)]
+pub struct StreamDigest {
+    context: Context<Blake2b256>,
+    count: u64,
+}
+
+impl StreamDigest {
+    pub fn new() -> Self {
+        StreamDigest::default()
+    }
+
+    /// Adds the checksum of the next block of the stream.
+    pub fn update(&mut self, checksum: &Checksum) {
+        self.context.append_data(checksum.as_bytes());
+        self.count += 1;
+    }
+
+    /// Number of blocks accumulated so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the digest of the blocks accumulated so far.
+    pub fn finalize(&self) -> Checksum {
+        let mut context = self.context.clone();
+        context.append_data(&self.count.to_be_bytes());
+        Checksum(context.finalize().into())
+    }
+}
+
+/// Integrity failure detected in a block stream.
+#[derive(Debug, thiserror::Error)]
+pub enum ChecksumError {
+    #[error("checksum mismatch for block {index} of the stream")]
+    BlockMismatch { index: u64 },
+    #[error("missing checksum for block {index} of the stream")]
+    MissingChecksum { index: u64 },
+    #[error("block stream digest mismatch")]
+    DigestMismatch,
+    #[error("block stream ended without a digest, it may have been truncated")]
+    Truncated,
+    #[error("block received after the end of stream digest")]
+    TrailingBlock,
+}
+
+impl From<ChecksumError> for Error {
+    fn from(e: ChecksumError) -> Self {
+        Error::new(Code::InvalidArgument, e)
+    }
+}
+
+/// Verifies the blocks received in a stream before they get deserialized.
+///
+/// Peers not supporting checksums send neither block checksums nor a stream
+/// digest, in which case nothing is verified. Once a checksum has been
+/// received, every following block must have one and the stream must be
+/// terminated by a digest, so truncated streams are detected. An empty
+/// stream is accepted as such.
+#[derive(Clone, Default)]
+pub struct StreamVerifier {
+    digest: StreamDigest,
+    checksummed: bool,
+    finished: bool,
+}
+
+impl StreamVerifier {
+    pub fn new() -> Self {
+        StreamVerifier::default()
+    }
+
+    /// Checks the next block of the stream, given the checksum and the
+    /// end-of-stream digest sent with it, if any.
+    pub fn verify_block(
+        &mut self,
+        content: &[u8],
+        checksum: Option<&Checksum>,
+        stream_digest: Option<&Checksum>,
+    ) -> Result<(), ChecksumError> {
+        let index = self.digest.count();
+        if self.finished {
+            return Err(ChecksumError::TrailingBlock);
+        }
+        match checksum {
+            Some(checksum) => {
+                if Checksum::compute(content) != *checksum {
+                    return Err(ChecksumError::BlockMismatch { index });
+                }
+                self.checksummed = true;
+                self.digest.update(checksum);
+            }
+            None if self.checksummed || stream_digest.is_some() => {
+                return Err(ChecksumError::MissingChecksum { index });
+            }
+            None => {}
+        }
+        if let Some(stream_digest) = stream_digest {
+            if self.digest.finalize() != *stream_digest {
+                return Err(ChecksumError::DigestMismatch);
+            }
+            self.finished = true;
+        }
+        Ok(())
+    }
+
+    /// Checks that the stream has been properly terminated,
+    /// to be called once the stream has ended.
+    pub fn finish(&self) -> Result<(), ChecksumError> {
+        if self.checksummed && !self.finished {
+            Err(ChecksumError::Truncated)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checksummed_stream(blocks: &[&[u8]]) -> Vec<(Checksum, Option<Checksum>)> {
+        let mut digest = StreamDigest::new();
+        let mut stream: Vec<_> = blocks
+            .iter()
+            .map(|block| {
+                let checksum = Checksum::compute(block);
+                digest.update(&checksum);
+                (checksum, None)
+            })
+            .collect();
+        if let Some(last) = stream.last_mut() {
+            last.1 = Some(digest.finalize());
+        }
+        stream
+    }
+
+    fn verify(
+        blocks: &[&[u8]],
+        stream: &[(Checksum, Option<Checksum>)],
+    ) -> Result<(), ChecksumError> {
+        let mut verifier = StreamVerifier::new();
+        for (block, (checksum, digest)) in blocks.iter().zip(stream) {
+            verifier.verify_block(block, Some(checksum), digest.as_ref())?;
+        }
+        verifier.finish()
+    }
+
+    const BLOCKS: &[&[u8]] = &[b"block 0", b"block 1", b"block 2"];
+
+    #[test]
+    fn complete_stream_verifies() {
+        let stream = checksummed_stream(BLOCKS);
+        verify(BLOCKS, &stream).unwrap();
+    }
+
+    #[test]
+    fn corrupted_block_is_detected() {
+        let stream = checksummed_stream(BLOCKS);
+        let corrupted: &[&[u8]] = &[b"block 0", b"block X", b"block 2"];
+        match verify(corrupted, &stream) {
+            Err(ChecksumError::BlockMismatch { index: 1 }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn truncated_stream_is_detected() {
+        let stream = checksummed_stream(BLOCKS);
+        match verify(&BLOCKS[..2], &stream[..2]) {
+            Err(ChecksumError::Truncated) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn reordered_stream_is_detected() {
+        let mut stream = checksummed_stream(BLOCKS);
+        stream.swap(0, 1);
+        let reordered: &[&[u8]] = &[b"block 1", b"block 0", b"block 2"];
+        match verify(reordered, &stream) {
+            Err(ChecksumError::DigestMismatch) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn stream_without_checksums_is_accepted() {
+        let mut verifier = StreamVerifier::new();
+        for block in BLOCKS {
+            verifier.verify_block(block, None, None).unwrap();
+        }
+        verifier.finish().unwrap();
+    }
+}
@@ -1,10 +1,12 @@
 #[allow(clippy::module_inception)]
 mod block;
+mod checksum;
 mod header;
 mod id;
 mod subscription;
 
 pub use block::Block;
+pub use checksum::{Checksum, ChecksumError, StreamDigest, StreamVerifier};
 pub use header::Header;
 pub use id::{try_ids_from_iter, BlockId, BlockIds};
-pub use subscription::{BlockEvent, ChainPullRequest};
+pub use subscription::{BlockEvent, ChainPullRequest, WindowUpdate};
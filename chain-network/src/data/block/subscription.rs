@@ -11,6 +11,11 @@ pub enum BlockEvent {
     Missing(ChainPullRequest),
 }
 
+/// Credit granted by the subscriber of a header subscription: the number of
+/// additional headers it is ready to receive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowUpdate(pub u32);
+
 /// A request to send headers in the block chain sequence.
 #[derive(Debug)]
 pub struct ChainPullRequest {
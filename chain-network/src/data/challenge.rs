@@ -0,0 +1,209 @@
+//! Challenge-response authentication of the node IDs.
+//!
+//! Each side of a connection proves the ownership of its node ID by signing
+//! a nonce chosen by the other side: the client sends its nonce in the
+//! handshake request and the server answers it in the handshake response,
+//! along with a nonce of its own that the client answers in the client
+//! authentication request.
+//!
+//! [`Challenges`] issues the nonces of a node and checks the answers of its
+//! peers. A nonce is only accepted once, from the peer it was issued to and
+//! before it expires, so that an answer observed on a connection cannot be
+//! replayed on another one. The node IDs proven by the peers are kept, to
+//! check the node IDs they advertise, e.g. in the gossip.
+
+use super::p2p::{AuthenticatedNodeId, NodeId, Peer};
+use crate::error::{Code, Error};
+
+use rand_core::{CryptoRng, RngCore};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Length of the nonces issued by [`Challenges`].
+pub const NONCE_LEN: usize = 32;
+
+/// A random nonce to be signed by a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nonce([u8; NONCE_LEN]);
+
+impl Nonce {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut nonce = [0; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+        Nonce(nonce)
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Rejection of the answer of a peer to a challenge.
+#[derive(Debug, thiserror::Error)]
+pub enum ChallengeError {
+    #[error("no challenge is pending for the peer")]
+    NotChallenged,
+    #[error("the challenge expired {0:?} ago")]
+    Expired(Duration),
+    #[error("invalid answer to the challenge")]
+    InvalidSignature(#[source] Error),
+}
+
+impl From<ChallengeError> for Error {
+    fn from(e: ChallengeError) -> Self {
+        let code = match e {
+            ChallengeError::NotChallenged => Code::FailedPrecondition,
+            ChallengeError::Expired(_) | ChallengeError::InvalidSignature(_) => {
+                Code::InvalidArgument
+            }
+        };
+        Error::new(code, e)
+    }
+}
+
+/// The pending challenges of a node and the node IDs its peers proved.
+#[derive(Debug)]
+pub struct Challenges {
+    ttl: Duration,
+    pending: HashMap<Peer, (Nonce, Instant)>,
+    proven: HashMap<Peer, NodeId>,
+}
+
+impl Challenges {
+    /// The answers to the challenges are accepted for `ttl` after their
+    /// issuance.
+    pub fn new(ttl: Duration) -> Self {
+        Challenges {
+            ttl,
+            pending: HashMap::new(),
+            proven: HashMap::new(),
+        }
+    }
+
+    /// Issue a new challenge to the peer, replacing the pending one, if any.
+    pub fn issue<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        peer: Peer,
+        now: Instant,
+    ) -> Nonce {
+        let nonce = Nonce::generate(rng);
+        self.pending.insert(peer, (nonce, now));
+        nonce
+    }
+
+    /// Check the answer of the peer to its pending challenge and record the
+    /// proven node ID. The challenge is consumed whether the answer is
+    /// accepted or not.
+    pub fn verify(
+        &mut self,
+        peer: &Peer,
+        auth: &AuthenticatedNodeId,
+        now: Instant,
+    ) -> Result<NodeId, ChallengeError> {
+        let (nonce, issued) = self
+            .pending
+            .remove(peer)
+            .ok_or(ChallengeError::NotChallenged)?;
+        let age = now.saturating_duration_since(issued);
+        if age > self.ttl {
+            return Err(ChallengeError::Expired(age - self.ttl));
+        }
+        auth.verify(nonce.as_bytes())
+            .map_err(ChallengeError::InvalidSignature)?;
+        self.proven.insert(peer.clone(), auth.id().clone());
+        Ok(auth.id().clone())
+    }
+
+    /// The node ID proven by the peer, if any.
+    pub fn proven_id(&self, peer: &Peer) -> Option<&NodeId> {
+        self.proven.get(peer)
+    }
+
+    /// Whether the peer proved the ownership of the node ID.
+    pub fn is_proven(&self, peer: &Peer, id: &NodeId) -> bool {
+        self.proven_id(peer) == Some(id)
+    }
+
+    /// Forget the challenge and the proven node ID of a disconnected peer.
+    pub fn forget(&mut self, peer: &Peer) {
+        self.pending.remove(peer);
+        self.proven.remove(peer);
+    }
+
+    /// Drop the challenges which can no longer be answered.
+    pub fn remove_expired(&mut self, now: Instant) {
+        let ttl = self.ttl;
+        self.pending
+            .retain(|_, (_, issued)| now.saturating_duration_since(*issued) <= ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::p2p::NodeKeyPair;
+    use rand::rngs::OsRng;
+
+    fn peer(port: u16) -> Peer {
+        Peer::from(std::net::SocketAddr::from(([192, 0, 2, 1], port)))
+    }
+
+    #[test]
+    fn answers_are_accepted_once_from_the_challenged_peer() {
+        let mut challenges = Challenges::new(Duration::from_secs(10));
+        let keys = NodeKeyPair::generate(OsRng);
+        let (alice, bob) = (peer(3000), peer(3001));
+        let now = Instant::now();
+
+        assert!(matches!(
+            challenges.verify(&alice, &keys.sign(&[0; NONCE_LEN]), now),
+            Err(ChallengeError::NotChallenged)
+        ));
+
+        let nonce = challenges.issue(&mut OsRng, alice.clone(), now);
+        let answer = keys.sign(nonce.as_bytes());
+        // the answer of another peer, or to another nonce
+        challenges.issue(&mut OsRng, bob.clone(), now);
+        assert!(matches!(
+            challenges.verify(&bob, &answer, now),
+            Err(ChallengeError::InvalidSignature(_))
+        ));
+
+        let id = challenges.verify(&alice, &answer, now).unwrap();
+        assert!(challenges.is_proven(&alice, &id));
+        assert!(!challenges.is_proven(&bob, &id));
+        // replayed
+        assert!(matches!(
+            challenges.verify(&alice, &answer, now),
+            Err(ChallengeError::NotChallenged)
+        ));
+
+        challenges.forget(&alice);
+        assert_eq!(challenges.proven_id(&alice), None);
+    }
+
+    #[test]
+    fn expired_challenges_are_rejected() {
+        let ttl = Duration::from_secs(10);
+        let mut challenges = Challenges::new(ttl);
+        let keys = NodeKeyPair::generate(OsRng);
+        let (alice, bob) = (peer(3000), peer(3001));
+        let now = Instant::now();
+
+        let nonce = challenges.issue(&mut OsRng, alice.clone(), now);
+        assert!(matches!(
+            challenges.verify(&alice, &keys.sign(nonce.as_bytes()), now + ttl * 2),
+            Err(ChallengeError::Expired(age)) if age == ttl
+        ));
+
+        challenges.issue(&mut OsRng, bob.clone(), now);
+        challenges.remove_expired(now + ttl * 2);
+        assert!(matches!(
+            challenges.verify(&bob, &keys.sign(&[0; NONCE_LEN]), now),
+            Err(ChallengeError::NotChallenged)
+        ));
+    }
+}
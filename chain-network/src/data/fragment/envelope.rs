@@ -0,0 +1,204 @@
+use super::Fragment;
+use crate::data::p2p::{NodeId, NodeKeyPair};
+use crate::error::{Code, Error};
+use chain_crypto::{Ed25519, PublicKey, Signature, Verification};
+
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SIGNING_DOMAIN: &[u8] = b"chain-network fragment envelope";
+const NODE_ID_LEN: usize = 32;
+const TIMESTAMP_LEN: usize = 8;
+const SIGNATURE_LEN: usize = 64;
+
+/// A fragment signed by the node relaying it, with the time it was relayed.
+///
+/// The envelope is not part of the consensus: it only lets the receiving
+/// node attribute the fragments to the peer which sent them, to throttle
+/// relays spamming invalid fragments.
+#[derive(Clone)]
+pub struct FragmentEnvelope {
+    fragment: Fragment,
+    relay: NodeId,
+    timestamp: u64,
+    signature: Signature<Vec<u8>, Ed25519>,
+}
+
+fn signing_data(fragment: &Fragment, timestamp: u64) -> Vec<u8> {
+    let mut data =
+        Vec::with_capacity(SIGNING_DOMAIN.len() + TIMESTAMP_LEN + fragment.as_bytes().len());
+    data.extend_from_slice(SIGNING_DOMAIN);
+    data.extend_from_slice(&timestamp.to_be_bytes());
+    data.extend_from_slice(fragment.as_bytes());
+    data
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl NodeKeyPair {
+    /// Wraps the fragment in an envelope signed by this node, stamped with
+    /// the given time.
+    pub fn sign_fragment(&self, fragment: Fragment, time: SystemTime) -> FragmentEnvelope {
+        let timestamp = unix_secs(time);
+        let signature = self
+            .0
+            .private_key()
+            .sign(&signing_data(&fragment, timestamp));
+        FragmentEnvelope {
+            fragment,
+            relay: NodeId(self.0.public_key().clone()),
+            timestamp,
+            signature,
+        }
+    }
+}
+
+impl FragmentEnvelope {
+    /// The node which relayed the fragment, as claimed by the envelope.
+    ///
+    /// Only use it for attribution once the envelope has been verified.
+    pub fn relay(&self) -> &NodeId {
+        &self.relay
+    }
+
+    pub fn fragment(&self) -> &Fragment {
+        &self.fragment
+    }
+
+    /// Time at which the relay signed the envelope
+    pub fn timestamp(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.timestamp)
+    }
+
+    pub fn into_fragment(self) -> Fragment {
+        self.fragment
+    }
+
+    /// Verifies that the envelope has been signed by the relay.
+    pub fn verify(&self) -> Result<(), Error> {
+        let data = signing_data(&self.fragment, self.timestamp);
+        match self.signature.verify(&self.relay.0, &data) {
+            Verification::Success => Ok(()),
+            Verification::Failed => Err(Error::new(
+                Code::InvalidArgument,
+                "invalid fragment envelope signature",
+            )),
+        }
+    }
+
+    /// Verifies the signature of the envelope and that it has been stamped
+    /// within `tolerance` of `now`, so that old envelopes cannot be replayed
+    /// to blame a relay.
+    pub fn verify_at(&self, now: SystemTime, tolerance: Duration) -> Result<(), Error> {
+        self.verify()?;
+        let now = unix_secs(now);
+        if now.max(self.timestamp) - now.min(self.timestamp) > tolerance.as_secs() {
+            return Err(Error::new(
+                Code::InvalidArgument,
+                "fragment envelope timestamp is out of the tolerated range",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Serializes the envelope as the relay node ID, the timestamp in
+    /// seconds since the Unix epoch, the signature and the fragment bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fragment = self.fragment.as_bytes();
+        let mut bytes =
+            Vec::with_capacity(NODE_ID_LEN + TIMESTAMP_LEN + SIGNATURE_LEN + fragment.len());
+        bytes.extend_from_slice(self.relay.as_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(self.signature.as_ref());
+        bytes.extend_from_slice(fragment);
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for FragmentEnvelope {
+    type Error = Error;
+
+    /// Decodes an envelope serialized with `to_bytes`.
+    ///
+    /// The signature is not verified.
+    fn try_from(src: &[u8]) -> Result<Self, Error> {
+        if src.len() < NODE_ID_LEN + TIMESTAMP_LEN + SIGNATURE_LEN {
+            return Err(Error::new(
+                Code::InvalidArgument,
+                "fragment envelope is too short",
+            ));
+        }
+        let (relay, rest) = src.split_at(NODE_ID_LEN);
+        let (timestamp, rest) = rest.split_at(TIMESTAMP_LEN);
+        let (signature, fragment) = rest.split_at(SIGNATURE_LEN);
+
+        let relay = PublicKey::from_binary(relay)
+            .map(NodeId)
+            .map_err(|e| Error::new(Code::InvalidArgument, e))?;
+        let mut timestamp_bytes = [0; TIMESTAMP_LEN];
+        timestamp_bytes.copy_from_slice(timestamp);
+        let signature =
+            Signature::from_binary(signature).map_err(|e| Error::new(Code::InvalidArgument, e))?;
+        Ok(FragmentEnvelope {
+            fragment: Fragment::from_bytes(fragment),
+            relay,
+            timestamp: u64::from_be_bytes(timestamp_bytes),
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn envelope(time: SystemTime) -> FragmentEnvelope {
+        let key_pair = NodeKeyPair::generate(OsRng);
+        key_pair.sign_fragment(Fragment::from_bytes(vec![1, 2, 3, 4]), time)
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let now = SystemTime::now();
+        let envelope = envelope(now);
+        envelope.verify().unwrap();
+        envelope.verify_at(now, Duration::from_secs(10)).unwrap();
+        envelope
+            .verify_at(now + Duration::from_secs(5), Duration::from_secs(10))
+            .unwrap();
+        assert!(envelope
+            .verify_at(now + Duration::from_secs(60), Duration::from_secs(10))
+            .is_err());
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let envelope = envelope(SystemTime::now());
+        let bytes = envelope.to_bytes();
+        let decoded = FragmentEnvelope::try_from(&bytes[..]).unwrap();
+        assert_eq!(decoded.relay(), envelope.relay());
+        assert_eq!(decoded.timestamp(), envelope.timestamp());
+        assert_eq!(decoded.fragment().as_bytes(), &[1, 2, 3, 4]);
+        decoded.verify().unwrap();
+    }
+
+    #[test]
+    fn tampered_envelope_is_rejected() {
+        let envelope = envelope(SystemTime::now());
+        let mut bytes = envelope.to_bytes();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = FragmentEnvelope::try_from(&bytes[..]).unwrap();
+        assert!(tampered.verify().is_err());
+
+        let other = self::envelope(SystemTime::now());
+        let mut bytes = other.relay().as_bytes().to_vec();
+        bytes.extend_from_slice(&envelope.to_bytes()[NODE_ID_LEN..]);
+        let forged = FragmentEnvelope::try_from(&bytes[..]).unwrap();
+        assert!(forged.verify().is_err());
+    }
+}
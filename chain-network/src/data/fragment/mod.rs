@@ -1,6 +1,10 @@
+mod envelope;
 #[allow(clippy::module_inception)]
 mod fragment;
 mod id;
+mod propagation;
 
+pub use envelope::FragmentEnvelope;
 pub use fragment::Fragment;
 pub use id::{try_ids_from_iter, FragmentId, FragmentIds};
+pub use propagation::{PeerPropagation, PropagationScore, PropagationTracker, Received};
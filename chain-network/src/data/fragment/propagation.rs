@@ -0,0 +1,236 @@
+use super::FragmentId;
+use crate::data::p2p::NodeId;
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Outcome of the reception of a fragment from a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Received {
+    /// The fragment had not been seen before, it should be propagated.
+    New,
+    /// The fragment had already been seen, `latency` after its first sighting.
+    Duplicate { latency: Duration },
+}
+
+/// Fragment propagation statistics of a peer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerPropagation {
+    first_seen: u64,
+    duplicates: u64,
+    duplicates_latency: Duration,
+}
+
+impl PeerPropagation {
+    /// Number of fragments first received from this peer
+    pub fn first_seen(&self) -> u64 {
+        self.first_seen
+    }
+
+    /// Number of fragments received from this peer which had already been
+    /// received from another peer
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Average delay between the first sighting of the duplicate fragments
+    /// and their reception from this peer
+    pub fn mean_duplicate_latency(&self) -> Duration {
+        if self.duplicates == 0 {
+            Duration::default()
+        } else {
+            let nanos = self.duplicates_latency.as_nanos() / self.duplicates as u128;
+            Duration::from_nanos(nanos as u64)
+        }
+    }
+
+    /// The share of the fragments this peer was the first to relay, lowered
+    /// by the latency of the duplicates it relays.
+    pub fn score(&self) -> PropagationScore {
+        let received = self.first_seen + self.duplicates;
+        if received == 0 {
+            return PropagationScore::NEUTRAL;
+        }
+        let novelty = self.first_seen as f64 / received as f64;
+        let latency = self.mean_duplicate_latency().as_secs_f64();
+        PropagationScore(novelty / (1.0 + latency))
+    }
+}
+
+/// Propagation score of a peer, between 0 and 1, the higher the better.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct PropagationScore(f64);
+
+impl PropagationScore {
+    /// Score of the peers which have not relayed any fragment yet, so that
+    /// new peers get a chance to be selected.
+    pub const NEUTRAL: Self = PropagationScore(0.5);
+
+    #[inline]
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+/// Deduplicates the fragments received by gossip and tracks the propagation
+/// statistics of the peers which relayed them.
+///
+/// Only the `capacity` most recent fragments are remembered; a fragment
+/// received again after being forgotten is counted as new.
+pub struct PropagationTracker {
+    capacity: usize,
+    seen: HashMap<FragmentId, Instant>,
+    // order of first sighting, the oldest fragments are forgotten first
+    order: VecDeque<FragmentId>,
+    peers: HashMap<NodeId, PeerPropagation>,
+}
+
+impl PropagationTracker {
+    /// Creates a tracker remembering at most `capacity` fragments.
+    pub fn new(capacity: usize) -> Self {
+        PropagationTracker {
+            capacity,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Records the reception of a fragment from `peer` at time `now`.
+    pub fn record(&mut self, peer: &NodeId, fragment: &FragmentId, now: Instant) -> Received {
+        let stats = self.peers.entry(peer.clone()).or_default();
+        if let Some(first_seen) = self.seen.get(fragment) {
+            let latency = now.saturating_duration_since(*first_seen);
+            stats.duplicates += 1;
+            stats.duplicates_latency += latency;
+            return Received::Duplicate { latency };
+        }
+
+        stats.first_seen += 1;
+        if self.capacity > 0 {
+            self.seen.insert(*fragment, now);
+            self.order.push_back(*fragment);
+            while self.order.len() > self.capacity {
+                let oldest = self.order.pop_front().unwrap();
+                self.seen.remove(&oldest);
+            }
+        }
+        Received::New
+    }
+
+    /// Whether the fragment has been received recently.
+    pub fn is_known(&self, fragment: &FragmentId) -> bool {
+        self.seen.contains_key(fragment)
+    }
+
+    pub fn peer(&self, peer: &NodeId) -> Option<&PeerPropagation> {
+        self.peers.get(peer)
+    }
+
+    /// Score of the peer, neutral if it has not relayed any fragment.
+    pub fn score(&self, peer: &NodeId) -> PropagationScore {
+        self.peers
+            .get(peer)
+            .map_or(PropagationScore::NEUTRAL, PeerPropagation::score)
+    }
+
+    /// Forgets the statistics of a peer, e.g. once disconnected.
+    pub fn remove_peer(&mut self, peer: &NodeId) {
+        self.peers.remove(peer);
+    }
+
+    /// Selects the `count` best scored peers among the candidates, best
+    /// first, to gossip the fragments to.
+    pub fn select_targets<'a, I>(&self, candidates: I, count: usize) -> Vec<NodeId>
+    where
+        I: IntoIterator<Item = &'a NodeId>,
+    {
+        let mut scored: Vec<_> = candidates
+            .into_iter()
+            .map(|peer| (self.score(peer), peer))
+            .collect();
+        // stable sort, candidates with the same score keep their order
+        scored.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        scored
+            .into_iter()
+            .take(count)
+            .map(|(_, peer)| peer.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::p2p::NodeKeyPair;
+    use rand::rngs::OsRng;
+    use std::convert::TryFrom;
+
+    fn node_id() -> NodeId {
+        NodeKeyPair::generate(OsRng).sign(&[]).into()
+    }
+
+    fn fragment_id(n: u8) -> FragmentId {
+        FragmentId::try_from(&[n; 32][..]).unwrap()
+    }
+
+    #[test]
+    fn duplicates_are_detected() {
+        let (alice, bob) = (node_id(), node_id());
+        let mut tracker = PropagationTracker::new(10);
+        let now = Instant::now();
+
+        assert_eq!(tracker.record(&alice, &fragment_id(1), now), Received::New);
+        assert!(tracker.is_known(&fragment_id(1)));
+        assert_eq!(
+            tracker.record(&bob, &fragment_id(1), now + Duration::from_secs(2)),
+            Received::Duplicate {
+                latency: Duration::from_secs(2)
+            }
+        );
+
+        let alice_stats = tracker.peer(&alice).unwrap();
+        assert_eq!(alice_stats.first_seen(), 1);
+        assert_eq!(alice_stats.duplicates(), 0);
+        let bob_stats = tracker.peer(&bob).unwrap();
+        assert_eq!(bob_stats.first_seen(), 0);
+        assert_eq!(bob_stats.duplicates(), 1);
+        assert_eq!(bob_stats.mean_duplicate_latency(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn oldest_fragments_are_forgotten() {
+        let peer = node_id();
+        let mut tracker = PropagationTracker::new(2);
+        let now = Instant::now();
+        for n in 0..3 {
+            tracker.record(&peer, &fragment_id(n), now);
+        }
+        assert!(!tracker.is_known(&fragment_id(0)));
+        assert!(tracker.is_known(&fragment_id(2)));
+        assert_eq!(tracker.record(&peer, &fragment_id(0), now), Received::New);
+    }
+
+    #[test]
+    fn well_behaved_peers_are_preferred() {
+        let (fast, slow, new) = (node_id(), node_id(), node_id());
+        let mut tracker = PropagationTracker::new(100);
+        let now = Instant::now();
+        for n in 0..10 {
+            tracker.record(&fast, &fragment_id(n), now);
+            tracker.record(&slow, &fragment_id(n), now + Duration::from_secs(3));
+        }
+
+        assert_eq!(tracker.score(&fast), PropagationScore(1.0));
+        assert_eq!(tracker.score(&slow), PropagationScore(0.0));
+        assert_eq!(tracker.score(&new), PropagationScore::NEUTRAL);
+        assert_eq!(
+            tracker.select_targets(vec![&slow, &new, &fast], 2),
+            vec![fast.clone(), new]
+        );
+
+        tracker.remove_peer(&fast);
+        assert_eq!(tracker.score(&fast), PropagationScore::NEUTRAL);
+    }
+}
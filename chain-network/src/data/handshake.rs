@@ -1,8 +1,54 @@
 use super::block::BlockId;
 use super::p2p::AuthenticatedNodeId;
 
+use std::ops::BitOr;
+
+/// Optional features of the protocol supported by a node, advertised
+/// in the handshake.
+///
+/// The bits not known to this implementation are preserved, so that they
+/// can be forwarded as received.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// The node accepts connections over QUIC, on the UDP port with the
+    /// same number as its TCP listening port.
+    pub const QUIC: Capabilities = Capabilities(1);
+
+    #[inline]
+    pub fn empty() -> Self {
+        Capabilities(0)
+    }
+
+    #[inline]
+    pub fn from_bits(bits: u32) -> Self {
+        Capabilities(bits)
+    }
+
+    #[inline]
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Capabilities(self.0 | rhs.0)
+    }
+}
+
 pub struct HandshakeResponse {
     pub block0_id: BlockId,
     pub auth: AuthenticatedNodeId,
     pub nonce: Box<[u8]>,
+    pub capabilities: Capabilities,
 }
@@ -0,0 +1,343 @@
+//! Keep-alive pings and the health of the connections to the peers.
+//!
+//! A node pings each connected peer after a period without traffic from it,
+//! and the peer answers with a pong echoing the ping. The round-trip time of
+//! the answered pings is averaged per peer. A ping which is not answered
+//! before the timeout is missed, unless the peer sent anything else in the
+//! meantime: any message proves that the peer is alive.
+//!
+//! [`KeepAlive`] tracks the pings of a node and rates the [`Liveness`] of its
+//! peers, so that the dead connections are evicted after the same number of
+//! missed pings whatever the transport, instead of waiting for the timeouts
+//! of TCP. Over gRPC, the pings are sent with the `KeepAlive` method, which
+//! the server answers without involving the node.
+
+use super::p2p::Peer;
+use crate::error::{Code, Error};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A keep-alive ping, identified by its sequence number. The pong answering
+/// a ping carries the same value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ping(u64);
+
+impl Ping {
+    #[inline]
+    pub fn new(seq: u64) -> Self {
+        Ping(seq)
+    }
+
+    #[inline]
+    pub fn seq(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Rejection of a pong received from a peer.
+#[derive(Debug, thiserror::Error)]
+pub enum KeepAliveError {
+    #[error("the peer is not tracked")]
+    UnknownPeer,
+    #[error("no ping is pending for the peer")]
+    NotPinged,
+    #[error("the pong answers ping {received} instead of ping {expected}")]
+    UnexpectedPong { expected: u64, received: u64 },
+}
+
+impl From<KeepAliveError> for Error {
+    fn from(e: KeepAliveError) -> Self {
+        let code = match e {
+            KeepAliveError::UnknownPeer | KeepAliveError::NotPinged => Code::FailedPrecondition,
+            KeepAliveError::UnexpectedPong { .. } => Code::InvalidArgument,
+        };
+        Error::new(code, e)
+    }
+}
+
+/// Settings of the keep-alive pings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepAliveConfig {
+    /// The peers are pinged after this period without traffic from them.
+    pub interval: Duration,
+    /// The pongs are expected within this delay after the ping.
+    pub timeout: Duration,
+    /// The peers missing this number of consecutive pings are dead.
+    pub max_missed: u32,
+}
+
+impl Default for KeepAliveConfig {
+    fn default() -> Self {
+        KeepAliveConfig {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            max_missed: 3,
+        }
+    }
+}
+
+/// Rating of the connection to a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Liveness {
+    /// The peer answered the last ping or sent traffic since then.
+    Alive,
+    /// The peer missed some consecutive pings, but less than the maximum.
+    Unresponsive { missed: u32 },
+    /// The peer missed the maximum number of consecutive pings: the
+    /// connection should be closed.
+    Dead,
+}
+
+/// Health of the connection to a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerHealth {
+    /// The round-trip time of the last answered ping.
+    pub last_rtt: Option<Duration>,
+    /// The smoothed round-trip time, an exponential moving average of the
+    /// answered pings giving a weight of 1/8 to each new sample.
+    pub rtt: Option<Duration>,
+    /// The number of consecutive pings the peer missed.
+    pub missed: u32,
+    /// When the peer was last heard from.
+    pub last_seen: Instant,
+}
+
+#[derive(Debug)]
+struct PeerState {
+    health: PeerHealth,
+    pending: Option<(Ping, Instant)>,
+    next_seq: u64,
+}
+
+/// The keep-alive pings of a node and the health of its peers.
+#[derive(Debug)]
+pub struct KeepAlive {
+    config: KeepAliveConfig,
+    peers: HashMap<Peer, PeerState>,
+}
+
+impl KeepAlive {
+    pub fn new(config: KeepAliveConfig) -> Self {
+        KeepAlive {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &KeepAliveConfig {
+        &self.config
+    }
+
+    /// Start tracking a newly connected peer. The peer is considered alive
+    /// from `now`.
+    pub fn connect(&mut self, peer: Peer, now: Instant) {
+        self.peers.insert(
+            peer,
+            PeerState {
+                health: PeerHealth {
+                    last_rtt: None,
+                    rtt: None,
+                    missed: 0,
+                    last_seen: now,
+                },
+                pending: None,
+                next_seq: 0,
+            },
+        );
+    }
+
+    /// Stop tracking a disconnected peer.
+    pub fn forget(&mut self, peer: &Peer) {
+        self.peers.remove(peer);
+    }
+
+    /// Record traffic received from the peer, which proves that it is alive.
+    /// The traffic of untracked peers is ignored.
+    pub fn record_activity(&mut self, peer: &Peer, now: Instant) {
+        if let Some(state) = self.peers.get_mut(peer) {
+            state.health.last_seen = now;
+            state.health.missed = 0;
+        }
+    }
+
+    /// Account for the pings which timed out and return the pings to send,
+    /// to the peers which have not been heard from for the interval and have
+    /// no ping pending.
+    pub fn poll(&mut self, now: Instant) -> Vec<(Peer, Ping)> {
+        let config = self.config;
+        let mut pings = Vec::new();
+        for (peer, state) in self.peers.iter_mut() {
+            if let Some((_, sent)) = state.pending {
+                if now.saturating_duration_since(sent) < config.timeout {
+                    continue;
+                }
+                state.pending = None;
+                if state.health.last_seen <= sent {
+                    state.health.missed += 1;
+                }
+            }
+            let idle = now.saturating_duration_since(state.health.last_seen);
+            if state.health.missed > 0 || idle >= config.interval {
+                let ping = Ping(state.next_seq);
+                state.next_seq = state.next_seq.wrapping_add(1);
+                state.pending = Some((ping, now));
+                pings.push((peer.clone(), ping));
+            }
+        }
+        pings
+    }
+
+    /// Check the pong received from the peer and return the round-trip time
+    /// of the ping it answers.
+    pub fn pong(
+        &mut self,
+        peer: &Peer,
+        pong: Ping,
+        now: Instant,
+    ) -> Result<Duration, KeepAliveError> {
+        let state = self
+            .peers
+            .get_mut(peer)
+            .ok_or(KeepAliveError::UnknownPeer)?;
+        let (ping, sent) = state.pending.ok_or(KeepAliveError::NotPinged)?;
+        if ping != pong {
+            return Err(KeepAliveError::UnexpectedPong {
+                expected: ping.seq(),
+                received: pong.seq(),
+            });
+        }
+        state.pending = None;
+
+        let rtt = now.saturating_duration_since(sent);
+        let health = &mut state.health;
+        health.last_rtt = Some(rtt);
+        health.rtt = Some(match health.rtt {
+            None => rtt,
+            Some(srtt) => (srtt * 7 + rtt) / 8,
+        });
+        health.missed = 0;
+        health.last_seen = now;
+        Ok(rtt)
+    }
+
+    /// The health of the connection to the peer, if it is tracked.
+    pub fn health(&self, peer: &Peer) -> Option<&PeerHealth> {
+        self.peers.get(peer).map(|state| &state.health)
+    }
+
+    /// The liveness of the peer, if it is tracked.
+    pub fn liveness(&self, peer: &Peer) -> Option<Liveness> {
+        self.health(peer).map(|health| match health.missed {
+            0 => Liveness::Alive,
+            missed if missed < self.config.max_missed => Liveness::Unresponsive { missed },
+            _ => Liveness::Dead,
+        })
+    }
+
+    /// The peers whose connection should be closed.
+    pub fn dead_peers(&self) -> Vec<Peer> {
+        self.peers
+            .keys()
+            .filter(|peer| self.liveness(peer) == Some(Liveness::Dead))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> Peer {
+        Peer::from(std::net::SocketAddr::from(([192, 0, 2, 1], port)))
+    }
+
+    fn config() -> KeepAliveConfig {
+        KeepAliveConfig {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            max_missed: 2,
+        }
+    }
+
+    #[test]
+    fn idle_peers_are_pinged_and_their_rtt_measured() {
+        let mut keep_alive = KeepAlive::new(config());
+        let (alice, bob) = (peer(3000), peer(3001));
+        let start = Instant::now();
+        keep_alive.connect(alice.clone(), start);
+        keep_alive.connect(bob.clone(), start);
+
+        assert!(keep_alive.poll(start + Duration::from_secs(10)).is_empty());
+
+        let now = start + Duration::from_secs(30);
+        keep_alive.record_activity(&bob, now);
+        let pings = keep_alive.poll(now);
+        assert_eq!(pings.len(), 1);
+        let (pinged, ping) = pings[0].clone();
+        assert_eq!(pinged, alice);
+        // a ping is pending
+        assert!(keep_alive.poll(now + Duration::from_secs(1)).is_empty());
+
+        assert!(matches!(
+            keep_alive.pong(&bob, ping, now),
+            Err(KeepAliveError::NotPinged)
+        ));
+        assert!(matches!(
+            keep_alive.pong(&alice, Ping::new(ping.seq() + 1), now),
+            Err(KeepAliveError::UnexpectedPong { .. })
+        ));
+        let rtt = keep_alive
+            .pong(&alice, ping, now + Duration::from_millis(800))
+            .unwrap();
+        assert_eq!(rtt, Duration::from_millis(800));
+
+        let now = now + Duration::from_secs(60);
+        let (_, ping) = keep_alive
+            .poll(now)
+            .into_iter()
+            .find(|(pinged, _)| *pinged == alice)
+            .unwrap();
+        keep_alive
+            .pong(&alice, ping, now + Duration::from_millis(400))
+            .unwrap();
+        let health = keep_alive.health(&alice).unwrap();
+        assert_eq!(health.last_rtt, Some(Duration::from_millis(400)));
+        assert_eq!(health.rtt, Some(Duration::from_millis(750)));
+        assert_eq!(keep_alive.liveness(&alice), Some(Liveness::Alive));
+    }
+
+    #[test]
+    fn peers_missing_pings_are_dead() {
+        let mut keep_alive = KeepAlive::new(config());
+        let (alice, bob) = (peer(3000), peer(3001));
+        let start = Instant::now();
+        keep_alive.connect(alice.clone(), start);
+        keep_alive.connect(bob.clone(), start);
+
+        let now = start + Duration::from_secs(30);
+        assert_eq!(keep_alive.poll(now).len(), 2);
+        // the traffic of bob makes up for the missing pong
+        keep_alive.record_activity(&bob, now + Duration::from_secs(5));
+
+        let now = now + Duration::from_secs(10);
+        let pings = keep_alive.poll(now);
+        assert_eq!(
+            keep_alive.liveness(&alice),
+            Some(Liveness::Unresponsive { missed: 1 })
+        );
+        assert_eq!(keep_alive.liveness(&bob), Some(Liveness::Alive));
+        // unresponsive peers are pinged again without waiting for the interval
+        assert_eq!(pings.len(), 1);
+        assert_eq!(pings[0].0, alice);
+
+        keep_alive.poll(now + Duration::from_secs(10));
+        assert_eq!(keep_alive.liveness(&alice), Some(Liveness::Dead));
+        assert_eq!(keep_alive.dead_peers(), vec![alice.clone()]);
+
+        keep_alive.forget(&alice);
+        assert_eq!(keep_alive.liveness(&alice), None);
+        assert!(keep_alive.dead_peers().is_empty());
+    }
+}
@@ -1,11 +1,15 @@
 pub mod block;
+mod challenge;
 pub mod fragment;
 pub mod gossip;
 mod handshake;
+mod keep_alive;
 pub mod p2p;
 
 pub use block::{Block, BlockEvent, BlockId, BlockIds, Header};
-pub use fragment::{Fragment, FragmentId, FragmentIds};
+pub use challenge::{ChallengeError, Challenges, Nonce, NONCE_LEN};
+pub use fragment::{Fragment, FragmentEnvelope, FragmentId, FragmentIds};
 pub use gossip::Gossip;
-pub use handshake::HandshakeResponse;
-pub use p2p::{AuthenticatedNodeId, NodeId, NodeKeyPair, Peer};
+pub use handshake::{Capabilities, HandshakeResponse};
+pub use keep_alive::{KeepAlive, KeepAliveConfig, KeepAliveError, Liveness, PeerHealth, Ping};
+pub use p2p::{AuthenticatedNodeId, NodeId, NodeKeyPair, Peer, Transport};
@@ -6,35 +6,75 @@ use std::convert::TryFrom;
 use std::fmt;
 use std::net::SocketAddr;
 
+/// The transport protocol used to reach a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// HTTP/2 over TCP.
+    Tcp,
+    /// QUIC over UDP. The node-to-node services are carried by
+    /// bidirectional QUIC streams, see the `quic` module.
+    Quic,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Transport::Tcp
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transport::Tcp => f.write_str("tcp"),
+            Transport::Quic => f.write_str("quic"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Peer {
     addr: SocketAddr,
+    transport: Transport,
 }
 
 impl Peer {
+    #[inline]
+    pub fn new(addr: SocketAddr, transport: Transport) -> Self {
+        Peer { addr, transport }
+    }
+
     #[inline]
     pub fn addr(&self) -> SocketAddr {
         self.addr
     }
+
+    #[inline]
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
 }
 
+/// A peer reached over TCP.
 impl From<SocketAddr> for Peer {
     #[inline]
     fn from(addr: SocketAddr) -> Self {
-        Peer { addr }
+        Peer::new(addr, Transport::Tcp)
     }
 }
 
 impl fmt::Display for Peer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.addr)
+        match self.transport {
+            Transport::Tcp => write!(f, "{}", self.addr),
+            Transport::Quic => write!(f, "quic://{}", self.addr),
+        }
     }
 }
 
 /// The key pair used to authenticate a network node,
 /// including the secret key.
 #[derive(Clone, Debug)]
-pub struct NodeKeyPair(KeyPair<Ed25519>);
+pub struct NodeKeyPair(pub(crate) KeyPair<Ed25519>);
 
 impl NodeKeyPair {
     /// Generates a key pair using the provided random number generator.
@@ -55,7 +95,7 @@ impl NodeKeyPair {
 
 /// Identifier of a network peer.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct NodeId(PublicKey<Ed25519>);
+pub struct NodeId(pub(crate) PublicKey<Ed25519>);
 
 impl NodeId {
     #[inline]
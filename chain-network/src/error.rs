@@ -1,26 +1,101 @@
-use std::{error, fmt};
+use std::{error, fmt, time::Duration};
 
 /// Common error codes for network protocol requests.
 ///
 /// These codes mimic the status codes used in gRPC and map one to one to
 /// those in the gRPC protocol implementation.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Code {
     Canceled,
     Unknown,
     InvalidArgument,
+    DeadlineExceeded,
     NotFound,
+    AlreadyExists,
+    PermissionDenied,
+    ResourceExhausted,
     FailedPrecondition,
     Aborted,
+    OutOfRange,
     Unimplemented,
     Internal,
     Unavailable,
+    DataLoss,
+    Unauthenticated,
+}
+
+/// Classification of the error codes by what a client can do about the
+/// failure of a request.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ErrorKind {
+    /// The failure is transient, the request may succeed if it is retried.
+    Retryable,
+    /// The peer is overloaded by the requests of the client; the request can
+    /// be retried after a delay, which the error may hint with
+    /// [`Error::retry_after`].
+    RateLimited,
+    /// The requested data is not known by the peer. Retrying the request
+    /// with the same peer is pointless, another peer may have the data.
+    NotFound,
+    /// The request or the response does not follow the protocol. The peer
+    /// can be considered misbehaving.
+    ProtocolViolation,
+    /// Any other failure, which should not be retried as is.
+    Other,
+}
+
+impl Code {
+    /// The kind of failure signaled by the code:
+    ///
+    /// * `Aborted`, `Unavailable` and `DeadlineExceeded` are retryable;
+    /// * `ResourceExhausted` signals a rate limit;
+    /// * `NotFound` stands on its own;
+    /// * `InvalidArgument`, `OutOfRange` and `DataLoss` are protocol
+    ///   violations;
+    /// * the other codes, including `Canceled`, are not retryable.
+    pub fn kind(self) -> ErrorKind {
+        match self {
+            Code::Aborted | Code::Unavailable | Code::DeadlineExceeded => ErrorKind::Retryable,
+            Code::ResourceExhausted => ErrorKind::RateLimited,
+            Code::NotFound => ErrorKind::NotFound,
+            Code::InvalidArgument | Code::OutOfRange | Code::DataLoss => {
+                ErrorKind::ProtocolViolation
+            }
+            Code::Canceled
+            | Code::Unknown
+            | Code::AlreadyExists
+            | Code::PermissionDenied
+            | Code::FailedPrecondition
+            | Code::Unimplemented
+            | Code::Internal
+            | Code::Unauthenticated => ErrorKind::Other,
+        }
+    }
+}
+
+impl ErrorKind {
+    /// Whether the same request can be sent again, after a delay in case the
+    /// requests are rate limited.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorKind::Retryable | ErrorKind::RateLimited)
+    }
+}
+
+/// Details of an error, carried along the code in the protocol.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ErrorDetails {
+    /// The delay after which the request may be retried.
+    pub retry_after: Option<Duration>,
+    /// A description of the data that was not found, e.g. the hex encoded
+    /// identifier of a block.
+    pub missing: Option<Box<str>>,
 }
 
 /// Represents errors that can be returned by the node protocol implementation.
 #[derive(Debug)]
 pub struct Error {
     code: Code,
+    details: ErrorDetails,
     source: Box<dyn error::Error + Send + Sync>,
 }
 
@@ -31,6 +106,7 @@ impl Error {
     {
         Error {
             code,
+            details: ErrorDetails::default(),
             source: source.into(),
         }
     }
@@ -39,9 +115,54 @@ impl Error {
         Error::new(Code::Unimplemented, "not yet implemented")
     }
 
+    /// The requests of the client are refused until the given delay has
+    /// elapsed.
+    pub fn rate_limited(retry_after: Duration) -> Self {
+        Error::new(Code::ResourceExhausted, "too many requests").with_retry_after(retry_after)
+    }
+
+    /// The described data is not known.
+    pub fn not_found<S: Into<Box<str>>>(missing: S) -> Self {
+        let missing = missing.into();
+        Error::new(Code::NotFound, format!("{} not found", missing)).with_missing(missing)
+    }
+
+    /// Hint the delay after which the request may be retried.
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.details.retry_after = Some(retry_after);
+        self
+    }
+
+    /// Describe the data that was not found.
+    pub fn with_missing<S: Into<Box<str>>>(mut self, missing: S) -> Self {
+        self.details.missing = Some(missing.into());
+        self
+    }
+
+    pub fn with_details(mut self, details: ErrorDetails) -> Self {
+        self.details = details;
+        self
+    }
+
     pub fn code(&self) -> Code {
         self.code
     }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.code.kind()
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    pub fn details(&self) -> &ErrorDetails {
+        &self.details
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.details.retry_after
+    }
 }
 
 impl error::Error for Error {
@@ -56,12 +177,19 @@ impl fmt::Display for Error {
             Code::Canceled => "processing canceled",
             Code::Unknown => "unknown error",
             Code::InvalidArgument => "invalid request data",
+            Code::DeadlineExceeded => "the deadline expired before the operation completed",
             Code::NotFound => "not found",
+            Code::AlreadyExists => "already exists",
+            Code::PermissionDenied => "permission denied",
+            Code::ResourceExhausted => "resources exhausted",
             Code::FailedPrecondition => "system state does not permit the operation",
             Code::Aborted => "the operation was aborted",
+            Code::OutOfRange => "the operation was attempted past the valid range",
             Code::Unimplemented => "not implemented",
             Code::Internal => "internal processing error",
             Code::Unavailable => "the service is unavailable",
+            Code::DataLoss => "unrecoverable data loss or corruption",
+            Code::Unauthenticated => "the request is not authenticated",
         };
         write!(f, "{} ({})", msg, self.source)
     }
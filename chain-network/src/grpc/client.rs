@@ -1,14 +1,14 @@
 use super::convert;
 use super::proto;
-use super::streaming::{InboundStream, OutboundStream};
+use super::streaming::{InboundBlockStream, InboundStream, OutboundStream};
 
 #[cfg(feature = "legacy")]
 use super::legacy;
 
-use crate::data::block::{Block, BlockEvent, BlockId, BlockIds, Header};
+use crate::data::block::{Block, BlockEvent, BlockId, BlockIds, Header, WindowUpdate};
 use crate::data::fragment::{Fragment, FragmentIds};
 use crate::data::p2p::{AuthenticatedNodeId, NodeId};
-use crate::data::{Gossip, HandshakeResponse};
+use crate::data::{Capabilities, Gossip, HandshakeResponse, Ping};
 use crate::error::{Error, HandshakeError};
 use crate::PROTOCOL_VERSION;
 use futures::prelude::*;
@@ -90,6 +90,9 @@ pub struct Client<T> {
 /// The inbound subscription stream of block events.
 pub type BlockSubscription = InboundStream<proto::BlockEvent, BlockEvent>;
 
+/// The inbound flow controlled subscription stream of headers.
+pub type HeaderSubscription = InboundStream<proto::Header, Header>;
+
 /// The inbound subscription stream of fragments.
 pub type FragmentSubscription = InboundStream<proto::Fragment, Fragment>;
 
@@ -168,10 +171,12 @@ where
             .authenticated(&res.signature)
             .map_err(HandshakeError::MalformedSignature)?;
         let nonce = res.nonce.into();
+        let capabilities = Capabilities::from_bits(res.capabilities);
         Ok(HandshakeResponse {
             block0_id,
             auth,
             nonce,
+            capabilities,
         })
     }
 
@@ -184,6 +189,17 @@ where
         Ok(())
     }
 
+    /// Sends a keep-alive ping to the server and returns the ping echoed
+    /// in its pong, to be checked with
+    /// [`KeepAlive::pong`](crate::data::KeepAlive::pong).
+    pub async fn keep_alive(&mut self, ping: Ping) -> Result<Ping, Error> {
+        use crate::grpc::convert::{FromProtobuf, IntoProtobuf};
+        let req = ping.into_message();
+        let res = self.inner.keep_alive(req).await?.into_inner();
+        let pong = Ping::from_message(res)?;
+        Ok(pong)
+    }
+
     /// One-off request for a list of peers known to the remote node.
     ///
     /// The peers are picked up accordingly to the Poldercast algorithm
@@ -206,15 +222,15 @@ where
     }
 
     /// Requests the identified blocks in a streamed response.
-    pub async fn get_blocks(
-        &mut self,
-        ids: BlockIds,
-    ) -> Result<InboundStream<proto::Block, Block>, Error> {
+    ///
+    /// The checksums sent by the peer are verified while the
+    /// blocks are received.
+    pub async fn get_blocks(&mut self, ids: BlockIds) -> Result<InboundBlockStream, Error> {
         let ids = proto::BlockIds {
             ids: convert::ids_into_repeated_bytes(ids.iter()),
         };
         let stream = self.inner.get_blocks(ids).await?.into_inner();
-        Ok(InboundStream::new(stream))
+        Ok(InboundBlockStream::new(stream))
     }
 
     /// Requests the headers of the identified blocks in a streamed response.
@@ -246,13 +262,13 @@ where
         &mut self,
         from: BlockIds,
         to: BlockId,
-    ) -> Result<InboundStream<proto::Block, Block>, Error> {
+    ) -> Result<InboundBlockStream, Error> {
         let req = proto::PullBlocksRequest {
             from: convert::ids_into_repeated_bytes(from.into_vec()),
             to: to.as_ref().to_vec(),
         };
         let stream = self.inner.pull_blocks(req).await?.into_inner();
-        Ok(InboundStream::new(stream))
+        Ok(InboundBlockStream::new(stream))
     }
 
     /// Stream blocks from the first of the given starting points
@@ -260,12 +276,12 @@ where
     pub async fn pull_blocks_to_tip(
         &mut self,
         from: BlockIds,
-    ) -> Result<InboundStream<proto::Block, Block>, Error> {
+    ) -> Result<InboundBlockStream, Error> {
         let req = proto::PullBlocksToTipRequest {
             from: convert::ids_into_repeated_bytes(from.into_vec()),
         };
         let stream = self.inner.pull_blocks_to_tip(req).await?.into_inner();
-        Ok(InboundStream::new(stream))
+        Ok(InboundBlockStream::new(stream))
     }
 
     /// Requests headers of blocks in the blockchain's chronological order,
@@ -325,6 +341,21 @@ where
         Ok(InboundStream::new(inbound))
     }
 
+    /// Establishes a flow controlled stream of the headers of new blocks.
+    ///
+    /// The server sends headers within the window granted by the updates
+    /// taken from the passed stream. If the window stays closed for too
+    /// long, the subscription ends with a `ResourceExhausted` error and the
+    /// missing headers have to be pulled.
+    pub async fn header_subscription<S>(&mut self, outbound: S) -> Result<HeaderSubscription, Error>
+    where
+        S: Stream<Item = WindowUpdate> + Send + Sync + 'static,
+    {
+        let req = self.subscription_request(OutboundStream::new(outbound));
+        let inbound = self.inner.header_subscription(req).await?.into_inner();
+        Ok(InboundStream::new(inbound))
+    }
+
     /// Establishes a bidirectional stream for exchanging fragments
     /// created or accepted by either of the peers.
     ///
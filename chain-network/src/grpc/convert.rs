@@ -1,13 +1,19 @@
 use super::proto;
 use crate::data::{
-    block::{self, Block, BlockEvent, BlockId, ChainPullRequest, Header},
+    block::{self, Block, BlockEvent, BlockId, ChainPullRequest, Header, WindowUpdate},
     fragment::Fragment,
     gossip::{Gossip, Node},
+    Ping,
 };
-use crate::error::{self, Error};
-use tonic::{Code, Status};
+use crate::error::{self, Error, ErrorDetails};
+use tonic::{metadata::MetadataValue, Code, Status};
 
 use std::convert::TryFrom;
+use std::time::Duration;
+
+// Metadata entries carrying the details of the errors
+const RETRY_AFTER_MS_HEADER: &str = "retry-after-ms";
+const MISSING_HEADER: &str = "missing-bin";
 
 pub(super) fn error_into_grpc(err: Error) -> Status {
     use error::Code::*;
@@ -16,17 +22,38 @@ pub(super) fn error_into_grpc(err: Error) -> Status {
         Canceled => Code::Cancelled,
         Unknown => Code::Unknown,
         InvalidArgument => Code::InvalidArgument,
+        DeadlineExceeded => Code::DeadlineExceeded,
         NotFound => Code::NotFound,
+        AlreadyExists => Code::AlreadyExists,
+        PermissionDenied => Code::PermissionDenied,
+        ResourceExhausted => Code::ResourceExhausted,
         FailedPrecondition => Code::FailedPrecondition,
         Aborted => Code::Aborted,
+        OutOfRange => Code::OutOfRange,
         Unimplemented => Code::Unimplemented,
         Internal => Code::Internal,
         Unavailable => Code::Unavailable,
+        DataLoss => Code::DataLoss,
+        Unauthenticated => Code::Unauthenticated,
         // When a new case has to be added here, remember to
         // add the corresponding case in error_from_grpc below.
     };
 
-    Status::new(code, err.to_string())
+    let mut status = Status::new(code, err.to_string());
+    let details = err.details();
+    if let Some(retry_after) = details.retry_after {
+        let millis = u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX);
+        status
+            .metadata_mut()
+            .insert(RETRY_AFTER_MS_HEADER, MetadataValue::from(millis));
+    }
+    if let Some(missing) = &details.missing {
+        status.metadata_mut().insert_bin(
+            MISSING_HEADER,
+            MetadataValue::from_bytes(missing.as_bytes()),
+        );
+    }
+    status
 }
 
 pub(super) fn error_from_grpc(e: Status) -> Error {
@@ -36,16 +63,40 @@ pub(super) fn error_from_grpc(e: Status) -> Error {
         Code::Cancelled => Canceled,
         Code::Unknown => Unknown,
         Code::InvalidArgument => InvalidArgument,
+        Code::DeadlineExceeded => DeadlineExceeded,
         Code::NotFound => NotFound,
+        Code::AlreadyExists => AlreadyExists,
+        Code::PermissionDenied => PermissionDenied,
+        Code::ResourceExhausted => ResourceExhausted,
         Code::FailedPrecondition => FailedPrecondition,
         Code::Aborted => Aborted,
+        Code::OutOfRange => OutOfRange,
         Code::Unimplemented => Unimplemented,
         Code::Internal => Internal,
         Code::Unavailable => Unavailable,
+        Code::DataLoss => DataLoss,
+        Code::Unauthenticated => Unauthenticated,
+        // a status of Ok is not an error the peer is supposed to send, and
+        // the codes gRPC may add later are not known
         _ => Unknown,
     };
 
-    Error::new(code, e)
+    // malformed details are ignored, the error itself is still relevant
+    let metadata = e.metadata();
+    let details = ErrorDetails {
+        retry_after: metadata
+            .get(RETRY_AFTER_MS_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|millis| millis.parse().ok())
+            .map(Duration::from_millis),
+        missing: metadata
+            .get_bin(MISSING_HEADER)
+            .and_then(|value| value.to_bytes().ok())
+            .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+            .map(String::into_boxed_str),
+    };
+
+    Error::new(code, e).with_details(details)
 }
 
 impl From<Error> for Status {
@@ -91,6 +142,8 @@ impl IntoProtobuf for Block {
     fn into_message(self) -> proto::Block {
         proto::Block {
             content: self.into(),
+            checksum: Vec::new(),
+            stream_digest: Vec::new(),
         }
     }
 }
@@ -111,6 +164,34 @@ impl IntoProtobuf for Header {
     }
 }
 
+impl FromProtobuf<proto::WindowUpdate> for WindowUpdate {
+    fn from_message(message: proto::WindowUpdate) -> Result<Self, Error> {
+        Ok(WindowUpdate(message.window))
+    }
+}
+
+impl IntoProtobuf for WindowUpdate {
+    type Message = proto::WindowUpdate;
+
+    fn into_message(self) -> proto::WindowUpdate {
+        proto::WindowUpdate { window: self.0 }
+    }
+}
+
+impl IntoProtobuf for Ping {
+    type Message = proto::Ping;
+
+    fn into_message(self) -> proto::Ping {
+        proto::Ping { seq: self.seq() }
+    }
+}
+
+impl FromProtobuf<proto::Pong> for Ping {
+    fn from_message(message: proto::Pong) -> Result<Self, Error> {
+        Ok(Ping::new(message.seq))
+    }
+}
+
 impl FromProtobuf<proto::Fragment> for Fragment {
     fn from_message(message: proto::Fragment) -> Result<Self, Error> {
         Ok(Fragment::from_bytes(message.content))
@@ -220,3 +301,56 @@ impl IntoProtobuf for BlockEvent {
         proto::BlockEvent { item: Some(item) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODES: [error::Code; 16] = [
+        error::Code::Canceled,
+        error::Code::Unknown,
+        error::Code::InvalidArgument,
+        error::Code::DeadlineExceeded,
+        error::Code::NotFound,
+        error::Code::AlreadyExists,
+        error::Code::PermissionDenied,
+        error::Code::ResourceExhausted,
+        error::Code::FailedPrecondition,
+        error::Code::Aborted,
+        error::Code::OutOfRange,
+        error::Code::Unimplemented,
+        error::Code::Internal,
+        error::Code::Unavailable,
+        error::Code::DataLoss,
+        error::Code::Unauthenticated,
+    ];
+
+    #[test]
+    fn codes_round_trip() {
+        for code in CODES.iter() {
+            let status = error_into_grpc(Error::new(*code, "error"));
+            assert_ne!(status.code(), Code::Ok);
+            assert_eq!(error_from_grpc(status).code(), *code);
+        }
+        assert_eq!(
+            error_from_grpc(Status::new(Code::Ok, "")).code(),
+            error::Code::Unknown
+        );
+    }
+
+    #[test]
+    fn details_round_trip() {
+        let err = error_from_grpc(error_into_grpc(Error::rate_limited(Duration::from_millis(
+            1500,
+        ))));
+        assert_eq!(err.kind(), error::ErrorKind::RateLimited);
+        assert!(err.is_retryable());
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(1500)));
+
+        let err = error_from_grpc(error_into_grpc(Error::not_found("block 0123")));
+        assert_eq!(err.kind(), error::ErrorKind::NotFound);
+        assert!(!err.is_retryable());
+        assert_eq!(err.details().missing.as_deref(), Some("block 0123"));
+        assert_eq!(err.retry_after(), None);
+    }
+}
@@ -1,12 +1,13 @@
 use super::proto;
-use super::streaming::{InboundStream, OutboundTryStream};
+use super::streaming::{InboundStream, OutboundBlockStream, OutboundTryStream};
 
 #[cfg(feature = "legacy")]
 use super::legacy;
 
 use crate::core::server::{BlockService, FragmentService, GossipService, Node};
 use crate::data::p2p::NodeId;
-use crate::data::{block, fragment, BlockId, Peer};
+use crate::data::{block, fragment, BlockId, Peer, Transport};
+use crate::metrics::{Direction, Meter, Method, Metrics};
 use crate::PROTOCOL_VERSION;
 use tonic::{Code, Status};
 
@@ -15,12 +16,15 @@ use tonic::metadata::MetadataValue;
 
 use std::convert::TryFrom;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 pub type Server<T> = proto::node_server::NodeServer<NodeService<T>>;
 
 /// Builder to customize the gRPC server.
 #[derive(Default)]
 pub struct Builder {
+    transport: Transport,
+    metrics: Option<Arc<dyn Metrics>>,
     #[cfg(feature = "legacy")]
     legacy_node_id: Option<legacy::NodeId>,
 }
@@ -28,11 +32,27 @@ pub struct Builder {
 impl Builder {
     pub fn new() -> Self {
         Builder {
+            transport: Transport::Tcp,
+            metrics: None,
             #[cfg(feature = "legacy")]
             legacy_node_id: None,
         }
     }
 
+    /// Set the transport the server accepts connections over, which the
+    /// peers passed to the node are reached with. TCP by default.
+    pub fn transport(&mut self, transport: Transport) -> &mut Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Report the messages exchanged by the server to the given metrics,
+    /// see the [`metrics`](crate::metrics) module.
+    pub fn metrics(&mut self, metrics: Arc<dyn Metrics>) -> &mut Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Make the server add "node-id-bin" metadata with the passed value
     /// into subscription responses, for backward compatibility with
     /// jormungandr versions prior to 0.9.
@@ -44,6 +64,8 @@ impl Builder {
 
     pub fn build<T: Node>(&self, inner: T) -> Server<T> {
         let service = NodeService {
+            transport: self.transport,
+            metrics: self.metrics.clone(),
             #[cfg(feature = "legacy")]
             legacy_node_id: self.legacy_node_id,
             ..NodeService::new(inner)
@@ -55,6 +77,8 @@ impl Builder {
 #[derive(Debug)]
 pub struct NodeService<T> {
     inner: T,
+    transport: Transport,
+    metrics: Option<Arc<dyn Metrics>>,
     #[cfg(feature = "legacy")]
     legacy_node_id: Option<legacy::NodeId>,
 }
@@ -66,11 +90,62 @@ where
     pub fn new(inner: T) -> Self {
         NodeService {
             inner,
+            transport: Transport::Tcp,
+            metrics: None,
             #[cfg(feature = "legacy")]
             legacy_node_id: None,
         }
     }
 
+    fn remote_peer(&self, maybe_addr: Option<SocketAddr>) -> Result<Peer, Status> {
+        match maybe_addr {
+            Some(addr) => Ok(Peer::new(addr, self.transport)),
+            None => Err(Status::internal(
+                "transport does not provide the remote address",
+            )),
+        }
+    }
+
+    fn meter(&self, peer: Option<Peer>, method: Method, direction: Direction) -> Option<Meter> {
+        self.metrics
+            .as_ref()
+            .map(|metrics| Meter::new(metrics.clone(), peer, method, direction))
+    }
+
+    /// The peer sending the request, recording the request message.
+    fn inbound<M: prost::Message>(&self, req: &tonic::Request<M>, method: Method) -> Option<Peer> {
+        let peer = req
+            .remote_addr()
+            .map(|addr| Peer::new(addr, self.transport));
+        if let Some(metrics) = &self.metrics {
+            metrics.record_message(
+                peer.as_ref(),
+                method,
+                Direction::Inbound,
+                req.get_ref().encoded_len(),
+            );
+        }
+        peer
+    }
+
+    fn outbound<M: prost::Message>(
+        &self,
+        peer: Option<&Peer>,
+        method: Method,
+        res: M,
+    ) -> tonic::Response<M> {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_message(peer, method, Direction::Outbound, res.encoded_len());
+        }
+        tonic::Response::new(res)
+    }
+
+    /// The peer opening a stream of inbound messages.
+    fn stream_peer<M>(&self, req: &tonic::Request<M>) -> Option<Peer> {
+        req.remote_addr()
+            .map(|addr| Peer::new(addr, self.transport))
+    }
+
     fn block_service(&self) -> Result<&T::BlockService, Status> {
         self.inner
             .block_service()
@@ -91,8 +166,18 @@ where
 
     #[allow(unused_mut)]
     #[allow(clippy::let_and_return)]
-    fn subscription_response<S>(&self, outbound: S) -> tonic::Response<OutboundTryStream<S>> {
-        let mut res = tonic::Response::new(OutboundTryStream::new(outbound));
+    fn subscription_response<S>(
+        &self,
+        peer: Option<Peer>,
+        method: Method,
+        outbound: S,
+    ) -> tonic::Response<OutboundTryStream<S>> {
+        let outbound = OutboundTryStream::new(outbound).with_meter(self.meter(
+            peer,
+            method,
+            Direction::Outbound,
+        ));
+        let mut res = tonic::Response::new(outbound);
         #[cfg(feature = "legacy")]
         if let Some(node_id) = self.legacy_node_id {
             let val = MetadataValue::from_bytes(&node_id.encode());
@@ -102,15 +187,6 @@ where
     }
 }
 
-fn remote_addr_to_peer(maybe_addr: Option<SocketAddr>) -> Result<Peer, Status> {
-    match maybe_addr {
-        Some(addr) => Ok(addr.into()),
-        None => Err(Status::internal(
-            "transport does not provide the remote address",
-        )),
-    }
-}
-
 #[tonic::async_trait]
 impl<T> proto::node_server::Node for NodeService<T>
 where
@@ -120,7 +196,8 @@ where
         &self,
         req: tonic::Request<proto::HandshakeRequest>,
     ) -> Result<tonic::Response<proto::HandshakeResponse>, tonic::Status> {
-        let peer = remote_addr_to_peer(req.remote_addr())?;
+        let metered_peer = self.inbound(&req, Method::Handshake);
+        let peer = self.remote_peer(req.remote_addr())?;
         let req = req.into_inner();
         let nonce = &req.nonce;
         let hr = self.inner.handshake(peer, nonce).await?;
@@ -130,39 +207,53 @@ where
             node_id: hr.auth.id().as_bytes().into(),
             signature: hr.auth.signature().into(),
             nonce: hr.nonce.into(),
+            capabilities: hr.capabilities.bits(),
         };
-        Ok(tonic::Response::new(res))
+        Ok(self.outbound(metered_peer.as_ref(), Method::Handshake, res))
     }
 
     async fn client_auth(
         &self,
         req: tonic::Request<proto::ClientAuthRequest>,
     ) -> Result<tonic::Response<proto::ClientAuthResponse>, tonic::Status> {
-        let peer = remote_addr_to_peer(req.remote_addr())?;
+        let metered_peer = self.inbound(&req, Method::ClientAuth);
+        let peer = self.remote_peer(req.remote_addr())?;
         let req = req.into_inner();
         let node_id = NodeId::try_from(&req.node_id[..])?;
         let auth = node_id.authenticated(&req.signature)?;
         self.inner.client_auth(peer, auth).await?;
         let res = proto::ClientAuthResponse {};
-        Ok(tonic::Response::new(res))
+        Ok(self.outbound(metered_peer.as_ref(), Method::ClientAuth, res))
+    }
+
+    async fn keep_alive(
+        &self,
+        req: tonic::Request<proto::Ping>,
+    ) -> Result<tonic::Response<proto::Pong>, tonic::Status> {
+        let peer = self.inbound(&req, Method::KeepAlive);
+        let ping = req.into_inner();
+        let res = proto::Pong { seq: ping.seq };
+        Ok(self.outbound(peer.as_ref(), Method::KeepAlive, res))
     }
 
     async fn tip(
         &self,
-        _: tonic::Request<proto::TipRequest>,
+        req: tonic::Request<proto::TipRequest>,
     ) -> Result<tonic::Response<proto::TipResponse>, tonic::Status> {
+        let peer = self.inbound(&req, Method::Tip);
         let service = self.block_service()?;
         let header = service.tip().await?;
         let res = proto::TipResponse {
             block_header: header.into(),
         };
-        Ok(tonic::Response::new(res))
+        Ok(self.outbound(peer.as_ref(), Method::Tip, res))
     }
 
     async fn peers(
         &self,
         req: tonic::Request<proto::PeersRequest>,
     ) -> Result<tonic::Response<proto::PeersResponse>, tonic::Status> {
+        let peer = self.inbound(&req, Method::Peers);
         let service = self.gossip_service()?;
         let peers = service.peers(req.into_inner().limit).await?;
         let res = proto::PeersResponse {
@@ -173,19 +264,23 @@ where
                 .map(|node| node.into_bytes())
                 .collect(),
         };
-        Ok(tonic::Response::new(res))
+        Ok(self.outbound(peer.as_ref(), Method::Peers, res))
     }
 
-    type GetBlocksStream = OutboundTryStream<<T::BlockService as BlockService>::GetBlocksStream>;
+    type GetBlocksStream = OutboundBlockStream<<T::BlockService as BlockService>::GetBlocksStream>;
 
     async fn get_blocks(
         &self,
         req: tonic::Request<proto::BlockIds>,
     ) -> Result<tonic::Response<Self::GetBlocksStream>, tonic::Status> {
+        let peer = self.inbound(&req, Method::GetBlocks);
         let service = self.block_service()?;
         let ids = block::try_ids_from_iter(req.into_inner().ids)?;
         let stream = service.get_blocks(ids).await?;
-        Ok(tonic::Response::new(OutboundTryStream::new(stream)))
+        let meter = self.meter(peer, Method::GetBlocks, Direction::Outbound);
+        Ok(tonic::Response::new(
+            OutboundBlockStream::new(stream).with_meter(meter),
+        ))
     }
 
     type GetHeadersStream = OutboundTryStream<<T::BlockService as BlockService>::GetHeadersStream>;
@@ -194,10 +289,14 @@ where
         &self,
         req: tonic::Request<proto::BlockIds>,
     ) -> Result<tonic::Response<Self::GetHeadersStream>, tonic::Status> {
+        let peer = self.inbound(&req, Method::GetHeaders);
         let service = self.block_service()?;
         let ids = block::try_ids_from_iter(req.into_inner().ids)?;
         let stream = service.get_headers(ids).await?;
-        Ok(tonic::Response::new(OutboundTryStream::new(stream)))
+        let meter = self.meter(peer, Method::GetHeaders, Direction::Outbound);
+        Ok(tonic::Response::new(
+            OutboundTryStream::new(stream).with_meter(meter),
+        ))
     }
 
     type GetFragmentsStream =
@@ -207,10 +306,14 @@ where
         &self,
         req: tonic::Request<proto::FragmentIds>,
     ) -> Result<tonic::Response<Self::GetFragmentsStream>, tonic::Status> {
+        let peer = self.inbound(&req, Method::GetFragments);
         let service = self.fragment_service()?;
         let ids = fragment::try_ids_from_iter(req.into_inner().ids)?;
         let stream = service.get_fragments(ids).await?;
-        Ok(tonic::Response::new(OutboundTryStream::new(stream)))
+        let meter = self.meter(peer, Method::GetFragments, Direction::Outbound);
+        Ok(tonic::Response::new(
+            OutboundTryStream::new(stream).with_meter(meter),
+        ))
     }
 
     type PullHeadersStream =
@@ -220,6 +323,7 @@ where
         &self,
         req: tonic::Request<proto::PullHeadersRequest>,
     ) -> Result<tonic::Response<Self::PullHeadersStream>, tonic::Status> {
+        let peer = self.inbound(&req, Method::PullHeaders);
         let service = self.block_service()?;
         let (from, to) = {
             let req = req.into_inner();
@@ -229,54 +333,72 @@ where
             )
         };
         let stream = service.pull_headers(from, to).await?;
-        Ok(tonic::Response::new(OutboundTryStream::new(stream)))
+        let meter = self.meter(peer, Method::PullHeaders, Direction::Outbound);
+        Ok(tonic::Response::new(
+            OutboundTryStream::new(stream).with_meter(meter),
+        ))
     }
 
-    type PullBlocksStream = OutboundTryStream<<T::BlockService as BlockService>::PullBlocksStream>;
+    type PullBlocksStream =
+        OutboundBlockStream<<T::BlockService as BlockService>::PullBlocksStream>;
 
     async fn pull_blocks(
         &self,
         req: tonic::Request<proto::PullBlocksRequest>,
     ) -> Result<tonic::Response<Self::PullBlocksStream>, tonic::Status> {
+        let peer = self.inbound(&req, Method::PullBlocks);
         let service = self.block_service()?;
         let req = req.into_inner();
         let from = block::try_ids_from_iter(req.from)?;
         let to = BlockId::try_from(&req.to[..])?;
         let stream = service.pull_blocks(from, to).await?;
-        Ok(tonic::Response::new(OutboundTryStream::new(stream)))
+        let meter = self.meter(peer, Method::PullBlocks, Direction::Outbound);
+        Ok(tonic::Response::new(
+            OutboundBlockStream::new(stream).with_meter(meter),
+        ))
     }
 
     type PullBlocksToTipStream =
-        OutboundTryStream<<T::BlockService as BlockService>::PullBlocksToTipStream>;
+        OutboundBlockStream<<T::BlockService as BlockService>::PullBlocksToTipStream>;
 
     async fn pull_blocks_to_tip(
         &self,
         req: tonic::Request<proto::PullBlocksToTipRequest>,
     ) -> Result<tonic::Response<Self::PullBlocksToTipStream>, tonic::Status> {
+        let peer = self.inbound(&req, Method::PullBlocksToTip);
         let service = self.block_service()?;
         let from = block::try_ids_from_iter(req.into_inner().from)?;
         let stream = service.pull_blocks_to_tip(from).await?;
-        Ok(tonic::Response::new(OutboundTryStream::new(stream)))
+        let meter = self.meter(peer, Method::PullBlocksToTip, Direction::Outbound);
+        Ok(tonic::Response::new(
+            OutboundBlockStream::new(stream).with_meter(meter),
+        ))
     }
 
     async fn push_headers(
         &self,
         req: tonic::Request<tonic::Streaming<proto::Header>>,
     ) -> Result<tonic::Response<proto::PushHeadersResponse>, tonic::Status> {
+        let peer = self.stream_peer(&req);
         let service = self.block_service()?;
-        let stream = InboundStream::new(req.into_inner());
+        let meter = self.meter(peer.clone(), Method::PushHeaders, Direction::Inbound);
+        let stream = InboundStream::new(req.into_inner()).with_meter(meter);
         service.push_headers(Box::pin(stream)).await?;
-        Ok(tonic::Response::new(proto::PushHeadersResponse {}))
+        let res = proto::PushHeadersResponse {};
+        Ok(self.outbound(peer.as_ref(), Method::PushHeaders, res))
     }
 
     async fn upload_blocks(
         &self,
         req: tonic::Request<tonic::Streaming<proto::Block>>,
     ) -> Result<tonic::Response<proto::UploadBlocksResponse>, tonic::Status> {
+        let peer = self.stream_peer(&req);
         let service = self.block_service()?;
-        let stream = InboundStream::new(req.into_inner());
+        let meter = self.meter(peer.clone(), Method::UploadBlocks, Direction::Inbound);
+        let stream = InboundStream::new(req.into_inner()).with_meter(meter);
         service.upload_blocks(Box::pin(stream)).await?;
-        Ok(tonic::Response::new(proto::UploadBlocksResponse {}))
+        let res = proto::UploadBlocksResponse {};
+        Ok(self.outbound(peer.as_ref(), Method::UploadBlocks, res))
     }
 
     type BlockSubscriptionStream =
@@ -287,10 +409,39 @@ where
         req: tonic::Request<tonic::Streaming<proto::Header>>,
     ) -> Result<tonic::Response<Self::BlockSubscriptionStream>, tonic::Status> {
         let service = self.block_service()?;
-        let peer = remote_addr_to_peer(req.remote_addr())?;
-        let inbound = InboundStream::new(req.into_inner());
-        let outbound = service.block_subscription(peer, Box::pin(inbound)).await?;
-        let res = self.subscription_response(outbound);
+        let peer = self.remote_peer(req.remote_addr())?;
+        let meter = self.meter(
+            Some(peer.clone()),
+            Method::BlockSubscription,
+            Direction::Inbound,
+        );
+        let inbound = InboundStream::new(req.into_inner()).with_meter(meter);
+        let outbound = service
+            .block_subscription(peer.clone(), Box::pin(inbound))
+            .await?;
+        let res = self.subscription_response(Some(peer), Method::BlockSubscription, outbound);
+        Ok(res)
+    }
+
+    type HeaderSubscriptionStream =
+        OutboundTryStream<<T::BlockService as BlockService>::HeaderSubscriptionStream>;
+
+    async fn header_subscription(
+        &self,
+        req: tonic::Request<tonic::Streaming<proto::WindowUpdate>>,
+    ) -> Result<tonic::Response<Self::HeaderSubscriptionStream>, tonic::Status> {
+        let service = self.block_service()?;
+        let peer = self.remote_peer(req.remote_addr())?;
+        let meter = self.meter(
+            Some(peer.clone()),
+            Method::HeaderSubscription,
+            Direction::Inbound,
+        );
+        let inbound = InboundStream::new(req.into_inner()).with_meter(meter);
+        let outbound = service
+            .header_subscription(peer.clone(), Box::pin(inbound))
+            .await?;
+        let res = self.subscription_response(Some(peer), Method::HeaderSubscription, outbound);
         Ok(res)
     }
 
@@ -302,12 +453,17 @@ where
         req: tonic::Request<tonic::Streaming<proto::Fragment>>,
     ) -> Result<tonic::Response<Self::FragmentSubscriptionStream>, tonic::Status> {
         let service = self.fragment_service()?;
-        let peer = remote_addr_to_peer(req.remote_addr())?;
-        let inbound = InboundStream::new(req.into_inner());
+        let peer = self.remote_peer(req.remote_addr())?;
+        let meter = self.meter(
+            Some(peer.clone()),
+            Method::FragmentSubscription,
+            Direction::Inbound,
+        );
+        let inbound = InboundStream::new(req.into_inner()).with_meter(meter);
         let outbound = service
-            .fragment_subscription(peer, Box::pin(inbound))
+            .fragment_subscription(peer.clone(), Box::pin(inbound))
             .await?;
-        let res = self.subscription_response(outbound);
+        let res = self.subscription_response(Some(peer), Method::FragmentSubscription, outbound);
         Ok(res)
     }
 
@@ -319,10 +475,17 @@ where
         req: tonic::Request<tonic::Streaming<proto::Gossip>>,
     ) -> Result<tonic::Response<Self::GossipSubscriptionStream>, tonic::Status> {
         let service = self.gossip_service()?;
-        let peer = remote_addr_to_peer(req.remote_addr())?;
-        let inbound = InboundStream::new(req.into_inner());
-        let outbound = service.gossip_subscription(peer, Box::pin(inbound)).await?;
-        let res = self.subscription_response(outbound);
+        let peer = self.remote_peer(req.remote_addr())?;
+        let meter = self.meter(
+            Some(peer.clone()),
+            Method::GossipSubscription,
+            Direction::Inbound,
+        );
+        let inbound = InboundStream::new(req.into_inner()).with_meter(meter);
+        let outbound = service
+            .gossip_subscription(peer.clone(), Box::pin(inbound))
+            .await?;
+        let res = self.subscription_response(Some(peer), Method::GossipSubscription, outbound);
         Ok(res)
     }
 }
@@ -1,9 +1,14 @@
+use crate::data::block::{Block, Checksum, StreamVerifier};
 use crate::error::Error;
 use crate::grpc::convert::{error_from_grpc, FromProtobuf};
+use crate::grpc::proto;
+use crate::metrics::Meter;
 use futures::prelude::*;
+use futures::ready;
 use pin_project::pin_project;
 use tonic::Streaming;
 
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -13,6 +18,7 @@ use std::task::{Context, Poll};
 pub struct InboundStream<P, T> {
     #[pin]
     inner: Streaming<P>,
+    meter: Option<Meter>,
     _phantom: PhantomData<T>,
 }
 
@@ -20,22 +26,32 @@ impl<P, T> InboundStream<P, T> {
     pub(crate) fn new(inner: Streaming<P>) -> Self {
         InboundStream {
             inner,
+            meter: None,
             _phantom: PhantomData,
         }
     }
+
+    pub(crate) fn with_meter(self, meter: Option<Meter>) -> Self {
+        InboundStream { meter, ..self }
+    }
 }
 
 impl<P, T> Stream for InboundStream<P, T>
 where
+    P: prost::Message,
     T: FromProtobuf<P>,
 {
     type Item = Result<T, Error>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         let this = self.project();
+        let meter = this.meter;
         this.inner.poll_next(cx).map(|opt| {
             opt.map(|item| match item {
                 Ok(msg) => {
+                    if let Some(meter) = meter {
+                        meter.record(&msg);
+                    }
                     let item = T::from_message(msg)?;
                     Ok(item)
                 }
@@ -44,3 +60,65 @@ where
         })
     }
 }
+
+/// Inbound stream of downloaded blocks, verifying the checksums sent
+/// along with the blocks before they are handed out.
+///
+/// The stream ends after the first error it yields, be it a verification
+/// failure or an error from the peer. If the stream is found to be
+/// truncated, an error is yielded as the last item.
+#[must_use = "streams do nothing unless polled"]
+#[pin_project]
+pub struct InboundBlockStream {
+    #[pin]
+    inner: Streaming<proto::Block>,
+    verifier: StreamVerifier,
+    done: bool,
+}
+
+impl InboundBlockStream {
+    pub(crate) fn new(inner: Streaming<proto::Block>) -> Self {
+        InboundBlockStream {
+            inner,
+            verifier: StreamVerifier::new(),
+            done: false,
+        }
+    }
+}
+
+fn optional_checksum(bytes: &[u8]) -> Result<Option<Checksum>, Error> {
+    if bytes.is_empty() {
+        Ok(None)
+    } else {
+        Checksum::try_from(bytes).map(Some)
+    }
+}
+
+fn verify_block(verifier: &mut StreamVerifier, msg: proto::Block) -> Result<Block, Error> {
+    let checksum = optional_checksum(&msg.checksum)?;
+    let stream_digest = optional_checksum(&msg.stream_digest)?;
+    verifier.verify_block(&msg.content, checksum.as_ref(), stream_digest.as_ref())?;
+    Block::from_message(msg)
+}
+
+impl Stream for InboundBlockStream {
+    type Item = Result<Block, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        let item = match ready!(this.inner.poll_next(cx)) {
+            Some(Ok(msg)) => verify_block(this.verifier, msg),
+            Some(Err(e)) => Err(error_from_grpc(e)),
+            None => {
+                *this.done = true;
+                return Poll::Ready(this.verifier.finish().err().map(|e| Err(e.into())));
+            }
+        };
+        // the blocks following a failure cannot be trusted
+        *this.done = item.is_err();
+        Poll::Ready(Some(item))
+    }
+}
@@ -1,5 +1,5 @@
 mod inbound;
 mod outbound;
 
-pub use inbound::InboundStream;
-pub(super) use outbound::{OutboundStream, OutboundTryStream};
+pub use inbound::{InboundBlockStream, InboundStream};
+pub(super) use outbound::{OutboundBlockStream, OutboundStream, OutboundTryStream};
@@ -1,6 +1,10 @@
+use crate::data::block::{Block, Checksum, StreamDigest};
 use crate::error::Error;
 use crate::grpc::convert::{error_into_grpc, IntoProtobuf};
+use crate::grpc::proto;
+use crate::metrics::Meter;
 use futures::prelude::*;
+use futures::ready;
 use pin_project::pin_project;
 use tonic::Status;
 
@@ -40,11 +44,16 @@ where
 pub struct OutboundTryStream<S> {
     #[pin]
     inner: S,
+    meter: Option<Meter>,
 }
 
 impl<S> OutboundTryStream<S> {
     pub(crate) fn new(inner: S) -> Self {
-        OutboundTryStream { inner }
+        OutboundTryStream { inner, meter: None }
+    }
+
+    pub(crate) fn with_meter(self, meter: Option<Meter>) -> Self {
+        OutboundTryStream { meter, ..self }
     }
 }
 
@@ -52,16 +61,136 @@ impl<S> Stream for OutboundTryStream<S>
 where
     S: TryStream<Error = Error>,
     S::Ok: IntoProtobuf,
+    <S::Ok as IntoProtobuf>::Message: prost::Message,
 {
     type Item = Result<<S::Ok as IntoProtobuf>::Message, Status>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
+        let meter = this.meter;
         this.inner.try_poll_next(cx).map(|maybe_item| {
             maybe_item.map(|item| match item {
-                Ok(data) => Ok(data.into_message()),
+                Ok(data) => {
+                    let message = data.into_message();
+                    if let Some(meter) = meter {
+                        meter.record(&message);
+                    }
+                    Ok(message)
+                }
                 Err(e) => Err(error_into_grpc(e)),
             })
         })
     }
 }
+
+/// Outbound stream of blocks to download, sending each block with its
+/// checksum and the digest of the whole stream along with the last block.
+///
+/// To know which block is the last one, each block is held back until
+/// the next one is produced.
+#[must_use = "streams do nothing unless polled"]
+#[pin_project]
+pub struct OutboundBlockStream<S> {
+    #[pin]
+    inner: S,
+    pending: Option<proto::Block>,
+    error: Option<Error>,
+    digest: StreamDigest,
+    done: bool,
+    meter: Option<Meter>,
+}
+
+impl<S> OutboundBlockStream<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        OutboundBlockStream {
+            inner,
+            pending: None,
+            error: None,
+            digest: StreamDigest::new(),
+            done: false,
+            meter: None,
+        }
+    }
+
+    pub(crate) fn with_meter(self, meter: Option<Meter>) -> Self {
+        OutboundBlockStream { meter, ..self }
+    }
+}
+
+fn record_block(
+    meter: Option<&Meter>,
+    message: proto::Block,
+) -> Option<Result<proto::Block, Status>> {
+    if let Some(meter) = meter {
+        meter.record(&message);
+    }
+    Some(Ok(message))
+}
+
+impl<S> Stream for OutboundBlockStream<S>
+where
+    S: TryStream<Ok = Block, Error = Error>,
+{
+    type Item = Result<proto::Block, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(e) = this.error.take() {
+                return Poll::Ready(Some(Err(error_into_grpc(e))));
+            }
+            if *this.done {
+                return Poll::Ready(None);
+            }
+            match ready!(this.inner.as_mut().try_poll_next(cx)) {
+                Some(Ok(block)) => {
+                    let mut message = block.into_message();
+                    let checksum = Checksum::compute(&message.content);
+                    this.digest.update(&checksum);
+                    message.checksum = checksum.as_bytes().to_vec();
+                    if let Some(message) = this.pending.replace(message) {
+                        return Poll::Ready(record_block(this.meter.as_ref(), message));
+                    }
+                }
+                Some(Err(e)) => {
+                    // send the block held back before the error,
+                    // and end the stream with the error
+                    *this.error = Some(e);
+                    *this.done = true;
+                    if let Some(message) = this.pending.take() {
+                        return Poll::Ready(record_block(this.meter.as_ref(), message));
+                    }
+                }
+                None => {
+                    *this.done = true;
+                    if let Some(mut message) = this.pending.take() {
+                        message.stream_digest = this.digest.finalize().as_bytes().to_vec();
+                        return Poll::Ready(record_block(this.meter.as_ref(), message));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Code;
+    use futures::executor::block_on_stream;
+
+    #[test]
+    fn block_stream_ends_after_error() {
+        let blocks = stream::iter(vec![
+            Ok(Block::from_bytes(vec![1])),
+            Err(Error::new(Code::Internal, "storage failure")),
+            Ok(Block::from_bytes(vec![2])),
+        ]);
+        let items: Vec<_> = block_on_stream(OutboundBlockStream::new(blocks)).collect();
+        assert_eq!(items.len(), 2);
+        let block = items[0].as_ref().unwrap();
+        assert_eq!(block.content, vec![1]);
+        assert!(block.stream_digest.is_empty());
+        assert!(items[1].is_err());
+    }
+}
@@ -1,9 +1,13 @@
 #![warn(clippy::all)]
 
+pub mod bootstrap;
 pub mod core;
 pub mod data;
 pub mod error;
 pub mod grpc;
+pub mod metrics;
+#[cfg(feature = "quic")]
+pub mod quic;
 
 /// Version of the protocol implemented by this crate.
 ///
@@ -0,0 +1,274 @@
+//! Bandwidth accounting of the node service.
+//!
+//! The gRPC server built with a [`Metrics`] implementation reports every
+//! message it receives or sends, with the method of the service it belongs
+//! to and the peer it was exchanged with, when the transport provides its
+//! address. The size of a message is its protobuf encoding, without the
+//! framing of gRPC and HTTP/2.
+//!
+//! [`BandwidthCounters`] is a simple implementation adding up the messages
+//! and their sizes by method and by peer.
+
+use crate::data::Peer;
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::sync::{Arc, Mutex};
+
+/// The services of the node, as implemented with the traits of
+/// [`core::server`](crate::core::server).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Service {
+    Node,
+    Block,
+    Fragment,
+    Gossip,
+}
+
+/// The methods of the node service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    Handshake,
+    ClientAuth,
+    KeepAlive,
+    Tip,
+    Peers,
+    GetBlocks,
+    GetHeaders,
+    GetFragments,
+    PullHeaders,
+    PullBlocks,
+    PullBlocksToTip,
+    PushHeaders,
+    UploadBlocks,
+    BlockSubscription,
+    HeaderSubscription,
+    FragmentSubscription,
+    GossipSubscription,
+}
+
+impl Method {
+    /// The service implementing the method.
+    pub fn service(self) -> Service {
+        use Method::*;
+        match self {
+            Handshake | ClientAuth | KeepAlive => Service::Node,
+            Tip | GetBlocks | GetHeaders | PullHeaders | PullBlocks | PullBlocksToTip
+            | PushHeaders | UploadBlocks | BlockSubscription | HeaderSubscription => Service::Block,
+            GetFragments | FragmentSubscription => Service::Fragment,
+            Peers | GossipSubscription => Service::Gossip,
+        }
+    }
+
+    /// The name of the method in the protocol definition.
+    pub fn as_str(self) -> &'static str {
+        use Method::*;
+        match self {
+            Handshake => "Handshake",
+            ClientAuth => "ClientAuth",
+            KeepAlive => "KeepAlive",
+            Tip => "Tip",
+            Peers => "Peers",
+            GetBlocks => "GetBlocks",
+            GetHeaders => "GetHeaders",
+            GetFragments => "GetFragments",
+            PullHeaders => "PullHeaders",
+            PullBlocks => "PullBlocks",
+            PullBlocksToTip => "PullBlocksToTip",
+            PushHeaders => "PushHeaders",
+            UploadBlocks => "UploadBlocks",
+            BlockSubscription => "BlockSubscription",
+            HeaderSubscription => "HeaderSubscription",
+            FragmentSubscription => "FragmentSubscription",
+            GossipSubscription => "GossipSubscription",
+        }
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Direction of a message, as seen by the node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// Receiver of the instrumentation of the node service.
+///
+/// The methods are called from the tasks serving the connections, an
+/// implementation should not block.
+pub trait Metrics: Debug + Send + Sync {
+    /// A message of `bytes` bytes was exchanged with the peer, if known,
+    /// by the method in the given direction.
+    fn record_message(
+        &self,
+        peer: Option<&Peer>,
+        method: Method,
+        direction: Direction,
+        bytes: usize,
+    );
+}
+
+/// Number of messages and their total size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Traffic {
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+impl Traffic {
+    fn add(&mut self, bytes: usize) {
+        self.messages += 1;
+        self.bytes += bytes as u64;
+    }
+}
+
+/// Counters of the traffic by method and by peer.
+#[derive(Debug, Default)]
+pub struct BandwidthCounters {
+    by_method: Mutex<HashMap<(Method, Direction), Traffic>>,
+    by_peer: Mutex<HashMap<(Peer, Direction), Traffic>>,
+}
+
+impl BandwidthCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The traffic of every method which exchanged messages so far.
+    pub fn by_method(&self) -> HashMap<(Method, Direction), Traffic> {
+        self.by_method.lock().unwrap().clone()
+    }
+
+    /// The traffic of every known peer which exchanged messages so far.
+    pub fn by_peer(&self) -> HashMap<(Peer, Direction), Traffic> {
+        self.by_peer.lock().unwrap().clone()
+    }
+
+    /// The traffic of all the methods of the service.
+    pub fn service_total(&self, service: Service, direction: Direction) -> Traffic {
+        self.by_method
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((method, dir), _)| method.service() == service && *dir == direction)
+            .fold(Traffic::default(), |total, (_, traffic)| Traffic {
+                messages: total.messages + traffic.messages,
+                bytes: total.bytes + traffic.bytes,
+            })
+    }
+}
+
+impl Metrics for BandwidthCounters {
+    fn record_message(
+        &self,
+        peer: Option<&Peer>,
+        method: Method,
+        direction: Direction,
+        bytes: usize,
+    ) {
+        self.by_method
+            .lock()
+            .unwrap()
+            .entry((method, direction))
+            .or_default()
+            .add(bytes);
+        if let Some(peer) = peer {
+            self.by_peer
+                .lock()
+                .unwrap()
+                .entry((peer.clone(), direction))
+                .or_default()
+                .add(bytes);
+        }
+    }
+}
+
+/// The instrumentation point of a stream of messages.
+#[derive(Clone, Debug)]
+pub(crate) struct Meter {
+    metrics: Arc<dyn Metrics>,
+    peer: Option<Peer>,
+    method: Method,
+    direction: Direction,
+}
+
+impl Meter {
+    pub(crate) fn new(
+        metrics: Arc<dyn Metrics>,
+        peer: Option<Peer>,
+        method: Method,
+        direction: Direction,
+    ) -> Self {
+        Meter {
+            metrics,
+            peer,
+            method,
+            direction,
+        }
+    }
+
+    pub(crate) fn record<M: prost::Message>(&self, message: &M) {
+        self.metrics.record_message(
+            self.peer.as_ref(),
+            self.method,
+            self.direction,
+            message.encoded_len(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traffic_is_counted_by_method_and_peer() {
+        let counters = BandwidthCounters::new();
+        let peer = Peer::from("192.0.2.1:3000".parse::<std::net::SocketAddr>().unwrap());
+        counters.record_message(Some(&peer), Method::PullBlocks, Direction::Outbound, 100);
+        counters.record_message(Some(&peer), Method::PullBlocks, Direction::Outbound, 50);
+        counters.record_message(Some(&peer), Method::Tip, Direction::Inbound, 2);
+        counters.record_message(None, Method::Peers, Direction::Outbound, 10);
+
+        let by_method = counters.by_method();
+        assert_eq!(
+            by_method[&(Method::PullBlocks, Direction::Outbound)],
+            Traffic {
+                messages: 2,
+                bytes: 150
+            }
+        );
+        assert_eq!(by_method.len(), 3);
+
+        let by_peer = counters.by_peer();
+        assert_eq!(
+            by_peer[&(peer.clone(), Direction::Outbound)],
+            Traffic {
+                messages: 2,
+                bytes: 150
+            }
+        );
+        assert_eq!(by_peer.len(), 2);
+
+        assert_eq!(
+            counters.service_total(Service::Block, Direction::Outbound),
+            Traffic {
+                messages: 2,
+                bytes: 150
+            }
+        );
+        assert_eq!(
+            counters.service_total(Service::Gossip, Direction::Outbound),
+            Traffic {
+                messages: 1,
+                bytes: 10
+            }
+        );
+    }
+}
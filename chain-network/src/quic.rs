@@ -0,0 +1,122 @@
+//! Adapters to run the node-to-node services over QUIC.
+//!
+//! A QUIC library provides each bidirectional stream as a pair of halves,
+//! one to send and one to receive. [`BiStream`] joins them into a single
+//! byte stream, which can be served by the gRPC server as an incoming
+//! connection and returned by the connector of a gRPC client endpoint.
+//! Each connection between two nodes is carried by its own bidirectional
+//! stream; the QUIC library still handles the handshake, the encryption and
+//! the connection migration when the address of an edge node changes.
+//!
+//! The server is to be built with the [`Transport::Quic`] transport so that
+//! the node gets the peers with the right transport, and should advertise
+//! [`Capabilities::QUIC`] in its handshake responses.
+//!
+//! [`Transport::Quic`]: crate::data::Transport::Quic
+//! [`Capabilities::QUIC`]: crate::data::Capabilities::QUIC
+
+use pin_project::pin_project;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The send and receive halves of a bidirectional QUIC stream,
+/// used as one connection.
+#[pin_project]
+#[derive(Debug)]
+pub struct BiStream<S, R> {
+    #[pin]
+    send: S,
+    #[pin]
+    recv: R,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl<S, R> BiStream<S, R> {
+    /// `remote_addr` is the current address of the remote end of the QUIC
+    /// connection the stream belongs to.
+    pub fn new(send: S, recv: R, remote_addr: Option<SocketAddr>) -> Self {
+        BiStream {
+            send,
+            recv,
+            remote_addr,
+        }
+    }
+
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    pub fn into_inner(self) -> (S, R) {
+        (self.send, self.recv)
+    }
+}
+
+impl<S, R: AsyncRead> AsyncRead for BiStream<S, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.project().recv.poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite, R> AsyncWrite for BiStream<S, R> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().send.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().send.poll_flush(cx)
+    }
+
+    /// Finishes the sending half, the receiving half is left to the
+    /// remote end to finish.
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().send.poll_shutdown(cx)
+    }
+}
+
+#[cfg(feature = "transport")]
+impl<S, R> tonic::transport::server::Connected for BiStream<S, R> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn halves_are_joined() {
+        let (send, mut remote_recv) = duplex(64);
+        let (mut remote_send, recv) = duplex(64);
+        let addr = "192.0.2.1:3000".parse().unwrap();
+        let mut stream = BiStream::new(send, recv, Some(addr));
+        assert_eq!(stream.remote_addr(), Some(addr));
+
+        block_on(async {
+            stream.write_all(b"ping").await.unwrap();
+            stream.shutdown().await.unwrap();
+            let mut received = Vec::new();
+            remote_recv.read_to_end(&mut received).await.unwrap();
+            assert_eq!(received, b"ping");
+
+            remote_send.write_all(b"pong").await.unwrap();
+            let mut received = [0; 4];
+            stream.read_exact(&mut received).await.unwrap();
+            assert_eq!(&received, b"pong");
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
 use rand_core::{OsRng, RngCore};
 
 use chain_storage::{
@@ -9,6 +9,8 @@ use chain_storage::{
 const BLOCK_DATA_LENGTH: usize = 1024;
 const SEQ_BENCH_N_BLOCKS: u32 = 5210;
 const SEQ_BENCH_FLUSH_POINT: u32 = 4096;
+const BATCH_SIZES: [usize; 3] = [16, 128, 1024];
+const DEPTHS: [u32; 4] = [1, 64, 1024, 4096];
 
 fn basic_benchmark(c: &mut Criterion) {
     let mut rng = OsRng;
@@ -184,5 +186,157 @@ fn seq_read_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, basic_benchmark, seq_read_benchmark);
+// a file store with a chain of `n` blocks after the genesis block, the
+// tempdir has to be kept for the lifetime of the store
+fn prepare_chain(n: u32) -> (tempfile::TempDir, BlockStore, Vec<Block>) {
+    let mut rng = OsRng;
+    let mut block_data = [0; BLOCK_DATA_LENGTH];
+
+    let tempdir = tempfile::TempDir::new().unwrap();
+    let path = {
+        let mut path = tempdir.path().to_path_buf();
+        path.push("test");
+        path
+    };
+    let store = BlockStore::file(path, BlockId(0).serialize_as_vec()).unwrap();
+
+    rng.fill_bytes(&mut block_data);
+    let mut blocks = vec![Block::genesis(Some(Box::new(block_data)))];
+    for _i in 0..n {
+        rng.fill_bytes(&mut block_data);
+        let block = blocks
+            .last()
+            .unwrap()
+            .make_child(Some(Box::new(block_data)));
+        blocks.push(block);
+    }
+
+    let serialized: Vec<_> = blocks.iter().map(Block::serialize_as_vec).collect();
+    let batch: Vec<_> = blocks
+        .iter()
+        .zip(serialized.iter())
+        .map(|(block, data)| (&data[..], block_info(block)))
+        .collect();
+    store.put_blocks(&batch).unwrap();
+
+    (tempdir, store, blocks)
+}
+
+fn block_info(block: &Block) -> BlockInfo {
+    BlockInfo::new(
+        block.id.serialize_as_vec(),
+        block.parent.serialize_as_vec(),
+        block.chain_length,
+    )
+}
+
+// a run of `n` blocks following the block
+fn make_run(parent: &Block, n: usize) -> Vec<(Vec<u8>, BlockInfo)> {
+    let mut rng = OsRng;
+    let mut block_data = [0; BLOCK_DATA_LENGTH];
+    let mut parent = parent.clone();
+    (0..n)
+        .map(|_| {
+            rng.fill_bytes(&mut block_data);
+            let block = parent.make_child(Some(Box::new(block_data)));
+            let run_entry = (block.serialize_as_vec(), block_info(&block));
+            parent = block;
+            run_entry
+        })
+        .collect()
+}
+
+fn put_blocks_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("put_run");
+    for n in BATCH_SIZES.iter().copied() {
+        group.throughput(Throughput::Elements(n as u64));
+
+        let (_tempdir, store, blocks) = prepare_chain(0);
+        let genesis = blocks[0].clone();
+        group.bench_with_input(BenchmarkId::new("put_block", n), &n, |b, &n| {
+            b.iter_batched(
+                || make_run(&genesis, n),
+                |run| {
+                    for (block, block_info) in run {
+                        store.put_block(&block, block_info).unwrap();
+                    }
+                },
+                BatchSize::PerIteration,
+            )
+        });
+
+        let (_tempdir, store, blocks) = prepare_chain(0);
+        let genesis = blocks[0].clone();
+        group.bench_with_input(BenchmarkId::new("put_blocks", n), &n, |b, &n| {
+            b.iter_batched(
+                || make_run(&genesis, n),
+                |run| {
+                    let batch: Vec<_> = run
+                        .iter()
+                        .map(|(block, block_info)| (&block[..], block_info.clone()))
+                        .collect();
+                    store.put_blocks(&batch).unwrap();
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn ancestor_benchmark(c: &mut Criterion) {
+    let (_tempdir, store, blocks) = prepare_chain(SEQ_BENCH_N_BLOCKS);
+    let tip_id = blocks.last().unwrap().id.serialize_as_vec();
+
+    let mut group = c.benchmark_group("get_nth_ancestor");
+    for depth in DEPTHS.iter().copied() {
+        group.bench_with_input(BenchmarkId::new("volatile", depth), &depth, |b, &depth| {
+            b.iter(|| store.get_nth_ancestor(&tip_id, depth).unwrap())
+        });
+    }
+
+    store
+        .flush_to_permanent_store(
+            &blocks[SEQ_BENCH_FLUSH_POINT as usize].id.serialize_as_vec(),
+            1,
+        )
+        .unwrap();
+    for depth in DEPTHS.iter().copied() {
+        group.bench_with_input(BenchmarkId::new("mixed", depth), &depth, |b, &depth| {
+            b.iter(|| store.get_nth_ancestor(&tip_id, depth).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn range_iter_benchmark(c: &mut Criterion) {
+    let (_tempdir, store, blocks) = prepare_chain(SEQ_BENCH_N_BLOCKS);
+    let tip_id = blocks.last().unwrap().id.serialize_as_vec();
+
+    let mut group = c.benchmark_group("iter_range");
+    for distance in DEPTHS.iter().copied() {
+        group.throughput(Throughput::Elements(distance as u64));
+        group.bench_with_input(
+            BenchmarkId::new("volatile", distance),
+            &distance,
+            |b, &distance| {
+                b.iter(|| {
+                    for block_res in store.iter(&tip_id, distance).unwrap() {
+                        let _block = block_res.unwrap();
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    basic_benchmark,
+    seq_read_benchmark,
+    put_blocks_benchmark,
+    ancestor_benchmark,
+    range_iter_benchmark
+);
 criterion_main!(benches);
@@ -0,0 +1,181 @@
+//! Asynchronous access to the store for the consumers running on tokio.
+
+use crate::{BlockInfo, BlockStore, Error, StorageIterator, Value};
+use tokio::{sync::mpsc, task};
+
+/// The number of blocks read ahead by an `AsyncStorageIterator`.
+const ITER_BUFFER: usize = 32;
+
+/// A `BlockStore` whose operations run on the blocking thread pool of tokio,
+/// so that they do not stall the tasks of the runtime.
+///
+/// The methods mirror the ones of `BlockStore` and must be called from
+/// within a tokio runtime. Cloning the store is cheap, the clones share the
+/// same underlying storage.
+#[derive(Clone)]
+pub struct AsyncBlockStore {
+    inner: BlockStore,
+}
+
+impl AsyncBlockStore {
+    pub fn new(inner: BlockStore) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped synchronous store.
+    pub fn blocking(&self) -> &BlockStore {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> BlockStore {
+        self.inner
+    }
+
+    /// Run an operation on the synchronous store from the blocking thread
+    /// pool, for the operations without an asynchronous counterpart.
+    ///
+    /// A panic of the operation is propagated to the caller.
+    pub async fn spawn_blocking<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&BlockStore) -> Result<R, Error> + Send + 'static,
+        R: Send + 'static,
+    {
+        let store = self.inner.clone();
+        match task::spawn_blocking(move || f(&store)).await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Err(Error::Cancelled),
+        }
+    }
+
+    pub async fn sync(&self) -> Result<(), Error> {
+        self.spawn_blocking(|store| store.sync()).await
+    }
+
+    pub async fn put_block(&self, block: Vec<u8>, block_info: BlockInfo) -> Result<(), Error> {
+        self.spawn_blocking(move |store| store.put_block(&block, block_info))
+            .await
+    }
+
+    pub async fn put_blocks(&self, blocks: Vec<(Vec<u8>, BlockInfo)>) -> Result<(), Error> {
+        self.spawn_blocking(move |store| {
+            let blocks: Vec<_> = blocks
+                .iter()
+                .map(|(block, block_info)| (&block[..], block_info.clone()))
+                .collect();
+            store.put_blocks(&blocks)
+        })
+        .await
+    }
+
+    pub async fn get_block(&self, block_id: Vec<u8>) -> Result<Value, Error> {
+        self.spawn_blocking(move |store| store.get_block(&block_id))
+            .await
+    }
+
+    pub async fn get_block_info(&self, block_id: Vec<u8>) -> Result<BlockInfo, Error> {
+        self.spawn_blocking(move |store| store.get_block_info(&block_id))
+            .await
+    }
+
+    pub async fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
+        self.spawn_blocking(move |store| store.get_blocks_by_chain_length(chain_length))
+            .await
+    }
+
+    pub async fn put_tag(&self, tag_name: String, block_id: Vec<u8>) -> Result<(), Error> {
+        self.spawn_blocking(move |store| store.put_tag(&tag_name, &block_id))
+            .await
+    }
+
+    pub async fn get_tag(&self, tag_name: String) -> Result<Option<Value>, Error> {
+        self.spawn_blocking(move |store| store.get_tag(&tag_name))
+            .await
+    }
+
+    pub async fn get_tips_ids(&self) -> Result<Vec<Value>, Error> {
+        self.spawn_blocking(|store| store.get_tips_ids()).await
+    }
+
+    pub async fn get_branches(&self) -> Result<Vec<Value>, Error> {
+        self.spawn_blocking(|store| store.get_branches()).await
+    }
+
+    pub async fn get_children(&self, block_id: Vec<u8>) -> Result<Vec<Value>, Error> {
+        self.spawn_blocking(move |store| store.get_children(&block_id))
+            .await
+    }
+
+    pub async fn block_exists(&self, block_id: Vec<u8>) -> Result<bool, Error> {
+        self.spawn_blocking(move |store| store.block_exists(&block_id))
+            .await
+    }
+
+    pub async fn flush_to_permanent_store(
+        &self,
+        to_block: Vec<u8>,
+        min_number: usize,
+    ) -> Result<usize, Error> {
+        self.spawn_blocking(move |store| store.flush_to_permanent_store(&to_block, min_number))
+            .await
+    }
+
+    /// Iterate over the blocks like `BlockStore::iter`. The blocks are read
+    /// ahead from the blocking thread pool and the iterator can be held
+    /// across await points.
+    pub async fn iterate_range(
+        &self,
+        to_block: Vec<u8>,
+        distance: u32,
+    ) -> Result<AsyncStorageIterator, Error> {
+        let iter = self
+            .spawn_blocking(move |store| store.iter(&to_block, distance))
+            .await?;
+        Ok(AsyncStorageIterator::spawn(iter))
+    }
+
+    /// Iterate over the blocks like `BlockStore::iter_from_checkpoints`, see
+    /// `iterate_range`.
+    pub async fn iterate_from_checkpoints(
+        &self,
+        checkpoints: Vec<Vec<u8>>,
+        to_block: Vec<u8>,
+    ) -> Result<AsyncStorageIterator, Error> {
+        let iter = self
+            .spawn_blocking(move |store| {
+                let checkpoints: Vec<_> = checkpoints.iter().map(|id| &id[..]).collect();
+                store.iter_from_checkpoints(&checkpoints, &to_block)
+            })
+            .await?;
+        Ok(AsyncStorageIterator::spawn(iter))
+    }
+}
+
+fn read_ahead(iter: StorageIterator, tx: mpsc::Sender<Result<Value, Error>>) {
+    for item in iter {
+        let failed = item.is_err();
+        // stop reading once the iterator is dropped
+        if tx.blocking_send(item).is_err() || failed {
+            break;
+        }
+    }
+}
+
+/// Asynchronous iterator over blocks, see `AsyncBlockStore::iterate_range`.
+pub struct AsyncStorageIterator {
+    rx: mpsc::Receiver<Result<Value, Error>>,
+}
+
+impl AsyncStorageIterator {
+    fn spawn(iter: StorageIterator) -> Self {
+        let (tx, rx) = mpsc::channel(ITER_BUFFER);
+        task::spawn_blocking(move || read_ahead(iter, tx));
+        Self { rx }
+    }
+
+    /// The next block, `None` once the iteration is over. The iteration
+    /// stops after the first error.
+    pub async fn next(&mut self) -> Option<Result<Value, Error>> {
+        self.rx.recv().await
+    }
+}
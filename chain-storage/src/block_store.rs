@@ -1,5 +1,8 @@
+#[cfg(feature = "encryption")]
+use crate::encryption::{KeyId, Keyring, KEY_SIZE};
 use crate::{
-    permanent_store::PermanentStore, BlockInfo, ConsistencyFailure, Error, StorageIterator, Value,
+    encryption::BlockCipher, group_commit::GroupCommit, permanent_store::PermanentStore, trace,
+    BlockInfo, ConsistencyFailure, Error, StorageIterator, Value,
 };
 use sled::{
     transaction::{
@@ -7,7 +10,7 @@ use sled::{
     },
     Tree,
 };
-use std::path::Path;
+use std::{path::Path, sync::Arc, time::Duration};
 
 #[derive(Clone)]
 pub struct BlockStore {
@@ -22,7 +25,23 @@ pub struct BlockStore {
     tags_tree: Tree,
 
     // needs to be kept so that the database is always closed correctly
-    _db: sled::Db,
+    db: Arc<VolatileDb>,
+    // set when the blocks are durable once `put_block` returns
+    group_commit: Option<Arc<GroupCommit>>,
+    // seals the blocks when they are encrypted at rest
+    cipher: BlockCipher,
+}
+
+// Flushes the volatile storage when the last handle of the store is dropped,
+// so that the files of a closed store are complete and can be copied.
+struct VolatileDb(sled::Db);
+
+impl Drop for VolatileDb {
+    fn drop(&mut self) {
+        // nothing can be reported from here, the data which could not be
+        // flushed is recovered from the log by the next opening
+        let _ = self.0.flush();
+    }
 }
 
 enum RemoveTipResult {
@@ -85,6 +104,14 @@ impl BlockStore {
     /// * `path` - a path to the storage directory.
     /// * `root_id` - the ID of the root block which the first block in this
     ///   block chain should refer to as a parent.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(path, root_id),
+            fields(path = %path.as_ref().display()),
+        )
+    )]
     pub fn file<P: AsRef<Path>, I: Into<Value> + Clone>(
         path: P,
         root_id: I,
@@ -146,10 +173,102 @@ impl BlockStore {
             branches_tips_tree,
             tags_tree,
 
-            _db: volatile,
+            db: Arc::new(VolatileDb(volatile)),
+            group_commit: None,
+            cipher: BlockCipher::default(),
         })
     }
 
+    /// Make the blocks durable before `put_block` returns.
+    ///
+    /// The volatile storage is flushed at most once per `commit_latency` by
+    /// the concurrent calls to `put_block`: a call waits for the flush in
+    /// progress, or waits for `commit_latency` for other writes to join
+    /// before flushing. A zero latency only groups the writes arriving during
+    /// a flush. Without this option the writes are flushed in the background
+    /// and on [`sync`](Self::sync).
+    ///
+    /// The option is shared by the handles cloned from the returned one.
+    pub fn with_commit_latency(mut self, commit_latency: Duration) -> Self {
+        self.group_commit = Some(Arc::new(GroupCommit::new(commit_latency)));
+        self
+    }
+
+    /// Encrypt the blocks at rest with the keys of `keyring`, see the
+    /// [`encryption`](crate::encryption) module. The store must have been
+    /// encrypted since its creation.
+    ///
+    /// The keyring is shared by the handles cloned from the returned one.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(mut self, keyring: Keyring) -> Self {
+        self.cipher = BlockCipher::new(keyring);
+        self
+    }
+
+    /// Seal the new blocks with the given key, and seal again with it the
+    /// blocks of the volatile storage. The blocks of the permanent storage
+    /// stay sealed with their key, which must stay in the keyring to read
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// `Error::NotEncrypted` if the store was not opened with
+    /// [`with_encryption`](Self::with_encryption).
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks sealed again.
+    #[cfg(feature = "encryption")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, key),
+            fields(rows = tracing::field::Empty),
+        )
+    )]
+    pub fn rotate_encryption_key(
+        &self,
+        key_id: KeyId,
+        key: [u8; KEY_SIZE],
+    ) -> Result<usize, Error> {
+        self.cipher.rotate(key_id, key)?;
+
+        let mut resealed = 0;
+        for block in self.blocks_tree.iter() {
+            let (id, sealed) = block?;
+            let block = match self.cipher.reseal(&id, &sealed)? {
+                Some(block) => block,
+                None => continue,
+            };
+            // the block was removed or written again with the current key
+            // in the meantime if the swap fails
+            if self
+                .blocks_tree
+                .compare_and_swap(&id, Some(&sealed), Some(block))?
+                .is_ok()
+            {
+                resealed += 1;
+            }
+        }
+        self.sync()?;
+
+        trace::record_rows(resealed);
+        Ok(resealed)
+    }
+
+    /// Flush the pending writes of the volatile storage to disk, so that the
+    /// files can be copied while the store is open. The storage is also
+    /// flushed when the last handle of the store is dropped.
+    ///
+    /// Integrity between the blocks does not depend on this call: a block is
+    /// stored with its indexes in a single transaction, and only when its
+    /// parent is in the store.
+    pub fn sync(&self) -> Result<(), Error> {
+        self.db.0.flush()?;
+        Ok(())
+    }
+
     /// Write a block to the store. The parent of the block must exist (unless
     /// it's the root id).
     ///
@@ -158,6 +277,19 @@ impl BlockStore {
     /// * `block` - a serialized representation of a block.
     /// * `block_info` - block metadata for internal needs (indexing, linking
     ///   between blocks, etc)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block, block_info),
+            fields(
+                block_id = %trace::HexId(block_info.id().as_ref()),
+                parent_id = %trace::HexId(block_info.parent_id().as_ref()),
+                chain_length = block_info.chain_length(),
+                size = block.len(),
+            ),
+        )
+    )]
     pub fn put_block(&self, block: &[u8], block_info: BlockInfo) -> Result<(), Error> {
         if self.block_exists(block_info.id().as_ref())? {
             return Err(Error::BlockAlreadyPresent);
@@ -166,6 +298,7 @@ impl BlockStore {
         let parent_in_permanent_store = self
             .permanent
             .contains_key(block_info.parent_id().as_ref())?;
+        let block = self.cipher.seal(block_info.id().as_ref(), block);
 
         (
             &self.blocks_tree,
@@ -179,14 +312,119 @@ impl BlockStore {
                     info,
                     chain_length_to_block_ids,
                     tips,
-                    block,
+                    &block,
                     &block_info,
                     self.root_id.as_ref(),
                     self.id_length,
                     parent_in_permanent_store,
                 )
-            })
-            .map_err(Into::into)
+            })?;
+
+        if let Some(group_commit) = &self.group_commit {
+            group_commit.commit(|| self.sync())?;
+        }
+        Ok(())
+    }
+
+    /// Write a contiguous run of blocks to the store in a single
+    /// transaction, which is much faster than writing the blocks one by one
+    /// when bootstrapping a chain. Each block must be the child of the
+    /// previous one, and the parent of the first block must exist (unless
+    /// it's the root id). Either all the blocks are written or none.
+    ///
+    /// # Arguments
+    ///
+    /// * `blocks` - the serialized blocks with their metadata, in the order
+    ///   of the chain.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, blocks),
+            fields(count = blocks.len()),
+        )
+    )]
+    pub fn put_blocks(&self, blocks: &[(&[u8], BlockInfo)]) -> Result<(), Error> {
+        let ((_, first_info), rest) = match blocks.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+
+        for pair in blocks.windows(2) {
+            let (parent, child) = (&pair[0].1, &pair[1].1);
+            if child.parent_id() != parent.id() || child.chain_length() != parent.chain_length() + 1
+            {
+                return Err(Error::BlocksNotContiguous);
+            }
+        }
+        for (_, block_info) in blocks {
+            if self.block_exists(block_info.id().as_ref())? {
+                return Err(Error::BlockAlreadyPresent);
+            }
+        }
+
+        let parent_in_permanent_store = self
+            .permanent
+            .contains_key(first_info.parent_id().as_ref())?;
+        let sealed: Vec<_> = blocks
+            .iter()
+            .map(|(block, block_info)| self.cipher.seal(block_info.id().as_ref(), *block))
+            .collect();
+        // this `unwrap` will never fail because `blocks` is not empty
+        let (first_block, rest_blocks) = sealed.split_first().unwrap();
+
+        (
+            &self.blocks_tree,
+            &self.info_tree,
+            &self.chain_length_index_tree,
+            &self.branches_tips_tree,
+        )
+            .transaction(|(blocks, info, chain_length_to_block_ids, tips)| {
+                put_block_impl(
+                    blocks,
+                    info,
+                    chain_length_to_block_ids,
+                    tips,
+                    first_block,
+                    first_info,
+                    self.root_id.as_ref(),
+                    self.id_length,
+                    parent_in_permanent_store,
+                )?;
+
+                // the parents of the next blocks are written by this
+                // transaction, their metadata is updated without reading it
+                // back
+                let mut parent_info = first_info.clone();
+                for ((_, block_info), block) in rest.iter().zip(rest_blocks) {
+                    parent_info.add_parent_ref();
+                    info.insert(parent_info.id().as_ref(), parent_info.serialize()?)?;
+
+                    tips.remove(parent_info.id().as_ref())?;
+                    tips.insert(block_info.id().as_ref(), &[])?;
+
+                    chain_length_to_block_ids.insert(
+                        build_chain_length_index(
+                            block_info.chain_length(),
+                            block_info.id().as_ref(),
+                        ),
+                        &[],
+                    )?;
+
+                    blocks.insert(block_info.id().as_ref(), block.as_ref())?;
+
+                    info.insert(block_info.id().as_ref(), block_info.serialize()?)?;
+
+                    parent_info = block_info.clone();
+                }
+
+                Ok(())
+            })?;
+
+        if let Some(group_commit) = &self.group_commit {
+            group_commit.commit(|| self.sync())?;
+        }
+        Ok(())
     }
 
     /// Get a block from the storage.
@@ -194,16 +432,30 @@ impl BlockStore {
     /// # Arguments
     ///
     /// * `block_id` - the serialized block identifier.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id), size = tracing::field::Empty),
+        )
+    )]
     pub fn get_block(&self, block_id: &[u8]) -> Result<Value, Error> {
-        if let Some(block) = self.permanent.get_block(block_id)? {
-            return Ok(block);
-        }
+        let block = self.get_sealed_block(block_id)?;
+        let block = self.cipher.open(block_id, block)?;
+        trace::record_size(block.as_ref().len());
+        Ok(block)
+    }
 
-        self.blocks_tree
-            .get(block_id)
-            .map_err(Into::into)
-            .and_then(|maybe_block| maybe_block.ok_or(Error::BlockNotFound))
-            .map(Value::volatile)
+    fn get_sealed_block(&self, block_id: &[u8]) -> Result<Value, Error> {
+        match self.permanent.get_block(block_id)? {
+            Some(block) => Ok(block),
+            None => self
+                .blocks_tree
+                .get(block_id)?
+                .map(Value::volatile)
+                .ok_or(Error::BlockNotFound),
+        }
     }
 
     /// Get the `BlockInfo` instance for the requested block.
@@ -211,12 +463,21 @@ impl BlockStore {
     /// # Arguments
     ///
     /// * `block_id` - the serialized block identifier.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id), chain_length = tracing::field::Empty),
+        )
+    )]
     pub fn get_block_info(&self, block_id: &[u8]) -> Result<BlockInfo, Error> {
-        if let Some(block_info) = self.permanent.get_block_info(block_id)? {
-            return Ok(block_info);
-        }
-
-        self.get_block_info_volatile(block_id)
+        let block_info = match self.permanent.get_block_info(block_id)? {
+            Some(block_info) => block_info,
+            None => self.get_block_info_volatile(block_id)?,
+        };
+        trace::record_chain_length(block_info.chain_length());
+        Ok(block_info)
     }
 
     fn get_block_info_volatile(&self, block_id: &[u8]) -> Result<BlockInfo, Error> {
@@ -234,27 +495,51 @@ impl BlockStore {
     /// return block contents, not their IDs. If there is a block at the given
     /// chain length in the permanent storage, only this block is returned.
     /// Other branches are considered to be ready of removal if there are any.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(rows = tracing::field::Empty),
+        )
+    )]
     pub fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
         if let Some(block) = self.permanent.get_block_by_chain_length(chain_length) {
+            let block = self
+                .cipher
+                .open_permanent(&self.permanent, chain_length, block)?;
+            trace::record_rows(1);
             return Ok(vec![block]);
         }
 
-        self.chain_length_index_tree
+        let blocks = self
+            .chain_length_index_tree
             .scan_prefix(build_chain_length_index_prefix(chain_length))
             .map(|scan_result| {
-                let (block_id, _) = scan_result?;
-
-                self.blocks_tree
-                    .get(block_id_from_chain_length_index(&block_id))?
-                    .ok_or(Error::Inconsistent(ConsistencyFailure::ChainLength))
-                    .map(Value::volatile)
+                let (index, _) = scan_result?;
+                let block_id = block_id_from_chain_length_index(&index);
+
+                let block = self
+                    .blocks_tree
+                    .get(block_id)?
+                    .ok_or(Error::Inconsistent(ConsistencyFailure::ChainLength))?;
+                self.cipher.open(block_id, Value::volatile(block))
             })
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(Into::into)
+            .collect::<Result<Vec<_>, _>>()?;
+        trace::record_rows(blocks.len());
+        Ok(blocks)
     }
 
     /// Add a tag for a given block. The block id can be later retrieved by this
     /// tag.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id)),
+        )
+    )]
     pub fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
         let permanent_store_index = self.permanent.block_id_index();
 
@@ -272,7 +557,46 @@ impl BlockStore {
             .map_err(Into::into)
     }
 
+    /// Set the tag to the given block if it currently points to `expected`,
+    /// or does not exist when `expected` is `None`. The check and the update
+    /// are done in one transaction, so that concurrent writers of the same
+    /// tag cannot silently overwrite each other: [`Error::TagChanged`] is
+    /// returned and the tag is left as is if it was changed in the meantime.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, expected, block_id),
+            fields(block_id = %trace::HexId(block_id)),
+        )
+    )]
+    pub fn compare_and_set_tag(
+        &self,
+        tag_name: &str,
+        expected: Option<&[u8]>,
+        block_id: &[u8],
+    ) -> Result<(), Error> {
+        let permanent_store_index = self.permanent.block_id_index();
+
+        (&self.info_tree, &self.tags_tree, permanent_store_index)
+            .transaction(move |(info, tags, permanent_store_index)| {
+                if tags.get(tag_name)?.as_deref() != expected {
+                    return Err(Error::TagChanged.into());
+                }
+                put_tag_impl(
+                    info,
+                    tags,
+                    permanent_store_index,
+                    tag_name,
+                    block_id,
+                    self.id_length,
+                )
+            })
+            .map_err(Into::into)
+    }
+
     /// Get the block ID for the given tag.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self),))]
     pub fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error> {
         self.tags_tree
             .get(tag_name)
@@ -280,16 +604,89 @@ impl BlockStore {
             .map_err(Into::into)
     }
 
-    /// Get identifier of all branches tips.
+    /// Get identifier of all branches tips: the heads of the known branches,
+    /// which are the blocks without children.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(rows = tracing::field::Empty),
+        )
+    )]
     pub fn get_tips_ids(&self) -> Result<Vec<Value>, Error> {
-        self.branches_tips_tree
+        let tips = self
+            .branches_tips_tree
             .iter()
             .map(|id_result| id_result.map(|(id, _)| Value::volatile(id)))
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(Into::into)
+            .collect::<Result<Vec<_>, _>>()?;
+        trace::record_rows(tips.len());
+        Ok(tips)
+    }
+
+    /// Get the identifiers of the heads of all the known branches, the blocks
+    /// without children. This is the same as `get_tips_ids`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn get_branches(&self) -> Result<Vec<Value>, Error> {
+        self.get_tips_ids()
+    }
+
+    /// Get the identifiers of the children of a block, the first blocks of
+    /// the branches following it. The tips of the branches have no children.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_id` - the serialized block identifier.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id), rows = tracing::field::Empty),
+        )
+    )]
+    pub fn get_children(&self, block_id: &[u8]) -> Result<Vec<Value>, Error> {
+        let chain_length = self.get_block_info(block_id)?.chain_length() + 1;
+        let mut children = Vec::new();
+
+        if let Some(block_info) = self
+            .permanent
+            .get_block_info_by_chain_length(chain_length)?
+        {
+            if block_info.parent_id().as_ref() == block_id {
+                children.push(block_info.id().clone());
+            }
+        }
+
+        for scan_result in self
+            .chain_length_index_tree
+            .scan_prefix(build_chain_length_index_prefix(chain_length))
+        {
+            let (index, _) = scan_result?;
+            let block_info = self
+                .get_block_info_volatile(block_id_from_chain_length_index(&index))
+                .map_err(|err| match err {
+                    Error::BlockNotFound => ConsistencyFailure::ChainLength.into(),
+                    err => err,
+                })?;
+            if block_info.parent_id().as_ref() == block_id {
+                children.push(block_info.id().clone());
+            }
+        }
+
+        trace::record_rows(children.len());
+        Ok(children)
     }
 
     /// Prune a branch with the given tip id from the storage.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, tip_id),
+            fields(tip_id = %trace::HexId(tip_id)),
+        )
+    )]
     pub fn prune_branch(&self, tip_id: &[u8]) -> Result<(), Error> {
         if !self.branches_tips_tree.contains_key(tip_id)? {
             return Err(Error::BranchNotFound);
@@ -342,7 +739,170 @@ impl BlockStore {
         Ok(())
     }
 
+    /// Prune every branch which does not lead to the block with the given
+    /// id, typically the last block of the chain that cannot be rolled back
+    /// anymore. The branches of the tagged blocks are kept, up to the tagged
+    /// block.
+    ///
+    /// # Returns
+    ///
+    /// The number of branches that were removed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id), rows = tracing::field::Empty),
+        )
+    )]
+    pub fn prune_stale_branches(&self, block_id: &[u8]) -> Result<usize, Error> {
+        // fail early if the block does not exist
+        self.get_block_info(block_id)?;
+
+        let mut pruned = 0;
+        for tip_id in self.get_tips_ids()? {
+            if self.is_ancestor(block_id, tip_id.as_ref())?.is_some() {
+                continue;
+            }
+            self.prune_branch(tip_id.as_ref())?;
+            if !self.branches_tips_tree.contains_key(tip_id.as_ref())? {
+                pruned += 1;
+            }
+        }
+
+        trace::record_rows(pruned);
+        Ok(pruned)
+    }
+
+    /// Remove from the volatile storage every block older than the block with
+    /// the given id which is not one of its ancestors, along with the blocks
+    /// built on top of them. Unlike
+    /// [`prune_stale_branches`](Self::prune_stale_branches), the tagged blocks
+    /// are removed too, and so are the tags pointing to them.
+    ///
+    /// The ancestors of the block are kept: they are the history of the
+    /// chain, which is moved to the append-only permanent storage by
+    /// [`flush_to_permanent_store`](Self::flush_to_permanent_store). The
+    /// parents of the remaining blocks are therefore all stored, and only the
+    /// reference counts of the ancestors the removed branches were forking
+    /// from are updated.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks that were removed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id), rows = tracing::field::Empty),
+        )
+    )]
+    pub fn prune_before(&self, block_id: &[u8]) -> Result<usize, Error> {
+        use std::collections::HashSet;
+
+        let block_info = self.get_block_info(block_id)?;
+
+        let mut ancestors = HashSet::new();
+        let mut current_id = block_info.parent_id().clone();
+        while let Some(parent_info) = self
+            .get_block_info_volatile(current_id.as_ref())
+            .map(Some)
+            .or_else(|err| match err {
+                Error::BlockNotFound => Ok(None),
+                e => Err(e),
+            })?
+        {
+            ancestors.insert(parent_info.id().as_ref().to_vec());
+            current_id = parent_info.parent_id().clone();
+        }
+
+        // the index is sorted by chain length, so the parent of a block is
+        // always visited before it
+        let mut pruned: Vec<BlockInfo> = Vec::new();
+        let mut pruned_ids = HashSet::new();
+        for index in self.chain_length_index_tree.iter().keys() {
+            let index = index?;
+            let id = block_id_from_chain_length_index(&index);
+            let info = self.get_block_info_volatile(id).map_err(|err| match err {
+                Error::BlockNotFound => ConsistencyFailure::ChainLength.into(),
+                err => err,
+            })?;
+            let older = info.chain_length() < block_info.chain_length();
+            if (older && !ancestors.contains(id)) || pruned_ids.contains(info.parent_id().as_ref())
+            {
+                pruned_ids.insert(id.to_vec());
+                pruned.push(info);
+            }
+        }
+
+        let mut pruned_tags = Vec::new();
+        for tag in self.tags_tree.iter() {
+            let (tag_name, tagged_id) = tag?;
+            if pruned_ids.contains(tagged_id.as_ref()) {
+                pruned_tags.push(tag_name);
+            }
+        }
+
+        (
+            &self.blocks_tree,
+            &self.info_tree,
+            &self.chain_length_index_tree,
+            &self.branches_tips_tree,
+            &self.tags_tree,
+        )
+            .transaction(|(blocks, info, chain_length_to_block_ids, tips, tags)| {
+                for tag_name in pruned_tags.iter() {
+                    let still_pruned = tags
+                        .get(tag_name)?
+                        .map_or(false, |tagged_id| pruned_ids.contains(tagged_id.as_ref()));
+                    if still_pruned {
+                        tags.remove(tag_name.clone())?;
+                    }
+                }
+
+                for block_info in pruned.iter() {
+                    let id = block_info.id().as_ref();
+                    info.remove(id)?;
+                    blocks.remove(id)?;
+                    chain_length_to_block_ids
+                        .remove(build_chain_length_index(block_info.chain_length(), id))?;
+                    tips.remove(id)?;
+
+                    // the branch was forking from an ancestor, which keeps
+                    // the child leading to the block
+                    let parent_id = block_info.parent_id();
+                    if !ancestors.contains(parent_id.as_ref()) {
+                        continue;
+                    }
+                    let parent_info_bin = info
+                        .get(parent_id.as_ref())?
+                        .ok_or(ConsistencyFailure::MissingParentBlock)?;
+                    let mut parent_info = BlockInfo::deserialize(
+                        &parent_info_bin[..],
+                        self.id_length,
+                        parent_id.clone(),
+                    )?;
+                    parent_info.remove_parent_ref();
+                    info.insert(parent_id.as_ref(), parent_info.serialize()?)?;
+                }
+
+                Ok::<_, ConflictableTransactionError<Error>>(())
+            })?;
+
+        trace::record_rows(pruned.len());
+        Ok(pruned.len())
+    }
+
     /// Check if the block with the given id exists.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id)),
+        )
+    )]
     pub fn block_exists(&self, block_id: &[u8]) -> Result<bool, Error> {
         if self.permanent.contains_key(block_id)? {
             return Ok(true);
@@ -362,6 +922,17 @@ impl BlockStore {
     ///   are `dist` blocks between them
     /// * `Ok(None)` - `ancestor` is not ancestor of `descendant`
     /// * `Err(error)` - `ancestor` or `descendant` was not found
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, ancestor_id, descendant_id),
+            fields(
+                ancestor_id = %trace::HexId(ancestor_id),
+                descendant_id = %trace::HexId(descendant_id),
+            ),
+        )
+    )]
     pub fn is_ancestor(
         &self,
         ancestor_id: &[u8],
@@ -431,6 +1002,14 @@ impl BlockStore {
 
     /// Get n-th (n = `distance`) ancestor of the block, identified by
     /// `block_id`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, block_id),
+            fields(block_id = %trace::HexId(block_id), chain_length = tracing::field::Empty),
+        )
+    )]
     pub fn get_nth_ancestor(&self, block_id: &[u8], distance: u32) -> Result<BlockInfo, Error> {
         let mut current = self.get_block_info(block_id)?;
 
@@ -442,6 +1021,7 @@ impl BlockStore {
 
         // if target is in the permanent storage it is always an ancestor
         if let Some(info) = self.permanent.get_block_info_by_chain_length(target)? {
+            trace::record_chain_length(info.chain_length());
             return Ok(info);
         }
 
@@ -464,6 +1044,7 @@ impl BlockStore {
             current = self.get_block_info_volatile(current.parent_id().as_ref())?;
         }
 
+        trace::record_chain_length(current.chain_length());
         Ok(current)
     }
 
@@ -485,6 +1066,14 @@ impl BlockStore {
     /// # Returns
     ///
     /// The number of blocks that were flushed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, to_block),
+            fields(to_block = %trace::HexId(to_block), rows = tracing::field::Empty),
+        )
+    )]
     pub fn flush_to_permanent_store(
         &self,
         to_block: &[u8],
@@ -547,10 +1136,12 @@ impl BlockStore {
             return Ok(0);
         }
 
+        // the blocks are moved as they are stored, sealed if they are
+        // encrypted
         let blocks = block_infos
             .iter()
             .rev()
-            .map(|block_info| self.get_block(block_info.id().as_ref()))
+            .map(|block_info| self.get_sealed_block(block_info.id().as_ref()))
             .collect::<Result<Vec<_>, Error>>()?;
         let block_refs: Vec<_> = blocks.iter().map(|block| block.as_ref()).collect();
         let ids: Vec<_> = block_infos
@@ -573,24 +1164,353 @@ impl BlockStore {
                 .remove(build_chain_length_index(chain_length, key))?;
         }
 
+        trace::record_rows(block_infos.len());
         Ok(block_infos.len())
     }
 
     /// Iterate to the given block starting from the block at the given
     /// `distance - 1`. `distance == 1` means that only `to_block` will be
     /// iterated. `distance == 0` means empty iterator.
-    pub fn iter(
-        &self,
-        to_block: &[u8],
-        distance: u32,
-    ) -> Result<impl Iterator<Item = Result<Value, Error>>, Error> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, to_block),
+            fields(to_block = %trace::HexId(to_block)),
+        )
+    )]
+    pub fn iter(&self, to_block: &[u8], distance: u32) -> Result<StorageIterator, Error> {
         StorageIterator::new(
             Value::from(to_block.to_vec()),
             distance,
             self.permanent.clone(),
             self.info_tree.clone(),
             self.blocks_tree.clone(),
+            self.cipher.clone(),
+        )
+    }
+
+    /// Iterate to the given block starting from the block following the
+    /// closest of the checkpoints which is an ancestor of `to_block`, or
+    /// `to_block` itself. The checkpoints which are not stored are ignored,
+    /// except the root id which starts the iteration at the genesis block.
+    ///
+    /// Unlike calling `is_ancestor` for every checkpoint, the ancestors of
+    /// `to_block` are walked at most once.
+    ///
+    /// # Errors
+    ///
+    /// `Error::CheckpointNotFound` if none of the checkpoints is an ancestor
+    /// of `to_block`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, checkpoints, to_block),
+            fields(checkpoints = checkpoints.len(), to_block = %trace::HexId(to_block)),
+        )
+    )]
+    pub fn iter_from_checkpoints(
+        &self,
+        checkpoints: &[&[u8]],
+        to_block: &[u8],
+    ) -> Result<StorageIterator, Error> {
+        let tip = self.get_block_info(to_block)?;
+
+        let mut lowest_chain_length: Option<u32> = None;
+        let mut permanent_chain_lengths = Vec::new();
+        for checkpoint in checkpoints {
+            let block_info = match self.get_block_info(checkpoint) {
+                Ok(block_info) => block_info,
+                Err(Error::BlockNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            let chain_length = block_info.chain_length();
+            lowest_chain_length =
+                Some(lowest_chain_length.map_or(chain_length, |l| l.min(chain_length)));
+            if self.permanent.contains_key(checkpoint)? {
+                permanent_chain_lengths.push(chain_length);
+            }
+        }
+
+        let mut current = tip.clone();
+        let mut distance = 0;
+        let found = loop {
+            if checkpoints.contains(&current.id().as_ref()) {
+                break Some(distance);
+            }
+
+            // the permanent storage holds a single chain, the closest
+            // checkpoint stored there is the longest one
+            if self.permanent.contains_key(current.id().as_ref())? {
+                break permanent_chain_lengths
+                    .iter()
+                    .filter(|chain_length| **chain_length < current.chain_length())
+                    .max()
+                    .map(|chain_length| distance + current.chain_length() - chain_length);
+            }
+
+            let parent_id = current.parent_id().as_ref();
+            if parent_id == self.root_id.as_ref()
+                || lowest_chain_length.map_or(true, |l| current.chain_length() <= l)
+            {
+                break None;
+            }
+
+            current = self.get_block_info(parent_id).map_err(|err| match err {
+                Error::BlockNotFound => ConsistencyFailure::MissingParentBlock.into(),
+                err => err,
+            })?;
+            distance += 1;
+        };
+
+        let distance = match found {
+            Some(distance) => distance,
+            None if checkpoints.contains(&self.root_id.as_ref()) => tip.chain_length() + 1,
+            None => return Err(Error::CheckpointNotFound),
+        };
+        self.iter(to_block, distance)
+    }
+
+    /// Export the block tree between the chain lengths `from` and `to`
+    /// (inclusive) in the Graphviz DOT format.
+    ///
+    /// Every block is a node labeled with its hexadecimal id, its chain length
+    /// and the tags pointing to it, with an edge to its parent when the parent
+    /// is in the range. Blocks of the same chain length are ranked together so
+    /// that forks show up side by side, and blocks of the permanent storage
+    /// are filled.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(rows = tracing::field::Empty),
+        )
+    )]
+    pub fn export_dot(&self, from: u32, to: u32) -> Result<String, Error> {
+        use std::collections::HashMap;
+        use std::fmt::Write;
+
+        let mut tags: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+        for tag in self.tags_tree.iter() {
+            let (tag_name, block_id) = tag?;
+            tags.entry(block_id.to_vec())
+                .or_default()
+                .push(String::from_utf8_lossy(&tag_name).into_owned());
+        }
+
+        let mut dot = String::from("digraph blocks {\n    rankdir=BT;\n");
+        let mut rows = 0;
+
+        for chain_length in from..=to {
+            let mut block_infos = Vec::new();
+            if let Some(block_info) = self
+                .permanent
+                .get_block_info_by_chain_length(chain_length)?
+            {
+                block_infos.push((block_info, true));
+            }
+            for index in self
+                .chain_length_index_tree
+                .scan_prefix(build_chain_length_index_prefix(chain_length))
+            {
+                let (index, _) = index?;
+                let block_info =
+                    self.get_block_info_volatile(block_id_from_chain_length_index(&index))?;
+                block_infos.push((block_info, false));
+            }
+            if block_infos.is_empty() {
+                continue;
+            }
+
+            // writing to a `String` cannot fail
+            dot.push_str("    { rank=same;");
+            for (block_info, _) in block_infos.iter() {
+                let _ = write!(dot, " \"{}\";", trace::HexId(block_info.id().as_ref()));
+            }
+            dot.push_str(" }\n");
+
+            for (block_info, permanent) in block_infos.iter() {
+                let id = trace::HexId(block_info.id().as_ref());
+                let _ = write!(
+                    dot,
+                    "    \"{}\" [label=\"{}\\nlength {}",
+                    id, id, chain_length
+                );
+                if let Some(tags) = tags.get(block_info.id().as_ref()) {
+                    let tags = tags.join(", ").replace('\\', "\\\\").replace('"', "\\\"");
+                    let _ = write!(dot, "\\ntags: {}", tags);
+                }
+                dot.push('"');
+                if *permanent {
+                    dot.push_str(", style=filled");
+                }
+                dot.push_str("];\n");
+                if chain_length > from {
+                    let _ = writeln!(
+                        dot,
+                        "    \"{}\" -> \"{}\";",
+                        id,
+                        trace::HexId(block_info.parent_id().as_ref())
+                    );
+                }
+            }
+            rows += block_infos.len();
+        }
+
+        dot.push_str("}\n");
+        trace::record_rows(rows);
+        Ok(dot)
+    }
+
+    /// Rebuild the information about the blocks of the volatile storage (their
+    /// parents, chain lengths and reference counts, the chain length index and
+    /// the branches tips) from the blocks themselves, for when it is corrupted
+    /// or lost while the blocks are intact.
+    ///
+    /// The chain length of a block is derived from its ancestors: a child of
+    /// the root block has a chain length of 0. The blocks of the permanent
+    /// storage are indexed by their position and are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - reads the ID of the parent of a serialized block.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks whose information was rebuilt.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, parent_id),
+            fields(rows = tracing::field::Empty),
+        )
+    )]
+    pub fn rebuild_block_info<F, E>(&self, mut parent_id: F) -> Result<usize, Error>
+    where
+        F: FnMut(&[u8]) -> Result<Value, E>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        use std::collections::HashMap;
+
+        let mut parents: HashMap<Vec<u8>, Value> = HashMap::new();
+        for block in self.blocks_tree.iter() {
+            let (id, block) = block?;
+            let block = self.cipher.open(&id, Value::volatile(block))?;
+            let parent =
+                parent_id(block.as_ref()).map_err(|err| Error::BlockDeserialize(err.into()))?;
+            parents.insert(id.to_vec(), parent);
+        }
+
+        let mut block_infos: HashMap<Vec<u8>, BlockInfo> = HashMap::new();
+        for id in parents.keys() {
+            if block_infos.contains_key(id) {
+                continue;
+            }
+
+            // go up to the first block with a known chain length, the blocks
+            // on the way are then numbered from there
+            let mut path: Vec<&[u8]> = Vec::new();
+            let mut current = id.as_slice();
+            let mut chain_length = loop {
+                // the blocks cannot be more than once on the path unless
+                // their parents form a cycle
+                if path.len() == parents.len() {
+                    return Err(ConsistencyFailure::MissingParentBlock.into());
+                }
+                path.push(current);
+
+                let parent = parents[current].as_ref();
+                if parent == self.root_id.as_ref() {
+                    break 0;
+                }
+                if let Some(block_info) = block_infos.get(parent) {
+                    break block_info.chain_length() + 1;
+                }
+                if let Some((parent, _)) = parents.get_key_value(parent) {
+                    current = parent.as_slice();
+                    continue;
+                }
+                match self.permanent.get_block_info(parent)? {
+                    Some(block_info) => break block_info.chain_length() + 1,
+                    None => return Err(ConsistencyFailure::MissingParentBlock.into()),
+                }
+            };
+
+            for id in path.into_iter().rev() {
+                block_infos.insert(
+                    id.to_vec(),
+                    BlockInfo::new(id.to_vec(), parents[id].clone(), chain_length),
+                );
+                chain_length += 1;
+            }
+        }
+
+        for parent in parents.values() {
+            if let Some(block_info) = block_infos.get_mut(parent.as_ref()) {
+                block_info.add_parent_ref();
+            }
+        }
+
+        for tag in self.tags_tree.iter() {
+            let (_, block_id) = tag?;
+            match block_infos.get_mut(block_id.as_ref()) {
+                Some(block_info) => block_info.add_tag_ref(),
+                None if self.permanent.contains_key(&block_id)? => {}
+                None => return Err(ConsistencyFailure::TaggedBlock.into()),
+            }
+        }
+
+        let mut info_batch = sled::Batch::default();
+        let mut chain_length_index_batch = sled::Batch::default();
+        let mut tips_batch = sled::Batch::default();
+
+        for key in self.info_tree.iter().keys() {
+            info_batch.remove(key?);
+        }
+        for key in self.chain_length_index_tree.iter().keys() {
+            chain_length_index_batch.remove(key?);
+        }
+        for key in self.branches_tips_tree.iter().keys() {
+            let key = key?;
+            // the last block of the permanent storage stays a tip until a
+            // block is added on top of it
+            let has_child = parents
+                .values()
+                .any(|parent| parent.as_ref() == key.as_ref());
+            if has_child || !self.permanent.contains_key(&key)? {
+                tips_batch.remove(key);
+            }
+        }
+
+        for block_info in block_infos.values() {
+            let id = block_info.id().as_ref();
+            info_batch.insert(id, block_info.serialize()?);
+            chain_length_index_batch
+                .insert(build_chain_length_index(block_info.chain_length(), id), &[]);
+            if block_info.parent_ref_count() == 0 {
+                tips_batch.insert(id, &[]);
+            }
+        }
+
+        (
+            &self.info_tree,
+            &self.chain_length_index_tree,
+            &self.branches_tips_tree,
         )
+            .transaction(|(info, chain_length_to_block_ids, tips)| {
+                info.apply_batch(&info_batch)?;
+                chain_length_to_block_ids.apply_batch(&chain_length_index_batch)?;
+                tips.apply_batch(&tips_batch)?;
+                Ok::<_, ConflictableTransactionError<Error>>(())
+            })?;
+
+        self.sync()?;
+
+        trace::record_rows(block_infos.len());
+        Ok(block_infos.len())
     }
 }
 
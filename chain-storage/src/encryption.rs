@@ -0,0 +1,241 @@
+//! Encryption of the blocks at rest.
+//!
+//! With the `encryption` feature enabled, [`BlockStore::with_encryption`]
+//! seals every block written to the store with ChaCha20-Poly1305, under the
+//! current key of a [`Keyring`]. The ID of the block is authenticated along
+//! with its contents, so that a stored block cannot be swapped for another
+//! one. Only the contents of the blocks are encrypted: their IDs, the block
+//! information and the tags are needed in clear by the indexes.
+//!
+//! A sealed block is stored as
+//!
+//! ```ignore
+//! key_id (4 bytes, little endian) ++ nonce (12 bytes) ++ ciphertext ++ tag (16 bytes)
+//! ```
+//!
+//! where the nonce is drawn from the operating system's generator.
+//!
+//! [`BlockStore::rotate_encryption_key`] makes a new key current and seals
+//! the blocks of the volatile storage again with it. The permanent storage is
+//! append-only: its blocks stay sealed with the key that was current when they
+//! were flushed, so the keys must be kept in the keyring for as long as such
+//! blocks are stored.
+//!
+//! A store is encrypted from its creation or not at all: the blocks written
+//! without encryption cannot be read once it is enabled, and the other way
+//! round.
+//!
+//! [`BlockStore::with_encryption`]: crate::BlockStore::with_encryption
+//! [`BlockStore::rotate_encryption_key`]: crate::BlockStore::rotate_encryption_key
+
+use crate::{permanent_store::PermanentStore, Error, Value};
+use std::borrow::Cow;
+
+#[cfg(feature = "encryption")]
+pub use self::keyring::{KeyId, Keyring, KEY_SIZE};
+
+#[cfg(feature = "encryption")]
+mod keyring {
+    use crate::Error;
+    use cryptoxide::chacha20poly1305::ChaCha20Poly1305;
+    use rand_core::{OsRng, RngCore};
+    use std::{collections::HashMap, convert::TryInto, fmt};
+
+    /// Identifies a key of the keyring in the sealed blocks
+    pub type KeyId = u32;
+
+    /// Size of the encryption keys, in bytes
+    pub const KEY_SIZE: usize = 32;
+
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+    const HEADER_SIZE: usize = std::mem::size_of::<KeyId>() + NONCE_SIZE;
+
+    /// The keys the blocks of a store are sealed with: new blocks are sealed
+    /// with the current key, the other keys are kept to read the blocks
+    /// sealed before a rotation.
+    #[derive(Clone)]
+    pub struct Keyring {
+        current: KeyId,
+        keys: HashMap<KeyId, [u8; KEY_SIZE]>,
+    }
+
+    impl Keyring {
+        /// A keyring sealing the blocks with the given key
+        pub fn new(key_id: KeyId, key: [u8; KEY_SIZE]) -> Self {
+            let mut keys = HashMap::new();
+            keys.insert(key_id, key);
+            Keyring {
+                current: key_id,
+                keys,
+            }
+        }
+
+        /// Add a key the blocks of the store were sealed with before a
+        /// rotation, the current key is left unchanged.
+        pub fn with_key(mut self, key_id: KeyId, key: [u8; KEY_SIZE]) -> Self {
+            self.keys.entry(key_id).or_insert(key);
+            self
+        }
+
+        /// The ID of the key the new blocks are sealed with
+        pub fn current_key_id(&self) -> KeyId {
+            self.current
+        }
+
+        /// Whether the blocks sealed with the key can be opened
+        pub fn contains_key(&self, key_id: KeyId) -> bool {
+            self.keys.contains_key(&key_id)
+        }
+
+        pub(crate) fn rotate(&mut self, key_id: KeyId, key: [u8; KEY_SIZE]) {
+            self.keys.insert(key_id, key);
+            self.current = key_id;
+        }
+
+        pub(crate) fn seal(&self, id: &[u8], block: &[u8]) -> Vec<u8> {
+            let mut nonce = [0u8; NONCE_SIZE];
+            OsRng.fill_bytes(&mut nonce);
+
+            let mut sealed = vec![0u8; HEADER_SIZE + block.len() + TAG_SIZE];
+            sealed[..HEADER_SIZE - NONCE_SIZE].copy_from_slice(&self.current.to_le_bytes());
+            sealed[HEADER_SIZE - NONCE_SIZE..HEADER_SIZE].copy_from_slice(&nonce);
+            let (ciphertext, tag) = sealed[HEADER_SIZE..].split_at_mut(block.len());
+            ChaCha20Poly1305::new(&self.keys[&self.current], &nonce, id)
+                .encrypt(block, ciphertext, tag);
+            sealed
+        }
+
+        pub(crate) fn open(&self, id: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Error> {
+            if sealed.len() < HEADER_SIZE + TAG_SIZE {
+                return Err(Error::BlockDecryption);
+            }
+            let key_id = sealed_key_id(sealed);
+            let key = self
+                .keys
+                .get(&key_id)
+                .ok_or(Error::UnknownEncryptionKey(key_id))?;
+            let nonce = &sealed[HEADER_SIZE - NONCE_SIZE..HEADER_SIZE];
+            let (ciphertext, tag) =
+                sealed[HEADER_SIZE..].split_at(sealed.len() - HEADER_SIZE - TAG_SIZE);
+
+            let mut block = vec![0u8; ciphertext.len()];
+            if ChaCha20Poly1305::new(key, nonce, id).decrypt(ciphertext, &mut block, tag) {
+                Ok(block)
+            } else {
+                Err(Error::BlockDecryption)
+            }
+        }
+    }
+
+    /// The ID of the key a block was sealed with, the block must be at least
+    /// as long as the header
+    pub(crate) fn sealed_key_id(sealed: &[u8]) -> KeyId {
+        KeyId::from_le_bytes(sealed[..HEADER_SIZE - NONCE_SIZE].try_into().unwrap())
+    }
+
+    // the keys are never printed
+    impl fmt::Debug for Keyring {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut key_ids: Vec<_> = self.keys.keys().collect();
+            key_ids.sort();
+            f.debug_struct("Keyring")
+                .field("current", &self.current)
+                .field("keys", &key_ids)
+                .finish()
+        }
+    }
+}
+
+/// Seals the blocks written to the store and opens the blocks read from it,
+/// when the store is encrypted. The keyring is shared by the clones.
+#[derive(Clone, Default)]
+pub(crate) struct BlockCipher {
+    #[cfg(feature = "encryption")]
+    keyring: Option<std::sync::Arc<std::sync::RwLock<Keyring>>>,
+}
+
+#[cfg(feature = "encryption")]
+impl BlockCipher {
+    pub(crate) fn new(keyring: Keyring) -> Self {
+        Self {
+            keyring: Some(std::sync::Arc::new(std::sync::RwLock::new(keyring))),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.keyring.is_some()
+    }
+
+    pub(crate) fn seal<'a>(&self, id: &[u8], block: &'a [u8]) -> Cow<'a, [u8]> {
+        match &self.keyring {
+            None => Cow::Borrowed(block),
+            Some(keyring) => Cow::Owned(keyring.read().unwrap().seal(id, block)),
+        }
+    }
+
+    pub(crate) fn open(&self, id: &[u8], stored: Value) -> Result<Value, Error> {
+        match &self.keyring {
+            None => Ok(stored),
+            Some(keyring) => keyring
+                .read()
+                .unwrap()
+                .open(id, stored.as_ref())
+                .map(Value::from),
+        }
+    }
+
+    /// Make the key current, returns `Error::NotEncrypted` if the store is
+    /// not encrypted
+    pub(crate) fn rotate(&self, key_id: KeyId, key: [u8; KEY_SIZE]) -> Result<(), Error> {
+        let keyring = self.keyring.as_ref().ok_or(Error::NotEncrypted)?;
+        keyring.write().unwrap().rotate(key_id, key);
+        Ok(())
+    }
+
+    /// Seal the stored block again if it is not sealed with the current key,
+    /// returns `None` if it already is
+    pub(crate) fn reseal(&self, id: &[u8], stored: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let keyring = self.keyring.as_ref().ok_or(Error::NotEncrypted)?;
+        let keyring = keyring.read().unwrap();
+        let block = keyring.open(id, stored)?;
+        if keyring::sealed_key_id(stored) == keyring.current_key_id() {
+            return Ok(None);
+        }
+        Ok(Some(keyring.seal(id, &block)))
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+impl BlockCipher {
+    fn is_enabled(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn seal<'a>(&self, _id: &[u8], block: &'a [u8]) -> Cow<'a, [u8]> {
+        Cow::Borrowed(block)
+    }
+
+    pub(crate) fn open(&self, _id: &[u8], stored: Value) -> Result<Value, Error> {
+        Ok(stored)
+    }
+}
+
+impl BlockCipher {
+    /// Open a block of the permanent storage, whose ID is looked up by chain
+    /// length only when the store is encrypted
+    pub(crate) fn open_permanent(
+        &self,
+        permanent: &PermanentStore,
+        chain_length: u32,
+        stored: Value,
+    ) -> Result<Value, Error> {
+        if !self.is_enabled() {
+            return Ok(stored);
+        }
+        let id = permanent
+            .get_block_id_by_chain_length(chain_length)
+            .ok_or(crate::ConsistencyFailure::ChainLength)?;
+        self.open(id.as_ref(), stored)
+    }
+}
@@ -14,18 +14,37 @@ pub enum Error {
     BlockAlreadyPresent,
     #[error("the parent block is missing for the required write")]
     MissingParent,
+    #[error("the blocks to write do not form a chain")]
+    BlocksNotContiguous,
     #[error("branch with the requested tip does not exist")]
     BranchNotFound,
+    #[error("the tag does not point to the expected block")]
+    TagChanged,
     #[error("failed to serialize block metadata")]
     BlockInfoSerialize(#[source] std::io::Error),
     #[error("failed to deserialize block metadata")]
     BlockInfoDeserialize(#[source] std::io::Error),
+    #[error("failed to read the parent of a stored block")]
+    BlockDeserialize(#[source] Box<dyn std::error::Error + Send + Sync>),
     #[error("the database is consistent")]
     Inconsistent(#[from] ConsistencyFailure),
     #[error(
         "cannot iterate over blocks because the provided distance is bigger than the chain length"
     )]
     CannotIterate,
+    #[error("none of the checkpoints is an ancestor of the block")]
+    CheckpointNotFound,
+    #[error("the blocking task running the operation was cancelled")]
+    Cancelled,
+    #[cfg(feature = "encryption")]
+    #[error("failed to decrypt a stored block")]
+    BlockDecryption,
+    #[cfg(feature = "encryption")]
+    #[error("the block is sealed with the key {0}, which is not in the keyring")]
+    UnknownEncryptionKey(u32),
+    #[cfg(feature = "encryption")]
+    #[error("the store is not encrypted")]
+    NotEncrypted,
 }
 
 #[derive(Debug, Error)]
@@ -0,0 +1,323 @@
+//! Fault injection for testing the consumers of the storage.
+
+use crate::{BlockInfo, BlockStore, Error, StorageIterator, Value};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// The operations of the store, to configure the faults of each of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Sync,
+    PutBlock,
+    PutBlocks,
+    GetBlock,
+    GetBlockInfo,
+    GetBlocksByChainLength,
+    PutTag,
+    CompareAndSetTag,
+    GetTag,
+    GetTipsIds,
+    GetBranches,
+    GetChildren,
+    PruneBranch,
+    PruneStaleBranches,
+    PruneBefore,
+    BlockExists,
+    IsAncestor,
+    GetNthAncestor,
+    FlushToPermanentStore,
+    Iter,
+    IterFromCheckpoints,
+    ExportDot,
+}
+
+#[derive(Default)]
+struct Faults {
+    latency: Option<Duration>,
+    // the next calls failing
+    fail_next: u64,
+    // every call at a multiple of the period fails
+    fail_every: Option<u64>,
+    calls: u64,
+    injected: u64,
+}
+
+struct State {
+    faults: HashMap<Operation, Faults>,
+    error: Box<dyn Fn(Operation) -> Error + Send + Sync>,
+}
+
+/// A `BlockStore` failing and slowing down its operations on demand.
+///
+/// The faults are set per operation and shared by all the clones of the
+/// store. A failing operation returns before reaching the wrapped store, by
+/// default with an I/O error of the volatile storage, and has no effect. The
+/// latency is added to the calls whether they fail or not.
+///
+/// ```ignore
+/// let store = FaultyBlockStore::new(BlockStore::memory(root_id)?)
+///     .with_latency(Operation::GetBlock, Duration::from_millis(5))
+///     .fail_every(Operation::PutBlock, 3);
+/// ```
+#[derive(Clone)]
+pub struct FaultyBlockStore {
+    inner: BlockStore,
+    state: Arc<Mutex<State>>,
+}
+
+fn injected_error(operation: Operation) -> Error {
+    Error::VolatileBackendError(sled::Error::Io(io::Error::new(
+        io::ErrorKind::Other,
+        format!("fault injected in {:?}", operation),
+    )))
+}
+
+impl FaultyBlockStore {
+    /// Wrap the store, without any fault until some are configured.
+    pub fn new(inner: BlockStore) -> Self {
+        Self {
+            inner,
+            state: Arc::new(Mutex::new(State {
+                faults: HashMap::new(),
+                error: Box::new(injected_error),
+            })),
+        }
+    }
+
+    /// The wrapped store, to prepare or check its content without faults.
+    pub fn inner(&self) -> &BlockStore {
+        &self.inner
+    }
+
+    /// Sleep for the given duration in every call of the operation.
+    pub fn with_latency(self, operation: Operation, latency: Duration) -> Self {
+        self.faults(operation, |faults| faults.latency = Some(latency));
+        self
+    }
+
+    /// Fail every call of the operation whose number, counting from 1, is a
+    /// multiple of `period`.
+    pub fn fail_every(self, operation: Operation, period: u64) -> Self {
+        assert!(period > 0, "the period of the faults cannot be zero");
+        self.faults(operation, |faults| faults.fail_every = Some(period));
+        self
+    }
+
+    /// Build the errors returned by the failing operations.
+    pub fn with_error<F>(self, error: F) -> Self
+    where
+        F: Fn(Operation) -> Error + Send + Sync + 'static,
+    {
+        self.state.lock().unwrap().error = Box::new(error);
+        self
+    }
+
+    /// Fail the next `count` calls of the operation, on top of the other
+    /// faults.
+    pub fn fail_next(&self, operation: Operation, count: u64) {
+        self.faults(operation, |faults| faults.fail_next += count);
+    }
+
+    /// Remove the faults and the latency of all the operations, the counters
+    /// are kept.
+    pub fn heal(&self) {
+        for faults in self.state.lock().unwrap().faults.values_mut() {
+            faults.latency = None;
+            faults.fail_next = 0;
+            faults.fail_every = None;
+        }
+    }
+
+    /// Number of calls of the operation, failed or not.
+    pub fn calls(&self, operation: Operation) -> u64 {
+        self.state
+            .lock()
+            .unwrap()
+            .faults
+            .get(&operation)
+            .map_or(0, |faults| faults.calls)
+    }
+
+    /// Number of calls of the operation which failed because of a fault.
+    pub fn injected_faults(&self, operation: Operation) -> u64 {
+        self.state
+            .lock()
+            .unwrap()
+            .faults
+            .get(&operation)
+            .map_or(0, |faults| faults.injected)
+    }
+
+    fn faults(&self, operation: Operation, f: impl FnOnce(&mut Faults)) {
+        f(self
+            .state
+            .lock()
+            .unwrap()
+            .faults
+            .entry(operation)
+            .or_default())
+    }
+
+    // counts the call and applies its faults, the lock is not held while
+    // sleeping so that the concurrent calls are not serialized
+    fn enter(&self, operation: Operation) -> Result<(), Error> {
+        let (latency, error) = {
+            let mut guard = self.state.lock().unwrap();
+            let state = &mut *guard;
+            let faults = state.faults.entry(operation).or_default();
+            faults.calls += 1;
+            let fail = if faults.fail_next > 0 {
+                faults.fail_next -= 1;
+                true
+            } else {
+                faults
+                    .fail_every
+                    .map_or(false, |period| faults.calls % period == 0)
+            };
+            let latency = faults.latency;
+            let error = if fail {
+                faults.injected += 1;
+                Some((state.error)(operation))
+            } else {
+                None
+            };
+            (latency, error)
+        };
+        if let Some(latency) = latency {
+            thread::sleep(latency);
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    pub fn sync(&self) -> Result<(), Error> {
+        self.enter(Operation::Sync)?;
+        self.inner.sync()
+    }
+
+    pub fn put_block(&self, block: &[u8], block_info: BlockInfo) -> Result<(), Error> {
+        self.enter(Operation::PutBlock)?;
+        self.inner.put_block(block, block_info)
+    }
+
+    pub fn put_blocks(&self, blocks: &[(&[u8], BlockInfo)]) -> Result<(), Error> {
+        self.enter(Operation::PutBlocks)?;
+        self.inner.put_blocks(blocks)
+    }
+
+    pub fn get_block(&self, block_id: &[u8]) -> Result<Value, Error> {
+        self.enter(Operation::GetBlock)?;
+        self.inner.get_block(block_id)
+    }
+
+    pub fn get_block_info(&self, block_id: &[u8]) -> Result<BlockInfo, Error> {
+        self.enter(Operation::GetBlockInfo)?;
+        self.inner.get_block_info(block_id)
+    }
+
+    pub fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
+        self.enter(Operation::GetBlocksByChainLength)?;
+        self.inner.get_blocks_by_chain_length(chain_length)
+    }
+
+    pub fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.enter(Operation::PutTag)?;
+        self.inner.put_tag(tag_name, block_id)
+    }
+
+    pub fn compare_and_set_tag(
+        &self,
+        tag_name: &str,
+        expected: Option<&[u8]>,
+        block_id: &[u8],
+    ) -> Result<(), Error> {
+        self.enter(Operation::CompareAndSetTag)?;
+        self.inner.compare_and_set_tag(tag_name, expected, block_id)
+    }
+
+    pub fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error> {
+        self.enter(Operation::GetTag)?;
+        self.inner.get_tag(tag_name)
+    }
+
+    pub fn get_tips_ids(&self) -> Result<Vec<Value>, Error> {
+        self.enter(Operation::GetTipsIds)?;
+        self.inner.get_tips_ids()
+    }
+
+    pub fn get_branches(&self) -> Result<Vec<Value>, Error> {
+        self.enter(Operation::GetBranches)?;
+        self.inner.get_branches()
+    }
+
+    pub fn get_children(&self, block_id: &[u8]) -> Result<Vec<Value>, Error> {
+        self.enter(Operation::GetChildren)?;
+        self.inner.get_children(block_id)
+    }
+
+    pub fn prune_branch(&self, tip_id: &[u8]) -> Result<(), Error> {
+        self.enter(Operation::PruneBranch)?;
+        self.inner.prune_branch(tip_id)
+    }
+
+    pub fn prune_stale_branches(&self, block_id: &[u8]) -> Result<usize, Error> {
+        self.enter(Operation::PruneStaleBranches)?;
+        self.inner.prune_stale_branches(block_id)
+    }
+
+    pub fn prune_before(&self, block_id: &[u8]) -> Result<usize, Error> {
+        self.enter(Operation::PruneBefore)?;
+        self.inner.prune_before(block_id)
+    }
+
+    pub fn block_exists(&self, block_id: &[u8]) -> Result<bool, Error> {
+        self.enter(Operation::BlockExists)?;
+        self.inner.block_exists(block_id)
+    }
+
+    pub fn is_ancestor(
+        &self,
+        ancestor_id: &[u8],
+        descendant_id: &[u8],
+    ) -> Result<Option<u32>, Error> {
+        self.enter(Operation::IsAncestor)?;
+        self.inner.is_ancestor(ancestor_id, descendant_id)
+    }
+
+    pub fn get_nth_ancestor(&self, block_id: &[u8], distance: u32) -> Result<BlockInfo, Error> {
+        self.enter(Operation::GetNthAncestor)?;
+        self.inner.get_nth_ancestor(block_id, distance)
+    }
+
+    pub fn flush_to_permanent_store(
+        &self,
+        to_block: &[u8],
+        min_number: usize,
+    ) -> Result<usize, Error> {
+        self.enter(Operation::FlushToPermanentStore)?;
+        self.inner.flush_to_permanent_store(to_block, min_number)
+    }
+
+    pub fn iter(&self, to_block: &[u8], distance: u32) -> Result<StorageIterator, Error> {
+        self.enter(Operation::Iter)?;
+        self.inner.iter(to_block, distance)
+    }
+
+    pub fn iter_from_checkpoints(
+        &self,
+        checkpoints: &[&[u8]],
+        to_block: &[u8],
+    ) -> Result<StorageIterator, Error> {
+        self.enter(Operation::IterFromCheckpoints)?;
+        self.inner.iter_from_checkpoints(checkpoints, to_block)
+    }
+
+    pub fn export_dot(&self, from: u32, to: u32) -> Result<String, Error> {
+        self.enter(Operation::ExportDot)?;
+        self.inner.export_dot(from, to)
+    }
+}
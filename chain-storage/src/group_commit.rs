@@ -0,0 +1,121 @@
+use std::{
+    sync::{Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// Shares the flushes of the volatile storage between concurrent writers.
+///
+/// A writer waiting for its write to be durable either joins the flush in
+/// progress, or the next one, or starts a flush itself. The writer starting
+/// a flush first waits for the commit latency, so that the writers arriving
+/// in the meantime are made durable by the same flush.
+pub(crate) struct GroupCommit {
+    latency: Duration,
+    state: Mutex<State>,
+    flushed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    // number of writes waiting or made durable so far
+    written: u64,
+    // all the writes up to this one are durable
+    durable: u64,
+    flushing: bool,
+}
+
+impl GroupCommit {
+    pub(crate) fn new(latency: Duration) -> Self {
+        GroupCommit {
+            latency,
+            state: Mutex::new(State::default()),
+            flushed: Condvar::new(),
+        }
+    }
+
+    /// Wait until the write just done by the caller is durable. `flush` is
+    /// called if the caller has to flush the storage itself.
+    ///
+    /// If the flush fails the error is returned to the writer which started
+    /// it, the other writers start a new flush.
+    pub(crate) fn commit<E, F>(&self, flush: F) -> Result<(), E>
+    where
+        F: Fn() -> Result<(), E>,
+    {
+        let mut state = self.state.lock().unwrap();
+        state.written += 1;
+        let ticket = state.written;
+
+        loop {
+            if state.durable >= ticket {
+                return Ok(());
+            }
+            if !state.flushing {
+                break;
+            }
+            state = self.flushed.wait(state).unwrap();
+        }
+
+        state.flushing = true;
+        drop(state);
+        thread::sleep(self.latency);
+        let target = self.state.lock().unwrap().written;
+
+        let result = flush();
+
+        let mut state = self.state.lock().unwrap();
+        if result.is_ok() {
+            state.durable = state.durable.max(target);
+        }
+        state.flushing = false;
+        self.flushed.notify_all();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Barrier,
+    };
+
+    #[test]
+    fn concurrent_writers_share_flushes() {
+        const WRITERS: usize = 8;
+        let commit = Arc::new(GroupCommit::new(Duration::from_millis(50)));
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(WRITERS));
+
+        let handles: Vec<_> = (0..WRITERS)
+            .map(|_| {
+                let commit = Arc::clone(&commit);
+                let flushes = Arc::clone(&flushes);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    commit.commit(|| {
+                        flushes.fetch_add(1, Ordering::SeqCst);
+                        Ok::<(), ()>(())
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+
+        let flushes = flushes.load(Ordering::SeqCst);
+        assert!(flushes >= 1 && flushes < WRITERS, "{} flushes", flushes);
+    }
+
+    #[test]
+    fn failed_flush_is_retried_by_the_next_writer() {
+        let commit = GroupCommit::new(Duration::from_millis(0));
+        assert_eq!(commit.commit(|| Err("disk full")), Err("disk full"));
+        assert_eq!(commit.commit(|| Ok::<(), &str>(())), Ok(()));
+        assert_eq!(commit.state.lock().unwrap().durable, 2);
+    }
+}
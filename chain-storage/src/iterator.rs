@@ -1,12 +1,17 @@
-use crate::{permanent_store::PermanentStore, BlockInfo, ConsistencyFailure, Error, Value};
+use crate::{
+    encryption::BlockCipher, permanent_store::PermanentStore, BlockInfo, ConsistencyFailure, Error,
+    Value,
+};
 use sled::Tree;
 
 /// Iterator over blocks. Starts from n-th ancestor of the given block.
 pub struct StorageIterator {
     state: IteratorState,
     to: Value,
+    permanent_store: PermanentStore,
     block_info: Tree,
     blocks: Tree,
+    cipher: BlockCipher,
 }
 
 enum IteratorState {
@@ -27,6 +32,7 @@ impl StorageIterator {
         permanent_store: PermanentStore,
         block_info: Tree,
         blocks: Tree,
+        cipher: BlockCipher,
     ) -> Result<Self, Error> {
         let to_info = if let Some(to_info_bin) = block_info.get(to.as_ref())? {
             BlockInfo::deserialize(to_info_bin.as_ref(), to.as_ref().len(), to.clone())?
@@ -60,8 +66,10 @@ impl StorageIterator {
         Ok(Self {
             state,
             to,
+            permanent_store,
             block_info,
             blocks,
+            cipher,
         })
     }
 }
@@ -81,8 +89,13 @@ impl Iterator for StorageIterator {
                 }
                 match iter.next() {
                     Some(item) => {
+                        let block = self.cipher.open_permanent(
+                            &self.permanent_store,
+                            *current_length,
+                            Value::permanent(item),
+                        );
                         *current_length += 1;
-                        Some(Ok(Value::permanent(item)))
+                        Some(block)
                     }
                     None => {
                         match gather_blocks_ids(self.to.clone(), &self.block_info, *current_length)
@@ -96,10 +109,15 @@ impl Iterator for StorageIterator {
             }
             IteratorState::Volatile { ids } => {
                 let id = ids.pop()?;
+                let cipher = &self.cipher;
                 self.blocks
                     .get(id.as_ref())
-                    .map(|maybe_value| maybe_value.map(Value::volatile))
                     .map_err(Into::into)
+                    .and_then(|maybe_value| {
+                        maybe_value
+                            .map(|value| cipher.open(id.as_ref(), Value::volatile(value)))
+                            .transpose()
+                    })
                     .transpose()
             }
         }
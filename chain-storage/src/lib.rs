@@ -90,6 +90,16 @@
 //! * Determine which branches do you want to remove.
 //! * Call, for example, `store.prune_branch(Block 4' id)`.
 //!
+//! Alternatively, `store.prune_stale_branches(Block 4 id)` removes every branch
+//! that does not lead to `Block 4`, here `Block 3'` and `Block 4'`. This is
+//! useful after flushing blocks to the permanent store, since the branches
+//! forking below the flushed blocks can no longer be selected.
+//!
+//! `store.prune_before(Block 4 id)` removes the same blocks, but does not
+//! spare the tagged ones: the tags pointing to the removed blocks are removed
+//! as well. The ancestors of `Block 4` are kept in both cases, the history of
+//! the chain is moved to the permanent storage instead.
+//!
 //! ## Performance benefits of permanent storage
 //!
 //! Since blocks in the permanent storage are stored just one after another (the
@@ -134,18 +144,63 @@
 //! │   └── flatfile    - storage file that can be transferred over the network
 //! └── volatile        - volatile storage
 //! ```
+//!
+//! # Recovery
+//!
+//! If the block information of the volatile storage is corrupted or lost,
+//! `store.rebuild_block_info(parent_id)` rebuilds it from the stored blocks,
+//! given a function reading the ID of the parent of a serialized block.
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature enabled, every public `BlockStore` method runs
+//! in a `debug` level [tracing](https://docs.rs/tracing) span carrying the
+//! hexadecimal block ids it is called with and, where relevant, the chain
+//! length of the block and the number of rows returned or flushed.
+//!
+//! # Asynchronous access
+//!
+//! With the `tokio` feature enabled, `AsyncBlockStore` wraps a `BlockStore`
+//! to run its operations on the blocking thread pool of tokio, and
+//! `AsyncBlockStore::iterate_range` reads the blocks ahead into an iterator
+//! which can be held across await points.
+//!
+//! # Encryption at rest
+//!
+//! With the `encryption` feature enabled, `BlockStore::with_encryption`
+//! seals the blocks with ChaCha20-Poly1305 under the keys of a `Keyring`,
+//! and `BlockStore::rotate_encryption_key` changes the key the blocks are
+//! sealed with. See the `encryption` module for the format of the sealed
+//! blocks.
+//!
+//! # Fault injection
+//!
+//! With the `with-bench` feature enabled, `test_utils::FaultyBlockStore`
+//! wraps a `BlockStore` to make its operations fail or slow down on demand,
+//! to test the error handling and the retries of the code using the store.
 
+#[cfg(feature = "tokio")]
+mod async_store;
 mod block_info;
 mod block_store;
+pub mod encryption;
 mod error;
+#[cfg(any(test, feature = "with-bench"))]
+mod faulty;
+mod group_commit;
 mod iterator;
 mod permanent_store;
 #[cfg(any(test, feature = "with-bench"))]
+mod test_suite;
+#[cfg(any(test, feature = "with-bench"))]
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
+mod trace;
 mod value;
 
+#[cfg(feature = "tokio")]
+pub use async_store::{AsyncBlockStore, AsyncStorageIterator};
 pub use block_info::BlockInfo;
 pub use block_store::BlockStore;
 pub use error::{ConsistencyFailure, Error};
@@ -61,6 +61,12 @@ impl PermanentStore {
             .map(Value::permanent)
     }
 
+    pub fn get_block_id_by_chain_length(&self, chain_length: u32) -> Option<Value> {
+        self.chain_length_index
+            .get_by_seqno(chain_length as usize)
+            .map(Value::permanent)
+    }
+
     pub fn get_block(&self, block_id: &[u8]) -> Result<Option<Value>, Error> {
         self.get_chain_length(block_id).map(|maybe_chain_length| {
             maybe_chain_length.and_then(|chain_length| self.get_block_by_chain_length(chain_length))
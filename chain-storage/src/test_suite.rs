@@ -0,0 +1,1220 @@
+//! Storage test suite shared by all the storage backends.
+
+/// Generate the storage test suite for a backend.
+///
+/// `$store` is the type of the store and `$prepare` an expression that can be
+/// called without argument to create an empty store whose root block id is
+/// `BlockId(0)`. `$prepare` returns a pair of a guard, that is kept alive for
+/// the duration of each test (a temporary directory for example), and the
+/// store. The store must expose the same methods as `BlockStore` and report
+/// the same `Error`s.
+///
+/// The tests are generated into the calling module, so each backend should
+/// invoke this macro in its own module:
+///
+/// ```ignore
+/// mod memory {
+///     use chain_storage::{test_utils::BlockId, BlockStore};
+///
+///     chain_storage::storage_test_suite!(BlockStore, || {
+///         ((), BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap())
+///     });
+/// }
+/// ```
+#[macro_export]
+macro_rules! storage_test_suite {
+    ($store:ty, $prepare:expr) => {
+        use std::{collections::HashSet, iter::FromIterator};
+        use $crate::test_utils::rand_core::{OsRng, RngCore};
+        use $crate::test_utils::{Block, BlockId};
+        use $crate::{BlockInfo, Error, Value};
+
+        const SIMULTANEOUS_READ_WRITE_ITERS: usize = 50;
+        const BLOCK_NUM_PERMANENT_TEST: usize = 1024;
+        const FLUSH_TO_BLOCK: usize = 512;
+        const FLUSH_TO_BLOCK_2: usize = 768;
+
+        fn pick_from_vector<'a, A, R: RngCore>(rng: &mut R, v: &'a [A]) -> &'a A {
+            let s = rng.next_u32() as usize;
+            // this doesn't need to be uniform
+            &v[s % v.len()]
+        }
+
+        fn generate_chain<R: RngCore>(rng: &mut R, store: &$store) -> Vec<Block> {
+            let mut blocks = vec![];
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+            blocks.push(genesis_block);
+
+            for _ in 0..10 {
+                let mut parent_block = pick_from_vector(rng, &blocks).clone();
+                let r = 1 + (rng.next_u32() % 99);
+                for _ in 0..r {
+                    let block = parent_block.make_child(None);
+                    let block_info = BlockInfo::new(
+                        block.id.serialize_as_vec(),
+                        block.parent.serialize_as_vec(),
+                        block.chain_length,
+                    );
+                    store
+                        .put_block(&block.serialize_as_vec(), block_info)
+                        .unwrap();
+                    parent_block = block.clone();
+                    blocks.push(block);
+                }
+            }
+
+            blocks
+        }
+
+        fn prepare_store() -> (impl Sized, $store) {
+            ($prepare)()
+        }
+
+        #[test]
+        fn tag_get_non_existent() {
+            let (_file, store) = prepare_store();
+            assert!(store.get_tag("tip").unwrap().is_none());
+        }
+
+        #[test]
+        fn tag_non_existent_block() {
+            let (_file, store) = prepare_store();
+            match store.put_tag("tip", &BlockId(0).serialize_as_vec()) {
+                Err(Error::BlockNotFound) => {}
+                err => panic!("{:?}", err),
+            }
+        }
+
+        #[test]
+        fn tag_put() {
+            let mut rng = OsRng;
+
+            let (_file, store) = prepare_store();
+            let blocks = generate_chain(&mut rng, &store);
+
+            store
+                .put_tag("tip", &blocks.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+            assert_eq!(
+                store.get_tag("tip").unwrap().unwrap(),
+                blocks.last().unwrap().id.serialize_as_value()
+            );
+        }
+
+        #[test]
+        fn tag_compare_and_set() {
+            let mut rng = OsRng;
+
+            let (_file, store) = prepare_store();
+            let blocks = generate_chain(&mut rng, &store);
+            let first = blocks.first().unwrap().id.serialize_as_vec();
+            let last = blocks.last().unwrap().id.serialize_as_vec();
+
+            store.compare_and_set_tag("tip", None, &first).unwrap();
+            match store.compare_and_set_tag("tip", None, &last) {
+                Err(Error::TagChanged) => {}
+                err => panic!("{:?}", err),
+            }
+            match store.compare_and_set_tag("tip", Some(&last), &last) {
+                Err(Error::TagChanged) => {}
+                err => panic!("{:?}", err),
+            }
+            assert_eq!(
+                store.get_tag("tip").unwrap().unwrap(),
+                blocks.first().unwrap().id.serialize_as_value()
+            );
+
+            store.compare_and_set_tag("tip", Some(&first), &last).unwrap();
+            assert_eq!(
+                store.get_tag("tip").unwrap().unwrap(),
+                blocks.last().unwrap().id.serialize_as_value()
+            );
+        }
+
+        #[test]
+        fn tag_overwrite() {
+            let mut rng = OsRng;
+
+            let (_file, store) = prepare_store();
+            let blocks = generate_chain(&mut rng, &store);
+
+            store
+                .put_tag("tip", &blocks.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+            store
+                .put_tag("tip", &blocks.first().unwrap().id.serialize_as_vec())
+                .unwrap();
+            assert_eq!(
+                store.get_tag("tip").unwrap().unwrap(),
+                blocks.first().unwrap().id.serialize_as_value()
+            );
+        }
+
+        #[test]
+        fn block_read_write() {
+            let (_file, store) = prepare_store();
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+
+            assert!(!store
+                .block_exists(genesis_block_info.id().as_ref())
+                .unwrap());
+
+            store
+                .put_block(
+                    &genesis_block.serialize_as_vec(),
+                    genesis_block_info.clone(),
+                )
+                .unwrap();
+            let genesis_block_restored = store
+                .get_block_info(&genesis_block.id.serialize_as_vec())
+                .unwrap();
+
+            assert!(store
+                .block_exists(genesis_block_info.id().as_ref())
+                .unwrap());
+
+            assert_eq!(
+                &genesis_block.id.serialize_as_vec()[..],
+                genesis_block_restored.id().as_ref()
+            );
+            assert_eq!(
+                &genesis_block.parent.serialize_as_vec()[..],
+                genesis_block_restored.parent_id().as_ref()
+            );
+            assert_eq!(
+                genesis_block.chain_length,
+                genesis_block_restored.chain_length()
+            );
+
+            assert_eq!(
+                vec![genesis_block.id.serialize_as_value()],
+                store.get_tips_ids().unwrap()
+            );
+        }
+
+        #[test]
+        fn put_blocks_batch() {
+            let (_file, store) = prepare_store();
+            let mut chain = vec![Block::genesis(None)];
+            for _ in 1..10 {
+                let block = chain.last().unwrap().make_child(None);
+                chain.push(block);
+            }
+            let serialized: Vec<_> = chain.iter().map(|block| block.serialize_as_vec()).collect();
+            let batch: Vec<_> = chain
+                .iter()
+                .zip(serialized.iter())
+                .map(|(block, data)| {
+                    let block_info = BlockInfo::new(
+                        block.id.serialize_as_vec(),
+                        block.parent.serialize_as_vec(),
+                        block.chain_length,
+                    );
+                    (&data[..], block_info)
+                })
+                .collect();
+
+            store.put_blocks(&[]).unwrap();
+            // the parent of the first block is missing
+            assert!(matches!(
+                store.put_blocks(&batch[5..]),
+                Err(Error::MissingParent)
+            ));
+            let gapped = [batch[0].clone(), batch[2].clone()];
+            assert!(matches!(
+                store.put_blocks(&gapped),
+                Err(Error::BlocksNotContiguous)
+            ));
+            assert!(!store.block_exists(&chain[0].id.serialize_as_vec()).unwrap());
+
+            store.put_blocks(&batch[..5]).unwrap();
+            assert!(matches!(
+                store.put_blocks(&batch[4..]),
+                Err(Error::BlockAlreadyPresent)
+            ));
+            store.put_blocks(&batch[5..]).unwrap();
+
+            for (block, data) in chain.iter().zip(serialized.iter()) {
+                let block_id = block.id.serialize_as_vec();
+                assert_eq!(store.get_block(&block_id).unwrap().as_ref(), &data[..]);
+                let block_info = store.get_block_info(&block_id).unwrap();
+                assert_eq!(block_info.chain_length(), block.chain_length);
+            }
+            assert_eq!(
+                vec![chain[9].id.serialize_as_value()],
+                store.get_tips_ids().unwrap()
+            );
+
+            // the blocks of the batch are linked: a fork keeps its parent
+            // when pruned, and pruning the main branch removes everything
+            let fork = chain[4].make_child(None);
+            let fork_info = BlockInfo::new(
+                fork.id.serialize_as_vec(),
+                fork.parent.serialize_as_vec(),
+                fork.chain_length,
+            );
+            store
+                .put_block(&fork.serialize_as_vec(), fork_info)
+                .unwrap();
+            store.prune_branch(&fork.id.serialize_as_vec()).unwrap();
+            assert!(store.block_exists(&chain[4].id.serialize_as_vec()).unwrap());
+            store.prune_branch(&chain[9].id.serialize_as_vec()).unwrap();
+            assert!(!store.block_exists(&chain[0].id.serialize_as_vec()).unwrap());
+        }
+
+        #[test]
+        fn nth_ancestor() {
+            let mut rng = OsRng;
+            let (_file, store) = prepare_store();
+            let blocks = generate_chain(&mut rng, &store);
+
+            let mut blocks_fetched = 0;
+            let mut total_distance = 0;
+            let nr_tests = 1000;
+
+            for _ in 0..nr_tests {
+                let block = pick_from_vector(&mut rng, &blocks);
+                assert_eq!(
+                    store.get_block(&block.id.serialize_as_vec()).unwrap(),
+                    block.serialize_as_value()
+                );
+
+                let distance = rng.next_u32().checked_rem(block.chain_length).unwrap_or(0);
+                total_distance += distance;
+
+                let ancestor_info = store
+                    .get_nth_ancestor(&block.id.serialize_as_vec(), distance)
+                    .unwrap();
+                blocks_fetched += block.chain_length - distance;
+
+                assert_eq!(ancestor_info.chain_length() + distance, block.chain_length);
+            }
+
+            let blocks_per_test = blocks_fetched as f64 / nr_tests as f64;
+
+            println!(
+                "fetched {} intermediate blocks ({} per test), total distance {}",
+                blocks_fetched, blocks_per_test, total_distance
+            );
+        }
+
+        #[test]
+        fn simultaneous_read_write() {
+            let mut rng = OsRng;
+            let (_file, store) = prepare_store();
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+            let mut blocks = vec![genesis_block];
+
+            for _ in 1..SIMULTANEOUS_READ_WRITE_ITERS {
+                let last_block = blocks.get(rng.next_u32() as usize % blocks.len()).unwrap();
+                let block = last_block.make_child(None);
+                blocks.push(block.clone());
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap()
+            }
+
+            let store_1 = store.clone();
+            let blocks_1 = blocks.clone();
+
+            let thread_1 = std::thread::spawn(move || {
+                for _ in 1..SIMULTANEOUS_READ_WRITE_ITERS {
+                    let block_id = blocks_1
+                        .get(rng.next_u32() as usize % blocks_1.len())
+                        .unwrap()
+                        .id
+                        .serialize_as_vec();
+                    store_1.get_block(&block_id).unwrap();
+                }
+            });
+
+            let thread_2 = std::thread::spawn(move || {
+                for _ in 1..SIMULTANEOUS_READ_WRITE_ITERS {
+                    let last_block = blocks.get(rng.next_u32() as usize % blocks.len()).unwrap();
+                    let block = last_block.make_child(None);
+                    let block_info = BlockInfo::new(
+                        block.id.serialize_as_vec(),
+                        block.parent.serialize_as_vec(),
+                        block.chain_length,
+                    );
+                    store
+                        .put_block(&block.serialize_as_vec(), block_info)
+                        .unwrap()
+                }
+            });
+
+            thread_1.join().unwrap();
+            thread_2.join().unwrap();
+        }
+
+        #[test]
+        fn branch_pruning() {
+            const MAIN_BRANCH_LEN: usize = 100;
+            const SECOND_BRANCH_LEN: usize = 25;
+            const BIFURCATION_POINT: usize = 50;
+
+            let (_file, store) = prepare_store();
+
+            let mut main_branch_blocks = vec![];
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+
+            let mut block = genesis_block.make_child(None);
+
+            main_branch_blocks.push(genesis_block);
+
+            for _i in 1..MAIN_BRANCH_LEN {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+                main_branch_blocks.push(block.clone());
+                block = block.make_child(None);
+            }
+
+            let mut second_branch_blocks = vec![main_branch_blocks[BIFURCATION_POINT].clone()];
+
+            block = main_branch_blocks[BIFURCATION_POINT].make_child(None);
+
+            for _i in 1..SECOND_BRANCH_LEN {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+                second_branch_blocks.push(block.clone());
+                block = block.make_child(None);
+            }
+
+            let expected_tips = {
+                let mut hs = HashSet::new();
+                hs.insert(main_branch_blocks.last().unwrap().id.serialize_as_value());
+                hs.insert(second_branch_blocks.last().unwrap().id.serialize_as_value());
+                hs
+            };
+            let actual_tips = HashSet::from_iter(store.get_tips_ids().unwrap().into_iter());
+            assert_eq!(expected_tips, actual_tips);
+
+            store
+                .prune_branch(&second_branch_blocks.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+
+            assert_eq!(
+                vec![main_branch_blocks.last().unwrap().id.serialize_as_value()],
+                store.get_tips_ids().unwrap()
+            );
+
+            store
+                .get_block(&second_branch_blocks[0].id.serialize_as_vec())
+                .unwrap();
+
+            for i in 1..SECOND_BRANCH_LEN {
+                let block_result = store.get_block(&second_branch_blocks[i].id.serialize_as_vec());
+                assert!(matches!(block_result, Err(Error::BlockNotFound)));
+            }
+
+            // tagged branch must not be removed
+            store
+                .put_tag(
+                    "tip",
+                    &main_branch_blocks.last().unwrap().id.serialize_as_vec(),
+                )
+                .unwrap();
+            store
+                .prune_branch(&main_branch_blocks.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+            assert!(store
+                .block_exists(&main_branch_blocks.last().unwrap().id.serialize_as_vec())
+                .unwrap());
+        }
+
+        #[test]
+        fn stale_branches_pruning() {
+            let (_file, store) = prepare_store();
+
+            let put_branch = |parent: &Block, len: usize| {
+                let mut blocks = vec![];
+                let mut block = parent.make_child(None);
+                for _i in 0..len {
+                    let block_info = BlockInfo::new(
+                        block.id.serialize_as_vec(),
+                        block.parent.serialize_as_vec(),
+                        block.chain_length,
+                    );
+                    store
+                        .put_block(&block.serialize_as_vec(), block_info)
+                        .unwrap();
+                    blocks.push(block.clone());
+                    block = block.make_child(None);
+                }
+                blocks
+            };
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+
+            let main_branch = put_branch(&genesis_block, 10);
+            let stale_branch = put_branch(&main_branch[3], 4);
+            let nested_stale_branch = put_branch(&stale_branch[1], 2);
+            let tagged_branch = put_branch(&main_branch[5], 3);
+            let descendant_branch = put_branch(&main_branch[8], 2);
+            store
+                .put_tag("fork", &tagged_branch[1].id.serialize_as_vec())
+                .unwrap();
+
+            assert!(matches!(
+                store.prune_stale_branches(&Block::genesis(None).id.serialize_as_vec()),
+                Err(Error::BlockNotFound)
+            ));
+
+            assert_eq!(
+                store
+                    .prune_stale_branches(&main_branch[7].id.serialize_as_vec())
+                    .unwrap(),
+                3
+            );
+
+            for block in stale_branch.iter().chain(nested_stale_branch.iter()) {
+                assert!(!store.block_exists(&block.id.serialize_as_vec()).unwrap());
+            }
+            assert!(!store
+                .block_exists(&tagged_branch[2].id.serialize_as_vec())
+                .unwrap());
+            for block in main_branch
+                .iter()
+                .chain(&tagged_branch[..2])
+                .chain(descendant_branch.iter())
+            {
+                assert!(store.block_exists(&block.id.serialize_as_vec()).unwrap());
+            }
+
+            let expected_tips = {
+                let mut hs = HashSet::new();
+                hs.insert(main_branch.last().unwrap().id.serialize_as_value());
+                hs.insert(tagged_branch[1].id.serialize_as_value());
+                hs.insert(descendant_branch.last().unwrap().id.serialize_as_value());
+                hs
+            };
+            let actual_tips = HashSet::from_iter(store.get_tips_ids().unwrap().into_iter());
+            assert_eq!(expected_tips, actual_tips);
+        }
+
+        #[test]
+        fn pruning_before_block() {
+            let (_file, store) = prepare_store();
+
+            let put_branch = |parent: &Block, len: usize| {
+                let mut blocks = vec![];
+                let mut block = parent.make_child(None);
+                for _i in 0..len {
+                    let block_info = BlockInfo::new(
+                        block.id.serialize_as_vec(),
+                        block.parent.serialize_as_vec(),
+                        block.chain_length,
+                    );
+                    store
+                        .put_block(&block.serialize_as_vec(), block_info)
+                        .unwrap();
+                    blocks.push(block.clone());
+                    block = block.make_child(None);
+                }
+                blocks
+            };
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+
+            let main_branch = put_branch(&genesis_block, 10);
+            let stale_branch = put_branch(&main_branch[3], 4);
+            let nested_stale_branch = put_branch(&stale_branch[1], 2);
+            let tagged_branch = put_branch(&main_branch[5], 3);
+            let descendant_branch = put_branch(&main_branch[8], 2);
+            store
+                .put_tag("fork", &tagged_branch[1].id.serialize_as_vec())
+                .unwrap();
+            store
+                .put_tag("ancestor", &main_branch[2].id.serialize_as_vec())
+                .unwrap();
+
+            assert_eq!(
+                store
+                    .prune_before(&main_branch[7].id.serialize_as_vec())
+                    .unwrap(),
+                9
+            );
+
+            for block in stale_branch
+                .iter()
+                .chain(nested_stale_branch.iter())
+                .chain(tagged_branch.iter())
+            {
+                assert!(!store.block_exists(&block.id.serialize_as_vec()).unwrap());
+            }
+            for block in main_branch.iter().chain(descendant_branch.iter()) {
+                assert!(store.block_exists(&block.id.serialize_as_vec()).unwrap());
+            }
+
+            assert!(store.get_tag("fork").unwrap().is_none());
+            assert_eq!(
+                store.get_tag("ancestor").unwrap().unwrap(),
+                main_branch[2].id.serialize_as_value()
+            );
+
+            let expected_tips = {
+                let mut hs = HashSet::new();
+                hs.insert(main_branch.last().unwrap().id.serialize_as_value());
+                hs.insert(descendant_branch.last().unwrap().id.serialize_as_value());
+                hs
+            };
+            let actual_tips = HashSet::from_iter(store.get_tips_ids().unwrap().into_iter());
+            assert_eq!(expected_tips, actual_tips);
+
+            // the blocks the removed branches were forking from are no
+            // longer referenced by them
+            store
+                .prune_branch(&descendant_branch.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+            store
+                .prune_branch(&main_branch.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+            for block in &main_branch[3..] {
+                assert!(!store.block_exists(&block.id.serialize_as_vec()).unwrap());
+            }
+            assert_eq!(
+                store.get_tips_ids().unwrap(),
+                vec![main_branch[2].id.serialize_as_value()]
+            );
+        }
+
+        #[test]
+        fn get_blocks_by_chain_length() {
+            const N_BLOCKS: usize = 5;
+
+            let (_file, store) = prepare_store();
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+
+            let mut blocks = vec![];
+
+            for _i in 0..N_BLOCKS {
+                let block = genesis_block.make_child(None);
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                let block = block.serialize_as_vec();
+                store.put_block(&block, block_info).unwrap();
+                blocks.push(block);
+            }
+
+            let chain_length = genesis_block.chain_length + 1;
+
+            let expected: HashSet<_, std::collections::hash_map::RandomState> = HashSet::from_iter(
+                blocks
+                    .into_iter()
+                    .map(|block| Value::owned(block.into_boxed_slice())),
+            );
+            let actual = HashSet::from_iter(
+                store
+                    .get_blocks_by_chain_length(chain_length)
+                    .unwrap()
+                    .into_iter(),
+            );
+
+            assert_eq!(expected, actual);
+        }
+
+        #[test]
+        fn export_dot() {
+            const FROM: usize = 45;
+            const TO: usize = 55;
+
+            let (_file, store, main_branch, second_branch) = generate_two_branches();
+            let hex = |block: &Block| {
+                block
+                    .id
+                    .serialize_as_vec()
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            };
+            store
+                .put_tag("tip", &second_branch.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+            store
+                .put_tag("fork", &second_branch[1].id.serialize_as_vec())
+                .unwrap();
+
+            let dot = store.export_dot(FROM as u32, TO as u32).unwrap();
+            assert!(dot.starts_with("digraph"));
+
+            let in_range = |block: &&Block| (FROM..=TO).contains(&(block.chain_length as usize));
+            let blocks: Vec<_> = main_branch
+                .iter()
+                .chain(second_branch.iter().skip(1))
+                .filter(in_range)
+                .collect();
+            assert_eq!(dot.matches("[label=").count(), blocks.len());
+            assert_eq!(dot.matches(" -> ").count(), blocks.len() - 1);
+            for block in blocks {
+                assert!(dot.contains(&format!(
+                    "\"{}\" [label=\"{}\\nlength {}",
+                    hex(block),
+                    hex(block),
+                    block.chain_length
+                )));
+            }
+            assert!(dot.contains(&format!(
+                "\"{}\" -> \"{}\"",
+                hex(&second_branch[1]),
+                hex(&second_branch[0])
+            )));
+            assert!(dot.contains("\\ntags: fork\""));
+            assert!(!dot.contains("tip"));
+        }
+
+        fn generate_two_branches() -> (impl Sized, $store, Vec<Block>, Vec<Block>) {
+            const MAIN_BRANCH_LEN: usize = 100;
+            const SECOND_BRANCH_LEN: usize = 25;
+            const BIFURCATION_POINT: usize = 50;
+
+            let (file, store) = prepare_store();
+
+            let mut main_branch_blocks = vec![];
+
+            let genesis_block = Block::genesis(None);
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+
+            let mut block = genesis_block.make_child(None);
+
+            main_branch_blocks.push(genesis_block);
+
+            for _i in 1..MAIN_BRANCH_LEN {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+                main_branch_blocks.push(block.clone());
+                block = block.make_child(None);
+            }
+
+            let mut second_branch_blocks = vec![main_branch_blocks[BIFURCATION_POINT].clone()];
+
+            block = main_branch_blocks[BIFURCATION_POINT].make_child(None);
+
+            for _i in 1..SECOND_BRANCH_LEN {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+                second_branch_blocks.push(block.clone());
+                block = block.make_child(None);
+            }
+
+            (file, store, main_branch_blocks, second_branch_blocks)
+        }
+
+        #[test]
+        fn children_and_tips() {
+            const BIFURCATION_POINT: usize = 50;
+            const FLUSH_TO: usize = 40;
+
+            let (_file, store, main_branch_blocks, second_branch_blocks) = generate_two_branches();
+            let ids = |values: Vec<Value>| -> HashSet<Vec<u8>> {
+                values.iter().map(|id| id.as_ref().to_vec()).collect()
+            };
+            let children =
+                |block: &Block| ids(store.get_children(&block.id.serialize_as_vec()).unwrap());
+
+            assert_eq!(
+                children(&main_branch_blocks[BIFURCATION_POINT]),
+                HashSet::from_iter(vec![
+                    main_branch_blocks[BIFURCATION_POINT + 1]
+                        .id
+                        .serialize_as_vec(),
+                    second_branch_blocks[1].id.serialize_as_vec(),
+                ])
+            );
+            let main_tip = main_branch_blocks.last().unwrap();
+            let second_tip = second_branch_blocks.last().unwrap();
+            assert!(children(main_tip).is_empty());
+            assert!(children(second_tip).is_empty());
+            assert_eq!(
+                ids(store.get_branches().unwrap()),
+                HashSet::from_iter(vec![
+                    main_tip.id.serialize_as_vec(),
+                    second_tip.id.serialize_as_vec(),
+                ])
+            );
+
+            store
+                .flush_to_permanent_store(&main_branch_blocks[FLUSH_TO].id.serialize_as_vec(), 1)
+                .unwrap();
+            for i in &[FLUSH_TO - 10, FLUSH_TO] {
+                assert_eq!(
+                    children(&main_branch_blocks[*i]),
+                    HashSet::from_iter(vec![main_branch_blocks[i + 1].id.serialize_as_vec()])
+                );
+            }
+
+            assert!(matches!(
+                store.get_children(&Block::genesis(None).id.serialize_as_vec()),
+                Err(Error::BlockNotFound)
+            ));
+        }
+
+        #[test]
+        fn iter_from_checkpoints() {
+            const TIP: usize = 60;
+            const CLOSEST: usize = 30;
+            const FLUSH_TO: usize = 40;
+
+            let (_file, store, main_branch_blocks, second_branch_blocks) = generate_two_branches();
+            let to_block = main_branch_blocks[TIP].id.serialize_as_vec();
+            let unknown = Block::genesis(None).id.serialize_as_vec();
+            let checkpoints: Vec<_> = vec![
+                &main_branch_blocks[10],
+                &second_branch_blocks[10],
+                &main_branch_blocks[CLOSEST],
+            ]
+            .into_iter()
+            .map(|block| block.id.serialize_as_vec())
+            .chain(std::iter::once(unknown.clone()))
+            .collect();
+            let checkpoints: Vec<_> = checkpoints.iter().map(|id| &id[..]).collect();
+
+            let check = |store: &$store| {
+                let blocks = store
+                    .iter_from_checkpoints(&checkpoints, &to_block)
+                    .unwrap()
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                assert_eq!(blocks.len(), TIP - CLOSEST);
+                for (block, expected) in blocks.iter().zip(&main_branch_blocks[CLOSEST + 1..]) {
+                    assert_eq!(block.as_ref(), &expected.serialize_as_vec()[..]);
+                }
+            };
+            check(&store);
+            store
+                .flush_to_permanent_store(&main_branch_blocks[FLUSH_TO].id.serialize_as_vec(), 1)
+                .unwrap();
+            check(&store);
+
+            assert_eq!(
+                store
+                    .iter_from_checkpoints(&[&to_block], &to_block)
+                    .unwrap()
+                    .count(),
+                0
+            );
+            let root_id = BlockId(0).serialize_as_vec();
+            for checkpoints in &[vec![&root_id[..]], vec![&root_id[..], &unknown[..]]] {
+                let blocks = store
+                    .iter_from_checkpoints(checkpoints, &to_block)
+                    .unwrap()
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                assert_eq!(blocks.len(), TIP + 1);
+                assert_eq!(blocks[0].as_ref(), &main_branch_blocks[0].serialize_as_vec()[..]);
+            }
+            assert!(matches!(
+                store.iter_from_checkpoints(
+                    &[&second_branch_blocks[10].id.serialize_as_vec()],
+                    &to_block
+                ),
+                Err(Error::CheckpointNotFound)
+            ));
+        }
+
+        #[test]
+        fn is_ancestor_same_branch() {
+            const FIRST: usize = 20;
+            const SECOND: usize = 30;
+
+            let (_file, store, main_branch_blocks, _) = generate_two_branches();
+
+            let result = store
+                .is_ancestor(
+                    &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
+                    &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
+                )
+                .unwrap()
+                .expect("should be a non-None result") as usize;
+            assert!(SECOND - FIRST == result);
+        }
+
+        #[test]
+        fn is_ancestor_wrong_order() {
+            const FIRST: usize = 30;
+            const SECOND: usize = 20;
+
+            let (_file, store, main_branch_blocks, _) = generate_two_branches();
+
+            let result = store
+                .is_ancestor(
+                    &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
+                    &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
+                )
+                .unwrap();
+            assert!(result.is_none());
+        }
+
+        #[test]
+        fn is_ancestor_different_branches() {
+            const FIRST: usize = 60;
+            const SECOND: usize = 10;
+
+            let (_file, store, main_branch_blocks, second_branch_blocks) = generate_two_branches();
+
+            let result = store
+                .is_ancestor(
+                    &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
+                    &second_branch_blocks[SECOND].id.serialize_as_vec()[..],
+                )
+                .unwrap();
+            assert!(result.is_none());
+        }
+
+        #[test]
+        fn is_ancestor_permanent_volatile() {
+            const PERMANENT_STORAGE_START: usize = 40;
+            const FIRST: usize = 10;
+            const SECOND: usize = 50;
+
+            let (_file, store, main_branch_blocks, _) = generate_two_branches();
+
+            store
+                .flush_to_permanent_store(
+                    &main_branch_blocks[PERMANENT_STORAGE_START]
+                        .id
+                        .serialize_as_vec(),
+                    1,
+                )
+                .unwrap();
+
+            let result = store
+                .is_ancestor(
+                    &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
+                    &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
+                )
+                .unwrap()
+                .expect("should be a non-None result") as usize;
+            assert!(SECOND - FIRST == result);
+        }
+
+        #[test]
+        fn is_ancestor_only_permanent() {
+            const PERMANENT_STORAGE_START: usize = 40;
+            const FIRST: usize = 10;
+            const SECOND: usize = 20;
+
+            let (_file, store, main_branch_blocks, _) = generate_two_branches();
+
+            store
+                .flush_to_permanent_store(
+                    &main_branch_blocks[PERMANENT_STORAGE_START]
+                        .id
+                        .serialize_as_vec(),
+                    1,
+                )
+                .unwrap();
+
+            let result = store
+                .is_ancestor(
+                    &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
+                    &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
+                )
+                .unwrap()
+                .expect("should be a non-None result") as usize;
+            assert!(SECOND - FIRST == result);
+        }
+
+        fn prepare_and_fill_store(n: usize) -> (impl Sized, $store, Vec<Block>) {
+            const BLOCK_DATA_LENGTH: usize = 512;
+
+            let mut rng = OsRng;
+            let mut block_data = [0; BLOCK_DATA_LENGTH];
+
+            let (file, store) = prepare_store();
+
+            let mut blocks = vec![];
+
+            rng.fill_bytes(&mut block_data);
+            let genesis_block =
+                Block::genesis(Some(block_data.clone().to_vec().into_boxed_slice()));
+            let genesis_block_info = BlockInfo::new(
+                genesis_block.id.serialize_as_vec(),
+                genesis_block.parent.serialize_as_vec(),
+                genesis_block.chain_length,
+            );
+            store
+                .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
+                .unwrap();
+
+            rng.fill_bytes(&mut block_data);
+            let mut block =
+                genesis_block.make_child(Some(block_data.clone().to_vec().into_boxed_slice()));
+
+            blocks.push(genesis_block);
+
+            for _i in 1..n {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+                blocks.push(block.clone());
+                rng.fill_bytes(&mut block_data);
+                block = block.make_child(Some(block_data.clone().to_vec().into_boxed_slice()));
+            }
+
+            (file, store, blocks)
+        }
+
+        fn prepare_permament_store() -> (impl Sized, $store, Vec<Block>) {
+            let (file, store, blocks) = prepare_and_fill_store(BLOCK_NUM_PERMANENT_TEST);
+
+            store
+                .flush_to_permanent_store(&blocks[FLUSH_TO_BLOCK].id.serialize_as_vec(), 1)
+                .unwrap();
+
+            (file, store, blocks)
+        }
+
+        #[test]
+        fn permanent_store_read() {
+            let (_file, store, blocks) = prepare_permament_store();
+
+            for block in blocks.iter() {
+                let block_id = block.id.serialize_as_vec();
+
+                let block_info = store.get_block_info(&block_id).unwrap();
+                assert_eq!(&block.id.serialize_as_vec()[..], block_info.id().as_ref());
+                assert_eq!(
+                    &block.parent.serialize_as_vec()[..],
+                    block_info.parent_id().as_ref()
+                );
+                assert_eq!(block.chain_length, block_info.chain_length());
+
+                let actual_block = store.get_block(&block_id).unwrap();
+                assert_eq!(block.serialize_as_value().as_ref(), actual_block.as_ref());
+            }
+        }
+
+        #[test]
+        fn permanent_store_flush_twice() {
+            let (_file, store, blocks) = prepare_permament_store();
+
+            store
+                .flush_to_permanent_store(&blocks[FLUSH_TO_BLOCK_2].id.serialize_as_vec(), 1)
+                .unwrap();
+
+            for (i, block) in store
+                .iter(
+                    &blocks[BLOCK_NUM_PERMANENT_TEST - 1].id.serialize_as_vec(),
+                    BLOCK_NUM_PERMANENT_TEST as u32,
+                )
+                .unwrap()
+                .map(|result| result.unwrap())
+                .enumerate()
+            {
+                assert_eq!(blocks[i].serialize_as_value(), block);
+            }
+        }
+
+        #[test]
+        fn permanent_store_tag() {
+            const TAGS_TEST_LENGTH: usize = 20;
+
+            let (_file, store, blocks) = prepare_permament_store();
+
+            store
+                .put_tag("test1", &blocks[TAGS_TEST_LENGTH].id.serialize_as_vec())
+                .unwrap();
+        }
+
+        #[test]
+        fn permanent_store_prune_main_branch() {
+            let (_file, store, blocks) = prepare_permament_store();
+
+            store
+                .prune_branch(&blocks.last().unwrap().id.serialize_as_vec())
+                .unwrap();
+
+            for i in 0..=FLUSH_TO_BLOCK {
+                assert!(store
+                    .block_exists(&blocks[i].id.serialize_as_vec())
+                    .unwrap());
+            }
+
+            for i in (FLUSH_TO_BLOCK + 1)..FLUSH_TO_BLOCK {
+                assert!(!store.block_exists(&blocks[i].serialize_as_vec()).unwrap());
+            }
+
+            assert_eq!(
+                vec![blocks[FLUSH_TO_BLOCK].id.serialize_as_value()],
+                store.get_tips_ids().unwrap()
+            );
+        }
+
+        #[test]
+        fn permanent_store_get_by_chain_length() {
+            const CHAIN_LENGTH: usize = 20;
+
+            let (_file, store, blocks) = prepare_permament_store();
+
+            let chain_length = blocks[CHAIN_LENGTH].chain_length;
+            assert_eq!(
+                vec![blocks[CHAIN_LENGTH].serialize_as_value()],
+                store.get_blocks_by_chain_length(chain_length).unwrap()
+            );
+        }
+
+        #[test]
+        fn iterator_only_volatile_storage() {
+            const TEST_BLOCK_NUM: usize = 32;
+
+            let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
+
+            for (i, block) in store
+                .iter(
+                    &blocks[blocks.len() - 1].id.serialize_as_vec()[..],
+                    TEST_BLOCK_NUM as u32,
+                )
+                .unwrap()
+                .enumerate()
+            {
+                assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
+            }
+        }
+
+        #[test]
+        fn iterator_volatile_and_permanent_storage() {
+            const TEST_BLOCK_NUM: usize = 32;
+            const FLUSH_AT: usize = 16;
+
+            let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
+
+            store
+                .flush_to_permanent_store(&blocks[FLUSH_AT].id.serialize_as_vec()[..], 1)
+                .unwrap();
+
+            for (i, block) in store
+                .iter(
+                    &blocks[blocks.len() - 1].id.serialize_as_vec()[..],
+                    TEST_BLOCK_NUM as u32,
+                )
+                .unwrap()
+                .enumerate()
+            {
+                assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
+            }
+        }
+
+        #[test]
+        fn iterator_only_permanent_storage() {
+            const TEST_BLOCK_NUM: usize = 32;
+
+            let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
+
+            store
+                .flush_to_permanent_store(&blocks[blocks.len() - 1].id.serialize_as_vec()[..], 1)
+                .unwrap();
+
+            for (i, block) in store
+                .iter(
+                    &blocks[blocks.len() - 1].id.serialize_as_vec()[..],
+                    TEST_BLOCK_NUM as u32,
+                )
+                .unwrap()
+                .enumerate()
+            {
+                assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
+            }
+        }
+    };
+}
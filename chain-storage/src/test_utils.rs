@@ -3,6 +3,12 @@
 use crate::Value;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+pub use crate::faulty::{FaultyBlockStore, Operation};
+
+// used by `storage_test_suite!`
+#[doc(hidden)]
+pub use rand_core;
+
 #[derive(Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq, Copy)]
 pub struct BlockId(pub u64);
 
@@ -1,40 +1,21 @@
-use crate::{
-    test_utils::{Block, BlockId},
-    BlockInfo, BlockStore, Error, Value,
-};
-use rand_core::{OsRng, RngCore};
-use std::{collections::HashSet, iter::FromIterator};
-
-const SIMULTANEOUS_READ_WRITE_ITERS: usize = 50;
-const BLOCK_NUM_PERMANENT_TEST: usize = 1024;
-const FLUSH_TO_BLOCK: usize = 512;
-const FLUSH_TO_BLOCK_2: usize = 768;
-
-pub fn pick_from_vector<'a, A, R: RngCore>(rng: &mut R, v: &'a [A]) -> &'a A {
-    let s = rng.next_u32() as usize;
-    // this doesn't need to be uniform
-    &v[s % v.len()]
-}
+use crate::BlockStore;
+
+mod file {
+    use super::*;
+
+    crate::storage_test_suite!(BlockStore, || {
+        let file = tempfile::TempDir::new().unwrap();
+        let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+        (file, store)
+    });
 
-pub fn generate_chain<R: RngCore>(rng: &mut R, store: &BlockStore) -> Vec<Block> {
-    let mut blocks = vec![];
-
-    let genesis_block = Block::genesis(None);
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-    store
-        .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
-        .unwrap();
-    blocks.push(genesis_block);
-
-    for _ in 0..10 {
-        let mut parent_block = pick_from_vector(rng, &blocks).clone();
-        let r = 1 + (rng.next_u32() % 99);
-        for _ in 0..r {
-            let block = parent_block.make_child(None);
+    #[test]
+    fn blocks_are_persisted_when_the_store_is_dropped() {
+        let file = tempfile::TempDir::new().unwrap();
+        let block = Block::genesis(None);
+        {
+            let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+            let other_handle = store.clone();
             let block_info = BlockInfo::new(
                 block.id.serialize_as_vec(),
                 block.parent.serialize_as_vec(),
@@ -43,388 +24,253 @@ pub fn generate_chain<R: RngCore>(rng: &mut R, store: &BlockStore) -> Vec<Block>
             store
                 .put_block(&block.serialize_as_vec(), block_info)
                 .unwrap();
-            parent_block = block.clone();
-            blocks.push(block);
+            store.sync().unwrap();
+            drop(store);
+            assert!(other_handle
+                .block_exists(&block.id.serialize_as_vec())
+                .unwrap());
         }
-    }
-
-    blocks
-}
-
-fn prepare_store() -> (tempfile::TempDir, BlockStore) {
-    let file = tempfile::TempDir::new().unwrap();
-    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
-
-    (file, store)
-}
-
-#[test]
-fn tag_get_non_existent() {
-    let (_file, store) = prepare_store();
-    assert!(store.get_tag("tip").unwrap().is_none());
-}
-
-#[test]
-fn tag_non_existent_block() {
-    let (_file, store) = prepare_store();
-    match store.put_tag("tip", &BlockId(0).serialize_as_vec()) {
-        Err(Error::BlockNotFound) => {}
-        err => panic!(err),
-    }
-}
 
-#[test]
-fn tag_put() {
-    let mut rng = OsRng;
-
-    let (_file, store) = prepare_store();
-    let blocks = generate_chain(&mut rng, &store);
-
-    store
-        .put_tag("tip", &blocks.last().unwrap().id.serialize_as_vec())
-        .unwrap();
-    assert_eq!(
-        store.get_tag("tip").unwrap().unwrap(),
-        blocks.last().unwrap().id.serialize_as_value()
-    );
-}
-
-#[test]
-fn tag_overwrite() {
-    let mut rng = OsRng;
-
-    let (_file, store) = prepare_store();
-    let blocks = generate_chain(&mut rng, &store);
-
-    store
-        .put_tag("tip", &blocks.last().unwrap().id.serialize_as_vec())
-        .unwrap();
-    store
-        .put_tag("tip", &blocks.first().unwrap().id.serialize_as_vec())
-        .unwrap();
-    assert_eq!(
-        store.get_tag("tip").unwrap().unwrap(),
-        blocks.first().unwrap().id.serialize_as_value()
-    );
-}
-
-#[test]
-fn block_read_write() {
-    let (_file, store) = prepare_store();
-    let genesis_block = Block::genesis(None);
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-
-    assert!(!store
-        .block_exists(genesis_block_info.id().as_ref())
-        .unwrap());
-
-    store
-        .put_block(
-            &genesis_block.serialize_as_vec(),
-            genesis_block_info.clone(),
-        )
-        .unwrap();
-    let genesis_block_restored = store
-        .get_block_info(&genesis_block.id.serialize_as_vec())
-        .unwrap();
-
-    assert!(store
-        .block_exists(genesis_block_info.id().as_ref())
-        .unwrap());
-
-    assert_eq!(
-        &genesis_block.id.serialize_as_vec()[..],
-        genesis_block_restored.id().as_ref()
-    );
-    assert_eq!(
-        &genesis_block.parent.serialize_as_vec()[..],
-        genesis_block_restored.parent_id().as_ref()
-    );
-    assert_eq!(
-        genesis_block.chain_length,
-        genesis_block_restored.chain_length()
-    );
-
-    assert_eq!(
-        vec![genesis_block.id.serialize_as_value()],
-        store.get_tips_ids().unwrap()
-    );
-}
-
-#[test]
-pub fn nth_ancestor() {
-    let mut rng = OsRng;
-    let file = tempfile::TempDir::new().unwrap();
-    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
-    let blocks = generate_chain(&mut rng, &store);
-
-    let mut blocks_fetched = 0;
-    let mut total_distance = 0;
-    let nr_tests = 1000;
-
-    for _ in 0..nr_tests {
-        let block = pick_from_vector(&mut rng, &blocks);
+        let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
         assert_eq!(
             store.get_block(&block.id.serialize_as_vec()).unwrap(),
             block.serialize_as_value()
         );
-
-        let distance = rng.next_u32().checked_rem(block.chain_length).unwrap_or(0);
-        total_distance += distance;
-
-        let ancestor_info = store
-            .get_nth_ancestor(&block.id.serialize_as_vec(), distance)
-            .unwrap();
-        blocks_fetched += block.chain_length - distance;
-
-        assert_eq!(ancestor_info.chain_length() + distance, block.chain_length);
-    }
-
-    let blocks_per_test = blocks_fetched as f64 / nr_tests as f64;
-
-    println!(
-        "fetched {} intermediate blocks ({} per test), total distance {}",
-        blocks_fetched, blocks_per_test, total_distance
-    );
-}
-
-#[test]
-fn simultaneous_read_write() {
-    let mut rng = OsRng;
-    let file = tempfile::TempDir::new().unwrap();
-    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
-
-    let genesis_block = Block::genesis(None);
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-    store
-        .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
-        .unwrap();
-    let mut blocks = vec![genesis_block];
-
-    for _ in 1..SIMULTANEOUS_READ_WRITE_ITERS {
-        let last_block = blocks.get(rng.next_u32() as usize % blocks.len()).unwrap();
-        let block = last_block.make_child(None);
-        blocks.push(block.clone());
-        let block_info = BlockInfo::new(
-            block.id.serialize_as_vec(),
-            block.parent.serialize_as_vec(),
-            block.chain_length,
-        );
-        store
-            .put_block(&block.serialize_as_vec(), block_info)
-            .unwrap()
     }
 
-    let store_1 = store.clone();
-    let blocks_1 = blocks.clone();
-
-    let thread_1 = std::thread::spawn(move || {
-        for _ in 1..SIMULTANEOUS_READ_WRITE_ITERS {
-            let block_id = blocks_1
-                .get(rng.next_u32() as usize % blocks_1.len())
+    #[test]
+    fn concurrent_blocks_are_durable_with_a_commit_latency() {
+        let file = tempfile::TempDir::new().unwrap();
+        let genesis = Block::genesis(None);
+        let children: Vec<_> = (0..8).map(|_| genesis.make_child(None)).collect();
+        {
+            let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec())
                 .unwrap()
-                .id
-                .serialize_as_vec();
-            store_1.get_block(&block_id).unwrap();
+                .with_commit_latency(std::time::Duration::from_millis(10));
+            let put = |store: &BlockStore, block: &Block| {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+            };
+            put(&store, &genesis);
+
+            let handles: Vec<_> = children
+                .iter()
+                .cloned()
+                .map(|block| {
+                    let store = store.clone();
+                    std::thread::spawn(move || put(&store, &block))
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
         }
-    });
 
-    let thread_2 = std::thread::spawn(move || {
-        for _ in 1..SIMULTANEOUS_READ_WRITE_ITERS {
-            let last_block = blocks.get(rng.next_u32() as usize % blocks.len()).unwrap();
-            let block = last_block.make_child(None);
-            let block_info = BlockInfo::new(
-                block.id.serialize_as_vec(),
-                block.parent.serialize_as_vec(),
-                block.chain_length,
+        let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+        for block in children.iter().chain(std::iter::once(&genesis)) {
+            assert_eq!(
+                store.get_block(&block.id.serialize_as_vec()).unwrap(),
+                block.serialize_as_value()
             );
-            store
-                .put_block(&block.serialize_as_vec(), block_info)
-                .unwrap()
         }
-    });
-
-    thread_1.join().unwrap();
-    thread_2.join().unwrap();
-}
-
-#[test]
-fn branch_pruning() {
-    const MAIN_BRANCH_LEN: usize = 100;
-    const SECOND_BRANCH_LEN: usize = 25;
-    const BIFURCATION_POINT: usize = 50;
-
-    let file = tempfile::TempDir::new().unwrap();
-    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
-
-    let mut main_branch_blocks = vec![];
-
-    let genesis_block = Block::genesis(None);
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-    store
-        .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
-        .unwrap();
-
-    let mut block = genesis_block.make_child(None);
-
-    main_branch_blocks.push(genesis_block);
-
-    for _i in 1..MAIN_BRANCH_LEN {
-        let block_info = BlockInfo::new(
-            block.id.serialize_as_vec(),
-            block.parent.serialize_as_vec(),
-            block.chain_length,
-        );
-        store
-            .put_block(&block.serialize_as_vec(), block_info)
-            .unwrap();
-        main_branch_blocks.push(block.clone());
-        block = block.make_child(None);
     }
 
-    let mut second_branch_blocks = vec![main_branch_blocks[BIFURCATION_POINT].clone()];
+    #[test]
+    fn block_info_is_rebuilt_from_the_blocks() {
+        let file = tempfile::TempDir::new().unwrap();
+        let genesis = Block::genesis(None);
+        let block1 = genesis.make_child(None);
+        let block2 = block1.make_child(None);
+        let fork2 = block1.make_child(None);
+        let fork3 = fork2.make_child(None);
+        {
+            let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+            for block in &[&genesis, &block1, &block2, &fork2, &fork3] {
+                let block_info = BlockInfo::new(
+                    block.id.serialize_as_vec(),
+                    block.parent.serialize_as_vec(),
+                    block.chain_length,
+                );
+                store
+                    .put_block(&block.serialize_as_vec(), block_info)
+                    .unwrap();
+            }
+            store.put_tag("tip", &block2.id.serialize_as_vec()).unwrap();
+            store
+                .flush_to_permanent_store(&block1.id.serialize_as_vec(), 1)
+                .unwrap();
+        }
 
-    block = main_branch_blocks[BIFURCATION_POINT].make_child(None);
+        {
+            let volatile = sled::open(file.path().join("volatile")).unwrap();
+            for tree in &["info", "length_to_block_ids", "branches_tips"] {
+                volatile.open_tree(tree).unwrap().clear().unwrap();
+            }
+        }
 
-    for _i in 1..SECOND_BRANCH_LEN {
-        let block_info = BlockInfo::new(
-            block.id.serialize_as_vec(),
-            block.parent.serialize_as_vec(),
-            block.chain_length,
-        );
-        store
-            .put_block(&block.serialize_as_vec(), block_info)
+        let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+        assert!(matches!(
+            store.get_block_info(&block2.id.serialize_as_vec()),
+            Err(Error::BlockNotFound)
+        ));
+
+        let rebuilt = store
+            .rebuild_block_info(|block| {
+                block
+                    .get(8..16)
+                    .map(|parent_id| Value::from(parent_id.to_vec()))
+                    .ok_or("block too short")
+            })
             .unwrap();
-        second_branch_blocks.push(block.clone());
-        block = block.make_child(None);
-    }
-
-    let expected_tips = {
-        let mut hs = HashSet::new();
-        hs.insert(main_branch_blocks.last().unwrap().id.serialize_as_value());
-        hs.insert(second_branch_blocks.last().unwrap().id.serialize_as_value());
-        hs
-    };
-    let actual_tips = HashSet::from_iter(store.get_tips_ids().unwrap().into_iter());
-    assert_eq!(expected_tips, actual_tips);
+        assert_eq!(rebuilt, 3);
 
-    store
-        .prune_branch(&second_branch_blocks.last().unwrap().id.serialize_as_vec())
-        .unwrap();
-
-    assert_eq!(
-        vec![main_branch_blocks.last().unwrap().id.serialize_as_value()],
-        store.get_tips_ids().unwrap()
-    );
-
-    store
-        .get_block(&second_branch_blocks[0].id.serialize_as_vec())
-        .unwrap();
+        for block in &[&block1, &block2, &fork2, &fork3] {
+            let block_info = store.get_block_info(&block.id.serialize_as_vec()).unwrap();
+            assert_eq!(block_info.chain_length(), block.chain_length);
+            assert_eq!(block_info.parent_id(), &block.parent.serialize_as_value());
+        }
+        assert_eq!(
+            store.get_blocks_by_chain_length(3).unwrap(),
+            vec![fork3.serialize_as_value()]
+        );
+        let tips: HashSet<_> = store
+            .get_tips_ids()
+            .unwrap()
+            .into_iter()
+            .map(|id| id.as_ref().to_vec())
+            .collect();
+        assert_eq!(
+            tips,
+            HashSet::from_iter(vec![
+                block2.id.serialize_as_vec(),
+                fork3.id.serialize_as_vec()
+            ])
+        );
 
-    for i in 1..SECOND_BRANCH_LEN {
-        let block_result = store.get_block(&second_branch_blocks[i].id.serialize_as_vec());
-        assert!(matches!(block_result, Err(Error::BlockNotFound)));
+        // the tag still holds its block, the fork is removed down to the
+        // common ancestor
+        store.prune_branch(&block2.id.serialize_as_vec()).unwrap();
+        assert!(store.block_exists(&block2.id.serialize_as_vec()).unwrap());
+        store.prune_branch(&fork3.id.serialize_as_vec()).unwrap();
+        assert!(!store.block_exists(&fork2.id.serialize_as_vec()).unwrap());
+        assert!(store.block_exists(&block1.id.serialize_as_vec()).unwrap());
     }
-
-    // tagged branch must not be removed
-    store
-        .put_tag(
-            "tip",
-            &main_branch_blocks.last().unwrap().id.serialize_as_vec(),
-        )
-        .unwrap();
-    store
-        .prune_branch(&main_branch_blocks.last().unwrap().id.serialize_as_vec())
-        .unwrap();
-    assert!(store
-        .block_exists(&main_branch_blocks.last().unwrap().id.serialize_as_vec())
-        .unwrap());
 }
 
-#[test]
-fn get_blocks_by_chain_length() {
-    const N_BLOCKS: usize = 5;
+mod memory {
+    use super::*;
 
-    let file = tempfile::TempDir::new().unwrap();
-    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+    crate::storage_test_suite!(BlockStore, || {
+        (
+            (),
+            BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap(),
+        )
+    });
+}
 
-    let genesis_block = Block::genesis(None);
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-    store
-        .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
-        .unwrap();
+mod faulty {
+    use super::*;
+    use crate::test_utils::{FaultyBlockStore, Operation};
+    use std::time::{Duration, Instant};
 
-    let mut blocks = vec![];
+    crate::storage_test_suite!(FaultyBlockStore, || {
+        (
+            (),
+            FaultyBlockStore::new(BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap()),
+        )
+    });
 
-    for _i in 0..N_BLOCKS {
-        let block = genesis_block.make_child(None);
+    fn put(store: &FaultyBlockStore, block: &Block) -> Result<(), Error> {
         let block_info = BlockInfo::new(
             block.id.serialize_as_vec(),
             block.parent.serialize_as_vec(),
             block.chain_length,
         );
-        let block = block.serialize_as_vec();
-        store.put_block(&block, block_info).unwrap();
-        blocks.push(block);
+        store.put_block(&block.serialize_as_vec(), block_info)
     }
 
-    let chain_length = genesis_block.chain_length + 1;
-
-    let expected: HashSet<_, std::collections::hash_map::RandomState> = HashSet::from_iter(
-        blocks
-            .into_iter()
-            .map(|block| Value::owned(block.into_boxed_slice())),
-    );
-    let actual = HashSet::from_iter(
-        store
-            .get_blocks_by_chain_length(chain_length)
-            .unwrap()
-            .into_iter(),
-    );
+    #[test]
+    fn failing_operations_have_no_effect() {
+        let store =
+            FaultyBlockStore::new(BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap())
+                .fail_every(Operation::PutBlock, 2);
+        let genesis = Block::genesis(None);
+        let child = genesis.make_child(None);
+
+        put(&store, &genesis).unwrap();
+        assert!(matches!(
+            put(&store, &child),
+            Err(Error::VolatileBackendError(_))
+        ));
+        assert!(!store
+            .inner()
+            .block_exists(&child.id.serialize_as_vec())
+            .unwrap());
+        // the retry is the third call
+        put(&store, &child).unwrap();
+        assert_eq!(store.calls(Operation::PutBlock), 3);
+        assert_eq!(store.injected_faults(Operation::PutBlock), 1);
+
+        store.fail_next(Operation::GetBlock, 1);
+        let other_handle = store.clone();
+        assert!(other_handle
+            .get_block(&child.id.serialize_as_vec())
+            .is_err());
+        assert!(other_handle.get_block(&child.id.serialize_as_vec()).is_ok());
+
+        store.fail_next(Operation::GetTag, 5);
+        store.heal();
+        assert_eq!(store.get_tag("tip").unwrap(), None);
+    }
 
-    assert_eq!(expected, actual);
+    #[test]
+    fn errors_and_latency_are_configurable() {
+        let latency = Duration::from_millis(20);
+        let store =
+            FaultyBlockStore::new(BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap())
+                .with_latency(Operation::GetTipsIds, latency)
+                .with_error(|_| Error::BlockNotFound);
+        store.fail_next(Operation::GetTipsIds, 1);
+
+        let start = Instant::now();
+        assert!(matches!(store.get_tips_ids(), Err(Error::BlockNotFound)));
+        assert!(store.get_tips_ids().unwrap().is_empty());
+        assert!(start.elapsed() >= 2 * latency);
+    }
 }
 
-fn generate_two_branches() -> (tempfile::TempDir, BlockStore, Vec<Block>, Vec<Block>) {
-    const MAIN_BRANCH_LEN: usize = 100;
-    const SECOND_BRANCH_LEN: usize = 25;
-    const BIFURCATION_POINT: usize = 50;
-
-    let (file, store) = prepare_store();
+#[cfg(feature = "encryption")]
+mod encrypted {
+    use super::*;
+    use crate::encryption::Keyring;
 
-    let mut main_branch_blocks = vec![];
+    const KEY_1: [u8; 32] = [1; 32];
+    const KEY_2: [u8; 32] = [2; 32];
 
-    let genesis_block = Block::genesis(None);
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-    store
-        .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
-        .unwrap();
-
-    let mut block = genesis_block.make_child(None);
+    crate::storage_test_suite!(BlockStore, || {
+        (
+            (),
+            BlockStore::memory(BlockId(0).serialize_as_vec())
+                .unwrap()
+                .with_encryption(Keyring::new(1, KEY_1)),
+        )
+    });
 
-    main_branch_blocks.push(genesis_block);
+    fn open(path: &std::path::Path, keyring: Option<Keyring>) -> BlockStore {
+        let store = BlockStore::file(path, BlockId(0).serialize_as_vec()).unwrap();
+        match keyring {
+            Some(keyring) => store.with_encryption(keyring),
+            None => store,
+        }
+    }
 
-    for _i in 1..MAIN_BRANCH_LEN {
+    fn put(store: &BlockStore, block: &Block) {
         let block_info = BlockInfo::new(
             block.id.serialize_as_vec(),
             block.parent.serialize_as_vec(),
@@ -433,335 +279,174 @@ fn generate_two_branches() -> (tempfile::TempDir, BlockStore, Vec<Block>, Vec<Bl
         store
             .put_block(&block.serialize_as_vec(), block_info)
             .unwrap();
-        main_branch_blocks.push(block.clone());
-        block = block.make_child(None);
     }
 
-    let mut second_branch_blocks = vec![main_branch_blocks[BIFURCATION_POINT].clone()];
+    #[test]
+    fn blocks_are_sealed_in_both_storages() {
+        let file = tempfile::TempDir::new().unwrap();
+        let genesis = Block::genesis(Some(vec![0xaa; 64].into_boxed_slice()));
+        let child = genesis.make_child(Some(vec![0xbb; 64].into_boxed_slice()));
+        {
+            let store = open(file.path(), Some(Keyring::new(1, KEY_1)));
+            put(&store, &genesis);
+            put(&store, &child);
+            store
+                .flush_to_permanent_store(&genesis.id.serialize_as_vec(), 1)
+                .unwrap();
+        }
 
-    block = main_branch_blocks[BIFURCATION_POINT].make_child(None);
+        let store = open(file.path(), None);
+        for block in &[&genesis, &child] {
+            let stored = store.get_block(&block.id.serialize_as_vec()).unwrap();
+            assert_ne!(stored, block.serialize_as_value());
+            assert!(!stored
+                .as_ref()
+                .windows(block.data.len())
+                .any(|window| window == block.data.as_ref()));
+        }
 
-    for _i in 1..SECOND_BRANCH_LEN {
-        let block_info = BlockInfo::new(
-            block.id.serialize_as_vec(),
-            block.parent.serialize_as_vec(),
-            block.chain_length,
-        );
-        store
-            .put_block(&block.serialize_as_vec(), block_info)
+        let store = open(file.path(), Some(Keyring::new(1, KEY_1)));
+        let blocks: Vec<_> = store
+            .iter(&child.id.serialize_as_vec(), 2)
+            .unwrap()
+            .collect::<Result<_, _>>()
             .unwrap();
-        second_branch_blocks.push(block.clone());
-        block = block.make_child(None);
+        assert_eq!(
+            blocks,
+            vec![genesis.serialize_as_value(), child.serialize_as_value()]
+        );
+        assert_eq!(
+            store.get_blocks_by_chain_length(0).unwrap(),
+            vec![genesis.serialize_as_value()]
+        );
     }
 
-    (file, store, main_branch_blocks, second_branch_blocks)
-}
-
-#[test]
-fn is_ancestor_same_branch() {
-    const FIRST: usize = 20;
-    const SECOND: usize = 30;
-
-    let (_file, store, main_branch_blocks, _) = generate_two_branches();
-
-    let result = store
-        .is_ancestor(
-            &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
-            &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
-        )
-        .unwrap()
-        .expect("should be a non-None result") as usize;
-    assert!(SECOND - FIRST == result);
-}
-
-#[test]
-fn is_ancestor_wrong_order() {
-    const FIRST: usize = 30;
-    const SECOND: usize = 20;
-
-    let (_file, store, main_branch_blocks, _) = generate_two_branches();
-
-    let result = store
-        .is_ancestor(
-            &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
-            &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
-        )
-        .unwrap();
-    assert!(result.is_none());
-}
-
-#[test]
-fn is_ancestor_different_branches() {
-    const FIRST: usize = 60;
-    const SECOND: usize = 10;
-
-    let (_file, store, main_branch_blocks, second_branch_blocks) = generate_two_branches();
-
-    let result = store
-        .is_ancestor(
-            &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
-            &second_branch_blocks[SECOND].id.serialize_as_vec()[..],
-        )
-        .unwrap();
-    assert!(result.is_none());
-}
-
-#[test]
-fn is_ancestor_permanent_volatile() {
-    const PERMANENT_STORAGE_START: usize = 40;
-    const FIRST: usize = 10;
-    const SECOND: usize = 50;
-
-    let (_file, store, main_branch_blocks, _) = generate_two_branches();
-
-    store
-        .flush_to_permanent_store(
-            &main_branch_blocks[PERMANENT_STORAGE_START]
-                .id
-                .serialize_as_vec(),
-            1,
-        )
-        .unwrap();
-
-    let result = store
-        .is_ancestor(
-            &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
-            &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
-        )
-        .unwrap()
-        .expect("should be a non-None result") as usize;
-    assert!(SECOND - FIRST == result);
-}
+    #[test]
+    fn rotation_keeps_the_blocks_readable() {
+        let file = tempfile::TempDir::new().unwrap();
+        let genesis = Block::genesis(None);
+        let child = genesis.make_child(None);
+        {
+            let store = open(file.path(), Some(Keyring::new(1, KEY_1)));
+            put(&store, &genesis);
+            put(&store, &child);
+            store
+                .flush_to_permanent_store(&genesis.id.serialize_as_vec(), 1)
+                .unwrap();
 
-#[test]
-fn is_ancestor_only_permanent() {
-    const PERMANENT_STORAGE_START: usize = 40;
-    const FIRST: usize = 10;
-    const SECOND: usize = 20;
+            // only the block of the volatile storage is sealed again
+            assert_eq!(store.rotate_encryption_key(2, KEY_2).unwrap(), 1);
+            assert_eq!(store.rotate_encryption_key(2, KEY_2).unwrap(), 0);
+        }
 
-    let (_file, store, main_branch_blocks, _) = generate_two_branches();
+        let store = open(file.path(), Some(Keyring::new(2, KEY_2)));
+        assert_eq!(
+            store.get_block(&child.id.serialize_as_vec()).unwrap(),
+            child.serialize_as_value()
+        );
+        assert!(matches!(
+            store.get_block(&genesis.id.serialize_as_vec()),
+            Err(Error::UnknownEncryptionKey(1))
+        ));
 
-    store
-        .flush_to_permanent_store(
-            &main_branch_blocks[PERMANENT_STORAGE_START]
-                .id
-                .serialize_as_vec(),
-            1,
-        )
-        .unwrap();
+        let store = open(file.path(), Some(Keyring::new(2, KEY_2).with_key(1, KEY_1)));
+        assert_eq!(
+            store.get_block(&genesis.id.serialize_as_vec()).unwrap(),
+            genesis.serialize_as_value()
+        );
+    }
 
-    let result = store
-        .is_ancestor(
-            &main_branch_blocks[FIRST].id.serialize_as_vec()[..],
-            &main_branch_blocks[SECOND].id.serialize_as_vec()[..],
-        )
-        .unwrap()
-        .expect("should be a non-None result") as usize;
-    assert!(SECOND - FIRST == result);
+    #[test]
+    fn blocks_are_not_opened_with_a_wrong_key() {
+        let store = BlockStore::memory(BlockId(0).serialize_as_vec())
+            .unwrap()
+            .with_encryption(Keyring::new(1, KEY_1));
+        let genesis = Block::genesis(None);
+        put(&store, &genesis);
+
+        let other_handle = store.clone().with_encryption(Keyring::new(1, KEY_2));
+        assert!(matches!(
+            other_handle.get_block(&genesis.id.serialize_as_vec()),
+            Err(Error::BlockDecryption)
+        ));
+
+        let unencrypted = BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap();
+        assert!(matches!(
+            unencrypted.rotate_encryption_key(2, KEY_2),
+            Err(Error::NotEncrypted)
+        ));
+    }
 }
 
-fn prepare_and_fill_store(n: usize) -> (tempfile::TempDir, BlockStore, Vec<Block>) {
-    const BLOCK_DATA_LENGTH: usize = 512;
-
-    let mut rng = OsRng;
-    let mut block_data = [0; BLOCK_DATA_LENGTH];
-
-    let file = tempfile::TempDir::new().unwrap();
-    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
-
-    let mut blocks = vec![];
-
-    rng.fill_bytes(&mut block_data);
-    let genesis_block = Block::genesis(Some(block_data.clone().to_vec().into_boxed_slice()));
-    let genesis_block_info = BlockInfo::new(
-        genesis_block.id.serialize_as_vec(),
-        genesis_block.parent.serialize_as_vec(),
-        genesis_block.chain_length,
-    );
-    store
-        .put_block(&genesis_block.serialize_as_vec(), genesis_block_info)
-        .unwrap();
-
-    rng.fill_bytes(&mut block_data);
-    let mut block = genesis_block.make_child(Some(block_data.clone().to_vec().into_boxed_slice()));
-
-    blocks.push(genesis_block);
+#[cfg(feature = "tokio")]
+mod async_store {
+    use super::*;
+    use crate::{
+        test_utils::{Block, BlockId},
+        AsyncBlockStore, BlockInfo, Error,
+    };
 
-    for _i in 1..n {
+    async fn put(store: &AsyncBlockStore, block: &Block) -> Result<(), Error> {
         let block_info = BlockInfo::new(
             block.id.serialize_as_vec(),
             block.parent.serialize_as_vec(),
             block.chain_length,
         );
-        store
-            .put_block(&block.serialize_as_vec(), block_info)
-            .unwrap();
-        blocks.push(block.clone());
-        rng.fill_bytes(&mut block_data);
-        block = block.make_child(Some(block_data.clone().to_vec().into_boxed_slice()));
+        store.put_block(block.serialize_as_vec(), block_info).await
     }
 
-    (file, store, blocks)
-}
-
-fn prepare_permament_store() -> (tempfile::TempDir, BlockStore, Vec<Block>) {
-    let (file, store, blocks) = prepare_and_fill_store(BLOCK_NUM_PERMANENT_TEST);
-
-    store
-        .flush_to_permanent_store(&blocks[FLUSH_TO_BLOCK].id.serialize_as_vec(), 1)
-        .unwrap();
-
-    (file, store, blocks)
-}
-
-#[test]
-fn permanent_store_read() {
-    let (_file, store, blocks) = prepare_permament_store();
+    #[tokio::test]
+    async fn blocks_are_stored_and_iterated() {
+        let store =
+            AsyncBlockStore::new(BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap());
 
-    for block in blocks.iter() {
-        let block_id = block.id.serialize_as_vec();
+        let mut blocks = vec![Block::genesis(None)];
+        for _ in 0..9 {
+            let child = blocks.last().unwrap().make_child(None);
+            blocks.push(child);
+        }
+        for block in &blocks {
+            put(&store, block).await.unwrap();
+        }
+        assert!(matches!(
+            put(&store, &blocks[0]).await,
+            Err(Error::BlockAlreadyPresent)
+        ));
 
-        let block_info = store.get_block_info(&block_id).unwrap();
-        assert_eq!(&block.id.serialize_as_vec()[..], block_info.id().as_ref());
+        let tip = blocks.last().unwrap();
         assert_eq!(
-            &block.parent.serialize_as_vec()[..],
-            block_info.parent_id().as_ref()
+            store.get_block(tip.id.serialize_as_vec()).await.unwrap(),
+            tip.serialize_as_value()
         );
-        assert_eq!(block.chain_length, block_info.chain_length());
-
-        let actual_block = store.get_block(&block_id).unwrap();
-        assert_eq!(block.serialize_as_value().as_ref(), actual_block.as_ref());
-    }
-}
-
-#[test]
-fn permanent_store_flush_twice() {
-    let (_file, store, blocks) = prepare_permament_store();
-
-    store
-        .flush_to_permanent_store(&blocks[FLUSH_TO_BLOCK_2].id.serialize_as_vec(), 1)
-        .unwrap();
+        assert!(matches!(
+            store.get_block(BlockId(42).serialize_as_vec()).await,
+            Err(Error::BlockNotFound)
+        ));
 
-    for (i, block) in store
-        .iter(
-            &blocks[BLOCK_NUM_PERMANENT_TEST - 1].id.serialize_as_vec(),
-            BLOCK_NUM_PERMANENT_TEST as u32,
-        )
-        .unwrap()
-        .map(|result| result.unwrap())
-        .enumerate()
-    {
-        assert_eq!(blocks[i].serialize_as_value(), block);
-    }
-}
-
-#[test]
-fn permanent_store_tag() {
-    const TAGS_TEST_LENGTH: usize = 20;
-
-    let (_file, store, blocks) = prepare_permament_store();
-
-    store
-        .put_tag("test1", &blocks[TAGS_TEST_LENGTH].id.serialize_as_vec())
-        .unwrap();
-}
-
-#[test]
-fn permanent_store_prune_main_branch() {
-    let (_file, store, blocks) = prepare_permament_store();
-
-    store
-        .prune_branch(&blocks.last().unwrap().id.serialize_as_vec())
-        .unwrap();
-
-    for i in 0..=FLUSH_TO_BLOCK {
-        assert!(store
-            .block_exists(&blocks[i].id.serialize_as_vec())
-            .unwrap());
-    }
-
-    for i in (FLUSH_TO_BLOCK + 1)..FLUSH_TO_BLOCK {
-        assert!(!store.block_exists(&blocks[i].serialize_as_vec()).unwrap());
-    }
-
-    assert_eq!(
-        vec![blocks[FLUSH_TO_BLOCK].id.serialize_as_value()],
-        store.get_tips_ids().unwrap()
-    );
-}
-
-#[test]
-fn permanent_store_get_by_chain_length() {
-    const CHAIN_LENGTH: usize = 20;
-
-    let (_file, store, blocks) = prepare_permament_store();
-
-    let chain_length = blocks[CHAIN_LENGTH].chain_length;
-    assert_eq!(
-        vec![blocks[CHAIN_LENGTH].serialize_as_value()],
-        store.get_blocks_by_chain_length(chain_length).unwrap()
-    );
-}
-
-#[test]
-fn iterator_only_volatile_storage() {
-    const TEST_BLOCK_NUM: usize = 32;
-
-    let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
-
-    for (i, block) in store
-        .iter(
-            &blocks[blocks.len() - 1].id.serialize_as_vec()[..],
-            TEST_BLOCK_NUM as u32,
-        )
-        .unwrap()
-        .enumerate()
-    {
-        assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
-    }
-}
-
-#[test]
-fn iterator_volatile_and_permanent_storage() {
-    const TEST_BLOCK_NUM: usize = 32;
-    const FLUSH_AT: usize = 16;
-
-    let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
-
-    store
-        .flush_to_permanent_store(&blocks[FLUSH_AT].id.serialize_as_vec()[..], 1)
-        .unwrap();
-
-    for (i, block) in store
-        .iter(
-            &blocks[blocks.len() - 1].id.serialize_as_vec()[..],
-            TEST_BLOCK_NUM as u32,
-        )
-        .unwrap()
-        .enumerate()
-    {
-        assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
-    }
-}
-
-#[test]
-fn iterator_only_permanent_storage() {
-    const TEST_BLOCK_NUM: usize = 32;
-
-    let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
+        store
+            .flush_to_permanent_store(blocks[4].id.serialize_as_vec(), 1)
+            .await
+            .unwrap();
 
-    store
-        .flush_to_permanent_store(&blocks[blocks.len() - 1].id.serialize_as_vec()[..], 1)
-        .unwrap();
+        // the iterator is held across await points
+        let mut iter = store
+            .iterate_range(tip.id.serialize_as_vec(), blocks.len() as u32)
+            .await
+            .unwrap();
+        for block in &blocks {
+            tokio::task::yield_now().await;
+            assert_eq!(
+                iter.next().await.unwrap().unwrap(),
+                block.serialize_as_value()
+            );
+        }
+        assert!(iter.next().await.is_none());
 
-    for (i, block) in store
-        .iter(
-            &blocks[blocks.len() - 1].id.serialize_as_vec()[..],
-            TEST_BLOCK_NUM as u32,
-        )
-        .unwrap()
-        .enumerate()
-    {
-        assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
+        assert!(matches!(
+            store
+                .iterate_range(tip.id.serialize_as_vec(), blocks.len() as u32 + 2)
+                .await,
+            Err(Error::CannotIterate)
+        ));
     }
 }
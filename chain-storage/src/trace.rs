@@ -0,0 +1,40 @@
+//! Helpers for the `tracing` spans of the [`BlockStore`](crate::BlockStore)
+//! methods, compiled to nothing when the `tracing` feature is disabled.
+
+use std::fmt;
+
+/// Displays a block id as hexadecimal in span fields and DOT exports.
+pub(crate) struct HexId<'a>(pub &'a [u8]);
+
+impl<'a> fmt::Display for HexId<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the number of rows (blocks, ids) returned or touched by the
+/// operation in the `rows` field of the current span.
+#[inline]
+pub(crate) fn record_rows(_rows: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("rows", _rows);
+}
+
+/// Records the size in bytes of the block read by the operation in the `size`
+/// field of the current span.
+#[inline]
+pub(crate) fn record_size(_size: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("size", _size);
+}
+
+/// Records the chain length of the block the operation is about in the
+/// `chain_length` field of the current span.
+#[inline]
+pub(crate) fn record_chain_length(_chain_length: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("chain_length", _chain_length);
+}
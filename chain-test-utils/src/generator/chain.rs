@@ -0,0 +1,130 @@
+use super::Generator;
+use chain_addr::{Address, Discrimination, Kind, KindType};
+use chain_crypto::{AsymmetricKey, Blake2b256, Ed25519, PublicKey, SecretKey};
+use rand_chacha::ChaChaRng;
+use rand_core::{RngCore, SeedableRng};
+use std::{fmt, marker::PhantomData};
+
+/// the keys are generated with a cryptographic RNG seeded by the given one,
+/// as the key generation of chain-crypto requires it
+fn crypto_rng(rng: &mut dyn RngCore) -> ChaChaRng {
+    let mut seed = <ChaChaRng as SeedableRng>::Seed::default();
+    rng.fill_bytes(&mut seed);
+    ChaChaRng::from_seed(seed)
+}
+
+/// secret keys of the algorithm `A`
+pub struct SecretKeys<A>(PhantomData<A>);
+
+/// public keys of the algorithm `A`, out of freshly generated secret keys
+pub struct PublicKeys<A>(PhantomData<A>);
+
+macro_rules! key_generator_impls {
+    ($generator:ident) => {
+        impl<A> Default for $generator<A> {
+            fn default() -> Self {
+                $generator(PhantomData)
+            }
+        }
+
+        impl<A> Clone for $generator<A> {
+            fn clone(&self) -> Self {
+                $generator(PhantomData)
+            }
+        }
+
+        impl<A> Copy for $generator<A> {}
+
+        impl<A> fmt::Debug for $generator<A> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(stringify!($generator))
+            }
+        }
+    };
+}
+
+key_generator_impls!(SecretKeys);
+key_generator_impls!(PublicKeys);
+
+impl<A: AsymmetricKey> Generator for SecretKeys<A> {
+    type Item = SecretKey<A>;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> SecretKey<A> {
+        SecretKey::generate(crypto_rng(rng))
+    }
+}
+
+impl<A: AsymmetricKey> Generator for PublicKeys<A> {
+    type Item = PublicKey<A::PubAlg>;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> PublicKey<A::PubAlg> {
+        SecretKeys::<A>::default().generate(rng).to_public()
+    }
+}
+
+/// hashes of random data
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Hashes;
+
+impl Generator for Hashes {
+    type Item = Blake2b256;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> Blake2b256 {
+        let mut data = [0; 32];
+        rng.fill_bytes(&mut data);
+        Blake2b256::new(&data)
+    }
+}
+
+/// addresses of a given discrimination, with fresh ed25519 keys
+///
+/// Without a kind set with [`Addresses::with_kind`], the kind is picked
+/// among the single, group and account kinds. The default generator makes
+/// test addresses.
+#[derive(Clone, Copy, Debug)]
+pub struct Addresses {
+    discrimination: Discrimination,
+    kind: Option<KindType>,
+}
+
+impl Addresses {
+    pub fn new(discrimination: Discrimination) -> Self {
+        Addresses {
+            discrimination,
+            kind: None,
+        }
+    }
+
+    /// only make addresses of the given kind
+    pub fn with_kind(mut self, kind: KindType) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+}
+
+impl Default for Addresses {
+    fn default() -> Self {
+        Addresses::new(Discrimination::Test)
+    }
+}
+
+impl Generator for Addresses {
+    type Item = Address;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> Address {
+        const KINDS: [KindType; 3] = [KindType::Single, KindType::Group, KindType::Account];
+
+        let keys = PublicKeys::<Ed25519>::default();
+        let kind = self
+            .kind
+            .unwrap_or_else(|| KINDS[rng.next_u32() as usize % KINDS.len()]);
+        let kind = match kind {
+            KindType::Single => Kind::Single(keys.generate(rng)),
+            KindType::Group => Kind::Group(keys.generate(rng), keys.generate(rng)),
+            KindType::Account => Kind::Account(keys.generate(rng)),
+            KindType::Multisig => Kind::Multisig(Hashes.generate(rng).into()),
+            KindType::Script => Kind::Script(Hashes.generate(rng).into()),
+        };
+        Address(self.discrimination, kind)
+    }
+}
@@ -0,0 +1,189 @@
+//! Generators of test values, usable from the property testing frameworks.
+//!
+//! A [`Generator`] produces values out of a source of randomness, without
+//! depending on a testing framework. [`Generated`] turns a generator into a
+//! quickcheck [`Arbitrary`], with the `proptest` feature
+//! [`GeneratorStrategy`] turns it into a proptest `Strategy` and with the
+//! `smoke` feature [`SmokeGenerator`] turns it into a `smoke::Generator`.
+//! The generator is given a RNG seeded by the framework, so a failing case
+//! is replayed as the framework replays its cases.
+//!
+//! The generators of the chain values (keys, hashes, addresses) are
+//! provided here, so the property tests of the crates do not each write
+//! their own.
+//!
+//! The values are not shrunk.
+
+mod chain;
+#[cfg(feature = "smoke")]
+mod smoke;
+#[cfg(feature = "proptest")]
+mod strategy;
+
+pub use self::chain::{Addresses, Hashes, PublicKeys, SecretKeys};
+#[cfg(feature = "smoke")]
+pub use self::smoke::SmokeGenerator;
+#[cfg(feature = "proptest")]
+pub use self::strategy::GeneratorStrategy;
+
+use quickcheck::{Arbitrary, Gen};
+use rand_chacha::ChaChaRng;
+use rand_core::{RngCore, SeedableRng};
+use std::fmt;
+
+/// produce values of type `Item` out of a source of randomness
+pub trait Generator {
+    type Item;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> Self::Item;
+}
+
+/// generator calling a function, see [`from_fn`]
+#[derive(Clone, Copy)]
+pub struct FromFn<F>(F);
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FromFn")
+    }
+}
+
+/// make a generator out of a function
+pub fn from_fn<T, F>(f: F) -> FromFn<F>
+where
+    F: Fn(&mut dyn RngCore) -> T,
+{
+    FromFn(f)
+}
+
+impl<T, F> Generator for FromFn<F>
+where
+    F: Fn(&mut dyn RngCore) -> T,
+{
+    type Item = T;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> T {
+        (self.0)(rng)
+    }
+}
+
+/// a value of the generator `G`, as a quickcheck [`Arbitrary`]
+///
+/// quickcheck does not give access to any instance of the generator, so it
+/// is created with its [`Default`] implementation.
+pub struct Generated<G: Generator>(pub G::Item);
+
+impl<G: Generator> Generated<G> {
+    pub fn into_inner(self) -> G::Item {
+        self.0
+    }
+}
+
+impl<G: Generator> Clone for Generated<G>
+where
+    G::Item: Clone,
+{
+    fn clone(&self) -> Self {
+        Generated(self.0.clone())
+    }
+}
+
+impl<G: Generator> fmt::Debug for Generated<G>
+where
+    G::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<G> Arbitrary for Generated<G>
+where
+    G: Generator + Default + 'static,
+    G::Item: Clone + Send + 'static,
+{
+    fn arbitrary<Q: Gen>(g: &mut Q) -> Self {
+        let mut seed = <ChaChaRng as SeedableRng>::Seed::default();
+        for byte in seed.iter_mut() {
+            *byte = u8::arbitrary(g);
+        }
+        Generated(G::default().generate(&mut ChaChaRng::from_seed(seed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_addr::{Discrimination, Kind, KindType};
+    use chain_crypto::{Ed25519, Verification};
+    use quickcheck_macros::quickcheck;
+
+    fn generate_with_seed<G: Generator>(generator: &G, seed: u8) -> G::Item {
+        generator.generate(&mut ChaChaRng::from_seed([seed; 32]))
+    }
+
+    #[test]
+    fn generators_are_deterministic() {
+        let keys = PublicKeys::<Ed25519>::default();
+        assert_eq!(generate_with_seed(&keys, 1), generate_with_seed(&keys, 1));
+        assert_ne!(generate_with_seed(&keys, 1), generate_with_seed(&keys, 2));
+
+        let addresses = Addresses::default();
+        assert_eq!(
+            generate_with_seed(&addresses, 1),
+            generate_with_seed(&addresses, 1)
+        );
+    }
+
+    #[test]
+    fn addresses_follow_the_settings() {
+        let addresses = Addresses::new(Discrimination::Production).with_kind(KindType::Group);
+        for seed in 0..16 {
+            let address = generate_with_seed(&addresses, seed);
+            assert_eq!(address.discrimination(), Discrimination::Production);
+            assert_eq!(address.to_kind_type(), KindType::Group);
+        }
+    }
+
+    #[quickcheck]
+    fn generated_addresses_are_valid(address: Generated<Addresses>) -> bool {
+        let address = address.into_inner();
+        let valid_kind = matches!(
+            address.kind(),
+            Kind::Single(_) | Kind::Group(_, _) | Kind::Account(_)
+        );
+        valid_kind
+            && address.discrimination() == Discrimination::Test
+            && chain_addr::Address::from_bytes(&address.to_bytes())
+                .map_or(false, |decoded| decoded == address)
+    }
+
+    #[quickcheck]
+    fn generated_secret_keys_match_their_public_keys(key: Generated<SecretKeys<Ed25519>>) -> bool {
+        let key = key.into_inner();
+        let message = b"message".as_ref();
+        key.sign(&message).verify(&key.to_public(), &message) == Verification::Success
+    }
+
+    #[test]
+    fn from_fn_calls_the_function() {
+        let generator = from_fn(|rng| rng.next_u64());
+        let mut rng = ChaChaRng::from_seed([0; 32]);
+        let expected = ChaChaRng::from_seed([0; 32]).next_u64();
+        assert_eq!(generator.generate(&mut rng), expected);
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn strategy_generates_values() {
+        use proptest::test_runner::TestRunner;
+
+        let strategy = GeneratorStrategy(Addresses::default().with_kind(KindType::Account));
+        TestRunner::default()
+            .run(&strategy, |address| {
+                proptest::prop_assert_eq!(address.to_kind_type(), KindType::Account);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
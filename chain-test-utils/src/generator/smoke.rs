@@ -0,0 +1,21 @@
+use super::Generator;
+use ::smoke::{generator::num, Generator as _};
+use rand_chacha::ChaChaRng;
+use rand_core::SeedableRng;
+
+/// a [`Generator`] as a [`::smoke::Generator`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmokeGenerator<G>(pub G);
+
+impl<G: Generator> ::smoke::Generator for SmokeGenerator<G> {
+    type Item = G::Item;
+
+    fn gen(&self, r: &mut ::smoke::R) -> G::Item {
+        let bytes = num::<u8>();
+        let mut seed = <ChaChaRng as SeedableRng>::Seed::default();
+        for byte in seed.iter_mut() {
+            *byte = bytes.gen(r);
+        }
+        self.0.generate(&mut ChaChaRng::from_seed(seed))
+    }
+}
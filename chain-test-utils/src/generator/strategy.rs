@@ -0,0 +1,27 @@
+use super::Generator;
+use proptest::{
+    strategy::{Just, NewTree, Strategy},
+    test_runner::TestRunner,
+};
+use rand_chacha::ChaChaRng;
+use rand_core::{RngCore, SeedableRng};
+use std::fmt;
+
+/// a [`Generator`] as a proptest [`Strategy`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GeneratorStrategy<G>(pub G);
+
+impl<G> Strategy for GeneratorStrategy<G>
+where
+    G: Generator + fmt::Debug,
+    G::Item: Clone + fmt::Debug,
+{
+    type Tree = Just<G::Item>;
+    type Value = G::Item;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let mut seed = <ChaChaRng as SeedableRng>::Seed::default();
+        runner.rng().fill_bytes(&mut seed);
+        Ok(Just(self.0.generate(&mut ChaChaRng::from_seed(seed))))
+    }
+}
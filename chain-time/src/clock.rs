@@ -0,0 +1,299 @@
+//! Slot clock for the block producers.
+//!
+//! The system clock can be adjusted at any time (NTP, manual change), which
+//! can make a naive `SystemTime::now()` based scheduler go back in time or
+//! skip slots. [`SlotClock`] follows a monotonic clock anchored on the system
+//! clock instead, and only re-anchors itself when the system clock drifts
+//! from it by more than a given tolerance.
+
+use crate::era::{EpochPosition, TimeEra};
+use crate::timeframe::{Slot, TimeFrame};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of the current time, abstracted so the clock can be tested
+/// without waiting for real slots
+pub trait TimeSource {
+    /// Current time of the system clock, which can be adjusted
+    fn system_time(&self) -> SystemTime;
+
+    /// Time elapsed since an arbitrary fixed point, never going backward
+    fn monotonic(&self) -> Duration;
+
+    /// Block the current thread for the given duration
+    fn sleep(&self, duration: Duration);
+}
+
+/// The operating system clocks
+#[derive(Debug, Clone)]
+pub struct SystemTimeSource {
+    origin: Instant,
+}
+
+impl SystemTimeSource {
+    pub fn new() -> Self {
+        SystemTimeSource {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemTimeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for SystemTimeSource {
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn monotonic(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// A clock telling the current slot of a time frame
+#[derive(Debug, Clone)]
+pub struct SlotClock<T = SystemTimeSource> {
+    time_frame: TimeFrame,
+    era: TimeEra,
+    tolerance: Duration,
+    source: T,
+    /// system time at the given monotonic time
+    anchor: (SystemTime, Duration),
+}
+
+impl SlotClock<SystemTimeSource> {
+    /// Create a slot clock following the system clock. Adjustments of the
+    /// system clock smaller than `tolerance` are ignored.
+    pub fn new(time_frame: TimeFrame, era: TimeEra, tolerance: Duration) -> Self {
+        Self::with_source(time_frame, era, tolerance, SystemTimeSource::new())
+    }
+}
+
+impl<T: TimeSource> SlotClock<T> {
+    pub fn with_source(
+        time_frame: TimeFrame,
+        era: TimeEra,
+        tolerance: Duration,
+        source: T,
+    ) -> Self {
+        let anchor = (source.system_time(), source.monotonic());
+        SlotClock {
+            time_frame,
+            era,
+            tolerance,
+            source,
+            anchor,
+        }
+    }
+
+    pub fn time_frame(&self) -> &TimeFrame {
+        &self.time_frame
+    }
+
+    pub fn era(&self) -> &TimeEra {
+        &self.era
+    }
+
+    /// Current time, following the monotonic clock as long as the system
+    /// clock does not drift from it by more than the tolerance
+    pub fn now(&mut self) -> SystemTime {
+        let (anchor_time, anchor_monotonic) = self.anchor;
+        let monotonic = self.source.monotonic();
+        let estimate = anchor_time + (monotonic - anchor_monotonic);
+        let system = self.source.system_time();
+        let drift = match system.duration_since(estimate) {
+            Ok(ahead) => ahead,
+            Err(behind) => behind.duration(),
+        };
+        if drift > self.tolerance {
+            self.anchor = (system, monotonic);
+            system
+        } else {
+            estimate
+        }
+    }
+
+    /// The current slot, or None if the time frame has not started yet
+    pub fn current_slot(&mut self) -> Option<Slot> {
+        let now = self.now();
+        self.time_frame.slot_at(&now)
+    }
+
+    /// The epoch and slot in the epoch of the current slot, or None if the
+    /// time frame or the era have not started yet
+    pub fn current_position(&mut self) -> Option<EpochPosition> {
+        self.current_slot()
+            .and_then(|slot| self.era.from_slot_to_era(slot))
+    }
+
+    /// The next slot to start and the time until it does
+    pub fn next_slot(&mut self) -> (Slot, Duration) {
+        let now = self.now();
+        let slot = match self.time_frame.slot_at(&now) {
+            Some(current) => Slot(current.0 + 1),
+            None => self.time_frame.slot0(),
+        };
+        let start = self
+            .time_frame
+            .slot_to_systemtime(slot)
+            .expect("the slot belongs to the time frame");
+        let wait = start.duration_since(now).unwrap_or_default();
+        (slot, wait)
+    }
+
+    /// Time until the next slot starts
+    pub fn time_until_next_slot(&mut self) -> Duration {
+        self.next_slot().1
+    }
+
+    /// Iterate over the slot boundaries, blocking until each slot starts.
+    ///
+    /// Every yielded slot is greater than the previous one: the slots missed
+    /// while the caller was busy, or skipped by an adjustment of the system
+    /// clock, are not yielded.
+    pub fn slots(&mut self) -> SlotBoundaries<'_, T> {
+        SlotBoundaries {
+            clock: self,
+            last: None,
+        }
+    }
+}
+
+/// Endless iterator over the slot boundaries of a [`SlotClock`]
+pub struct SlotBoundaries<'a, T> {
+    clock: &'a mut SlotClock<T>,
+    last: Option<Slot>,
+}
+
+impl<'a, T: TimeSource> Iterator for SlotBoundaries<'a, T> {
+    type Item = Slot;
+
+    fn next(&mut self) -> Option<Slot> {
+        loop {
+            let (_, wait) = self.clock.next_slot();
+            self.clock.source.sleep(wait);
+            match self.clock.current_slot() {
+                Some(slot) if self.last < Some(slot) => {
+                    self.last = Some(slot);
+                    return Some(slot);
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::era::{Epoch, EpochSlotOffset};
+    use crate::timeframe::SlotDuration;
+    use crate::timeline::Timeline;
+    use std::cell::Cell;
+
+    struct TestTimeSource {
+        system: Cell<SystemTime>,
+        monotonic: Cell<Duration>,
+    }
+
+    impl TestTimeSource {
+        fn adjust_system_time(&self, time: SystemTime) {
+            self.system.set(time);
+        }
+    }
+
+    impl TimeSource for &TestTimeSource {
+        fn system_time(&self) -> SystemTime {
+            self.system.get()
+        }
+
+        fn monotonic(&self) -> Duration {
+            self.monotonic.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.system.set(self.system.get() + duration);
+            self.monotonic.set(self.monotonic.get() + duration);
+        }
+    }
+
+    fn clock(source: &TestTimeSource, start: SystemTime) -> SlotClock<&TestTimeSource> {
+        let time_frame = TimeFrame::new(Timeline::new(start), SlotDuration::from_secs(10));
+        let era = TimeEra::new(Slot(0), Epoch(0), 6);
+        SlotClock::with_source(time_frame, era, Duration::from_secs(2), source)
+    }
+
+    fn source(now: SystemTime) -> TestTimeSource {
+        TestTimeSource {
+            system: Cell::new(now),
+            monotonic: Cell::new(Duration::from_secs(1_000)),
+        }
+    }
+
+    #[test]
+    pub fn current_and_next_slot() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let source = source(start + Duration::from_secs(64));
+        let mut clock = clock(&source, start);
+
+        assert_eq!(clock.current_slot(), Some(Slot(6)));
+        assert_eq!(
+            clock.current_position(),
+            Some(EpochPosition {
+                epoch: Epoch(1),
+                slot: EpochSlotOffset(0)
+            })
+        );
+        assert_eq!(clock.next_slot(), (Slot(7), Duration::from_secs(6)));
+    }
+
+    #[test]
+    pub fn waits_for_the_time_frame_to_start() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let source = source(start - Duration::from_secs(15));
+        let mut clock = clock(&source, start);
+
+        assert_eq!(clock.current_slot(), None);
+        assert_eq!(clock.time_until_next_slot(), Duration::from_secs(15));
+        assert_eq!(clock.slots().next(), Some(Slot(0)));
+    }
+
+    #[test]
+    pub fn small_adjustments_are_ignored() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let source = source(start + Duration::from_secs(19));
+        let mut clock = clock(&source, start);
+        assert_eq!(clock.current_slot(), Some(Slot(1)));
+
+        // the system clock jumps to the next slot, within the tolerance
+        source.adjust_system_time(start + Duration::from_secs(21));
+        assert_eq!(clock.current_slot(), Some(Slot(1)));
+
+        // a larger adjustment is followed
+        source.adjust_system_time(start + Duration::from_secs(45));
+        assert_eq!(clock.current_slot(), Some(Slot(4)));
+    }
+
+    #[test]
+    pub fn slot_boundaries_never_go_backward() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let source = source(start + Duration::from_secs(5));
+        let mut clock = clock(&source, start);
+
+        let mut slots = clock.slots();
+        assert_eq!(slots.next(), Some(Slot(1)));
+        assert_eq!(slots.next(), Some(Slot(2)));
+
+        // the system clock goes back by more than a slot
+        source.adjust_system_time(start + Duration::from_secs(5));
+        assert_eq!(slots.next(), Some(Slot(3)));
+        assert_eq!(slots.next(), Some(Slot(4)));
+    }
+}
@@ -68,11 +68,31 @@ impl TimeEra {
         }
     }
 
+    /// The era following this one from `epoch`, whose epochs have the given
+    /// number of slots. The epochs of this era before `epoch` keep their
+    /// slots.
+    pub fn with_epoch_length(&self, epoch: Epoch, slots_per_epoch: u32) -> Self {
+        let slot_start = self.from_era_to_slot(EpochPosition {
+            epoch,
+            slot: EpochSlotOffset(0),
+        });
+        TimeEra {
+            epoch_start: epoch,
+            slot_start,
+            slots_per_epoch,
+        }
+    }
+
     /// retrieve the number of slots in an epoch during a given Epoch
     pub fn slots_per_epoch(&self) -> u32 {
         self.slots_per_epoch
     }
 
+    /// The first epoch of the era
+    pub fn epoch_start(&self) -> Epoch {
+        self.epoch_start
+    }
+
     /// Try to return the epoch/inner-epoch-slot associated.
     ///
     /// If the slot in parameter is before the beginning of this era, then
@@ -96,7 +116,9 @@ impl TimeEra {
         assert!(pos.epoch >= self.epoch_start);
         assert!(pos.slot.0 < self.slots_per_epoch);
 
-        let slot_offset = (pos.epoch.0 as u64) * (self.slots_per_epoch as u64) + pos.slot.0 as u64;
+        let slot_offset = ((pos.epoch.0 - self.epoch_start.0) as u64)
+            * (self.slots_per_epoch as u64)
+            + pos.slot.0 as u64;
         Slot(self.slot_start.0 + slot_offset)
     }
 }
@@ -181,5 +203,27 @@ mod test {
                 slot: EpochSlotOffset(2)
             }
         );
+
+        assert_eq!(era.from_era_to_slot(p1), slot1);
+        assert_eq!(era.from_era_to_slot(p3), slot3);
+
+        // epochs of 2 slots from epoch 4, which starts at slot 2 + 2 * 4
+        let next_era = era.with_epoch_length(Epoch(4), 2);
+        assert_eq!(next_era.epoch_start(), Epoch(4));
+        assert_eq!(
+            next_era.from_slot_to_era(Slot(10)).unwrap(),
+            EpochPosition {
+                epoch: Epoch(4),
+                slot: EpochSlotOffset(0)
+            }
+        );
+        assert_eq!(
+            next_era.from_slot_to_era(slot3).unwrap(),
+            EpochPosition {
+                epoch: Epoch(9),
+                slot: EpochSlotOffset(0)
+            }
+        );
+        assert_eq!(next_era.from_slot_to_era(Slot(9)), None);
     }
 }
@@ -1,11 +1,13 @@
 #[macro_use]
 extern crate cfg_if;
 
+pub mod clock;
 pub mod era;
 pub mod timeframe;
 pub mod timeline;
 pub mod units;
 
+pub use clock::{SlotClock, SystemTimeSource, TimeSource};
 pub use era::{Epoch, TimeEra};
 pub use timeframe::{Slot, SlotDuration, TimeFrame};
 pub use timeline::{TimeOffsetSeconds, Timeline};
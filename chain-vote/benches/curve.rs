@@ -3,7 +3,7 @@
 
 use chain_vote::debug::gang;
 use chain_vote::debug::gang::GroupElement;
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use rand_chacha::ChaCha20Rng;
 use rand_core::SeedableRng;
 
@@ -15,6 +15,29 @@ fn mul(c: &mut Criterion) {
     c.bench_function("Point - Scalar multiplication", |b| b.iter(|| &g * &scalar));
 }
 
+fn fixed_base_mul(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let g = gang::GroupElement::from_hash(b"random element");
+    let scalar = gang::Scalar::random(&mut rng);
+    let mut group = c.benchmark_group("Fixed base multiplication");
+
+    for &window in [2usize, 4, 6, 8].iter() {
+        group.bench_with_input(
+            BenchmarkId::new("Table generation", window),
+            &window,
+            |b, &window| b.iter(|| gang::FixedBaseTable::generate_with_window(&g, window)),
+        );
+        let table = gang::FixedBaseTable::generate_with_window(&g, window);
+        group.bench_with_input(
+            BenchmarkId::new("Multiplication", window),
+            &window,
+            |b, _| b.iter(|| table.mul(&scalar)),
+        );
+    }
+
+    group.finish();
+}
+
 fn addition(c: &mut Criterion) {
     let point1 = gang::GroupElement::from_hash(b"random element");
     let point2 = gang::GroupElement::from_hash(b"random element");
@@ -74,6 +97,7 @@ criterion_group!(
     config = Criterion::default();
     targets =
     mul,
+    fixed_base_mul,
     from_hash,
     to_bytes,
     decompress,
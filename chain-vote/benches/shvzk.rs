@@ -38,6 +38,18 @@ fn encrypt_and_prove(c: &mut Criterion) {
         );
     }
 
+    let precomputed = ek.precompute();
+    for &number_candidates in [2usize, 4, 8].iter() {
+        let parameter_string = format!("{} candidates", number_candidates);
+        group.bench_with_input(
+            BenchmarkId::new("Encrypt and Prove with precomputed key", parameter_string),
+            &number_candidates,
+            |b, &nr| {
+                b.iter(|| encrypt_vote_precomputed(&mut rng, &crs, &precomputed, Vote::new(nr, 0)))
+            },
+        );
+    }
+
     group.finish();
 }
 
@@ -0,0 +1,96 @@
+//! Scaling of the ballot verification and of the tally aggregation with the
+//! number of threads of the rayon pool. Run on a machine with at least 8
+//! cores to see the whole range.
+
+use chain_vote::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const THREADS: [usize; 5] = [1, 2, 4, 8, 16];
+const BALLOTS: usize = 256;
+const OPTIONS: usize = 4;
+
+fn election(rng: &mut ChaCha20Rng) -> (CRS, EncryptingVoteKey) {
+    let crs = CRS::from_hash(&[0u8; 32]);
+    let mc = [MemberCommunicationKey::new(rng).to_public()];
+    let member = MemberState::new(rng, 1, &crs, &mc, 0);
+    let ek = EncryptingVoteKey::from_participants(&[member.public_key()]);
+    (crs, ek)
+}
+
+fn ballots(
+    rng: &mut ChaCha20Rng,
+    crs: &CRS,
+    ek: &EncryptingVoteKey,
+) -> Vec<(EncryptedVote, ProofOfCorrectVote)> {
+    (0..BALLOTS)
+        .map(|i| encrypt_vote(rng, crs, ek, Vote::new(OPTIONS, i % OPTIONS)))
+        .collect()
+}
+
+fn pool(threads: usize) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap()
+}
+
+fn verify(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let (crs, ek) = election(&mut rng);
+    let votes = ballots(&mut rng, &crs, &ek);
+    let mut group = c.benchmark_group("Verify ballots");
+    group.sample_size(10);
+
+    for &threads in THREADS.iter() {
+        let pool = pool(threads);
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} ballots", BALLOTS), threads),
+            &threads,
+            |b, _| b.iter(|| pool.install(|| verify_votes(&crs, &ek, &votes))),
+        );
+    }
+
+    group.finish();
+}
+
+fn aggregate(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let (crs, ek) = election(&mut rng);
+    let votes: Vec<_> = ballots(&mut rng, &crs, &ek)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (vote, _proof))| (vote, i as u64 + 1))
+        .collect();
+    let mut group = c.benchmark_group("Aggregate tally");
+
+    for &threads in THREADS.iter() {
+        let pool = pool(threads);
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} ballots", BALLOTS), threads),
+            &threads,
+            |b, _| {
+                b.iter(|| {
+                    pool.install(|| {
+                        let mut tally = EncryptedTally::new(OPTIONS);
+                        tally.add_batch(&votes);
+                        tally
+                    })
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    name = tally;
+    config = Criterion::default();
+    targets =
+    verify,
+    aggregate,
+);
+
+criterion_main!(tally);
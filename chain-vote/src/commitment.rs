@@ -1,4 +1,4 @@
-use crate::gang::{GroupElement, Scalar};
+use crate::gang::{GroupElement, HashToCurveVersion, Scalar};
 use std::ops::{Add, Mul};
 
 /// Pedersen commitment
@@ -14,8 +14,14 @@ pub struct CommitmentKey {
 
 impl CommitmentKey {
     pub fn generate_from_seed(buffer: &mut [u8]) -> Self {
+        Self::generate_from_seed_versioned(buffer, HashToCurveVersion::Legacy)
+    }
+
+    /// Derive the commitment key from the seed with the given hash to curve
+    /// algorithm
+    pub fn generate_from_seed_versioned(buffer: &[u8], version: HashToCurveVersion) -> Self {
         CommitmentKey {
-            h: GroupElement::from_hash(buffer),
+            h: GroupElement::from_hash_versioned(buffer, version),
         }
     }
 }
@@ -1,5 +1,6 @@
-use crate::gang::{GroupElement, Scalar};
-use crate::gargamel::{PublicKey, SecretKey};
+use crate::coordination::EncryptedShares;
+use crate::gang::{GroupElement, HashToCurveVersion, Scalar};
+use crate::gargamel::{PrecomputedPublicKey, PublicKey, SecretKey};
 use crate::hybrid;
 use crate::hybrid::SymmetricKey;
 use crate::math::Polynomial;
@@ -14,16 +15,21 @@ pub struct MemberSecretKey(pub(crate) SecretKey);
 pub struct MemberPublicKey(pub(crate) PublicKey);
 
 #[derive(Clone)]
-pub struct MemberCommunicationKey(SecretKey);
+pub struct MemberCommunicationKey(pub(crate) SecretKey);
 
 /// Committee Member communication public key (with other committee members)
-#[derive(Clone)]
-pub struct MemberCommunicationPublicKey(PublicKey);
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MemberCommunicationPublicKey(pub(crate) PublicKey);
 
 /// The overall committee public key used for everyone to encrypt their vote to.
 #[derive(Clone)]
 pub struct ElectionPublicKey(pub(crate) PublicKey);
 
+/// Election public key with precomputed tables speeding up the encryption of
+/// votes, worth building when encrypting many votes or on slow devices.
+#[derive(Clone)]
+pub struct PrecomputedElectionPublicKey(pub(crate) PrecomputedPublicKey);
+
 impl ElectionPublicKey {
     #[doc(hidden)]
     pub fn as_raw(&self) -> &PublicKey {
@@ -43,6 +49,12 @@ pub struct MemberState {
 
 pub type CRS = GroupElement;
 
+/// Derive the common reference string of an election from a seed shared by
+/// the committee and the voters, with the given hash to curve algorithm
+pub fn crs_from_seed(seed: &[u8], version: HashToCurveVersion) -> CRS {
+    GroupElement::from_hash_versioned(seed, version)
+}
+
 impl MemberState {
     /// Generate a new member state from random, where the number
     pub fn new<R: RngCore + CryptoRng>(
@@ -124,6 +136,15 @@ impl MemberState {
             pk: self.apubs[0].clone(),
         })
     }
+
+    /// The commitments of the member and the shares it encrypted for the
+    /// other members of the committee, to be sent to them.
+    pub fn encrypted_shares(&self) -> EncryptedShares {
+        EncryptedShares {
+            commitments: self.es.clone(),
+            shares: self.encrypted.clone(),
+        }
+    }
 }
 
 impl MemberSecretKey {
@@ -184,6 +205,10 @@ impl MemberCommunicationPublicKey {
     pub fn to_bytes(&self) -> Vec<u8> {
         self.0.to_bytes()
     }
+
+    pub fn from_bytes(buf: &[u8]) -> Option<Self> {
+        PublicKey::from_bytes(buf).map(Self)
+    }
 }
 
 impl ElectionPublicKey {
@@ -203,4 +228,17 @@ impl ElectionPublicKey {
     pub fn from_bytes(buf: &[u8]) -> Option<Self> {
         PublicKey::from_bytes(buf).map(ElectionPublicKey)
     }
+
+    /// Precompute the encryption tables of this key with the default window
+    pub fn precompute(&self) -> PrecomputedElectionPublicKey {
+        PrecomputedElectionPublicKey(PrecomputedPublicKey::new(self.0.clone()))
+    }
+
+    /// Precompute the encryption tables of this key with a window of the given
+    /// number of bits, between 1 and 8. Each additional bit roughly doubles
+    /// the size of the tables and the time to build them, and makes the
+    /// encryption faster.
+    pub fn precompute_with_window(&self, window: usize) -> PrecomputedElectionPublicKey {
+        PrecomputedElectionPublicKey(PrecomputedPublicKey::with_window(self.0.clone(), window))
+    }
 }
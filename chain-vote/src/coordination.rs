@@ -0,0 +1,512 @@
+//! Authentication of the messages exchanged by the committee members.
+//!
+//! The members of a committee coordinate over some transport to set up the
+//! election key and to decrypt the tally: each member announces its election
+//! public key, sends the shares it encrypted for the others, and eventually
+//! publishes its share of the decryption of the tally. A [`Signed`] message
+//! wraps any of them with the identifier of the election, a round counter
+//! and the index of the sender in the committee, all signed with the
+//! [`MemberCommunicationKey`] of the sender.
+//!
+//! A [`MessageVerifier`] holds the communication public keys of the
+//! committee and accepts a message only once its signature checks out and
+//! its round is greater than the last round accepted for the same sender and
+//! kind of message, so that a message cannot be replayed in another
+//! election or later in the same one.
+
+use crate::committee::{MemberCommunicationKey, MemberCommunicationPublicKey};
+use crate::encoding::Reader;
+use crate::gang::{GroupElement, Scalar};
+use crate::hybrid::HybridCiphertext;
+use crate::{MemberPublicKey, TallyDecryptShare};
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::digest::Digest;
+use rand_core::{CryptoRng, RngCore};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+const DOMAIN: &[u8] = b"chain-vote committee coordination";
+
+/// Identifier of the election the committee coordinates for, typically the
+/// hash of the vote plan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ElectionId([u8; Self::BYTES_LEN]);
+
+impl ElectionId {
+    pub const BYTES_LEN: usize = 32;
+
+    pub fn new(bytes: [u8; Self::BYTES_LEN]) -> Self {
+        ElectionId(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; Self::BYTES_LEN] {
+        &self.0
+    }
+}
+
+/// The kinds of messages exchanged by the committee members
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    MemberAnnouncement = 1,
+    EncryptedShares = 2,
+    DecryptShare = 3,
+}
+
+impl MessageKind {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            1 => Some(MessageKind::MemberAnnouncement),
+            2 => Some(MessageKind::EncryptedShares),
+            3 => Some(MessageKind::DecryptShare),
+            _ => None,
+        }
+    }
+}
+
+/// Content of a coordination message
+pub trait Payload: Sized {
+    const KIND: MessageKind;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// The announcement of the election public key of a member
+impl Payload for MemberPublicKey {
+    const KIND: MessageKind = MessageKind::MemberAnnouncement;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        MemberPublicKey::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != MemberPublicKey::BYTES_LEN {
+            return None;
+        }
+        MemberPublicKey::from_bytes(bytes)
+    }
+}
+
+impl Payload for TallyDecryptShare {
+    const KIND: MessageKind = MessageKind::DecryptShare;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        TallyDecryptShare::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        TallyDecryptShare::from_bytes(bytes)
+    }
+}
+
+/// The commitments of a member and the shares of its secret it encrypted
+/// for each of the other members of the committee, in the order of the
+/// committee.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedShares {
+    pub(crate) commitments: Vec<GroupElement>,
+    pub(crate) shares: Vec<(HybridCiphertext, HybridCiphertext)>,
+}
+
+impl EncryptedShares {
+    const SHARE_BYTES_LEN: usize = Scalar::BYTES_LEN;
+
+    /// Number of members the shares are encrypted for
+    pub fn recipients(&self) -> usize {
+        self.shares.len()
+    }
+}
+
+impl Payload for EncryptedShares {
+    const KIND: MessageKind = MessageKind::EncryptedShares;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut r = Vec::new();
+        r.extend_from_slice(&(self.commitments.len() as u32).to_be_bytes());
+        for commitment in &self.commitments {
+            r.extend_from_slice(commitment.to_bytes().as_ref());
+        }
+        r.extend_from_slice(&(self.shares.len() as u32).to_be_bytes());
+        for (ecomm, eshek) in &self.shares {
+            r.extend_from_slice(&ecomm.to_bytes());
+            r.extend_from_slice(&eshek.to_bytes());
+        }
+        r
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let share_len = HybridCiphertext::bytes_len(Self::SHARE_BYTES_LEN);
+        let mut reader = Reader::new(bytes);
+        let commitments = (0..reader.len()?)
+            .map(|_| GroupElement::from_bytes(reader.take(GroupElement::BYTES_LEN)?))
+            .collect::<Option<Vec<_>>>()?;
+        let shares = (0..reader.len()?)
+            .map(|_| {
+                let ecomm = HybridCiphertext::from_bytes(reader.take(share_len)?)?;
+                let eshek = HybridCiphertext::from_bytes(reader.take(share_len)?)?;
+                Some((ecomm, eshek))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if !reader.is_empty() {
+            return None;
+        }
+        Some(EncryptedShares {
+            commitments,
+            shares,
+        })
+    }
+}
+
+/// Schnorr signature with a member communication key
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Signature {
+    r: GroupElement,
+    s: Scalar,
+}
+
+impl Signature {
+    const BYTES_LEN: usize = GroupElement::BYTES_LEN + Scalar::BYTES_LEN;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut r = Vec::with_capacity(Self::BYTES_LEN);
+        r.extend_from_slice(self.r.to_bytes().as_ref());
+        r.extend_from_slice(self.s.to_bytes().as_ref());
+        r
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES_LEN {
+            return None;
+        }
+        let (r, s) = bytes.split_at(GroupElement::BYTES_LEN);
+        Some(Signature {
+            r: GroupElement::from_bytes(r)?,
+            s: Scalar::from_bytes(s)?,
+        })
+    }
+}
+
+/// What a coordination message is about, covered by its signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageHeader {
+    pub election: ElectionId,
+    /// counter of the rounds of the coordination, strictly increasing for
+    /// the messages of a kind sent by a member
+    pub round: u64,
+    /// index of the sender in the committee, starting from 0
+    pub sender: u32,
+}
+
+impl MessageHeader {
+    const BYTES_LEN: usize = 1 + ElectionId::BYTES_LEN + 8 + 4;
+
+    fn to_bytes(&self, kind: MessageKind) -> Vec<u8> {
+        let mut r = Vec::with_capacity(Self::BYTES_LEN);
+        r.push(kind as u8);
+        r.extend_from_slice(self.election.as_bytes());
+        r.extend_from_slice(&self.round.to_be_bytes());
+        r.extend_from_slice(&self.sender.to_be_bytes());
+        r
+    }
+}
+
+fn challenge(
+    public_key: &GroupElement,
+    commitment: &GroupElement,
+    header: &[u8],
+    payload: &[u8],
+) -> Scalar {
+    let mut ctx = Blake2b::new(32);
+    ctx.input(DOMAIN);
+    ctx.input(public_key.to_bytes().as_ref());
+    ctx.input(commitment.to_bytes().as_ref());
+    ctx.input(header);
+    ctx.input(&(payload.len() as u64).to_be_bytes());
+    ctx.input(payload);
+    // the hashes above the order of the group are rejected, they are
+    // rehashed with a counter until one is a valid scalar
+    let mut counter = 0u8;
+    loop {
+        let mut h = [0u8; 32];
+        let mut x = ctx.clone();
+        x.input(&[counter]);
+        x.result(&mut h);
+        if let Some(c) = Scalar::from_bytes(&h) {
+            return c;
+        }
+        counter = counter.wrapping_add(1);
+    }
+}
+
+/// A coordination message signed by its sender
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signed<P> {
+    header: MessageHeader,
+    payload: P,
+    signature: Signature,
+}
+
+impl<P: Payload> Signed<P> {
+    /// Sign the payload as the member at index `sender` of the committee
+    pub fn sign<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        key: &MemberCommunicationKey,
+        header: MessageHeader,
+        payload: P,
+    ) -> Self {
+        let public_key = &GroupElement::generator() * &key.0.sk;
+        let k = Scalar::random(rng);
+        let r = &GroupElement::generator() * &k;
+        let c = challenge(
+            &public_key,
+            &r,
+            &header.to_bytes(P::KIND),
+            &payload.to_bytes(),
+        );
+        let s = &k + &(&c * &key.0.sk);
+        Signed {
+            header,
+            payload,
+            signature: Signature { r, s },
+        }
+    }
+
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    /// The payload, whose authenticity is not checked
+    pub fn payload_unverified(&self) -> &P {
+        &self.payload
+    }
+
+    /// Check the signature of the message against the communication public
+    /// key of its sender, without any check of its election or round
+    pub fn verify_signature(&self, public_key: &MemberCommunicationPublicKey) -> bool {
+        let c = challenge(
+            &public_key.0.pk,
+            &self.signature.r,
+            &self.header.to_bytes(P::KIND),
+            &self.payload.to_bytes(),
+        );
+        &GroupElement::generator() * &self.signature.s
+            == &self.signature.r + &(&public_key.0.pk * &c)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut r = self.header.to_bytes(P::KIND);
+        r.extend_from_slice(&self.signature.to_bytes());
+        r.extend_from_slice(&self.payload.to_bytes());
+        r
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < MessageHeader::BYTES_LEN + Signature::BYTES_LEN {
+            return None;
+        }
+        let (header, rest) = bytes.split_at(MessageHeader::BYTES_LEN);
+        let (signature, payload) = rest.split_at(Signature::BYTES_LEN);
+        if MessageKind::from_u8(header[0])? != P::KIND {
+            return None;
+        }
+        let mut reader = Reader::new(&header[1..]);
+        let election = ElectionId(reader.take(ElectionId::BYTES_LEN)?.try_into().unwrap());
+        let round = reader.u64()?;
+        let sender = reader.len()? as u32;
+        Some(Signed {
+            header: MessageHeader {
+                election,
+                round,
+                sender,
+            },
+            payload: P::from_bytes(payload)?,
+            signature: Signature::from_bytes(signature)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CoordinationError {
+    #[error("the message is for another election")]
+    WrongElection,
+    #[error("no committee member at index {sender}")]
+    UnknownSender { sender: u32 },
+    #[error("invalid signature of the message of member {sender}")]
+    InvalidSignature { sender: u32 },
+    #[error("replayed message of member {sender}: round {round} is not after round {last}")]
+    Replayed { sender: u32, round: u64, last: u64 },
+}
+
+/// Verification of the messages received from the committee of an
+/// election, remembering the last round accepted from every member.
+#[derive(Clone, Debug)]
+pub struct MessageVerifier {
+    election: ElectionId,
+    committee: Vec<MemberCommunicationPublicKey>,
+    last_rounds: HashMap<(u32, MessageKind), u64>,
+}
+
+impl MessageVerifier {
+    pub fn new(election: ElectionId, committee: Vec<MemberCommunicationPublicKey>) -> Self {
+        MessageVerifier {
+            election,
+            committee,
+            last_rounds: HashMap::new(),
+        }
+    }
+
+    /// The last round accepted from the member for the kind of message
+    pub fn last_round(&self, sender: u32, kind: MessageKind) -> Option<u64> {
+        self.last_rounds.get(&(sender, kind)).copied()
+    }
+
+    /// Check the message and record its round, returning its authenticated
+    /// payload. A rejected message leaves the verifier unchanged.
+    pub fn accept<'a, P: Payload>(
+        &mut self,
+        message: &'a Signed<P>,
+    ) -> Result<&'a P, CoordinationError> {
+        let header = &message.header;
+        if header.election != self.election {
+            return Err(CoordinationError::WrongElection);
+        }
+        let public_key =
+            self.committee
+                .get(header.sender as usize)
+                .ok_or(CoordinationError::UnknownSender {
+                    sender: header.sender,
+                })?;
+        if !message.verify_signature(public_key) {
+            return Err(CoordinationError::InvalidSignature {
+                sender: header.sender,
+            });
+        }
+        let key = (header.sender, P::KIND);
+        if let Some(&last) = self.last_rounds.get(&key) {
+            if header.round <= last {
+                return Err(CoordinationError::Replayed {
+                    sender: header.sender,
+                    round: header.round,
+                    last,
+                });
+            }
+        }
+        self.last_rounds.insert(key, header.round);
+        Ok(&message.payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncryptedTally, MemberState, CRS};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn header(election: ElectionId, round: u64, sender: u32) -> MessageHeader {
+        MessageHeader {
+            election,
+            round,
+            sender,
+        }
+    }
+
+    #[test]
+    fn coordination_messages_are_authenticated() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = CRS::from_hash(b"coordination");
+        let election = ElectionId::new([1; 32]);
+
+        let keys: Vec<_> = (0..3)
+            .map(|_| MemberCommunicationKey::new(&mut rng))
+            .collect();
+        let committee: Vec<_> = keys.iter().map(|k| k.to_public()).collect();
+        let members: Vec<_> = (0..3)
+            .map(|i| MemberState::new(&mut rng, 2, &h, &committee, i))
+            .collect();
+        let mut verifier = MessageVerifier::new(election, committee.clone());
+
+        let announcement = Signed::sign(
+            &mut rng,
+            &keys[0],
+            header(election, 0, 0),
+            members[0].public_key(),
+        );
+        let decoded = Signed::<MemberPublicKey>::from_bytes(&announcement.to_bytes()).unwrap();
+        assert_eq!(decoded, announcement);
+        assert_eq!(verifier.accept(&decoded), Ok(&members[0].public_key()));
+        // the same message cannot be accepted twice
+        assert_eq!(
+            verifier.accept(&decoded),
+            Err(CoordinationError::Replayed {
+                sender: 0,
+                round: 0,
+                last: 0
+            })
+        );
+        // nor decoded as another kind of message
+        assert!(Signed::<TallyDecryptShare>::from_bytes(&announcement.to_bytes()).is_none());
+
+        let shares = Signed::sign(
+            &mut rng,
+            &keys[1],
+            header(election, 0, 1),
+            members[1].encrypted_shares(),
+        );
+        let decoded = Signed::<EncryptedShares>::from_bytes(&shares.to_bytes()).unwrap();
+        assert_eq!(decoded.payload_unverified().recipients(), 2);
+        assert!(verifier.accept(&decoded).is_ok());
+
+        // signed by a member but claiming to be another one
+        let forged = Signed::sign(
+            &mut rng,
+            &keys[2],
+            header(election, 1, 1),
+            members[1].encrypted_shares(),
+        );
+        assert_eq!(
+            verifier.accept(&forged),
+            Err(CoordinationError::InvalidSignature { sender: 1 })
+        );
+        assert_eq!(
+            verifier.last_round(1, MessageKind::EncryptedShares),
+            Some(0)
+        );
+
+        let (_, decrypt_share) = EncryptedTally::new(2).finish(members[2].secret_key());
+        let other_election = Signed::sign(
+            &mut rng,
+            &keys[2],
+            header(ElectionId::new([2; 32]), 1, 2),
+            decrypt_share.clone(),
+        );
+        assert_eq!(
+            verifier.accept(&other_election),
+            Err(CoordinationError::WrongElection)
+        );
+        let unknown = Signed::sign(
+            &mut rng,
+            &keys[2],
+            header(election, 1, 3),
+            decrypt_share.clone(),
+        );
+        assert_eq!(
+            verifier.accept(&unknown),
+            Err(CoordinationError::UnknownSender { sender: 3 })
+        );
+        let mut tampered = Signed::sign(
+            &mut rng,
+            &keys[2],
+            header(election, 1, 2),
+            decrypt_share.clone(),
+        );
+        tampered.header.round = 2;
+        assert_eq!(
+            verifier.accept(&tampered),
+            Err(CoordinationError::InvalidSignature { sender: 2 })
+        );
+        tampered.header.round = 1;
+        assert_eq!(verifier.accept(&tampered), Ok(&decrypt_share));
+    }
+}
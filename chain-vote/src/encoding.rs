@@ -0,0 +1,658 @@
+//! Canonical encodings of the election artifacts.
+//!
+//! The `to_bytes`/`from_bytes` of the artifacts are raw encodings whose
+//! length depends on the number of options agreed out of band. The canonical
+//! encoding of an artifact is self-describing: a one byte [`ArtifactKind`]
+//! tag, a one byte version, then the body of that version of the format.
+//! Decoding is strict, a different tag, an unknown version, a body which
+//! does not decode or any byte left after it are all rejected, so that a
+//! format can evolve by bumping its version without any ambiguity on the
+//! artifacts already published.
+//!
+//! Integers are big endian. The lengths of version 1 with the default
+//! p256k1 backend, whose group elements are 65 bytes long, are, with `n` the
+//! number of options and `m` the base 2 logarithm of `n` rounded up to a
+//! power of two (the length of a proof of correct vote):
+//!
+//! | artifact                       | tag | body                                  | total length                     |
+//! |--------------------------------|-----|---------------------------------------|----------------------------------|
+//! | `MemberPublicKey`              | 1   | group element                         | 2 + 65                           |
+//! | `MemberCommunicationPublicKey` | 2   | group element                         | 2 + 65                           |
+//! | `ElectionPublicKey`            | 3   | group element                         | 2 + 65                           |
+//! | ballot                         | 4   | `n` (u16), `n` ciphertexts, proof     | 2 + 2 + 130n + 1 + 421m + 32     |
+//! | `WeightedBallot`               | 5   | weight (u64), ballot body             | 2 + 8 + 2 + 130n + 1 + 421m + 32 |
+//! | `EncryptedTally`               | 6   | `n` (u16), `n` ciphertexts            | 2 + 2 + 130n                     |
+//! | `TallyState`                   | 7   | `n` (u16), `n` group elements         | 2 + 2 + 65n                      |
+//! | `TallyDecryptShare`            | 8   | `n` (u16), `n` group elements         | 2 + 2 + 65n                      |
+//! | `Tally`                        | 9   | `n` (u16), `n` vote counts (u64)      | 2 + 2 + 8n                       |
+//! | `BallotReceipt`                | 10  | receipt                               | 2 + 32                           |
+//!
+//! With the ristretto255 backend the group elements are 32 bytes long: the
+//! keys take 2 + 32 bytes, a ciphertext 64 bytes and a round of a proof of
+//! correct vote 256 bytes.
+//!
+//! A proof of correct vote is encoded as `m` (u8) followed by the `m`
+//! announcements (three group elements each), the `m` ciphertexts (two group
+//! elements each), the `m` responses (three scalars each) and the final
+//! scalar. The scalars are 32 bytes long with both backends.
+//!
+//! The length of an encoding is also given by
+//! [`CanonicalEncoding::canonical_len`], and for the artifacts of an election
+//! with `n` options by [`canonical_len`].
+
+use crate::gang::{GroupElement, Scalar};
+use crate::shvzk::{Proof, IBA, ZWV};
+use crate::{
+    gargamel, BallotReceipt, Ciphertext, ElectionPublicKey, EncryptedTally, EncryptedVote,
+    MemberCommunicationPublicKey, MemberPublicKey, ProofOfCorrectVote, Tally, TallyDecryptShare,
+    TallyState, WeightedBallot,
+};
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+/// The version of the formats produced by [`CanonicalEncoding::to_canonical_bytes`]
+pub const CURRENT_VERSION: u8 = 1;
+
+/// Length of the tag and version preceding every body
+pub const HEADER_LEN: usize = 2;
+
+/// The largest number of options of a canonically encoded artifact
+pub const MAX_OPTIONS: usize = u16::MAX as usize;
+
+/// Tag identifying the artifact in the first byte of its canonical encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    MemberPublicKey = 1,
+    MemberCommunicationPublicKey = 2,
+    ElectionPublicKey = 3,
+    Ballot = 4,
+    WeightedBallot = 5,
+    EncryptedTally = 6,
+    TallyState = 7,
+    TallyDecryptShare = 8,
+    Tally = 9,
+    BallotReceipt = 10,
+}
+
+impl ArtifactKind {
+    pub fn from_u8(tag: u8) -> Option<Self> {
+        Some(match tag {
+            1 => ArtifactKind::MemberPublicKey,
+            2 => ArtifactKind::MemberCommunicationPublicKey,
+            3 => ArtifactKind::ElectionPublicKey,
+            4 => ArtifactKind::Ballot,
+            5 => ArtifactKind::WeightedBallot,
+            6 => ArtifactKind::EncryptedTally,
+            7 => ArtifactKind::TallyState,
+            8 => ArtifactKind::TallyDecryptShare,
+            9 => ArtifactKind::Tally,
+            10 => ArtifactKind::BallotReceipt,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum EncodingError {
+    #[error("missing artifact header")]
+    MissingHeader,
+    #[error("unknown artifact tag {0}")]
+    UnknownKind(u8),
+    #[error("expected an artifact of kind {expected:?}, found {found:?}")]
+    UnexpectedKind {
+        expected: ArtifactKind,
+        found: ArtifactKind,
+    },
+    #[error("unsupported version {version} for artifact {kind:?}")]
+    UnsupportedVersion { kind: ArtifactKind, version: u8 },
+    #[error("invalid or truncated body for artifact {0:?}")]
+    InvalidBody(ArtifactKind),
+    #[error("{0} trailing bytes after the artifact")]
+    TrailingBytes(usize),
+}
+
+/// Versioned, self-describing and strict byte encoding of an artifact
+pub trait CanonicalEncoding: Sized {
+    const KIND: ArtifactKind;
+
+    /// Length of the body in the current version
+    fn body_len(&self) -> usize;
+
+    /// Write the body in the current version, of `body_len` bytes
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Read the body of the given version, `None` if the version is not
+    /// supported or the body is invalid. Trailing bytes are checked by the
+    /// caller.
+    fn read_body(version: u8, reader: &mut Reader) -> Option<Self>;
+
+    /// Whether this version of the format can be decoded
+    fn supports_version(version: u8) -> bool {
+        version == CURRENT_VERSION
+    }
+
+    /// Length of the canonical encoding, header included
+    fn canonical_len(&self) -> usize {
+        HEADER_LEN + self.body_len()
+    }
+
+    /// Write the canonical encoding, of `canonical_len` bytes, without
+    /// building it in memory first
+    fn write_canonical_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[Self::KIND as u8, CURRENT_VERSION])?;
+        self.write_body(&mut writer)
+    }
+
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.canonical_len());
+        self.write_canonical_to(&mut bytes)
+            .expect("writing to a Vec does not fail");
+        debug_assert_eq!(bytes.len(), self.canonical_len());
+        bytes
+    }
+
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        let mut reader = Reader::new(bytes);
+        let header = reader
+            .take(HEADER_LEN)
+            .ok_or(EncodingError::MissingHeader)?;
+        let found =
+            ArtifactKind::from_u8(header[0]).ok_or(EncodingError::UnknownKind(header[0]))?;
+        if found != Self::KIND {
+            return Err(EncodingError::UnexpectedKind {
+                expected: Self::KIND,
+                found,
+            });
+        }
+        let version = header[1];
+        if !Self::supports_version(version) {
+            return Err(EncodingError::UnsupportedVersion {
+                kind: Self::KIND,
+                version,
+            });
+        }
+        let artifact =
+            Self::read_body(version, &mut reader).ok_or(EncodingError::InvalidBody(Self::KIND))?;
+        if !reader.is_empty() {
+            return Err(EncodingError::TrailingBytes(reader.remaining()));
+        }
+        Ok(artifact)
+    }
+}
+
+/// Length of the current canonical encoding of an artifact of an election
+/// with the given number of options
+pub fn canonical_len(kind: ArtifactKind, options: usize) -> usize {
+    let body = match kind {
+        ArtifactKind::MemberPublicKey
+        | ArtifactKind::MemberCommunicationPublicKey
+        | ArtifactKind::ElectionPublicKey => GroupElement::BYTES_LEN,
+        ArtifactKind::Ballot => ballot_body_len(options, proof_len(options)),
+        ArtifactKind::WeightedBallot => 8 + ballot_body_len(options, proof_len(options)),
+        ArtifactKind::EncryptedTally => 2 + options * Ciphertext::BYTES_LEN,
+        ArtifactKind::TallyState | ArtifactKind::TallyDecryptShare => {
+            2 + options * GroupElement::BYTES_LEN
+        }
+        ArtifactKind::Tally => 2 + options * 8,
+        ArtifactKind::BallotReceipt => BallotReceipt::BYTES_LEN,
+    };
+    HEADER_LEN + body
+}
+
+/// Length of the proof of a ballot, made on the unit vector padded to the
+/// next power of two
+fn proof_len(options: usize) -> usize {
+    options.next_power_of_two().trailing_zeros() as usize
+}
+
+fn ballot_body_len(options: usize, proof_len: usize) -> usize {
+    2 + options * Ciphertext::BYTES_LEN
+        + 1
+        + proof_len * (IBA::BYTES_LEN + Ciphertext::BYTES_LEN + ZWV::BYTES_LEN)
+        + Scalar::BYTES_LEN
+}
+
+/// Sequential reader over an encoded artifact
+pub struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader(bytes)
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        let mut value = [0u8; 2];
+        value.copy_from_slice(self.take(2)?);
+        Some(u16::from_be_bytes(value))
+    }
+
+    /// A 32 bits length
+    pub(crate) fn len(&mut self) -> Option<usize> {
+        let mut len = [0u8; 4];
+        len.copy_from_slice(self.take(4)?);
+        Some(u32::from_be_bytes(len) as usize)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        let mut value = [0u8; 8];
+        value.copy_from_slice(self.take(8)?);
+        Some(u64::from_be_bytes(value))
+    }
+
+    fn options(&mut self) -> Option<usize> {
+        match self.u16()? {
+            0 => None,
+            n => Some(n as usize),
+        }
+    }
+
+    fn group_elements(&mut self, n: usize) -> Option<Vec<GroupElement>> {
+        (0..n)
+            .map(|_| GroupElement::from_bytes(self.take(GroupElement::BYTES_LEN)?))
+            .collect()
+    }
+
+    fn ciphertexts(&mut self, n: usize) -> Option<Vec<Ciphertext>> {
+        (0..n)
+            .map(|_| Ciphertext::from_bytes(self.take(Ciphertext::BYTES_LEN)?))
+            .collect()
+    }
+}
+
+fn write_options<W: Write>(writer: &mut W, options: usize) -> io::Result<()> {
+    let options = u16::try_from(options).expect("at most MAX_OPTIONS options");
+    writer.write_all(&options.to_be_bytes())
+}
+
+fn write_ballot<W: Write>(writer: &mut W, vote: &[Ciphertext], proof: &Proof) -> io::Result<()> {
+    write_options(writer, vote.len())?;
+    for ciphertext in vote {
+        ciphertext.write_to(&mut *writer)?;
+    }
+    proof.write_to(writer)
+}
+
+fn read_ballot(reader: &mut Reader) -> Option<(EncryptedVote, Proof)> {
+    let options = reader.options()?;
+    let vote = reader.ciphertexts(options)?;
+    let len = reader.u8()? as usize;
+    // a proof of a different length would only be rejected by the
+    // verification
+    if len != proof_len(options) {
+        return None;
+    }
+    let ibas = (0..len)
+        .map(|_| IBA::from_bytes(reader.take(IBA::BYTES_LEN)?))
+        .collect::<Option<Vec<_>>>()?;
+    let ds = reader.ciphertexts(len)?;
+    let zwvs = (0..len)
+        .map(|_| ZWV::from_bytes(reader.take(ZWV::BYTES_LEN)?))
+        .collect::<Option<Vec<_>>>()?;
+    let r = Scalar::from_bytes(reader.take(Scalar::BYTES_LEN)?)?;
+    Some((vote, Proof::from_parts(ibas, ds, zwvs, r)))
+}
+
+impl CanonicalEncoding for MemberPublicKey {
+    const KIND: ArtifactKind = ArtifactKind::MemberPublicKey;
+
+    fn body_len(&self) -> usize {
+        MemberPublicKey::BYTES_LEN
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        MemberPublicKey::from_bytes(reader.take(MemberPublicKey::BYTES_LEN)?)
+    }
+}
+
+impl CanonicalEncoding for MemberCommunicationPublicKey {
+    const KIND: ArtifactKind = ArtifactKind::MemberCommunicationPublicKey;
+
+    fn body_len(&self) -> usize {
+        gargamel::PublicKey::BYTES_LEN
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        gargamel::PublicKey::from_bytes(reader.take(gargamel::PublicKey::BYTES_LEN)?)
+            .map(MemberCommunicationPublicKey::from_public_key)
+    }
+}
+
+impl CanonicalEncoding for ElectionPublicKey {
+    const KIND: ArtifactKind = ArtifactKind::ElectionPublicKey;
+
+    fn body_len(&self) -> usize {
+        gargamel::PublicKey::BYTES_LEN
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        ElectionPublicKey::from_bytes(reader.take(gargamel::PublicKey::BYTES_LEN)?)
+    }
+}
+
+/// A ballot, the encrypted vote and its proof of correctness
+impl CanonicalEncoding for (EncryptedVote, ProofOfCorrectVote) {
+    const KIND: ArtifactKind = ArtifactKind::Ballot;
+
+    fn body_len(&self) -> usize {
+        ballot_body_len(self.0.len(), self.1.len())
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_ballot(writer, &self.0, &self.1)
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        read_ballot(reader)
+    }
+}
+
+impl CanonicalEncoding for WeightedBallot {
+    const KIND: ArtifactKind = ArtifactKind::WeightedBallot;
+
+    fn body_len(&self) -> usize {
+        8 + ballot_body_len(self.ciphertexts().len(), self.proof().len())
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.weight().to_be_bytes())?;
+        write_ballot(writer, self.ciphertexts(), self.proof())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        let weight = reader.u64()?;
+        if weight == 0 {
+            return None;
+        }
+        let (ciphertexts, proof) = read_ballot(reader)?;
+        Some(WeightedBallot::from_parts(ciphertexts, proof, weight))
+    }
+}
+
+impl CanonicalEncoding for EncryptedTally {
+    const KIND: ArtifactKind = ArtifactKind::EncryptedTally;
+
+    fn body_len(&self) -> usize {
+        2 + self.r.len() * Ciphertext::BYTES_LEN
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_options(writer, self.r.len())?;
+        for ciphertext in &self.r {
+            ciphertext.write_to(&mut *writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        let options = reader.options()?;
+        reader.ciphertexts(options).map(|r| EncryptedTally { r })
+    }
+}
+
+impl CanonicalEncoding for TallyState {
+    const KIND: ArtifactKind = ArtifactKind::TallyState;
+
+    fn body_len(&self) -> usize {
+        2 + TallyState::bytes_len(self.r2s.len())
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_options(writer, self.r2s.len())?;
+        writer.write_all(&self.to_bytes())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        let options = reader.options()?;
+        reader.group_elements(options).map(|r2s| TallyState { r2s })
+    }
+}
+
+impl CanonicalEncoding for TallyDecryptShare {
+    const KIND: ArtifactKind = ArtifactKind::TallyDecryptShare;
+
+    fn body_len(&self) -> usize {
+        2 + TallyDecryptShare::bytes_len(self.options())
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_options(writer, self.options())?;
+        self.write_to(writer)
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        let options = reader.options()?;
+        reader
+            .group_elements(options)
+            .map(|r1s| TallyDecryptShare { r1s })
+    }
+}
+
+impl CanonicalEncoding for Tally {
+    const KIND: ArtifactKind = ArtifactKind::Tally;
+
+    fn body_len(&self) -> usize {
+        2 + self.votes.len() * 8
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_options(writer, self.votes.len())?;
+        for votes in &self.votes {
+            writer.write_all(&votes.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        let options = reader.options()?;
+        let votes = (0..options)
+            .map(|_| reader.u64())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Tally { votes })
+    }
+}
+
+impl CanonicalEncoding for BallotReceipt {
+    const KIND: ArtifactKind = ArtifactKind::BallotReceipt;
+
+    fn body_len(&self) -> usize {
+        BallotReceipt::BYTES_LEN
+    }
+
+    fn write_body<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.as_ref())
+    }
+
+    fn read_body(_version: u8, reader: &mut Reader) -> Option<Self> {
+        BallotReceipt::from_bytes(reader.take(BallotReceipt::BYTES_LEN)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        encrypt_vote, encrypt_weighted_vote, MemberCommunicationKey, MemberState, Vote, CRS,
+    };
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+    use std::fmt::Debug;
+
+    fn check<T: CanonicalEncoding + PartialEq + Debug>(artifact: &T, options: usize) {
+        let bytes = artifact.to_canonical_bytes();
+        assert_eq!(bytes.len(), artifact.canonical_len());
+        assert_eq!(bytes.len(), canonical_len(T::KIND, options));
+        assert_eq!(bytes[..HEADER_LEN], [T::KIND as u8, CURRENT_VERSION]);
+        assert_eq!(&T::from_canonical_bytes(&bytes).unwrap(), artifact);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            T::from_canonical_bytes(&trailing).unwrap_err(),
+            EncodingError::TrailingBytes(1)
+        );
+        assert_eq!(
+            T::from_canonical_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            EncodingError::InvalidBody(T::KIND)
+        );
+        let mut version = bytes.clone();
+        version[1] = CURRENT_VERSION + 1;
+        assert_eq!(
+            T::from_canonical_bytes(&version).unwrap_err(),
+            EncodingError::UnsupportedVersion {
+                kind: T::KIND,
+                version: CURRENT_VERSION + 1,
+            }
+        );
+        let mut kind = bytes;
+        kind[0] = if T::KIND == ArtifactKind::Tally {
+            ArtifactKind::BallotReceipt as u8
+        } else {
+            ArtifactKind::Tally as u8
+        };
+        assert!(matches!(
+            T::from_canonical_bytes(&kind).unwrap_err(),
+            EncodingError::UnexpectedKind { .. }
+        ));
+    }
+
+    #[test]
+    fn canonical_round_trips() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let crs = CRS::from_hash(&[0u8; 32]);
+        let mc = MemberCommunicationKey::new(&mut rng);
+        let member = MemberState::new(&mut rng, 1, &crs, &[mc.to_public()], 0);
+        let ek = ElectionPublicKey::from_participants(&[member.public_key()]);
+
+        check(&member.public_key(), 0);
+        assert_eq!(
+            MemberCommunicationPublicKey::from_canonical_bytes(
+                &mc.to_public().to_canonical_bytes()
+            )
+            .unwrap()
+            .to_bytes(),
+            mc.to_public().to_bytes()
+        );
+        assert_eq!(
+            ElectionPublicKey::from_canonical_bytes(&ek.to_canonical_bytes())
+                .unwrap()
+                .to_bytes(),
+            ek.to_bytes()
+        );
+
+        for &options in &[1, 2, 3, 4, 5] {
+            let ballot = encrypt_vote(&mut rng, &crs, &ek, Vote::new(options, options - 1));
+            check(&ballot, options);
+            check(&BallotReceipt::compute(&ballot.0, &ballot.1), options);
+            let weighted = encrypt_weighted_vote(&mut rng, &crs, &ek, Vote::new(options, 0), 7);
+            check(&weighted, options);
+
+            let mut tally = EncryptedTally::new(options);
+            tally.add(&ballot.0, 3);
+            tally.add_weighted(&weighted);
+            check(&tally, options);
+            let (state, share) = tally.finish(member.secret_key());
+            check(&share, options);
+            let decoded = TallyState::from_canonical_bytes(&state.to_canonical_bytes()).unwrap();
+            assert_eq!(decoded.to_bytes(), state.to_bytes());
+            check(
+                &Tally {
+                    votes: (0..options as u64).collect(),
+                },
+                options,
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_lengths() {
+        assert_eq!(
+            Tally::from_canonical_bytes(&[]).unwrap_err(),
+            EncodingError::MissingHeader
+        );
+        assert_eq!(
+            Tally::from_canonical_bytes(&[0xff, CURRENT_VERSION]).unwrap_err(),
+            EncodingError::UnknownKind(0xff)
+        );
+        // a tally over zero options
+        assert_eq!(
+            Tally::from_canonical_bytes(&[ArtifactKind::Tally as u8, CURRENT_VERSION, 0, 0])
+                .unwrap_err(),
+            EncodingError::InvalidBody(ArtifactKind::Tally)
+        );
+
+        // a proof whose length does not match the number of options
+        let mut rng = ChaCha20Rng::from_seed([1u8; 32]);
+        let crs = CRS::from_hash(&[0u8; 32]);
+        let mc = MemberCommunicationKey::new(&mut rng);
+        let member = MemberState::new(&mut rng, 1, &crs, &[mc.to_public()], 0);
+        let ek = ElectionPublicKey::from_participants(&[member.public_key()]);
+        let (vote, proof) = encrypt_vote(&mut rng, &crs, &ek, Vote::new(4, 1));
+        let mut bytes = (vote, proof).to_canonical_bytes();
+        bytes[HEADER_LEN + 2 + 4 * Ciphertext::BYTES_LEN] = 3;
+        assert_eq!(
+            <(EncryptedVote, ProofOfCorrectVote)>::from_canonical_bytes(&bytes).unwrap_err(),
+            EncodingError::InvalidBody(ArtifactKind::Ballot)
+        );
+        assert_eq!(proof_len(1), 0);
+        assert_eq!(proof_len(4), 2);
+        assert_eq!(proof_len(5), 3);
+    }
+
+    // the lengths given in the module documentation
+    #[cfg(not(feature = "ristretto255"))]
+    #[test]
+    fn documented_lengths() {
+        let (n, m) = (4, 2);
+        assert_eq!(canonical_len(ArtifactKind::MemberPublicKey, n), 2 + 65);
+        assert_eq!(
+            canonical_len(ArtifactKind::Ballot, n),
+            2 + 2 + 130 * n + 1 + 421 * m + 32
+        );
+        assert_eq!(
+            canonical_len(ArtifactKind::WeightedBallot, n),
+            2 + 8 + 2 + 130 * n + 1 + 421 * m + 32
+        );
+        assert_eq!(
+            canonical_len(ArtifactKind::EncryptedTally, n),
+            2 + 2 + 130 * n
+        );
+        assert_eq!(
+            canonical_len(ArtifactKind::TallyDecryptShare, n),
+            2 + 2 + 65 * n
+        );
+        assert_eq!(canonical_len(ArtifactKind::BallotReceipt, n), 2 + 32);
+    }
+}
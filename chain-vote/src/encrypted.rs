@@ -1,5 +1,5 @@
 use crate::gang::Scalar;
-use crate::gargamel::{self, Ciphertext, PublicKey};
+use crate::gargamel::{Ciphertext, Encryptor};
 use crate::unit_vector::UnitVector;
 use rand_core::{CryptoRng, RngCore};
 
@@ -65,16 +65,16 @@ pub struct EncryptingVote {
 }
 
 impl EncryptingVote {
-    pub fn prepare<R: RngCore + CryptoRng>(
+    pub fn prepare<R: RngCore + CryptoRng, E: Encryptor>(
         rng: &mut R,
-        public_key: &PublicKey,
+        public_key: &E,
         vote: &UnitVector,
     ) -> Self {
         let mut rs = Vec::new();
         let mut ciphers = Vec::new();
         for vote_element in vote.iter() {
             let r = Scalar::random(rng);
-            let cipher = gargamel::encrypt(public_key, &vote_element.into(), &r);
+            let cipher = public_key.encrypt(&vote_element.into(), &r);
             rs.push(r);
             ciphers.push(cipher);
         }
@@ -1,5 +1,4 @@
 use super::*;
-use rayon::prelude::*;
 use std::collections::HashMap;
 
 // make steps asymmetric, in order to better use caching of baby steps.
@@ -74,35 +73,35 @@ pub fn baby_step_giant_step(
     let baby_step_size = table.baby_step_size;
     let giant_step = &table.giant_step;
     let table = &table.table;
-    points
-        .into_par_iter()
-        .map(|mut point| {
-            let mut a = 0;
-            loop {
-                #[cfg(not(feature = "ristretto255"))]
-                if let Some(x) = table.get(&point.compress().map(|(c, _sign)| c.to_bytes())) {
-                    let r = if Scalar::from_u64(*x) * GroupElement::generator() == point {
-                        a * baby_step_size + x
-                    } else {
-                        a * baby_step_size - x
-                    };
-                    return Ok(r);
-                }
-
-                #[cfg(feature = "ristretto255")]
-                if let Some(x) = table.get(&Some(point.to_bytes())) {
-                    let r = a * baby_step_size + x;
-                    return Ok(r);
-                }
-
-                if a * baby_step_size > max_log {
-                    return Err(MaxLogExceeded);
-                }
-                point = point + giant_step;
-                a += 1;
+    crate::parallel::map(&points, |point| {
+        let mut point = point.clone();
+        let mut a = 0;
+        loop {
+            #[cfg(not(feature = "ristretto255"))]
+            if let Some(x) = table.get(&point.compress().map(|(c, _sign)| c.to_bytes())) {
+                let r = if Scalar::from_u64(*x) * GroupElement::generator() == point {
+                    a * baby_step_size + x
+                } else {
+                    a * baby_step_size - x
+                };
+                return Ok(r);
             }
-        })
-        .collect()
+
+            #[cfg(feature = "ristretto255")]
+            if let Some(x) = table.get(&Some(point.to_bytes())) {
+                let r = a * baby_step_size + x;
+                return Ok(r);
+            }
+
+            if a * baby_step_size > max_log {
+                return Err(MaxLogExceeded);
+            }
+            point = point + giant_step;
+            a += 1;
+        }
+    })
+    .into_iter()
+    .collect()
 }
 
 #[cfg(test)]
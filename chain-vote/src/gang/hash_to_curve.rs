@@ -0,0 +1,128 @@
+//! Hashing to the group following RFC 9380 ("Hashing to Elliptic Curves").
+//!
+//! The historical `GroupElement::from_hash` construction is kept as
+//! [`HashToCurveVersion::Legacy`] so that already published common reference
+//! strings keep their value, new users should prefer
+//! [`HashToCurveVersion::Rfc9380`].
+
+use cryptoxide::digest::Digest;
+
+/// Selects the algorithm used to map arbitrary bytes to a group element.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HashToCurveVersion {
+    /// Ad-hoc construction used before the RFC suites were available.
+    Legacy,
+    /// Random oracle suite of RFC 9380 for the backend curve
+    /// (`ristretto255_XMD:SHA-512_R255MAP_RO_` or
+    /// `secp256k1_XMD:SHA-256_SSWU_RO_`).
+    Rfc9380,
+}
+
+impl Default for HashToCurveVersion {
+    fn default() -> Self {
+        HashToCurveVersion::Legacy
+    }
+}
+
+/// Domain separation tag used by `from_hash_versioned` for the RFC 9380 suites.
+pub const CHAIN_VOTE_DST: &[u8] = b"CHAIN-VOTE-V01-CS01-with-hash-to-curve";
+
+/// `expand_message_xmd` as specified in section 5.3.1 of RFC 9380.
+///
+/// Returns `None` if the requested length or the domain separation tag are
+/// out of the bounds allowed by the specification.
+pub fn expand_message_xmd<D: Digest>(
+    mut hash: D,
+    msg: &[u8],
+    dst: &[u8],
+    len_in_bytes: usize,
+) -> Option<Vec<u8>> {
+    let b_in_bytes = hash.output_bytes();
+    let s_in_bytes = hash.block_size();
+    let ell = (len_in_bytes + b_in_bytes - 1) / b_in_bytes;
+    if ell > 255 || len_in_bytes > 65535 || dst.len() > 255 {
+        return None;
+    }
+    let dst_len = [dst.len() as u8];
+    let len_in_bytes_str = (len_in_bytes as u16).to_be_bytes();
+
+    let mut b_0 = vec![0u8; b_in_bytes];
+    hash.reset();
+    hash.input(&vec![0u8; s_in_bytes]);
+    hash.input(msg);
+    hash.input(&len_in_bytes_str);
+    hash.input(&[0u8]);
+    hash.input(dst);
+    hash.input(&dst_len);
+    hash.result(&mut b_0);
+
+    let mut uniform_bytes = Vec::with_capacity(ell * b_in_bytes);
+    let mut b_i = vec![0u8; b_in_bytes];
+    for i in 1..=ell {
+        let mut chained = b_0.clone();
+        if i > 1 {
+            chained
+                .iter_mut()
+                .zip(b_i.iter())
+                .for_each(|(c, b)| *c ^= b);
+        }
+        hash.reset();
+        hash.input(&chained);
+        hash.input(&[i as u8]);
+        hash.input(dst);
+        hash.input(&dst_len);
+        hash.result(&mut b_i);
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    uniform_bytes.truncate(len_in_bytes);
+    Some(uniform_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cryptoxide::sha2::{Sha256, Sha512};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // test vectors from RFC 9380 appendix K.1
+    #[test]
+    fn expand_message_xmd_sha256() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        let out = expand_message_xmd(Sha256::new(), b"", dst, 0x20).unwrap();
+        assert_eq!(
+            hex(&out),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        let out = expand_message_xmd(Sha256::new(), b"abc", dst, 0x20).unwrap();
+        assert_eq!(
+            hex(&out),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+    }
+
+    // test vectors from RFC 9380 appendix K.3
+    #[test]
+    fn expand_message_xmd_sha512() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA512-256";
+        let out = expand_message_xmd(Sha512::new(), b"", dst, 0x20).unwrap();
+        assert_eq!(
+            hex(&out),
+            "6b9a7312411d92f921c6f68ca0b6380730a1a4d982c507211a90964c394179ba"
+        );
+    }
+
+    #[test]
+    fn expand_message_xmd_bounds() {
+        assert!(expand_message_xmd(Sha256::new(), b"", &[0u8; 256], 32).is_none());
+        assert!(expand_message_xmd(Sha256::new(), b"", b"dst", 256 * 32).is_none());
+        assert_eq!(
+            expand_message_xmd(Sha256::new(), b"", b"dst", 255 * 32)
+                .unwrap()
+                .len(),
+            255 * 32
+        );
+    }
+}
@@ -1,14 +1,20 @@
 mod babystep;
+mod hash_to_curve;
 #[cfg(not(feature = "ristretto255"))]
 mod p256k1;
+mod precomp;
 #[cfg(feature = "ristretto255")]
 mod ristretto255;
+#[cfg(not(feature = "ristretto255"))]
+mod sswu;
 
 #[cfg(not(feature = "ristretto255"))]
 pub use self::p256k1::*;
 #[cfg(feature = "ristretto255")]
 pub use self::ristretto255::*;
 pub use babystep::{baby_step_giant_step, BabyStepsTable};
+pub use hash_to_curve::{expand_message_xmd, HashToCurveVersion, CHAIN_VOTE_DST};
+pub use precomp::FixedBaseTable;
 
 #[cfg(test)]
 mod tests {
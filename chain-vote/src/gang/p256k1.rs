@@ -6,6 +6,9 @@ use rand_core::{CryptoRng, RngCore};
 use std::hash::{Hash, Hasher};
 use std::ops::{Add, Mul, Sub};
 
+use super::hash_to_curve::{HashToCurveVersion, CHAIN_VOTE_DST};
+use super::sswu;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Scalar(IScalar);
 
@@ -80,6 +83,37 @@ impl GroupElement {
         }
     }
 
+    /// Point from hash, following the `secp256k1_XMD:SHA-256_SSWU_RO_`
+    /// suite of RFC 9380 with the domain separation tag `dst`: the message
+    /// is hashed to two field elements, each mapped to the curve with the
+    /// simplified SWU map and the 3-isogeny, and the two points are added.
+    ///
+    /// The sum is the point at infinity only if the two mapped points are
+    /// opposite, which happens with negligible probability; the identity is
+    /// then returned, as the suite specifies.
+    ///
+    /// Panics if `dst` is longer than 255 bytes.
+    pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        match sswu::hash_to_curve(msg, dst) {
+            Some((x, y)) => {
+                let mut bytes = [0u8; Self::BYTES_LEN];
+                bytes[0] = 0x4;
+                bytes[1..33].copy_from_slice(&x);
+                bytes[33..65].copy_from_slice(&y);
+                Self::from_bytes(&bytes).expect("the mapped point is on the curve")
+            }
+            None => Self::zero(),
+        }
+    }
+
+    /// Point from hash with the given algorithm version
+    pub fn from_hash_versioned(buffer: &[u8], version: HashToCurveVersion) -> Self {
+        match version {
+            HashToCurveVersion::Legacy => Self::from_hash(buffer),
+            HashToCurveVersion::Rfc9380 => Self::hash_to_curve(buffer, CHAIN_VOTE_DST),
+        }
+    }
+
     fn from_x_bytes(bytes: &[u8], sign: Sign) -> Option<Self> {
         let x_coord = Coordinate::from_bytes(bytes)?;
         Self::decompress(&x_coord, sign)
@@ -149,6 +183,50 @@ impl GroupElement {
     }
 }
 
+/// Points of a `FixedBaseTable` window, looked up in constant time.
+///
+/// The points are kept as their affine coordinates: a lookup reads every
+/// entry and masks all but the selected one, without branching on the index.
+/// The identity has no affine coordinates and cannot be part of a row.
+#[derive(Debug, Clone)]
+pub(super) struct LookupRow(Vec<[u8; GroupElement::BYTES_LEN - 1]>);
+
+impl LookupRow {
+    pub(super) fn new(points: &[GroupElement]) -> Self {
+        LookupRow(
+            points
+                .iter()
+                .map(|point| {
+                    let bytes = point.to_bytes();
+                    assert_eq!(bytes[0], 0x4, "the identity cannot be looked up");
+                    let mut coordinates = [0u8; GroupElement::BYTES_LEN - 1];
+                    coordinates.copy_from_slice(&bytes[1..]);
+                    coordinates
+                })
+                .collect(),
+        )
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The point at `index`, in constant time
+    pub(super) fn select(&self, index: usize) -> GroupElement {
+        let mut bytes = [0u8; GroupElement::BYTES_LEN];
+        bytes[0] = 0x4;
+        for (i, coordinates) in self.0.iter().enumerate() {
+            // 0xff if i == index, 0 otherwise
+            let diff = (i ^ index) as u64;
+            let mask = (((diff | diff.wrapping_neg()) >> 63) as u8).wrapping_sub(1);
+            for (b, c) in bytes[1..].iter_mut().zip(coordinates.iter()) {
+                *b |= c & mask;
+            }
+        }
+        GroupElement::from_bytes(&bytes).expect("the rows only hold points of the curve")
+    }
+}
+
 impl Scalar {
     pub const BYTES_LEN: usize = 32;
 
@@ -406,4 +484,39 @@ mod test {
         .expect("This point is on the curve");
         assert_eq!(element, element2)
     }
+
+    #[test]
+    fn from_hash_versioned() {
+        let legacy = GroupElement::from_hash_versioned(&[1u8], HashToCurveVersion::Legacy);
+        assert_eq!(legacy, GroupElement::from_hash(&[1u8]));
+
+        let rfc = GroupElement::from_hash_versioned(&[1u8], HashToCurveVersion::Rfc9380);
+        assert_eq!(rfc, GroupElement::hash_to_curve(&[1u8], CHAIN_VOTE_DST));
+        assert_ne!(rfc, legacy);
+        assert_ne!(rfc, GroupElement::hash_to_curve(&[1u8], b"other-dst"));
+    }
+
+    // test vectors from RFC 9380 appendix J.8.1
+    #[test]
+    fn hash_to_curve_test_vectors() {
+        let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+        let point = GroupElement::hash_to_curve(b"abc", dst);
+        let mut expected = vec![4u8];
+        expected.extend_from_slice(&[
+            0x33, 0x77, 0xe0, 0x1e, 0xab, 0x42, 0xdb, 0x29, 0x6b, 0x51, 0x22, 0x93, 0x12, 0x0c,
+            0x6c, 0xee, 0x72, 0xb6, 0xec, 0xf9, 0xf9, 0x20, 0x57, 0x60, 0xbd, 0x9f, 0xf1, 0x1f,
+            0xb3, 0xcb, 0x2c, 0x4b,
+        ]);
+        expected.extend_from_slice(&[
+            0x7f, 0x95, 0x89, 0x0f, 0x33, 0xef, 0xeb, 0xd1, 0x04, 0x4d, 0x38, 0x2a, 0x01, 0xb1,
+            0xbe, 0xe0, 0x90, 0x0f, 0xb6, 0x11, 0x6f, 0x94, 0x68, 0x8d, 0x48, 0x7c, 0x6c, 0x7b,
+            0x9c, 0x83, 0x71, 0xf6,
+        ]);
+        assert_eq!(&point.to_bytes()[..], &expected[..]);
+
+        for i in 0u32..32 {
+            let point = GroupElement::hash_to_curve(&i.to_be_bytes(), CHAIN_VOTE_DST);
+            assert_ne!(point, GroupElement::zero());
+        }
+    }
 }
@@ -0,0 +1,178 @@
+use super::*;
+
+#[cfg(not(feature = "ristretto255"))]
+use super::p256k1::LookupRow;
+#[cfg(feature = "ristretto255")]
+use super::ristretto255::LookupRow;
+
+// 16 precomputed multiples per window, 64 windows for a 256 bits scalar
+const DEFAULT_WINDOW: usize = 4;
+
+const SCALAR_BITS: usize = Scalar::BYTES_LEN * 8;
+
+// hashed with the base to the point offsetting all the entries of its table
+const OFFSET_SEED: &[u8] = b"chain-vote fixed base table offset";
+
+/// Precomputed multiples of a fixed base point, to speed up the
+/// multiplications of this point by a scalar.
+///
+/// The scalar is split in windows of `window` bits, and for each window all the
+/// multiples of the base shifted to this window are precomputed, so that a
+/// multiplication only costs one addition per window, and no doubling.
+///
+/// The table holds `ceil(256 / window) * 2^window` points, and building it
+/// costs as many point additions: with the default window of 4 bits this is
+/// 1024 points, a multiplication being then about 4 times faster than without
+/// table. Larger windows make multiplications faster, at the expense of a table
+/// growing exponentially.
+///
+/// The multiplications are constant time: every entry of a window is read to
+/// look up the digit of the scalar (see `LookupRow`), and a point is added for
+/// every window, the zero digits included. Every entry is offset by a point
+/// hashed from the base, of unknown discrete logarithm, so that no entry is
+/// the identity, and the offsets are subtracted from the sum once all the
+/// windows are added.
+#[derive(Debug, Clone)]
+pub struct FixedBaseTable {
+    window: usize,
+    // for each window i, the points d * 2^(window * i) * base + offset for d in 0..2^window
+    table: Vec<LookupRow>,
+    // the sum of the offsets of all the windows
+    offsets: GroupElement,
+}
+
+impl FixedBaseTable {
+    /// Generate the table of the base with the default window
+    pub fn generate(base: &GroupElement) -> Self {
+        Self::generate_with_window(base, DEFAULT_WINDOW)
+    }
+
+    /// Generate the table of the base with a window of the given number of
+    /// bits, between 1 and 8.
+    pub fn generate_with_window(base: &GroupElement, window: usize) -> Self {
+        assert!(
+            (1..=8).contains(&window),
+            "window of {} bits is not between 1 and 8",
+            window
+        );
+        let windows = (SCALAR_BITS + window - 1) / window;
+        let digits = 1 << window;
+        // derived from the base, so that no base can be chosen to have one
+        // of its multiples cancel the offset
+        let mut seed = OFFSET_SEED.to_vec();
+        seed.extend_from_slice(&base.to_bytes());
+        let offset = GroupElement::from_hash(&seed);
+
+        let mut table = Vec::with_capacity(windows);
+        let mut window_base = base.clone();
+        for _ in 0..windows {
+            let mut row = Vec::with_capacity(digits);
+            let mut e = offset.clone();
+            for _ in 0..digits {
+                row.push(e.clone());
+                e = &e + &window_base;
+            }
+            // e is now the offset plus 2^window times the base of this window
+            window_base = &e - &offset;
+            table.push(LookupRow::new(&row));
+        }
+        let offsets = &offset * windows as u64;
+        Self {
+            window,
+            table,
+            offsets,
+        }
+    }
+
+    /// Number of bits of the windows the scalars are split in
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Number of points held by the table
+    pub fn len(&self) -> usize {
+        self.table.iter().map(LookupRow::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Multiply the base of the table by the scalar
+    pub fn mul(&self, scalar: &Scalar) -> GroupElement {
+        let bytes = scalar_le_bytes(scalar);
+        let mask = (1u16 << self.window) - 1;
+        let mut result = GroupElement::zero();
+        for (i, row) in self.table.iter().enumerate() {
+            let bit = i * self.window;
+            let byte = bit / 8;
+            let mut bits = bytes[byte] as u16;
+            if byte + 1 < bytes.len() {
+                bits |= (bytes[byte + 1] as u16) << 8;
+            }
+            let digit = (bits >> (bit % 8)) & mask;
+            result = &result + &row.select(digit as usize);
+        }
+        &result - &self.offsets
+    }
+}
+
+fn scalar_le_bytes(scalar: &Scalar) -> [u8; Scalar::BYTES_LEN] {
+    #[allow(unused_mut)]
+    let mut bytes = scalar.to_bytes();
+    // sec2 scalars are serialized in big endian, ristretto ones in little endian
+    #[cfg(not(feature = "ristretto255"))]
+    bytes.reverse();
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn little_endian_scalar() {
+        let bytes = scalar_le_bytes(&Scalar::from_u64(0x0102));
+        assert_eq!(bytes[0], 0x02);
+        assert_eq!(bytes[1], 0x01);
+        assert!(bytes[2..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn table_size() {
+        let table = FixedBaseTable::generate(&GroupElement::generator());
+        assert_eq!(table.window(), DEFAULT_WINDOW);
+        assert_eq!(table.len(), 64 * 16);
+
+        let table = FixedBaseTable::generate_with_window(&GroupElement::generator(), 3);
+        assert_eq!(table.len(), 86 * 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn window_too_large() {
+        FixedBaseTable::generate_with_window(&GroupElement::generator(), 9);
+    }
+
+    #[test]
+    fn same_as_multiplication() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let base = GroupElement::from_hash(b"fixed base");
+        let mut scalars = vec![
+            Scalar::zero(),
+            Scalar::one(),
+            Scalar::one().negate(),
+            Scalar::from_u64(u64::MAX),
+        ];
+        scalars.extend((0..8).map(|_| Scalar::random(&mut rng)));
+
+        for window in 1..=8 {
+            let table = FixedBaseTable::generate_with_window(&base, window);
+            for scalar in scalars.iter() {
+                assert_eq!(table.mul(scalar), &base * scalar, "window {}", window);
+            }
+        }
+    }
+}
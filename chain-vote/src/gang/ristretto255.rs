@@ -5,8 +5,13 @@ use curve25519_dalek_ng::{
     traits::Identity,
 };
 
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+
 use cryptoxide::blake2b::Blake2b;
 use cryptoxide::digest::Digest;
+use cryptoxide::sha2::Sha512;
+
+use super::hash_to_curve::{expand_message_xmd, HashToCurveVersion, CHAIN_VOTE_DST};
 
 use rand_core::{CryptoRng, RngCore};
 use std::hash::{Hash, Hasher};
@@ -70,6 +75,26 @@ impl GroupElement {
         GroupElement(Point::from_uniform_bytes(&result))
     }
 
+    /// Point from hash, following the `ristretto255_XMD:SHA-512_R255MAP_RO_`
+    /// suite of RFC 9380 with the domain separation tag `dst`.
+    ///
+    /// Panics if `dst` is longer than 255 bytes.
+    pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        let uniform_bytes = expand_message_xmd(Sha512::new(), msg, dst, 64)
+            .expect("domain separation tag too long");
+        let mut result = [0u8; 64];
+        result.copy_from_slice(&uniform_bytes);
+        GroupElement(Point::from_uniform_bytes(&result))
+    }
+
+    /// Point from hash with the given algorithm version
+    pub fn from_hash_versioned(buffer: &[u8], version: HashToCurveVersion) -> Self {
+        match version {
+            HashToCurveVersion::Legacy => Self::from_hash(buffer),
+            HashToCurveVersion::Rfc9380 => Self::hash_to_curve(buffer, CHAIN_VOTE_DST),
+        }
+    }
+
     pub fn sum<'a, I>(i: I) -> Self
     where
         I: Iterator<Item = &'a Self>,
@@ -82,6 +107,30 @@ impl GroupElement {
     }
 }
 
+/// Points of a `FixedBaseTable` window, looked up in constant time: a lookup
+/// reads every entry and conditionally assigns the selected one.
+#[derive(Debug, Clone)]
+pub(super) struct LookupRow(Vec<Point>);
+
+impl LookupRow {
+    pub(super) fn new(points: &[GroupElement]) -> Self {
+        LookupRow(points.iter().map(|point| point.0).collect())
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The point at `index`, in constant time
+    pub(super) fn select(&self, index: usize) -> GroupElement {
+        let mut selected = Point::identity();
+        for (i, point) in self.0.iter().enumerate() {
+            selected.conditional_assign(point, (i as u64).ct_eq(&(index as u64)));
+        }
+        GroupElement(selected)
+    }
+}
+
 impl Scalar {
     pub const BYTES_LEN: usize = 32;
 
@@ -362,4 +411,15 @@ mod test {
 
         assert_eq!(element, element2)
     }
+
+    #[test]
+    fn from_hash_versioned() {
+        let legacy = GroupElement::from_hash_versioned(&[1u8], HashToCurveVersion::Legacy);
+        assert_eq!(legacy, GroupElement::from_hash(&[1u8]));
+
+        let rfc = GroupElement::from_hash_versioned(&[1u8], HashToCurveVersion::Rfc9380);
+        assert_eq!(rfc, GroupElement::hash_to_curve(&[1u8], CHAIN_VOTE_DST));
+        assert_ne!(rfc, legacy);
+        assert_ne!(rfc, GroupElement::hash_to_curve(&[1u8], b"other-dst"));
+    }
 }
@@ -0,0 +1,365 @@
+//! `secp256k1_XMD:SHA-256_SSWU_RO_` suite of RFC 9380.
+//!
+//! secp256k1 has `A = 0` so the simplified SWU map is applied on a 3-isogenous
+//! curve and the result is mapped back through the isogeny (section 6.6.3).
+//! Hashing to the curve works on public data only, so none of the arithmetic
+//! below attempts to be constant time.
+
+use super::hash_to_curve::expand_message_xmd;
+use cryptoxide::sha2::Sha256;
+use std::cmp::Ordering;
+
+/// Element of the base field, little endian 64 bits limbs, always reduced.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Fe([u64; 4]);
+
+const P: [u64; 4] = [
+    0xFFFF_FFFE_FFFF_FC2F,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+];
+/// 2^256 mod p
+const C: u64 = 0x1_0000_03D1;
+const P_MINUS_2: [u64; 4] = [
+    0xFFFF_FFFE_FFFF_FC2D,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+];
+/// (p + 1) / 4, p = 3 mod 4 so this exponent computes square roots
+const P_PLUS_1_DIV_4: [u64; 4] = [
+    0xFFFF_FFFF_BFFF_FF0C,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0x3FFF_FFFF_FFFF_FFFF,
+];
+
+fn cmp(a: &[u64; 4], b: &[u64; 4]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+fn sub_borrow(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut r = [0u64; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (v, b1) = a[i].overflowing_sub(b[i]);
+        let (v, b2) = v.overflowing_sub(borrow as u64);
+        r[i] = v;
+        borrow = b1 | b2;
+    }
+    (r, borrow)
+}
+
+fn add_carry(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut r = [0u64; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (v, c1) = a[i].overflowing_add(b[i]);
+        let (v, c2) = v.overflowing_add(carry as u64);
+        r[i] = v;
+        carry = c1 | c2;
+    }
+    (r, carry)
+}
+
+impl Fe {
+    const ZERO: Fe = Fe([0; 4]);
+    const ONE: Fe = Fe([1, 0, 0, 0]);
+
+    fn from_u64(v: u64) -> Self {
+        Fe([v, 0, 0, 0])
+    }
+
+    /// Parse a big endian hexadecimal constant, the value must be reduced
+    fn from_hex(s: &str) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let end = s.len() - 16 * i;
+            *limb = u64::from_str_radix(&s[end - 16..end], 16).unwrap();
+        }
+        debug_assert_eq!(cmp(&limbs, &P), Ordering::Less);
+        Fe(limbs)
+    }
+
+    /// Reduce a big endian integer of at most 512 bits
+    fn from_be_bytes_wide(bytes: &[u8]) -> Self {
+        assert!(bytes.len() <= 64);
+        let mut t = [0u64; 8];
+        for (i, b) in bytes.iter().rev().enumerate() {
+            t[i / 8] |= (*b as u64) << (8 * (i % 8));
+        }
+        Self::reduce_wide(&t)
+    }
+
+    fn to_be_bytes(self) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, limb) in self.0.iter().rev().enumerate() {
+            out[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        out
+    }
+
+    fn reduce_wide(t: &[u64; 8]) -> Self {
+        // fold the high half using 2^256 = C (mod p)
+        let mut r = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let v = t[i] as u128 + (t[i + 4] as u128) * (C as u128) + carry;
+            r[i] = v as u64;
+            carry = v >> 64;
+        }
+        // carry is at most 34 bits, fold it once more
+        let folded = carry * (C as u128);
+        let (mut r, overflow) = add_carry(&r, &[folded as u64, (folded >> 64) as u64, 0, 0]);
+        if overflow {
+            r = add_carry(&r, &[C, 0, 0, 0]).0;
+        }
+        Self::reduce_once(r)
+    }
+
+    fn reduce_once(r: [u64; 4]) -> Self {
+        if cmp(&r, &P) != Ordering::Less {
+            Fe(sub_borrow(&r, &P).0)
+        } else {
+            Fe(r)
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let (r, carry) = add_carry(&self.0, &other.0);
+        if carry {
+            Fe(add_carry(&r, &[C, 0, 0, 0]).0)
+        } else {
+            Self::reduce_once(r)
+        }
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        let (r, borrow) = sub_borrow(&self.0, &other.0);
+        if borrow {
+            Fe(add_carry(&r, &P).0)
+        } else {
+            Fe(r)
+        }
+    }
+
+    fn neg(&self) -> Self {
+        Fe::ZERO.sub(self)
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let mut t = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let v = t[i + j] as u128 + (self.0[i] as u128) * (other.0[j] as u128) + carry;
+                t[i + j] = v as u64;
+                carry = v >> 64;
+            }
+            t[i + 4] = carry as u64;
+        }
+        Self::reduce_wide(&t)
+    }
+
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+
+    fn pow(&self, exp: &[u64; 4]) -> Self {
+        let mut r = Fe::ONE;
+        for limb in exp.iter().rev() {
+            for bit in (0..64).rev() {
+                r = r.square();
+                if (limb >> bit) & 1 == 1 {
+                    r = r.mul(self);
+                }
+            }
+        }
+        r
+    }
+
+    fn inverse(&self) -> Self {
+        self.pow(&P_MINUS_2)
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        let r = self.pow(&P_PLUS_1_DIV_4);
+        if r.square() == *self {
+            Some(r)
+        } else {
+            None
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Fe::ZERO
+    }
+
+    fn sgn0(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+}
+
+/// Evaluate a polynomial given by its coefficients in increasing degree
+fn poly(coefficients: &[&str], x: &Fe) -> Fe {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Fe::ZERO, |acc, k| acc.mul(x).add(&Fe::from_hex(k)))
+}
+
+// constants of the isogenous curve E' and of the 3-isogeny (RFC 9380 appendix E.1)
+const ISO_A: &str = "3f8731abdd661adca08a5558f0f5d272e953d363cb6f0e5d405447c01a444533";
+const ISO_B: u64 = 1771;
+const Z: u64 = 11; // Z = -11
+const X_NUM: [&str; 4] = [
+    "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa8c7",
+    "07d3d4c80bc321d5b9f315cea7fd44c5d595d2fc0bf63b92dfff1044f17c6581",
+    "534c328d23f234e6e2a413deca25caece4506144037c40314ecbd0b53d9dd262",
+    "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa88c",
+];
+const X_DEN: [&str; 3] = [
+    "d35771193d94918a9ca34ccbb7b640dd86cd409542f8487d9fe6b745781eb49b",
+    "edadc6f64383dc1df7c4b2d51b54225406d36b641f5e41bbc52a56612a8c6d14",
+    "0000000000000000000000000000000000000000000000000000000000000001",
+];
+const Y_NUM: [&str; 4] = [
+    "4bda12f684bda12f684bda12f684bda12f684bda12f684bda12f684b8e38e23c",
+    "c75e0c32d5cb7c0fa9d0a54b12a0a6d5647ab046d686da6fdffc90fc201d71a3",
+    "29a6194691f91a73715209ef6512e576722830a201be2018a765e85a9ecee931",
+    "2f684bda12f684bda12f684bda12f684bda12f684bda12f684bda12f38e38d84",
+];
+const Y_DEN: [&str; 4] = [
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffff93b",
+    "7a06534bb8bdb49fd5e9e6632722c2989467c1bfc8e8d978dfb425d2685c2573",
+    "6484aa716545ca2cf3a70c3fa8fe337e0a3d21162f0d6299a7bf8192bfd2a76f",
+    "0000000000000000000000000000000000000000000000000000000000000001",
+];
+
+/// Simplified SWU map to E' (section 6.6.2)
+fn map_to_iso_curve(u: &Fe) -> (Fe, Fe) {
+    let a = Fe::from_hex(ISO_A);
+    let b = Fe::from_u64(ISO_B);
+    let z = Fe::from_u64(Z).neg();
+    let g = |x: &Fe| x.square().mul(x).add(&a.mul(x)).add(&b);
+
+    let z_u2 = z.mul(&u.square());
+    let tv1 = z_u2.square().add(&z_u2);
+    let x1 = if tv1.is_zero() {
+        b.mul(&z.mul(&a).inverse())
+    } else {
+        b.neg().mul(&a.inverse()).mul(&Fe::ONE.add(&tv1.inverse()))
+    };
+    let (x, mut y) = match g(&x1).sqrt() {
+        Some(y1) => (x1, y1),
+        None => {
+            let x2 = z_u2.mul(&x1);
+            let y2 = g(&x2).sqrt().expect("either g(x1) or g(x2) is a square");
+            (x2, y2)
+        }
+    };
+    if u.sgn0() != y.sgn0() {
+        y = y.neg();
+    }
+    (x, y)
+}
+
+/// 3-isogeny map from E' to secp256k1 (appendix E.1)
+fn iso_map(x: &Fe, y: &Fe) -> Option<(Fe, Fe)> {
+    let x_den = poly(&X_DEN, x);
+    let y_den = poly(&Y_DEN, x);
+    if x_den.is_zero() || y_den.is_zero() {
+        return None;
+    }
+    let x_out = poly(&X_NUM, x).mul(&x_den.inverse());
+    let y_out = y.mul(&poly(&Y_NUM, x)).mul(&y_den.inverse());
+    Some((x_out, y_out))
+}
+
+/// Affine addition on secp256k1, `None` being the point at infinity
+fn add_points(p: Option<(Fe, Fe)>, q: Option<(Fe, Fe)>) -> Option<(Fe, Fe)> {
+    let (x1, y1) = match p {
+        None => return q,
+        Some(p) => p,
+    };
+    let (x2, y2) = match q {
+        None => return p,
+        Some(q) => q,
+    };
+    let lambda = if x1 == x2 {
+        if y1 != y2 || y1.is_zero() {
+            return None;
+        }
+        let three_x2 = Fe::from_u64(3).mul(&x1.square());
+        three_x2.mul(&y1.add(&y1).inverse())
+    } else {
+        y2.sub(&y1).mul(&x2.sub(&x1).inverse())
+    };
+    let x3 = lambda.square().sub(&x1).sub(&x2);
+    let y3 = lambda.mul(&x1.sub(&x3)).sub(&y1);
+    Some((x3, y3))
+}
+
+/// Hash `msg` to an affine point of secp256k1, returned as big endian
+/// `(x, y)` coordinates, or `None` for the point at infinity.
+///
+/// Panics if `dst` is longer than 255 bytes.
+pub(super) fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Option<([u8; 32], [u8; 32])> {
+    // hash_to_field with m = 1, L = 48 and count = 2
+    let uniform_bytes =
+        expand_message_xmd(Sha256::new(), msg, dst, 96).expect("domain separation tag too long");
+    let q0 = {
+        let (x, y) = map_to_iso_curve(&Fe::from_be_bytes_wide(&uniform_bytes[0..48]));
+        iso_map(&x, &y)
+    };
+    let q1 = {
+        let (x, y) = map_to_iso_curve(&Fe::from_be_bytes_wide(&uniform_bytes[48..96]));
+        iso_map(&x, &y)
+    };
+    // secp256k1 has cofactor 1, clearing it is a no-op
+    add_points(q0, q1).map(|(x, y)| (x.to_be_bytes(), y.to_be_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn field_arithmetic() {
+        let a = Fe::from_hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        assert_eq!(a.mul(&a.inverse()), Fe::ONE);
+        assert_eq!(a.sub(&a), Fe::ZERO);
+        assert_eq!(a.add(&a.neg()), Fe::ZERO);
+        let s = a.square();
+        let r = s.sqrt().unwrap();
+        assert!(r == a || r == a.neg());
+    }
+
+    // test vectors from RFC 9380 appendix J.8.1
+    #[test]
+    fn rfc9380_test_vectors() {
+        let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+        let vectors: [(&[u8], &str, &str); 2] = [
+            (
+                b"",
+                "c1cae290e291aee617ebaef1be6d73861479c48b841eaba9b7b5852ddfeb1346",
+                "64fa678e07ae116126f08b022a94af6de15985c996c3a91b64c406a960e51067",
+            ),
+            (
+                b"abc",
+                "3377e01eab42db296b512293120c6cee72b6ecf9f9205760bd9ff11fb3cb2c4b",
+                "7f95890f33efebd1044d382a01b1bee0900fb6116f94688d487c6c7b9c8371f6",
+            ),
+        ];
+        for (msg, x, y) in vectors.iter() {
+            let (px, py) = hash_to_curve(msg, dst).unwrap();
+            assert_eq!(hex(&px), *x);
+            assert_eq!(hex(&py), *y);
+        }
+    }
+}
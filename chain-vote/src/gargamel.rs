@@ -1,8 +1,9 @@
 #![allow(dead_code)]
 
-use crate::gang::{GroupElement, Scalar};
+use crate::gang::{FixedBaseTable, GroupElement, Scalar};
 use rand_core::{CryptoRng, RngCore};
-use std::ops::{Add, Mul};
+use std::io::{self, Write};
+use std::ops::{Add, Mul, Sub};
 
 // ElGamal Ciphertext
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -21,6 +22,25 @@ pub struct Keypair {
     pub public_key: PublicKey,
 }
 
+/// Public key with precomputed tables for the two fixed bases of the
+/// encryption, the generator and the key itself, making encryptions faster
+/// when many of them are done with the same key.
+#[derive(Debug, Clone)]
+pub struct PrecomputedPublicKey {
+    public_key: PublicKey,
+    generator_table: FixedBaseTable,
+    key_table: FixedBaseTable,
+}
+
+/// Encryption with an ElGamal public key
+pub trait Encryptor {
+    fn public_key(&self) -> &PublicKey;
+
+    fn encrypt_point(&self, n: &GroupElement, r: &Scalar) -> Ciphertext;
+
+    fn encrypt(&self, m: &Scalar, r: &Scalar) -> Ciphertext;
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Ciphertext {
     e1: GroupElement,
@@ -41,6 +61,63 @@ impl PublicKey {
     }
 }
 
+impl PrecomputedPublicKey {
+    /// Precompute the tables with the default window
+    pub fn new(public_key: PublicKey) -> Self {
+        let generator_table = FixedBaseTable::generate(&GroupElement::generator());
+        let key_table = FixedBaseTable::generate(&public_key.pk);
+        Self {
+            public_key,
+            generator_table,
+            key_table,
+        }
+    }
+
+    /// Precompute the tables with a window of the given number of bits,
+    /// see [`FixedBaseTable`](crate::gang::FixedBaseTable) for the tradeoffs
+    pub fn with_window(public_key: PublicKey, window: usize) -> Self {
+        let generator_table =
+            FixedBaseTable::generate_with_window(&GroupElement::generator(), window);
+        let key_table = FixedBaseTable::generate_with_window(&public_key.pk, window);
+        Self {
+            public_key,
+            generator_table,
+            key_table,
+        }
+    }
+}
+
+impl Encryptor for PublicKey {
+    fn public_key(&self) -> &PublicKey {
+        self
+    }
+
+    fn encrypt_point(&self, n: &GroupElement, r: &Scalar) -> Ciphertext {
+        encrypt_point(self, n, r)
+    }
+
+    fn encrypt(&self, m: &Scalar, r: &Scalar) -> Ciphertext {
+        encrypt(self, m, r)
+    }
+}
+
+impl Encryptor for PrecomputedPublicKey {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    fn encrypt_point(&self, n: &GroupElement, r: &Scalar) -> Ciphertext {
+        Ciphertext {
+            e1: self.generator_table.mul(r),
+            e2: n + &self.key_table.mul(r),
+        }
+    }
+
+    fn encrypt(&self, m: &Scalar, r: &Scalar) -> Ciphertext {
+        self.encrypt_point(&self.generator_table.mul(m), r)
+    }
+}
+
 impl SecretKey {
     pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
         let sk = Scalar::random(rng);
@@ -85,6 +162,13 @@ impl Ciphertext {
         r
     }
 
+    /// Write the byte representation, same as `to_bytes` without the
+    /// intermediate allocation
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.e1.to_bytes().as_ref())?;
+        writer.write_all(self.e2.to_bytes().as_ref())
+    }
+
     pub fn from_bytes(slice: &[u8]) -> Option<Ciphertext> {
         let e1 = GroupElement::from_bytes(&slice[..GroupElement::BYTES_LEN])?;
         let e2 = GroupElement::from_bytes(&slice[GroupElement::BYTES_LEN..])?;
@@ -162,6 +246,17 @@ impl<'a, 'b> Add<&'b Ciphertext> for &'a Ciphertext {
     }
 }
 
+impl<'a, 'b> Sub<&'b Ciphertext> for &'a Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, other: &'b Ciphertext) -> Ciphertext {
+        Ciphertext {
+            e1: &self.e1 - &other.e1,
+            e2: &self.e2 - &other.e2,
+        }
+    }
+}
+
 impl<'a> Mul<Scalar> for &'a Ciphertext {
     type Output = Ciphertext;
     fn mul(self, rhs: Scalar) -> Self::Output {
@@ -245,6 +340,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn precomputed_encryption() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let keypair = generate(&mut rng);
+        let precomputed = PrecomputedPublicKey::new(keypair.public_key.clone());
+
+        for n in 0..5 {
+            let m = Scalar::from_u64(n * 24);
+            let r = Scalar::random(&mut rng);
+            assert_eq!(
+                precomputed.encrypt(&m, &r),
+                encrypt(&keypair.public_key, &m, &r)
+            );
+        }
+    }
+
     #[test]
     fn encrypt_decrypt() {
         let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
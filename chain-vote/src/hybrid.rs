@@ -5,7 +5,7 @@ use cryptoxide::chacha20::ChaCha20;
 use cryptoxide::digest::Digest;
 use rand_core::{CryptoRng, RngCore};
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HybridCiphertext {
     // ElGamal Ciphertext
     e1: gargamel::Ciphertext,
@@ -13,6 +13,31 @@ pub struct HybridCiphertext {
     e2: Box<[u8]>,
 }
 
+impl HybridCiphertext {
+    /// Size of the byte representation of the ciphertext of a message of
+    /// the given length.
+    pub fn bytes_len(message_len: usize) -> usize {
+        gargamel::Ciphertext::BYTES_LEN + message_len
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut r = self.e1.to_bytes();
+        r.extend_from_slice(&self.e2);
+        r
+    }
+
+    pub fn from_bytes(slice: &[u8]) -> Option<Self> {
+        if slice.len() < gargamel::Ciphertext::BYTES_LEN {
+            return None;
+        }
+        let (e1, e2) = slice.split_at(gargamel::Ciphertext::BYTES_LEN);
+        Some(HybridCiphertext {
+            e1: gargamel::Ciphertext::from_bytes(e1)?,
+            e2: e2.into(),
+        })
+    }
+}
+
 /// The hybrid encryption scheme uses a group element as a
 /// representation of the symmetric key. This facilitates
 /// its exchange using ElGamal encryption.
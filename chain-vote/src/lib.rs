@@ -2,13 +2,20 @@
 
 mod commitment;
 pub mod committee;
+mod coordination;
+pub mod encoding;
 mod encrypted;
 mod gang;
 pub mod gargamel;
 mod hybrid;
 mod math;
+mod parallel;
+mod receipt;
 pub mod shvzk;
+pub mod single;
+mod transcript;
 mod unit_vector;
+mod weighted;
 
 // re-export under a debug module
 #[doc(hidden)]
@@ -22,14 +29,25 @@ pub mod debug {
 }
 
 pub use committee::{
-    MemberCommunicationKey, MemberCommunicationPublicKey, MemberPublicKey, MemberState,
+    crs_from_seed, MemberCommunicationKey, MemberCommunicationPublicKey, MemberPublicKey,
+    MemberState, PrecomputedElectionPublicKey,
 };
+pub use coordination::{
+    CoordinationError, ElectionId, EncryptedShares, MessageHeader, MessageKind, MessageVerifier,
+    Payload as CoordinationPayload, Signed,
+};
+pub use encoding::{ArtifactKind, CanonicalEncoding, EncodingError};
 pub use encrypted::EncryptingVote;
 use gang::GroupElement;
-pub use gang::{BabyStepsTable as TallyOptimizationTable, Scalar};
+pub use gang::{BabyStepsTable as TallyOptimizationTable, HashToCurveVersion, Scalar};
 pub use gargamel::Ciphertext;
 use rand_core::{CryptoRng, RngCore};
+pub use receipt::BallotReceipt;
+pub use single::{SingleMemberDecryptShare, SingleMemberElectionKey, SingleMemberKey};
+use std::io::{self, Write};
+pub use transcript::{Transcript, TranscriptBallot, TranscriptError};
 pub use unit_vector::UnitVector;
+pub use weighted::WeightedBallot;
 
 /// Secret key for opening vote
 pub type OpeningVoteKey = committee::MemberSecretKey;
@@ -67,6 +85,18 @@ pub fn encrypt_vote<R: RngCore + CryptoRng>(
     (ev.ciphertexts, proof)
 }
 
+/// Same as `encrypt_vote`, using the precomputed tables of the key
+pub fn encrypt_vote_precomputed<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    crs: &CRS,
+    public_key: &PrecomputedElectionPublicKey,
+    vote: Vote,
+) -> (EncryptedVote, ProofOfCorrectVote) {
+    let ev = EncryptingVote::prepare(rng, &public_key.0, &vote);
+    let proof = shvzk::prove(rng, &crs, &public_key.0, ev.clone());
+    (ev.ciphertexts, proof)
+}
+
 /// Verify that the encrypted vote is valid without opening it
 #[allow(clippy::ptr_arg)]
 pub fn verify_vote(
@@ -78,6 +108,87 @@ pub fn verify_vote(
     shvzk::verify(&crs, &public_key.0, vote, proof)
 }
 
+/// Verify a batch of encrypted votes, returning the validity of each vote
+/// in the order of the batch
+///
+/// The proofs are checked in parallel with the `parallel` feature.
+pub fn verify_votes(
+    crs: &CRS,
+    public_key: &EncryptingVoteKey,
+    votes: &[(EncryptedVote, ProofOfCorrectVote)],
+) -> Vec<bool> {
+    parallel::map(votes, |(vote, proof)| {
+        shvzk::verify(crs, &public_key.0, vote, proof)
+    })
+}
+
+/// Same as `encrypt_vote`, also returning the receipt the voter keeps to
+/// later check that the ballot has been published on the bulletin board
+pub fn encrypt_vote_with_receipt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    crs: &CRS,
+    public_key: &EncryptingVoteKey,
+    vote: Vote,
+) -> (EncryptedVote, ProofOfCorrectVote, BallotReceipt) {
+    let (vote, proof) = encrypt_vote(rng, crs, public_key, vote);
+    let receipt = BallotReceipt::compute(&vote, &proof);
+    (vote, proof, receipt)
+}
+
+/// Verify that a ballot published on the bulletin board matches a receipt
+#[allow(clippy::ptr_arg)]
+pub fn verify_vote_receipt(
+    receipt: &BallotReceipt,
+    vote: &EncryptedVote,
+    proof: &ProofOfCorrectVote,
+) -> bool {
+    receipt.verify(vote, proof)
+}
+
+/// Take a vote and encrypt it weighted by the stake of the voter, which
+/// must not be zero
+pub fn encrypt_weighted_vote<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    crs: &CRS,
+    public_key: &EncryptingVoteKey,
+    vote: Vote,
+    weight: u64,
+) -> WeightedBallot {
+    WeightedBallot::prepare(rng, crs, &public_key.0, vote, weight)
+}
+
+/// Same as `encrypt_weighted_vote`, using the precomputed tables of the key
+pub fn encrypt_weighted_vote_precomputed<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    crs: &CRS,
+    public_key: &PrecomputedElectionPublicKey,
+    vote: Vote,
+    weight: u64,
+) -> WeightedBallot {
+    WeightedBallot::prepare(rng, crs, &public_key.0, vote, weight)
+}
+
+/// Verify that the weighted ballot is valid for its weight without opening it
+pub fn verify_weighted_vote(
+    crs: &CRS,
+    public_key: &EncryptingVoteKey,
+    ballot: &WeightedBallot,
+) -> bool {
+    ballot.verify(crs, public_key)
+}
+
+/// Verify a batch of weighted ballots, returning the validity of each
+/// ballot in the order of the batch
+///
+/// The proofs are checked in parallel with the `parallel` feature.
+pub fn verify_weighted_votes(
+    crs: &CRS,
+    public_key: &EncryptingVoteKey,
+    ballots: &[WeightedBallot],
+) -> Vec<bool> {
+    parallel::map(ballots, |ballot| ballot.verify(crs, public_key))
+}
+
 /// The encrypted tally
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EncryptedTally {
@@ -123,6 +234,89 @@ impl EncryptedTally {
         }
     }
 
+    /// Add a weighted ballot to the tally, its weight being already part of
+    /// the ciphertexts
+    ///
+    /// Same as `add`, an assert will trigger if the number of options differs
+    pub fn add_weighted(&mut self, ballot: &WeightedBallot) {
+        self.add(ballot.ciphertexts(), 1);
+    }
+
+    /// Remove an encrypted vote added before with the same weight, by
+    /// homomorphic subtraction
+    ///
+    /// Same as `add`, an assert will trigger if the number of options differs.
+    /// The tally can only be trusted if the vote was actually added to it:
+    /// keeping track of the ballots is left to the caller.
+    #[allow(clippy::ptr_arg)]
+    pub fn remove(&mut self, vote: &EncryptedVote, weight: u64) {
+        assert_eq!(vote.len(), self.r.len());
+        for (ri, ci) in self.r.iter_mut().zip(vote.iter()) {
+            *ri = &*ri - &(ci * weight);
+        }
+    }
+
+    /// Replace the earlier ballot of a voter by their latest one, so that a
+    /// voter can correct their vote until the end of the voting period
+    ///
+    /// The tally ends up the same as if only the latest ballot had been added.
+    /// The earlier ballot and its weight are the ones that were added to the
+    /// tally, as recorded by the ledger for the voter.
+    #[allow(clippy::ptr_arg)]
+    pub fn replace(
+        &mut self,
+        previous: &EncryptedVote,
+        previous_weight: u64,
+        vote: &EncryptedVote,
+        weight: u64,
+    ) {
+        self.remove(previous, previous_weight);
+        self.add(vote, weight);
+    }
+
+    /// Same as `replace`, for weighted ballots
+    pub fn replace_weighted(&mut self, previous: &WeightedBallot, ballot: &WeightedBallot) {
+        self.replace(previous.ciphertexts(), 1, ballot.ciphertexts(), 1);
+    }
+
+    /// Add a batch of encrypted votes with their weights to the tally
+    ///
+    /// The result is the same as adding the votes one by one, the batch is
+    /// aggregated in parallel with the `parallel` feature. Same as `add`, an
+    /// assert will trigger if the number of options differs.
+    pub fn add_batch(&mut self, votes: &[(EncryptedVote, u64)]) {
+        let options = self.r.len();
+        let batch = parallel::fold_reduce(
+            votes,
+            || EncryptedTally::new(options),
+            |mut tally, (vote, weight)| {
+                tally.add(vote, *weight);
+                tally
+            },
+            |left, right| left + right,
+        );
+        for (ri, bi) in self.r.iter_mut().zip(batch.r.iter()) {
+            *ri = &*ri + bi;
+        }
+    }
+
+    /// Same as `add_batch`, for weighted ballots
+    pub fn add_weighted_batch(&mut self, ballots: &[WeightedBallot]) {
+        let options = self.r.len();
+        let batch = parallel::fold_reduce(
+            ballots,
+            || EncryptedTally::new(options),
+            |mut tally, ballot| {
+                tally.add_weighted(ballot);
+                tally
+            },
+            |left, right| left + right,
+        );
+        for (ri, bi) in self.r.iter_mut().zip(batch.r.iter()) {
+            *ri = &*ri + bi;
+        }
+    }
+
     pub fn finish(&self, secret_key: &OpeningVoteKey) -> (TallyState, TallyDecryptShare) {
         let mut dshares = Vec::with_capacity(self.r.len());
         let mut r2s = Vec::with_capacity(self.r.len());
@@ -193,6 +387,20 @@ impl TallyDecryptShare {
         group_elements_to_bytes(&self.r1s)
     }
 
+    /// Length of `to_bytes`, the same as `bytes_len(self.options())`
+    pub fn serialized_len(&self) -> usize {
+        Self::bytes_len(self.options())
+    }
+
+    /// Write the same bytes as `to_bytes`, without the intermediate
+    /// allocation
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for r1 in &self.r1s {
+            writer.write_all(r1.to_bytes().as_ref())?;
+        }
+        Ok(())
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         group_elements_from_bytes(bytes).map(|r1s| Self { r1s })
     }
@@ -221,7 +429,6 @@ fn group_elements_bytes_len(n: usize) -> usize {
 }
 
 fn group_elements_to_bytes(elements: &[gang::GroupElement]) -> Vec<u8> {
-    use std::io::Write;
     let mut bytes: Vec<u8> = Vec::with_capacity(group_elements_bytes_len(elements.len()));
     for element in elements {
         bytes.write_all(element.to_bytes().as_ref()).unwrap();
@@ -245,17 +452,11 @@ fn result_vector(
     tally_state: &TallyState,
     decrypt_shares: &[TallyDecryptShare],
 ) -> Vec<gang::GroupElement> {
-    let ris = (0..tally_state.r2s.len())
-        .map(|i| gang::GroupElement::sum(decrypt_shares.iter().map(|ds| &ds.r1s[i])));
-
-    let results = tally_state
-        .r2s
-        .iter()
-        .zip(ris)
-        .map(|(r2, r1)| r2 - r1)
-        .collect::<Vec<_>>();
-
-    results
+    let options = (0..tally_state.r2s.len()).collect::<Vec<_>>();
+    parallel::map(&options, |&i| {
+        let r1 = gang::GroupElement::sum(decrypt_shares.iter().map(|ds| &ds.r1s[i]));
+        &tally_state.r2s[i] - &r1
+    })
 }
 
 pub fn tally(
@@ -344,6 +545,146 @@ mod tests {
         assert!(tr.verify(&ts, &shares));
     }
 
+    #[test]
+    fn replaced_ballot_is_not_counted() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = CRS::from_hash(&[0u8; 32]);
+        let mc = [MemberCommunicationKey::new(&mut rng).to_public()];
+        let m1 = MemberState::new(&mut rng, 1, &h, &mc, 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+
+        let vote_options = 3;
+        let first = encrypt_vote(&mut rng, &h, &ek, Vote::new(vote_options, 0));
+        let other = encrypt_vote(&mut rng, &h, &ek, Vote::new(vote_options, 1));
+        let latest = encrypt_vote(&mut rng, &h, &ek, Vote::new(vote_options, 2));
+
+        let mut tally = EncryptedTally::new(vote_options);
+        tally.add(&first.0, 3);
+        tally.add(&other.0, 5);
+        tally.replace(&first.0, 3, &latest.0, 4);
+
+        let weighted_first =
+            encrypt_weighted_vote(&mut rng, &h, &ek, Vote::new(vote_options, 1), 2);
+        let weighted_latest =
+            encrypt_weighted_vote(&mut rng, &h, &ek, Vote::new(vote_options, 0), 2);
+        tally.add_weighted(&weighted_first);
+        tally.replace_weighted(&weighted_first, &weighted_latest);
+
+        let (ts, tds1) = tally.finish(m1.secret_key());
+        let shares = vec![tds1];
+        let table = TallyOptimizationTable::generate_with_balance(20, 1);
+        let tr = crate::tally(20, &ts, &shares, &table).unwrap();
+        assert_eq!(tr.votes, vec![2, 5, 4]);
+        assert!(tr.verify(&ts, &shares));
+
+        let mut emptied = EncryptedTally::new(vote_options);
+        emptied.add(&other.0, 5);
+        emptied.remove(&other.0, 5);
+        assert_eq!(emptied, EncryptedTally::new(vote_options));
+    }
+
+    #[test]
+    fn batch_tally_and_verification() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = CRS::from_hash(&[0u8; 32]);
+        let mc = [MemberCommunicationKey::new(&mut rng).to_public()];
+        let m1 = MemberState::new(&mut rng, 1, &h, &mc, 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+
+        let vote_options = 3;
+        let mut votes: Vec<_> = (0..7)
+            .map(|i| encrypt_vote(&mut rng, &h, &ek, Vote::new(vote_options, i % vote_options)))
+            .collect();
+
+        let mut sequential = EncryptedTally::new(vote_options);
+        for (weight, (vote, _proof)) in votes.iter().enumerate() {
+            sequential.add(vote, weight as u64);
+        }
+        let weighted: Vec<_> = votes
+            .iter()
+            .enumerate()
+            .map(|(weight, (vote, _proof))| (vote.clone(), weight as u64))
+            .collect();
+        let mut batch = EncryptedTally::new(vote_options);
+        batch.add_batch(&weighted[..3]);
+        batch.add_batch(&weighted[3..]);
+        assert_eq!(batch, sequential);
+
+        // swap the proofs of two ballots for different options
+        let proof = votes[0].1.clone();
+        votes[0].1 = votes[1].1.clone();
+        votes[1].1 = proof;
+        let valid = verify_votes(&h, &ek, &votes);
+        assert_eq!(valid, vec![false, false, true, true, true, true, true]);
+    }
+
+    #[test]
+    fn precomputed_encryption() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let h = CRS::from_hash(&[0u8; 32]);
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let mc = [mc1.to_public()];
+        let m1 = MemberState::new(&mut rng, 1, &h, &mc, 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+        let precomputed = ek.precompute_with_window(6);
+
+        let vote_options = 3;
+        let mut tally = EncryptedTally::new(vote_options);
+        for option in 0..vote_options {
+            let (vote, proof) = encrypt_vote_precomputed(
+                &mut rng,
+                &h,
+                &precomputed,
+                Vote::new(vote_options, option),
+            );
+            assert!(verify_vote(&h, &ek, &vote, &proof));
+            tally.add(&vote, option as u64 + 1);
+        }
+
+        let (ts, tds1) = tally.finish(m1.secret_key());
+        let shares = vec![tds1];
+        let table = TallyOptimizationTable::generate(10);
+        let tr = crate::tally(10, &ts, &shares, &table).unwrap();
+        assert_eq!(tr.votes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn ballot_receipt() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let mut shared_string =
+            b"Example of a shared string. This should be VotePlan.to_id()".to_owned();
+        let h = CRS::from_hash(&mut shared_string);
+
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let mc = [mc1.to_public()];
+        let m1 = MemberState::new(&mut rng, 1, &h, &mc, 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+
+        let vote_options = 3;
+        let (e1, e1_proof, receipt) =
+            encrypt_vote_with_receipt(&mut rng, &h, &ek, Vote::new(vote_options, 1));
+        let (e2, e2_proof, other_receipt) =
+            encrypt_vote_with_receipt(&mut rng, &h, &ek, Vote::new(vote_options, 1));
+
+        assert!(verify_vote(&h, &ek, &e1, &e1_proof));
+        assert!(verify_vote_receipt(&receipt, &e1, &e1_proof));
+        assert_ne!(receipt, other_receipt);
+
+        // another ballot, or the same ciphertexts with another proof
+        assert!(!verify_vote_receipt(&receipt, &e2, &e2_proof));
+        assert!(!verify_vote_receipt(&receipt, &e1, &e2_proof));
+
+        let mut tampered = e1.clone();
+        tampered.swap(0, 1);
+        assert!(!verify_vote_receipt(&receipt, &tampered, &e1_proof));
+
+        let bytes = receipt.to_bytes();
+        assert_eq!(BallotReceipt::from_bytes(&bytes), Some(receipt));
+        assert_eq!(BallotReceipt::from_bytes(&bytes[1..]), None);
+    }
+
     #[test]
     fn encdec3() {
         let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
@@ -0,0 +1,51 @@
+//! Loops over the ballots and the tally options, run on the rayon thread
+//! pool when the `parallel` feature is enabled and sequentially otherwise.
+//!
+//! The results are the same in both cases: the items are processed in an
+//! unspecified order, but the outputs are returned in the order of the
+//! inputs and the reduction operations are associative and commutative.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Apply `f` to every item, the results are in the order of the items
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Fold the items into accumulators starting from `identity`, then merge
+/// the accumulators with `reduce`, which has to be associative and
+/// commutative
+pub(crate) fn fold_reduce<T, A, I, F, R>(items: &[T], identity: I, fold: F, reduce: R) -> A
+where
+    T: Sync,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(A, &T) -> A + Sync + Send,
+    R: Fn(A, A) -> A + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items
+            .par_iter()
+            .fold(&identity, fold)
+            .reduce(&identity, reduce)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let _ = reduce;
+        items.iter().fold(identity(), fold)
+    }
+}
@@ -0,0 +1,70 @@
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::digest::Digest;
+
+use crate::{EncryptedVote, ProofOfCorrectVote};
+
+const DOMAIN: &[u8] = b"chain-vote ballot receipt";
+
+/// Short commitment to an encrypted ballot and its proof of correctness.
+///
+/// The voter keeps the receipt obtained when encrypting their ballot, and can
+/// later check that the ballot published on the bulletin board is the one they
+/// cast, without having to keep (or compare) the whole ciphertext and proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BallotReceipt([u8; Self::BYTES_LEN]);
+
+impl BallotReceipt {
+    pub const BYTES_LEN: usize = 32;
+
+    /// Compute the receipt of an encrypted ballot, the Blake2b-256 hash of
+    /// the ciphertexts followed by every element of the proof.
+    #[allow(clippy::ptr_arg)]
+    pub fn compute(vote: &EncryptedVote, proof: &ProofOfCorrectVote) -> Self {
+        let mut ctx = Blake2b::new(Self::BYTES_LEN);
+        ctx.input(DOMAIN);
+        ctx.input(&(vote.len() as u64).to_be_bytes());
+        for c in vote {
+            ctx.input(&c.to_bytes());
+        }
+        ctx.input(&(proof.len() as u64).to_be_bytes());
+        for iba in proof.ibas() {
+            ctx.input(&iba.to_bytes());
+        }
+        for d in proof.ds() {
+            ctx.input(&d.to_bytes());
+        }
+        for zwv in proof.zwvs() {
+            ctx.input(&zwv.to_bytes());
+        }
+        ctx.input(&proof.r().to_bytes());
+
+        let mut receipt = [0u8; Self::BYTES_LEN];
+        ctx.result(&mut receipt);
+        BallotReceipt(receipt)
+    }
+
+    /// Check that a published ballot is the one this receipt was issued for
+    #[allow(clippy::ptr_arg)]
+    pub fn verify(&self, vote: &EncryptedVote, proof: &ProofOfCorrectVote) -> bool {
+        Self::compute(vote, proof) == *self
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::BYTES_LEN] {
+        self.0
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES_LEN {
+            return None;
+        }
+        let mut receipt = [0u8; Self::BYTES_LEN];
+        receipt.copy_from_slice(bytes);
+        Some(BallotReceipt(receipt))
+    }
+}
+
+impl AsRef<[u8]> for BallotReceipt {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -5,10 +5,12 @@ use rand_core::{CryptoRng, RngCore};
 use crate::commitment::{Commitment, CommitmentKey};
 use crate::encrypted::{EncryptingVote, PTP};
 use crate::gang::Scalar;
-use crate::gargamel::{encrypt, Ciphertext, PublicKey};
+use crate::gargamel::{encrypt, Ciphertext, Encryptor, PublicKey};
 use crate::math::Polynomial;
 use crate::unit_vector::binrep;
 use crate::CRS;
+use std::convert::TryFrom;
+use std::io::{self, Write};
 
 struct ABCD {
     alpha: Scalar,
@@ -79,6 +81,13 @@ impl IBA {
         debug_assert_eq!(buf.len(), Self::BYTES_LEN);
         buf
     }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for component in [&self.i, &self.b, &self.a].iter() {
+            writer.write_all(&component.to_bytes())?;
+        }
+        Ok(())
+    }
 }
 
 impl ZWV {
@@ -103,6 +112,13 @@ impl ZWV {
         debug_assert_eq!(buf.len(), Self::BYTES_LEN);
         buf
     }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for component in [&self.z, &self.w, &self.v].iter() {
+            writer.write_all(&component.to_bytes())?;
+        }
+        Ok(())
+    }
 }
 
 #[allow(clippy::len_without_is_empty)]
@@ -137,6 +153,34 @@ impl Proof {
     pub fn r(&self) -> &Scalar {
         &self.r
     }
+
+    /// Length of the serialization of the proof: its length as a byte,
+    /// then the announcements, the ciphertexts, the responses and the
+    /// final scalar
+    pub fn serialized_len(&self) -> usize {
+        1 + self.len() * (IBA::BYTES_LEN + Ciphertext::BYTES_LEN + ZWV::BYTES_LEN)
+            + Scalar::BYTES_LEN
+    }
+
+    /// Write the serialization of the proof, of `serialized_len` bytes
+    ///
+    /// # Panics
+    ///
+    /// The length of the proof must fit in a byte.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let len = u8::try_from(self.len()).expect("proof length fits in a byte");
+        writer.write_all(&[len])?;
+        for iba in self.ibas() {
+            iba.write_to(&mut writer)?;
+        }
+        for d in self.ds() {
+            d.write_to(&mut writer)?;
+        }
+        for zwv in self.zwvs() {
+            zwv.write_to(&mut writer)?;
+        }
+        writer.write_all(&self.r.to_bytes())
+    }
 }
 
 impl IBA {
@@ -195,10 +239,10 @@ impl ChallengeContext {
     }
 }
 
-pub(crate) fn prove<R: RngCore + CryptoRng>(
+pub(crate) fn prove<R: RngCore + CryptoRng, E: Encryptor>(
     rng: &mut R,
     crs: &CRS,
-    public_key: &PublicKey,
+    public_key: &E,
     encrypting_vote: EncryptingVote,
 ) -> Proof {
     let ck = CommitmentKey { h: crs.clone() };
@@ -227,7 +271,7 @@ pub(crate) fn prove<R: RngCore + CryptoRng>(
     debug_assert_eq!(ibas.len(), bits);
 
     // Generate First verifier challenge
-    let cc = ChallengeContext::new(public_key, ciphers.as_ref(), &ibas);
+    let cc = ChallengeContext::new(public_key.public_key(), ciphers.as_ref(), &ibas);
     let cy = cc.first_challenge();
 
     let (ds, rs) = {
@@ -282,7 +326,7 @@ pub(crate) fn prove<R: RngCore + CryptoRng>(
                     sum = sum + (cy.power(j) * pjs[j].get_coefficient_at(i))
                 }
 
-                encrypt(public_key, &sum, r)
+                public_key.encrypt(&sum, r)
             })
             .collect::<Vec<_>>();
 
@@ -441,4 +485,26 @@ mod tests {
         let proof = prove(&mut r, &crs, &public_key, ev.clone());
         assert!(verify(&crs, &public_key, &ev.ciphertexts, &proof))
     }
+
+    #[test]
+    fn write_to_matches_the_parts() {
+        let mut r = ChaCha20Rng::from_seed([0u8; 32]);
+        let public_key = gargamel::generate(&mut r).public_key;
+        let unit_vector = UnitVector::new(5, 1);
+        let ev = EncryptingVote::prepare(&mut r, &public_key, &unit_vector);
+        let crs = CRS::from_hash(&[0u8; 32]);
+        let proof = prove(&mut r, &crs, &public_key, ev);
+
+        let mut bytes = Vec::new();
+        proof.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), proof.serialized_len());
+        assert_eq!(bytes[0] as usize, proof.len());
+
+        let mut parts = vec![proof.len() as u8];
+        proof.ibas().for_each(|iba| parts.extend(iba.to_bytes()));
+        proof.ds().for_each(|d| parts.extend(d.to_bytes()));
+        proof.zwvs().for_each(|zwv| parts.extend(zwv.to_bytes()));
+        parts.extend_from_slice(&proof.r().to_bytes());
+        assert_eq!(bytes, parts);
+    }
 }
@@ -0,0 +1,157 @@
+//! Committee of a single trusted member, for test networks and small
+//! deployments which do not run the distributed key generation.
+//!
+//! The election key is the public key of the only member (t = n = 1), so the
+//! ballots are encrypted and verified as with any committee, and the member
+//! decrypts the tally alone. The keys and decrypt shares of this mode have
+//! their own types, so that they cannot be mixed up with the ones of a
+//! threshold committee by accident: converting them to the general types is
+//! always explicit.
+//!
+//! # Warning
+//!
+//! The member can decrypt every ballot on its own and nobody can tally the
+//! election without it. The tooling using this mode should show [`WARNING`]
+//! to its users.
+
+use crate::{
+    committee::{ElectionPublicKey, MemberPublicKey, MemberSecretKey},
+    gang::GroupElement,
+    gargamel::{PublicKey, SecretKey},
+    EncryptedTally, Tally, TallyDecryptShare, TallyError, TallyOptimizationTable, TallyState,
+};
+use rand_core::{CryptoRng, RngCore};
+
+/// Warning to show to the users of an election with a single member committee
+pub const WARNING: &str = "the election is tallied by a single trusted member, \
+    who can decrypt every ballot: only use it for test networks and small deployments";
+
+/// Secret key of the only member of the committee
+#[derive(Clone)]
+pub struct SingleMemberKey(MemberSecretKey);
+
+/// Election public key of a single member committee
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingleMemberElectionKey(PublicKey);
+
+/// Decrypt share of the only member of the committee, which is enough to
+/// decrypt the tally
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingleMemberDecryptShare(TallyDecryptShare);
+
+impl SingleMemberKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        SingleMemberKey(MemberSecretKey(SecretKey::generate(rng)))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        MemberSecretKey::from_bytes(bytes).map(SingleMemberKey)
+    }
+
+    pub fn election_key(&self) -> SingleMemberElectionKey {
+        SingleMemberElectionKey(PublicKey {
+            pk: &GroupElement::generator() * &(self.0).0.sk,
+        })
+    }
+
+    /// Same as `EncryptedTally::finish` for the only member
+    pub fn finish(&self, tally: &EncryptedTally) -> (TallyState, SingleMemberDecryptShare) {
+        let (state, share) = tally.finish(&self.0);
+        (state, SingleMemberDecryptShare(share))
+    }
+
+    /// Decrypt the tally in one step, up to `max_votes` per option
+    pub fn decrypt_tally(
+        &self,
+        tally: &EncryptedTally,
+        max_votes: u64,
+        table: &TallyOptimizationTable,
+    ) -> Result<Tally, TallyError> {
+        let (state, share) = self.finish(tally);
+        share.tally(max_votes, &state, table)
+    }
+}
+
+impl SingleMemberElectionKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    pub fn from_bytes(buf: &[u8]) -> Option<Self> {
+        PublicKey::from_bytes(buf).map(SingleMemberElectionKey)
+    }
+
+    /// The public key of the member, as listed in the committee of a vote
+    /// plan
+    pub fn to_member_public_key(&self) -> MemberPublicKey {
+        MemberPublicKey(self.0.clone())
+    }
+
+    /// The key to encrypt and verify the ballots of the election with
+    pub fn to_election_public_key(&self) -> ElectionPublicKey {
+        ElectionPublicKey(self.0.clone())
+    }
+}
+
+impl SingleMemberDecryptShare {
+    pub fn tally(
+        &self,
+        max_votes: u64,
+        state: &TallyState,
+        table: &TallyOptimizationTable,
+    ) -> Result<Tally, TallyError> {
+        crate::tally(max_votes, state, std::slice::from_ref(&self.0), table)
+    }
+
+    /// The decrypt share as published with the tally of any committee
+    pub fn into_decrypt_share(self) -> TallyDecryptShare {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encrypt_vote, verify_vote, Vote, CRS};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn single_member_election() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = CRS::from_hash(&[0u8; 32]);
+        let key = SingleMemberKey::generate(&mut rng);
+        let election_key = key.election_key();
+        assert_eq!(
+            SingleMemberElectionKey::from_bytes(&election_key.to_bytes()),
+            Some(election_key.clone())
+        );
+        // the same as the election key of a committee made of the member
+        let ek = election_key.to_election_public_key();
+        assert_eq!(
+            ElectionPublicKey::from_participants(&[election_key.to_member_public_key()]).to_bytes(),
+            ek.to_bytes()
+        );
+
+        let vote_options = 3;
+        let mut tally = EncryptedTally::new(vote_options);
+        for (option, weight) in &[(0, 4), (2, 3), (0, 2)] {
+            let (vote, proof) = encrypt_vote(&mut rng, &h, &ek, Vote::new(vote_options, *option));
+            assert!(verify_vote(&h, &ek, &vote, &proof));
+            tally.add(&vote, *weight);
+        }
+
+        let max_votes = 10;
+        let table = TallyOptimizationTable::generate_with_balance(max_votes, 1);
+        let key = SingleMemberKey::from_bytes(&key.to_bytes()).unwrap();
+        let result = key.decrypt_tally(&tally, max_votes, &table).unwrap();
+        assert_eq!(result.votes, vec![6, 0, 3]);
+
+        let (state, share) = key.finish(&tally);
+        assert!(result.verify(&state, &[share.into_decrypt_share()]));
+    }
+}
@@ -0,0 +1,332 @@
+//! Election transcript, to audit a private election from a single file.
+//!
+//! A [`Transcript`] gathers all the public data of an election: the common
+//! reference string, the public keys of the committee members, every ballot
+//! with its proof and weight, the encrypted tally, the decryption shares of
+//! the committee and the final result. [`Transcript::verify`] replays all the
+//! checks, so that anyone holding the transcript can make sure the published
+//! result is the one of the ballots cast.
+
+use std::convert::TryFrom;
+
+use crate::encoding::Reader;
+use crate::gang::{GroupElement, Scalar};
+use crate::shvzk::{Proof, IBA, ZWV};
+use crate::{
+    verify_vote, Ciphertext, EncryptedTally, EncryptedVote, EncryptingVoteKey, MemberPublicKey,
+    ProofOfCorrectVote, Tally, TallyDecryptShare, CRS,
+};
+
+const VERSION: u8 = 1;
+
+/// A ballot of the election, as published on the bulletin board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptBallot {
+    pub vote: EncryptedVote,
+    pub proof: ProofOfCorrectVote,
+    /// voting power of the voter
+    pub weight: u64,
+}
+
+/// All the public data of an election
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transcript {
+    pub crs: CRS,
+    pub members: Vec<MemberPublicKey>,
+    pub options: usize,
+    pub ballots: Vec<TranscriptBallot>,
+    pub encrypted_tally: EncryptedTally,
+    /// decryption shares of the encrypted tally, one per committee member
+    pub decrypt_shares: Vec<TallyDecryptShare>,
+    pub result: Tally,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TranscriptError {
+    #[error("the election has no committee member")]
+    NoCommitteeMember,
+    #[error("ballot {index} does not have {expected} options")]
+    BallotOptionsMismatch { index: usize, expected: usize },
+    #[error("invalid proof for ballot {index}")]
+    InvalidBallotProof { index: usize },
+    #[error("the encrypted tally is not the sum of the ballots")]
+    EncryptedTallyMismatch,
+    #[error("expected {expected} decryption shares, found {actual}")]
+    DecryptSharesCountMismatch { expected: usize, actual: usize },
+    #[error("decryption share {index} does not have {expected} options")]
+    DecryptShareOptionsMismatch { index: usize, expected: usize },
+    #[error("the result does not have {expected} options")]
+    ResultOptionsMismatch { expected: usize },
+    #[error("the result does not match the decrypted tally")]
+    InvalidResult,
+}
+
+impl Transcript {
+    /// Replay all the verifications of the election: the proof of every
+    /// ballot, the encrypted tally against the ballots, and the result
+    /// against the encrypted tally and the decryption shares.
+    pub fn verify(&self) -> Result<(), TranscriptError> {
+        if self.members.is_empty() {
+            return Err(TranscriptError::NoCommitteeMember);
+        }
+        let election_key = EncryptingVoteKey::from_participants(&self.members);
+
+        let mut encrypted_tally = EncryptedTally::new(self.options);
+        for (index, ballot) in self.ballots.iter().enumerate() {
+            if ballot.vote.len() != self.options {
+                return Err(TranscriptError::BallotOptionsMismatch {
+                    index,
+                    expected: self.options,
+                });
+            }
+            if !verify_vote(&self.crs, &election_key, &ballot.vote, &ballot.proof) {
+                return Err(TranscriptError::InvalidBallotProof { index });
+            }
+            encrypted_tally.add(&ballot.vote, ballot.weight);
+        }
+        if encrypted_tally != self.encrypted_tally {
+            return Err(TranscriptError::EncryptedTallyMismatch);
+        }
+
+        if self.decrypt_shares.len() != self.members.len() {
+            return Err(TranscriptError::DecryptSharesCountMismatch {
+                expected: self.members.len(),
+                actual: self.decrypt_shares.len(),
+            });
+        }
+        for (index, share) in self.decrypt_shares.iter().enumerate() {
+            if share.options() != self.options {
+                return Err(TranscriptError::DecryptShareOptionsMismatch {
+                    index,
+                    expected: self.options,
+                });
+            }
+        }
+        if self.result.votes.len() != self.options {
+            return Err(TranscriptError::ResultOptionsMismatch {
+                expected: self.options,
+            });
+        }
+        if !self
+            .result
+            .verify(&encrypted_tally.state(), &self.decrypt_shares)
+        {
+            return Err(TranscriptError::InvalidResult);
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![VERSION];
+        bytes.extend_from_slice(self.crs.to_bytes().as_ref());
+        write_len(&mut bytes, self.members.len());
+        for member in &self.members {
+            bytes.extend_from_slice(&member.to_bytes());
+        }
+        write_len(&mut bytes, self.options);
+        write_len(&mut bytes, self.ballots.len());
+        for ballot in &self.ballots {
+            bytes.extend_from_slice(&ballot.weight.to_be_bytes());
+            for ciphertext in &ballot.vote {
+                bytes.extend_from_slice(&ciphertext.to_bytes());
+            }
+            write_proof(&mut bytes, &ballot.proof);
+        }
+        bytes.extend_from_slice(&self.encrypted_tally.to_bytes());
+        write_len(&mut bytes, self.decrypt_shares.len());
+        for share in &self.decrypt_shares {
+            bytes.extend_from_slice(&share.to_bytes());
+        }
+        for votes in &self.result.votes {
+            bytes.extend_from_slice(&votes.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(1)? != [VERSION] {
+            return None;
+        }
+        let crs = GroupElement::from_bytes(reader.take(GroupElement::BYTES_LEN)?)?;
+        let members = (0..reader.len()?)
+            .map(|_| MemberPublicKey::from_bytes(reader.take(MemberPublicKey::BYTES_LEN)?))
+            .collect::<Option<Vec<_>>>()?;
+        let options = reader.len()?;
+        let ballots = (0..reader.len()?)
+            .map(|_| {
+                let weight = reader.u64()?;
+                let vote = (0..options)
+                    .map(|_| Ciphertext::from_bytes(reader.take(Ciphertext::BYTES_LEN)?))
+                    .collect::<Option<Vec<_>>>()?;
+                let proof = read_proof(&mut reader)?;
+                Some(TranscriptBallot {
+                    vote,
+                    proof,
+                    weight,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let encrypted_tally =
+            EncryptedTally::from_bytes(reader.take(options.checked_mul(Ciphertext::BYTES_LEN)?)?)?;
+        let decrypt_shares = (0..reader.len()?)
+            .map(|_| {
+                TallyDecryptShare::from_bytes(reader.take(TallyDecryptShare::bytes_len(options))?)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let votes = (0..options)
+            .map(|_| reader.u64())
+            .collect::<Option<Vec<_>>>()?;
+        if !reader.is_empty() {
+            return None;
+        }
+        Some(Transcript {
+            crs,
+            members,
+            options,
+            ballots,
+            encrypted_tally,
+            decrypt_shares,
+            result: Tally { votes },
+        })
+    }
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    let len = u32::try_from(len).expect("transcript collections fit in 32 bits");
+    bytes.extend_from_slice(&len.to_be_bytes());
+}
+
+fn write_proof(bytes: &mut Vec<u8>, proof: &Proof) {
+    write_len(bytes, proof.len());
+    for iba in proof.ibas() {
+        bytes.extend_from_slice(&iba.to_bytes());
+    }
+    for d in proof.ds() {
+        bytes.extend_from_slice(&d.to_bytes());
+    }
+    for zwv in proof.zwvs() {
+        bytes.extend_from_slice(&zwv.to_bytes());
+    }
+    bytes.extend_from_slice(&proof.r().to_bytes());
+}
+
+fn read_proof(reader: &mut Reader) -> Option<Proof> {
+    let len = reader.len()?;
+    let ibas = (0..len)
+        .map(|_| IBA::from_bytes(reader.take(IBA::BYTES_LEN)?))
+        .collect::<Option<Vec<_>>>()?;
+    let ds = (0..len)
+        .map(|_| Ciphertext::from_bytes(reader.take(Ciphertext::BYTES_LEN)?))
+        .collect::<Option<Vec<_>>>()?;
+    let zwvs = (0..len)
+        .map(|_| ZWV::from_bytes(reader.take(ZWV::BYTES_LEN)?))
+        .collect::<Option<Vec<_>>>()?;
+    let r = Scalar::from_bytes(reader.take(Scalar::BYTES_LEN)?)?;
+    Some(Proof::from_parts(ibas, ds, zwvs, r))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        encrypt_vote, tally, MemberCommunicationKey, MemberState, TallyOptimizationTable, Vote,
+    };
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn election() -> Transcript {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let shared_string = b"Example of a shared string. This should be VotePlan.to_id()";
+        let crs = CRS::from_hash(shared_string);
+
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let mc2 = MemberCommunicationKey::new(&mut rng);
+        let mc = [mc1.to_public(), mc2.to_public()];
+        let m1 = MemberState::new(&mut rng, 2, &crs, &mc, 0);
+        let m2 = MemberState::new(&mut rng, 2, &crs, &mc, 1);
+        let members = vec![m1.public_key(), m2.public_key()];
+        let ek = EncryptingVoteKey::from_participants(&members);
+
+        let options = 3;
+        let ballots = [(0, 4), (2, 3), (0, 1)]
+            .iter()
+            .map(|&(choice, weight)| {
+                let (vote, proof) = encrypt_vote(&mut rng, &crs, &ek, Vote::new(options, choice));
+                TranscriptBallot {
+                    vote,
+                    proof,
+                    weight,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut encrypted_tally = EncryptedTally::new(options);
+        for ballot in &ballots {
+            encrypted_tally.add(&ballot.vote, ballot.weight);
+        }
+        let (_, share1) = encrypted_tally.finish(m1.secret_key());
+        let (state, share2) = encrypted_tally.finish(m2.secret_key());
+        let decrypt_shares = vec![share1, share2];
+        let table = TallyOptimizationTable::generate_with_balance(8, 1);
+        let result = tally(8, &state, &decrypt_shares, &table).unwrap();
+
+        Transcript {
+            crs,
+            members,
+            options,
+            ballots,
+            encrypted_tally,
+            decrypt_shares,
+            result,
+        }
+    }
+
+    #[test]
+    fn verify_and_serialize() {
+        let transcript = election();
+        assert_eq!(transcript.result.votes, vec![5, 0, 3]);
+        transcript.verify().unwrap();
+
+        let bytes = transcript.to_bytes();
+        let decoded = Transcript::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, transcript);
+        decoded.verify().unwrap();
+
+        assert!(Transcript::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn verify_detects_tampering() {
+        let transcript = election();
+
+        let mut tampered = transcript.clone();
+        tampered.ballots[1].weight += 1;
+        assert_eq!(
+            tampered.verify(),
+            Err(TranscriptError::EncryptedTallyMismatch)
+        );
+
+        let mut tampered = transcript.clone();
+        tampered.ballots[0].vote.swap(0, 1);
+        assert_eq!(
+            tampered.verify(),
+            Err(TranscriptError::InvalidBallotProof { index: 0 })
+        );
+
+        let mut tampered = transcript.clone();
+        tampered.decrypt_shares.pop();
+        assert_eq!(
+            tampered.verify(),
+            Err(TranscriptError::DecryptSharesCountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        let mut tampered = transcript;
+        tampered.result.votes = vec![3, 0, 5];
+        assert_eq!(tampered.verify(), Err(TranscriptError::InvalidResult));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    encrypted::EncryptingVote, gargamel::Encryptor, shvzk, Ciphertext, EncryptedVote,
+    EncryptingVoteKey, ProofOfCorrectVote, Scalar, Vote, CRS,
+};
+use rand_core::{CryptoRng, RngCore};
+
+/// An encrypted ballot carrying the stake of the voter.
+///
+/// Each ciphertext encrypts the weight for the chosen option and zero for the
+/// others, so the ballot can be added as is to a tally. The weight is public,
+/// and the proof of correct vote is made on the ciphertexts divided by the
+/// weight: it only verifies if they are the encryption of a unit vector
+/// multiplied by exactly the announced weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedBallot {
+    ciphertexts: EncryptedVote,
+    proof: ProofOfCorrectVote,
+    weight: u64,
+}
+
+impl WeightedBallot {
+    /// Encrypt the vote weighted by `weight`, which must not be zero
+    pub(crate) fn prepare<R: RngCore + CryptoRng, E: Encryptor>(
+        rng: &mut R,
+        crs: &CRS,
+        public_key: &E,
+        vote: Vote,
+        weight: u64,
+    ) -> Self {
+        assert_ne!(weight, 0, "a weighted ballot cannot have a zero weight");
+        let ev = EncryptingVote::prepare(rng, public_key, &vote);
+        let proof = shvzk::prove(rng, crs, public_key, ev.clone());
+        let ciphertexts = ev.ciphertexts.iter().map(|c| c * weight).collect();
+        WeightedBallot {
+            ciphertexts,
+            proof,
+            weight,
+        }
+    }
+
+    pub(crate) fn from_parts(
+        ciphertexts: EncryptedVote,
+        proof: ProofOfCorrectVote,
+        weight: u64,
+    ) -> Self {
+        WeightedBallot {
+            ciphertexts,
+            proof,
+            weight,
+        }
+    }
+
+    pub fn ciphertexts(&self) -> &EncryptedVote {
+        &self.ciphertexts
+    }
+
+    pub fn proof(&self) -> &ProofOfCorrectVote {
+        &self.proof
+    }
+
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    /// Verify that the ballot is the encryption of a unit vector multiplied
+    /// by the weight, without opening it
+    pub fn verify(&self, crs: &CRS, public_key: &EncryptingVoteKey) -> bool {
+        if self.weight == 0 {
+            return false;
+        }
+        let inverse = Scalar::from_u64(self.weight).inverse();
+        let unweighted: Vec<Ciphertext> = self.ciphertexts.iter().map(|c| c * &inverse).collect();
+        shvzk::verify(crs, &public_key.0, &unweighted, &self.proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tally, EncryptedTally, MemberCommunicationKey, MemberState, TallyOptimizationTable,
+    };
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn weighted_tally() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = CRS::from_hash(&[0u8; 32]);
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let m1 = MemberState::new(&mut rng, 1, &h, &[mc1.to_public()], 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+
+        let vote_options = 3;
+        let mut tally_weighted = EncryptedTally::new(vote_options);
+        for (option, weight) in [(0, 7), (2, 3), (0, 1)].iter() {
+            let ballot = crate::encrypt_weighted_vote(
+                &mut rng,
+                &h,
+                &ek,
+                Vote::new(vote_options, *option),
+                *weight,
+            );
+            assert!(crate::verify_weighted_vote(&h, &ek, &ballot));
+            tally_weighted.add_weighted(&ballot);
+        }
+
+        let (ts, tds1) = tally_weighted.finish(m1.secret_key());
+        let shares = vec![tds1];
+        let table = TallyOptimizationTable::generate(11);
+        let tr = tally(11, &ts, &shares, &table).unwrap();
+        assert_eq!(tr.votes, vec![8, 0, 3]);
+    }
+
+    #[test]
+    fn weight_is_bound_to_the_ciphertexts() {
+        let mut rng = ChaCha20Rng::from_seed([1u8; 32]);
+        let h = CRS::from_hash(&[0u8; 32]);
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let m1 = MemberState::new(&mut rng, 1, &h, &[mc1.to_public()], 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+
+        let ballot = crate::encrypt_weighted_vote(&mut rng, &h, &ek, Vote::new(2, 1), 5);
+        assert!(ballot.verify(&h, &ek));
+
+        for weight in [0, 1, 4, 6].iter() {
+            let forged = WeightedBallot {
+                weight: *weight,
+                ..ballot.clone()
+            };
+            assert!(!forged.verify(&h, &ek));
+        }
+
+        // the ciphertexts of an unweighted vote claimed with a larger weight
+        let (vote, proof) = crate::encrypt_vote(&mut rng, &h, &ek, Vote::new(2, 1));
+        let inflated = WeightedBallot {
+            ciphertexts: vote,
+            proof,
+            weight: 5,
+        };
+        assert!(!inflated.verify(&h, &ek));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_weight() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = CRS::from_hash(&[0u8; 32]);
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let m1 = MemberState::new(&mut rng, 1, &h, &[mc1.to_public()], 0);
+        let ek = EncryptingVoteKey::from_participants(&[m1.public_key()]);
+        crate::encrypt_weighted_vote(&mut rng, &h, &ek, Vote::new(2, 0), 0);
+    }
+}